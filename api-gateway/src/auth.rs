@@ -0,0 +1,102 @@
+//! API-key authentication of write endpoints.
+//!
+//! When `api.auth.api_keys` is non-empty, endpoints that queue
+//! transactions or store data require one of the keys, sent as
+//! `Authorization: Bearer <key>` or `X-Api-Key: <key>`. Requests without
+//! a valid key are rejected with `401 Unauthorized` and an `unauthorized`
+//! error body. Read endpoints, `/health`, `/ready` and the metrics
+//! exporter stay open.
+
+use std::sync::Arc;
+
+use axum::{
+    extract::{Request, State},
+    http::{HeaderMap, HeaderName, HeaderValue, StatusCode, header},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+
+use crate::error::ApiError;
+
+/// Header carrying an API key, as an alternative to `Authorization`.
+pub const API_KEY_HEADER: HeaderName = HeaderName::from_static("x-api-key");
+
+/// Request extension identifying the key a request was authenticated
+/// with, so the rate limiter can count requests per key.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct ApiKeyId(pub usize);
+
+/// The configured API keys.
+#[derive(Debug)]
+pub struct ApiKeys {
+    keys: Vec<Vec<u8>>,
+}
+
+impl ApiKeys {
+    pub fn new(keys: &[String]) -> Self {
+        Self {
+            keys: keys.iter().map(|k| k.as_bytes().to_vec()).collect(),
+        }
+    }
+
+    /// Whether any key is configured, i.e. whether authentication is on.
+    pub fn is_enabled(&self) -> bool {
+        !self.keys.is_empty()
+    }
+
+    /// Returns the id of the configured key equal to `presented`.
+    ///
+    /// Every key is compared in full so the time taken does not reveal
+    /// how much of a key was guessed.
+    fn find(&self, presented: &[u8]) -> Option<ApiKeyId> {
+        self.keys.iter().enumerate().fold(None, |found, (i, key)| {
+            if constant_time_eq(key, presented) {
+                Some(ApiKeyId(i))
+            } else {
+                found
+            }
+        })
+    }
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// Returns the key sent with the request, if any.
+fn presented_key(headers: &HeaderMap) -> Option<&[u8]> {
+    if let Some(value) = headers.get(API_KEY_HEADER) {
+        return Some(value.as_bytes());
+    }
+    let auth = headers.get(header::AUTHORIZATION)?.as_bytes();
+    let scheme = auth.get(..7)?;
+    scheme
+        .eq_ignore_ascii_case(b"bearer ")
+        .then(|| auth[7..].trim_ascii())
+}
+
+/// Rejects requests that carry no configured API key.
+///
+/// On success the matching [`ApiKeyId`] is added to the request's
+/// extensions.
+pub async fn require_api_key(
+    State(keys): State<Arc<ApiKeys>>,
+    mut req: Request,
+    next: Next,
+) -> Response {
+    match presented_key(req.headers()).and_then(|key| keys.find(key)) {
+        Some(id) => {
+            req.extensions_mut().insert(id);
+            next.run(req).await
+        }
+        None => (
+            [(header::WWW_AUTHENTICATE, HeaderValue::from_static("Bearer"))],
+            ApiError::new(
+                StatusCode::UNAUTHORIZED,
+                "unauthorized",
+                "a valid API key is required",
+            ),
+        )
+            .into_response(),
+    }
+}
@@ -1,15 +1,82 @@
 //! API gateway configuration.
 //!
-//! For now this only configures the HTTP listen address. The underlying
-//! chain configuration is taken from `chain::ChainConfig::default()`.
+//! This configures the HTTP listen addresses (plain or TLS) and API limits. It is loaded
+//! from the `[api]` section of a TOML/YAML file (see `configs/api.toml`)
+//! and `API_<FIELD>` environment variables; the underlying chain
+//! configuration is loaded separately as a `chain::ChainConfig`.
 
 use std::net::SocketAddr;
+use std::path::Path;
+
+use chain::config::{
+    ConfigError, duplicate_listener_problems, env_listeners_override, env_override,
+    parse_config_file,
+};
+use chain::{ArtefactStoreConfig, ListenerConfig};
+use serde::Deserialize;
 
 /// Configuration for the API gateway HTTP server.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
 pub struct ApiConfig {
-    /// Address to bind the HTTP server to.
-    pub listen_addr: SocketAddr,
+    /// Addresses to bind the HTTP server to, each plain or TLS.
+    pub listeners: Vec<ListenerConfig>,
+    /// Maximum number of blocks a single `GET /export/blocks` may request.
+    pub max_export_blocks: u64,
+    /// Number of recent `Idempotency-Key`s remembered by tx-submitting
+    /// endpoints.
+    pub idempotency_cache_size: usize,
+    /// Chain events buffered per `GET /ws` subscriber before the slowest
+    /// ones start missing events.
+    pub event_buffer_size: usize,
+    /// Blob store behind `POST /models/upload` and
+    /// `GET /models/{aid}/bytes`; disabled by default.
+    pub artefact_store: ArtefactStoreConfig,
+    /// Serve the JSON-RPC 2.0 endpoint `POST /rpc`; disabled by default.
+    pub rpc_enabled: bool,
+    /// Maximum number of requests in one JSON-RPC batch.
+    pub rpc_max_batch_size: usize,
+    /// API keys required by write endpoints; disabled by default.
+    pub auth: AuthConfig,
+    /// Request rate limit of write endpoints; disabled by default.
+    pub rate_limit: RateLimitConfig,
+}
+
+/// API-key authentication of write endpoints (see `auth`).
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AuthConfig {
+    /// Keys accepted in an `Authorization: Bearer <key>` or `X-Api-Key`
+    /// header. Write endpoints are open to everyone while this is empty.
+    pub api_keys: Vec<String>,
+}
+
+/// Token-bucket rate limit of write endpoints (see `rate_limit`).
+///
+/// Requests carrying a valid API key are counted per key, all others per
+/// client IP address.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RateLimitConfig {
+    /// Whether to limit the request rate.
+    pub enabled: bool,
+    /// Sustained requests per second allowed per client.
+    pub requests_per_second: u32,
+    /// Requests a client may send at once after being idle.
+    pub burst: u32,
+    /// Number of clients tracked before idle ones are forgotten.
+    pub max_clients: usize,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            requests_per_second: 10,
+            burst: 20,
+            max_clients: 10_000,
+        }
+    }
 }
 
 impl Default for ApiConfig {
@@ -20,6 +87,129 @@ impl Default for ApiConfig {
         let addr: SocketAddr = "0.0.0.0:8081"
             .parse()
             .expect("hard-coded API listen address should parse");
-        Self { listen_addr: addr }
+        Self {
+            listeners: vec![ListenerConfig::plain(addr)],
+            max_export_blocks: 10_000,
+            idempotency_cache_size: 10_000,
+            event_buffer_size: 1_024,
+            artefact_store: ArtefactStoreConfig::default(),
+            rpc_enabled: false,
+            rpc_max_batch_size: 100,
+            auth: AuthConfig::default(),
+            rate_limit: RateLimitConfig::default(),
+        }
+    }
+}
+
+/// Layout of the API config file: everything lives under `[api]`.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct ApiConfigFile {
+    api: ApiConfig,
+}
+
+impl ApiConfig {
+    /// Resolves the gateway configuration.
+    ///
+    /// Starts from `path` if given (otherwise from the defaults) and
+    /// applies `API_LISTENERS` (comma-separated plain addresses),
+    /// `API_MAX_EXPORT_BLOCKS`, `API_IDEMPOTENCY_CACHE_SIZE`, and
+    /// `API_EVENT_BUFFER_SIZE`, `API_ARTEFACT_STORE_{ENABLED,PATH,MAX_BLOB_BYTES}`,
+    /// `API_RPC_ENABLED` and `API_RPC_MAX_BATCH_SIZE`, `API_AUTH_API_KEYS`
+    /// (comma-separated), and
+    /// `API_RATE_LIMIT_{ENABLED,REQUESTS_PER_SECOND,BURST,MAX_CLIENTS}`
+    /// environment overrides on top. TLS listeners can only be configured
+    /// from a file.
+    pub fn load(path: Option<&Path>) -> Result<Self, ConfigError> {
+        let mut cfg = match path {
+            Some(path) => parse_config_file::<ApiConfigFile>(path)?.api,
+            None => Self::default(),
+        };
+
+        let lookup = |var: &str| std::env::var(var).ok();
+        env_listeners_override(&lookup, "API_LISTENERS", &mut cfg.listeners)?;
+        env_override(&lookup, "API_MAX_EXPORT_BLOCKS", &mut cfg.max_export_blocks)?;
+        env_override(
+            &lookup,
+            "API_IDEMPOTENCY_CACHE_SIZE",
+            &mut cfg.idempotency_cache_size,
+        )?;
+        env_override(&lookup, "API_EVENT_BUFFER_SIZE", &mut cfg.event_buffer_size)?;
+        let store = &mut cfg.artefact_store;
+        env_override(&lookup, "API_ARTEFACT_STORE_ENABLED", &mut store.enabled)?;
+        env_override(&lookup, "API_ARTEFACT_STORE_PATH", &mut store.path)?;
+        env_override(
+            &lookup,
+            "API_ARTEFACT_STORE_MAX_BLOB_BYTES",
+            &mut store.max_blob_bytes,
+        )?;
+        env_override(&lookup, "API_RPC_ENABLED", &mut cfg.rpc_enabled)?;
+        env_override(
+            &lookup,
+            "API_RPC_MAX_BATCH_SIZE",
+            &mut cfg.rpc_max_batch_size,
+        )?;
+        if let Some(raw) = lookup("API_AUTH_API_KEYS") {
+            cfg.auth.api_keys = raw
+                .split(',')
+                .map(str::trim)
+                .filter(|key| !key.is_empty())
+                .map(String::from)
+                .collect();
+        }
+        let limit = &mut cfg.rate_limit;
+        env_override(&lookup, "API_RATE_LIMIT_ENABLED", &mut limit.enabled)?;
+        env_override(
+            &lookup,
+            "API_RATE_LIMIT_REQUESTS_PER_SECOND",
+            &mut limit.requests_per_second,
+        )?;
+        env_override(&lookup, "API_RATE_LIMIT_BURST", &mut limit.burst)?;
+        env_override(
+            &lookup,
+            "API_RATE_LIMIT_MAX_CLIENTS",
+            &mut limit.max_clients,
+        )?;
+
+        let mut problems = Vec::new();
+        if cfg.listeners.is_empty() {
+            problems.push("api.listeners must not be empty".to_string());
+        }
+        problems.extend(duplicate_listener_problems("api.listeners", &cfg.listeners));
+        if cfg.max_export_blocks == 0 {
+            problems.push("api.max_export_blocks must be greater than 0".to_string());
+        }
+        if cfg.event_buffer_size == 0 {
+            problems.push("api.event_buffer_size must be greater than 0".to_string());
+        }
+        let store = &cfg.artefact_store;
+        if store.enabled && store.path.trim().is_empty() {
+            problems.push("api.artefact_store.path must not be empty".to_string());
+        }
+        if store.enabled && store.max_blob_bytes == 0 {
+            problems.push("api.artefact_store.max_blob_bytes must be greater than 0".to_string());
+        }
+        if cfg.rpc_max_batch_size == 0 {
+            problems.push("api.rpc_max_batch_size must be greater than 0".to_string());
+        }
+        if cfg.auth.api_keys.iter().any(|key| key.trim().is_empty()) {
+            problems.push("api.auth.api_keys must not contain empty keys".to_string());
+        }
+        let limit = &cfg.rate_limit;
+        if limit.enabled && limit.requests_per_second == 0 {
+            problems.push("api.rate_limit.requests_per_second must be greater than 0".to_string());
+        }
+        if limit.enabled && limit.burst == 0 {
+            problems.push("api.rate_limit.burst must be greater than 0".to_string());
+        }
+        if limit.enabled && limit.max_clients == 0 {
+            problems.push("api.rate_limit.max_clients must be greater than 0".to_string());
+        }
+
+        if problems.is_empty() {
+            Ok(cfg)
+        } else {
+            Err(ConfigError::Invalid(problems))
+        }
     }
 }
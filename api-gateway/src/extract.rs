@@ -0,0 +1,35 @@
+//! Request extractors that reject with an [`ApiError`].
+//!
+//! These wrap axum's extractors of the same name so that malformed bodies,
+//! path segments and query strings get the JSON error body of every other
+//! failure instead of axum's plain-text rejections. [`Json`] also works as
+//! a response, like its axum counterpart.
+
+use axum::{
+    extract::{FromRequest, FromRequestParts},
+    response::{IntoResponse, Response},
+};
+use serde::Serialize;
+
+use crate::error::ApiError;
+
+/// JSON request body; see [`axum::Json`].
+#[derive(FromRequest)]
+#[from_request(via(axum::Json), rejection(ApiError))]
+pub struct Json<T>(pub T);
+
+impl<T: Serialize> IntoResponse for Json<T> {
+    fn into_response(self) -> Response {
+        axum::Json(self.0).into_response()
+    }
+}
+
+/// Path parameters; see [`axum::extract::Path`].
+#[derive(FromRequestParts)]
+#[from_request(via(axum::extract::Path), rejection(ApiError))]
+pub struct Path<T>(pub T);
+
+/// Query string; see [`axum::extract::Query`].
+#[derive(FromRequestParts)]
+#[from_request(via(axum::extract::Query), rejection(ApiError))]
+pub struct Query<T>(pub T);
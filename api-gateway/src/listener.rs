@@ -0,0 +1,53 @@
+//! Serving the router on `chain::Listener`s.
+//!
+//! `axum::serve` accepts anything implementing its `Listener` trait. This
+//! adapter lets each configured listen address, plain or TLS, be served by
+//! its own `axum::serve` call. Handlers learn the peer address through
+//! `ConnectInfo<ClientAddr>`.
+
+use std::io;
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use axum::extract::connect_info::Connected;
+use axum::serve::IncomingStream;
+
+use chain::{Connection, Listener};
+
+/// Delay before retrying after a failed accept, e.g. when out of file
+/// descriptors.
+const ACCEPT_RETRY_DELAY: Duration = Duration::from_secs(1);
+
+/// A bound [`Listener`] usable with `axum::serve`.
+pub struct ServeListener(pub Listener);
+
+impl axum::serve::Listener for ServeListener {
+    type Io = Connection;
+    type Addr = SocketAddr;
+
+    async fn accept(&mut self) -> (Self::Io, Self::Addr) {
+        loop {
+            match self.0.accept().await {
+                Ok(accepted) => return accepted,
+                Err(e) => {
+                    tracing::warn!("failed to accept connection: {e}");
+                    tokio::time::sleep(ACCEPT_RETRY_DELAY).await;
+                }
+            }
+        }
+    }
+
+    fn local_addr(&self) -> io::Result<Self::Addr> {
+        Ok(self.0.local_addr())
+    }
+}
+
+/// Remote address of a connection accepted by a [`ServeListener`].
+#[derive(Clone, Copy, Debug)]
+pub struct ClientAddr(pub SocketAddr);
+
+impl Connected<IncomingStream<'_, ServeListener>> for ClientAddr {
+    fn connect_info(stream: IncomingStream<'_, ServeListener>) -> Self {
+        Self(*stream.remote_addr())
+    }
+}
@@ -4,42 +4,79 @@
 //!
 //! This binary exposes a small HTTP API on top of the `chain` crate:
 //!
-//! - `GET /health`
-//! - `POST /models/register`
+//! - `GET /health`, `GET /ready`
+//! - `POST /v1/models/register`
+//! - `GET /v1/models`, `GET /v1/models/{aid}`
+//! - `GET /v1/blocks/{height_or_hash}`, `GET /v1/blocks/tip`
+//! - `GET /v1/blocks/{hash}/raw`
+//! - `GET /v1/export/blocks`
+//! - `GET /v1/validators/{id}/report`
+//! - `GET /v1/ws` (WebSocket chain event stream)
+//! - `POST /rpc` (JSON-RPC 2.0, if `api.rpc_enabled`)
+//! - `GET /openapi.json` (OpenAPI 3 document), `GET /docs` (Swagger UI)
+//!
+//! Errors are answered with a JSON body carrying a machine-readable `code`
+//! (see `error`). Write endpoints can require an API key (see `auth`) and
+//! be rate limited per client (see `rate_limit`).
+//!
+//! The `/v1` endpoints are also reachable without the prefix for existing
+//! clients; those responses carry deprecation headers (see `versioning`).
 //!
 //! It embeds a `DefaultConsensusEngine` (RocksDB-backed), a simple queued
-//! transaction pool, a background block producer loop, and a Prometheus
-//! metrics exporter on `/metrics`.
+//! transaction pool, a background block producer loop, a stale fork
+//! garbage collector, and a Prometheus metrics exporter on `/metrics` (or a
+//! pusher to a Pushgateway or remote-write endpoint in push mode).
+//!
+//! The chain configuration is read from `--config <file>` (or
+//! `CHAIN_CONFIG`) and the gateway's own settings from `--api-config
+//! <file>` (or `API_CONFIG`); both fall back to their defaults and accept
+//! environment variable overrides. Run with `--help` for details.
 
+mod auth;
 mod config;
+mod error;
+mod extract;
+mod listener;
+mod openapi;
+mod rate_limit;
 mod routes;
 mod state;
+mod versioning;
 
+use std::future::IntoFuture;
+use std::path::PathBuf;
 use std::sync::Arc;
 
 use axum::{
-    Router,
+    Router, middleware,
     routing::{get, post},
 };
-use tokio::signal;
+use tracing_subscriber::{EnvFilter, Layer, layer::SubscriberExt, util::SubscriberInitExt};
+use utoipa::OpenApi;
+use utoipa_swagger_ui::SwaggerUi;
 
+use auth::ApiKeys;
 use chain::{
-    AccountId, BaseValidity, ChainConfig, CombinedValidator, Hash256, HttpMlVerifier,
-    MetricsRegistry, MlConfig, MlValidity, run_prometheus_http_server,
+    AccountId, AsyncMlValidity, BaseValidity, BlockStore, BlockSync, ChainConfig,
+    CombinedValidator, ConsensusConfig, ForkGc, GcConfig, GossipMessage, Hash256, InboundGossip,
+    LeaderElection, Listener, MetricsMode, MetricsPusher, MetricsRegistry, MlClient, MlConfig,
+    MlOutagePolicy, MlStatsLog, NetworkEvent, OtlpTracing, P2pHandle, P2pNetwork, PeekedTxs,
+    PeerId, ProposerSelector, ProposerValidity, Pruner, PruningConfig, RevalidationQueue, Shutdown,
+    StateHandle, StatefulValidity, ValidatorPipeline, VrfKeypair, WatchdogEvent,
+    collect_storage_metrics, serve_prometheus,
 };
 use config::ApiConfig;
-use routes::{health, models};
-use state::{AppState, QueuedTxPool, SharedState};
+use listener::{ClientAddr, ServeListener};
+use openapi::{ApiDoc, OPENAPI_PATH, SWAGGER_UI_PATH};
+use rate_limit::RateLimiter;
+use routes::{
+    accounts, admin, artefacts, blobs, blocks, events, explorer, export, health, models, rpc, sync,
+    transfers, txs, validators,
+};
+use state::{AppState, IdempotencyCache, SharedState};
 
 #[tokio::main]
 async fn main() {
-    // Basic tracing setup.
-    tracing_subscriber::fmt()
-        .with_env_filter(
-            std::env::var("RUST_LOG").unwrap_or_else(|_| "api_gateway=info,chain=info".to_string()),
-        )
-        .init();
-
     if let Err(e) = run().await {
         eprintln!("fatal error: {e}");
         std::process::exit(1);
@@ -47,123 +84,586 @@ async fn main() {
 }
 
 async fn run() -> Result<(), String> {
-    // For now we use default configs. These can be externalised later.
-    let api_cfg = ApiConfig::default();
-    let chain_cfg = ChainConfig::default();
+    let (chain_path, api_path) = match parse_args()? {
+        Command::Run { config, api_config } => (config, api_config),
+        Command::Help => {
+            println!("{USAGE}");
+            return Ok(());
+        }
+    };
+    let chain_cfg = ChainConfig::load(chain_path.as_deref()).map_err(|e| e.to_string())?;
+    let api_cfg = ApiConfig::load(api_path.as_deref()).map_err(|e| e.to_string())?;
+
+    // Basic tracing setup. The decision log directive comes last so the
+    // chain config decides about `mlsnitch::decisions` whatever `RUST_LOG`
+    // says.
+    let log_filter =
+        std::env::var("RUST_LOG").unwrap_or_else(|_| "api_gateway=info,chain=info".to_string());
+    // Spans go to the OTLP exporter, if enabled, with its own filter.
+    let otlp = OtlpTracing::init(&chain_cfg.metrics.otlp, "mlsnitch-api-gateway")
+        .map_err(|e| format!("failed to start OTLP exporter: {e}"))?;
+    tracing_subscriber::registry()
+        .with(
+            tracing_subscriber::fmt::layer().with_filter(EnvFilter::new(format!(
+                "{log_filter},{}",
+                chain_cfg.decision_log.filter_directive()
+            ))),
+        )
+        .with(otlp.as_ref().map(OtlpTracing::layer))
+        .init();
+
+    for path in chain_path.iter().chain(api_path.iter()) {
+        tracing::info!("loaded configuration from {}", path.display());
+    }
+
+    // The servers and every background task stop on Ctrl-C.
+    let shutdown = Shutdown::on_ctrl_c();
 
     // ---------------------------
     // Metrics
     // ---------------------------
 
     let metrics = Arc::new(
-        MetricsRegistry::new()
+        MetricsRegistry::from_config(&chain_cfg.metrics)
             .map_err(|e| format!("failed to initialise metrics registry: {e}"))?,
     );
 
     // Metrics exporter.
-    if chain_cfg.metrics.enabled {
-        let metrics_clone = metrics.clone();
-        let addr = chain_cfg.metrics.listen_addr;
-        tokio::spawn(async move {
-            if let Err(e) = run_prometheus_http_server(metrics_clone, addr).await {
-                eprintln!("metrics HTTP server error: {e}");
-            }
-        });
-        tracing::info!("metrics exporter listening on http://{}/metrics", addr);
+    if chain_cfg.metrics.enabled && chain_cfg.metrics.mode == MetricsMode::Pull {
+        let listeners = Listener::bind_all(&chain_cfg.metrics.listeners)
+            .await
+            .map_err(|e| format!("failed to start metrics exporter: {e}"))?;
+        for listener in listeners {
+            tracing::info!(
+                "metrics exporter listening on {}://{}/metrics",
+                listener.scheme(),
+                listener.local_addr()
+            );
+            let metrics_clone = metrics.clone();
+            let shutdown = shutdown.clone();
+            tokio::spawn(async move {
+                if let Err(e) = serve_prometheus(metrics_clone, listener, shutdown).await {
+                    eprintln!("metrics HTTP server error: {e}");
+                }
+            });
+        }
     }
 
+    // Metrics pusher, pushing once more after the store is flushed.
+    let pusher = if chain_cfg.metrics.enabled && chain_cfg.metrics.mode == MetricsMode::Push {
+        let pusher = MetricsPusher::new(
+            metrics.clone(),
+            &chain_cfg.metrics.push,
+            "mlsnitch-api-gateway",
+        )
+        .map_err(|e| format!("failed to start metrics pusher: {e}"))?;
+        tracing::info!(
+            "pushing metrics to {} every {}s",
+            chain_cfg.metrics.push.url,
+            chain_cfg.metrics.push.interval_secs
+        );
+        tokio::spawn(pusher.clone().run(shutdown.clone()));
+        Some(pusher)
+    } else {
+        None
+    };
+
     // ---------------------------
     // Storage + consensus engine
     // ---------------------------
 
-    let store = chain::RocksDbBlockStore::open(&chain_cfg.storage).map_err(|e| {
-        format!(
-            "failed to open RocksDB store at {}: {e:?}",
-            chain_cfg.storage.path
-        )
-    })?;
+    // Shared, so read routes can use a `ChainReader` while the producer
+    // holds the engine.
+    let store = chain::PersistentStore::open(&chain_cfg.storage)
+        .map(chain::SharedStore::new)
+        .map_err(|e| {
+            format!(
+                "failed to open {:?} store at {}: {e:?}",
+                chain_cfg.storage.backend, chain_cfg.storage.path
+            )
+        })?;
+
+    if chain_cfg.storage.metrics.enabled {
+        let interval =
+            std::time::Duration::from_secs(chain_cfg.storage.metrics.interval_secs.max(1));
+        tokio::spawn(collect_storage_metrics(
+            store.clone(),
+            metrics.storage.clone(),
+            interval,
+            shutdown.clone(),
+        ));
+    }
 
-    let ml_verifier = HttpMlVerifier::new(
-        chain_cfg.ml_client.base_url.clone(),
-        chain_cfg.ml_client.timeout,
+    let ml_verifier = MlClient::from_config(
+        &chain_cfg.ml_client,
+        Some(metrics.consensus.outbound_requests.clone()),
     )
-    .map_err(|e| format!("failed to create HttpMlVerifier: {e:?}"))?;
+    .map_err(|e| format!("failed to create ML service client: {e:?}"))?;
 
-    let base_validity = BaseValidity::new(&chain_cfg.consensus);
-    let ml_validity = MlValidity::new(ml_verifier, MlConfig::default());
-    let validator = CombinedValidator::new(base_validity, ml_validity);
+    let selector = LeaderElection::from_config(
+        &chain_cfg.validators,
+        chain_cfg.consensus.block_time_secs,
+        chain_cfg.genesis.genesis_block().compute_hash().0,
+    );
+    let schemes = &chain_cfg.genesis.params.schemes;
+    let base_validity = BaseValidity::new(&chain_cfg.consensus)
+        .with_signatures_required(chain_cfg.tx_validity.require_signatures)
+        .with_scheme_registry(schemes.clone());
+    let proposer_validity = ProposerValidity::new(selector.clone());
+    // Filled with the tip state once the engine is built.
+    let state_handle = StateHandle::new();
+    let stateful_validity = StatefulValidity::new(state_handle.clone());
+    // Verdict statistics handed from the ML stage to the engine, which
+    // stores them with the block's receipts.
+    let ml_stats = MlStatsLog::new();
+    let ml_cfg = chain_cfg.ml.clone().with_scheme_registry(schemes);
+    let ml_validity = AsyncMlValidity::new(ml_verifier, ml_cfg.clone())
+        .with_metrics(metrics.consensus.clone())
+        .with_stats_log(ml_stats.clone());
+    let revalidation = ml_validity.revalidation_queue().clone();
+    let pipeline = ValidatorPipeline::new()
+        .with_stage("base", base_validity)
+        .with_stage("proposer", proposer_validity)
+        .with_stage("state", stateful_validity);
+    let validator = CombinedValidator::new(pipeline, ml_validity);
 
     let fork_choice = chain::DefaultForkChoice::default();
 
-    let engine: chain::DefaultConsensusEngine =
-        chain::ConsensusEngine::new(chain_cfg.consensus.clone(), store, validator, fork_choice);
+    // Chain events fan out to `GET /ws` subscribers; the receiver is
+    // dropped, so events are discarded while nobody is subscribed.
+    let (events, _) = tokio::sync::broadcast::channel(api_cfg.event_buffer_size);
+
+    let mut engine: chain::DefaultConsensusEngine = chain::ConsensusEngine::with_genesis(
+        chain_cfg.consensus.clone(),
+        &chain_cfg.genesis,
+        store,
+        validator,
+        fork_choice,
+    )
+    .map_err(|e| format!("failed to initialise consensus engine: {e}"))?
+    .with_invariant_checks(&chain_cfg.invariants)
+    .with_events(events.clone())
+    .with_metrics(metrics.consensus.clone())
+    .with_state_handle(state_handle)
+    .with_ml_stats(ml_stats)
+    .with_reorg_listener(|event| {
+        tracing::info!(
+            old_tip = %hex::encode(event.old_tip.0.as_bytes()),
+            new_tip = %hex::encode(event.new_tip.0.as_bytes()),
+            depth = event.depth,
+            "chain reorg"
+        );
+    });
+
+    // In a real node the VRF key would be loaded next to the Dilithium key.
+    let vrf_key = VrfKeypair::from_seed(&Hash256::compute(b"api-gateway-proposer-vrf").0);
+    match &selector {
+        LeaderElection::Vrf(vrf) => {
+            tracing::info!(
+                vrf_key = %hex::encode(vrf_key.public().0),
+                "VRF leader election enabled"
+            );
+            engine = engine.with_vrf_key(vrf_key.clone(), *vrf.seed());
+        }
+        // Stake-weighted leaders follow the stake bonded on the tip.
+        LeaderElection::Stake(stake) => engine = engine.with_stake_table(stake.table().clone()),
+        LeaderElection::RoundRobin(_) => {}
+    }
+
+    // Seed chain-derived gauges from the replayed chain.
+    metrics
+        .consensus
+        .backfill(engine.state(), engine.store())
+        .map_err(|e| format!("failed to backfill chain metrics: {e}"))?;
 
     // ---------------------------
     // Proposer identity + tx pool
     // ---------------------------
 
-    // In a real node this would be derived from a Dilithium public key.
-    let proposer_id = {
-        let seed = b"api-gateway-proposer";
-        AccountId(Hash256::compute(seed))
+    // The proposer account comes from the node's ML-DSA key file when one is
+    // configured; otherwise fall back to a fixed demo identity.
+    let proposer_id = match chain_cfg
+        .node_keypair()
+        .map_err(|e| format!("failed to load node key: {e}"))?
+    {
+        Some((keypair, created)) => {
+            tracing::info!(
+                account = %hex::encode(keypair.account_id().0.as_bytes()),
+                created,
+                "node key ready"
+            );
+            keypair.account_id()
+        }
+        None => {
+            tracing::warn!("no node_key_path configured; using the demo proposer identity");
+            AccountId(Hash256::compute(b"api-gateway-proposer"))
+        }
+    };
+    if !selector.validators().is_empty() && !selector.validators().contains(&proposer_id) {
+        tracing::warn!(
+            proposer = %hex::encode(proposer_id.0.as_bytes()),
+            "proposer is not in the validator set; this node will never propose"
+        );
+    }
+
+    let tx_pool = chain::Mempool::with_validator(
+        chain_cfg.mempool.clone(),
+        chain::StatelessTxValidity::new(&chain_cfg.tx_validity)
+            .with_scheme_registry(schemes.clone()),
+    )
+    .with_metrics(&metrics.consensus);
+
+    // ---------------------------
+    // P2P network
+    // ---------------------------
+
+    // Peers' requests are answered from the shared store; what they send
+    // is imported by `run_network_events` once the state is built.
+    let network = if chain_cfg.network.p2p.enabled {
+        let (events_tx, events_rx) =
+            tokio::sync::mpsc::channel(chain_cfg.network.p2p.event_buffer.max(1));
+        let (network, handle) = P2pNetwork::with_metrics(
+            &chain_cfg.network,
+            engine.store().clone(),
+            events_tx,
+            &metrics.consensus,
+        )
+        .map_err(|e| format!("failed to start p2p network: {e}"))?;
+        tracing::info!(peer_id = %handle.local_peer_id(), "p2p network enabled");
+        Some((network, handle, events_rx))
+    } else {
+        None
     };
 
-    let tx_pool = QueuedTxPool::new();
+    // ---------------------------
+    // Artefact blob store
+    // ---------------------------
+
+    let artefacts = if api_cfg.artefact_store.enabled {
+        let store = chain::FsArtefactStore::open(&api_cfg.artefact_store).map_err(|e| {
+            format!(
+                "failed to open artefact store at {}: {e}",
+                api_cfg.artefact_store.path
+            )
+        })?;
+        Some(store)
+    } else {
+        None
+    };
 
     // ---------------------------
     // Shared state
     // ---------------------------
 
     let app_state: SharedState = Arc::new(AppState {
+        chain: engine.reader(),
         engine: tokio::sync::Mutex::new(engine),
         tx_pool: tokio::sync::Mutex::new(tx_pool),
+        inclusion: tokio::sync::Mutex::new(chain::InclusionTracker::new()),
+        watchdog: tokio::sync::Mutex::new(chain::ProducerWatchdog::new(&chain_cfg.watchdog)),
         proposer_id,
         metrics: metrics.clone(),
+        max_export_blocks: api_cfg.max_export_blocks,
+        rpc_max_batch_size: api_cfg.rpc_max_batch_size,
+        idempotency: tokio::sync::Mutex::new(IdempotencyCache::new(api_cfg.idempotency_cache_size)),
+        events,
+        sync: chain::SyncHandle::new(),
+        p2p: network.as_ref().map(|(_, handle, _)| handle.clone()),
+        artefacts,
     });
 
     // ---------------------------
     // Block producer loop
     // ---------------------------
 
-    let block_interval_secs = chain_cfg.consensus.block_time_secs;
+    // Tasks that use the engine are joined on shutdown, before the store
+    // is flushed.
+    let mut tasks = Vec::new();
+
+    let producer_consensus = chain_cfg.consensus.clone();
     let producer_state = app_state.clone();
-    tokio::spawn(async move {
-        run_block_producer(producer_state, block_interval_secs).await;
-    });
+    let producer_shutdown = shutdown.clone();
+    tasks.push(tokio::spawn(async move {
+        run_block_producer(
+            producer_state,
+            selector,
+            vrf_key,
+            producer_consensus,
+            producer_shutdown,
+        )
+        .await;
+    }));
+
+    // ---------------------------
+    // P2P gossip and catch-up
+    // ---------------------------
+
+    if let Some((network, handle, events)) = network {
+        tokio::spawn(network.run(shutdown.clone()));
+        let sync = BlockSync::new(chain_cfg.network.sync.clone())
+            .with_handle(app_state.sync.clone())
+            .with_metrics(&metrics.consensus);
+        let events_state = app_state.clone();
+        let events_shutdown = shutdown.clone();
+        tasks.push(tokio::spawn(async move {
+            run_network_events(events_state, handle, events, sync, events_shutdown).await;
+        }));
+    }
+
+    // ---------------------------
+    // Fork garbage collection
+    // ---------------------------
+
+    if chain_cfg.gc.enabled {
+        let gc_state = app_state.clone();
+        let gc_cfg = chain_cfg.gc.clone();
+        let gc_shutdown = shutdown.clone();
+        tasks.push(tokio::spawn(async move {
+            run_fork_gc(gc_state, gc_cfg, gc_shutdown).await;
+        }));
+    }
+
+    // ---------------------------
+    // ML revalidation
+    // ---------------------------
+
+    if chain_cfg.ml.outage_policy == MlOutagePolicy::DeferToRevalidationQueue {
+        let verifier = MlClient::from_config(
+            &chain_cfg.ml_client,
+            Some(metrics.consensus.outbound_requests.clone()),
+        )
+        .map_err(|e| format!("failed to create ML revalidation client: {e:?}"))?;
+        let revalidation_shutdown = shutdown.clone();
+        tokio::spawn(async move {
+            run_ml_revalidation(revalidation, verifier, ml_cfg, revalidation_shutdown).await;
+        });
+    }
+
+    // ---------------------------
+    // History pruning
+    // ---------------------------
+
+    if chain_cfg.storage.pruning.enabled() {
+        let prune_state = app_state.clone();
+        let prune_cfg = chain_cfg.storage.pruning.clone();
+        let prune_shutdown = shutdown.clone();
+        tasks.push(tokio::spawn(async move {
+            run_history_pruning(prune_state, prune_cfg, prune_shutdown).await;
+        }));
+    }
 
     // ---------------------------
     // HTTP router
     // ---------------------------
 
-    let app = Router::new()
-        .route("/health", get(health::health))
+    // Endpoints that queue transactions or store data sit behind the API
+    // key check and rate limit, if configured; the key check runs first so
+    // authenticated clients are limited per key.
+    let api_keys = Arc::new(ApiKeys::new(&api_cfg.auth.api_keys));
+    let rate_limiter = api_cfg
+        .rate_limit
+        .enabled
+        .then(|| Arc::new(RateLimiter::new(&api_cfg.rate_limit)));
+    if api_keys.is_enabled() {
+        tracing::info!("write endpoints require an API key");
+    }
+    let guard_writes = |router: Router<SharedState>| {
+        let router = match &rate_limiter {
+            Some(limiter) => router.route_layer(middleware::from_fn_with_state(
+                limiter.clone(),
+                rate_limit::limit,
+            )),
+            None => router,
+        };
+        if api_keys.is_enabled() {
+            router.route_layer(middleware::from_fn_with_state(
+                api_keys.clone(),
+                auth::require_api_key,
+            ))
+        } else {
+            router
+        }
+    };
+
+    let writes_v1 = Router::new()
         .route("/models/register", post(models::register_model))
-        .with_state(app_state);
+        .route("/models/use", post(models::use_model))
+        .route("/models/upload", post(blobs::upload_model))
+        .route("/transfer", post(transfers::transfer));
+    // Node internals are not for the public; they share the write guard.
+    let admin_v1 = Router::new().route("/admin/orphans", get(admin::get_orphans));
+    let api_v1 = Router::new()
+        .route("/models", get(artefacts::list_models))
+        .route("/models/{aid}", get(artefacts::get_artefact))
+        .route("/models/{aid}/bytes", get(blobs::get_model_bytes))
+        .route("/blocks", get(blocks::get_recent_blocks))
+        .route("/blocks/tip", get(blocks::get_tip_block))
+        .route("/blocks/{id}", get(blocks::get_block))
+        .route("/blocks/{id}/raw", get(blocks::get_block_raw))
+        .route("/txs/{hash}", get(txs::get_tx))
+        .route("/txs/{hash}/receipt", get(txs::get_tx_receipt))
+        .route("/artefacts/{aid}", get(artefacts::get_artefact))
+        .route("/export/blocks", get(export::export_blocks))
+        .route("/sync", get(sync::get_sync_status))
+        .route(
+            "/validators/{id}/report",
+            get(validators::get_validator_report),
+        )
+        .route("/accounts/{id}", get(accounts::get_account))
+        .route(
+            "/accounts/{id}/inclusion",
+            get(accounts::get_inclusion_stats),
+        )
+        .route("/accounts/{id}/txs", get(accounts::get_account_txs))
+        .route("/ws", get(events::subscribe))
+        .merge(guard_writes(writes_v1))
+        .merge(guard_writes(admin_v1));
+
+    // Unversioned aliases of v1 stay mounted for existing clients; the
+    // versioning middleware marks their responses as deprecated.
+    let mut app = Router::new()
+        .route("/health", get(health::health))
+        .route("/ready", get(health::ready))
+        .route("/explorer", get(explorer::explorer));
+    // JSON-RPC carries its own version, so it is mounted once, outside
+    // `/v1`. It can submit transactions, so it is guarded as a whole.
+    if api_cfg.rpc_enabled {
+        app = app.merge(guard_writes(Router::new().route("/rpc", post(rpc::handle))));
+    }
+    let app = app
+        .nest("/v1", api_v1.clone())
+        .merge(api_v1)
+        .merge(SwaggerUi::new(SWAGGER_UI_PATH).url(OPENAPI_PATH, ApiDoc::openapi()))
+        .fallback(error::not_found)
+        .layer(middleware::from_fn(versioning::negotiate))
+        .with_state(app_state.clone());
 
     // ---------------------------
     // axum 0.8 server (hyper 1 / tokio 1.48 style)
     // ---------------------------
 
-    tracing::info!("API gateway listening on http://{}", api_cfg.listen_addr);
-
-    let listener = tokio::net::TcpListener::bind(api_cfg.listen_addr)
+    // One server per listen address; all of them stop on the same signal.
+    let listeners = Listener::bind_all(&api_cfg.listeners)
         .await
-        .map_err(|e| format!("failed to bind {}: {e}", api_cfg.listen_addr))?;
+        .map_err(|e| format!("failed to start API server: {e}"))?;
+    let servers = listeners.into_iter().map(|listener| {
+        tracing::info!(
+            "API gateway listening on {}://{}",
+            listener.scheme(),
+            listener.local_addr()
+        );
+        let shutdown = shutdown.clone();
+        let app = app
+            .clone()
+            .into_make_service_with_connect_info::<ClientAddr>();
+        axum::serve(ServeListener(listener), app)
+            .with_graceful_shutdown(async move { shutdown.wait().await })
+            .into_future()
+    });
 
-    axum::serve(listener, app)
-        .with_graceful_shutdown(shutdown_signal())
-        .await
-        .map_err(|e| format!("API server error: {e}"))?;
+    let served = futures_util::future::try_join_all(servers).await;
+
+    // Stop the background tasks too if a server failed, let an in-flight
+    // proposal finish, then make the stored chain durable.
+    tracing::info!("shutting down");
+    shutdown.trigger();
+    for task in tasks {
+        let _ = task.await;
+    }
+    if let Err(e) = app_state.engine.lock().await.store_mut().flush() {
+        tracing::error!("failed to flush block store: {e}");
+    }
+    drop(app_state);
+
+    if let Some(pusher) = pusher {
+        let pushed = pusher.push().await;
+        if let Err(e) = pushed {
+            tracing::error!("final metrics push failed: {e}");
+        }
+    }
+
+    if let Some(otlp) = otlp {
+        // Flushing blocks until the collector has answered.
+        let _ = tokio::task::spawn_blocking(move || otlp.shutdown()).await;
+    }
+    served.map_err(|e| format!("API server error: {e}"))?;
 
     Ok(())
 }
 
+const USAGE: &str = "\
+usage: api-gateway [--config <path>] [--api-config <path>]
+
+options:
+  --config <path>      chain configuration (.toml, .yaml, or .yml)
+                       (default: $CHAIN_CONFIG, or built-in defaults)
+  --api-config <path>  API gateway configuration with an [api] section
+                       (default: $API_CONFIG, or built-in defaults)
+  -h, --help           print this help
+
+Fields can be overridden with CHAIN_<SECTION>_<FIELD> and API_<FIELD>
+environment variables, e.g. API_LISTENERS=127.0.0.1:8081,[::1]:8081.";
+
+/// What the command line asked the binary to do.
+enum Command {
+    /// Run the gateway with the given config files, if any.
+    Run {
+        config: Option<PathBuf>,
+        api_config: Option<PathBuf>,
+    },
+    /// Print usage and exit.
+    Help,
+}
+
+/// Parses command-line arguments.
+///
+/// Config files not given on the command line are taken from
+/// `CHAIN_CONFIG` and `API_CONFIG`.
+fn parse_args() -> Result<Command, String> {
+    let mut config = None;
+    let mut api_config = None;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let (flag, inline) = match arg.split_once('=') {
+            Some((flag, value)) => (flag.to_string(), Some(value.to_string())),
+            None => (arg, None),
+        };
+        let target = match flag.as_str() {
+            "-h" | "--help" => return Ok(Command::Help),
+            "--config" => &mut config,
+            "--api-config" => &mut api_config,
+            other => return Err(format!("unknown argument {other:?}\n\n{USAGE}")),
+        };
+        let path = inline
+            .or_else(|| args.next())
+            .ok_or_else(|| format!("{flag} requires a path\n\n{USAGE}"))?;
+        *target = Some(PathBuf::from(path));
+    }
+    Ok(Command::Run {
+        config: config.or_else(|| std::env::var_os("CHAIN_CONFIG").map(PathBuf::from)),
+        api_config: api_config.or_else(|| std::env::var_os("API_CONFIG").map(PathBuf::from)),
+    })
+}
+
 /// Background block producer loop.
 ///
-/// Periodically asks the consensus engine to propose and import a new block
-/// using the queued transaction pool.
-async fn run_block_producer(state: SharedState, interval_secs: u64) {
-    let interval = std::time::Duration::from_secs(interval_secs.max(1));
+/// Every `consensus.block_time_secs`, asks the consensus engine to propose
+/// and import a new block from the mempool, then re-synchronises the
+/// mempool with the new state. Slots this node does not win under
+/// `selector` (holding `vrf_key`) are skipped. On `shutdown`, a proposal in
+/// progress is finished before the loop returns.
+///
+/// The block's transactions are peeked ([`PeekedTxs`]) and only leave the
+/// mempool once the import is over, so the mempool is not held while the
+/// block is validated.
+async fn run_block_producer(
+    state: SharedState,
+    selector: LeaderElection,
+    vrf_key: VrfKeypair,
+    consensus: ConsensusConfig,
+    shutdown: Shutdown,
+) {
+    let interval = std::time::Duration::from_secs(consensus.block_time_secs.max(1));
     tracing::info!(
         "block producer running with interval {}s",
         interval.as_secs()
@@ -175,30 +675,306 @@ async fn run_block_producer(state: SharedState, interval_secs: u64) {
 
         {
             let mut engine_guard = state.engine.lock().await;
+            let mut inclusion_guard = state.inclusion.lock().await;
+            let mut watchdog_guard = state.watchdog.lock().await;
+
+            // Only the slot leader proposes; everyone else waits for the
+            // next slot.
+            let skipped_slot = match engine_guard.next_block_timestamp(timestamp) {
+                Ok(slot_time) if !selector.may_propose(&state.proposer_id, &vrf_key, slot_time) => {
+                    Some(selector.expected_proposer(slot_time))
+                }
+                Ok(_) => None,
+                Err(e) => {
+                    tracing::warn!("failed to read chain tip: {e}");
+                    None
+                }
+            };
+
+            if let Some(leader) = skipped_slot {
+                match leader {
+                    Some(leader) => tracing::debug!(
+                        leader = %hex::encode(leader.0.as_bytes()),
+                        "skipping slot owned by another validator"
+                    ),
+                    None => tracing::debug!("not elected for this slot"),
+                }
+            } else if !watchdog_guard.should_propose(start) {
+                tracing::debug!("block production paused; waiting for the next ML probe");
+            } else {
+                let mut txs = PeekedTxs::peek(&*state.tx_pool.lock().await, &consensus);
+                let proposed = engine_guard
+                    .propose_block_async(state.proposer_id, &mut txs, timestamp)
+                    .await;
+                txs.settle(&mut *state.tx_pool.lock().await);
+                match proposed {
+                    Ok((hash, block)) => {
+                        let elapsed = start.elapsed().as_secs_f64();
+                        state
+                            .metrics
+                            .consensus
+                            .block_validation_seconds
+                            .observe(elapsed);
+                        if let Err(e) = state
+                            .metrics
+                            .consensus
+                            .backfill(engine_guard.state(), engine_guard.store())
+                        {
+                            tracing::warn!("failed to update chain metrics: {e}");
+                        }
+                        for inclusion in inclusion_guard.observe_block(&block) {
+                            state
+                                .metrics
+                                .consensus
+                                .registration_inclusion_delay_blocks
+                                .observe(inclusion.delay_blocks as f64);
+                        }
+
+                        tracing::info!(
+                            height = block.header.height,
+                            hash = %hex::encode(hash.0.as_bytes()),
+                            "proposed block"
+                        );
+                        if let Some(p2p) = &state.p2p
+                            && let Err(e) = p2p.publish(GossipMessage::Block(block)).await
+                        {
+                            tracing::warn!("failed to gossip proposed block: {e}");
+                        }
+                        if let Some(WatchdogEvent::Resumed) = watchdog_guard.observe_success() {
+                            tracing::info!("ML verifier probe succeeded; block production resumed");
+                        }
+                    }
+                    Err(e) => {
+                        let paused = watchdog_guard.is_paused();
+                        match watchdog_guard.observe_error(&e, std::time::Instant::now()) {
+                            Some(WatchdogEvent::Paused { failures }) => tracing::error!(
+                                failures,
+                                "pausing block production after repeated ML verifier failures: {e}"
+                            ),
+                            _ if paused => tracing::debug!("ML verifier probe failed: {e}"),
+                            _ => tracing::warn!("failed to propose block: {e}"),
+                        }
+                    }
+                }
+            }
+
+            // Drop transactions whose nonces are now used on chain, and
+            // reopen nonces of transactions a failed proposal dropped.
             let mut pool_guard = state.tx_pool.lock().await;
+            pool_guard.prune(engine_guard.state());
+            inclusion_guard.retain_pending(|hash| pool_guard.contains(hash));
+        }
 
-            match engine_guard.propose_block(state.proposer_id, &mut *pool_guard, timestamp) {
-                Ok((hash, block)) => {
-                    let elapsed = start.elapsed().as_secs_f64();
-                    state
-                        .metrics
-                        .consensus
-                        .block_validation_seconds
-                        .observe(elapsed);
-
-                    tracing::info!(
-                        height = block.header.height,
-                        hash = %hex::encode(hash.0.as_bytes()),
-                        "proposed block"
+        if !shutdown.sleep(interval).await {
+            break;
+        }
+    }
+    tracing::info!("block producer stopped");
+}
+
+/// Imports what peers send over the p2p network until `shutdown`.
+///
+/// A newly connected peer, or a gossiped block whose parent is unknown
+/// here, starts a `sync` run from that peer; other gossiped blocks are
+/// imported directly. Gossiped transactions go to the mempool and votes to
+/// the engine. Peers are untrusted, so rejections are only logged.
+async fn run_network_events(
+    state: SharedState,
+    p2p: P2pHandle,
+    mut events: tokio::sync::mpsc::Receiver<NetworkEvent>,
+    sync: BlockSync,
+    shutdown: Shutdown,
+) {
+    loop {
+        let event = tokio::select! {
+            _ = shutdown.wait() => break,
+            event = events.recv() => match event {
+                Some(event) => event,
+                None => break,
+            },
+        };
+        let (source, message) = match event {
+            NetworkEvent::PeerConnected(peer) => {
+                sync_from_peer(&state, &sync, &p2p, peer).await;
+                continue;
+            }
+            NetworkEvent::Gossip { source, message } => (source, message),
+        };
+        match message {
+            InboundGossip::Block(block) => {
+                let parent_known = block.header.height == 0
+                    || matches!(
+                        state.chain.store().get_block(&block.header.parent),
+                        Ok(Some(_))
                     );
+                if !parent_known {
+                    sync_from_peer(&state, &sync, &p2p, source).await;
+                    continue;
                 }
-                Err(e) => {
-                    tracing::warn!("failed to propose block: {e}");
+                let height = block.header.height;
+                let mut engine = state.engine.lock().await;
+                match engine.import_block_async(block).await {
+                    Ok(hash) => tracing::info!(
+                        height,
+                        hash = %hex::encode(hash.0.as_bytes()),
+                        peer = %source,
+                        "imported gossiped block"
+                    ),
+                    Err(e) => tracing::warn!(peer = %source, "rejected gossiped block: {e}"),
+                }
+            }
+            InboundGossip::Tx(tx) => {
+                let engine = state.engine.lock().await;
+                let mut pool = state.tx_pool.lock().await;
+                if let Err(e) = pool.insert(tx, engine.state()) {
+                    tracing::debug!(peer = %source, "rejected gossiped transaction: {e}");
                 }
             }
+            InboundGossip::Vote(vote) => {
+                if let Err(e) = state.engine.lock().await.add_vote(vote) {
+                    tracing::debug!(peer = %source, "rejected gossiped vote: {e}");
+                }
+            }
+            InboundGossip::Duplicate => {}
         }
+    }
+    tracing::info!("p2p event handler stopped");
+}
+
+/// Catches up with `peer`, holding the engine for the whole run.
+async fn sync_from_peer(state: &SharedState, sync: &BlockSync, p2p: &P2pHandle, peer: PeerId) {
+    let mut engine = state.engine.lock().await;
+    match sync.sync(&mut engine, &p2p.peer(peer)).await {
+        Ok(0) => {}
+        Ok(imported) => tracing::info!(%peer, imported, "synced blocks from peer"),
+        Err(e) => tracing::warn!(%peer, "block sync failed: {e}"),
+    }
+}
+
+/// Background stale fork garbage collection loop.
+///
+/// Periodically deletes blocks on side branches that fell too far behind
+/// the canonical tip and records the reclaimed space in metrics.
+async fn run_fork_gc(state: SharedState, cfg: GcConfig, shutdown: Shutdown) {
+    let interval = std::time::Duration::from_secs(cfg.interval_secs.max(1));
+    let gc = ForkGc::from_config(&cfg);
+    tracing::info!(
+        depth = cfg.depth,
+        archive = cfg.archive,
+        "fork gc running with interval {}s",
+        interval.as_secs()
+    );
 
-        tokio::time::sleep(interval).await;
+    while shutdown.sleep(interval).await {
+        let result = {
+            let mut engine_guard = state.engine.lock().await;
+            engine_guard.collect_stale_forks(&gc)
+        };
+        let report = match result {
+            Ok(report) => report,
+            Err(e) => {
+                tracing::warn!("fork gc failed: {e}");
+                continue;
+            }
+        };
+
+        let consensus = &state.metrics.consensus;
+        consensus.gc_blocks_pruned.inc_by(report.blocks_removed);
+        consensus.gc_reclaimed_bytes.inc_by(report.bytes_reclaimed);
+
+        if report.blocks_removed > 0 {
+            tracing::info!(
+                blocks = report.blocks_removed,
+                bytes = report.bytes_reclaimed,
+                "fork gc removed stale blocks"
+            );
+        }
+    }
+}
+
+/// Background ML revalidation loop.
+///
+/// Periodically re-checks the artefacts of blocks accepted while the ML
+/// service was unreachable and reports any that turn out inauthentic.
+async fn run_ml_revalidation(
+    queue: RevalidationQueue,
+    verifier: MlClient,
+    cfg: MlConfig,
+    shutdown: Shutdown,
+) {
+    let interval = std::time::Duration::from_secs(cfg.revalidation.interval_secs.max(1));
+    tracing::info!(
+        queue_size = cfg.revalidation.queue_size,
+        "ML revalidation running with interval {}s",
+        interval.as_secs()
+    );
+
+    while shutdown.sleep(interval).await {
+        if queue.is_empty() {
+            continue;
+        }
+
+        let summary = queue.revalidate(&verifier, &cfg).await;
+        for late in &summary.rejections {
+            tracing::warn!(
+                block = %hex::encode(late.block_hash.0.as_bytes()),
+                height = late.height,
+                proposer = %hex::encode(late.proposer.0.as_bytes()),
+                aid = %hex::encode(late.aid.0.as_bytes()),
+                scheme = %late.verdict.scheme_id,
+                "deferred artefact failed ML revalidation"
+            );
+        }
+        tracing::info!(
+            accepted = summary.accepted,
+            rejected = summary.rejections.len(),
+            retried = summary.retried,
+            dropped = summary.dropped,
+            "ML revalidation round finished"
+        );
+    }
+}
+
+/// Background history pruning loop.
+///
+/// Periodically deletes finalized blocks that fall outside the configured
+/// retention window and records how many were removed.
+async fn run_history_pruning(state: SharedState, cfg: PruningConfig, shutdown: Shutdown) {
+    let interval = std::time::Duration::from_secs(cfg.interval_secs.max(1));
+    let pruner = Pruner::from_config(&cfg);
+    tracing::info!(
+        keep_blocks = cfg.keep_blocks,
+        keep_days = cfg.keep_days,
+        "history pruning running with interval {}s",
+        interval.as_secs()
+    );
+
+    while shutdown.sleep(interval).await {
+        let result = {
+            let mut engine_guard = state.engine.lock().await;
+            engine_guard.prune(&pruner, current_unix_timestamp())
+        };
+        let report = match result {
+            Ok(report) => report,
+            Err(e) => {
+                tracing::warn!("history pruning failed: {e}");
+                continue;
+            }
+        };
+
+        state
+            .metrics
+            .consensus
+            .pruned_blocks
+            .inc_by(report.blocks_pruned);
+
+        if report.blocks_pruned > 0 {
+            tracing::info!(
+                blocks = report.blocks_pruned,
+                checkpoint_height = report.checkpoint_height,
+                "history pruning removed old blocks"
+            );
+        }
     }
 }
 
@@ -211,10 +987,3 @@ fn current_unix_timestamp() -> u64 {
         .unwrap_or_else(|_| Duration::from_secs(0))
         .as_secs()
 }
-
-/// Waits for Ctrl-C and returns, used for graceful shutdown.
-async fn shutdown_signal() {
-    // Wait for Ctrl+C
-    let _ = signal::ctrl_c().await;
-    tracing::info!("shutdown signal received");
-}
@@ -0,0 +1,64 @@
+//! OpenAPI description of the HTTP API.
+//!
+//! The document is generated from the route handlers' `#[utoipa::path]`
+//! annotations and served at `GET /openapi.json`, with Swagger UI at
+//! `/docs`. Only the `/v1` paths are listed; their unversioned aliases are
+//! deprecated. JSON-RPC (`POST /rpc`) follows its own specification and
+//! is described in the README instead.
+
+use utoipa::OpenApi;
+
+use crate::error::ErrorBody;
+use crate::routes::{
+    QueuedTxResponse, SignedDto, accounts, admin, artefacts, blobs, blocks, events, export, health,
+    models, sync, transfers, txs, validators,
+};
+
+/// Path of the generated document.
+pub const OPENAPI_PATH: &str = "/openapi.json";
+
+/// Path Swagger UI is served under.
+pub const SWAGGER_UI_PATH: &str = "/docs";
+
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "mlsnitch API gateway"),
+    paths(
+        health::health,
+        health::ready,
+        models::register_model,
+        models::use_model,
+        blobs::upload_model,
+        blobs::get_model_bytes,
+        artefacts::list_models,
+        artefacts::get_artefact,
+        blocks::get_recent_blocks,
+        blocks::get_tip_block,
+        blocks::get_block,
+        blocks::get_block_raw,
+        export::export_blocks,
+        transfers::transfer,
+        txs::get_tx,
+        txs::get_tx_receipt,
+        sync::get_sync_status,
+        admin::get_orphans,
+        validators::get_validator_report,
+        accounts::get_account,
+        accounts::get_inclusion_stats,
+        accounts::get_account_txs,
+        events::subscribe,
+    ),
+    components(schemas(ErrorBody, QueuedTxResponse, SignedDto, export::ExportFormat)),
+    tags(
+        (name = "health", description = "Liveness and readiness probes"),
+        (name = "models", description = "Model registration, usage and artefact bytes"),
+        (name = "blocks", description = "Block lookup and export"),
+        (name = "transactions", description = "Transaction status and receipts"),
+        (name = "accounts", description = "Transfers, account state and per-account statistics"),
+        (name = "validators", description = "Validator accountability"),
+        (name = "sync", description = "Block sync progress"),
+        (name = "admin", description = "Node diagnostics, behind the API key"),
+        (name = "events", description = "WebSocket chain event stream"),
+    )
+)]
+pub struct ApiDoc;
@@ -0,0 +1,125 @@
+//! Per-client rate limiting of write endpoints.
+//!
+//! Each client gets a token bucket holding up to `api.rate_limit.burst`
+//! requests and refilled at `requests_per_second`. Requests authenticated
+//! with an API key (see `auth`) are counted per key, all others per
+//! client IP address. A client with an empty bucket is answered with
+//! `429 Too Many Requests`, a `rate_limited` error body and a
+//! `Retry-After` header.
+//!
+//! Buckets are forgotten once they have refilled. If `max_clients`
+//! clients are still being throttled, new clients are refused until one
+//! of them goes idle.
+
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    http::{StatusCode, header},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+
+use crate::auth::ApiKeyId;
+use crate::config::RateLimitConfig;
+use crate::error::ApiError;
+use crate::listener::ClientAddr;
+
+/// Who a request is counted against.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+enum ClientKey {
+    ApiKey(ApiKeyId),
+    Ip(IpAddr),
+}
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+impl Bucket {
+    /// Tokens available at `now`, capped at `burst`.
+    fn tokens_at(&self, now: Instant, rate: f64, burst: f64) -> f64 {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        (self.tokens + elapsed * rate).min(burst)
+    }
+}
+
+/// Token buckets of all clients seen recently.
+#[derive(Debug)]
+pub struct RateLimiter {
+    rate: f64,
+    burst: f64,
+    max_clients: usize,
+    buckets: Mutex<HashMap<ClientKey, Bucket>>,
+}
+
+impl RateLimiter {
+    pub fn new(cfg: &RateLimitConfig) -> Self {
+        Self {
+            rate: f64::from(cfg.requests_per_second),
+            burst: f64::from(cfg.burst),
+            max_clients: cfg.max_clients,
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Takes one token from `client`'s bucket, or returns how long until
+    /// one is available.
+    fn acquire(&self, client: ClientKey, now: Instant) -> Result<(), Duration> {
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+        if !buckets.contains_key(&client) && buckets.len() >= self.max_clients {
+            // Full buckets carry no information; a returning client starts
+            // with a full one anyway.
+            buckets.retain(|_, b| b.tokens_at(now, self.rate, self.burst) < self.burst);
+            if buckets.len() >= self.max_clients {
+                return Err(Duration::from_secs(1));
+            }
+        }
+
+        let bucket = buckets.entry(client).or_insert(Bucket {
+            tokens: self.burst,
+            updated: now,
+        });
+        bucket.tokens = bucket.tokens_at(now, self.rate, self.burst);
+        bucket.updated = now;
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1.0 - bucket.tokens) / self.rate))
+        }
+    }
+}
+
+/// Rejects requests from clients that exceeded their rate.
+pub async fn limit(
+    State(limiter): State<Arc<RateLimiter>>,
+    ConnectInfo(ClientAddr(addr)): ConnectInfo<ClientAddr>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let client = match req.extensions().get::<ApiKeyId>() {
+        Some(id) => ClientKey::ApiKey(*id),
+        None => ClientKey::Ip(addr.ip()),
+    };
+    match limiter.acquire(client, Instant::now()) {
+        Ok(()) => next.run(req).await,
+        Err(wait) => {
+            let retry_after = wait.as_secs_f64().ceil().max(1.0) as u64;
+            (
+                [(header::RETRY_AFTER, retry_after.to_string())],
+                ApiError::new(
+                    StatusCode::TOO_MANY_REQUESTS,
+                    "rate_limited",
+                    format!("rate limit exceeded; retry in {retry_after}s"),
+                ),
+            )
+                .into_response()
+        }
+    }
+}
@@ -0,0 +1,286 @@
+//! Account state, transaction history and registration statistics routes.
+
+use axum::extract::State;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use utoipa::{IntoParams, ToSchema};
+
+use chain::{AccountSummary, Block, BlockHash, BlockStore, StateReader, TxLocation};
+
+use super::txs::tx_json;
+use super::{as_storage_error, parse_account};
+use crate::error::{ApiError, ErrorBody};
+use crate::extract::{Json, Path, Query};
+use crate::state::SharedState;
+
+/// Default number of transactions per `GET /accounts/{id}/txs` page.
+const DEFAULT_PAGE_SIZE: usize = 50;
+
+/// Maximum number of transactions per `GET /accounts/{id}/txs` page.
+const MAX_PAGE_SIZE: usize = 500;
+
+/// Response body for `GET /accounts/{id}`.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AccountResponse {
+    /// Hex-encoded `AccountId`.
+    pub account: String,
+    /// Spendable balance.
+    pub balance: u64,
+    /// Nonce of the account's next transaction to execute on-chain.
+    pub nonce: u64,
+    /// Nonce a new transaction should use: `nonce` advanced past the
+    /// account's consecutive transactions waiting in the mempool.
+    pub next_nonce: u64,
+    /// Stake bonding and unbonding.
+    pub stake: StakeResponse,
+    /// Hex-encoded `Aid`s of the artefacts the account owns, revoked ones
+    /// included, in ascending order.
+    pub owned_aids: Vec<String>,
+}
+
+/// Stake record within an [`AccountResponse`].
+#[derive(Debug, Serialize, ToSchema)]
+pub struct StakeResponse {
+    /// Currently bonded stake.
+    pub bonded: u64,
+    /// Stake counted for proposer selection this epoch.
+    pub active: u64,
+    /// Stake waiting to return to the balance.
+    pub unbonding: Vec<UnbondingResponse>,
+}
+
+/// One unbonding entry within a [`StakeResponse`].
+#[derive(Debug, Serialize, ToSchema)]
+pub struct UnbondingResponse {
+    pub amount: u64,
+    /// First epoch in which `amount` is returned to the balance.
+    pub release_epoch: u64,
+}
+
+/// `GET /accounts/{id}`
+///
+/// Returns the account's balance, nonces, stake and owned artefacts at
+/// the current tip, read through [`StateReader`], so clients can check
+/// funds and pick a nonce before submitting. Unknown accounts read as
+/// empty, with a zero balance and nonce.
+///
+/// `next_nonce` reads the mempool, which the block producer does not hold
+/// while a block is validated, so the route does not wait on imports.
+#[utoipa::path(
+    get,
+    path = "/v1/accounts/{id}",
+    tag = "accounts",
+    params(("id" = String, Path, description = "Account address (`mls1…`) or hex")),
+    responses(
+        (status = 200, description = "Account state", body = AccountResponse),
+        (status = 400, description = "Malformed address", body = ErrorBody),
+    )
+)]
+pub async fn get_account(
+    State(state): State<SharedState>,
+    Path(id_hex): Path<String>,
+) -> Result<Json<AccountResponse>, ApiError> {
+    let account = parse_account(&id_hex)?;
+
+    let chain_state = state.chain.state();
+    let AccountSummary {
+        account: record,
+        stake,
+        owned,
+    } = chain_state.account_summary(&account);
+    let next_nonce = state
+        .tx_pool
+        .lock()
+        .await
+        .next_nonce(&account, &chain_state);
+
+    Ok(Json(AccountResponse {
+        account: hex::encode(account.0.as_bytes()),
+        balance: record.balance,
+        nonce: record.nonce,
+        next_nonce,
+        stake: StakeResponse {
+            bonded: stake.bonded,
+            active: stake.active,
+            unbonding: stake
+                .unbonding
+                .iter()
+                .map(|u| UnbondingResponse {
+                    amount: u.amount,
+                    release_epoch: u.release_epoch,
+                })
+                .collect(),
+        },
+        owned_aids: owned
+            .iter()
+            .map(|aid| hex::encode(aid.as_hash().as_bytes()))
+            .collect(),
+    }))
+}
+
+/// Response body for `GET /accounts/{id}/inclusion`.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct InclusionStatsResponse {
+    /// Hex-encoded owner `AccountId`.
+    pub owner: String,
+    /// Registrations by this owner included in a block.
+    pub included: u64,
+    /// Registrations by this owner still waiting in the mempool.
+    pub pending: u64,
+    /// Mean blocks between admission and inclusion; `null` if none were
+    /// included yet.
+    pub avg_delay_blocks: Option<f64>,
+    /// Longest inclusion delay seen, in blocks.
+    pub max_delay_blocks: u64,
+}
+
+/// `GET /accounts/{id}/inclusion`
+///
+/// Returns how long this owner's model registrations waited between
+/// mempool admission and block inclusion. Only registrations submitted
+/// through this gateway since it started are counted; unknown owners get
+/// an all-zero response.
+#[utoipa::path(
+    get,
+    path = "/v1/accounts/{id}/inclusion",
+    tag = "accounts",
+    params(("id" = String, Path, description = "Account address (`mls1…`) or hex")),
+    responses(
+        (status = 200, description = "Inclusion statistics", body = InclusionStatsResponse),
+        (status = 400, description = "Malformed address", body = ErrorBody),
+    )
+)]
+pub async fn get_inclusion_stats(
+    State(state): State<SharedState>,
+    Path(id_hex): Path<String>,
+) -> Result<Json<InclusionStatsResponse>, ApiError> {
+    let owner = parse_account(&id_hex)?;
+
+    let (stats, pending) = {
+        let inclusion = state.inclusion.lock().await;
+        (inclusion.owner_stats(&owner), inclusion.pending_for(&owner))
+    };
+
+    Ok(Json(InclusionStatsResponse {
+        owner: hex::encode(owner.0.as_bytes()),
+        included: stats.included,
+        pending,
+        avg_delay_blocks: stats.average_delay_blocks(),
+        max_delay_blocks: stats.max_delay_blocks,
+    }))
+}
+
+/// Query parameters for `GET /accounts/{id}/txs`.
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct AccountTxsQuery {
+    /// Number of transactions to return; defaults to 50, capped at 500.
+    pub limit: Option<usize>,
+    /// Cursor to continue after, as returned in `next`.
+    pub after: Option<String>,
+}
+
+/// One transaction in a `GET /accounts/{id}/txs` page.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AccountTxEntry {
+    /// Hex-encoded transaction hash.
+    pub hash: String,
+    /// Height of the canonical block holding the transaction.
+    pub block_height: u64,
+    /// Hex-encoded hash of that block.
+    pub block_hash: String,
+    /// Position of the transaction in the block.
+    pub index: usize,
+    /// The transaction, rendered like `GET /txs/{hash}`.
+    pub tx: Value,
+}
+
+/// Response body for `GET /accounts/{id}/txs`.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AccountTxsPage {
+    /// Transactions the account sent or received, newest first.
+    pub txs: Vec<AccountTxEntry>,
+    /// Cursor for the following page, or `None` on the last page.
+    pub next: Option<String>,
+}
+
+/// Encodes the position of a transaction as a page cursor.
+fn cursor(location: &TxLocation) -> String {
+    format!("{}:{}", location.height, location.index)
+}
+
+/// Parses a cursor written by [`cursor`].
+fn parse_cursor(s: &str) -> Result<(u64, usize), ApiError> {
+    s.split_once(':')
+        .and_then(|(height, index)| Some((height.parse().ok()?, index.parse().ok()?)))
+        .ok_or_else(|| ApiError::bad_request("invalid_cursor", "malformed cursor"))
+}
+
+/// `GET /accounts/{id}/txs?limit=N&after=CURSOR`
+///
+/// Lists the canonical transactions the account sent or received
+/// (transfer and model transfer recipients and slashed proposers
+/// included), newest first, from the engine's per-account transaction
+/// index. Pass the returned `next` as `after` to fetch the following page.
+/// Unknown accounts get an empty list.
+#[utoipa::path(
+    get,
+    path = "/v1/accounts/{id}/txs",
+    tag = "accounts",
+    params(
+        ("id" = String, Path, description = "Account address (`mls1…`) or hex"),
+        AccountTxsQuery,
+    ),
+    responses(
+        (status = 200, description = "One page of transactions", body = AccountTxsPage),
+        (status = 400, description = "Malformed address or cursor", body = ErrorBody),
+        (status = 500, description = "Storage error", body = ErrorBody),
+    )
+)]
+pub async fn get_account_txs(
+    State(state): State<SharedState>,
+    Path(id_hex): Path<String>,
+    Query(query): Query<AccountTxsQuery>,
+) -> Result<Json<AccountTxsPage>, ApiError> {
+    let account = parse_account(&id_hex)?;
+    let after = query.after.as_deref().map(parse_cursor).transpose()?;
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_PAGE_SIZE)
+        .clamp(1, MAX_PAGE_SIZE);
+
+    let mut locations = state
+        .chain
+        .account_txs(&account, after, limit + 1)
+        .map_err(as_storage_error)?;
+    let next = (locations.len() > limit).then(|| cursor(&locations[limit - 1]));
+    locations.truncate(limit);
+
+    // Consecutive entries often share a block, so the last one is kept.
+    let mut txs = Vec::with_capacity(locations.len());
+    let mut cached: Option<(BlockHash, Block)> = None;
+    for location in &locations {
+        if cached.as_ref().map(|(hash, _)| *hash) != Some(location.block_hash) {
+            cached = state
+                .chain
+                .store()
+                .get_block(&location.block_hash)
+                .map_err(as_storage_error)?
+                .map(|block| (location.block_hash, block));
+        }
+        let Some(tx) = cached
+            .as_ref()
+            .and_then(|(_, block)| block.txs.get(location.index))
+        else {
+            continue;
+        };
+        txs.push(AccountTxEntry {
+            hash: hex::encode(tx.compute_hash().0.as_bytes()),
+            block_height: location.height,
+            block_hash: hex::encode(location.block_hash.0.as_bytes()),
+            index: location.index,
+            tx: tx_json(tx),
+        });
+    }
+
+    Ok(Json(AccountTxsPage { txs, next }))
+}
@@ -0,0 +1,70 @@
+use std::time::SystemTime;
+
+use axum::{Json, extract::State};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use chain::PendingBlock;
+
+use crate::state::SharedState;
+
+/// A block waiting to be imported.
+#[derive(Serialize, ToSchema)]
+pub struct PendingBlockResponse {
+    /// Hex-encoded block hash.
+    pub hash: String,
+    /// Height the block claims.
+    pub height: u64,
+    /// Hex-encoded hash of the parent the block waits for.
+    pub missing_parent: String,
+    /// Seconds since the block was received.
+    pub age_secs: f64,
+}
+
+impl PendingBlockResponse {
+    fn new(pending: &PendingBlock, now: SystemTime) -> Self {
+        Self {
+            hash: hex::encode(pending.hash.0.as_bytes()),
+            height: pending.height,
+            missing_parent: hex::encode(pending.parent.0.as_bytes()),
+            age_secs: pending.age(now).as_secs_f64(),
+        }
+    }
+}
+
+/// Blocks received but not imported yet.
+#[derive(Serialize, ToSchema)]
+pub struct PendingBlocksResponse {
+    /// Blocks parked in the orphan buffer until their parent arrives,
+    /// oldest first.
+    pub orphans: Vec<PendingBlockResponse>,
+    /// Blocks announced to the running sync and not imported yet, in
+    /// height order.
+    pub import_queue: Vec<PendingBlockResponse>,
+}
+
+/// `GET /admin/orphans`
+///
+/// Lists the blocks waiting in the orphan buffer and in the sync import
+/// queue, with the parent each one is missing and how long it has waited,
+/// to diagnose a node that stopped advancing. Reads the engine's shared
+/// view and the [`chain::SyncHandle`]; it never waits on the engine.
+#[utoipa::path(
+    get,
+    path = "/v1/admin/orphans",
+    tag = "admin",
+    responses((status = 200, description = "Buffered blocks", body = PendingBlocksResponse))
+)]
+pub async fn get_orphans(State(state): State<SharedState>) -> Json<PendingBlocksResponse> {
+    let now = SystemTime::now();
+    let listed = |pending: &[PendingBlock]| -> Vec<PendingBlockResponse> {
+        pending
+            .iter()
+            .map(|pending| PendingBlockResponse::new(pending, now))
+            .collect()
+    };
+    Json(PendingBlocksResponse {
+        orphans: listed(&state.chain.orphans()),
+        import_queue: listed(&state.sync.status().queue),
+    })
+}
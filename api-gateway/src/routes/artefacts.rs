@@ -0,0 +1,193 @@
+//! Artefact registry lookup routes.
+//!
+//! The registry is read from the execution-layer chain state at the
+//! current tip. `GET /models/{aid}` is an alias of `GET /artefacts/{aid}`.
+
+use axum::extract::State;
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+use chain::{ArtefactMetadata, ChunkedAid, WmProfile};
+
+use super::models::WmProfileDto;
+use super::{as_storage_error, parse_account, parse_aid};
+use crate::error::{ApiError, ErrorBody};
+use crate::extract::{Json, Path, Query};
+use crate::state::SharedState;
+
+/// Default number of artefacts per `GET /models` page.
+const DEFAULT_PAGE_SIZE: usize = 50;
+
+/// Maximum number of artefacts per `GET /models` page.
+const MAX_PAGE_SIZE: usize = 500;
+
+/// Response body for `GET /artefacts/{aid}` and `GET /models/{aid}`, and
+/// one entry of `GET /models`.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ArtefactResponse {
+    /// Hex-encoded artefact `Aid`.
+    pub aid: String,
+    /// Hex-encoded owner `AccountId`.
+    pub owner: String,
+    /// Watermark scheme the artefact was registered under.
+    pub scheme_id: String,
+    /// Hex-encoded evidence hash.
+    pub evidence_hash: String,
+    /// Watermark detector parameters.
+    #[schema(value_type = WmProfileDto)]
+    pub wm_profile: WmProfile,
+    /// Height of the block that registered the artefact.
+    pub registered_at: u64,
+    /// Height of the block that revoked the artefact, if revoked.
+    pub revoked_at: Option<u64>,
+    /// Chunked form of the `Aid`, if registered with one.
+    pub chunked: Option<ChunkedAidResponse>,
+}
+
+/// Chunked `Aid` form within an [`ArtefactResponse`].
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ChunkedAidResponse {
+    pub chunk_size: u32,
+    pub total_len: u64,
+    /// Hex-encoded Merkle root over the chunk hashes.
+    pub root: String,
+}
+
+impl From<&ChunkedAid> for ChunkedAidResponse {
+    fn from(chunked: &ChunkedAid) -> Self {
+        Self {
+            chunk_size: chunked.chunk_size,
+            total_len: chunked.total_len,
+            root: hex::encode(chunked.root.as_bytes()),
+        }
+    }
+}
+
+impl From<&ArtefactMetadata> for ArtefactResponse {
+    fn from(meta: &ArtefactMetadata) -> Self {
+        Self {
+            aid: hex::encode(meta.aid.as_hash().as_bytes()),
+            owner: hex::encode(meta.owner.0.as_bytes()),
+            scheme_id: meta.evidence.scheme_id.clone(),
+            evidence_hash: hex::encode(meta.evidence.evidence_hash.0.as_bytes()),
+            wm_profile: meta.evidence.wm_profile.clone(),
+            registered_at: meta.registered_at,
+            revoked_at: meta.revoked_at,
+            chunked: meta.chunked.as_ref().map(ChunkedAidResponse::from),
+        }
+    }
+}
+
+/// Query parameters for `GET /models`.
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct ModelsQuery {
+    /// Number of artefacts to return; defaults to 50, capped at 500.
+    pub limit: Option<usize>,
+    /// `Aid` to continue after, as returned in `next` (hex or `aid1…`).
+    pub after: Option<String>,
+    /// Only list artefacts currently owned by this account (`mls1…` or
+    /// hex).
+    pub owner: Option<String>,
+    /// Only list artefacts registered under this watermark scheme.
+    pub scheme: Option<String>,
+}
+
+/// Response body for `GET /models`.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ModelsPage {
+    /// Registered artefacts in ascending `Aid` order.
+    pub models: Vec<ArtefactResponse>,
+    /// Cursor for the following page, or `None` on the last page.
+    pub next: Option<String>,
+}
+
+/// `GET /models?limit=N&after=AID&owner=ACCOUNT&scheme=ID`
+///
+/// Lists registered artefacts, revoked ones included, in ascending `Aid`
+/// order, optionally only those of the current `owner` and those
+/// registered under `scheme`. A scheme is looked up in the engine's
+/// per-scheme registration index instead of scanning the registry. Pass
+/// the returned `next` as `after` to fetch the following page.
+#[utoipa::path(
+    get,
+    path = "/v1/models",
+    tag = "models",
+    params(ModelsQuery),
+    responses(
+        (status = 200, description = "One page of artefacts", body = ModelsPage),
+        (status = 400, description = "Malformed cursor or owner", body = ErrorBody),
+        (status = 500, description = "Storage error", body = ErrorBody),
+    )
+)]
+pub async fn list_models(
+    State(state): State<SharedState>,
+    Query(query): Query<ModelsQuery>,
+) -> Result<Json<ModelsPage>, ApiError> {
+    let after = query.after.as_deref().map(parse_aid).transpose()?;
+    let owner = query.owner.as_deref().map(parse_account).transpose()?;
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_PAGE_SIZE)
+        .clamp(1, MAX_PAGE_SIZE);
+
+    let chain_state = state.chain.state();
+    let candidates: Vec<&ArtefactMetadata> = match &query.scheme {
+        // The index lists every registration transaction, including ones
+        // that failed, so each is checked against the registry.
+        Some(scheme) => state
+            .chain
+            .registrations(scheme)
+            .map_err(as_storage_error)?
+            .into_iter()
+            .filter_map(|registration| chain_state.artefact(&registration.aid))
+            .filter(|meta| meta.evidence.scheme_id == *scheme)
+            .collect(),
+        None => chain_state.artefacts().map(|(_, meta)| meta).collect(),
+    };
+    let mut artefacts: Vec<&ArtefactMetadata> = candidates
+        .into_iter()
+        .filter(|meta| owner.is_none_or(|owner| meta.owner == owner))
+        .filter(|meta| after.is_none_or(|after| meta.aid.0.0 > after.0.0))
+        .collect();
+    artefacts.sort_by_key(|meta| meta.aid.0.0);
+    artefacts.dedup_by_key(|meta| meta.aid.0.0);
+
+    let next = (artefacts.len() > limit)
+        .then(|| hex::encode(artefacts[limit - 1].aid.as_hash().as_bytes()));
+    let models = artefacts
+        .into_iter()
+        .take(limit)
+        .map(ArtefactResponse::from)
+        .collect();
+
+    Ok(Json(ModelsPage { models, next }))
+}
+
+/// `GET /artefacts/{aid}`, `GET /models/{aid}`
+///
+/// Returns the registry entry for an artefact from the current chain
+/// state. Unregistered artefacts return `404`.
+#[utoipa::path(
+    get,
+    path = "/v1/artefacts/{aid}",
+    tag = "models",
+    params(("aid" = String, Path, description = "Artefact address (`aid1…`) or hex")),
+    responses(
+        (status = 200, description = "Registry entry", body = ArtefactResponse),
+        (status = 400, description = "Malformed address", body = ErrorBody),
+        (status = 404, description = "Artefact not registered", body = ErrorBody),
+    )
+)]
+pub async fn get_artefact(
+    State(state): State<SharedState>,
+    Path(aid_hex): Path<String>,
+) -> Result<Json<ArtefactResponse>, ApiError> {
+    let aid = parse_aid(&aid_hex)?;
+
+    let chain_state = state.chain.state();
+    let meta = chain_state
+        .artefact(&aid)
+        .ok_or_else(|| ApiError::not_found("artefact_not_found", "artefact not registered"))?;
+
+    Ok(Json(ArtefactResponse::from(meta)))
+}
@@ -0,0 +1,154 @@
+//! Artefact blob upload and download routes.
+//!
+//! Model bytes are kept in the node's content-addressed artefact store
+//! (`api.artefact_store`), keyed by the `Aid` they hash to. Both routes
+//! answer `404` when the store is disabled.
+
+use axum::{
+    body::Body,
+    extract::State,
+    http::{StatusCode, header},
+    response::IntoResponse,
+};
+use futures_util::StreamExt;
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use chain::{ArtefactStoreError, FsArtefactStore};
+
+use super::parse_aid;
+use crate::error::{ApiError, ErrorBody};
+use crate::extract::{Json, Path};
+use crate::state::SharedState;
+
+/// Response body for `POST /models/upload`.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct UploadResponse {
+    /// Hex-encoded `Aid` of the uploaded bytes.
+    pub aid: String,
+    /// Size of the upload in bytes.
+    pub size: u64,
+    /// `false` if the store already held these bytes.
+    pub created: bool,
+}
+
+fn artefact_store(state: &SharedState) -> Result<&FsArtefactStore, ApiError> {
+    state.artefacts.as_ref().ok_or_else(|| {
+        ApiError::not_found(
+            "artefact_store_disabled",
+            "artefact store is not enabled on this node",
+        )
+    })
+}
+
+fn as_store_error(e: ArtefactStoreError) -> ApiError {
+    let (status, code) = match e {
+        ArtefactStoreError::TooLarge { .. } => (StatusCode::PAYLOAD_TOO_LARGE, "blob_too_large"),
+        ArtefactStoreError::Io(_) | ArtefactStoreError::Corrupted(_) => {
+            tracing::error!("artefact store error: {e}");
+            (StatusCode::INTERNAL_SERVER_ERROR, "artefact_store_error")
+        }
+    };
+    ApiError::new(status, code, format!("artefact store error: {e}"))
+}
+
+/// `POST /models/upload`
+///
+/// Stores the raw request body as a model artefact and returns its `Aid`,
+/// computed while the body streams in, so uploads are never buffered in
+/// memory. Answers `201` for new bytes, `200` if the store already held
+/// them, and `413` once the body exceeds `api.artefact_store.max_blob_bytes`.
+/// Uploading does not register the model; pass the returned `aid` to
+/// `POST /models/register` for that.
+#[utoipa::path(
+    post,
+    path = "/v1/models/upload",
+    tag = "models",
+    request_body(content = Vec<u8>, content_type = "application/octet-stream"),
+    responses(
+        (status = 200, description = "Bytes were already stored", body = UploadResponse),
+        (status = 201, description = "Bytes stored", body = UploadResponse),
+        (status = 404, description = "Artefact store disabled", body = ErrorBody),
+        (status = 401, description = "API key missing", body = ErrorBody),
+        (status = 413, description = "Upload too large", body = ErrorBody),
+        (status = 429, description = "Rate limited", body = ErrorBody),
+    )
+)]
+pub async fn upload_model(
+    State(state): State<SharedState>,
+    body: Body,
+) -> Result<(StatusCode, Json<UploadResponse>), ApiError> {
+    let store = artefact_store(&state)?;
+    let mut upload = store.begin_upload().await.map_err(as_store_error)?;
+
+    let mut stream = body.into_data_stream();
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk
+            .map_err(|_| ApiError::bad_request("invalid_body", "failed to read request body"))?;
+        upload.write(&chunk).await.map_err(as_store_error)?;
+    }
+    let stored = upload.commit().await.map_err(as_store_error)?;
+
+    let status = if stored.created {
+        StatusCode::CREATED
+    } else {
+        StatusCode::OK
+    };
+    Ok((
+        status,
+        Json(UploadResponse {
+            aid: hex::encode(stored.aid.0.as_bytes()),
+            size: stored.size,
+            created: stored.created,
+        }),
+    ))
+}
+
+/// `GET /models/{aid}/bytes`
+///
+/// Streams the stored bytes of an artefact as `application/octet-stream`.
+/// The blob is re-hashed before anything is sent; if it no longer hashes
+/// to `aid` it is taken out of the store and `500` is returned instead of
+/// corrupted bytes.
+#[utoipa::path(
+    get,
+    path = "/v1/models/{aid}/bytes",
+    tag = "models",
+    params(("aid" = String, Path, description = "Artefact address (`aid1…`) or hex")),
+    responses(
+        (status = 200, description = "Model bytes", content_type = "application/octet-stream", body = Vec<u8>),
+        (status = 404, description = "Bytes not stored, or store disabled", body = ErrorBody),
+        (status = 500, description = "Stored bytes are corrupted", body = ErrorBody),
+    )
+)]
+pub async fn get_model_bytes(
+    State(state): State<SharedState>,
+    Path(aid_hex): Path<String>,
+) -> Result<impl IntoResponse, ApiError> {
+    let aid = parse_aid(&aid_hex)?;
+    let store = artefact_store(&state)?;
+
+    let reader = store
+        .open_verified(&aid)
+        .await
+        .map_err(as_store_error)?
+        .ok_or_else(|| {
+            ApiError::not_found("artefact_bytes_not_found", "artefact bytes not found")
+        })?;
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/octet-stream".to_string()),
+            (header::CONTENT_LENGTH, reader.size().to_string()),
+            (
+                header::ETAG,
+                format!("\"{}\"", hex::encode(aid.0.as_bytes())),
+            ),
+            (
+                header::CACHE_CONTROL,
+                "public, max-age=31536000, immutable".to_string(),
+            ),
+        ],
+        Body::from_stream(reader.into_stream()),
+    ))
+}
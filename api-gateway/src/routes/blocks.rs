@@ -0,0 +1,269 @@
+//! Block access routes.
+
+use axum::{
+    extract::State,
+    http::{HeaderMap, HeaderName, HeaderValue, header},
+    response::IntoResponse,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use utoipa::{IntoParams, ToSchema};
+
+use chain::{Block, BlockHash, BlockStore};
+
+use super::txs::tx_json;
+use super::{as_invalid_hash, as_storage_error, hex_to_hash256};
+use crate::error::{ApiError, ErrorBody};
+use crate::extract::{Json, Path, Query};
+use crate::state::SharedState;
+
+/// Response header carrying the hex-encoded block hash.
+const BLOCK_HASH_HEADER: HeaderName = HeaderName::from_static("x-block-hash");
+
+/// Response header carrying the `from` of the next `GET /blocks` page.
+const NEXT_CURSOR_HEADER: HeaderName = HeaderName::from_static("x-next-cursor");
+
+/// Default number of blocks returned by `GET /blocks`.
+const DEFAULT_RECENT_BLOCKS: u64 = 20;
+
+/// Maximum number of blocks returned by `GET /blocks`.
+const MAX_RECENT_BLOCKS: u64 = 100;
+
+/// Query parameters for `GET /blocks`.
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct RecentBlocksQuery {
+    /// Number of blocks to return, newest first; defaults to 20, capped at
+    /// 100.
+    pub limit: Option<u64>,
+    /// Height of the newest block to return; defaults to the tip. Pass the
+    /// `X-Next-Cursor` of the previous page to continue below it.
+    pub from: Option<u64>,
+}
+
+/// One block in the `GET /blocks` response, and the body of
+/// `GET /blocks/{height_or_hash}` and `GET /blocks/tip`.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct BlockSummary {
+    /// Canonical height.
+    pub height: u64,
+    /// Hex-encoded block hash.
+    pub hash: String,
+    /// Hex-encoded parent hash.
+    pub parent: String,
+    /// Block timestamp, in Unix seconds.
+    pub timestamp: u64,
+    /// Hex-encoded proposer `AccountId`.
+    pub proposer: String,
+    /// Hex-encoded Merkle root over the block's transaction hashes.
+    pub txs_root: String,
+    /// Hex-encoded Merkle root of the post-block chain state.
+    pub state_root: String,
+    /// Hex-encoded Merkle root of the block's transaction receipts.
+    pub receipts_root: String,
+    /// Hex-encoded hashes of the block's transactions, in block order.
+    pub tx_hashes: Vec<String>,
+    /// The block's transactions, rendered like `GET /txs/{hash}`.
+    pub txs: Vec<Value>,
+}
+
+impl From<&Block> for BlockSummary {
+    fn from(block: &Block) -> Self {
+        Self {
+            height: block.header.height,
+            hash: hex::encode(block.compute_hash().0.as_bytes()),
+            parent: hex::encode(block.header.parent.0.as_bytes()),
+            timestamp: block.header.timestamp,
+            proposer: hex::encode(block.header.proposer.0.as_bytes()),
+            txs_root: hex::encode(block.header.txs_root.as_bytes()),
+            state_root: hex::encode(block.header.state_root.as_bytes()),
+            receipts_root: hex::encode(block.header.receipts_root.as_bytes()),
+            tx_hashes: block
+                .txs
+                .iter()
+                .map(|tx| hex::encode(tx.compute_hash().0.as_bytes()))
+                .collect(),
+            txs: block.txs.iter().map(tx_json).collect(),
+        }
+    }
+}
+
+/// `GET /blocks?from=H&limit=N`
+///
+/// Returns canonical blocks from height `from` (default: the tip)
+/// downwards, newest first, read through the store's height index. When
+/// older blocks remain, the `X-Next-Cursor` header holds the `from` of the
+/// next page; the body stays a plain list for existing clients. An empty
+/// chain returns an empty list.
+#[utoipa::path(
+    get,
+    path = "/v1/blocks",
+    tag = "blocks",
+    params(RecentBlocksQuery),
+    responses(
+        (status = 200, description = "Canonical blocks, newest first; `X-Next-Cursor` holds the next page's `from`", body = [BlockSummary]),
+        (status = 500, description = "Storage error", body = ErrorBody),
+    )
+)]
+pub async fn get_recent_blocks(
+    State(state): State<SharedState>,
+    Query(query): Query<RecentBlocksQuery>,
+) -> Result<(HeaderMap, Json<Vec<BlockSummary>>), ApiError> {
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_RECENT_BLOCKS)
+        .min(MAX_RECENT_BLOCKS);
+
+    let Some(tip) = state.chain.tip_block().map_err(as_storage_error)? else {
+        return Ok((HeaderMap::new(), Json(Vec::new())));
+    };
+    let from = query
+        .from
+        .map_or(tip.header.height, |from| from.min(tip.header.height));
+
+    // Pruned heights have no block, so the list stops there.
+    let mut blocks = Vec::new();
+    let mut height = Some(from);
+    while let Some(h) = height {
+        if blocks.len() as u64 >= limit {
+            break;
+        }
+        let Some(block) = state
+            .chain
+            .store()
+            .get_block_by_height(h)
+            .map_err(as_storage_error)?
+        else {
+            break;
+        };
+        blocks.push(BlockSummary::from(&block));
+        height = h.checked_sub(1);
+    }
+
+    let mut headers = HeaderMap::new();
+    if blocks.len() as u64 == limit
+        && let Some(next) = blocks.last().and_then(|block| block.height.checked_sub(1))
+    {
+        headers.insert(NEXT_CURSOR_HEADER, HeaderValue::from(next));
+    }
+
+    Ok((headers, Json(blocks)))
+}
+
+/// `GET /blocks/{height_or_hash}`
+///
+/// Returns one block, rendered like an entry of `GET /blocks`. A decimal
+/// number is looked up as a canonical height; a 64-character hex string
+/// as a block hash, which also finds blocks on side branches. Unknown
+/// blocks return `404`.
+#[utoipa::path(
+    get,
+    path = "/v1/blocks/{id}",
+    tag = "blocks",
+    params(("id" = String, Path, description = "Decimal height or hex block hash")),
+    responses(
+        (status = 200, description = "The block", body = BlockSummary),
+        (status = 400, description = "Malformed height or hash", body = ErrorBody),
+        (status = 404, description = "Unknown block", body = ErrorBody),
+    )
+)]
+pub async fn get_block(
+    State(state): State<SharedState>,
+    Path(height_or_hash): Path<String>,
+) -> Result<Json<BlockSummary>, ApiError> {
+    let block = if height_or_hash.len() < 2 * chain::HASH_LEN
+        && !height_or_hash.is_empty()
+        && height_or_hash.bytes().all(|b| b.is_ascii_digit())
+    {
+        let height = height_or_hash
+            .parse::<u64>()
+            .map_err(|_| ApiError::bad_request("invalid_height", "height out of range"))?;
+        state
+            .chain
+            .store()
+            .get_block_by_height(height)
+            .map_err(as_storage_error)?
+    } else {
+        let hash = BlockHash(hex_to_hash256(&height_or_hash).map_err(as_invalid_hash)?);
+        state
+            .chain
+            .store()
+            .get_block(&hash)
+            .map_err(as_storage_error)?
+    }
+    .ok_or_else(|| ApiError::not_found("block_not_found", "block not found"))?;
+
+    Ok(Json(BlockSummary::from(&block)))
+}
+
+/// `GET /blocks/tip`
+///
+/// Returns the canonical tip, rendered like an entry of `GET /blocks`. An
+/// empty chain returns `404`.
+#[utoipa::path(
+    get,
+    path = "/v1/blocks/tip",
+    tag = "blocks",
+    responses(
+        (status = 200, description = "The canonical tip", body = BlockSummary),
+        (status = 404, description = "Empty chain", body = ErrorBody),
+    )
+)]
+pub async fn get_tip_block(
+    State(state): State<SharedState>,
+) -> Result<Json<BlockSummary>, ApiError> {
+    let block = state
+        .chain
+        .tip_block()
+        .map_err(as_storage_error)?
+        .ok_or_else(|| ApiError::not_found("chain_empty", "chain is empty"))?;
+
+    Ok(Json(BlockSummary::from(&block)))
+}
+
+/// `GET /blocks/{hash}/raw`
+///
+/// Returns the block's canonical bincode encoding as
+/// `application/octet-stream`. The body is content-addressed: its
+/// BLAKE3-256 hash equals `hash`, so clients can verify what they fetched
+/// without trusting the gateway. Blocks never change once stored, so the
+/// response is marked immutable for caches.
+#[utoipa::path(
+    get,
+    path = "/v1/blocks/{hash}/raw",
+    tag = "blocks",
+    params(("hash" = String, Path, description = "Hex block hash")),
+    responses(
+        (status = 200, description = "Canonical block bytes", content_type = "application/octet-stream", body = Vec<u8>),
+        (status = 400, description = "Malformed hash", body = ErrorBody),
+        (status = 404, description = "Unknown block", body = ErrorBody),
+    )
+)]
+pub async fn get_block_raw(
+    State(state): State<SharedState>,
+    Path(hash_hex): Path<String>,
+) -> Result<impl IntoResponse, ApiError> {
+    let hash = BlockHash(hex_to_hash256(&hash_hex).map_err(as_invalid_hash)?);
+
+    let block = state
+        .chain
+        .store()
+        .get_block(&hash)
+        .map_err(as_storage_error)?
+        .ok_or_else(|| ApiError::not_found("block_not_found", "block not found"))?;
+
+    let bytes = block.canonical_bytes();
+    let hash_hex = hex::encode(hash.0.as_bytes());
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/octet-stream".to_string()),
+            (header::ETAG, format!("\"{hash_hex}\"")),
+            (
+                header::CACHE_CONTROL,
+                "public, max-age=31536000, immutable".to_string(),
+            ),
+            (BLOCK_HASH_HEADER, hash_hex),
+        ],
+        bytes,
+    ))
+}
@@ -0,0 +1,99 @@
+//! WebSocket event stream.
+
+use axum::{
+    extract::{
+        State,
+        ws::{Message, WebSocket, WebSocketUpgrade},
+    },
+    response::Response,
+};
+use serde_json::{Value, json};
+use tokio::sync::broadcast::{Receiver, error::RecvError};
+
+use chain::ChainEvent;
+
+use super::blocks::BlockSummary;
+use crate::state::SharedState;
+
+/// `GET /ws`
+///
+/// Upgrades to a WebSocket that streams one JSON text message per chain
+/// event: `block` (an imported block, rendered like `GET /blocks`),
+/// `new_tip`, and `block_rejected` (with `kind` `ml_rejected`,
+/// `ml_unavailable`, `ml_budget_exhausted`, or `invalid`). A client too slow to keep up receives
+/// a `lagged` message with the number of events it missed. Messages sent
+/// by the client are ignored.
+#[utoipa::path(
+    get,
+    path = "/v1/ws",
+    tag = "events",
+    responses((status = 101, description = "Switched to the WebSocket event stream"))
+)]
+pub async fn subscribe(ws: WebSocketUpgrade, State(state): State<SharedState>) -> Response {
+    let events = state.events.subscribe();
+    ws.on_upgrade(move |socket| stream_events(socket, events))
+}
+
+/// Forwards `events` to `socket` until either side closes.
+async fn stream_events(mut socket: WebSocket, mut events: Receiver<ChainEvent>) {
+    loop {
+        tokio::select! {
+            event = events.recv() => {
+                let message = match event {
+                    Ok(event) => event_json(&event),
+                    Err(RecvError::Lagged(skipped)) => json!({ "type": "lagged", "skipped": skipped }),
+                    Err(RecvError::Closed) => break,
+                };
+                if socket.send(Message::Text(message.to_string().into())).await.is_err() {
+                    break;
+                }
+            }
+            incoming = socket.recv() => match incoming {
+                Some(Ok(Message::Close(_)) | Err(_)) | None => break,
+                Some(Ok(_)) => {}
+            },
+        }
+    }
+}
+
+/// Renders a chain event as a JSON message.
+fn event_json(event: &ChainEvent) -> Value {
+    let hex32 = |h: &chain::Hash256| hex::encode(h.as_bytes());
+    match event {
+        ChainEvent::BlockImported { block, .. } => json!({
+            "type": "block",
+            "block": BlockSummary::from(&**block),
+        }),
+        ChainEvent::NewTip {
+            hash,
+            height,
+            reorg_depth,
+        } => json!({
+            "type": "new_tip",
+            "hash": hex32(&hash.0),
+            "height": height,
+            "reorg_depth": reorg_depth,
+        }),
+        ChainEvent::BlockRejected {
+            hash,
+            height,
+            proposer,
+            kind,
+            code,
+            reason,
+        } => json!({
+            "type": "block_rejected",
+            "hash": hex32(&hash.0),
+            "height": height,
+            "proposer": hex32(&proposer.0),
+            "kind": kind.as_str(),
+            "code": code,
+            "reason": reason,
+        }),
+        ChainEvent::BlockCommitted { hash, height } => json!({
+            "type": "block_committed",
+            "hash": hex32(&hash.0),
+            "height": height,
+        }),
+    }
+}
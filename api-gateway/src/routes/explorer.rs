@@ -0,0 +1,17 @@
+//! Embedded block explorer page.
+//!
+//! `GET /explorer` serves a single self-contained HTML page that renders
+//! recent blocks, transaction details, and artefact lookups by calling the
+//! gateway's own JSON APIs (`/v1/blocks`, `/v1/txs/{hash}`,
+//! `/v1/artefacts/{aid}`) from the browser. It needs no build step or
+//! separate front-end, which keeps demos self-contained.
+
+use axum::response::Html;
+
+/// The explorer page, embedded at compile time.
+const EXPLORER_HTML: &str = include_str!("explorer.html");
+
+/// `GET /explorer`
+pub async fn explorer() -> Html<&'static str> {
+    Html(EXPLORER_HTML)
+}
@@ -0,0 +1,147 @@
+//! Bulk block export.
+//!
+//! `GET /export/blocks` streams a height range of canonical blocks so that
+//! analytics pipelines do not need one request per block. Blocks are read
+//! by a background task and handed to the response body through a small
+//! bounded channel: the shared store is only locked for one block lookup
+//! at a time, and a slow client stalls the reader instead of buffering the whole
+//! range in memory.
+
+use axum::{
+    body::{Body, Bytes},
+    extract::State,
+    http::header,
+    response::IntoResponse,
+};
+use serde::Deserialize;
+use tokio::sync::mpsc;
+use utoipa::{IntoParams, ToSchema};
+
+use chain::{Block, BlockStore};
+
+use crate::error::{ApiError, ErrorBody};
+use crate::extract::Query;
+use crate::state::SharedState;
+
+/// Number of encoded blocks buffered between the reader and the client.
+const EXPORT_CHANNEL_CAPACITY: usize = 16;
+
+/// Encoding used for exported blocks.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    /// One JSON object per line: `{"height", "hash", "block"}`.
+    #[default]
+    Ndjson,
+    /// Frames of a 4-byte big-endian length followed by the canonical
+    /// bincode block bytes.
+    Binary,
+}
+
+/// Query parameters for `GET /export/blocks`.
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct ExportQuery {
+    /// First canonical height to export (inclusive).
+    pub from: u64,
+    /// Last canonical height to export (inclusive).
+    pub to: u64,
+    /// Output encoding; defaults to `ndjson`.
+    #[serde(default)]
+    pub format: ExportFormat,
+}
+
+/// Encodes a single block in the requested format.
+fn encode_block(block: &Block, format: ExportFormat) -> Result<Bytes, String> {
+    match format {
+        ExportFormat::Ndjson => {
+            let line = serde_json::json!({
+                "height": block.header.height,
+                "hash": hex::encode(block.compute_hash().0.as_bytes()),
+                "block": block,
+            });
+            let mut bytes = serde_json::to_vec(&line).map_err(|e| e.to_string())?;
+            bytes.push(b'\n');
+            Ok(Bytes::from(bytes))
+        }
+        ExportFormat::Binary => {
+            let canonical = block.canonical_bytes();
+            let len = u32::try_from(canonical.len()).map_err(|e| e.to_string())?;
+            let mut bytes = Vec::with_capacity(4 + canonical.len());
+            bytes.extend_from_slice(&len.to_be_bytes());
+            bytes.extend_from_slice(&canonical);
+            Ok(Bytes::from(bytes))
+        }
+    }
+}
+
+/// `GET /export/blocks?from=H1&to=H2[&format=ndjson|binary]`
+///
+/// Streams the canonical blocks at heights `from..=to`. The range is capped
+/// at `max_export_blocks`; the stream ends early at the first height with
+/// no canonical block (e.g. past the tip).
+#[utoipa::path(
+    get,
+    path = "/v1/export/blocks",
+    tag = "blocks",
+    params(ExportQuery),
+    responses(
+        (status = 200, description = "Block stream", content(
+            (String = "application/x-ndjson"),
+            (Vec<u8> = "application/octet-stream"),
+        )),
+        (status = 400, description = "Invalid or oversized range", body = ErrorBody),
+    )
+)]
+pub async fn export_blocks(
+    State(state): State<SharedState>,
+    Query(query): Query<ExportQuery>,
+) -> Result<impl IntoResponse, ApiError> {
+    if query.to < query.from {
+        return Err(ApiError::bad_request(
+            "invalid_range",
+            "`to` must not be below `from`",
+        ));
+    }
+    let count = query.to - query.from + 1;
+    if count > state.max_export_blocks {
+        return Err(ApiError::bad_request(
+            "range_too_large",
+            format!(
+                "range of {count} blocks exceeds the limit of {}",
+                state.max_export_blocks
+            ),
+        ));
+    }
+
+    let (tx, mut rx) = mpsc::channel::<Result<Bytes, String>>(EXPORT_CHANNEL_CAPACITY);
+    let reader_state = state.clone();
+    tokio::spawn(async move {
+        for height in query.from..=query.to {
+            let block = match reader_state.chain.store().get_block_by_height(height) {
+                Ok(Some(block)) => block,
+                Ok(None) => break,
+                Err(e) => {
+                    // Ends the body with an error so the client sees a
+                    // truncated export rather than a short one.
+                    let _ = tx.send(Err(format!("storage error: {e}"))).await;
+                    break;
+                }
+            };
+            // A send error means the client went away; stop reading.
+            if tx.send(encode_block(&block, query.format)).await.is_err() {
+                break;
+            }
+        }
+    });
+
+    let stream = futures_util::stream::poll_fn(move |cx| rx.poll_recv(cx));
+    let content_type = match query.format {
+        ExportFormat::Ndjson => "application/x-ndjson",
+        ExportFormat::Binary => "application/octet-stream",
+    };
+
+    Ok((
+        [(header::CONTENT_TYPE, content_type)],
+        Body::from_stream(stream),
+    ))
+}
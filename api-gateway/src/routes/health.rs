@@ -1,8 +1,11 @@
-use axum::{Json, http::StatusCode};
+use axum::{Json, extract::State, http::StatusCode};
 use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::state::SharedState;
 
 /// Simple health-check response.
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct HealthResponse {
     pub status: &'static str,
 }
@@ -10,6 +13,52 @@ pub struct HealthResponse {
 /// `GET /health`
 ///
 /// Returns a basic JSON document indicating liveness.
+#[utoipa::path(
+    get,
+    path = "/health",
+    tag = "health",
+    responses((status = 200, description = "The gateway is up", body = HealthResponse))
+)]
 pub async fn health() -> (StatusCode, Json<HealthResponse>) {
     (StatusCode::OK, Json(HealthResponse { status: "ok" }))
 }
+
+/// Readiness response.
+#[derive(Serialize, ToSchema)]
+pub struct ReadyResponse {
+    /// `"ready"`, or `"paused"` while the producer watchdog holds block
+    /// production.
+    pub status: &'static str,
+    /// Consecutive proposals rejected because the ML verifier was
+    /// unavailable.
+    pub consecutive_ml_failures: u32,
+}
+
+/// `GET /ready`
+///
+/// Returns `503 Service Unavailable` while block production is paused by
+/// the watchdog (the ML verifier keeps failing), and `200 OK` otherwise.
+#[utoipa::path(
+    get,
+    path = "/ready",
+    tag = "health",
+    responses(
+        (status = 200, description = "Producing blocks", body = ReadyResponse),
+        (status = 503, description = "Block production paused", body = ReadyResponse),
+    )
+)]
+pub async fn ready(State(state): State<SharedState>) -> (StatusCode, Json<ReadyResponse>) {
+    let watchdog = state.watchdog.lock().await;
+    let (code, status) = if watchdog.is_paused() {
+        (StatusCode::SERVICE_UNAVAILABLE, "paused")
+    } else {
+        (StatusCode::OK, "ready")
+    };
+    (
+        code,
+        Json(ReadyResponse {
+            status,
+            consecutive_ml_failures: watchdog.consecutive_failures(),
+        }),
+    )
+}
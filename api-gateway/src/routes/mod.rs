@@ -1,4 +1,166 @@
 //! HTTP route handlers for the API gateway.
 
+use axum::http::StatusCode;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use chain::{
+    AccountId, AddressError, Aid, GossipMessage, HASH_LEN, Hash256, MempoolError, PublicKey,
+    Signature, Transaction, TxHash,
+};
+
+use crate::error::ApiError;
+use crate::state::SharedState;
+
+pub mod accounts;
+pub mod admin;
+pub mod artefacts;
+pub mod blobs;
+pub mod blocks;
+pub mod events;
+pub mod explorer;
+pub mod export;
 pub mod health;
 pub mod models;
+pub mod rpc;
+pub mod sync;
+pub mod transfers;
+pub mod txs;
+pub mod validators;
+
+/// Parses a 32-byte hex string into a `Hash256`.
+pub(crate) fn hex_to_hash256(hex_str: &str) -> Result<Hash256, &'static str> {
+    let bytes = hex::decode(hex_str).map_err(|_| "invalid hex encoding")?;
+    if bytes.len() != HASH_LEN {
+        return Err("expected 32-byte hash");
+    }
+    let mut arr = [0u8; HASH_LEN];
+    arr.copy_from_slice(&bytes);
+    Ok(Hash256(arr))
+}
+
+/// Parses an account address (`mls1…`) or 32-byte hex string.
+pub(crate) fn parse_account(s: &str) -> Result<AccountId, ApiError> {
+    s.parse()
+        .map_err(|e: AddressError| ApiError::bad_request("invalid_address", e.to_string()))
+}
+
+/// Parses an artefact address (`aid1…`) or 32-byte hex string.
+pub(crate) fn parse_aid(s: &str) -> Result<Aid, ApiError> {
+    s.parse()
+        .map_err(|e: AddressError| ApiError::bad_request("invalid_address", e.to_string()))
+}
+
+/// Rejects a malformed hash parameter, e.g. from [`hex_to_hash256`].
+pub(crate) fn as_invalid_hash(msg: &'static str) -> ApiError {
+    ApiError::bad_request("invalid_hash", msg)
+}
+
+pub(crate) fn as_storage_error(e: chain::StorageError) -> ApiError {
+    ApiError::internal("storage_error", format!("storage error: {e}"))
+}
+
+/// Maps a mempool rejection to an HTTP status and error code.
+pub(crate) fn as_mempool_error(err: MempoolError) -> ApiError {
+    let (status, code) = match &err {
+        MempoolError::Invalid(_) => (StatusCode::BAD_REQUEST, "invalid_transaction"),
+        MempoolError::Full => (StatusCode::SERVICE_UNAVAILABLE, "mempool_full"),
+        MempoolError::TooLarge { .. } => (StatusCode::PAYLOAD_TOO_LARGE, "tx_too_large"),
+        MempoolError::Duplicate(_) => (StatusCode::CONFLICT, "duplicate_transaction"),
+        MempoolError::NonceTooLow { .. } => (StatusCode::CONFLICT, "nonce_too_low"),
+        MempoolError::NonceTooHigh { .. } => (StatusCode::CONFLICT, "nonce_too_high"),
+        MempoolError::Underpriced { .. } => (StatusCode::CONFLICT, "underpriced"),
+    };
+    ApiError::new(status, code, err.to_string())
+}
+
+/// Response body for transaction submissions other than registrations.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct QueuedTxResponse {
+    pub status: &'static str,
+    /// Hex-encoded hash of the queued transaction.
+    pub tx_hash: String,
+}
+
+/// Client signature over a submitted transaction.
+///
+/// The client builds the transaction from the request fields plus `fee`
+/// and `nonce`, and signs its `Transaction::signing_bytes` with the
+/// sender's ML-DSA-65 key.
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct SignedDto {
+    /// Hex-encoded ML-DSA-65 public key of the sender.
+    pub public_key_hex: String,
+    /// Hex-encoded detached signature.
+    pub signature_hex: String,
+    /// Fee the client signed.
+    pub fee: u64,
+    /// Nonce the client signed.
+    pub nonce: u64,
+}
+
+impl SignedDto {
+    /// Attaches the signature to `tx` and checks it against the public
+    /// key, which must belong to the sender.
+    pub(crate) fn sign(&self, tx: &mut Transaction) -> Result<(), ApiError> {
+        let public_key = PublicKey(hex::decode(&self.public_key_hex).map_err(|_| {
+            ApiError::bad_request("invalid_hex", "invalid public key hex encoding")
+        })?);
+        *tx.signature_mut() =
+            Signature(hex::decode(&self.signature_hex).map_err(|_| {
+                ApiError::bad_request("invalid_hex", "invalid signature hex encoding")
+            })?);
+        tx.verify_signature(&public_key).map_err(|e| {
+            ApiError::new(
+                StatusCode::UNAUTHORIZED,
+                "bad_signature",
+                format!("bad signature: {e}"),
+            )
+        })
+    }
+}
+
+/// Admits the transaction `build` returns for `sender` to the mempool.
+///
+/// `build` is given the fee and nonce to use. With a client signature
+/// they are the signed ones, and the signature is checked before the
+/// transaction is queued. Without one, the gateway submits an unsigned
+/// placeholder paying the current epoch's minimum fee, with the sender's
+/// next nonce past any of its transactions that are still pooled. The
+/// pool checks the transaction with its stateless validator before
+/// queueing it.
+pub(crate) async fn queue_tx(
+    state: &SharedState,
+    sender: AccountId,
+    signed: Option<&SignedDto>,
+    build: impl FnOnce(u64, u64) -> Transaction,
+) -> Result<TxHash, ApiError> {
+    let engine = state.engine.lock().await;
+    let mut pool = state.tx_pool.lock().await;
+    let tx = match signed {
+        Some(signed) => {
+            let mut tx = build(signed.fee, signed.nonce);
+            signed.sign(&mut tx)?;
+            tx
+        }
+        None => build(
+            engine.current_epoch().min_fee(),
+            pool.next_nonce(&sender, engine.state()),
+        ),
+    };
+    let hash = pool
+        .insert(tx.clone(), engine.state())
+        .map_err(as_mempool_error)?;
+    gossip_tx(state, tx).await;
+    Ok(hash)
+}
+
+/// Gossips a transaction the mempool just admitted, if the p2p network is
+/// enabled. Peers that miss it still see it in the block including it.
+pub(crate) async fn gossip_tx(state: &SharedState, tx: Transaction) {
+    if let Some(p2p) = &state.p2p
+        && let Err(e) = p2p.publish(GossipMessage::Tx(tx)).await
+    {
+        tracing::warn!("failed to gossip transaction: {e}");
+    }
+}
@@ -1,34 +1,104 @@
-use axum::{Json, extract::State, http::StatusCode};
+use axum::{
+    extract::State,
+    http::{HeaderMap, StatusCode},
+};
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 
 use chain::{
-    AccountId, Aid, EvidenceHash, EvidenceRef, HASH_LEN, Hash256, Signature, Transaction, WmProfile,
+    ArtefactManifest, ChunkedAid, EvidenceHash, EvidenceRef, Hash256, ModelUseMetadata, Signature,
+    Transaction, TxUseModel, WmProfile,
+};
+
+use super::{
+    QueuedTxResponse, SignedDto, as_invalid_hash, as_mempool_error, as_storage_error, gossip_tx,
+    hex_to_hash256, parse_account, parse_aid, queue_tx,
 };
+use crate::error::{ApiError, ErrorBody};
+use crate::extract::Json;
+use crate::state::{IdempotentOutcome, SharedState};
+use crate::versioning::DeprecationNotices;
+
+/// Header clients set to make retried submissions safe.
+const IDEMPOTENCY_KEY_HEADER: &str = "idempotency-key";
 
-use crate::state::SharedState;
+/// Maximum accepted length of an `Idempotency-Key` value.
+const MAX_IDEMPOTENCY_KEY_LEN: usize = 255;
 
 /// Request body for `POST /models/register`.
 ///
 /// This is intentionally minimal: the client passes
-/// - `owner_account_hex`: hex-encoded `AccountId` (Hash256),
-/// - `aid_hex`: hex-encoded `Aid` (Hash256),
-/// - `scheme_id`, `evidence_hash_hex`, and `wm_profile` parameters.
-#[derive(Debug, Deserialize)]
+/// - `owner_account_hex`: `mls1…` address or hex-encoded `AccountId`,
+/// - `aid_hex` and/or `manifest` or `chunked`: the artefact identity,
+/// - `scheme_id` and `evidence_hash_hex`, and optionally `wm_profile`,
+/// - optionally `evidence_payload_hex`, the raw evidence bytes.
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct RegisterModelRequest {
-    /// Hex-encoded account identifier for the model owner.
+    /// Model owner, as a `mls1…` address or hex-encoded account id.
     pub owner_account_hex: String,
-    /// Hex-encoded model artefact identifier (`Aid`).
-    pub aid_hex: String,
+    /// Model artefact identifier (`Aid`), as an `aid1…` address or hex.
+    ///
+    /// Optional when `manifest` is given, in which case the `Aid` is
+    /// derived from it; if both are given they must agree. Registering
+    /// with `aid_hex` alone is deprecated and answered with a `Warning`.
+    #[serde(default)]
+    pub aid_hex: Option<String>,
+    /// Optional multi-file artefact manifest the `Aid` is derived from.
+    #[serde(default)]
+    pub manifest: Option<ManifestDto>,
+    /// Optional chunked form the `Aid` is derived from, for large models
+    /// hashed in chunks. Mutually exclusive with `manifest`.
+    #[serde(default)]
+    pub chunked: Option<ChunkedAidDto>,
     /// Watermark scheme identifier.
     pub scheme_id: String,
     /// Hex-encoded evidence hash (hash of watermark key + parameters).
     pub evidence_hash_hex: String,
-    /// Watermark profile thresholds and bands.
-    pub wm_profile: WmProfileDto,
+    /// Watermark profile thresholds and bands; defaults to the current
+    /// epoch's profile.
+    #[serde(default)]
+    pub wm_profile: Option<WmProfileDto>,
+    /// Optional hex-encoded evidence payload (watermark key + parameters).
+    ///
+    /// When present, its hash is recomputed and must equal
+    /// `evidence_hash_hex`; otherwise the request is rejected.
+    #[serde(default)]
+    pub evidence_payload_hex: Option<String>,
+    /// Client signature over the registration; without one the gateway
+    /// submits an unsigned placeholder.
+    #[serde(default)]
+    pub signed: Option<SignedDto>,
+}
+
+/// DTO version of [`ArtefactManifest`] used in the API.
+///
+/// The evidence hash is not repeated here; it is taken from the request's
+/// `evidence_hash_hex`.
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct ManifestDto {
+    /// Hex-encoded hash of the model weights file.
+    pub model_hash_hex: String,
+    /// Hex-encoded hash of the tokenizer file, if any.
+    #[serde(default)]
+    pub tokenizer_hash_hex: Option<String>,
+    /// Hex-encoded hash of the model config file, if any.
+    #[serde(default)]
+    pub config_hash_hex: Option<String>,
+}
+
+/// DTO version of [`ChunkedAid`] used in the API.
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct ChunkedAidDto {
+    /// Chunk size in bytes.
+    pub chunk_size: u32,
+    /// Total model length in bytes.
+    pub total_len: u64,
+    /// Hex-encoded Merkle root over the chunk hashes.
+    pub root_hex: String,
 }
 
 /// DTO version of [`WmProfile`] used in the API.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct WmProfileDto {
     pub tau_input: f32,
     pub tau_feat: f32,
@@ -48,80 +118,332 @@ impl From<WmProfileDto> for WmProfile {
 }
 
 /// Response body for `POST /models/register`.
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct RegisterModelResponse {
     pub status: &'static str,
     pub aid: String,
+    /// Hex-encoded hash of the queued transaction.
+    pub tx_hash: String,
 }
 
-/// Parses a 32-byte hex string into a `Hash256`.
-fn hex_to_hash256(hex_str: &str) -> Result<Hash256, &'static str> {
-    let bytes = hex::decode(hex_str).map_err(|_| "invalid hex encoding")?;
-    if bytes.len() != HASH_LEN {
-        return Err("expected 32-byte hash");
+impl From<&IdempotentOutcome> for RegisterModelResponse {
+    fn from(outcome: &IdempotentOutcome) -> Self {
+        Self {
+            status: "queued",
+            aid: outcome.aid_hex.clone(),
+            tx_hash: hex::encode(outcome.tx_hash.0.as_bytes()),
+        }
     }
-    let mut arr = [0u8; HASH_LEN];
-    arr.copy_from_slice(&bytes);
-    Ok(Hash256(arr))
+}
+
+/// Reads the optional `Idempotency-Key` header.
+fn idempotency_key(headers: &HeaderMap) -> Result<Option<String>, ApiError> {
+    let Some(value) = headers.get(IDEMPOTENCY_KEY_HEADER) else {
+        return Ok(None);
+    };
+    let key = value.to_str().map_err(|_| {
+        ApiError::bad_request(
+            "invalid_idempotency_key",
+            "Idempotency-Key must be visible ASCII",
+        )
+    })?;
+    if key.is_empty() || key.len() > MAX_IDEMPOTENCY_KEY_LEN {
+        return Err(ApiError::bad_request(
+            "invalid_idempotency_key",
+            "Idempotency-Key must be between 1 and 255 characters",
+        ));
+    }
+    Ok(Some(key.to_string()))
 }
 
 /// `POST /models/register`
 ///
-/// Queues a `TxRegisterModel` into the local transaction pool. The block
-/// producer loop will eventually include it in a block, subject to
-/// validity predicates.
+/// Admits a `TxRegisterModel` to the local mempool. The block producer
+/// loop will eventually include it in a block, subject to validity
+/// predicates. Mempool rejections map to `400` (fails stateless
+/// transaction checks), `503` (pool full), `413` (too large), or `409`
+/// (nonce conflict). A `signed` envelope whose signature does not verify
+/// is rejected with `401`.
+///
+/// If the request carries an `Idempotency-Key` header that was already
+/// used, nothing is queued and the original response is returned again;
+/// reusing a key for a different registration is rejected with `422`.
+#[utoipa::path(
+    post,
+    path = "/v1/models/register",
+    tag = "models",
+    request_body = RegisterModelRequest,
+    params(("Idempotency-Key" = Option<String>, Header, description = "Makes retries safe")),
+    responses(
+        (status = 202, description = "Registration queued", body = RegisterModelResponse),
+        (status = 400, description = "Malformed request or invalid transaction", body = ErrorBody),
+        (status = 401, description = "Signature does not verify, or API key missing", body = ErrorBody),
+        (status = 409, description = "Nonce conflict or duplicate", body = ErrorBody),
+        (status = 413, description = "Transaction too large", body = ErrorBody),
+        (status = 422, description = "Idempotency-Key reused for a different request", body = ErrorBody),
+        (status = 429, description = "Rate limited", body = ErrorBody),
+        (status = 503, description = "Mempool full", body = ErrorBody),
+    )
+)]
 pub async fn register_model(
     State(state): State<SharedState>,
+    headers: HeaderMap,
     Json(body): Json<RegisterModelRequest>,
-) -> Result<(StatusCode, Json<RegisterModelResponse>), (StatusCode, String)> {
-    // Parse owner account.
-    let owner_hash = hex_to_hash256(&body.owner_account_hex).map_err(as_bad_request)?;
-    let owner = AccountId(owner_hash);
+) -> Result<(StatusCode, DeprecationNotices, Json<RegisterModelResponse>), ApiError> {
+    let idempotency_key = idempotency_key(&headers)?;
 
-    // Parse aid.
-    let aid_hash = hex_to_hash256(&body.aid_hex).map_err(as_bad_request)?;
-    let aid = Aid(aid_hash);
+    let mut notices = DeprecationNotices::default();
+    if body.manifest.is_none() && body.chunked.is_none() {
+        notices.push("registering by aid_hex alone is deprecated; send a manifest instead");
+    }
+
+    // Parse owner account.
+    let owner = parse_account(&body.owner_account_hex)?;
 
     // Parse evidence hash.
-    let evidence_hash = hex_to_hash256(&body.evidence_hash_hex).map_err(as_bad_request)?;
+    let evidence_hash = hex_to_hash256(&body.evidence_hash_hex).map_err(as_invalid_hash)?;
     let ev_hash = EvidenceHash(evidence_hash);
 
-    let wm_profile: WmProfile = body.wm_profile.into();
+    // Parse the artefact manifest, if any.
+    let manifest = match &body.manifest {
+        Some(dto) => {
+            let manifest = ArtefactManifest::new(
+                hex_to_hash256(&dto.model_hash_hex).map_err(as_invalid_hash)?,
+                parse_optional_hash(dto.tokenizer_hash_hex.as_deref())?,
+                parse_optional_hash(dto.config_hash_hex.as_deref())?,
+                ev_hash,
+            );
+            manifest
+                .validate()
+                .map_err(|e| ApiError::bad_request("invalid_manifest", e.to_string()))?;
+            Some(manifest)
+        }
+        None => None,
+    };
+
+    // Parse the chunked aid, if any.
+    let chunked = match &body.chunked {
+        Some(dto) => {
+            let chunked = ChunkedAid {
+                chunk_size: dto.chunk_size,
+                total_len: dto.total_len,
+                root: hex_to_hash256(&dto.root_hex).map_err(as_invalid_hash)?,
+            };
+            chunked
+                .validate()
+                .map_err(|e| ApiError::bad_request("invalid_chunked", e.to_string()))?;
+            Some(chunked)
+        }
+        None => None,
+    };
+
+    // Parse aid, or derive it from the manifest or chunked form.
+    let derived = match (&manifest, &chunked) {
+        (Some(_), Some(_)) => {
+            return Err(ApiError::bad_request(
+                "invalid_request",
+                "manifest and chunked are mutually exclusive",
+            ));
+        }
+        (Some(manifest), None) => Some((manifest.aid(), "aid_hex does not match manifest")),
+        (None, Some(chunked)) => Some((chunked.aid(), "aid_hex does not match chunked")),
+        (None, None) => None,
+    };
+    let aid = match (&body.aid_hex, derived) {
+        (Some(aid_hex), derived) => {
+            let aid = parse_aid(aid_hex)?;
+            if let Some((derived, mismatch)) = derived
+                && derived != aid
+            {
+                return Err(ApiError::bad_request("aid_mismatch", mismatch));
+            }
+            aid
+        }
+        (None, Some((derived, _))) => derived,
+        (None, None) => {
+            return Err(ApiError::bad_request(
+                "invalid_request",
+                "one of aid_hex, manifest or chunked is required",
+            ));
+        }
+    };
+
+    // If the client sent the evidence payload, don't trust the hash blindly.
+    if let Some(payload_hex) = &body.evidence_payload_hex {
+        let payload = hex::decode(payload_hex).map_err(|_| {
+            ApiError::bad_request("invalid_hex", "invalid evidence payload hex encoding")
+        })?;
+        if !ev_hash.matches_payload(&payload) {
+            return Err(ApiError::bad_request(
+                "evidence_mismatch",
+                "evidence payload does not match evidence_hash_hex",
+            ));
+        }
+    }
+
+    let wm_profile: WmProfile = match body.wm_profile {
+        Some(dto) => dto.into(),
+        None => state.chain.current_epoch().wm_profile().clone(),
+    };
     let evidence = EvidenceRef {
         scheme_id: body.scheme_id.clone(),
         evidence_hash: ev_hash,
         wm_profile,
     };
 
-    // In a full implementation the client would sign the canonical
-    // transaction encoding with a Dilithium key. For now we accept an
-    // empty signature placeholder.
-    let tx_reg = chain::TxRegisterModel {
-        owner,
-        aid,
-        evidence,
-        fee: 0,
-        nonce: 0,
-        signature: Signature(Vec::new()),
+    // Everything a retried request must repeat exactly; the nonce is
+    // assigned below and is deliberately not part of it.
+    let fingerprint = {
+        let fields = serde_json::to_vec(&(owner, aid, chunked, &evidence))
+            .map_err(|e| ApiError::internal("internal_error", e.to_string()))?;
+        Hash256::compute(&fields)
     };
 
-    let tx = Transaction::RegisterModel(tx_reg);
-
-    {
-        // Enqueue the transaction.
+    let outcome = {
+        // The engine is locked first, as by the block producer, so the
+        // state cannot change between reading it and queueing. The
+        // idempotency cache is locked last, for the whole submission, so
+        // concurrent retries with the same key cannot both enqueue.
+        let engine = state.engine.lock().await;
         let mut pool = state.tx_pool.lock().await;
-        pool.push(tx);
-    }
+        let mut inclusion = state.inclusion.lock().await;
+        let mut keys = state.idempotency.lock().await;
+
+        if let Some(prev) = idempotency_key.as_deref().and_then(|k| keys.get(k)) {
+            if prev.fingerprint != fingerprint {
+                return Err(ApiError::new(
+                    StatusCode::UNPROCESSABLE_ENTITY,
+                    "idempotency_key_reused",
+                    "Idempotency-Key was already used for a different request",
+                ));
+            }
+            return Ok((StatusCode::ACCEPTED, notices, Json(prev.into())));
+        }
+
+        // A signed registration carries its own fee and nonce. Unsigned
+        // ones get an empty placeholder signature, the minimum fee, and
+        // the owner's next nonce in chain state, skipping past any of the
+        // owner's transactions that are still pooled.
+        let (fee, nonce) = match &body.signed {
+            Some(signed) => (signed.fee, signed.nonce),
+            None => (
+                engine.current_epoch().min_fee(),
+                pool.next_nonce(&owner, engine.state()),
+            ),
+        };
+        let mut tx = Transaction::RegisterModel(chain::TxRegisterModel {
+            owner,
+            aid,
+            chunked,
+            evidence,
+            fee,
+            nonce,
+            signature: Signature(Vec::new()),
+        });
+        if let Some(signed) = &body.signed {
+            signed.sign(&mut tx)?;
+        }
+
+        // Admit the transaction to the mempool, remembering the tip height
+        // so its inclusion delay can be measured.
+        let tip_height = engine
+            .tip_block()
+            .map_err(as_storage_error)?
+            .map_or(0, |block| block.header.height);
+        let tx_hash = pool
+            .insert(tx.clone(), engine.state())
+            .map_err(as_mempool_error)?;
+        inclusion.record_admission(&tx, tx_hash, tip_height);
+        gossip_tx(&state, tx).await;
+        let outcome = IdempotentOutcome {
+            fingerprint,
+            tx_hash,
+            aid_hex: hex::encode(aid.as_hash().as_bytes()),
+        };
+
+        if let Some(key) = idempotency_key {
+            keys.insert(key, outcome.clone());
+        }
+        outcome
+    };
+
+    Ok((StatusCode::ACCEPTED, notices, Json((&outcome).into())))
+}
+
+/// Request body for `POST /models/use`.
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct UseModelRequest {
+    /// Caller, who pays the fee, as a `mls1…` address or hex.
+    pub caller_account_hex: String,
+    /// Registered model, as an `aid1…` address or hex.
+    pub aid_hex: String,
+    /// Task the model was used for, e.g. `"image_classification"`.
+    pub task: String,
+    /// Optional model version or deployment label.
+    #[serde(default)]
+    pub version: Option<String>,
+    /// Client signature; without one the gateway submits an unsigned
+    /// placeholder.
+    #[serde(default)]
+    pub signed: Option<SignedDto>,
+}
+
+/// `POST /models/use`
+///
+/// Admits a `TxUseModel` recording that the caller used a registered
+/// model. Rejections map to statuses as for `POST /models/register`: a
+/// blank task or a revoked `Aid` is answered with `400`, and a `signed`
+/// envelope whose signature does not verify with `401`. An unregistered
+/// `Aid` is only caught when the transaction is executed.
+#[utoipa::path(
+    post,
+    path = "/v1/models/use",
+    tag = "models",
+    request_body = UseModelRequest,
+    responses(
+        (status = 202, description = "Transaction queued", body = QueuedTxResponse),
+        (status = 400, description = "Malformed request or invalid transaction", body = ErrorBody),
+        (status = 401, description = "Signature does not verify, or API key missing", body = ErrorBody),
+        (status = 409, description = "Nonce conflict or duplicate", body = ErrorBody),
+        (status = 429, description = "Rate limited", body = ErrorBody),
+        (status = 503, description = "Mempool full", body = ErrorBody),
+    )
+)]
+pub async fn use_model(
+    State(state): State<SharedState>,
+    Json(body): Json<UseModelRequest>,
+) -> Result<(StatusCode, Json<QueuedTxResponse>), ApiError> {
+    let caller = parse_account(&body.caller_account_hex)?;
+    let aid = parse_aid(&body.aid_hex)?;
+    let metadata = ModelUseMetadata {
+        task: body.task.clone(),
+        version: body.version.clone(),
+    };
+
+    let tx_hash = queue_tx(&state, caller, body.signed.as_ref(), |fee, nonce| {
+        Transaction::UseModel(TxUseModel {
+            caller,
+            aid,
+            metadata,
+            fee,
+            nonce,
+            signature: Signature(Vec::new()),
+        })
+    })
+    .await?;
 
     Ok((
         StatusCode::ACCEPTED,
-        Json(RegisterModelResponse {
+        Json(QueuedTxResponse {
             status: "queued",
-            aid: body.aid_hex,
+            tx_hash: hex::encode(tx_hash.0.as_bytes()),
         }),
     ))
 }
 
-fn as_bad_request(msg: &'static str) -> (StatusCode, String) {
-    (StatusCode::BAD_REQUEST, msg.to_string())
+/// Parses an optional 32-byte hex string into a `Hash256`.
+fn parse_optional_hash(hex_str: Option<&str>) -> Result<Option<Hash256>, ApiError> {
+    hex_str
+        .map(|h| hex_to_hash256(h).map_err(as_invalid_hash))
+        .transpose()
 }
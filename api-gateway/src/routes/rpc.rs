@@ -0,0 +1,331 @@
+//! JSON-RPC 2.0 endpoint.
+//!
+//! `POST /rpc` serves the chain to explorers and scripts built for generic
+//! JSON-RPC chain tooling. A body holds one request object or a batch (an
+//! array of them); requests without an `id` are notifications, which run
+//! but get no response. Parameters may be positional or named:
+//!
+//! | Method | Params | Result |
+//! |---|---|---|
+//! | `chain_getTip` | none | tip block, or `null` on an empty chain |
+//! | `chain_getBlockByHeight` | `height` | canonical block, or `null` |
+//! | `chain_getBlockByHash` | `hash` (hex) | block, or `null` |
+//! | `chain_submitTransaction` | `tx` (hex canonical bytes), optional `public_key` (hex) | transaction hash (hex) |
+//! | `mlsnitch_getArtefact` | `aid` (`aid1…` or hex) | registry entry, or `null` |
+//!
+//! Blocks and artefacts are rendered like `GET /blocks/{id}` and
+//! `GET /artefacts/{aid}`. Failures use the standard JSON-RPC error codes
+//! plus the server-defined codes below, with a human-readable `message`.
+
+use axum::{
+    Json,
+    body::Bytes,
+    extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json::{Value, json};
+
+use chain::{Aid, BlockHash, BlockStore, MempoolError, PublicKey, Transaction, check_fraud_proof};
+
+use super::artefacts::ArtefactResponse;
+use super::blocks::BlockSummary;
+use super::{gossip_tx, hex_to_hash256};
+use crate::state::SharedState;
+
+/// The body is not valid JSON.
+pub const PARSE_ERROR: i64 = -32700;
+/// The body is not a valid request object or batch.
+pub const INVALID_REQUEST: i64 = -32600;
+/// The method does not exist.
+pub const METHOD_NOT_FOUND: i64 = -32601;
+/// The params are missing or malformed.
+pub const INVALID_PARAMS: i64 = -32602;
+/// The node failed to answer, e.g. on a storage error.
+pub const INTERNAL_ERROR: i64 = -32603;
+/// The mempool rejected the transaction; `data.reason` says why.
+pub const TX_REJECTED: i64 = -32000;
+/// The mempool is full and the transaction's fee is too low to evict
+/// anything.
+pub const MEMPOOL_FULL: i64 = -32001;
+/// The transaction's signature does not verify under `public_key`.
+pub const BAD_SIGNATURE: i64 = -32002;
+
+/// Error object of a failed call.
+#[derive(Debug, Serialize)]
+pub struct RpcError {
+    pub code: i64,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<Value>,
+}
+
+impl RpcError {
+    fn new(code: i64, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+            data: None,
+        }
+    }
+
+    fn invalid_params(message: impl Into<String>) -> Self {
+        Self::new(INVALID_PARAMS, message)
+    }
+
+    fn storage(e: chain::StorageError) -> Self {
+        Self::new(INTERNAL_ERROR, format!("storage error: {e}"))
+    }
+
+    fn rejected(reason: String) -> Self {
+        Self {
+            code: TX_REJECTED,
+            message: "transaction rejected".to_string(),
+            data: Some(json!({ "reason": reason })),
+        }
+    }
+}
+
+/// Outcome of one call: exactly one of `result` and `error`.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum Outcome {
+    Result(Value),
+    Error(RpcError),
+}
+
+/// Response object for one request.
+#[derive(Debug, Serialize)]
+struct RpcResponse {
+    jsonrpc: &'static str,
+    #[serde(flatten)]
+    outcome: Outcome,
+    id: Value,
+}
+
+impl RpcResponse {
+    fn new(id: Value, outcome: Result<Value, RpcError>) -> Self {
+        Self {
+            jsonrpc: "2.0",
+            outcome: match outcome {
+                Ok(result) => Outcome::Result(result),
+                Err(error) => Outcome::Error(error),
+            },
+            id,
+        }
+    }
+}
+
+/// `POST /rpc`
+///
+/// Always answers `200` with a response object or an array of them, or
+/// `204` if every request was a notification. Batches hold at most
+/// `api.rpc_max_batch_size` requests.
+pub async fn handle(State(state): State<SharedState>, body: Bytes) -> Response {
+    let body: Value = match serde_json::from_slice(&body) {
+        Ok(body) => body,
+        Err(e) => {
+            let error = RpcError::new(PARSE_ERROR, format!("parse error: {e}"));
+            return Json(RpcResponse::new(Value::Null, Err(error))).into_response();
+        }
+    };
+
+    match body {
+        Value::Array(requests) => {
+            let problem = if requests.is_empty() {
+                Some("empty batch".to_string())
+            } else if requests.len() > state.rpc_max_batch_size {
+                Some(format!(
+                    "batch of {} requests exceeds the limit of {}",
+                    requests.len(),
+                    state.rpc_max_batch_size
+                ))
+            } else {
+                None
+            };
+            if let Some(problem) = problem {
+                let error = RpcError::new(INVALID_REQUEST, problem);
+                return Json(RpcResponse::new(Value::Null, Err(error))).into_response();
+            }
+
+            // Requests run in order, so a batch can submit several
+            // transactions from one sender with consecutive nonces.
+            let mut responses = Vec::new();
+            for request in requests {
+                responses.extend(call(&state, request).await);
+            }
+            if responses.is_empty() {
+                StatusCode::NO_CONTENT.into_response()
+            } else {
+                Json(responses).into_response()
+            }
+        }
+        request => match call(&state, request).await {
+            Some(response) => Json(response).into_response(),
+            None => StatusCode::NO_CONTENT.into_response(),
+        },
+    }
+}
+
+/// Runs one request; returns `None` for notifications.
+async fn call(state: &SharedState, request: Value) -> Option<RpcResponse> {
+    let Value::Object(request) = request else {
+        let error = RpcError::new(INVALID_REQUEST, "request must be an object");
+        return Some(RpcResponse::new(Value::Null, Err(error)));
+    };
+    let id = request.get("id").cloned();
+    if let Some(id) = &id
+        && !matches!(id, Value::Null | Value::Number(_) | Value::String(_))
+    {
+        let error = RpcError::new(INVALID_REQUEST, "id must be a string, number or null");
+        return Some(RpcResponse::new(Value::Null, Err(error)));
+    }
+
+    let outcome = if request.get("jsonrpc").and_then(Value::as_str) != Some("2.0") {
+        Err(RpcError::new(INVALID_REQUEST, "jsonrpc must be \"2.0\""))
+    } else if let Some(Value::String(method)) = request.get("method") {
+        dispatch(state, method, Params(request.get("params"))).await
+    } else {
+        Err(RpcError::new(INVALID_REQUEST, "method must be a string"))
+    };
+    id.map(|id| RpcResponse::new(id, outcome))
+}
+
+async fn dispatch(
+    state: &SharedState,
+    method: &str,
+    params: Params<'_>,
+) -> Result<Value, RpcError> {
+    match method {
+        "chain_getTip" => {
+            let block = state.chain.tip_block().map_err(RpcError::storage)?;
+            to_value(block.as_ref().map(BlockSummary::from))
+        }
+        "chain_getBlockByHeight" => {
+            let height: u64 = params.required(0, "height")?;
+            let block = state
+                .chain
+                .store()
+                .get_block_by_height(height)
+                .map_err(RpcError::storage)?;
+            to_value(block.as_ref().map(BlockSummary::from))
+        }
+        "chain_getBlockByHash" => {
+            let hash: String = params.required(0, "hash")?;
+            let hash = BlockHash(hex_to_hash256(&hash).map_err(RpcError::invalid_params)?);
+            let block = state
+                .chain
+                .store()
+                .get_block(&hash)
+                .map_err(RpcError::storage)?;
+            to_value(block.as_ref().map(BlockSummary::from))
+        }
+        "chain_submitTransaction" => {
+            let tx: String = params.required(0, "tx")?;
+            let public_key: Option<String> = params.optional(1, "public_key")?;
+            submit_transaction(state, &tx, public_key.as_deref()).await
+        }
+        "mlsnitch_getArtefact" => {
+            let aid: String = params.required(0, "aid")?;
+            let aid: Aid = aid
+                .parse()
+                .map_err(|e: chain::AddressError| RpcError::invalid_params(e.to_string()))?;
+            let chain_state = state.chain.state();
+            to_value(chain_state.artefact(&aid).map(ArtefactResponse::from))
+        }
+        other => Err(RpcError::new(
+            METHOD_NOT_FOUND,
+            format!("method {other:?} not found"),
+        )),
+    }
+}
+
+/// Admits a transaction given as hex-encoded canonical bytes to the
+/// mempool and returns its hash.
+///
+/// With `public_key`, the signature is checked like a `signed` envelope of
+/// the REST routes before the transaction is pooled. A `TxSlash` must name
+/// a stored block its fraud proof matches, as block validation requires.
+async fn submit_transaction(
+    state: &SharedState,
+    tx_hex: &str,
+    public_key: Option<&str>,
+) -> Result<Value, RpcError> {
+    let bytes = hex::decode(tx_hex).map_err(|_| RpcError::invalid_params("invalid tx hex"))?;
+    let tx = Transaction::from_canonical_bytes(&bytes)
+        .ok_or_else(|| RpcError::invalid_params("tx is not a canonical transaction encoding"))?;
+    if let Some(public_key) = public_key {
+        let public_key = PublicKey(
+            hex::decode(public_key)
+                .map_err(|_| RpcError::invalid_params("invalid public key hex"))?,
+        );
+        tx.verify_signature(&public_key)
+            .map_err(|e| RpcError::new(BAD_SIGNATURE, format!("bad signature: {e}")))?;
+    }
+    if let Transaction::Slash(slash) = &tx {
+        check_fraud_proof(&slash.proof, state.chain.store())
+            .map_err(|e| RpcError::rejected(e.to_string()))?;
+    }
+
+    // The engine first, as by the block producer.
+    let engine = state.engine.lock().await;
+    let mut pool = state.tx_pool.lock().await;
+    let mut inclusion = state.inclusion.lock().await;
+    let tip_height = engine
+        .tip_block()
+        .map_err(RpcError::storage)?
+        .map_or(0, |block| block.header.height);
+    let tx_hash = pool
+        .insert(tx.clone(), engine.state())
+        .map_err(|e| match e {
+            MempoolError::Full => RpcError::new(MEMPOOL_FULL, e.to_string()),
+            e => RpcError::rejected(e.to_string()),
+        })?;
+    inclusion.record_admission(&tx, tx_hash, tip_height);
+    gossip_tx(state, tx).await;
+
+    Ok(Value::String(hex::encode(tx_hash.0.as_bytes())))
+}
+
+fn to_value(result: impl Serialize) -> Result<Value, RpcError> {
+    serde_json::to_value(result).map_err(|e| RpcError::new(INTERNAL_ERROR, e.to_string()))
+}
+
+/// A request's `params`: an array (positional) or an object (named).
+#[derive(Clone, Copy)]
+struct Params<'a>(Option<&'a Value>);
+
+impl Params<'_> {
+    /// Returns the parameter at `index`, or named `name`, if present.
+    fn optional<T: DeserializeOwned>(
+        &self,
+        index: usize,
+        name: &str,
+    ) -> Result<Option<T>, RpcError> {
+        let value = match self.0 {
+            None | Some(Value::Null) => None,
+            Some(Value::Array(values)) => values.get(index),
+            Some(Value::Object(values)) => values.get(name),
+            Some(_) => {
+                return Err(RpcError::invalid_params(
+                    "params must be an array or an object",
+                ));
+            }
+        };
+        value
+            .filter(|value| !value.is_null())
+            .map(|value| {
+                T::deserialize(value)
+                    .map_err(|e| RpcError::invalid_params(format!("invalid {name}: {e}")))
+            })
+            .transpose()
+    }
+
+    /// Returns the parameter at `index`, or named `name`.
+    fn required<T: DeserializeOwned>(&self, index: usize, name: &str) -> Result<T, RpcError> {
+        self.optional(index, name)?
+            .ok_or_else(|| RpcError::invalid_params(format!("missing param {name}")))
+    }
+}
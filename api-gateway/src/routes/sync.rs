@@ -0,0 +1,48 @@
+use axum::{Json, extract::State};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::state::SharedState;
+
+/// Block sync progress.
+#[derive(Serialize, ToSchema)]
+pub struct SyncResponse {
+    /// `"syncing"` while catching up with a peer, `"idle"` otherwise.
+    pub status: &'static str,
+    /// Local tip height when the current (or last) sync run started.
+    pub start_height: u64,
+    /// Local tip height reached by the run.
+    pub current_height: u64,
+    /// Tip height last reported by the peer.
+    pub target_height: u64,
+    /// Blocks still to be imported.
+    pub remaining: u64,
+    /// Blocks imported by the run.
+    pub blocks_imported: u64,
+    /// Error that ended the last run, if any.
+    pub last_error: Option<String>,
+}
+
+/// `GET /sync`
+///
+/// Reports the progress of block sync from the shared
+/// [`chain::SyncHandle`]. A node that has never synced reports an idle
+/// run at height 0.
+#[utoipa::path(
+    get,
+    path = "/v1/sync",
+    tag = "sync",
+    responses((status = 200, description = "Sync progress", body = SyncResponse))
+)]
+pub async fn get_sync_status(State(state): State<SharedState>) -> Json<SyncResponse> {
+    let status = state.sync.status();
+    Json(SyncResponse {
+        status: if status.syncing { "syncing" } else { "idle" },
+        start_height: status.start_height,
+        current_height: status.current_height,
+        target_height: status.target_height,
+        remaining: status.remaining(),
+        blocks_imported: status.blocks_imported,
+        last_error: status.last_error,
+    })
+}
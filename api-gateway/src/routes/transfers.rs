@@ -0,0 +1,76 @@
+//! Value transfer route.
+
+use axum::{extract::State, http::StatusCode};
+use serde::Deserialize;
+use utoipa::ToSchema;
+
+use chain::{Signature, Transaction, TxTransfer};
+
+use super::{QueuedTxResponse, SignedDto, parse_account, queue_tx};
+use crate::error::{ApiError, ErrorBody};
+use crate::extract::Json;
+use crate::state::SharedState;
+
+/// Request body for `POST /transfer`.
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct TransferRequest {
+    /// Account sending the funds, which also pays the fee, as a `mls1…`
+    /// address or hex.
+    pub from_account_hex: String,
+    /// Account receiving the funds, as a `mls1…` address or hex.
+    pub to_account_hex: String,
+    /// Amount to transfer.
+    pub amount: u64,
+    /// Client signature; without one the gateway submits an unsigned
+    /// placeholder.
+    #[serde(default)]
+    pub signed: Option<SignedDto>,
+}
+
+/// `POST /transfer`
+///
+/// Admits a `TxTransfer` moving `amount` between two accounts. Rejections
+/// map to statuses as for `POST /models/register`: transfers to the sender
+/// itself or of a zero amount fail the stateless checks with `400`, and a
+/// `signed` envelope whose signature does not verify with `401`.
+#[utoipa::path(
+    post,
+    path = "/v1/transfer",
+    tag = "accounts",
+    request_body = TransferRequest,
+    responses(
+        (status = 202, description = "Transfer queued", body = QueuedTxResponse),
+        (status = 400, description = "Malformed request or invalid transaction", body = ErrorBody),
+        (status = 401, description = "Signature does not verify, or API key missing", body = ErrorBody),
+        (status = 409, description = "Nonce conflict or duplicate", body = ErrorBody),
+        (status = 429, description = "Rate limited", body = ErrorBody),
+        (status = 503, description = "Mempool full", body = ErrorBody),
+    )
+)]
+pub async fn transfer(
+    State(state): State<SharedState>,
+    Json(body): Json<TransferRequest>,
+) -> Result<(StatusCode, Json<QueuedTxResponse>), ApiError> {
+    let from = parse_account(&body.from_account_hex)?;
+    let to = parse_account(&body.to_account_hex)?;
+
+    let tx_hash = queue_tx(&state, from, body.signed.as_ref(), |fee, nonce| {
+        Transaction::Transfer(TxTransfer {
+            from,
+            to,
+            amount: body.amount,
+            fee,
+            nonce,
+            signature: Signature(Vec::new()),
+        })
+    })
+    .await?;
+
+    Ok((
+        StatusCode::ACCEPTED,
+        Json(QueuedTxResponse {
+            status: "queued",
+            tx_hash: hex::encode(tx_hash.0.as_bytes()),
+        }),
+    ))
+}
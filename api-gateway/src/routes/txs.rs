@@ -0,0 +1,329 @@
+//! Transaction lookup routes.
+
+use axum::extract::State;
+use serde::Serialize;
+use serde_json::{Value, json};
+use utoipa::ToSchema;
+
+use chain::{BlockStore, MlVerdictStats, Receipt, ReceiptEvent, Transaction, TxHash};
+
+use super::{as_invalid_hash, as_storage_error, hex_to_hash256};
+use crate::error::{ApiError, ErrorBody};
+use crate::extract::{Json, Path};
+use crate::state::SharedState;
+
+/// Response body for `GET /txs/{hash}`.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TxResponse {
+    /// Hex-encoded transaction hash.
+    pub hash: String,
+    /// `"pending"`, `"included"`, or `"unknown"`.
+    pub status: &'static str,
+    /// Height of the canonical block holding the transaction, if included.
+    pub block_height: Option<u64>,
+    /// Hex-encoded hash of that block, if included.
+    pub block_hash: Option<String>,
+    /// The transaction, see [`tx_json`]; `None` if unknown.
+    pub tx: Option<Value>,
+}
+
+/// Renders a transaction as JSON with hex-encoded hashes and accounts.
+///
+/// The derived `Serialize` impls encode hashes as byte arrays, which is
+/// exact but unreadable in an explorer.
+pub(crate) fn tx_json(tx: &Transaction) -> Value {
+    let hex32 = |h: &chain::Hash256| hex::encode(h.as_bytes());
+    match tx {
+        Transaction::RegisterModel(tx) => json!({
+            "kind": "register_model",
+            "owner": hex32(&tx.owner.0),
+            "aid": hex32(tx.aid.as_hash()),
+            "scheme_id": tx.evidence.scheme_id,
+            "evidence_hash": hex32(&tx.evidence.evidence_hash.0),
+            "wm_profile": tx.evidence.wm_profile,
+            "fee": tx.fee,
+            "nonce": tx.nonce,
+        }),
+        Transaction::UseModel(tx) => json!({
+            "kind": "use_model",
+            "caller": hex32(&tx.caller.0),
+            "aid": hex32(tx.aid.as_hash()),
+            "task": tx.metadata.task,
+            "version": tx.metadata.version,
+            "fee": tx.fee,
+            "nonce": tx.nonce,
+        }),
+        Transaction::Transfer(tx) => json!({
+            "kind": "transfer",
+            "from": hex32(&tx.from.0),
+            "to": hex32(&tx.to.0),
+            "amount": tx.amount,
+            "fee": tx.fee,
+            "nonce": tx.nonce,
+        }),
+        Transaction::TransferModel(tx) => json!({
+            "kind": "transfer_model",
+            "current_owner": hex32(&tx.current_owner.0),
+            "new_owner": hex32(&tx.new_owner.0),
+            "aid": hex32(tx.aid.as_hash()),
+            "fee": tx.fee,
+            "nonce": tx.nonce,
+        }),
+        Transaction::RevokeModel(tx) => json!({
+            "kind": "revoke_model",
+            "signer": hex32(&tx.signer.0),
+            "aid": hex32(tx.aid.as_hash()),
+            "fee": tx.fee,
+            "nonce": tx.nonce,
+        }),
+        Transaction::Stake(tx) => json!({
+            "kind": "stake",
+            "staker": hex32(&tx.staker.0),
+            "amount": tx.amount,
+            "fee": tx.fee,
+            "nonce": tx.nonce,
+        }),
+        Transaction::Unstake(tx) => json!({
+            "kind": "unstake",
+            "staker": hex32(&tx.staker.0),
+            "amount": tx.amount,
+            "fee": tx.fee,
+            "nonce": tx.nonce,
+        }),
+        Transaction::Slash(tx) => json!({
+            "kind": "slash",
+            "reporter": hex32(&tx.proof.reporter.0),
+            "proposer": hex32(&tx.proof.proposer.0),
+            "block_hash": hex32(&tx.proof.block_hash.0),
+            "aid": hex32(tx.proof.aid.as_hash()),
+            "scheme_id": tx.proof.verdict.scheme_id,
+            "score": tx.proof.verdict.score,
+            "fee": tx.fee,
+            "nonce": tx.nonce,
+        }),
+    }
+}
+
+/// `GET /txs/{hash}`
+///
+/// Looks a transaction up in the mempool (`pending`) and, through the
+/// [`chain::ChainReader`]'s transaction index, on the canonical chain
+/// (`included`). A hash found in neither is reported as `unknown`: it was
+/// never submitted, was dropped from the mempool, or was only included in
+/// blocks a reorg retracted. Never waits on the engine.
+#[utoipa::path(
+    get,
+    path = "/v1/txs/{hash}",
+    tag = "transactions",
+    params(("hash" = String, Path, description = "Hex transaction hash")),
+    responses(
+        (status = 200, description = "Transaction status", body = TxResponse),
+        (status = 400, description = "Malformed hash", body = ErrorBody),
+    )
+)]
+pub async fn get_tx(
+    State(state): State<SharedState>,
+    Path(hash_hex): Path<String>,
+) -> Result<Json<TxResponse>, ApiError> {
+    let hash = TxHash(hex_to_hash256(&hash_hex).map_err(as_invalid_hash)?);
+    let hash_hex = hex::encode(hash.0.as_bytes());
+
+    // The producer only removes a transaction from the pool once its block
+    // is stored, so checking the pool first never misses one in between.
+    {
+        let pool = state.tx_pool.lock().await;
+        if let Some(tx) = pool.get(&hash) {
+            return Ok(Json(TxResponse {
+                hash: hash_hex,
+                status: "pending",
+                block_height: None,
+                block_hash: None,
+                tx: Some(tx_json(tx)),
+            }));
+        }
+    }
+
+    let included = match state.chain.locate_tx(&hash).map_err(as_storage_error)? {
+        Some(location) => state
+            .chain
+            .store()
+            .get_block(&location.block_hash)
+            .map_err(as_storage_error)?
+            .and_then(|block| block.txs.get(location.index).map(tx_json))
+            .map(|tx| (location, tx)),
+        None => None,
+    };
+
+    Ok(Json(match included {
+        Some((location, tx)) => TxResponse {
+            hash: hash_hex,
+            status: "included",
+            block_height: Some(location.height),
+            block_hash: Some(hex::encode(location.block_hash.0.as_bytes())),
+            tx: Some(tx),
+        },
+        None => TxResponse {
+            hash: hash_hex,
+            status: "unknown",
+            block_height: None,
+            block_hash: None,
+            tx: None,
+        },
+    }))
+}
+
+/// Response body for `GET /txs/{hash}/receipt`.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ReceiptResponse {
+    /// Hex-encoded transaction hash.
+    pub hash: String,
+    /// Height of the canonical block holding the transaction.
+    pub block_height: u64,
+    /// Hex-encoded hash of that block.
+    pub block_hash: String,
+    /// Position of the transaction in the block.
+    pub index: u32,
+    /// Hex-encoded account charged the fee.
+    pub payer: String,
+    /// Fee charged.
+    pub fee: u64,
+    /// State changes made by the transaction, see [`event_json`].
+    pub events: Vec<Value>,
+    /// ML verdict statistics of a `register_model` transaction, see
+    /// [`ml_verdict_json`]; `None` for other transactions or if the node
+    /// did not validate the block itself.
+    pub ml_verdict: Option<Value>,
+}
+
+/// Renders the ML verdict statistics of a registration as JSON.
+fn ml_verdict_json(stats: &MlVerdictStats) -> Value {
+    json!({
+        "aid": hex::encode(stats.aid.as_hash().as_bytes()),
+        "scheme_id": stats.scheme_id,
+        "decision": stats.decision,
+        "score": stats.score,
+        "trigger_acc": stats.trigger_acc,
+        "feat_dist": stats.feat_dist,
+        "logit_stat": stats.logit_stat,
+        "latency_ms": stats.latency_ms,
+    })
+}
+
+/// Renders a receipt event as JSON with hex-encoded hashes and accounts.
+fn event_json(event: &ReceiptEvent) -> Value {
+    let hex32 = |h: &chain::Hash256| hex::encode(h.as_bytes());
+    match event {
+        ReceiptEvent::ArtefactRegistered { aid, owner } => json!({
+            "kind": "artefact_registered",
+            "aid": hex32(aid.as_hash()),
+            "owner": hex32(&owner.0),
+        }),
+        ReceiptEvent::ModelUsed { aid, caller } => json!({
+            "kind": "model_used",
+            "aid": hex32(aid.as_hash()),
+            "caller": hex32(&caller.0),
+        }),
+        ReceiptEvent::Transferred { from, to, amount } => json!({
+            "kind": "transferred",
+            "from": hex32(&from.0),
+            "to": hex32(&to.0),
+            "amount": amount,
+        }),
+        ReceiptEvent::ArtefactTransferred { aid, from, to } => json!({
+            "kind": "artefact_transferred",
+            "aid": hex32(aid.as_hash()),
+            "from": hex32(&from.0),
+            "to": hex32(&to.0),
+        }),
+        ReceiptEvent::ArtefactRevoked { aid, by } => json!({
+            "kind": "artefact_revoked",
+            "aid": hex32(aid.as_hash()),
+            "by": hex32(&by.0),
+        }),
+        ReceiptEvent::Bonded { staker, amount } => json!({
+            "kind": "bonded",
+            "staker": hex32(&staker.0),
+            "amount": amount,
+        }),
+        ReceiptEvent::UnbondingStarted {
+            staker,
+            amount,
+            release_epoch,
+        } => json!({
+            "kind": "unbonding_started",
+            "staker": hex32(&staker.0),
+            "amount": amount,
+            "release_epoch": release_epoch,
+        }),
+        ReceiptEvent::Slashed {
+            proposer,
+            block_hash,
+            burned,
+        } => json!({
+            "kind": "slashed",
+            "proposer": hex32(&proposer.0),
+            "block_hash": hex32(&block_hash.0),
+            "burned": burned,
+        }),
+    }
+}
+
+/// `GET /txs/{hash}/receipt`
+///
+/// Returns the receipt of a transaction included in the canonical chain.
+/// Answers `404` if the transaction is not included, or if the node does
+/// not hold the receipts of its block (e.g. blocks stored by a node
+/// version that did not record receipts).
+#[utoipa::path(
+    get,
+    path = "/v1/txs/{hash}/receipt",
+    tag = "transactions",
+    params(("hash" = String, Path, description = "Hex transaction hash")),
+    responses(
+        (status = 200, description = "The receipt", body = ReceiptResponse),
+        (status = 400, description = "Malformed hash", body = ErrorBody),
+        (status = 404, description = "No receipt for the transaction", body = ErrorBody),
+    )
+)]
+pub async fn get_tx_receipt(
+    State(state): State<SharedState>,
+    Path(hash_hex): Path<String>,
+) -> Result<Json<ReceiptResponse>, ApiError> {
+    let hash = TxHash(hex_to_hash256(&hash_hex).map_err(as_invalid_hash)?);
+    let not_found = || ApiError::not_found("receipt_not_found", "receipt not found");
+
+    let location = state
+        .chain
+        .locate_tx(&hash)
+        .map_err(as_storage_error)?
+        .ok_or_else(not_found)?;
+    let receipt: Receipt = state
+        .chain
+        .store()
+        .get_receipts(&location.block_hash)
+        .map_err(as_storage_error)?
+        .and_then(|mut receipts| {
+            (location.index < receipts.len()).then(|| receipts.swap_remove(location.index))
+        })
+        .ok_or_else(not_found)?;
+    let ml_verdict = state
+        .chain
+        .store()
+        .get_ml_stats(&location.block_hash)
+        .map_err(as_storage_error)?
+        .unwrap_or_default()
+        .iter()
+        .find(|stats| stats.index == receipt.index)
+        .map(ml_verdict_json);
+
+    Ok(Json(ReceiptResponse {
+        hash: hex::encode(hash.0.as_bytes()),
+        block_height: location.height,
+        block_hash: hex::encode(location.block_hash.0.as_bytes()),
+        index: receipt.index,
+        payer: hex::encode(receipt.payer.0.as_bytes()),
+        fee: receipt.fee,
+        events: receipt.events.iter().map(event_json).collect(),
+        ml_verdict,
+    }))
+}
@@ -0,0 +1,78 @@
+//! Validator accountability routes.
+
+use axum::extract::State;
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use chain::ValidatorReport;
+
+use super::{as_storage_error, parse_account};
+use crate::error::{ApiError, ErrorBody};
+use crate::extract::{Json, Path};
+use crate::state::SharedState;
+
+/// Response body for `GET /validators/{id}/report`.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ValidatorReportResponse {
+    /// Hex-encoded proposer `AccountId`.
+    pub proposer: String,
+    /// Blocks by this proposer on the canonical chain.
+    pub canonical_blocks: u64,
+    /// Blocks accepted by this node since it started.
+    pub blocks_accepted: u64,
+    /// Blocks rejected by this node since it started.
+    pub blocks_rejected: u64,
+    /// Average time spent validating this proposer's blocks (including the
+    /// ML authenticity check), in milliseconds; `null` if none were seen.
+    pub avg_validation_ms: Option<f64>,
+    /// Block-time slots that passed without a block right before this
+    /// proposer's canonical blocks.
+    pub missed_slots: u64,
+}
+
+impl From<ValidatorReport> for ValidatorReportResponse {
+    fn from(report: ValidatorReport) -> Self {
+        Self {
+            proposer: hex::encode(report.proposer.0.as_bytes()),
+            canonical_blocks: report.canonical_blocks,
+            blocks_accepted: report.stats.blocks_accepted,
+            blocks_rejected: report.stats.blocks_rejected,
+            avg_validation_ms: report
+                .stats
+                .average_validation_time()
+                .map(|d| d.as_secs_f64() * 1_000.0),
+            missed_slots: report.missed_slots,
+        }
+    }
+}
+
+/// `GET /validators/{id}/report`
+///
+/// Returns per-proposer performance statistics for accountability
+/// analysis. Chain-derived figures scan the whole canonical chain; import
+/// counts and validation times cover blocks this node has seen since it
+/// started. Unknown proposers get an all-zero report.
+#[utoipa::path(
+    get,
+    path = "/v1/validators/{id}/report",
+    tag = "validators",
+    params(("id" = String, Path, description = "Proposer address (`mls1…`) or hex")),
+    responses(
+        (status = 200, description = "Performance report", body = ValidatorReportResponse),
+        (status = 400, description = "Malformed address", body = ErrorBody),
+        (status = 500, description = "Storage error", body = ErrorBody),
+    )
+)]
+pub async fn get_validator_report(
+    State(state): State<SharedState>,
+    Path(id_hex): Path<String>,
+) -> Result<Json<ValidatorReportResponse>, ApiError> {
+    let proposer = parse_account(&id_hex)?;
+
+    let report = state
+        .chain
+        .validator_report(&proposer)
+        .map_err(as_storage_error)?;
+
+    Ok(Json(report.into()))
+}
@@ -1,38 +1,64 @@
-//! Shared application state and transaction pool implementation.
+//! Shared application state.
 
-use std::collections::VecDeque;
+use std::collections::{HashMap, VecDeque};
 use std::sync::Arc;
 
-use tokio::sync::Mutex;
+use tokio::sync::{Mutex, broadcast};
 
-use chain::{AccountId, DefaultConsensusEngine, MetricsRegistry, Transaction, TxPool};
+use chain::{
+    AccountId, ChainEvent, DefaultChainReader, DefaultConsensusEngine, FsArtefactStore, Hash256,
+    InclusionTracker, Mempool, MetricsRegistry, P2pHandle, ProducerWatchdog, SyncHandle, TxHash,
+};
 
-/// Simple in-memory transaction pool backed by a FIFO queue.
-///
-/// HTTP handlers push transactions into the queue; the block producer
-/// drains them when constructing blocks.
+/// Outcome of a tx-submitting request, remembered under its idempotency key.
+#[derive(Clone, Debug)]
+pub struct IdempotentOutcome {
+    /// Hash of the request fields that determine the transaction; a retry
+    /// with the same key but a different request is rejected.
+    pub fingerprint: Hash256,
+    /// Hash of the transaction that was queued.
+    pub tx_hash: TxHash,
+    /// Hex-encoded `Aid` returned to the client.
+    pub aid_hex: String,
+}
+
+/// Bounded map from `Idempotency-Key` header values to the outcome of the
+/// first request that used them. The oldest key is forgotten once the
+/// cache is full.
 #[derive(Default)]
-pub struct QueuedTxPool {
-    queue: VecDeque<Transaction>,
+pub struct IdempotencyCache {
+    capacity: usize,
+    entries: HashMap<String, IdempotentOutcome>,
+    order: VecDeque<String>,
 }
 
-impl QueuedTxPool {
-    pub fn new() -> Self {
+impl IdempotencyCache {
+    pub fn new(capacity: usize) -> Self {
         Self {
-            queue: VecDeque::new(),
+            capacity,
+            entries: HashMap::new(),
+            order: VecDeque::new(),
         }
     }
 
-    /// Enqueues a new transaction to be included in a future block.
-    pub fn push(&mut self, tx: Transaction) {
-        self.queue.push_back(tx);
+    /// Returns the remembered outcome for `key`, if any.
+    pub fn get(&self, key: &str) -> Option<&IdempotentOutcome> {
+        self.entries.get(key)
     }
-}
 
-impl TxPool for QueuedTxPool {
-    fn select_for_block(&mut self, max_txs: usize, _max_bytes: usize) -> Vec<Transaction> {
-        let take = max_txs.min(self.queue.len());
-        self.queue.drain(0..take).collect()
+    /// Remembers `outcome` under `key`, evicting the oldest key if full.
+    pub fn insert(&mut self, key: String, outcome: IdempotentOutcome) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.entries.insert(key.clone(), outcome).is_none() {
+            self.order.push_back(key);
+        }
+        while self.order.len() > self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.entries.remove(&oldest);
+            }
+        }
     }
 }
 
@@ -42,13 +68,39 @@ impl TxPool for QueuedTxPool {
 /// `State` extractor.
 pub struct AppState {
     /// Embedded consensus engine (storage + validators + fork choice).
+    ///
+    /// Locked by the block producer, background maintenance and
+    /// transaction submission; read-only routes use [`chain`](Self::chain)
+    /// instead, so they do not wait for a block to be validated.
     pub engine: Mutex<DefaultConsensusEngine>,
-    /// Transaction pool feeding the proposer.
-    pub tx_pool: Mutex<QueuedTxPool>,
+    /// Read handle to the engine's chain.
+    pub chain: DefaultChainReader,
+    /// Fee-priority mempool feeding the proposer.
+    pub tx_pool: Mutex<Mempool>,
+    /// Admission heights and per-owner inclusion delays of registrations.
+    pub inclusion: Mutex<InclusionTracker>,
+    /// Pauses the block producer while the ML verifier keeps failing.
+    pub watchdog: Mutex<ProducerWatchdog>,
     /// Proposer identity used by the block producer loop.
     pub proposer_id: AccountId,
     /// Metrics registry shared between consensus and the API.
     pub metrics: Arc<MetricsRegistry>,
+    /// Maximum number of blocks per bulk export request.
+    pub max_export_blocks: u64,
+    /// Maximum number of requests in one JSON-RPC batch.
+    pub rpc_max_batch_size: usize,
+    /// Recently used `Idempotency-Key`s and what they produced.
+    pub idempotency: Mutex<IdempotencyCache>,
+    /// Sender the engine publishes chain events into; `GET /ws`
+    /// subscribes to it.
+    pub events: broadcast::Sender<ChainEvent>,
+    /// Progress of block sync, served by `GET /sync`.
+    pub sync: SyncHandle,
+    /// The libp2p network, if `network.p2p.enabled`; proposed blocks and
+    /// admitted transactions are gossiped through it.
+    pub p2p: Option<P2pHandle>,
+    /// Artefact blob store, if `api.artefact_store.enabled`.
+    pub artefacts: Option<FsArtefactStore>,
 }
 
 /// Thread-safe alias for `AppState`.
@@ -0,0 +1,178 @@
+//! API versioning and soft-deprecation warnings.
+//!
+//! Every endpoint is served under a version prefix (`/v1/...`). The
+//! original unversioned paths keep working as aliases of the current
+//! version so existing clients do not break, but their responses carry
+//! deprecation headers pointing at the versioned path.
+//!
+//! Clients may also pin a version with the `X-Api-Version` request header.
+//! On an unversioned path the header selects the version; on a versioned
+//! path it must agree with the prefix. Unsupported versions are rejected
+//! with `400 Bad Request` and an `unsupported_version` error body.
+//!
+//! Handlers can flag deprecated request shapes (fields, field
+//! combinations) by returning [`DeprecationNotices`] as part of their
+//! response. The [`negotiate`] middleware turns those, and unversioned
+//! path use, into:
+//!
+//! - `Deprecation: true`,
+//! - one `Warning: 299 - "<message>"` header per notice, and
+//! - for unversioned paths, `Link: </v1/...>; rel="successor-version"`.
+//!
+//! Every response carries the `X-Api-Version` that served it.
+
+use axum::{
+    extract::Request,
+    http::{HeaderMap, HeaderName, HeaderValue},
+    middleware::Next,
+    response::{IntoResponse, IntoResponseParts, Response, ResponseParts},
+};
+
+use crate::error::ApiError;
+use crate::openapi::{OPENAPI_PATH, SWAGGER_UI_PATH};
+
+/// Version served by unversioned paths and advertised to clients.
+pub const CURRENT_VERSION: u32 = 1;
+
+/// All versions this gateway can serve.
+pub const SUPPORTED_VERSIONS: &[u32] = &[1];
+
+/// Request/response header carrying the API version.
+pub const API_VERSION_HEADER: HeaderName = HeaderName::from_static("x-api-version");
+
+/// Paths that are intentionally unversioned (e.g. liveness probes, the
+/// explorer page, JSON-RPC, API docs) and never marked as deprecated.
+const UNVERSIONED_PATHS: &[&str] = &["/health", "/ready", "/explorer", "/rpc", OPENAPI_PATH];
+
+const DEPRECATION_HEADER: HeaderName = HeaderName::from_static("deprecation");
+
+/// Deprecation warnings attached to a response by a handler.
+///
+/// Returning an empty set is a no-op, so handlers can always include it in
+/// their response tuple.
+#[derive(Clone, Debug, Default)]
+pub struct DeprecationNotices(Vec<String>);
+
+impl DeprecationNotices {
+    /// Adds a human-readable warning, e.g. which field to use instead.
+    pub fn push(&mut self, message: impl Into<String>) {
+        self.0.push(message.into());
+    }
+}
+
+impl IntoResponseParts for DeprecationNotices {
+    type Error = std::convert::Infallible;
+
+    fn into_response_parts(self, mut res: ResponseParts) -> Result<ResponseParts, Self::Error> {
+        if !self.0.is_empty() {
+            res.extensions_mut().insert(self);
+        }
+        Ok(res)
+    }
+}
+
+/// Returns `true` for paths that are intentionally unversioned, including
+/// the Swagger UI assets under `/docs`.
+fn is_unversioned(path: &str) -> bool {
+    UNVERSIONED_PATHS.contains(&path)
+        || path
+            .strip_prefix(SWAGGER_UI_PATH)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+}
+
+/// Splits a `/v{N}/...` path into its version and the unversioned rest.
+fn split_version(path: &str) -> Option<(u32, &str)> {
+    let rest = path.strip_prefix("/v")?;
+    let (digits, tail) = rest.split_at(rest.find('/').unwrap_or(rest.len()));
+    let version = digits.parse().ok()?;
+    Some((version, if tail.is_empty() { "/" } else { tail }))
+}
+
+/// Parses the `X-Api-Version` request header, if present.
+fn requested_version(headers: &HeaderMap) -> Result<Option<u32>, String> {
+    let Some(value) = headers.get(&API_VERSION_HEADER) else {
+        return Ok(None);
+    };
+    let raw = value
+        .to_str()
+        .map_err(|_| "X-Api-Version must be ASCII".to_string())?;
+    let trimmed = raw.trim();
+    trimmed
+        .strip_prefix('v')
+        .unwrap_or(trimmed)
+        .parse()
+        .map(Some)
+        .map_err(|_| format!("invalid X-Api-Version {raw:?}"))
+}
+
+/// Formats a `Warning` header value (RFC 7234 code 299, "miscellaneous
+/// persistent warning").
+fn warning_value(message: &str) -> Option<HeaderValue> {
+    let escaped = message.replace('\\', "\\\\").replace('"', "\\\"");
+    HeaderValue::from_str(&format!("299 - \"{escaped}\"")).ok()
+}
+
+/// Middleware that negotiates the API version and emits deprecation
+/// headers. Apply it to the whole router.
+pub async fn negotiate(req: Request, next: Next) -> Response {
+    let path = req.uri().path().to_string();
+    let requested = match requested_version(req.headers()) {
+        Ok(v) => v,
+        Err(msg) => return ApiError::bad_request("invalid_version", msg).into_response(),
+    };
+
+    let path_version = split_version(&path);
+    let legacy = path_version.is_none() && !is_unversioned(&path);
+    let version = match (path_version, requested) {
+        (Some((v, _)), Some(h)) if v != h => {
+            return ApiError::bad_request(
+                "version_conflict",
+                format!("X-Api-Version {h} conflicts with the /v{v} path"),
+            )
+            .into_response();
+        }
+        (Some((v, _)), _) => v,
+        (None, Some(h)) => h,
+        (None, None) => CURRENT_VERSION,
+    };
+    if !SUPPORTED_VERSIONS.contains(&version) {
+        return ApiError::bad_request(
+            "unsupported_version",
+            format!("unsupported API version {version}; supported: {SUPPORTED_VERSIONS:?}"),
+        )
+        .into_response();
+    }
+
+    let mut res = next.run(req).await;
+
+    let mut warnings = res
+        .extensions_mut()
+        .remove::<DeprecationNotices>()
+        .unwrap_or_default();
+    if legacy {
+        warnings.push(format!(
+            "unversioned API paths are deprecated; use /v{version}{path}"
+        ));
+    }
+
+    let headers = res.headers_mut();
+    headers.insert(API_VERSION_HEADER, HeaderValue::from(version));
+    if warnings.0.is_empty() {
+        return res;
+    }
+
+    tracing::debug!(%path, warnings = ?warnings.0, "served deprecated API usage");
+    headers.insert(DEPRECATION_HEADER, HeaderValue::from_static("true"));
+    if legacy
+        && let Ok(link) =
+            HeaderValue::from_str(&format!("</v{version}{path}>; rel=\"successor-version\""))
+    {
+        headers.insert(axum::http::header::LINK, link);
+    }
+    for message in &warnings.0 {
+        if let Some(value) = warning_value(message) {
+            headers.append(axum::http::header::WARNING, value);
+        }
+    }
+    res
+}
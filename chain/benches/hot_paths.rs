@@ -0,0 +1,223 @@
+//! Criterion benchmarks for the per-block hot paths.
+//!
+//! Every group runs over the same deterministic blocks of
+//! [`BLOCK_SIZES`] transactions (an even mix of model registrations and
+//! transfers from a fixed set of senders), so results can be compared
+//! across commits with criterion baselines:
+//!
+//! ```text
+//! cargo bench -p chain --bench hot_paths -- --save-baseline main
+//! # ...check out another commit...
+//! cargo bench -p chain --bench hot_paths -- --baseline main
+//! ```
+
+use std::hint::black_box;
+
+use criterion::{BatchSize, BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
+use tempfile::TempDir;
+
+use chain::{
+    AccountId, Aid, BaseValidity, Block, BlockHash, BlockStore, BlockValidator, ChainParams,
+    ChainState, ConsensusConfig, EvidenceHash, EvidenceRef, GenesisAccount, GenesisConfig,
+    HASH_LEN, Hash256, Header, Mempool, MempoolConfig, RocksDbBlockStore, Signature, StorageConfig,
+    Transaction, TxPool, TxRegisterModel, TxTransfer, ValidationContext,
+};
+
+/// Transactions per block for every benchmark.
+const BLOCK_SIZES: [usize; 4] = [10, 100, 1_000, 10_000];
+
+/// Transactions each sender contributes, with consecutive nonces.
+const TXS_PER_SENDER: usize = 4;
+
+fn account(i: usize) -> AccountId {
+    AccountId(Hash256::compute(format!("bench-account-{i}").as_bytes()))
+}
+
+/// Transaction `i` of a block: even indices register a model, odd ones
+/// transfer to the next sender.
+fn transaction(i: usize) -> Transaction {
+    let sender = i / TXS_PER_SENDER;
+    let nonce = (i % TXS_PER_SENDER) as u64;
+    if i.is_multiple_of(2) {
+        Transaction::RegisterModel(TxRegisterModel {
+            owner: account(sender),
+            aid: Aid(Hash256::compute(format!("bench-model-{i}").as_bytes())),
+            chunked: None,
+            evidence: EvidenceRef {
+                scheme_id: "wm-bench".to_string(),
+                evidence_hash: EvidenceHash(Hash256::compute(
+                    format!("bench-evidence-{i}").as_bytes(),
+                )),
+                wm_profile: ChainParams::default().wm_profile,
+            },
+            fee: 1,
+            nonce,
+            signature: Signature(vec![0xab; 64]),
+        })
+    } else {
+        Transaction::Transfer(TxTransfer {
+            from: account(sender),
+            to: account(sender + 1),
+            amount: 10,
+            fee: 1,
+            nonce,
+            signature: Signature(vec![0xab; 64]),
+        })
+    }
+}
+
+fn transactions(n: usize) -> Vec<Transaction> {
+    (0..n).map(transaction).collect()
+}
+
+/// A block of `n` transactions with a valid transactions root.
+fn block(n: usize) -> Block {
+    let mut block = Block {
+        header: Header {
+            parent: BlockHash(Hash256([7u8; HASH_LEN])),
+            height: 1,
+            timestamp: 1_700_000_005,
+            proposer: account(usize::MAX),
+            txs_root: Hash256([0u8; HASH_LEN]),
+            state_root: Hash256([0u8; HASH_LEN]),
+            receipts_root: Hash256([0u8; HASH_LEN]),
+            pos_proof: None,
+        },
+        txs: transactions(n),
+    };
+    block.header.txs_root = block.compute_txs_root();
+    block
+}
+
+/// State in which every sender of an `n`-transaction block is funded.
+fn funded_state(n: usize) -> ChainState {
+    GenesisConfig {
+        accounts: (0..=n / TXS_PER_SENDER)
+            .map(|i| GenesisAccount {
+                account: account(i),
+                balance: 1_000_000,
+                stake: 0,
+            })
+            .collect(),
+        ..GenesisConfig::default()
+    }
+    .initial_state()
+}
+
+fn encoding(c: &mut Criterion) {
+    let mut group = c.benchmark_group("encoding");
+    for n in BLOCK_SIZES {
+        let block = block(n);
+        group.throughput(Throughput::Bytes(block.canonical_bytes().len() as u64));
+        group.bench_with_input(
+            BenchmarkId::new("block_canonical_bytes", n),
+            &block,
+            |b, block| b.iter(|| black_box(block).canonical_bytes()),
+        );
+    }
+    group.finish();
+}
+
+fn hashing(c: &mut Criterion) {
+    let mut group = c.benchmark_group("hashing");
+    // The block hash covers the header only; transactions enter it through
+    // the transactions root.
+    let header_only = block(0);
+    group.bench_function("block_compute_hash", |b| {
+        b.iter(|| black_box(&header_only).compute_hash())
+    });
+    for n in BLOCK_SIZES {
+        let block = block(n);
+        group.throughput(Throughput::Elements(n as u64));
+        group.bench_with_input(BenchmarkId::new("txs_root", n), &block, |b, block| {
+            b.iter(|| black_box(block).compute_txs_root())
+        });
+    }
+    group.finish();
+}
+
+fn validation(c: &mut Criterion) {
+    let cfg = ConsensusConfig {
+        max_block_txs: usize::MAX,
+        max_block_size_bytes: usize::MAX,
+        ..ConsensusConfig::default()
+    };
+    let validity = BaseValidity::new(&cfg);
+    let ctx = ValidationContext::detached();
+
+    let mut group = c.benchmark_group("validation");
+    for n in BLOCK_SIZES {
+        let block = block(n);
+        validity
+            .validate(&block, &ctx)
+            .expect("benchmark blocks pass base validation");
+        group.throughput(Throughput::Elements(n as u64));
+        group.bench_with_input(BenchmarkId::new("base_validity", n), &block, |b, block| {
+            b.iter(|| validity.validate(black_box(block), &ctx))
+        });
+    }
+    group.finish();
+}
+
+fn mempool(c: &mut Criterion) {
+    let cfg = MempoolConfig {
+        max_txs: usize::MAX,
+        max_bytes: usize::MAX,
+        ..MempoolConfig::default()
+    };
+
+    let mut group = c.benchmark_group("mempool");
+    for n in BLOCK_SIZES {
+        let state = funded_state(n);
+        let txs = transactions(n);
+        group.throughput(Throughput::Elements(n as u64));
+        group.bench_with_input(BenchmarkId::new("select_for_block", n), &n, |b, &n| {
+            b.iter_batched(
+                || {
+                    let mut pool = Mempool::new(cfg.clone());
+                    for tx in &txs {
+                        pool.insert(tx.clone(), &state)
+                            .expect("benchmark transactions are admitted");
+                    }
+                    pool
+                },
+                |mut pool| pool.select_for_block(n, usize::MAX),
+                BatchSize::LargeInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+fn rocksdb(c: &mut Criterion) {
+    let dir = TempDir::new().expect("create temp dir");
+    let mut store = RocksDbBlockStore::open(&StorageConfig {
+        path: dir.path().to_string_lossy().to_string(),
+        create_if_missing: true,
+        ..StorageConfig::default()
+    })
+    .expect("open RocksDB");
+
+    let mut group = c.benchmark_group("rocksdb");
+    for n in BLOCK_SIZES {
+        let block = block(n);
+        let hash = block.compute_hash();
+        group.throughput(Throughput::Bytes(block.canonical_bytes().len() as u64));
+        // Re-writing the same block overwrites the same keys, so the store
+        // does not grow while the benchmark runs.
+        group.bench_with_input(BenchmarkId::new("put_block", n), &block, |b, block| {
+            b.iter_batched(
+                || block.clone(),
+                |block| store.put_block(block).expect("put block"),
+                BatchSize::SmallInput,
+            )
+        });
+        group.bench_with_input(BenchmarkId::new("get_block", n), &hash, |b, hash| {
+            b.iter(|| store.get_block(black_box(hash)).expect("get block"))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, encoding, hashing, validation, mempool, rocksdb);
+criterion_main!(benches);
@@ -0,0 +1,16 @@
+//! Compiles `proto/ml_verifier.proto` for the gRPC ML client.
+//!
+//! Uses the `protoc` from `$PROTOC` if set, and otherwise the vendored
+//! binary from `protoc-bin-vendored`, so no system install is required.
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    if std::env::var_os("PROTOC").is_none() {
+        let protoc = protoc_bin_vendored::protoc_bin_path()?;
+        // SAFETY: build scripts are single-threaded.
+        unsafe { std::env::set_var("PROTOC", protoc) };
+    }
+
+    println!("cargo:rerun-if-changed=proto/ml_verifier.proto");
+    tonic_prost_build::compile_protos("proto/ml_verifier.proto")?;
+    Ok(())
+}
@@ -0,0 +1,331 @@
+//! Filesystem artefact store.
+//!
+//! Layout under the configured directory:
+//!
+//! - `blobs/<first two hex digits>/<aid hex>`: complete, verified blobs,
+//! - `tmp/`: uploads in progress, renamed into `blobs/` once hashed,
+//! - `corrupt/`: blobs that failed verification on read, kept for
+//!   inspection.
+//!
+//! Uploads and reads never share a file: a finished upload is moved into
+//! place with a rename, so a reader holding a blob open keeps seeing the
+//! bytes it verified.
+
+use std::io::SeekFrom;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use bytes::Bytes;
+use futures_util::Stream;
+use tokio::fs::File;
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
+
+use super::{ArtefactStoreConfig, ArtefactStoreError};
+use crate::types::{Aid, Hash256};
+
+/// Size of the buffer blobs are read and hashed in.
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Distinguishes concurrent uploads started within the same nanosecond.
+static UPLOAD_SEQ: AtomicU64 = AtomicU64::new(0);
+
+/// Artefact blobs stored in a local directory, keyed by [`Aid`].
+#[derive(Clone, Debug)]
+pub struct FsArtefactStore {
+    root: PathBuf,
+    max_blob_bytes: u64,
+}
+
+/// A blob written by [`BlobUpload::commit`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StoredBlob {
+    /// Identifier derived from the uploaded bytes.
+    pub aid: Aid,
+    /// Blob size in bytes.
+    pub size: u64,
+    /// `false` if the store already held these bytes.
+    pub created: bool,
+}
+
+impl FsArtefactStore {
+    /// Opens the store at `cfg.path`, creating its directories if missing.
+    pub fn open(cfg: &ArtefactStoreConfig) -> Result<Self, ArtefactStoreError> {
+        let root = PathBuf::from(&cfg.path);
+        for dir in ["blobs", "tmp", "corrupt"] {
+            std::fs::create_dir_all(root.join(dir))?;
+        }
+        Ok(Self {
+            root,
+            max_blob_bytes: cfg.max_blob_bytes,
+        })
+    }
+
+    /// Largest blob accepted, in bytes.
+    pub fn max_blob_bytes(&self) -> u64 {
+        self.max_blob_bytes
+    }
+
+    fn blob_path(&self, aid: &Aid) -> PathBuf {
+        let hex = hex::encode(aid.0.as_bytes());
+        self.root.join("blobs").join(&hex[..2]).join(hex)
+    }
+
+    /// Starts a new upload. The bytes written to it are hashed as they
+    /// arrive; [`BlobUpload::commit`] files them under the resulting `Aid`.
+    pub async fn begin_upload(&self) -> Result<BlobUpload, ArtefactStoreError> {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos())
+            .unwrap_or_default();
+        let seq = UPLOAD_SEQ.fetch_add(1, Ordering::Relaxed);
+        let tmp_path = self
+            .root
+            .join("tmp")
+            .join(format!("{}-{nanos}-{seq}", std::process::id()));
+        let file = File::create(&tmp_path).await?;
+        Ok(BlobUpload {
+            store: self.clone(),
+            file,
+            tmp_path: Some(tmp_path),
+            hasher: blake3::Hasher::new(),
+            size: 0,
+        })
+    }
+
+    /// Returns `true` if a blob is stored under `aid`. The blob is not
+    /// verified.
+    pub async fn contains(&self, aid: &Aid) -> bool {
+        tokio::fs::try_exists(self.blob_path(aid))
+            .await
+            .unwrap_or(false)
+    }
+
+    /// Opens the blob stored under `aid` after checking that its bytes
+    /// still hash to `aid`.
+    ///
+    /// Returns `Ok(None)` if no blob is stored. A blob that fails the check
+    /// is moved to `corrupt/`, so that it can be uploaded again, and
+    /// [`ArtefactStoreError::Corrupted`] is returned.
+    pub async fn open_verified(&self, aid: &Aid) -> Result<Option<BlobReader>, ArtefactStoreError> {
+        let path = self.blob_path(aid);
+        let mut file = match File::open(&path).await {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut hasher = blake3::Hasher::new();
+        let mut buf = vec![0u8; CHUNK_SIZE];
+        let mut size = 0u64;
+        loop {
+            let n = file.read(&mut buf).await?;
+            if n == 0 {
+                break;
+            }
+            hasher.update(&buf[..n]);
+            size += n as u64;
+        }
+        if Aid(Hash256(*hasher.finalize().as_bytes())) != *aid {
+            self.quarantine(aid, &path).await?;
+            return Err(ArtefactStoreError::Corrupted(*aid));
+        }
+
+        file.seek(SeekFrom::Start(0)).await?;
+        Ok(Some(BlobReader { file, size }))
+    }
+
+    /// Reads the whole blob stored under `aid`, verified as by
+    /// [`open_verified`](Self::open_verified).
+    pub async fn get(&self, aid: &Aid) -> Result<Option<Vec<u8>>, ArtefactStoreError> {
+        let Some(mut reader) = self.open_verified(aid).await? else {
+            return Ok(None);
+        };
+        let mut bytes = Vec::with_capacity(usize::try_from(reader.size).unwrap_or_default());
+        reader.file.read_to_end(&mut bytes).await?;
+        Ok(Some(bytes))
+    }
+
+    async fn quarantine(&self, aid: &Aid, path: &Path) -> Result<(), ArtefactStoreError> {
+        let hex = hex::encode(aid.0.as_bytes());
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos())
+            .unwrap_or_default();
+        let dest = self.root.join("corrupt").join(format!("{hex}-{nanos}"));
+        tokio::fs::rename(path, dest).await?;
+        Ok(())
+    }
+}
+
+/// An upload in progress. Dropping it without calling
+/// [`commit`](Self::commit) discards the bytes written so far.
+#[derive(Debug)]
+pub struct BlobUpload {
+    store: FsArtefactStore,
+    file: File,
+    /// Cleared once the file has been moved into place.
+    tmp_path: Option<PathBuf>,
+    hasher: blake3::Hasher,
+    size: u64,
+}
+
+impl BlobUpload {
+    /// Appends `chunk` to the upload.
+    ///
+    /// Fails with [`ArtefactStoreError::TooLarge`] once the upload exceeds
+    /// the store's size limit.
+    pub async fn write(&mut self, chunk: &[u8]) -> Result<(), ArtefactStoreError> {
+        let limit = self.store.max_blob_bytes;
+        self.size = self.size.saturating_add(chunk.len() as u64);
+        if self.size > limit {
+            return Err(ArtefactStoreError::TooLarge { limit });
+        }
+        self.hasher.update(chunk);
+        self.file.write_all(chunk).await?;
+        Ok(())
+    }
+
+    /// Number of bytes written so far.
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// Finishes the upload and files the blob under the `Aid` of its bytes.
+    /// If the store already holds that blob the upload is discarded.
+    pub async fn commit(mut self) -> Result<StoredBlob, ArtefactStoreError> {
+        self.file.flush().await?;
+        self.file.sync_all().await?;
+
+        let aid = Aid(Hash256(*self.hasher.finalize().as_bytes()));
+        let dest = self.store.blob_path(&aid);
+        let created = !self.store.contains(&aid).await;
+        if created {
+            if let Some(parent) = dest.parent() {
+                tokio::fs::create_dir_all(parent).await?;
+            }
+            if let Some(tmp_path) = self.tmp_path.take() {
+                tokio::fs::rename(&tmp_path, &dest).await?;
+            }
+        }
+        Ok(StoredBlob {
+            aid,
+            size: self.size,
+            created,
+        })
+    }
+}
+
+impl Drop for BlobUpload {
+    fn drop(&mut self) {
+        if let Some(tmp_path) = self.tmp_path.take() {
+            let _ = std::fs::remove_file(tmp_path);
+        }
+    }
+}
+
+/// A verified blob opened for reading.
+#[derive(Debug)]
+pub struct BlobReader {
+    file: File,
+    size: u64,
+}
+
+impl BlobReader {
+    /// Blob size in bytes.
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// Streams the blob in chunks.
+    pub fn into_stream(self) -> impl Stream<Item = std::io::Result<Bytes>> + Send {
+        futures_util::stream::try_unfold(self.file, |mut file| async move {
+            let mut buf = vec![0u8; CHUNK_SIZE];
+            let n = file.read(&mut buf).await?;
+            if n == 0 {
+                return Ok(None);
+            }
+            buf.truncate(n);
+            Ok(Some((Bytes::from(buf), file)))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures_util::TryStreamExt;
+
+    fn open(dir: &tempfile::TempDir, max_blob_bytes: u64) -> FsArtefactStore {
+        FsArtefactStore::open(&ArtefactStoreConfig {
+            enabled: true,
+            path: dir.path().to_string_lossy().into_owned(),
+            max_blob_bytes,
+        })
+        .expect("open store")
+    }
+
+    #[tokio::test]
+    async fn upload_is_keyed_by_aid_and_deduplicated() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = open(&dir, 1 << 20);
+        let model = vec![42u8; 3 * CHUNK_SIZE / 2];
+
+        let mut upload = store.begin_upload().await.unwrap();
+        for chunk in model.chunks(1000) {
+            upload.write(chunk).await.unwrap();
+        }
+        let stored = upload.commit().await.unwrap();
+        assert_eq!(stored.aid, Aid::from_model_bytes(&model));
+        assert_eq!(stored.size, model.len() as u64);
+        assert!(stored.created);
+
+        let mut again = store.begin_upload().await.unwrap();
+        again.write(&model).await.unwrap();
+        assert!(!again.commit().await.unwrap().created);
+
+        let reader = store.open_verified(&stored.aid).await.unwrap().unwrap();
+        assert_eq!(reader.size(), model.len() as u64);
+        let streamed: Vec<Bytes> = reader.into_stream().try_collect().await.unwrap();
+        assert_eq!(streamed.concat(), model);
+        assert_eq!(
+            std::fs::read_dir(dir.path().join("tmp")).unwrap().count(),
+            0
+        );
+    }
+
+    #[tokio::test]
+    async fn oversized_uploads_are_discarded() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = open(&dir, 10);
+
+        let mut upload = store.begin_upload().await.unwrap();
+        upload.write(&[1; 8]).await.unwrap();
+        assert!(matches!(
+            upload.write(&[1; 8]).await,
+            Err(ArtefactStoreError::TooLarge { limit: 10 })
+        ));
+        drop(upload);
+        assert_eq!(
+            std::fs::read_dir(dir.path().join("tmp")).unwrap().count(),
+            0
+        );
+    }
+
+    #[tokio::test]
+    async fn corrupted_blobs_are_quarantined() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = open(&dir, 1 << 20);
+        let mut upload = store.begin_upload().await.unwrap();
+        upload.write(b"model weights").await.unwrap();
+        let aid = upload.commit().await.unwrap().aid;
+
+        std::fs::write(store.blob_path(&aid), b"tampered weights").unwrap();
+        assert!(matches!(
+            store.get(&aid).await,
+            Err(ArtefactStoreError::Corrupted(bad)) if bad == aid
+        ));
+        assert!(!store.contains(&aid).await);
+        assert_eq!(store.get(&aid).await.unwrap(), None);
+    }
+}
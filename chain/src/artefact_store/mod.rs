@@ -0,0 +1,82 @@
+//! Content-addressed storage for model artefact bytes.
+//!
+//! An [`Aid`] is the hash of a model's canonical bytes, but the chain only
+//! records the hash. This module keeps the bytes themselves, keyed by the
+//! `Aid` they hash to, so that nodes can serve them to verifiers and
+//! clients.
+//!
+//! Blobs are written through a [`BlobUpload`], which hashes the bytes as
+//! they stream in and only files them under their `Aid` once complete; a
+//! blob can therefore never be stored under the wrong key. Reads re-hash
+//! the blob before handing it out ([`FsArtefactStore::open_verified`]), so
+//! on-disk corruption is detected rather than served.
+//!
+//! The only backend is the local filesystem ([`FsArtefactStore`]).
+
+use std::fmt;
+
+use serde::Deserialize;
+
+use crate::types::Aid;
+
+pub mod fs;
+
+pub use fs::{BlobReader, BlobUpload, FsArtefactStore, StoredBlob};
+
+/// Configuration for the artefact blob store.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ArtefactStoreConfig {
+    /// Whether artefact bytes are stored at all. Disabled by default.
+    pub enabled: bool,
+    /// Directory holding the blobs.
+    pub path: String,
+    /// Largest blob accepted, in bytes.
+    pub max_blob_bytes: u64,
+}
+
+impl Default for ArtefactStoreConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            path: "data/artefacts".to_string(),
+            max_blob_bytes: 4 * 1024 * 1024 * 1024,
+        }
+    }
+}
+
+/// Errors produced by the artefact store.
+#[derive(Debug)]
+pub enum ArtefactStoreError {
+    /// Underlying filesystem error.
+    Io(std::io::Error),
+    /// An upload exceeded [`ArtefactStoreConfig::max_blob_bytes`].
+    TooLarge { limit: u64 },
+    /// A stored blob no longer hashes to its `Aid`. The blob has been moved
+    /// out of the store.
+    Corrupted(Aid),
+}
+
+impl From<std::io::Error> for ArtefactStoreError {
+    fn from(e: std::io::Error) -> Self {
+        ArtefactStoreError::Io(e)
+    }
+}
+
+impl fmt::Display for ArtefactStoreError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ArtefactStoreError::Io(e) => write!(f, "io: {e}"),
+            ArtefactStoreError::TooLarge { limit } => {
+                write!(f, "artefact exceeds the {limit} byte limit")
+            }
+            ArtefactStoreError::Corrupted(aid) => write!(
+                f,
+                "stored artefact {} failed integrity verification",
+                hex::encode(aid.0.as_bytes())
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ArtefactStoreError {}
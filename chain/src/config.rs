@@ -3,48 +3,406 @@
 //! This module aggregates configuration for:
 //!
 //! - consensus parameters (`ConsensusConfig`),
-//! - storage (RocksDB path and creation flags),
-//! - ML verification client (ML service URL + timeout),
-//! - metrics exporter (enable flag + listen address).
+//! - storage (RocksDB or SQLite backend, path, creation flags, and history
+//!   pruning),
+//! - ML verification client (HTTP, gRPC, or a local ONNX detector,
+//!   endpoint, TLS, timeout, retries, circuit breaker, auth),
+//! - metrics exporter (enable flag + listen addresses, optionally with TLS,
+//!   or a Pushgateway / remote-write endpoint in push mode) and OTLP trace
+//!   export (collector endpoint, sampling),
+//! - fork garbage collection (depth, interval, archive mode),
+//! - consensus invariant checks (enable flag, finality depth),
+//! - the block producer watchdog (ML failure threshold, probe interval),
+//! - the ML decision log (enable flag),
+//! - the transaction mempool (count, byte, and per-account limits),
+//! - peer-to-peer message encoding (gossip compression, message size),
+//! - stateless transaction checks on submission (fee bounds, signatures),
+//! - the validator set used for slot-based proposer scheduling,
+//! - the genesis specification (chain id, initial balances and artefacts).
 //!
-//! The goal is to have a single `ChainConfig` struct that higher-level
-//! binaries (e.g. `main.rs`) can construct from defaults, config files,
-//! or environment variables as needed.
+//! A `ChainConfig` can be built from defaults, loaded from a TOML or YAML
+//! file ([`ChainConfig::from_file`]), and overridden field by field with
+//! `CHAIN_<SECTION>_<FIELD>` environment variables
+//! ([`ChainConfig::apply_env_overrides`]). The file layout mirrors the
+//! struct layout; see `configs/devnet.toml`. Every section and field is
+//! optional and falls back to its default, but unknown keys are rejected
+//! so that typos do not silently fall back to defaults.
 
+use std::collections::HashMap;
+use std::fmt;
 use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
 use std::time::Duration;
 
-use crate::consensus::ConsensusConfig;
-use crate::storage::RocksDbConfig;
+use libp2p::Multiaddr;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Deserializer};
+
+use crate::consensus::{
+    ConsensusConfig, ElectionKind, GcConfig, GenesisConfig, InvariantConfig, ValidatorSetConfig,
+    WatchdogConfig,
+};
+use crate::keystore::{self, KeystoreError};
+use crate::mempool::MempoolConfig;
+use crate::metrics::{HistogramBuckets, MetricsMode, OtlpConfig, PushConfig};
+use crate::ml_client::CircuitBreakerConfig;
+use crate::network::NetworkConfig;
+use crate::storage::{StorageBackend, StorageConfig};
+use crate::transport::{ListenerConfig, TransportConfig};
+use crate::types::Keypair;
+use crate::validation::{DecisionLogConfig, MlConfig, MlOutagePolicy, TxValidityConfig};
+
+/// Errors produced while loading or validating configuration.
+#[derive(Debug)]
+pub enum ConfigError {
+    /// The config file could not be read.
+    Read {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    /// The config file is not valid TOML/YAML or does not match the
+    /// expected layout.
+    Parse { path: PathBuf, message: String },
+    /// The config file extension is not `.toml`, `.yaml`, or `.yml`.
+    UnsupportedFormat(PathBuf),
+    /// An environment variable override could not be parsed.
+    Env { var: String, message: String },
+    /// The configuration was loaded but is not usable.
+    Invalid(Vec<String>),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Read { path, source } => {
+                write!(f, "failed to read config file {}: {source}", path.display())
+            }
+            ConfigError::Parse { path, message } => {
+                write!(f, "invalid config file {}: {message}", path.display())
+            }
+            ConfigError::UnsupportedFormat(path) => write!(
+                f,
+                "unsupported config file {}: expected a .toml, .yaml, or .yml extension",
+                path.display()
+            ),
+            ConfigError::Env { var, message } => {
+                write!(f, "invalid value for environment variable {var}: {message}")
+            }
+            ConfigError::Invalid(problems) => {
+                write!(f, "invalid configuration: {}", problems.join("; "))
+            }
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ConfigError::Read { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+/// Reads and deserializes a TOML or YAML config file.
+///
+/// The format is chosen by the file extension (`.toml`, `.yaml`, `.yml`).
+pub fn parse_config_file<T: DeserializeOwned>(path: &Path) -> Result<T, ConfigError> {
+    let extension = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(str::to_ascii_lowercase);
+    let contents = std::fs::read_to_string(path).map_err(|source| ConfigError::Read {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    let parse_error = |message: String| ConfigError::Parse {
+        path: path.to_path_buf(),
+        message,
+    };
+
+    match extension.as_deref() {
+        Some("toml") => toml::from_str(&contents).map_err(|e| parse_error(e.to_string())),
+        Some("yaml" | "yml") => {
+            serde_yaml::from_str(&contents).map_err(|e| parse_error(e.to_string()))
+        }
+        _ => Err(ConfigError::UnsupportedFormat(path.to_path_buf())),
+    }
+}
+
+/// Overwrites `target` with the parsed value of environment variable
+/// `var`, if `lookup` returns one.
+pub fn env_override<T>(
+    lookup: &impl Fn(&str) -> Option<String>,
+    var: &str,
+    target: &mut T,
+) -> Result<(), ConfigError>
+where
+    T: FromStr,
+    T::Err: fmt::Display,
+{
+    if let Some(raw) = lookup(var) {
+        *target = raw.trim().parse().map_err(|e: T::Err| ConfigError::Env {
+            var: var.to_string(),
+            message: format!("{e} (got {raw:?})"),
+        })?;
+    }
+    Ok(())
+}
+
+/// Overwrites `target` with the comma-separated listen addresses in
+/// environment variable `var`, if `lookup` returns one.
+///
+/// Listeners set this way are plain TCP; TLS listeners can only be
+/// configured from a file.
+pub fn env_listeners_override(
+    lookup: &impl Fn(&str) -> Option<String>,
+    var: &str,
+    target: &mut Vec<ListenerConfig>,
+) -> Result<(), ConfigError> {
+    if let Some(raw) = lookup(var) {
+        *target = raw
+            .split(',')
+            .map(str::parse)
+            .collect::<Result<_, _>>()
+            .map_err(|e| ConfigError::Env {
+                var: var.to_string(),
+                message: format!("{e} (got {raw:?})"),
+            })?;
+    }
+    Ok(())
+}
+
+/// Overwrites `target` with the comma-separated entries of environment
+/// variable `var`, if `lookup` returns one. An empty value clears it.
+pub fn env_list_override(
+    lookup: &impl Fn(&str) -> Option<String>,
+    var: &str,
+    target: &mut Vec<String>,
+) {
+    if let Some(raw) = lookup(var) {
+        *target = raw
+            .split(',')
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+            .map(str::to_string)
+            .collect();
+    }
+}
+
+/// Returns a problem for each address bound by more than one of
+/// `listeners`, prefixed with the config key `field`.
+pub fn duplicate_listener_problems(field: &str, listeners: &[ListenerConfig]) -> Vec<String> {
+    let mut problems = Vec::new();
+    for (i, listener) in listeners.iter().enumerate() {
+        let first = listeners.iter().position(|l| l.addr == listener.addr);
+        if first == Some(i) && listeners[i + 1..].iter().any(|l| l.addr == listener.addr) {
+            problems.push(format!("{field} binds {} more than once", listener.addr));
+        }
+    }
+    problems
+}
+
+/// Returns `true` if `s` is a valid Prometheus label name, which also
+/// makes it a valid metric name prefix.
+fn is_metric_ident(s: &str) -> bool {
+    let mut chars = s.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Deserializes a whole number of seconds into a [`Duration`].
+fn duration_from_secs<'de, D: Deserializer<'de>>(d: D) -> Result<Duration, D::Error> {
+    u64::deserialize(d).map(Duration::from_secs)
+}
+
+/// Deserializes a whole number of milliseconds into a [`Duration`].
+fn duration_from_millis<'de, D: Deserializer<'de>>(d: D) -> Result<Duration, D::Error> {
+    u64::deserialize(d).map(Duration::from_millis)
+}
+
+/// Wire protocol used to reach the ML verification service.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MlProtocol {
+    /// JSON over HTTP (`HttpMlVerifier`), using `base_url`.
+    #[default]
+    Http,
+    /// gRPC (`GrpcMlVerifier`), using the `grpc` section.
+    Grpc,
+    /// In-process ONNX detector (`LocalOnnxVerifier`), using the `local`
+    /// section. Requires the `local-onnx` feature.
+    Local,
+}
+
+impl FromStr for MlProtocol {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "http" => Ok(MlProtocol::Http),
+            "grpc" => Ok(MlProtocol::Grpc),
+            "local" => Ok(MlProtocol::Local),
+            _ => Err("expected \"http\", \"grpc\", or \"local\"".to_string()),
+        }
+    }
+}
+
+/// Connection settings for the gRPC ML client.
+///
+/// TLS is used when `endpoint` starts with `https://`.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GrpcClientConfig {
+    /// gRPC endpoint of the ML service, e.g. `"http://127.0.0.1:50051"`.
+    pub endpoint: String,
+    /// TLS settings for `https://` endpoints.
+    pub tls: GrpcTlsConfig,
+}
+
+impl Default for GrpcClientConfig {
+    fn default() -> Self {
+        Self {
+            endpoint: "http://127.0.0.1:50051".to_string(),
+            tls: GrpcTlsConfig::default(),
+        }
+    }
+}
+
+/// TLS settings for the gRPC ML client.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GrpcTlsConfig {
+    /// PEM file with the CA certificate(s) to trust. Defaults to the
+    /// webpki root store.
+    pub ca_cert: Option<PathBuf>,
+    /// Server name to verify the certificate against, if it differs from
+    /// the endpoint host.
+    pub domain_name: Option<String>,
+    /// PEM client certificate for mutual TLS (requires `client_key`).
+    pub client_cert: Option<PathBuf>,
+    /// PEM private key for `client_cert`.
+    pub client_key: Option<PathBuf>,
+}
+
+impl GrpcTlsConfig {
+    fn is_set(&self) -> bool {
+        self.ca_cert.is_some()
+            || self.domain_name.is_some()
+            || self.client_cert.is_some()
+            || self.client_key.is_some()
+    }
+}
+
+/// Detector settings for the local ONNX ML client.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LocalModelConfig {
+    /// ONNX detector model file.
+    pub model_path: PathBuf,
+    /// Minimum trigger accuracy per watermark scheme (keyed by
+    /// `scheme_id`). Schemes not listed are judged by their `WmProfile`.
+    pub thresholds: HashMap<String, f32>,
+}
 
 /// Configuration for the ML verification client.
 ///
 /// This is used to construct an HTTP or gRPC client that implements
-/// `validation::MlVerifier`.
-#[derive(Clone, Debug)]
+/// `validation::AsyncMlVerifier`; see `ml_client::MlClient::from_config`.
+///
+/// In config files the durations are given as `timeout_secs`,
+/// `retry_backoff_ms`, and `retry_max_backoff_ms`.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
 pub struct MlClientConfig {
+    /// Protocol used to reach the service.
+    pub protocol: MlProtocol,
     /// Base URL of the ML verification service, e.g. `"http://127.0.0.1:8080"`.
     pub base_url: String,
-    /// Request timeout for ML verification calls.
+    /// Request timeout for ML verification calls (per attempt).
+    #[serde(rename = "timeout_secs", deserialize_with = "duration_from_secs")]
     pub timeout: Duration,
+    /// Number of retries after a failed attempt (HTTP only).
+    pub max_retries: u32,
+    /// Delay before the first retry; doubled for every further retry.
+    #[serde(rename = "retry_backoff_ms", deserialize_with = "duration_from_millis")]
+    pub retry_backoff: Duration,
+    /// Upper bound on the delay between retries.
+    #[serde(
+        rename = "retry_max_backoff_ms",
+        deserialize_with = "duration_from_millis"
+    )]
+    pub retry_max_backoff: Duration,
+    /// Fraction in `[0, 1]` by which retry delays are randomly shortened.
+    pub retry_jitter: f64,
+    /// Circuit breaker around verification calls (HTTP only).
+    pub circuit_breaker: CircuitBreakerConfig,
+    /// Bearer token for the ML service, if it requires one.
+    pub auth_token: Option<String>,
+    /// gRPC endpoint and TLS settings, used when `protocol` is `grpc`.
+    pub grpc: GrpcClientConfig,
+    /// Detector model, used when `protocol` is `local`.
+    pub local: LocalModelConfig,
+}
+
+impl MlClientConfig {
+    /// Returns the [`TransportConfig`] for the ML client's HTTP transport.
+    pub fn transport(&self) -> TransportConfig {
+        TransportConfig {
+            timeout: self.timeout,
+            max_retries: self.max_retries,
+            retry_backoff: self.retry_backoff,
+            max_backoff: self.retry_max_backoff,
+            jitter: self.retry_jitter,
+            bearer_token: self.auth_token.clone(),
+        }
+    }
 }
 
 impl Default for MlClientConfig {
     fn default() -> Self {
         Self {
+            protocol: MlProtocol::Http,
             base_url: "http://127.0.0.1:8080".to_string(),
             timeout: Duration::from_secs(2),
+            max_retries: 2,
+            retry_backoff: Duration::from_millis(100),
+            retry_max_backoff: Duration::from_secs(2),
+            retry_jitter: 0.2,
+            circuit_breaker: CircuitBreakerConfig::default(),
+            auth_token: None,
+            grpc: GrpcClientConfig::default(),
+            local: LocalModelConfig::default(),
         }
     }
 }
 
 /// Configuration for the Prometheus metrics exporter.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
 pub struct MetricsConfig {
-    /// Whether to run a `/metrics` HTTP exporter.
+    /// Whether metrics are exported at all.
     pub enabled: bool,
-    /// Address to bind the metrics HTTP server to.
-    pub listen_addr: SocketAddr,
+    /// Whether metrics are scraped from a `/metrics` HTTP exporter
+    /// (`pull`) or pushed to the collector in `push`.
+    pub mode: MetricsMode,
+    /// Addresses to bind the metrics HTTP server to, each plain or TLS.
+    /// Used in `pull` mode.
+    pub listeners: Vec<ListenerConfig>,
+    /// Collector to push to in `push` mode.
+    pub push: PushConfig,
+    /// Export of tracing spans to an OpenTelemetry collector.
+    pub otlp: OtlpConfig,
+    /// Prefix of every metric name; empty for none.
+    pub namespace: String,
+    /// Labels added to every metric, e.g. `node_id` or `network`, so
+    /// several nodes can share one Prometheus.
+    pub const_labels: HashMap<String, String>,
+    /// Histogram bucket overrides.
+    pub buckets: HistogramBuckets,
 }
 
 impl Default for MetricsConfig {
@@ -57,7 +415,13 @@ impl Default for MetricsConfig {
             .expect("hard-coded metrics listen address should parse");
         Self {
             enabled: true,
-            listen_addr: addr,
+            mode: MetricsMode::Pull,
+            listeners: vec![ListenerConfig::plain(addr)],
+            push: PushConfig::default(),
+            otlp: OtlpConfig::default(),
+            namespace: "chain".to_string(),
+            const_labels: HashMap::new(),
+            buckets: HistogramBuckets::default(),
         }
     }
 }
@@ -69,11 +433,1023 @@ impl Default for MetricsConfig {
 /// - consensus tuning (`consensus`),
 /// - persistent storage (`storage`),
 /// - ML verification client (`ml_client`),
-/// - Prometheus metrics exporter (`metrics`).
-#[derive(Clone, Debug, Default)]
+/// - ML block validation and outage handling (`ml`),
+/// - Prometheus metrics exporter (`metrics`),
+/// - stale fork garbage collection (`gc`),
+/// - consensus invariant checks (`invariants`),
+/// - block producer watchdog (`watchdog`),
+/// - ML decision log (`decision_log`),
+/// - transaction mempool limits (`mempool`),
+/// - peer-to-peer message encoding (`network`),
+/// - stateless transaction checks (`tx_validity`),
+/// - proposer scheduling validator set (`validators`),
+/// - genesis specification (`genesis`),
+/// - the node's encrypted key file (`node_key_path`).
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
 pub struct ChainConfig {
     pub consensus: ConsensusConfig,
-    pub storage: RocksDbConfig,
+    pub storage: StorageConfig,
     pub ml_client: MlClientConfig,
+    pub ml: MlConfig,
     pub metrics: MetricsConfig,
+    pub gc: GcConfig,
+    pub invariants: InvariantConfig,
+    pub watchdog: WatchdogConfig,
+    pub decision_log: DecisionLogConfig,
+    pub mempool: MempoolConfig,
+    pub network: NetworkConfig,
+    pub tx_validity: TxValidityConfig,
+    pub validators: ValidatorSetConfig,
+    pub genesis: GenesisConfig,
+    /// Encrypted key file holding the node's ML-DSA key pair, which
+    /// determines its proposer account. Created on first start if missing;
+    /// the password comes from
+    /// [`NODE_KEY_PASSWORD_ENV`](keystore::NODE_KEY_PASSWORD_ENV). Unset by
+    /// default, in which case the node uses a fixed demo identity.
+    pub node_key_path: Option<String>,
+}
+
+impl ChainConfig {
+    /// Loads and validates a configuration file (TOML or YAML, chosen by
+    /// extension). Missing sections and fields take their default values.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, ConfigError> {
+        let cfg: Self = parse_config_file(path.as_ref())?;
+        cfg.validate()?;
+        Ok(cfg)
+    }
+
+    /// Builds a configuration from the defaults overridden by
+    /// `CHAIN_<SECTION>_<FIELD>` environment variables, and validates it.
+    pub fn from_env() -> Result<Self, ConfigError> {
+        let mut cfg = Self::default();
+        cfg.apply_env_overrides()?;
+        cfg.validate()?;
+        Ok(cfg)
+    }
+
+    /// Resolves the configuration a node binary should run with.
+    ///
+    /// Starts from `path` if given (otherwise from the defaults), applies
+    /// environment variable overrides on top, and validates the result.
+    pub fn load(path: Option<&Path>) -> Result<Self, ConfigError> {
+        let mut cfg = match path {
+            Some(path) => parse_config_file(path)?,
+            None => Self::default(),
+        };
+        cfg.apply_env_overrides()?;
+        cfg.validate()?;
+        Ok(cfg)
+    }
+
+    /// Applies overrides from the process environment.
+    ///
+    /// See [`apply_env_with`](Self::apply_env_with) for the variable names.
+    pub fn apply_env_overrides(&mut self) -> Result<(), ConfigError> {
+        self.apply_env_with(|var| std::env::var(var).ok())
+    }
+
+    /// Applies overrides from `lookup`, which maps a variable name to its
+    /// value.
+    ///
+    /// Every scalar field can be overridden with a variable named
+    /// `CHAIN_<SECTION>_<FIELD>` in upper case, using the config file field
+    /// names, e.g. `CHAIN_CONSENSUS_BLOCK_TIME_SECS`,
+    /// `CHAIN_STORAGE_PATH`, or `CHAIN_ML_CLIENT_RETRY_BACKOFF_MS`. An empty
+    /// `CHAIN_ML_CLIENT_AUTH_TOKEN`, `CHAIN_ML_CLIENT_GRPC_TLS_CA_CERT` or
+    /// `CHAIN_METRICS_PUSH_AUTH_TOKEN` clears the value. `CHAIN_METRICS_LISTENERS` takes a comma-separated
+    /// list of addresses. gRPC client certificates, local detector
+    /// thresholds, listener TLS settings, metric constant labels and
+    /// histogram buckets, genesis accounts and artefacts, and validator
+    /// accounts can only be set from a file.
+    pub fn apply_env_with(
+        &mut self,
+        lookup: impl Fn(&str) -> Option<String>,
+    ) -> Result<(), ConfigError> {
+        let consensus = &mut self.consensus;
+        env_override(
+            &lookup,
+            "CHAIN_CONSENSUS_BLOCK_TIME_SECS",
+            &mut consensus.block_time_secs,
+        )?;
+        env_override(
+            &lookup,
+            "CHAIN_CONSENSUS_MAX_BLOCK_TXS",
+            &mut consensus.max_block_txs,
+        )?;
+        env_override(
+            &lookup,
+            "CHAIN_CONSENSUS_MAX_BLOCK_SIZE_BYTES",
+            &mut consensus.max_block_size_bytes,
+        )?;
+        env_override(
+            &lookup,
+            "CHAIN_CONSENSUS_ALLOW_EMPTY_BLOCKS",
+            &mut consensus.allow_empty_blocks,
+        )?;
+        env_override(
+            &lookup,
+            "CHAIN_CONSENSUS_ORPHAN_BUFFER_SIZE",
+            &mut consensus.orphan_buffer_size,
+        )?;
+        env_override(
+            &lookup,
+            "CHAIN_CONSENSUS_SLOT_TIMING",
+            &mut consensus.slot_timing,
+        )?;
+        env_override(
+            &lookup,
+            "CHAIN_CONSENSUS_MONOTONIC_TIMESTAMPS",
+            &mut consensus.monotonic_timestamps,
+        )?;
+        env_override(
+            &lookup,
+            "CHAIN_CONSENSUS_MAX_FUTURE_DRIFT_SECS",
+            &mut consensus.max_future_drift_secs,
+        )?;
+
+        env_override(&lookup, "CHAIN_STORAGE_BACKEND", &mut self.storage.backend)?;
+        env_override(&lookup, "CHAIN_STORAGE_PATH", &mut self.storage.path)?;
+        env_override(
+            &lookup,
+            "CHAIN_STORAGE_CREATE_IF_MISSING",
+            &mut self.storage.create_if_missing,
+        )?;
+        let pruning = &mut self.storage.pruning;
+        env_override(
+            &lookup,
+            "CHAIN_STORAGE_PRUNING_KEEP_BLOCKS",
+            &mut pruning.keep_blocks,
+        )?;
+        env_override(
+            &lookup,
+            "CHAIN_STORAGE_PRUNING_KEEP_DAYS",
+            &mut pruning.keep_days,
+        )?;
+        env_override(
+            &lookup,
+            "CHAIN_STORAGE_PRUNING_FINALITY_DEPTH",
+            &mut pruning.finality_depth,
+        )?;
+        env_override(
+            &lookup,
+            "CHAIN_STORAGE_PRUNING_INTERVAL_SECS",
+            &mut pruning.interval_secs,
+        )?;
+        env_override(
+            &lookup,
+            "CHAIN_STORAGE_METRICS_ENABLED",
+            &mut self.storage.metrics.enabled,
+        )?;
+        env_override(
+            &lookup,
+            "CHAIN_STORAGE_METRICS_INTERVAL_SECS",
+            &mut self.storage.metrics.interval_secs,
+        )?;
+
+        let ml = &mut self.ml_client;
+        env_override(&lookup, "CHAIN_ML_CLIENT_PROTOCOL", &mut ml.protocol)?;
+        env_override(&lookup, "CHAIN_ML_CLIENT_BASE_URL", &mut ml.base_url)?;
+        let mut timeout_secs = ml.timeout.as_secs();
+        env_override(&lookup, "CHAIN_ML_CLIENT_TIMEOUT_SECS", &mut timeout_secs)?;
+        ml.timeout = Duration::from_secs(timeout_secs);
+        env_override(&lookup, "CHAIN_ML_CLIENT_MAX_RETRIES", &mut ml.max_retries)?;
+        let mut backoff_ms = u64::try_from(ml.retry_backoff.as_millis()).unwrap_or(u64::MAX);
+        env_override(&lookup, "CHAIN_ML_CLIENT_RETRY_BACKOFF_MS", &mut backoff_ms)?;
+        ml.retry_backoff = Duration::from_millis(backoff_ms);
+        let mut max_backoff_ms =
+            u64::try_from(ml.retry_max_backoff.as_millis()).unwrap_or(u64::MAX);
+        env_override(
+            &lookup,
+            "CHAIN_ML_CLIENT_RETRY_MAX_BACKOFF_MS",
+            &mut max_backoff_ms,
+        )?;
+        ml.retry_max_backoff = Duration::from_millis(max_backoff_ms);
+        env_override(
+            &lookup,
+            "CHAIN_ML_CLIENT_RETRY_JITTER",
+            &mut ml.retry_jitter,
+        )?;
+        env_override(
+            &lookup,
+            "CHAIN_ML_CLIENT_CIRCUIT_BREAKER_FAILURE_THRESHOLD",
+            &mut ml.circuit_breaker.failure_threshold,
+        )?;
+        env_override(
+            &lookup,
+            "CHAIN_ML_CLIENT_CIRCUIT_BREAKER_OPEN_SECS",
+            &mut ml.circuit_breaker.open_secs,
+        )?;
+        if let Some(token) = lookup("CHAIN_ML_CLIENT_AUTH_TOKEN") {
+            ml.auth_token = Some(token).filter(|t| !t.is_empty());
+        }
+        env_override(
+            &lookup,
+            "CHAIN_ML_CLIENT_GRPC_ENDPOINT",
+            &mut ml.grpc.endpoint,
+        )?;
+        if let Some(path) = lookup("CHAIN_ML_CLIENT_GRPC_TLS_CA_CERT") {
+            ml.grpc.tls.ca_cert = Some(PathBuf::from(path)).filter(|p| !p.as_os_str().is_empty());
+        }
+        if let Some(path) = lookup("CHAIN_ML_CLIENT_LOCAL_MODEL_PATH") {
+            ml.local.model_path = PathBuf::from(path);
+        }
+
+        let ml = &mut self.ml;
+        env_override(
+            &lookup,
+            "CHAIN_ML_MAX_ARTEFACTS_PER_BLOCK",
+            &mut ml.max_artefacts_per_block,
+        )?;
+        env_override(&lookup, "CHAIN_ML_MAX_CONCURRENCY", &mut ml.max_concurrency)?;
+        env_override(&lookup, "CHAIN_ML_PROFILE_BOUNDS", &mut ml.profile_bounds)?;
+        env_override(&lookup, "CHAIN_ML_OUTAGE_POLICY", &mut ml.outage_policy)?;
+        env_override(
+            &lookup,
+            "CHAIN_ML_VERIFICATION_BUDGET_MS",
+            &mut ml.verification_budget_ms,
+        )?;
+        env_override(
+            &lookup,
+            "CHAIN_ML_REVALIDATION_QUEUE_SIZE",
+            &mut ml.revalidation.queue_size,
+        )?;
+        env_override(
+            &lookup,
+            "CHAIN_ML_REVALIDATION_INTERVAL_SECS",
+            &mut ml.revalidation.interval_secs,
+        )?;
+        env_override(
+            &lookup,
+            "CHAIN_ML_REVALIDATION_MAX_ATTEMPTS",
+            &mut ml.revalidation.max_attempts,
+        )?;
+
+        env_override(&lookup, "CHAIN_METRICS_ENABLED", &mut self.metrics.enabled)?;
+        env_override(&lookup, "CHAIN_METRICS_MODE", &mut self.metrics.mode)?;
+        env_listeners_override(
+            &lookup,
+            "CHAIN_METRICS_LISTENERS",
+            &mut self.metrics.listeners,
+        )?;
+        let push = &mut self.metrics.push;
+        env_override(&lookup, "CHAIN_METRICS_PUSH_TARGET", &mut push.target)?;
+        env_override(&lookup, "CHAIN_METRICS_PUSH_URL", &mut push.url)?;
+        env_override(&lookup, "CHAIN_METRICS_PUSH_JOB", &mut push.job)?;
+        env_override(&lookup, "CHAIN_METRICS_PUSH_INSTANCE", &mut push.instance)?;
+        env_override(
+            &lookup,
+            "CHAIN_METRICS_PUSH_INTERVAL_SECS",
+            &mut push.interval_secs,
+        )?;
+        env_override(
+            &lookup,
+            "CHAIN_METRICS_PUSH_TIMEOUT_MS",
+            &mut push.timeout_ms,
+        )?;
+        if let Some(token) = lookup("CHAIN_METRICS_PUSH_AUTH_TOKEN") {
+            push.auth_token = Some(token).filter(|t| !t.is_empty());
+        }
+        env_override(
+            &lookup,
+            "CHAIN_METRICS_OTLP_ENABLED",
+            &mut self.metrics.otlp.enabled,
+        )?;
+        env_override(
+            &lookup,
+            "CHAIN_METRICS_OTLP_ENDPOINT",
+            &mut self.metrics.otlp.endpoint,
+        )?;
+        env_override(
+            &lookup,
+            "CHAIN_METRICS_OTLP_SERVICE_NAME",
+            &mut self.metrics.otlp.service_name,
+        )?;
+        env_override(
+            &lookup,
+            "CHAIN_METRICS_OTLP_SAMPLE_RATIO",
+            &mut self.metrics.otlp.sample_ratio,
+        )?;
+        env_override(
+            &lookup,
+            "CHAIN_METRICS_NAMESPACE",
+            &mut self.metrics.namespace,
+        )?;
+
+        env_override(&lookup, "CHAIN_GC_ENABLED", &mut self.gc.enabled)?;
+        env_override(&lookup, "CHAIN_GC_DEPTH", &mut self.gc.depth)?;
+        env_override(
+            &lookup,
+            "CHAIN_GC_INTERVAL_SECS",
+            &mut self.gc.interval_secs,
+        )?;
+        env_override(&lookup, "CHAIN_GC_ARCHIVE", &mut self.gc.archive)?;
+
+        env_override(
+            &lookup,
+            "CHAIN_INVARIANTS_ENABLED",
+            &mut self.invariants.enabled,
+        )?;
+        env_override(
+            &lookup,
+            "CHAIN_INVARIANTS_FINALITY_DEPTH",
+            &mut self.invariants.finality_depth,
+        )?;
+
+        env_override(
+            &lookup,
+            "CHAIN_WATCHDOG_MAX_ML_FAILURES",
+            &mut self.watchdog.max_ml_failures,
+        )?;
+        env_override(
+            &lookup,
+            "CHAIN_WATCHDOG_PROBE_INTERVAL_SECS",
+            &mut self.watchdog.probe_interval_secs,
+        )?;
+
+        env_override(
+            &lookup,
+            "CHAIN_DECISION_LOG_ENABLED",
+            &mut self.decision_log.enabled,
+        )?;
+
+        let mempool = &mut self.mempool;
+        env_override(&lookup, "CHAIN_MEMPOOL_MAX_TXS", &mut mempool.max_txs)?;
+        env_override(&lookup, "CHAIN_MEMPOOL_MAX_BYTES", &mut mempool.max_bytes)?;
+        env_override(
+            &lookup,
+            "CHAIN_MEMPOOL_MAX_TXS_PER_ACCOUNT",
+            &mut mempool.max_txs_per_account,
+        )?;
+
+        let network = &mut self.network;
+        env_override(
+            &lookup,
+            "CHAIN_NETWORK_COMPRESSION",
+            &mut network.compression,
+        )?;
+        env_override(
+            &lookup,
+            "CHAIN_NETWORK_COMPRESSION_MIN_BYTES",
+            &mut network.compression_min_bytes,
+        )?;
+        env_override(
+            &lookup,
+            "CHAIN_NETWORK_COMPRESSION_LEVEL",
+            &mut network.compression_level,
+        )?;
+        env_override(
+            &lookup,
+            "CHAIN_NETWORK_MAX_MESSAGE_BYTES",
+            &mut network.max_message_bytes,
+        )?;
+        env_override(
+            &lookup,
+            "CHAIN_NETWORK_SYNC_HEADER_BATCH",
+            &mut network.sync.header_batch,
+        )?;
+        env_override(
+            &lookup,
+            "CHAIN_NETWORK_SYNC_PARALLEL_BODIES",
+            &mut network.sync.parallel_bodies,
+        )?;
+        env_override(
+            &lookup,
+            "CHAIN_NETWORK_P2P_ENABLED",
+            &mut network.p2p.enabled,
+        )?;
+        env_list_override(
+            &lookup,
+            "CHAIN_NETWORK_P2P_LISTEN_ADDRS",
+            &mut network.p2p.listen_addrs,
+        );
+        env_list_override(
+            &lookup,
+            "CHAIN_NETWORK_P2P_BOOTSTRAP_PEERS",
+            &mut network.p2p.bootstrap_peers,
+        );
+
+        let tx_validity = &mut self.tx_validity;
+        env_override(
+            &lookup,
+            "CHAIN_TX_VALIDITY_MIN_FEE",
+            &mut tx_validity.min_fee,
+        )?;
+        env_override(
+            &lookup,
+            "CHAIN_TX_VALIDITY_MAX_FEE",
+            &mut tx_validity.max_fee,
+        )?;
+        env_override(
+            &lookup,
+            "CHAIN_TX_VALIDITY_REQUIRE_SIGNATURES",
+            &mut tx_validity.require_signatures,
+        )?;
+
+        env_override(
+            &lookup,
+            "CHAIN_VALIDATORS_ELECTION",
+            &mut self.validators.election,
+        )?;
+
+        env_override(
+            &lookup,
+            "CHAIN_GENESIS_CHAIN_ID",
+            &mut self.genesis.chain_id,
+        )?;
+        env_override(
+            &lookup,
+            "CHAIN_GENESIS_TIMESTAMP",
+            &mut self.genesis.timestamp,
+        )?;
+
+        if let Some(path) = lookup("CHAIN_NODE_KEY_PATH") {
+            self.node_key_path = Some(path).filter(|p| !p.is_empty());
+        }
+
+        Ok(())
+    }
+
+    /// Loads the node's key pair from [`node_key_path`](Self::node_key_path),
+    /// generating and writing a new one if the file does not exist yet.
+    ///
+    /// Returns `Ok(None)` when no key file is configured, and otherwise the
+    /// key pair and whether it was newly generated. The password is read
+    /// from the [`NODE_KEY_PASSWORD_ENV`](keystore::NODE_KEY_PASSWORD_ENV)
+    /// environment variable.
+    pub fn node_keypair(&self) -> Result<Option<(Keypair, bool)>, KeystoreError> {
+        let Some(path) = &self.node_key_path else {
+            return Ok(None);
+        };
+        let password = keystore::node_key_password()?;
+        keystore::load_or_generate(Path::new(path), password.as_bytes()).map(Some)
+    }
+
+    /// Checks that the configuration is usable, reporting every problem
+    /// found rather than just the first.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let mut problems = Vec::new();
+
+        if self.consensus.block_time_secs == 0 {
+            problems.push("consensus.block_time_secs must be greater than 0".to_string());
+        }
+        if self.consensus.max_block_txs == 0 {
+            problems.push("consensus.max_block_txs must be greater than 0".to_string());
+        }
+        if self.consensus.max_block_size_bytes == 0 {
+            problems.push("consensus.max_block_size_bytes must be greater than 0".to_string());
+        }
+        if self.storage.path.trim().is_empty() {
+            problems.push("storage.path must not be empty".to_string());
+        }
+        if self
+            .node_key_path
+            .as_ref()
+            .is_some_and(|path| path.trim().is_empty())
+        {
+            problems.push("node_key_path must not be empty when set".to_string());
+        }
+        if self.storage.backend == StorageBackend::Sqlite && !cfg!(feature = "sqlite") {
+            problems.push("storage.backend \"sqlite\" requires the sqlite feature".to_string());
+        }
+        let pruning = &self.storage.pruning;
+        if pruning.enabled() && pruning.finality_depth == 0 {
+            problems.push(
+                "storage.pruning.finality_depth must be greater than 0 when pruning is enabled"
+                    .to_string(),
+            );
+        }
+        if pruning.enabled() && pruning.interval_secs == 0 {
+            problems.push(
+                "storage.pruning.interval_secs must be greater than 0 when pruning is enabled"
+                    .to_string(),
+            );
+        }
+        if self.storage.metrics.enabled && self.storage.metrics.interval_secs == 0 {
+            problems.push(
+                "storage.metrics.interval_secs must be greater than 0 when storage metrics are enabled"
+                    .to_string(),
+            );
+        }
+        let ml = &self.ml_client;
+        match ml.protocol {
+            MlProtocol::Http => {
+                let base_url = &ml.base_url;
+                if !(base_url.starts_with("http://") || base_url.starts_with("https://")) {
+                    problems.push(format!(
+                        "ml_client.base_url must start with http:// or https:// (got {base_url:?})"
+                    ));
+                }
+            }
+            MlProtocol::Grpc => {
+                let endpoint = &ml.grpc.endpoint;
+                if endpoint.starts_with("http://") {
+                    if ml.grpc.tls.is_set() {
+                        problems
+                            .push("ml_client.grpc.tls requires an https:// endpoint".to_string());
+                    }
+                } else if !endpoint.starts_with("https://") {
+                    problems.push(format!(
+                        "ml_client.grpc.endpoint must start with http:// or https:// (got {endpoint:?})"
+                    ));
+                }
+                if ml.grpc.tls.client_cert.is_some() != ml.grpc.tls.client_key.is_some() {
+                    problems.push(
+                        "ml_client.grpc.tls.client_cert and client_key must be set together"
+                            .to_string(),
+                    );
+                }
+            }
+            MlProtocol::Local => {
+                if ml.local.model_path.as_os_str().is_empty() {
+                    problems.push(
+                        "ml_client.local.model_path must be set when protocol is \"local\""
+                            .to_string(),
+                    );
+                }
+                if !cfg!(feature = "local-onnx") {
+                    problems.push(
+                        "ml_client.protocol \"local\" requires the local-onnx feature".to_string(),
+                    );
+                }
+            }
+        }
+        if self.ml_client.timeout.is_zero() {
+            problems.push("ml_client.timeout_secs must be greater than 0".to_string());
+        }
+        if ml.retry_max_backoff < ml.retry_backoff {
+            problems.push(
+                "ml_client.retry_max_backoff_ms must not be less than retry_backoff_ms".to_string(),
+            );
+        }
+        if !(0.0..=1.0).contains(&ml.retry_jitter) {
+            problems.push("ml_client.retry_jitter must be between 0 and 1".to_string());
+        }
+        if ml.circuit_breaker.failure_threshold > 0 && ml.circuit_breaker.open_secs == 0 {
+            problems.push(
+                "ml_client.circuit_breaker.open_secs must be greater than 0 when the breaker is enabled"
+                    .to_string(),
+            );
+        }
+        if self.ml.max_artefacts_per_block == 0 || self.ml.max_concurrency == 0 {
+            problems.push(
+                "ml.max_artefacts_per_block and ml.max_concurrency must be greater than 0"
+                    .to_string(),
+            );
+        }
+        for (scheme, threshold) in &self.ml.score_thresholds {
+            if !(0.0..=1.0).contains(threshold) {
+                problems.push(format!(
+                    "ml.score_thresholds.{scheme} must be between 0 and 1"
+                ));
+            }
+        }
+        for (scheme, bounds) in &self.ml.verdict_bounds {
+            if bounds
+                .min_trigger_acc
+                .is_some_and(|acc| !(0.0..=1.0).contains(&acc))
+            {
+                problems.push(format!(
+                    "ml.verdict_bounds.{scheme}.min_trigger_acc must be between 0 and 1"
+                ));
+            }
+            if bounds.max_feat_dist.is_some_and(|dist| !dist.is_finite()) {
+                problems.push(format!(
+                    "ml.verdict_bounds.{scheme}.max_feat_dist must be finite"
+                ));
+            }
+            if bounds
+                .logit_stat
+                .is_some_and(|r| !(r.min.is_finite() && r.max.is_finite() && r.min <= r.max))
+            {
+                problems.push(format!(
+                    "ml.verdict_bounds.{scheme}.logit_stat must have finite bounds with min <= max"
+                ));
+            }
+        }
+        if self.ml.outage_policy == MlOutagePolicy::DeferToRevalidationQueue
+            && (self.ml.revalidation.queue_size == 0 || self.ml.revalidation.interval_secs == 0)
+        {
+            problems.push(
+                "ml.revalidation.queue_size and ml.revalidation.interval_secs must be greater than 0 when the outage policy is defer_to_revalidation_queue"
+                    .to_string(),
+            );
+        }
+        let pushing = self.metrics.enabled && self.metrics.mode == MetricsMode::Push;
+        if self.metrics.enabled && !pushing && self.metrics.listeners.is_empty() {
+            problems
+                .push("metrics.listeners must not be empty when metrics are enabled".to_string());
+        }
+        if pushing {
+            let push = &self.metrics.push;
+            if push.url.is_empty() {
+                problems.push(
+                    "metrics.push.url must not be empty when metrics.mode is \"push\"".to_string(),
+                );
+            }
+            if push.interval_secs == 0 {
+                problems.push("metrics.push.interval_secs must be greater than 0".to_string());
+            }
+            if push.timeout_ms == 0 {
+                problems.push("metrics.push.timeout_ms must be greater than 0".to_string());
+            }
+        }
+        problems.extend(duplicate_listener_problems(
+            "metrics.listeners",
+            &self.metrics.listeners,
+        ));
+        if self.metrics.otlp.enabled && self.metrics.otlp.endpoint.is_empty() {
+            problems.push(
+                "metrics.otlp.endpoint must not be empty when OTLP export is enabled".to_string(),
+            );
+        }
+        if !(0.0..=1.0).contains(&self.metrics.otlp.sample_ratio) {
+            problems.push("metrics.otlp.sample_ratio must be between 0 and 1".to_string());
+        }
+        if !self.metrics.namespace.is_empty() && !is_metric_ident(&self.metrics.namespace) {
+            problems.push(format!(
+                "metrics.namespace {:?} must consist of letters, digits and underscores and not start with a digit",
+                self.metrics.namespace
+            ));
+        }
+        for name in self.metrics.const_labels.keys() {
+            if !is_metric_ident(name) || name.starts_with("__") {
+                problems.push(format!(
+                    "metrics.const_labels key {name:?} is not a valid Prometheus label name"
+                ));
+            }
+        }
+        for (histogram, buckets) in self.metrics.buckets.overrides() {
+            if buckets.is_empty()
+                || buckets.iter().any(|b| !b.is_finite())
+                || buckets.windows(2).any(|w| w[0] >= w[1])
+            {
+                problems.push(format!(
+                    "metrics.buckets.{histogram} must be a non-empty list of strictly increasing finite numbers"
+                ));
+            }
+        }
+        if self.gc.enabled && !self.gc.archive && self.gc.depth == 0 {
+            problems.push("gc.depth must be greater than 0 when gc is enabled".to_string());
+        }
+        if self.invariants.enabled && self.invariants.finality_depth == 0 {
+            problems.push(
+                "invariants.finality_depth must be greater than 0 when invariants are enabled"
+                    .to_string(),
+            );
+        }
+        if self.watchdog.max_ml_failures > 0 && self.watchdog.probe_interval_secs == 0 {
+            problems.push(
+                "watchdog.probe_interval_secs must be greater than 0 when the watchdog is enabled"
+                    .to_string(),
+            );
+        }
+        if self.mempool.max_txs == 0 || self.mempool.max_bytes == 0 {
+            problems
+                .push("mempool.max_txs and mempool.max_bytes must be greater than 0".to_string());
+        }
+        if self.mempool.max_txs_per_account == 0 {
+            problems.push("mempool.max_txs_per_account must be greater than 0".to_string());
+        }
+        let levels = zstd::compression_level_range();
+        if self.network.compression && !levels.contains(&self.network.compression_level) {
+            problems.push(format!(
+                "network.compression_level must be between {} and {}",
+                levels.start(),
+                levels.end()
+            ));
+        }
+        if self.network.max_message_bytes == 0 {
+            problems.push("network.max_message_bytes must be greater than 0".to_string());
+        }
+        if self.network.sync.header_batch == 0 {
+            problems.push("network.sync.header_batch must be greater than 0".to_string());
+        }
+        if self.network.sync.parallel_bodies == 0 {
+            problems.push("network.sync.parallel_bodies must be greater than 0".to_string());
+        }
+        let p2p = &self.network.p2p;
+        for (field, addrs) in [
+            ("listen_addrs", &p2p.listen_addrs),
+            ("bootstrap_peers", &p2p.bootstrap_peers),
+        ] {
+            for addr in addrs {
+                if let Err(e) = addr.parse::<Multiaddr>() {
+                    problems.push(format!(
+                        "network.p2p.{field} has an invalid address {addr:?}: {e}"
+                    ));
+                }
+            }
+        }
+        if p2p.enabled && p2p.listen_addrs.is_empty() && p2p.bootstrap_peers.is_empty() {
+            problems
+                .push("network.p2p needs listen_addrs or bootstrap_peers when enabled".to_string());
+        }
+        if p2p.request_timeout_secs == 0 {
+            problems.push("network.p2p.request_timeout_secs must be greater than 0".to_string());
+        }
+        if p2p.event_buffer == 0 {
+            problems.push("network.p2p.event_buffer must be greater than 0".to_string());
+        }
+        if self.tx_validity.min_fee > self.tx_validity.max_fee {
+            problems.push("tx_validity.min_fee must not exceed tx_validity.max_fee".to_string());
+        }
+        let accounts = &self.validators.accounts;
+        if (1..accounts.len()).any(|i| accounts[..i].contains(&accounts[i])) {
+            problems.push("validators.accounts must not contain duplicates".to_string());
+        }
+        if self.validators.election == ElectionKind::Vrf
+            && self.validators.vrf_keys.len() != accounts.len()
+        {
+            problems.push(
+                "validators.vrf_keys must list one key per account when validators.election is \"vrf\""
+                    .to_string(),
+            );
+        }
+        if self.genesis.chain_id.trim().is_empty() {
+            problems.push("genesis.chain_id must not be empty".to_string());
+        }
+        if self.genesis.staking.epoch_length == 0 {
+            problems.push("genesis.staking.epoch_length must be greater than 0".to_string());
+        }
+        if self.genesis.staking.slash_percent > 100 {
+            problems.push("genesis.staking.slash_percent must not exceed 100".to_string());
+        }
+        let schemes = &self.genesis.params.schemes;
+        problems.extend(
+            schemes
+                .problems()
+                .into_iter()
+                .map(|p| format!("genesis.params.schemes.{p}")),
+        );
+        for artefact in &self.genesis.artefacts {
+            if let Err(e) = schemes.check(&artefact.evidence) {
+                problems.push(format!(
+                    "genesis.artefacts: {e} (aid {})",
+                    hex::encode(artefact.aid.0.as_bytes())
+                ));
+            }
+        }
+        let wm_profile = &self.genesis.params.wm_profile;
+        if wm_profile.logit_band_low > wm_profile.logit_band_high {
+            problems.push(
+                "genesis.params.wm_profile.logit_band_low must not exceed logit_band_high"
+                    .to_string(),
+            );
+        }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(ConfigError::Invalid(problems))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::consensus::VrfPublicKey;
+    use crate::metrics::PushTarget;
+    use std::collections::HashMap;
+
+    fn write_config(name: &str, contents: &str) -> (tempfile::TempDir, PathBuf) {
+        let dir = tempfile::tempdir().expect("create temp dir");
+        let path = dir.path().join(name);
+        std::fs::write(&path, contents).expect("write config file");
+        (dir, path)
+    }
+
+    #[test]
+    fn devnet_config_file_parses() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("../configs/devnet.toml");
+        let cfg = ChainConfig::from_file(&path).expect("devnet.toml should load");
+
+        assert_eq!(cfg.consensus.max_block_txs, 1000);
+        assert_eq!(cfg.storage.path, "data/devnet-db");
+        assert_eq!(cfg.ml_client.retry_backoff, Duration::from_millis(100));
+        assert_eq!(cfg.genesis.chain_id, "mlsnitch-devnet");
+    }
+
+    #[test]
+    fn toml_and_yaml_files_fill_missing_fields_with_defaults() {
+        let (_dir, toml_path) = write_config(
+            "node.toml",
+            "[consensus]\nblock_time_secs = 9\n\n[ml_client]\ntimeout_secs = 7\n",
+        );
+        let (_dir2, yaml_path) = write_config(
+            "node.yaml",
+            "consensus:\n  block_time_secs: 9\nml_client:\n  timeout_secs: 7\n",
+        );
+
+        for path in [toml_path, yaml_path] {
+            let cfg = ChainConfig::from_file(&path).expect("config should load");
+            assert_eq!(cfg.consensus.block_time_secs, 9);
+            assert_eq!(cfg.consensus.max_block_txs, 10_000);
+            assert_eq!(cfg.ml_client.timeout, Duration::from_secs(7));
+            assert!(cfg.metrics.enabled);
+        }
+    }
+
+    #[test]
+    fn unknown_keys_and_extensions_are_rejected() {
+        let (_dir, path) = write_config("node.toml", "[consensus]\nblock_time = 9\n");
+        let err = ChainConfig::from_file(&path).unwrap_err();
+        assert!(matches!(err, ConfigError::Parse { .. }));
+        assert!(err.to_string().contains("block_time"));
+
+        let (_dir, path) = write_config("node.json", "{}");
+        assert!(matches!(
+            ChainConfig::from_file(&path),
+            Err(ConfigError::UnsupportedFormat(_))
+        ));
+    }
+
+    #[test]
+    fn env_overrides_replace_individual_fields() {
+        let vars: HashMap<&str, &str> = [
+            ("CHAIN_CONSENSUS_BLOCK_TIME_SECS", "3"),
+            ("CHAIN_ML_CLIENT_RETRY_BACKOFF_MS", "250"),
+            ("CHAIN_ML_CLIENT_AUTH_TOKEN", "secret"),
+            ("CHAIN_METRICS_LISTENERS", "127.0.0.1:9999, [::1]:9999"),
+            ("CHAIN_DECISION_LOG_ENABLED", "false"),
+            ("CHAIN_ML_CLIENT_RETRY_JITTER", "0.5"),
+            ("CHAIN_ML_CLIENT_CIRCUIT_BREAKER_FAILURE_THRESHOLD", "0"),
+            ("CHAIN_METRICS_OTLP_ENABLED", "true"),
+            ("CHAIN_METRICS_OTLP_ENDPOINT", "http://collector:4317"),
+            ("CHAIN_STORAGE_PRUNING_KEEP_DAYS", "30"),
+            ("CHAIN_STORAGE_BACKEND", "SQLite"),
+            ("CHAIN_ML_OUTAGE_POLICY", "accept_with_flag"),
+            (
+                "CHAIN_NETWORK_P2P_BOOTSTRAP_PEERS",
+                "/ip4/10.0.0.2/tcp/7600, /dns4/node-3/tcp/7600",
+            ),
+        ]
+        .into_iter()
+        .collect();
+
+        let mut cfg = ChainConfig::default();
+        cfg.apply_env_with(|var| vars.get(var).map(|v| v.to_string()))
+            .expect("overrides should apply");
+
+        assert_eq!(cfg.consensus.block_time_secs, 3);
+        assert_eq!(cfg.ml_client.retry_backoff, Duration::from_millis(250));
+        assert_eq!(cfg.ml_client.auth_token.as_deref(), Some("secret"));
+        assert_eq!(cfg.metrics.listeners.len(), 2);
+        assert_eq!(cfg.metrics.listeners[1].addr.port(), 9999);
+        assert!(cfg.metrics.listeners[1].tls.is_none());
+        assert!(!cfg.decision_log.enabled);
+        assert_eq!(cfg.ml.outage_policy, MlOutagePolicy::AcceptWithFlag);
+        assert_eq!(cfg.ml_client.retry_jitter, 0.5);
+        assert_eq!(cfg.ml_client.circuit_breaker.failure_threshold, 0);
+        assert!(cfg.metrics.otlp.enabled);
+        assert_eq!(cfg.metrics.otlp.endpoint, "http://collector:4317");
+        assert_eq!(cfg.storage.path, StorageConfig::default().path);
+        assert_eq!(cfg.storage.pruning.keep_days, 30);
+        assert_eq!(cfg.storage.backend, StorageBackend::Sqlite);
+        assert_eq!(
+            cfg.network.p2p.bootstrap_peers,
+            ["/ip4/10.0.0.2/tcp/7600", "/dns4/node-3/tcp/7600"]
+        );
+        assert_eq!(cfg.validate().is_ok(), cfg!(feature = "sqlite"));
+    }
+
+    #[test]
+    fn malformed_env_override_names_the_variable() {
+        let mut cfg = ChainConfig::default();
+        let err = cfg
+            .apply_env_with(|var| (var == "CHAIN_GC_DEPTH").then(|| "deep".to_string()))
+            .unwrap_err();
+
+        assert!(err.to_string().contains("CHAIN_GC_DEPTH"));
+    }
+
+    #[test]
+    fn validate_reports_every_problem() {
+        let mut cfg = ChainConfig::default();
+        assert!(cfg.validate().is_ok());
+
+        cfg.consensus.block_time_secs = 0;
+        cfg.ml_client.base_url = "127.0.0.1:8080".to_string();
+        cfg.tx_validity.min_fee = 10;
+        cfg.tx_validity.max_fee = 1;
+        let listener = cfg.metrics.listeners[0].clone();
+        cfg.metrics.listeners.push(listener);
+        cfg.network.compression_level = 99;
+        cfg.validators.election = ElectionKind::Vrf;
+        cfg.validators.vrf_keys.push(VrfPublicKey([0; 32]));
+        cfg.genesis.staking.epoch_length = 0;
+        cfg.genesis.staking.slash_percent = 101;
+        cfg.genesis.params.wm_profile.logit_band_low = 1.0;
+        cfg.network.sync.parallel_bodies = 0;
+        cfg.ml.outage_policy = MlOutagePolicy::DeferToRevalidationQueue;
+        cfg.ml.revalidation.interval_secs = 0;
+        cfg.network.p2p.bootstrap_peers = vec!["10.0.0.2:7600".to_string()];
+
+        match cfg.validate() {
+            Err(ConfigError::Invalid(problems)) => assert_eq!(problems.len(), 12),
+            other => panic!("expected Invalid, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn metrics_overrides_load_and_validate() {
+        let (_dir, path) = write_config(
+            "node.toml",
+            "[metrics]\nnamespace = \"mlsnitch\"\n\n[metrics.const_labels]\nnode_id = \"v1\"\nnetwork = \"devnet\"\n\n[metrics.buckets]\nml_auth_seconds = [0.1, 1.0, 30.0]\n",
+        );
+        let cfg = ChainConfig::from_file(&path).expect("metrics config should load");
+        assert_eq!(cfg.metrics.namespace, "mlsnitch");
+        assert_eq!(cfg.metrics.const_labels["network"], "devnet");
+        assert_eq!(
+            cfg.metrics.buckets.ml_auth_seconds.as_deref(),
+            Some(&[0.1, 1.0, 30.0][..])
+        );
+        assert!(cfg.metrics.buckets.reorg_depth.is_none());
+
+        let mut cfg = ChainConfig::default();
+        cfg.metrics.namespace = "ml-snitch".to_string();
+        cfg.metrics
+            .const_labels
+            .insert("__node".to_string(), "v1".to_string());
+        cfg.metrics.buckets.reorg_depth = Some(vec![1.0, 1.0]);
+        match cfg.validate() {
+            Err(ConfigError::Invalid(problems)) => assert_eq!(problems.len(), 3),
+            other => panic!("expected Invalid, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn metrics_push_mode_loads_and_validates() {
+        let (_dir, path) = write_config(
+            "node.toml",
+            "[metrics]\nmode = \"push\"\nlisteners = []\n\n[metrics.push]\ntarget = \"remote_write\"\nurl = \"http://prometheus:9090/api/v1/write\"\ninstance = \"v1\"\ninterval_secs = 5\n",
+        );
+        let cfg = ChainConfig::from_file(&path).expect("push config should load");
+        assert_eq!(cfg.metrics.mode, MetricsMode::Push);
+        assert_eq!(cfg.metrics.push.target, PushTarget::RemoteWrite);
+        assert_eq!(cfg.metrics.push.instance, "v1");
+        assert_eq!(cfg.metrics.push.interval_secs, 5);
+
+        let mut cfg = ChainConfig::default();
+        cfg.apply_env_with(|var| match var {
+            "CHAIN_METRICS_MODE" => Some("push".to_string()),
+            "CHAIN_METRICS_PUSH_URL" => Some(String::new()),
+            "CHAIN_METRICS_PUSH_INTERVAL_SECS" => Some("0".to_string()),
+            _ => None,
+        })
+        .expect("overrides should apply");
+        match cfg.validate() {
+            Err(ConfigError::Invalid(problems)) => assert_eq!(problems.len(), 2),
+            other => panic!("expected Invalid, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn grpc_client_settings_load_and_validate() {
+        let (_dir, path) = write_config(
+            "node.toml",
+            "[ml_client]\nprotocol = \"grpc\"\n\n[ml_client.grpc]\nendpoint = \"https://ml:50051\"\n\n[ml_client.grpc.tls]\ndomain_name = \"ml.internal\"\n",
+        );
+        let cfg = ChainConfig::from_file(&path).expect("grpc config should load");
+        assert_eq!(cfg.ml_client.protocol, MlProtocol::Grpc);
+        assert_eq!(cfg.ml_client.grpc.endpoint, "https://ml:50051");
+        assert_eq!(
+            cfg.ml_client.grpc.tls.domain_name.as_deref(),
+            Some("ml.internal")
+        );
+
+        let mut cfg = ChainConfig::default();
+        cfg.apply_env_with(|var| match var {
+            "CHAIN_ML_CLIENT_PROTOCOL" => Some("grpc".to_string()),
+            _ => None,
+        })
+        .expect("overrides should apply");
+        cfg.ml_client.grpc.tls.client_cert = Some(PathBuf::from("client.pem"));
+
+        match cfg.validate() {
+            Err(ConfigError::Invalid(problems)) => assert_eq!(problems.len(), 2),
+            other => panic!("expected Invalid, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn local_client_settings_load_and_validate() {
+        let (_dir, path) = write_config(
+            "node.toml",
+            "[ml_client]\nprotocol = \"local\"\n\n[ml_client.local]\nmodel_path = \"models/detector.onnx\"\n\n[ml_client.local.thresholds]\nwm-test-1 = 0.8\n",
+        );
+        let cfg: ChainConfig = parse_config_file(&path).expect("local config should parse");
+        assert_eq!(cfg.ml_client.protocol, MlProtocol::Local);
+        assert_eq!(
+            cfg.ml_client.local.model_path,
+            PathBuf::from("models/detector.onnx")
+        );
+        assert_eq!(cfg.ml_client.local.thresholds.get("wm-test-1"), Some(&0.8));
+        assert_eq!(cfg.validate().is_ok(), cfg!(feature = "local-onnx"));
+
+        let mut cfg = ChainConfig::default();
+        cfg.ml_client.protocol = MlProtocol::Local;
+        assert!(matches!(cfg.validate(), Err(ConfigError::Invalid(_))));
+    }
 }
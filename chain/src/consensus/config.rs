@@ -1,8 +1,11 @@
+use serde::Deserialize;
+
 /// Consensus configuration parameters.
 ///
 /// This includes both protocol-level knobs (e.g. target block time) and
 /// implementation-level limits (e.g. maximum transactions per block).
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
 pub struct ConsensusConfig {
     /// Target block time in seconds for the simulator.
     pub block_time_secs: u64,
@@ -12,6 +15,20 @@ pub struct ConsensusConfig {
     pub max_block_size_bytes: usize,
     /// Whether to allow empty blocks when the transaction pool is empty.
     pub allow_empty_blocks: bool,
+    /// Maximum number of blocks with an unknown parent kept until the
+    /// parent arrives. `0` disables the orphan buffer and such blocks are
+    /// rejected outright.
+    pub orphan_buffer_size: usize,
+    /// Slot-based timing: block timestamps must equal
+    /// `genesis_time + height * block_time_secs`, and locally proposed
+    /// blocks are stamped with their slot time instead of the wall clock.
+    pub slot_timing: bool,
+    /// Non-genesis blocks must carry a timestamp strictly greater than
+    /// their parent's.
+    pub monotonic_timestamps: bool,
+    /// How far, in seconds, a block timestamp may run ahead of the
+    /// validator's clock. `0` disables the check.
+    pub max_future_drift_secs: u64,
 }
 
 impl Default for ConsensusConfig {
@@ -21,6 +38,10 @@ impl Default for ConsensusConfig {
             max_block_txs: 10_000,
             max_block_size_bytes: 1_000_000,
             allow_empty_blocks: true,
+            orphan_buffer_size: 0,
+            slot_timing: false,
+            monotonic_timestamps: true,
+            max_future_drift_secs: 15,
         }
     }
 }
@@ -37,6 +58,10 @@ mod tests {
         assert_eq!(cfg.max_block_txs, 10_000);
         assert_eq!(cfg.max_block_size_bytes, 1_000_000);
         assert!(cfg.allow_empty_blocks);
+        assert_eq!(cfg.orphan_buffer_size, 0);
+        assert!(!cfg.slot_timing);
+        assert!(cfg.monotonic_timestamps);
+        assert_eq!(cfg.max_future_drift_secs, 15);
     }
 
     #[test]
@@ -46,12 +71,20 @@ mod tests {
             max_block_txs: 1_234,
             max_block_size_bytes: 512_000,
             allow_empty_blocks: false,
+            orphan_buffer_size: 16,
+            slot_timing: true,
+            monotonic_timestamps: false,
+            max_future_drift_secs: 60,
         };
 
         assert_eq!(cfg.block_time_secs, 42);
         assert_eq!(cfg.max_block_txs, 1_234);
         assert_eq!(cfg.max_block_size_bytes, 512_000);
         assert!(!cfg.allow_empty_blocks);
+        assert_eq!(cfg.orphan_buffer_size, 16);
+        assert!(cfg.slot_timing);
+        assert!(!cfg.monotonic_timestamps);
+        assert_eq!(cfg.max_future_drift_secs, 60);
     }
 
     #[test]
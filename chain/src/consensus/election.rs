@@ -0,0 +1,409 @@
+//! VRF-based leader election, and the choice between schedules.
+//!
+//! Round-robin scheduling makes every slot leader public in advance. With
+//! VRF election, each validator instead evaluates a verifiable random
+//! function (schnorrkel / sr25519) on the slot number and the network's
+//! seed. The VRF output is a lottery ticket: the validator may propose if
+//! its ticket falls below its share of the ticket space, so one leader is
+//! expected per slot and nobody can tell in advance who it will be.
+//!
+//! Every validator weighs the same for now, so each wins a slot with
+//! probability `1 / validators`. Some slots have no leader and some have
+//! several; fork choice settles the latter like any other fork.
+//!
+//! The winner attaches its VRF output and proof to the block as a
+//! [`PosProof`], and [`ProposerValidity`](crate::validation::ProposerValidity)
+//! checks them against the VRF public key the validator registered in
+//! [`ValidatorSetConfig::vrf_keys`].
+//!
+//! [`LeaderElection`] wraps the schedule picked by
+//! [`ValidatorSetConfig::election`]: round-robin, this VRF lottery, or
+//! stake-weighted selection ([`StakeWeightedSelector`]).
+
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+use schnorrkel::context::SigningTranscript;
+use schnorrkel::vrf::{VRFInOut, VRFPreOut, VRFProof};
+use schnorrkel::{ExpansionMode, Keypair, MiniSecretKey, PublicKey};
+use serde::{Deserialize, Serialize};
+
+use crate::types::{AccountId, Hash256, Header, PosProof};
+
+use super::schedule::{ProposerSelector, RoundRobinSelector, ValidatorSet, ValidatorSetConfig};
+use super::stake::{StakeTable, StakeWeightedSelector};
+
+/// Signing context of slot VRFs.
+const VRF_CONTEXT: &[u8] = b"mlsnitch-slot-vrf";
+
+/// Context the lottery ticket is derived under from the VRF output.
+const TICKET_CONTEXT: &[u8] = b"mlsnitch-slot-ticket";
+
+/// How slot leaders are chosen.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ElectionKind {
+    /// Slot `s` belongs to validator `s % len` ([`RoundRobinSelector`]).
+    #[default]
+    RoundRobin,
+    /// Private VRF lottery per slot ([`VrfSelector`]).
+    Vrf,
+    /// Leader drawn in proportion to active stake
+    /// ([`StakeWeightedSelector`]).
+    Stake,
+}
+
+impl FromStr for ElectionKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "round_robin" => Ok(ElectionKind::RoundRobin),
+            "vrf" => Ok(ElectionKind::Vrf),
+            "stake" => Ok(ElectionKind::Stake),
+            _ => Err("expected \"round_robin\", \"vrf\" or \"stake\"".to_string()),
+        }
+    }
+}
+
+/// A validator's VRF public key (a compressed Ristretto point).
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub struct VrfPublicKey(pub [u8; 32]);
+
+/// A validator's VRF key pair, used to prove slot eligibility.
+#[derive(Clone)]
+pub struct VrfKeypair(Keypair);
+
+impl VrfKeypair {
+    /// Derives a key pair from a 32-byte secret seed.
+    pub fn from_seed(seed: &[u8; 32]) -> Self {
+        let secret =
+            MiniSecretKey::from_bytes(seed).expect("a 32-byte seed is a valid mini secret key");
+        Self(secret.expand_to_keypair(ExpansionMode::Ed25519))
+    }
+
+    /// Returns the public key to register in the validator set.
+    pub fn public(&self) -> VrfPublicKey {
+        VrfPublicKey(self.0.public.to_bytes())
+    }
+
+    /// Evaluates the VRF for `slot` under the network `seed`, returning
+    /// the output and proof whether or not they win the slot.
+    pub fn prove(&self, seed: &Hash256, slot: u64) -> PosProof {
+        let (inout, proof, _) = self.0.vrf_sign(slot_transcript(seed, slot));
+        PosProof {
+            output: inout.to_preout().to_bytes(),
+            proof: proof.to_bytes().to_vec(),
+        }
+    }
+
+    /// Returns this key's lottery ticket for `slot`, without a proof.
+    fn ticket(&self, seed: &Hash256, slot: u64) -> u64 {
+        ticket(&self.0.vrf_create_hash(slot_transcript(seed, slot)))
+    }
+}
+
+impl fmt::Debug for VrfKeypair {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("VrfKeypair")
+            .field("public", &hex::encode(self.public().0))
+            .finish_non_exhaustive()
+    }
+}
+
+/// Builds the VRF input for `slot`.
+fn slot_transcript(seed: &Hash256, slot: u64) -> impl SigningTranscript {
+    let mut input = Vec::with_capacity(40);
+    input.extend_from_slice(seed.as_bytes());
+    input.extend_from_slice(&slot.to_be_bytes());
+    schnorrkel::signing_context(VRF_CONTEXT).bytes(&input)
+}
+
+/// Derives the lottery ticket from a VRF input/output pair.
+fn ticket(inout: &VRFInOut) -> u64 {
+    u64::from_be_bytes(inout.make_bytes::<[u8; 8]>(TICKET_CONTEXT))
+}
+
+/// Returns the largest winning ticket for a validator holding `weight`
+/// out of `total`.
+fn threshold(weight: u64, total: u64) -> u64 {
+    (u128::from(u64::MAX) * u128::from(weight) / u128::from(total.max(1))) as u64
+}
+
+/// VRF lottery schedule.
+///
+/// An empty validator set disables scheduling, as for
+/// [`RoundRobinSelector`].
+#[derive(Clone, Debug)]
+pub struct VrfSelector {
+    validators: ValidatorSet,
+    keys: HashMap<AccountId, VrfPublicKey>,
+    block_time_secs: u64,
+    seed: Hash256,
+}
+
+impl VrfSelector {
+    /// Creates a lottery over `validators`, whose VRF public keys are in
+    /// `keys`, with slots of `block_time_secs`.
+    ///
+    /// `seed` separates networks, so that a VRF output from one chain says
+    /// nothing about another; nodes use the genesis block hash.
+    pub fn new(
+        validators: ValidatorSet,
+        keys: impl IntoIterator<Item = (AccountId, VrfPublicKey)>,
+        block_time_secs: u64,
+        seed: Hash256,
+    ) -> Self {
+        Self {
+            validators,
+            keys: keys.into_iter().collect(),
+            block_time_secs,
+            seed,
+        }
+    }
+
+    /// Returns the validator set taking part in the lottery.
+    pub fn validators(&self) -> &ValidatorSet {
+        &self.validators
+    }
+
+    /// Returns the network seed mixed into every VRF input.
+    pub fn seed(&self) -> &Hash256 {
+        &self.seed
+    }
+
+    /// Returns the slot containing `timestamp`.
+    pub fn slot(&self, timestamp: u64) -> u64 {
+        timestamp / self.block_time_secs.max(1)
+    }
+
+    /// Returns `true` if `account`, holding `key`, wins the slot containing
+    /// `timestamp`.
+    ///
+    /// Always `false` if `key` is not the one registered for `account`.
+    pub fn is_leader(&self, account: &AccountId, key: &VrfKeypair, timestamp: u64) -> bool {
+        if self.validators.is_empty() {
+            return true;
+        }
+        if self.keys.get(account) != Some(&key.public()) {
+            return false;
+        }
+        key.ticket(&self.seed, self.slot(timestamp)) <= self.threshold()
+    }
+
+    /// Returns the largest winning ticket.
+    fn threshold(&self) -> u64 {
+        threshold(1, self.validators.len() as u64)
+    }
+}
+
+impl ProposerSelector for VrfSelector {
+    /// Leaders are private, so anyone may try; [`verify_eligibility`]
+    /// decides.
+    ///
+    /// [`verify_eligibility`]: ProposerSelector::verify_eligibility
+    fn expected_proposer(&self, _timestamp: u64) -> Option<AccountId> {
+        None
+    }
+
+    fn verify_eligibility(&self, header: &Header) -> Result<(), String> {
+        if self.validators.is_empty() {
+            return Ok(());
+        }
+        let proposer = hex::encode(&header.proposer.0.as_bytes()[..8]);
+        if !self.validators.contains(&header.proposer) {
+            return Err(format!("proposer {proposer} is not a validator"));
+        }
+        let key = self
+            .keys
+            .get(&header.proposer)
+            .ok_or_else(|| format!("proposer {proposer} has no registered VRF key"))?;
+        let pos_proof = header
+            .pos_proof
+            .as_ref()
+            .ok_or("missing VRF eligibility proof")?;
+
+        let public =
+            PublicKey::from_bytes(&key.0).map_err(|e| format!("invalid VRF public key: {e}"))?;
+        let output = VRFPreOut::from_bytes(&pos_proof.output)
+            .map_err(|e| format!("invalid VRF output: {e}"))?;
+        let proof = VRFProof::from_bytes(&pos_proof.proof)
+            .map_err(|e| format!("invalid VRF proof: {e}"))?;
+        let slot = self.slot(header.timestamp);
+        let (inout, _) = public
+            .vrf_verify(slot_transcript(&self.seed, slot), &output, &proof)
+            .map_err(|_| format!("VRF proof of {proposer} does not verify for slot {slot}"))?;
+
+        if ticket(&inout) > self.threshold() {
+            return Err(format!("proposer {proposer} did not win slot {slot}"));
+        }
+        Ok(())
+    }
+}
+
+/// The leader schedule selected by [`ValidatorSetConfig::election`].
+#[derive(Clone, Debug)]
+pub enum LeaderElection {
+    RoundRobin(RoundRobinSelector),
+    Vrf(VrfSelector),
+    Stake(StakeWeightedSelector),
+}
+
+impl Default for LeaderElection {
+    fn default() -> Self {
+        LeaderElection::RoundRobin(RoundRobinSelector::default())
+    }
+}
+
+impl LeaderElection {
+    /// Builds the schedule described by `cfg` with slots of
+    /// `block_time_secs`; `seed` is only used by VRF election.
+    ///
+    /// VRF keys are paired with `cfg.accounts` by position.
+    pub fn from_config(cfg: &ValidatorSetConfig, block_time_secs: u64, seed: Hash256) -> Self {
+        let validators = ValidatorSet::from_config(cfg);
+        match cfg.election {
+            ElectionKind::RoundRobin => {
+                LeaderElection::RoundRobin(RoundRobinSelector::new(validators, block_time_secs))
+            }
+            ElectionKind::Vrf => {
+                let keys = cfg
+                    .accounts
+                    .iter()
+                    .copied()
+                    .zip(cfg.vrf_keys.iter().copied());
+                LeaderElection::Vrf(VrfSelector::new(validators, keys, block_time_secs, seed))
+            }
+            ElectionKind::Stake => LeaderElection::Stake(StakeWeightedSelector::new(
+                validators,
+                StakeTable::new(),
+                block_time_secs,
+                seed,
+            )),
+        }
+    }
+
+    /// Returns the validator set being scheduled.
+    pub fn validators(&self) -> &ValidatorSet {
+        match self {
+            LeaderElection::RoundRobin(selector) => selector.validators(),
+            LeaderElection::Vrf(selector) => selector.validators(),
+            LeaderElection::Stake(selector) => selector.validators(),
+        }
+    }
+
+    /// Returns `true` if `account`, holding `key`, may propose a block
+    /// stamped with `timestamp`.
+    pub fn may_propose(&self, account: &AccountId, key: &VrfKeypair, timestamp: u64) -> bool {
+        match self {
+            LeaderElection::RoundRobin(_) | LeaderElection::Stake(_) => self
+                .expected_proposer(timestamp)
+                .is_none_or(|leader| leader == *account),
+            LeaderElection::Vrf(selector) => selector.is_leader(account, key, timestamp),
+        }
+    }
+}
+
+impl ProposerSelector for LeaderElection {
+    fn expected_proposer(&self, timestamp: u64) -> Option<AccountId> {
+        match self {
+            LeaderElection::RoundRobin(selector) => selector.expected_proposer(timestamp),
+            LeaderElection::Vrf(selector) => selector.expected_proposer(timestamp),
+            LeaderElection::Stake(selector) => selector.expected_proposer(timestamp),
+        }
+    }
+
+    fn verify_eligibility(&self, header: &Header) -> Result<(), String> {
+        match self {
+            LeaderElection::RoundRobin(selector) => selector.verify_eligibility(header),
+            LeaderElection::Vrf(selector) => selector.verify_eligibility(header),
+            LeaderElection::Stake(selector) => selector.verify_eligibility(header),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{BlockHash, HASH_LEN};
+
+    fn account(byte: u8) -> AccountId {
+        AccountId(Hash256([byte; HASH_LEN]))
+    }
+
+    fn header(proposer: u8, timestamp: u64, pos_proof: Option<PosProof>) -> Header {
+        Header {
+            parent: BlockHash(Hash256([0u8; HASH_LEN])),
+            height: 1,
+            timestamp,
+            proposer: account(proposer),
+            txs_root: Hash256([0u8; HASH_LEN]),
+            state_root: Hash256([0u8; HASH_LEN]),
+            receipts_root: Hash256([0u8; HASH_LEN]),
+            pos_proof,
+        }
+    }
+
+    #[test]
+    fn winners_prove_their_slots_and_others_cannot() {
+        let keys: Vec<VrfKeypair> = (1..=4).map(|b| VrfKeypair::from_seed(&[b; 32])).collect();
+        let seed = Hash256([9u8; HASH_LEN]);
+        let selector = VrfSelector::new(
+            ValidatorSet::new((1..=4).map(account)),
+            (1..=4)
+                .map(account)
+                .zip(keys.iter().map(VrfKeypair::public)),
+            5,
+            seed,
+        );
+
+        let mut wins = 0;
+        for slot in 0..64u64 {
+            let timestamp = slot * 5;
+            for (i, key) in keys.iter().enumerate() {
+                let who = i as u8 + 1;
+                let proof = key.prove(&seed, slot);
+                let result = selector.verify_eligibility(&header(who, timestamp, Some(proof)));
+                assert_eq!(
+                    result.is_ok(),
+                    selector.is_leader(&account(who), key, timestamp),
+                    "slot {slot}, validator {who}: {result:?}"
+                );
+                wins += usize::from(result.is_ok());
+
+                // Someone else's proof never verifies.
+                let other = &keys[(i + 1) % keys.len()];
+                let stolen = other.prove(&seed, slot);
+                assert!(
+                    selector
+                        .verify_eligibility(&header(who, timestamp, Some(stolen)))
+                        .is_err()
+                );
+            }
+        }
+        // One expected leader per slot.
+        assert!((32..=96).contains(&wins), "{wins} wins in 64 slots");
+
+        assert!(selector.verify_eligibility(&header(1, 0, None)).is_err());
+        assert!(
+            selector
+                .verify_eligibility(&header(7, 0, Some(keys[0].prove(&seed, 0))))
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn round_robin_election_ignores_proofs() {
+        let cfg = ValidatorSetConfig {
+            accounts: vec![account(1), account(2)],
+            ..ValidatorSetConfig::default()
+        };
+        let election = LeaderElection::from_config(&cfg, 5, Hash256([0u8; HASH_LEN]));
+        let key = VrfKeypair::from_seed(&[1u8; 32]);
+
+        assert!(election.may_propose(&account(1), &key, 0));
+        assert!(!election.may_propose(&account(1), &key, 5));
+        assert!(election.verify_eligibility(&header(2, 5, None)).is_ok());
+    }
+}
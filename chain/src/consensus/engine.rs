@@ -3,28 +3,183 @@
 //! The consensus engine wires together:
 //!
 //! - a [`BlockStore`] for persistence,
-//! - a [`BlockValidator`] for `V_base` and `V_cons`,
-//! - a [`ForkChoice`] implementation, and
-//! - a [`Proposer`] for block construction.
+//! - a [`BlockValidator`] (or [`AsyncBlockValidator`]) for `V_base` and
+//!   `V_cons`,
+//! - a [`ForkChoice`] implementation,
+//! - a [`Proposer`] for block construction, and
+//! - the execution layer, which evolves [`ChainState`] as blocks are
+//!   imported.
 //!
 //! It exposes methods to propose new blocks (for local leadership) and to
 //! import blocks (from local or remote proposers) into the canonical chain.
+//!
+//! Before validation, every imported block must link to the stored chain:
+//! its parent must be known (or it must be a genesis block) and its height
+//! must be exactly one above its parent's. Blocks with an unknown parent
+//! are rejected, or parked in an [`OrphanBuffer`] when
+//! [`ConsensusConfig::orphan_buffer_size`] is non-zero and imported once the
+//! parent arrives. Validators then get the parent and a read-only view of
+//! the store through a [`ValidationContext`].
+//!
+//! With [`ConsensusConfig::monotonic_timestamps`] (the default), a block's
+//! timestamp must also be strictly greater than its parent's; how far it
+//! may run ahead of the local clock is checked by
+//! [`BaseValidity`](crate::validation::BaseValidity).
+//!
+//! With [`ConsensusConfig::slot_timing`], non-genesis blocks must also carry
+//! their slot time, `genesis_time + height * block_time_secs`, where
+//! `genesis_time` is the timestamp of the canonical height-0 block. Locally
+//! proposed blocks are stamped with it, so traces from different nodes line
+//! up regardless of wall-clock jitter.
+//!
+//! Engines built with [`ConsensusEngine::with_genesis`] start from an
+//! explicit [`GenesisConfig`]: the genesis block is written to an empty
+//! store (or checked against a non-empty one), it is the only block accepted
+//! at height 0, and chain state is replayed from the spec's initial state.
+//! Engines built with [`ConsensusEngine::new`] keep the implicit genesis: any
+//! height-0 block with an all-zero parent, executed against empty state.
+//!
+//! The engine also keeps per-proposer [`ProposerStats`] (accepted and
+//! rejected blocks, validation time) for [`ValidatorReport`]s.
+//!
+//! The tip follows the configured [`ForkChoice`], which may switch it to a
+//! block on a side branch. Every such switch is reported as a
+//! [`ReorgEvent`] (old tip, new tip, and how many blocks were retracted) to
+//! the listener set with [`ConsensusEngine::with_reorg_listener`].
+//! Transactions of retracted blocks that the new branch does not include
+//! are kept and handed back to the [`TxPool`] on the next proposal (or by
+//! [`ConsensusEngine::return_retracted_txs`]).
+//!
+//! A failed proposal returns its transactions to the pool as well, unless
+//! they may be why it failed; see [`ConsensusEngine::propose_block`].
+//!
+//! With [`ConsensusEngine::with_metrics`], the engine keeps the tip, reorg,
+//! and import counters of [`ConsensusMetrics`] current as blocks arrive.
+//!
+//! With [`ConsensusEngine::with_vrf_key`], locally proposed blocks carry a
+//! VRF eligibility proof for their slot in `Header::pos_proof`, as VRF
+//! leader [`election`](super::election) requires.
+//!
+//! With [`ConsensusEngine::with_stake_table`], the engine copies the tip
+//! state's active stakes into a [`StakeTable`] whenever the tip changes, so
+//! that stake-weighted proposer selection follows the chain. Likewise,
+//! [`ConsensusEngine::with_state_handle`] publishes the tip state through a
+//! [`StateHandle`] for validators that check blocks against it.
+//!
+//! The engine tracks the [`Epoch`] of the next block, recomputed from the
+//! tip state whenever the tip changes; see
+//! [`ConsensusEngine::current_epoch`].
+//!
+//! Blocks rejected because the ML verifier judged an artefact inauthentic
+//! are counted in `blocks_rejected_ml`. With
+//! [`ConsensusEngine::with_fraud_reporter`], each such block also yields a
+//! [`FraudProof`] against its proposer, collected with
+//! [`ConsensusEngine::take_fraud_proofs`] for submission in a `TxSlash`.
+//!
+//! With [`ConsensusEngine::with_votes`], the engine counts validator
+//! [`Vote`]s passed to [`ConsensusEngine::add_vote`]. When a quorum commits
+//! a block, the fork choice is told through [`ForkChoice::on_commit`], and a
+//! stored committed block off the canonical chain becomes the tip at once.
+//!
+//! Engines over a [`SharedStore`] hand out [`ChainReader`]s
+//! ([`ConsensusEngine::reader`]) that read the tip, blocks, state and
+//! transaction index without borrowing the engine.
+//!
+//! With [`ConsensusEngine::with_invariant_checks`], the engine asserts the
+//! [`invariants`](super::invariants) after every committed block, GC
+//! pass, and pruning pass.
+//!
+//! Proposals and imports run inside `propose_block` and `import_block`
+//! `tracing` spans carrying the block height and hash, which the ML
+//! validators and clients nest their own spans under.
+//!
+//! Storage failures are never swallowed: a block that cannot be persisted
+//! (or a tip that cannot be updated) fails the import with
+//! [`ConsensusError::Storage`].
+
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use std::time::{Duration, Instant};
 
-use crate::types::{AccountId, Block, BlockHash};
+use tokio::sync::broadcast;
+use tracing::{Span, field};
+
+use crate::execution::{
+    ChainState, MlStatsLog, StateHandle, apply_block_with_receipts, receipts_root, replay_chain,
+};
+use crate::metrics::ConsensusMetrics;
+use crate::storage::{SharedStore, StorageError};
+use crate::types::{
+    AccountId, Block, BlockHash, FraudProof, HASH_LEN, Hash256, Keypair, SealedBlock, Signature,
+    Transaction, TxHash,
+};
 
 use super::config::ConsensusConfig;
+use super::election::VrfKeypair;
+use super::epoch::Epoch;
 use super::error::ConsensusError;
-use super::fork_choice::ForkChoice;
+use super::error::ValidationError;
+use super::events::{ChainEvent, RejectionKind};
+use super::fork_choice::{ForkChoice, ReorgEvent, common_ancestor};
+use super::gc::{ForkGc, GcReport};
+use super::genesis::GenesisConfig;
+use super::invariants::{InvariantChecker, InvariantConfig};
+use super::orphans::{OrphanBuffer, PendingBlock};
 use super::proposer::{Proposer, TxPool};
-use super::store::BlockStore;
-use super::validator::BlockValidator;
+use super::prune::{PruneReport, Pruner};
+use super::reader::{ChainReader, ChainView, SharedView};
+use super::report::{ProposerStats, ValidatorReport};
+use super::schedule::ValidatorSet;
+use super::stake::StakeTable;
+use super::store::{BlockStore, Checkpoint};
+use super::tx_index::{TxIndex, TxLocation};
+use super::validator::{AsyncBlockValidator, BlockValidator, ValidationContext};
+use super::votes::{QuorumCertificate, Vote, VoteAggregator};
+
+/// Callback receiving the engine's [`ReorgEvent`]s.
+type ReorgListener = Box<dyn FnMut(&ReorgEvent) + Send>;
+
+/// Records `block`'s hash on `span`, unless the span is disabled.
+fn record_block_hash(span: &Span, block: &Block) {
+    if !span.is_disabled() {
+        span.record("hash", hex::encode(block.compute_hash().0.as_bytes()));
+    }
+}
+
+/// Returns the transactions of a failed proposal worth proposing again;
+/// see [`ConsensusEngine::propose_block`].
+fn retryable_txs(block: Block, err: &ConsensusError) -> Vec<Transaction> {
+    let cause = match err {
+        ConsensusError::Validation(e) => e.root(),
+        ConsensusError::Storage(_) | ConsensusError::Other(_) => return block.txs,
+        ConsensusError::Execution(_) | ConsensusError::Vote(_) => return Vec::new(),
+    };
+    match cause {
+        ValidationError::MlRejected { aid, .. } => block
+            .txs
+            .into_iter()
+            .filter(|tx| !matches!(tx, Transaction::RegisterModel(reg) if reg.aid == *aid))
+            .collect(),
+        ValidationError::MlUnavailable(_)
+        | ValidationError::MlBudgetExhausted { .. }
+        | ValidationError::NotGenesis
+        | ValidationError::BadHeight { .. }
+        | ValidationError::TimestampNotAfterParent { .. }
+        | ValidationError::TimestampInFuture { .. }
+        | ValidationError::OffSlot { .. }
+        | ValidationError::WrongProposer { .. }
+        | ValidationError::IneligibleProposer(_) => block.txs,
+        _ => Vec::new(),
+    }
+}
 
 /// Fully-configurable consensus engine.
 ///
 /// This struct is generic over:
 ///
 /// - `S`: storage backend implementing [`BlockStore`],
-/// - `V`: block validator implementing [`BlockValidator`],
+/// - `V`: block validator implementing [`BlockValidator`] and/or
+///   [`AsyncBlockValidator`],
 /// - `F`: fork-choice rule implementing [`ForkChoice`].
 pub struct ConsensusEngine<S, V, F> {
     pub config: ConsensusConfig,
@@ -32,23 +187,399 @@ pub struct ConsensusEngine<S, V, F> {
     validator: V,
     fork_choice: F,
     proposer: Proposer,
+    /// State after executing the current tip.
+    state: Arc<ChainState>,
+    /// Blocks waiting for their parent to be imported.
+    orphans: OrphanBuffer,
+    /// Hash of the configured genesis block, if any.
+    genesis: Option<BlockHash>,
+    /// State the genesis block is executed against.
+    genesis_state: ChainState,
+    /// Invariant checker, if checks are enabled.
+    invariants: Option<InvariantChecker>,
+    /// Vote aggregator, if votes are counted.
+    votes: Option<VoteAggregator>,
+    /// VRF key and network seed proposals are proven with, if any.
+    vrf_key: Option<(VrfKeypair, Hash256)>,
+    /// Stake table kept in sync with the tip state, if any.
+    stake_table: Option<StakeTable>,
+    /// Shared snapshot of the tip state, if any.
+    state_handle: Option<StateHandle>,
+    /// Epoch of the block after the tip.
+    epoch: Epoch,
+    /// Account fraud proofs are issued by, if any.
+    fraud_reporter: Option<Keypair>,
+    /// Fraud proofs not yet taken by [`take_fraud_proofs`](Self::take_fraud_proofs).
+    fraud_proofs: Vec<FraudProof>,
+    /// Called after every tip switch to a side branch.
+    reorg_listener: Option<ReorgListener>,
+    /// Transactions of blocks retracted by reorgs that the new canonical
+    /// chain does not include, oldest first.
+    retracted: Vec<Transaction>,
+    /// Tip, state, epoch, transaction index and proposer statistics, as
+    /// shared with [`ChainReader`]s.
+    view: SharedView,
+    /// Channel [`ChainEvent`]s are published into, if any.
+    events: Option<broadcast::Sender<ChainEvent>>,
+    /// Metrics updated on every import, if any.
+    metrics: Option<ConsensusMetrics>,
+    /// Log the ML verdict statistics stored with each block are taken
+    /// from, if any.
+    ml_stats: Option<MlStatsLog>,
 }
 
 impl<S, V, F> ConsensusEngine<S, V, F>
 where
     S: BlockStore,
-    V: BlockValidator,
     F: ForkChoice,
 {
-    /// Creates a new consensus engine.
-    pub fn new(config: ConsensusConfig, store: S, validator: V, fork_choice: F) -> Self {
+    /// Creates a new consensus engine with an implicit genesis.
+    ///
+    /// If the store already has a tip, the chain state is rebuilt by
+    /// re-executing the canonical chain from genesis and the transaction
+    /// index by reading every stored block; failing to read or replay them
+    /// returns the error rather than starting from an empty state.
+    pub fn new(
+        config: ConsensusConfig,
+        store: S,
+        validator: V,
+        fork_choice: F,
+    ) -> Result<Self, ConsensusError> {
+        Self::from_parts(
+            config,
+            store,
+            validator,
+            fork_choice,
+            None,
+            ChainState::new(),
+        )
+    }
+
+    /// Creates a new consensus engine that starts from `genesis`.
+    ///
+    /// An empty store is initialised with the genesis block as its tip. A
+    /// non-empty store must already hold that same block at height 0;
+    /// otherwise this returns [`ConsensusError::Storage`], since the node
+    /// would be following a different network. Failing to read or write
+    /// the store is reported the same way, and failing to replay the
+    /// stored chain as in [`new`](Self::new).
+    pub fn with_genesis(
+        config: ConsensusConfig,
+        genesis: &GenesisConfig,
+        mut store: S,
+        validator: V,
+        fork_choice: F,
+    ) -> Result<Self, ConsensusError> {
+        let block = genesis.genesis_block();
+        let hash = block.compute_hash();
+
+        match (store.tip()?, store.get_hash_at_height(0)?) {
+            (None, _) => {
+                store.put_block_and_set_tip(block)?;
+            }
+            (Some(_), Some(stored)) if stored == hash => {}
+            (Some(_), stored) => {
+                return Err(ConsensusError::Storage(format!(
+                    "genesis mismatch: store has {}, spec yields {}",
+                    stored.map_or_else(|| "none".to_string(), |h| hex::encode(h.0.as_bytes())),
+                    hex::encode(hash.0.as_bytes())
+                )));
+            }
+        }
+
+        Self::from_parts(
+            config,
+            store,
+            validator,
+            fork_choice,
+            Some(hash),
+            genesis.initial_state(),
+        )
+    }
+
+    fn from_parts(
+        config: ConsensusConfig,
+        store: S,
+        validator: V,
+        fork_choice: F,
+        genesis: Option<BlockHash>,
+        genesis_state: ChainState,
+    ) -> Result<Self, ConsensusError> {
         let proposer = Proposer::from_config(&config);
-        Self {
+        let orphans = OrphanBuffer::new(config.orphan_buffer_size);
+        let tip = store.tip()?;
+        let state = match &tip {
+            Some(tip) => replay_chain(&store, tip, &genesis_state)?,
+            None => genesis_state.clone(),
+        };
+        let tip_height = match &tip {
+            Some(tip) => store.get_block(tip)?.map(|block| block.header.height),
+            None => None,
+        };
+        let epoch = Epoch::at(tip_height.map_or(0, |h| h + 1), &state);
+        let tx_index = TxIndex::rebuild(&store)?;
+        let state = Arc::new(state);
+        let view = Arc::new(RwLock::new(ChainView {
+            tip,
+            state: Arc::clone(&state),
+            epoch: epoch.clone(),
+            tx_index,
+            proposer_stats: HashMap::new(),
+            orphans: Vec::new(),
+        }));
+        Ok(Self {
             config,
             store,
             validator,
             fork_choice,
             proposer,
+            state,
+            orphans,
+            genesis,
+            genesis_state,
+            invariants: None,
+            votes: None,
+            vrf_key: None,
+            stake_table: None,
+            state_handle: None,
+            epoch,
+            fraud_reporter: None,
+            fraud_proofs: Vec::new(),
+            reorg_listener: None,
+            retracted: Vec::new(),
+            view,
+            events: None,
+            metrics: None,
+            ml_stats: None,
+        })
+    }
+
+    /// Enables or disables invariant checks according to `cfg`.
+    ///
+    /// When enabled, the engine panics as soon as an invariant is violated.
+    pub fn with_invariant_checks(mut self, cfg: &InvariantConfig) -> Self {
+        self.invariants = cfg
+            .enabled
+            .then(|| InvariantChecker::new(cfg.finality_depth));
+        self.assert_invariants();
+        self
+    }
+
+    /// Proves every non-genesis proposal's slot with `key` under the
+    /// network `seed`, storing the proof in `Header::pos_proof`.
+    ///
+    /// `seed` must match the [`VrfSelector`](super::election::VrfSelector)
+    /// that validates the blocks.
+    pub fn with_vrf_key(mut self, key: VrfKeypair, seed: Hash256) -> Self {
+        self.vrf_key = Some((key, seed));
+        self
+    }
+
+    /// Keeps `table` filled with the active stakes of the tip state, for a
+    /// [`StakeWeightedSelector`](super::stake::StakeWeightedSelector)
+    /// sharing it.
+    pub fn with_stake_table(mut self, table: StakeTable) -> Self {
+        table.update(&self.state);
+        self.stake_table = Some(table);
+        self
+    }
+
+    /// Keeps `handle` filled with the tip state, for validators such as
+    /// [`StatefulValidity`](crate::validation::StatefulValidity) sharing it.
+    pub fn with_state_handle(mut self, handle: StateHandle) -> Self {
+        if let Ok(Some(tip)) = self.store.tip() {
+            handle.update(tip, &self.state);
+        }
+        self.state_handle = Some(handle);
+        self
+    }
+
+    fn view(&self) -> RwLockReadGuard<'_, ChainView> {
+        self.view.read().expect("chain view lock poisoned")
+    }
+
+    fn view_mut(&self) -> RwLockWriteGuard<'_, ChainView> {
+        self.view.write().expect("chain view lock poisoned")
+    }
+
+    /// Copies the tip, its state and the epoch into the shared view, and
+    /// the tip state into the stake table and state handle, if any.
+    fn refresh_tip_views(&self, tip: BlockHash) {
+        {
+            let mut view = self.view_mut();
+            view.tip = Some(tip);
+            view.state = Arc::clone(&self.state);
+            view.epoch = self.epoch.clone();
+        }
+        if let Some(table) = &self.stake_table {
+            table.update(&self.state);
+        }
+        if let Some(handle) = &self.state_handle {
+            handle.update(tip, &self.state);
+        }
+    }
+
+    /// Returns the epoch of the block after the tip.
+    ///
+    /// Its validator set and parameters hold for every block of the
+    /// epoch; [`Epoch::number`] changes once the tip reaches the epoch's
+    /// last block (or a reorg moves it to another epoch).
+    pub fn current_epoch(&self) -> &Epoch {
+        &self.epoch
+    }
+
+    /// Issues a [`FraudProof`] signed by `reporter` for every block
+    /// rejected by the ML verifier, unless `reporter` proposed it.
+    pub fn with_fraud_reporter(mut self, reporter: Keypair) -> Self {
+        self.fraud_reporter = Some(reporter);
+        self
+    }
+
+    /// Returns the fraud proofs issued since the last call.
+    pub fn take_fraud_proofs(&mut self) -> Vec<FraudProof> {
+        std::mem::take(&mut self.fraud_proofs)
+    }
+
+    /// Counts an ML rejection of `block` and, with a fraud reporter, issues
+    /// a proof against its proposer (once per block).
+    fn on_ml_rejection(&mut self, block: &SealedBlock, validated: &Result<(), ConsensusError>) {
+        let Err(ConsensusError::Validation(err)) = validated else {
+            return;
+        };
+        let ValidationError::MlRejected { aid, verdict } = err.root() else {
+            return;
+        };
+        if let Some(metrics) = &self.metrics {
+            metrics.blocks_rejected_ml.inc();
+        }
+        let Some(reporter) = &self.fraud_reporter else {
+            return;
+        };
+        let block_hash = block.hash();
+        if block.header.proposer == reporter.account_id()
+            || self.fraud_proofs.iter().any(|p| p.block_hash == block_hash)
+        {
+            return;
+        }
+        let mut proof = FraudProof {
+            block_hash,
+            height: block.header.height,
+            proposer: block.header.proposer,
+            aid: *aid,
+            verdict: verdict.clone(),
+            reporter: reporter.account_id(),
+            reporter_key: reporter.public_key(),
+            signature: Signature(Vec::new()),
+        };
+        reporter.sign_fraud_proof(&mut proof);
+        self.fraud_proofs.push(proof);
+    }
+
+    /// Counts votes from `validators` passed to
+    /// [`add_vote`](Self::add_vote).
+    ///
+    /// Pair this with a [`QuorumForkChoice`](super::votes::QuorumForkChoice)
+    /// so that committed blocks are never retracted.
+    pub fn with_votes(mut self, validators: ValidatorSet) -> Self {
+        self.votes = Some(VoteAggregator::new(validators));
+        self
+    }
+
+    /// Registers `listener` to be called with a [`ReorgEvent`] whenever the
+    /// tip switches to a block that does not extend the previous tip.
+    ///
+    /// The listener runs synchronously inside the import, after the new
+    /// tip has been persisted; it should be cheap.
+    pub fn with_reorg_listener(
+        mut self,
+        listener: impl FnMut(&ReorgEvent) + Send + 'static,
+    ) -> Self {
+        self.reorg_listener = Some(Box::new(listener));
+        self
+    }
+
+    /// Publishes a [`ChainEvent`] into `events` for every imported,
+    /// rejected, or newly canonical block.
+    ///
+    /// Keep a clone of the sender to `subscribe` to the events. Sending
+    /// never blocks the import; it does nothing while nobody subscribes.
+    pub fn with_events(mut self, events: broadcast::Sender<ChainEvent>) -> Self {
+        self.events = Some(events);
+        self
+    }
+
+    /// Updates `metrics` as blocks are proposed, imported, orphaned, or
+    /// become the tip, and records every reorg with
+    /// [`ConsensusMetrics::observe_reorg`].
+    ///
+    /// The tip gauges are set from the current tip straight away.
+    pub fn with_metrics(mut self, metrics: ConsensusMetrics) -> Self {
+        match self.tip_block() {
+            Ok(Some(tip)) => metrics.observe_tip(&tip.header),
+            Ok(None) => {}
+            Err(e) => tracing::warn!(error = %e, "failed to read tip for metrics"),
+        }
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Stores the [`MlVerdictStats`](crate::execution::MlVerdictStats)
+    /// recorded in `log` for each imported block next to its receipts.
+    ///
+    /// Share `log` with the ML validator (see
+    /// [`MlValidity::with_stats_log`](crate::validation::MlValidity::with_stats_log)).
+    pub fn with_ml_stats(mut self, log: MlStatsLog) -> Self {
+        self.ml_stats = Some(log);
+        self
+    }
+
+    /// Sends `event` to the subscribers, if events are enabled.
+    fn publish(&self, event: ChainEvent) {
+        if let Some(events) = &self.events {
+            // An error only means nobody is subscribed right now.
+            let _ = events.send(event);
+        }
+    }
+
+    /// Publishes a [`ChainEvent::BlockRejected`] if `result` is an error.
+    ///
+    /// `rejected` is the block's hash and height, computed before import
+    /// only when events are enabled.
+    fn publish_rejection(
+        &self,
+        rejected: Option<(BlockHash, u64)>,
+        proposer: AccountId,
+        result: &Result<BlockHash, ConsensusError>,
+    ) {
+        if let (Some((hash, height)), Err(err)) = (rejected, result) {
+            self.publish(ChainEvent::BlockRejected {
+                hash,
+                height,
+                proposer,
+                kind: RejectionKind::of(err),
+                code: err.kind(),
+                reason: err.to_string(),
+            });
+        }
+    }
+
+    /// Counts a failed import of a linked block by
+    /// [`ConsensusError::kind`], if metrics are enabled.
+    fn count_rejection(&self, result: &Result<BlockHash, ConsensusError>) {
+        if let (Some(metrics), Err(err)) = (&self.metrics, result) {
+            metrics
+                .blocks_rejected
+                .with_label_values(&[err.kind()])
+                .inc();
+        }
+    }
+
+    /// Panics if invariant checks are enabled and one is violated.
+    fn assert_invariants(&mut self) {
+        if let Some(checker) = &mut self.invariants
+            && let Err(violation) = checker.check(&self.store)
+        {
+            panic!("consensus invariant violated: {violation}");
         }
     }
 
@@ -66,21 +597,590 @@ where
     }
 
     /// Returns the hash of the current tip of the best chain, if any.
-    pub fn tip(&self) -> Option<BlockHash> {
+    pub fn tip(&self) -> Result<Option<BlockHash>, StorageError> {
         self.store.tip()
     }
 
     /// Returns the tip block, if any.
-    pub fn tip_block(&self) -> Option<Block> {
-        self.tip().and_then(|h| self.store.get_block(&h))
+    pub fn tip_block(&self) -> Result<Option<Block>, StorageError> {
+        match self.tip()? {
+            Some(hash) => self.store.get_block(&hash),
+            None => Ok(None),
+        }
+    }
+
+    /// Returns the chain state after executing the current tip.
+    pub fn state(&self) -> &ChainState {
+        &self.state
     }
 
+    /// Returns the hash of the configured genesis block, if any.
+    pub fn genesis_hash(&self) -> Option<BlockHash> {
+        self.genesis
+    }
+
+    /// Returns import statistics for `proposer` since the engine started.
+    pub fn proposer_stats(&self, proposer: &AccountId) -> ProposerStats {
+        self.view()
+            .proposer_stats
+            .get(proposer)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Builds a performance report for `proposer` from the canonical chain
+    /// and the engine's import statistics.
+    pub fn validator_report(&self, proposer: &AccountId) -> Result<ValidatorReport, StorageError> {
+        ValidatorReport::build(
+            &self.store,
+            *proposer,
+            self.proposer_stats(proposer),
+            self.config.block_time_secs,
+        )
+    }
+
+    /// Returns where transaction `hash` sits on the canonical chain, or
+    /// `None` if no canonical block includes it.
+    pub fn locate_tx(&self, hash: &TxHash) -> Result<Option<TxLocation>, StorageError> {
+        self.view().tx_index.canonical_location(&self.store, hash)
+    }
+
+    /// Returns the most recent block committed by a quorum of votes, if
+    /// any.
+    pub fn last_commit(&self) -> Option<&QuorumCertificate> {
+        self.votes.as_ref().and_then(VoteAggregator::committed)
+    }
+
+    /// Counts `vote`, returning the certificate if it commits a block.
+    ///
+    /// On a commit the fork choice is notified, and if the committed block
+    /// is stored but not canonical it becomes the tip, reported like any
+    /// other reorg. Returns [`ConsensusError::Other`] unless votes were
+    /// enabled with [`with_votes`](Self::with_votes), and
+    /// [`ConsensusError::Vote`] for votes that cannot be counted.
+    pub fn add_vote(&mut self, vote: Vote) -> Result<Option<QuorumCertificate>, ConsensusError> {
+        let Some(votes) = &mut self.votes else {
+            return Err(ConsensusError::Other("votes are not enabled".to_string()));
+        };
+        let Some(commit) = votes.add_vote(vote)? else {
+            return Ok(None);
+        };
+
+        self.fork_choice.on_commit(&commit);
+        self.adopt_committed(&commit)?;
+        if let Some(metrics) = &self.metrics {
+            metrics.committed_height.set(commit.height as i64);
+        }
+        self.publish(ChainEvent::BlockCommitted {
+            hash: commit.block_hash,
+            height: commit.height,
+        });
+        Ok(Some(commit))
+    }
+
+    /// Makes the committed block the tip if it is stored but not on the
+    /// canonical chain.
+    fn adopt_committed(&mut self, commit: &QuorumCertificate) -> Result<(), ConsensusError> {
+        if self.store.get_hash_at_height(commit.height)? == Some(commit.block_hash) {
+            return Ok(());
+        }
+        let Some(block) = self.store.get_block(&commit.block_hash)? else {
+            return Ok(());
+        };
+
+        let reorg = match self.store.tip()? {
+            Some(old_tip) => self.reorg_depth(old_tip, &block)?.map(|depth| ReorgEvent {
+                old_tip,
+                new_tip: commit.block_hash,
+                depth,
+            }),
+            None => None,
+        };
+        let retracted = match &reorg {
+            Some(event) => self.retracted_txs(event.old_tip, commit.block_hash, &block)?,
+            None => Vec::new(),
+        };
+        let state = replay_chain(&self.store, &commit.block_hash, &self.genesis_state)?;
+        self.store.set_tip(commit.block_hash)?;
+        self.retracted.extend(retracted);
+        self.state = Arc::new(state);
+        self.epoch = Epoch::at(commit.height + 1, &self.state);
+        self.refresh_tip_views(commit.block_hash);
+
+        if let Some(metrics) = &self.metrics {
+            metrics.observe_tip(&block.header);
+            if let Some(event) = &reorg {
+                metrics.observe_reorg(event);
+            }
+        }
+        if let (Some(event), Some(listener)) = (reorg, &mut self.reorg_listener) {
+            listener(&event);
+        }
+        self.publish(ChainEvent::NewTip {
+            hash: commit.block_hash,
+            height: commit.height,
+            reorg_depth: reorg.map(|event| event.depth),
+        });
+        self.assert_invariants();
+        Ok(())
+    }
+
+    /// Returns the number of blocks waiting for an unknown parent.
+    pub fn orphan_count(&self) -> usize {
+        self.orphans.len()
+    }
+
+    /// Returns the blocks waiting for an unknown parent, oldest first.
+    pub fn orphans(&self) -> Vec<PendingBlock> {
+        self.orphans.pending()
+    }
+
+    /// Copies the orphan buffer's contents into the shared view and the
+    /// orphan gauges, after blocks were buffered or taken out.
+    fn refresh_orphans(&self) {
+        let pending = self.orphans.pending();
+        if let Some(metrics) = &self.metrics {
+            metrics.observe_orphans(&pending);
+        }
+        self.view_mut().orphans = pending;
+    }
+
+    /// Deletes blocks on stale fork branches using the given collector.
+    ///
+    /// See [`ForkGc::collect`] for the exact retention rule.
+    pub fn collect_stale_forks(&mut self, gc: &ForkGc) -> Result<GcReport, StorageError> {
+        let report = gc.collect(&mut self.store)?;
+        if report.blocks_removed > 0 {
+            self.view_mut().tx_index.prune(&self.store)?;
+        }
+        self.assert_invariants();
+        Ok(report)
+    }
+
+    /// Prunes old history using the given pruner, at wall-clock time `now`.
+    ///
+    /// The state after the new cut-off block is computed and stored as the
+    /// store's checkpoint together with the deletions. With invariant
+    /// checks enabled, nothing above the checker's finalized height is
+    /// pruned. See [`Pruner::plan`] for how the cut-off is chosen.
+    pub fn prune(&mut self, pruner: &Pruner, now: u64) -> Result<PruneReport, ConsensusError> {
+        let finalized = match &self.invariants {
+            Some(checker) => checker.finalized().map_or(0, |(height, _)| height),
+            None => u64::MAX,
+        };
+        let Some(plan) = pruner.plan(&self.store, finalized, now)? else {
+            return Ok(PruneReport {
+                blocks_pruned: 0,
+                checkpoint_height: self.store.checkpoint()?.map(|c| c.height),
+            });
+        };
+
+        let state = replay_chain(&self.store, &plan.checkpoint, &self.genesis_state)?;
+        let checkpoint = Checkpoint {
+            hash: plan.checkpoint,
+            height: plan.checkpoint_height,
+            state,
+        };
+        self.store.prune_to(checkpoint, &plan.pruned)?;
+        self.view_mut().tx_index.prune(&self.store)?;
+        self.assert_invariants();
+
+        Ok(PruneReport {
+            blocks_pruned: plan.pruned.len() as u64,
+            checkpoint_height: Some(plan.checkpoint_height),
+        })
+    }
+
+    /// Builds a candidate block on top of the current tip.
+    ///
+    /// The block is executed against the tip state to fill in its state
+    /// root. If execution fails the root is left empty and the import
+    /// reports the execution error.
+    fn build_candidate<P>(
+        &self,
+        proposer_id: AccountId,
+        tx_pool: &mut P,
+        timestamp: u64,
+    ) -> Result<Block, ConsensusError>
+    where
+        P: TxPool,
+    {
+        let mut block = self
+            .proposer
+            .build_block(&self.store, proposer_id, tx_pool, timestamp)?;
+        if self.config.slot_timing
+            && let Some(slot) = self.slot_timestamp(block.header.height)?
+        {
+            block.header.timestamp = slot;
+        }
+        if let Some((key, seed)) = &self.vrf_key
+            && block.header.height > 0
+        {
+            let slot = block.header.timestamp / self.config.block_time_secs.max(1);
+            block.header.pos_proof = Some(key.prove(seed, slot));
+        }
+        if let Ok((post_state, receipts)) = apply_block_with_receipts(&self.state, &block) {
+            block.header.state_root = post_state.state_root();
+            block.header.receipts_root = receipts_root(&receipts);
+        }
+        Ok(block)
+    }
+
+    /// Returns the timestamp a block proposed at wall-clock time `now`
+    /// would carry: `now` itself, or under slot timing the slot time of the
+    /// next height.
+    ///
+    /// Producer loops use this to look up the slot leader before building a
+    /// block.
+    pub fn next_block_timestamp(&self, now: u64) -> Result<u64, StorageError> {
+        if !self.config.slot_timing {
+            return Ok(now);
+        }
+        let next_height = match self.tip_block()? {
+            Some(tip) => tip.header.height + 1,
+            None => 0,
+        };
+        Ok(self.slot_timestamp(next_height)?.unwrap_or(now))
+    }
+
+    /// Returns the slot time of `height`, i.e.
+    /// `genesis_time + height * block_time_secs`, or `None` while no genesis
+    /// block is stored.
+    pub fn slot_timestamp(&self, height: u64) -> Result<Option<u64>, StorageError> {
+        let Some(genesis) = self.store.get_block_by_height(0)? else {
+            return Ok(None);
+        };
+        Ok(Some(genesis.header.timestamp.saturating_add(
+            height.saturating_mul(self.config.block_time_secs),
+        )))
+    }
+
+    /// Checks that `block` extends a stored block (or is a genesis block),
+    /// returning the parent (`None` for a genesis block).
+    ///
+    /// Returns [`ConsensusError::Storage`] if the parent is unknown and
+    /// [`ConsensusError::Validation`] if the height does not follow on from
+    /// the parent's, if monotonic timestamps are on and the timestamp is not
+    /// after the parent's, or if slot timing is on and the timestamp is not
+    /// the block's slot time.
+    fn check_chain_link(&self, block: &Block) -> Result<Option<Block>, ConsensusError> {
+        if block.header.height == 0 {
+            let is_genesis = match self.genesis {
+                Some(genesis) => block.compute_hash() == genesis,
+                None => block.header.parent == BlockHash(Hash256([0u8; HASH_LEN])),
+            };
+            return if is_genesis {
+                Ok(None)
+            } else {
+                Err(ValidationError::NotGenesis.into())
+            };
+        }
+
+        let Some(parent) = self.store.get_block(&block.header.parent)? else {
+            return Err(ConsensusError::Storage(format!(
+                "unknown parent block {}",
+                hex::encode(block.header.parent.0.as_bytes())
+            )));
+        };
+
+        if parent.header.height.checked_add(1) != Some(block.header.height) {
+            return Err(ValidationError::BadHeight {
+                parent_height: parent.header.height,
+                height: block.header.height,
+            }
+            .into());
+        }
+
+        if self.config.monotonic_timestamps && block.header.timestamp <= parent.header.timestamp {
+            return Err(ValidationError::TimestampNotAfterParent {
+                timestamp: block.header.timestamp,
+                parent: parent.header.timestamp,
+            }
+            .into());
+        }
+
+        if self.config.slot_timing
+            && let Some(slot) = self.slot_timestamp(block.header.height)?
+            && block.header.timestamp != slot
+        {
+            return Err(ValidationError::OffSlot {
+                timestamp: block.header.timestamp,
+                slot,
+            }
+            .into());
+        }
+
+        Ok(Some(parent))
+    }
+
+    /// Runs [`check_chain_link`](Self::check_chain_link), parking the block
+    /// in the orphan buffer if its parent is unknown and buffering is
+    /// enabled.
+    fn link_or_buffer(&mut self, block: &Block) -> Result<Option<Block>, ConsensusError> {
+        match self.check_chain_link(block) {
+            // Only an unknown parent makes a block an orphan, not a failed
+            // store read.
+            Err(ConsensusError::Storage(msg))
+                if matches!(self.store.get_block(&block.header.parent), Ok(None)) =>
+            {
+                if self.orphans.insert(block.clone()) {
+                    if let Some(metrics) = &self.metrics {
+                        metrics.blocks_orphaned.inc();
+                    }
+                    self.refresh_orphans();
+                    Err(ConsensusError::Storage(format!(
+                        "{msg}; block buffered as orphan"
+                    )))
+                } else {
+                    Err(ConsensusError::Storage(msg))
+                }
+            }
+            other => other,
+        }
+    }
+
+    /// Returns the state the block's parent left behind.
+    ///
+    /// Blocks extending the current tip reuse the cached state; blocks on
+    /// side branches replay their ancestry from genesis.
+    fn parent_state(&self, block: &Block) -> Result<ChainState, ConsensusError> {
+        match self.store.tip()? {
+            None => Ok(ChainState::clone(&self.state)),
+            Some(tip) if tip == block.header.parent => Ok(ChainState::clone(&self.state)),
+            Some(_) => Ok(replay_chain(
+                &self.store,
+                &block.header.parent,
+                &self.genesis_state,
+            )?),
+        }
+    }
+
+    /// Returns how many blocks of the chain ending in `old_tip` are
+    /// retracted when `block` becomes the tip, or `None` if nothing is
+    /// (e.g. `block` descends from `old_tip`).
+    ///
+    /// Without a stored common ancestor the whole old chain is retracted.
+    fn reorg_depth(&self, old_tip: BlockHash, block: &Block) -> Result<Option<u64>, StorageError> {
+        let Some(old_block) = self.store.get_block(&old_tip)? else {
+            return Ok(None);
+        };
+        let depth = match common_ancestor(&self.store, &old_block, block)? {
+            Some(ancestor) => old_block.header.height - ancestor.height,
+            None => old_block.header.height + 1,
+        };
+        Ok((depth > 0).then_some(depth))
+    }
+
+    /// Returns the transactions of the blocks retracted when the chain
+    /// ending in `old_tip` gives way to `block` (hashing to `hash`), minus
+    /// those `block`'s branch includes, oldest first.
+    fn retracted_txs(
+        &self,
+        old_tip: BlockHash,
+        hash: BlockHash,
+        block: &Block,
+    ) -> Result<Vec<Transaction>, StorageError> {
+        let Some(old_block) = self.store.get_block(&old_tip)? else {
+            return Ok(Vec::new());
+        };
+        let mut old = (old_tip, old_block);
+        let mut new = (hash, block.clone());
+        let mut retracted = Vec::new();
+        let mut included = HashSet::new();
+        // Step back on the higher branch, or on both at equal heights,
+        // until the branches meet (or run out of stored blocks).
+        while old.0 != new.0 {
+            let (old_height, new_height) = (old.1.header.height, new.1.header.height);
+            if new_height >= old_height {
+                included.extend(new.1.txs.iter().map(Transaction::compute_hash));
+                let parent = new.1.header.parent;
+                match self.store.get_block(&parent)? {
+                    Some(block) if new_height > 0 => new = (parent, block),
+                    _ => break,
+                }
+            }
+            if old_height >= new_height {
+                retracted.push(std::mem::take(&mut old.1.txs));
+                let parent = old.1.header.parent;
+                match self.store.get_block(&parent)? {
+                    Some(block) if old_height > 0 => old = (parent, block),
+                    _ => break,
+                }
+            }
+        }
+        Ok(retracted
+            .into_iter()
+            .rev()
+            .flatten()
+            .filter(|tx| !included.contains(&tx.compute_hash()))
+            .collect())
+    }
+
+    /// Hands the transactions retracted by reorgs since the last call back
+    /// to `tx_pool`, skipping those whose nonce the tip state has used up.
+    ///
+    /// [`propose_block`](Self::propose_block) does this before selecting
+    /// transactions; nodes that only import blocks can call it themselves.
+    pub fn return_retracted_txs<P>(&mut self, tx_pool: &mut P)
+    where
+        P: TxPool,
+    {
+        let txs: Vec<Transaction> = std::mem::take(&mut self.retracted)
+            .into_iter()
+            .filter(|tx| tx.nonce() >= self.state.account(&tx.sender()).nonce)
+            .collect();
+        if !txs.is_empty() {
+            tx_pool.reinsert(txs);
+        }
+    }
+
+    /// Records the outcome of validating and committing a block.
+    fn record_import(&mut self, proposer: AccountId, validation_time: Duration, accepted: bool) {
+        self.view_mut()
+            .proposer_stats
+            .entry(proposer)
+            .or_default()
+            .record(validation_time, accepted);
+    }
+
+    /// Executes and persists an already-validated block and updates the tip.
+    ///
+    /// This performs:
+    ///
+    /// - execution of the block's transactions on top of its parent state,
+    /// - a check that the post-state and receipts match the header's state
+    ///   and receipts roots,
+    /// - persistence of the block, its receipts and ML verdict statistics,
+    ///   and the tip if fork-choice prefers it, in one [`BlockStore`] write,
+    /// - indexing of its transactions, once that write succeeded,
+    /// - fork-choice update via the configured [`ForkChoice`],
+    /// - a [`ReorgEvent`] if the tip switched branches,
+    /// - invariant checks, if enabled.
+    fn commit_block(&mut self, block: SealedBlock) -> Result<BlockHash, ConsensusError> {
+        // 1. Execute the block; failing transactions reject the whole block.
+        let (post_state, receipts) =
+            apply_block_with_receipts(&self.parent_state(&block)?, &block)?;
+        let state_root = post_state.state_root();
+        if state_root != block.header.state_root {
+            return Err(ValidationError::StateRootMismatch {
+                header: block.header.state_root,
+                computed: state_root,
+            }
+            .into());
+        }
+        let receipts_root = receipts_root(&receipts);
+        if receipts_root != block.header.receipts_root {
+            return Err(ValidationError::ReceiptsRootMismatch {
+                header: block.header.receipts_root,
+                computed: receipts_root,
+            }
+            .into());
+        }
+
+        // 2. Take the block's hash from the seal.
+        let new_hash = block.hash();
+
+        // 3. Decide whether this block should become the new tip.
+        let current_tip = self.store.tip()?;
+        let should_update_tip =
+            self.fork_choice
+                .should_update_tip(&self.store, current_tip, &block)?;
+
+        // 4. Measure the reorg, if the new tip would not extend the old
+        //    one.
+        let reorg = match current_tip {
+            Some(old_tip) if should_update_tip && old_tip != block.header.parent => {
+                self.reorg_depth(old_tip, &block)?.map(|depth| ReorgEvent {
+                    old_tip,
+                    new_tip: new_hash,
+                    depth,
+                })
+            }
+            _ => None,
+        };
+        let retracted = match &reorg {
+            Some(event) => self.retracted_txs(event.old_tip, new_hash, &block)?,
+            None => Vec::new(),
+        };
+
+        // 5. Persist the block with its receipts and ML verdict statistics,
+        //    together with the tip if fork-choice prefers it, then index it
+        //    (and cache the tip state).
+        let imported = Arc::new(block.block().clone());
+        let height = block.header.height;
+        let timestamp = block.header.timestamp;
+        let ml_stats = self
+            .ml_stats
+            .as_ref()
+            .map_or_else(Vec::new, |log| log.take(&new_hash));
+        self.store
+            .put_executed_block(block, &receipts, &ml_stats, should_update_tip)?;
+        self.view_mut().tx_index.insert_block(new_hash, &imported);
+        if should_update_tip {
+            self.retracted.extend(retracted);
+            self.state = Arc::new(post_state);
+            self.epoch = Epoch::at(height + 1, &self.state);
+            self.refresh_tip_views(new_hash);
+        }
+
+        if let Some(metrics) = &self.metrics {
+            metrics.blocks_imported.inc();
+            if should_update_tip {
+                metrics.chain_height.set(height as i64);
+                metrics.chain_tip_timestamp_seconds.set(timestamp as i64);
+            }
+            if let Some(event) = &reorg {
+                metrics.observe_reorg(event);
+            }
+        }
+        if let (Some(event), Some(listener)) = (reorg, &mut self.reorg_listener) {
+            listener(&event);
+        }
+        if self.events.is_some() {
+            self.publish(ChainEvent::BlockImported {
+                hash: new_hash,
+                block: imported,
+            });
+            if should_update_tip {
+                self.publish(ChainEvent::NewTip {
+                    hash: new_hash,
+                    height,
+                    reorg_depth: reorg.map(|event| event.depth),
+                });
+            }
+        }
+
+        self.assert_invariants();
+        Ok(new_hash)
+    }
+}
+
+impl<S, V, F> ConsensusEngine<S, V, F>
+where
+    S: BlockStore + Sync,
+    V: BlockValidator,
+    F: ForkChoice,
+{
     /// Proposes a new block using the embedded [`Proposer`].
     ///
     /// This:
-    /// 1. Builds a candidate block on top of the current tip.
-    /// 2. Validates and imports it (so it updates the fork choice if valid).
-    /// 3. Returns the new block hash and the block itself.
+    /// 1. Hands transactions retracted by reorgs back to `tx_pool` (see
+    ///    [`return_retracted_txs`](Self::return_retracted_txs)).
+    /// 2. Builds a candidate block on top of the current tip, stamped with
+    ///    `timestamp` (or with its slot time under slot timing).
+    /// 3. Validates and imports it (so it updates the fork choice if valid).
+    /// 4. Returns the new block hash and the block itself.
+    ///
+    /// If the import fails, the candidate's transactions go back into
+    /// `tx_pool`, except when they may be the cause: an ML rejection drops
+    /// the rejected registration, and any other failure of the block's
+    /// content drops them all. Storage errors, an unavailable ML verifier,
+    /// an exhausted ML verification budget and header problems keep them.
+    #[tracing::instrument(
+        name = "propose_block",
+        skip_all,
+        fields(proposer = %hex::encode(proposer_id.0.as_bytes()))
+    )]
     pub fn propose_block<P>(
         &mut self,
         proposer_id: AccountId,
@@ -90,54 +1190,210 @@ where
     where
         P: TxPool,
     {
-        let block = self
-            .proposer
-            .build_block(&self.store, proposer_id, tx_pool, timestamp);
-        let hash = self.import_block(block.clone())?;
+        self.return_retracted_txs(tx_pool);
+        let block = self.build_candidate(proposer_id, tx_pool, timestamp)?;
+        let hash = match self.import_block(block.clone()) {
+            Ok(hash) => hash,
+            Err(e) => {
+                tx_pool.reinsert(retryable_txs(block, &e));
+                return Err(e);
+            }
+        };
+        if let Some(metrics) = &self.metrics {
+            metrics.blocks_proposed.inc();
+        }
         Ok((hash, block))
     }
 
     /// Validates and imports a block into the chain.
     ///
     /// This method is used both for locally proposed blocks and blocks
-    /// received from the network. It performs:
+    /// received from the network. It performs block validation via the
+    /// configured [`BlockValidator`] and then executes and persists the
+    /// block and updates the fork choice.
     ///
-    /// - block validation via the configured [`BlockValidator`],
-    /// - persistence via [`BlockStore`],
-    /// - fork-choice update via the configured [`ForkChoice`].
+    /// On success, any buffered orphans that now connect to the chain are
+    /// imported as well; orphans that fail to import are dropped.
     pub fn import_block(&mut self, block: Block) -> Result<BlockHash, ConsensusError> {
-        // 1. Run validity predicates (V_base + V_cons).
-        self.validator
-            .validate(&block)
-            .map_err(ConsensusError::from)?;
+        let hash = self.import_linked_block(block)?;
+
+        let mut pending = self.orphans.take_children(&hash);
+        let adopted = !pending.is_empty();
+        while let Some(orphan) = pending.pop() {
+            match self.import_linked_block(orphan) {
+                Ok(h) => pending.extend(self.orphans.take_children(&h)),
+                Err(e) => tracing::warn!(error = %e, "dropping orphan block"),
+            }
+        }
+        if adopted {
+            self.refresh_orphans();
+        }
+
+        Ok(hash)
+    }
+
+    /// Link check, validation, and commit for a single block.
+    #[tracing::instrument(
+        name = "import_block",
+        skip_all,
+        fields(height = block.header.height, hash = field::Empty)
+    )]
+    fn import_linked_block(&mut self, block: Block) -> Result<BlockHash, ConsensusError> {
+        record_block_hash(&Span::current(), &block);
+        let parent = self.link_or_buffer(&block)?;
+
+        // Encode and hash the block once for validation and storage.
+        let block = block.seal();
+
+        // Run validity predicates (V_base + V_cons).
+        let proposer = block.header.proposer;
+        let rejected = self
+            .events
+            .as_ref()
+            .map(|_| (block.hash(), block.header.height));
+        let ctx = ValidationContext::new(parent.as_ref(), &self.store);
+        let started = Instant::now();
+        let validated = self
+            .validator
+            .validate_sealed(&block, &ctx)
+            .map_err(ConsensusError::from);
+        let validation_time = started.elapsed();
+        self.on_ml_rejection(&block, &validated);
+
+        let result = validated.and_then(|()| self.commit_block(block));
+        self.record_import(proposer, validation_time, result.is_ok());
+        self.count_rejection(&result);
+        self.publish_rejection(rejected, proposer, &result);
+        result
+    }
+}
+
+impl<S, V, F> ConsensusEngine<S, V, F>
+where
+    S: BlockStore + Sync,
+    V: AsyncBlockValidator,
+    F: ForkChoice,
+{
+    /// Async counterpart of [`propose_block`](Self::propose_block).
+    ///
+    /// Use this when the validator awaits I/O (e.g. an [`AsyncMlValidity`]
+    /// talking to the ML service) from inside a Tokio runtime.
+    ///
+    /// [`AsyncMlValidity`]: crate::validation::AsyncMlValidity
+    #[tracing::instrument(
+        name = "propose_block",
+        skip_all,
+        fields(proposer = %hex::encode(proposer_id.0.as_bytes()))
+    )]
+    pub async fn propose_block_async<P>(
+        &mut self,
+        proposer_id: AccountId,
+        tx_pool: &mut P,
+        timestamp: u64,
+    ) -> Result<(BlockHash, Block), ConsensusError>
+    where
+        P: TxPool,
+    {
+        self.return_retracted_txs(tx_pool);
+        let block = self.build_candidate(proposer_id, tx_pool, timestamp)?;
+        let hash = match self.import_block_async(block.clone()).await {
+            Ok(hash) => hash,
+            Err(e) => {
+                tx_pool.reinsert(retryable_txs(block, &e));
+                return Err(e);
+            }
+        };
+        if let Some(metrics) = &self.metrics {
+            metrics.blocks_proposed.inc();
+        }
+        Ok((hash, block))
+    }
+
+    /// Async counterpart of [`import_block`](Self::import_block).
+    pub async fn import_block_async(&mut self, block: Block) -> Result<BlockHash, ConsensusError> {
+        let hash = self.import_linked_block_async(block).await?;
+
+        let mut pending = self.orphans.take_children(&hash);
+        let adopted = !pending.is_empty();
+        while let Some(orphan) = pending.pop() {
+            match self.import_linked_block_async(orphan).await {
+                Ok(h) => pending.extend(self.orphans.take_children(&h)),
+                Err(e) => tracing::warn!(error = %e, "dropping orphan block"),
+            }
+        }
+        if adopted {
+            self.refresh_orphans();
+        }
 
-        // 2. Compute the block's hash and height.
-        let new_hash = block.compute_hash();
+        Ok(hash)
+    }
 
-        // 3. Decide whether this block should become the new tip.
-        let current_tip = self.store.tip();
-        let should_update_tip =
-            self.fork_choice
-                .should_update_tip(&self.store, current_tip, &block);
+    /// Async counterpart of [`import_linked_block`](Self::import_linked_block).
+    #[tracing::instrument(
+        name = "import_block",
+        skip_all,
+        fields(height = block.header.height, hash = field::Empty)
+    )]
+    async fn import_linked_block_async(
+        &mut self,
+        block: Block,
+    ) -> Result<BlockHash, ConsensusError> {
+        record_block_hash(&Span::current(), &block);
+        let parent = self.link_or_buffer(&block)?;
 
-        // 4. Persist the block.
-        self.store.put_block(block);
+        // Encode and hash the block once for validation and storage.
+        let block = block.seal();
 
-        // 5. Update tip if fork-choice prefers the new block.
-        if should_update_tip {
-            self.store.set_tip(new_hash);
-        }
+        // Run validity predicates (V_base + V_cons).
+        let proposer = block.header.proposer;
+        let rejected = self
+            .events
+            .as_ref()
+            .map(|_| (block.hash(), block.header.height));
+        let ctx = ValidationContext::new(parent.as_ref(), &self.store);
+        let started = Instant::now();
+        let validated = self
+            .validator
+            .validate_sealed(&block, &ctx)
+            .await
+            .map_err(ConsensusError::from);
+        let validation_time = started.elapsed();
+        self.on_ml_rejection(&block, &validated);
 
-        Ok(new_hash)
+        let result = validated.and_then(|()| self.commit_block(block));
+        self.record_import(proposer, validation_time, result.is_ok());
+        self.count_rejection(&result);
+        self.publish_rejection(rejected, proposer, &result);
+        result
+    }
+}
+
+impl<S, V, F> ConsensusEngine<SharedStore<S>, V, F>
+where
+    S: BlockStore,
+{
+    /// Returns a read handle to this engine's chain.
+    ///
+    /// The reader shares the engine's store and tip view, so it keeps
+    /// answering while the engine is busy importing (e.g. awaiting ML
+    /// validation behind a lock the reader does not need).
+    pub fn reader(&self) -> ChainReader<S> {
+        ChainReader::new(
+            self.store.clone(),
+            Arc::clone(&self.view),
+            self.genesis,
+            self.config.block_time_secs,
+        )
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::execution::EMPTY_STATE_ROOT;
     use crate::types::{
-        Aid, Block, BlockHash, EvidenceHash, EvidenceRef, HASH_LEN, Hash256, Header, Transaction,
-        WmProfile,
+        Aid, Block, BlockHash, EvidenceHash, EvidenceRef, FraudVerdict, HASH_LEN, Hash256, Header,
+        Transaction, WmProfile,
     };
     use std::collections::HashMap;
 
@@ -149,6 +1405,10 @@ mod tests {
     struct InMemoryBlockStore {
         blocks: HashMap<BlockHash, Block>,
         tip: Option<BlockHash>,
+        /// Makes every write fail, like a full disk.
+        fail_writes: bool,
+        /// Makes listing the stored blocks fail.
+        fail_scans: bool,
     }
 
     impl InMemoryBlockStore {
@@ -156,26 +1416,66 @@ mod tests {
             Self {
                 blocks: HashMap::new(),
                 tip: None,
+                fail_writes: false,
+                fail_scans: false,
             }
         }
     }
 
     impl BlockStore for InMemoryBlockStore {
-        fn get_block(&self, hash: &BlockHash) -> Option<Block> {
-            self.blocks.get(hash).cloned()
+        fn get_block(&self, hash: &BlockHash) -> Result<Option<Block>, StorageError> {
+            Ok(self.blocks.get(hash).cloned())
         }
 
-        fn put_block(&mut self, block: Block) {
+        fn put_block(&mut self, block: Block) -> Result<(), StorageError> {
+            if self.fail_writes {
+                return Err(StorageError::MissingColumnFamily("blocks"));
+            }
             let hash = block.compute_hash();
             self.blocks.insert(hash, block);
+            Ok(())
         }
 
-        fn tip(&self) -> Option<BlockHash> {
-            self.tip
+        fn tip(&self) -> Result<Option<BlockHash>, StorageError> {
+            Ok(self.tip)
         }
 
-        fn set_tip(&mut self, hash: BlockHash) {
+        fn set_tip(&mut self, hash: BlockHash) -> Result<(), StorageError> {
+            if self.fail_writes {
+                return Err(StorageError::MissingColumnFamily("meta"));
+            }
             self.tip = Some(hash);
+            Ok(())
+        }
+
+        fn get_hash_at_height(&self, height: u64) -> Result<Option<BlockHash>, StorageError> {
+            let Some(mut cursor) = self.tip else {
+                return Ok(None);
+            };
+            loop {
+                let Some(block) = self.blocks.get(&cursor) else {
+                    return Ok(None);
+                };
+                if block.header.height == height {
+                    return Ok(Some(cursor));
+                }
+                if block.header.height < height {
+                    return Ok(None);
+                }
+                cursor = block.header.parent;
+            }
+        }
+
+        fn block_hashes(&self) -> Result<Vec<BlockHash>, StorageError> {
+            if self.fail_scans {
+                return Err(StorageError::MissingColumnFamily("blocks"));
+            }
+            Ok(self.blocks.keys().copied().collect())
+        }
+
+        fn delete_block(&mut self, hash: &BlockHash) -> Result<(), StorageError> {
+            self.blocks.remove(hash);
+            Ok(())
         }
     }
 
@@ -212,6 +1512,22 @@ mod tests {
             let take = max_txs.min(self.txs.len());
             self.txs.drain(0..take).collect()
         }
+
+        fn peek(&self, max_txs: usize, _max_bytes: usize) -> Vec<Transaction> {
+            self.txs.iter().take(max_txs).cloned().collect()
+        }
+
+        fn remove(&mut self, tx_hash: &TxHash) -> Option<Transaction> {
+            let index = self
+                .txs
+                .iter()
+                .position(|tx| tx.compute_hash() == *tx_hash)?;
+            Some(self.txs.remove(index))
+        }
+
+        fn reinsert(&mut self, txs: Vec<Transaction>) {
+            self.txs.splice(0..0, txs);
+        }
     }
 
     /// Build a minimal RegisterModel tx just to get something in the block.
@@ -229,6 +1545,7 @@ mod tests {
         let tx_reg = crate::types::tx::TxRegisterModel {
             owner,
             aid,
+            chunked: None,
             evidence: evidence_ref,
             fee: 0,
             nonce: 0,
@@ -245,12 +1562,17 @@ mod tests {
             max_block_txs: 100,
             max_block_size_bytes: 1_000_000,
             allow_empty_blocks: true,
+            orphan_buffer_size: 0,
+            slot_timing: false,
+            monotonic_timestamps: true,
+            max_future_drift_secs: 15,
         };
         let store = InMemoryBlockStore::new();
         let validator = AcceptAllValidator;
         let fork_choice = LongestChainForkChoice;
 
-        let mut engine = ConsensusEngine::new(cfg, store, validator, fork_choice);
+        let mut engine = ConsensusEngine::new(cfg, store, validator, fork_choice)
+            .expect("empty store needs no replay");
 
         let proposer_id = dummy_account(1);
         let txs = vec![dummy_register_tx(1, 2)];
@@ -262,7 +1584,7 @@ mod tests {
 
         assert_eq!(block.header.height, 0);
 
-        let tip = engine.tip().expect("tip should be set");
+        let tip = engine.tip().unwrap().expect("tip should be set");
         assert_eq!(tip.0.as_bytes(), hash.0.as_bytes());
     }
 
@@ -273,12 +1595,17 @@ mod tests {
             max_block_txs: 100,
             max_block_size_bytes: 1_000_000,
             allow_empty_blocks: true,
+            orphan_buffer_size: 0,
+            slot_timing: false,
+            monotonic_timestamps: true,
+            max_future_drift_secs: 15,
         };
         let store = InMemoryBlockStore::new();
         let validator = AcceptAllValidator;
         let fork_choice = LongestChainForkChoice;
 
-        let mut engine = ConsensusEngine::new(cfg, store, validator, fork_choice);
+        let mut engine = ConsensusEngine::new(cfg, store, validator, fork_choice)
+            .expect("empty store needs no replay");
 
         let proposer_id = dummy_account(1);
 
@@ -295,12 +1622,20 @@ mod tests {
                 height: 0,
                 timestamp: 1_700_000_001,
                 proposer: proposer_id,
+                txs_root: Hash256([0u8; HASH_LEN]),
+                state_root: Hash256([0u8; HASH_LEN]),
+                receipts_root: Hash256([0u8; HASH_LEN]),
                 pos_proof: None,
             };
-            Block {
+            let mut block = Block {
                 header,
                 txs: vec![dummy_register_tx(3, 4)],
-            }
+            };
+            let (post_state, receipts) =
+                apply_block_with_receipts(&ChainState::new(), &block).expect("alt block executes");
+            block.header.state_root = post_state.state_root();
+            block.header.receipts_root = receipts_root(&receipts);
+            block
         };
         let alt_hash = alt_block.compute_hash();
         engine
@@ -308,7 +1643,7 @@ mod tests {
             .expect("alternate block should also be valid");
 
         // Tip should still point to the original height-0 block (ties stay).
-        let tip1 = engine.tip().unwrap();
+        let tip1 = engine.tip().unwrap().expect("tip should be set");
         assert_eq!(tip1.0.as_bytes(), h0.0.as_bytes());
 
         // Now propose a new block on top of the current tip (height 1).
@@ -317,8 +1652,1011 @@ mod tests {
             .propose_block(proposer_id, &mut tx_pool2, 1_700_000_010)
             .expect("b1 valid");
 
-        let tip2 = engine.tip().unwrap();
+        let tip2 = engine.tip().unwrap().expect("tip should be set");
         assert_eq!(tip2.0.as_bytes(), h1.0.as_bytes());
         assert_ne!(tip2.0.as_bytes(), alt_hash.0.as_bytes());
     }
+
+    #[test]
+    fn longer_side_branch_triggers_reorg_event() {
+        let cfg = ConsensusConfig {
+            block_time_secs: 5,
+            max_block_txs: 100,
+            max_block_size_bytes: 1_000_000,
+            allow_empty_blocks: true,
+            orphan_buffer_size: 0,
+            slot_timing: false,
+            monotonic_timestamps: true,
+            max_future_drift_secs: 15,
+        };
+        let events = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let sink = events.clone();
+        let mut engine = ConsensusEngine::new(
+            cfg,
+            InMemoryBlockStore::new(),
+            AcceptAllValidator,
+            LongestChainForkChoice,
+        )
+        .expect("empty store needs no replay")
+        .with_reorg_listener(move |event| sink.lock().unwrap().push(*event));
+
+        let block = |parent: BlockHash, height: u64, timestamp: u64| Block {
+            header: Header {
+                parent,
+                height,
+                timestamp,
+                proposer: dummy_account(1),
+                txs_root: Hash256([0u8; HASH_LEN]),
+                state_root: Hash256([0u8; HASH_LEN]),
+                receipts_root: Hash256([0u8; HASH_LEN]),
+                pos_proof: None,
+            },
+            txs: Vec::new(),
+        };
+
+        let g = engine
+            .import_block(block(BlockHash(Hash256([0u8; HASH_LEN])), 0, 0))
+            .unwrap();
+        let m1 = engine.import_block(block(g, 1, 10)).unwrap();
+        let m2 = engine.import_block(block(m1, 2, 20)).unwrap();
+
+        // A side branch from genesis catches up (tie, tip stays) and then
+        // overtakes the main branch.
+        let s1 = engine.import_block(block(g, 1, 11)).unwrap();
+        let s2 = engine.import_block(block(s1, 2, 21)).unwrap();
+        assert_eq!(engine.tip().unwrap(), Some(m2));
+        assert!(events.lock().unwrap().is_empty());
+
+        let s3 = engine.import_block(block(s2, 3, 31)).unwrap();
+        assert_eq!(engine.tip().unwrap(), Some(s3));
+        assert_eq!(
+            *events.lock().unwrap(),
+            vec![ReorgEvent {
+                old_tip: m2,
+                new_tip: s3,
+                depth: 2,
+            }]
+        );
+
+        // Extending the new tip is not a reorg.
+        engine.import_block(block(s3, 4, 41)).unwrap();
+        assert_eq!(events.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn quorum_commit_adopts_and_locks_the_committed_branch() {
+        use super::super::votes::QuorumForkChoice;
+
+        let validators = ValidatorSet::new((1..=4).map(dummy_account));
+        let mut engine = ConsensusEngine::new(
+            ConsensusConfig::default(),
+            InMemoryBlockStore::new(),
+            AcceptAllValidator,
+            QuorumForkChoice::new(LongestChainForkChoice),
+        )
+        .expect("empty store needs no replay")
+        .with_votes(validators);
+
+        let block = |parent: BlockHash, height: u64, timestamp: u64| Block {
+            header: Header {
+                parent,
+                height,
+                timestamp,
+                proposer: dummy_account(1),
+                txs_root: Hash256([0u8; HASH_LEN]),
+                state_root: Hash256([0u8; HASH_LEN]),
+                receipts_root: Hash256([0u8; HASH_LEN]),
+                pos_proof: None,
+            },
+            txs: Vec::new(),
+        };
+        let vote = |block_hash: BlockHash, height: u64, validator: u8| Vote {
+            block_hash,
+            height,
+            validator: dummy_account(validator),
+            validator_key: crate::types::PublicKey(vec![]),
+            signature: crate::types::Signature(vec![]),
+        };
+
+        let g = engine
+            .import_block(block(BlockHash(Hash256([0u8; HASH_LEN])), 0, 0))
+            .unwrap();
+        let m1 = engine.import_block(block(g, 1, 10)).unwrap();
+        let m2 = engine.import_block(block(m1, 2, 20)).unwrap();
+        let s1 = engine.import_block(block(g, 1, 11)).unwrap();
+        assert_eq!(engine.tip().unwrap(), Some(m2));
+
+        // Two of four votes are not a quorum; the third commits the side
+        // branch and retracts the longer main branch.
+        assert!(engine.add_vote(vote(s1, 1, 1)).unwrap().is_none());
+        assert!(engine.add_vote(vote(s1, 1, 2)).unwrap().is_none());
+        let commit = engine.add_vote(vote(s1, 1, 3)).unwrap().expect("quorum");
+        assert_eq!(commit.block_hash, s1);
+        assert_eq!(engine.last_commit().map(|c| c.height), Some(1));
+        assert_eq!(engine.tip().unwrap(), Some(s1));
+
+        // The main branch can no longer win, however long it grows.
+        engine.import_block(block(m2, 3, 30)).unwrap();
+        assert_eq!(engine.tip().unwrap(), Some(s1));
+        let s2 = engine.import_block(block(s1, 2, 21)).unwrap();
+        assert_eq!(engine.tip().unwrap(), Some(s2));
+
+        assert!(matches!(
+            engine.add_vote(vote(m1, 1, 4)),
+            Err(ConsensusError::Vote(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn propose_block_async_updates_tip() {
+        let cfg = ConsensusConfig {
+            block_time_secs: 5,
+            max_block_txs: 100,
+            max_block_size_bytes: 1_000_000,
+            allow_empty_blocks: true,
+            orphan_buffer_size: 0,
+            slot_timing: false,
+            monotonic_timestamps: true,
+            max_future_drift_secs: 15,
+        };
+        let store = InMemoryBlockStore::new();
+        let mut engine =
+            ConsensusEngine::new(cfg, store, AcceptAllValidator, LongestChainForkChoice)
+                .expect("empty store needs no replay");
+
+        let mut tx_pool = TestTxPool::new(vec![dummy_register_tx(1, 2)]);
+        let (hash, block) = engine
+            .propose_block_async(dummy_account(1), &mut tx_pool, 1_700_000_000)
+            .await
+            .expect("async proposal should succeed");
+
+        assert_eq!(block.header.height, 0);
+        assert_eq!(engine.tip().unwrap(), Some(hash));
+    }
+
+    #[test]
+    fn validator_report_counts_accepted_and_rejected_blocks() {
+        let store = InMemoryBlockStore::new();
+        let mut engine = ConsensusEngine::new(
+            ConsensusConfig::default(),
+            store,
+            AcceptAllValidator,
+            LongestChainForkChoice,
+        )
+        .expect("empty store needs no replay");
+        let proposer_id = dummy_account(7);
+
+        let mut tx_pool = TestTxPool::new(vec![dummy_register_tx(1, 2)]);
+        engine
+            .propose_block(proposer_id, &mut tx_pool, 1_700_000_000)
+            .expect("first block is valid");
+
+        // Replays owner 1's nonce 0, so execution rejects the block.
+        let mut tx_pool = TestTxPool::new(vec![dummy_register_tx(1, 3)]);
+        assert!(
+            engine
+                .propose_block(proposer_id, &mut tx_pool, 1_700_000_005)
+                .is_err()
+        );
+
+        let report = engine.validator_report(&proposer_id).unwrap();
+        assert_eq!(report.canonical_blocks, 1);
+        assert_eq!(report.stats.blocks_accepted, 1);
+        assert_eq!(report.stats.blocks_rejected, 1);
+        assert!(report.stats.average_validation_time().is_some());
+
+        let other = engine.validator_report(&dummy_account(8)).unwrap();
+        assert_eq!(other.canonical_blocks, 0);
+        assert_eq!(other.stats, ProposerStats::default());
+    }
+
+    #[test]
+    fn import_block_executes_transactions_into_state() {
+        let cfg = ConsensusConfig::default();
+        let mut engine = ConsensusEngine::new(
+            cfg,
+            InMemoryBlockStore::new(),
+            AcceptAllValidator,
+            LongestChainForkChoice,
+        )
+        .expect("empty store needs no replay");
+
+        let tx = dummy_register_tx(1, 2);
+        let mut tx_pool = TestTxPool::new(vec![tx.clone()]);
+        let (hash, _) = engine
+            .propose_block(dummy_account(1), &mut tx_pool, 1_700_000_000)
+            .expect("proposal should succeed");
+
+        let location = engine
+            .locate_tx(&tx.compute_hash())
+            .unwrap()
+            .expect("transaction is indexed");
+        assert_eq!(
+            (location.block_hash, location.height, location.index),
+            (hash, 0, 0)
+        );
+        assert!(engine.locate_tx(&TxHash(dummy_hash(9))).unwrap().is_none());
+
+        let meta = engine
+            .state()
+            .artefact(&Aid(dummy_hash(2)))
+            .expect("artefact should be registered");
+        assert_eq!(meta.owner, dummy_account(1));
+        assert_eq!(engine.state().account(&dummy_account(1)).nonce, 1);
+    }
+
+    #[test]
+    fn import_block_rejects_block_that_fails_execution() {
+        let cfg = ConsensusConfig::default();
+        let mut engine = ConsensusEngine::new(
+            cfg,
+            InMemoryBlockStore::new(),
+            AcceptAllValidator,
+            LongestChainForkChoice,
+        )
+        .expect("empty store needs no replay");
+
+        // Same owner twice with nonce 0: the second tx has a bad nonce.
+        let mut tx_pool = TestTxPool::new(vec![dummy_register_tx(1, 2), dummy_register_tx(1, 3)]);
+        let err = engine
+            .propose_block(dummy_account(1), &mut tx_pool, 1_700_000_000)
+            .unwrap_err();
+
+        assert!(matches!(err, ConsensusError::Execution(_)));
+        assert!(engine.tip().unwrap().is_none());
+        assert!(engine.state().artefact(&Aid(dummy_hash(2))).is_none());
+        // The transactions caused the failure, so they are not retried.
+        assert!(tx_pool.txs.is_empty());
+    }
+
+    #[test]
+    fn failed_proposal_returns_transactions_unless_they_are_at_fault() {
+        let mut engine = ConsensusEngine::new(
+            ConsensusConfig::default(),
+            InMemoryBlockStore::new(),
+            AcceptAllValidator,
+            LongestChainForkChoice,
+        )
+        .expect("empty store needs no replay");
+        engine.store_mut().fail_writes = true;
+
+        let tx = dummy_register_tx(1, 2);
+        let mut tx_pool = TestTxPool::new(vec![tx.clone()]);
+        let err = engine
+            .propose_block(dummy_account(1), &mut tx_pool, 1_700_000_000)
+            .unwrap_err();
+        assert!(matches!(err, ConsensusError::Storage(_)));
+        assert_eq!(tx_pool.txs.len(), 1);
+        assert_eq!(tx_pool.txs[0].compute_hash(), tx.compute_hash());
+
+        let rejected: ConsensusError = ValidationError::MlRejected {
+            aid: Aid(dummy_hash(2)),
+            verdict: FraudVerdict {
+                scheme_id: "wm-v1".to_string(),
+                score: Some(0.1),
+            },
+        }
+        .into();
+        let block = Block {
+            header: child_of(BlockHash(dummy_hash(0)), 1).header,
+            txs: vec![dummy_register_tx(1, 2), dummy_register_tx(3, 4)],
+        };
+        let kept = retryable_txs(block.clone(), &rejected);
+        assert_eq!(kept.len(), 1);
+        assert_eq!(kept[0].compute_hash(), block.txs[1].compute_hash());
+        let unavailable: ConsensusError =
+            ValidationError::MlUnavailable("timeout".to_string()).into();
+        assert_eq!(retryable_txs(block.clone(), &unavailable).len(), 2);
+        let bad_nonce = ConsensusError::Execution("bad nonce".to_string());
+        assert!(retryable_txs(block, &bad_nonce).is_empty());
+    }
+
+    #[test]
+    fn reorg_returns_retracted_transactions_to_the_pool() {
+        let mut engine = ConsensusEngine::new(
+            ConsensusConfig::default(),
+            InMemoryBlockStore::new(),
+            AcceptAllValidator,
+            LongestChainForkChoice,
+        )
+        .expect("empty store needs no replay");
+        let proposer_id = dummy_account(1);
+        let (g, _) = engine
+            .propose_block(proposer_id, &mut TestTxPool::new(Vec::new()), 1_700_000_000)
+            .expect("genesis valid");
+        let genesis_state = engine.state().clone();
+
+        // The main branch registers owner 1's and owner 3's artefacts.
+        let mut tx_pool = TestTxPool::new(vec![dummy_register_tx(1, 2), dummy_register_tx(3, 4)]);
+        engine
+            .propose_block(proposer_id, &mut tx_pool, 1_700_000_010)
+            .expect("main block valid");
+
+        // A longer side branch includes only owner 3's registration.
+        let executed = |parent, height, timestamp, txs, state: &ChainState| {
+            let mut block = Block {
+                header: Header {
+                    timestamp,
+                    ..child_of(parent, height).header
+                },
+                txs,
+            };
+            let (post_state, receipts) =
+                apply_block_with_receipts(state, &block).expect("side block executes");
+            block.header.state_root = post_state.state_root();
+            block.header.receipts_root = receipts_root(&receipts);
+            (block, post_state)
+        };
+        let (s1, s1_state) = executed(
+            g,
+            1,
+            1_700_000_011,
+            vec![dummy_register_tx(3, 4)],
+            &genesis_state,
+        );
+        let s1 = engine.import_block(s1).unwrap();
+        let (s2, _) = executed(s1, 2, 1_700_000_021, Vec::new(), &s1_state);
+        let s2 = engine.import_block(s2).unwrap();
+        assert_eq!(engine.tip().unwrap(), Some(s2));
+
+        let mut tx_pool = TestTxPool::new(Vec::new());
+        engine.return_retracted_txs(&mut tx_pool);
+        assert_eq!(tx_pool.txs.len(), 1);
+        assert_eq!(
+            tx_pool.txs[0].compute_hash(),
+            dummy_register_tx(1, 2).compute_hash()
+        );
+
+        // They are handed back only once.
+        engine.return_retracted_txs(&mut tx_pool);
+        assert_eq!(tx_pool.txs.len(), 1);
+    }
+
+    #[test]
+    fn events_report_imports_tips_and_rejections() {
+        let (sender, mut events) = broadcast::channel(16);
+        let mut engine = ConsensusEngine::new(
+            ConsensusConfig::default(),
+            InMemoryBlockStore::new(),
+            AcceptAllValidator,
+            LongestChainForkChoice,
+        )
+        .expect("empty store needs no replay")
+        .with_events(sender);
+        let mut tx_pool = TestTxPool::new(vec![dummy_register_tx(1, 2)]);
+        let mut block = engine
+            .build_candidate(dummy_account(1), &mut tx_pool, 1_700_000_000)
+            .expect("candidate builds");
+        let state_root = block.header.state_root;
+
+        block.header.state_root = EMPTY_STATE_ROOT;
+        engine.import_block(block.clone()).unwrap_err();
+        match events.try_recv().unwrap() {
+            ChainEvent::BlockRejected {
+                hash,
+                height,
+                kind,
+                code,
+                ..
+            } => {
+                assert_eq!((hash, height), (block.compute_hash(), 0));
+                assert_eq!(kind, RejectionKind::Invalid);
+                assert_eq!(code, "state_root_mismatch");
+            }
+            other => panic!("unexpected event: {other:?}"),
+        }
+
+        block.header.state_root = state_root;
+        let hash = engine.import_block(block).unwrap();
+        assert!(matches!(
+            events.try_recv().unwrap(),
+            ChainEvent::BlockImported { hash: h, ref block } if h == hash && block.txs.len() == 1
+        ));
+        assert!(matches!(
+            events.try_recv().unwrap(),
+            ChainEvent::NewTip { hash: h, height: 0, reorg_depth: None } if h == hash
+        ));
+        assert!(events.try_recv().is_err());
+    }
+
+    #[test]
+    fn import_block_checks_state_root() {
+        let mut engine = ConsensusEngine::new(
+            ConsensusConfig::default(),
+            InMemoryBlockStore::new(),
+            AcceptAllValidator,
+            LongestChainForkChoice,
+        )
+        .expect("empty store needs no replay");
+        let mut tx_pool = TestTxPool::new(vec![dummy_register_tx(1, 2)]);
+        let mut block = engine
+            .build_candidate(dummy_account(1), &mut tx_pool, 1_700_000_000)
+            .expect("candidate builds");
+        let state_root = block.header.state_root;
+        assert_ne!(state_root, EMPTY_STATE_ROOT);
+
+        block.header.state_root = EMPTY_STATE_ROOT;
+        let err = engine.import_block(block.clone()).unwrap_err();
+        assert!(matches!(err, ConsensusError::Validation(_)));
+        assert!(engine.tip().unwrap().is_none());
+
+        block.header.state_root = state_root;
+        engine.import_block(block).expect("correct root imports");
+        assert_eq!(engine.state().state_root(), state_root);
+    }
+
+    #[test]
+    fn import_block_checks_and_stores_receipts() {
+        let ml_stats = MlStatsLog::new();
+        let mut engine = ConsensusEngine::new(
+            ConsensusConfig::default(),
+            crate::storage::InMemoryBlockStore::new(),
+            AcceptAllValidator,
+            LongestChainForkChoice,
+        )
+        .expect("empty store needs no replay")
+        .with_ml_stats(ml_stats.clone());
+        let mut tx_pool = TestTxPool::new(vec![dummy_register_tx(1, 2)]);
+        let mut block = engine
+            .build_candidate(dummy_account(1), &mut tx_pool, 1_700_000_000)
+            .expect("candidate builds");
+        let receipts_root = block.header.receipts_root;
+
+        block.header.receipts_root = Hash256([0u8; HASH_LEN]);
+        match engine.import_block(block.clone()).unwrap_err() {
+            ConsensusError::Validation(err) => assert_eq!(err.kind(), "receipts_root_mismatch"),
+            other => panic!("unexpected error: {other:?}"),
+        }
+
+        block.header.receipts_root = receipts_root;
+        let stats = vec![crate::execution::MlVerdictStats {
+            tx_hash: block.txs[0].compute_hash(),
+            index: 0,
+            aid: Aid(dummy_hash(2)),
+            scheme_id: "wm-test".to_string(),
+            decision: "accepted".to_string(),
+            score: Some(0.9),
+            trigger_acc: None,
+            feat_dist: None,
+            logit_stat: None,
+            latency_ms: Some(5),
+        }];
+        ml_stats.record(block.compute_hash(), stats.clone());
+        let hash = engine
+            .import_block(block.clone())
+            .expect("correct root imports");
+        let receipts = engine
+            .store()
+            .get_receipts(&hash)
+            .unwrap()
+            .expect("receipts are stored");
+        assert_eq!(receipts.len(), 1);
+        assert_eq!(receipts[0].tx_hash, block.txs[0].compute_hash());
+        assert_eq!(crate::execution::receipts_root(&receipts), receipts_root);
+        assert_eq!(engine.store().get_ml_stats(&hash).unwrap(), Some(stats));
+        assert!(ml_stats.take(&hash).is_empty());
+    }
+
+    fn child_of(parent: BlockHash, height: u64) -> Block {
+        Block {
+            header: Header {
+                parent,
+                height,
+                timestamp: 1_700_000_000 + height,
+                proposer: dummy_account(1),
+                txs_root: Hash256([0u8; HASH_LEN]),
+                state_root: Hash256([0u8; HASH_LEN]),
+                receipts_root: Hash256([0u8; HASH_LEN]),
+                pos_proof: None,
+            },
+            txs: Vec::new(),
+        }
+    }
+
+    /// Rejects blocks unless the context names their stored parent.
+    struct ParentLinkValidator;
+
+    impl BlockValidator for ParentLinkValidator {
+        fn validate(
+            &self,
+            block: &Block,
+            ctx: &ValidationContext<'_>,
+        ) -> Result<(), ValidationError> {
+            if ctx.height != block.header.height {
+                return Err(ValidationError::Invalid("wrong context height"));
+            }
+            let Some(parent) = ctx.parent else {
+                return Ok(());
+            };
+            // Not imported, as its methods would clash with `BlockStore`'s
+            // on the test store.
+            let stored =
+                super::super::validator::ReadStore::get_block(ctx.store, &block.header.parent)
+                    .map_err(|_| ValidationError::Invalid("store read failed"))?;
+            if stored.map(|b| b.compute_hash()) != Some(parent.compute_hash()) {
+                return Err(ValidationError::Invalid("parent not stored"));
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn validators_see_the_parent_and_the_stored_chain() {
+        let mut engine = ConsensusEngine::new(
+            ConsensusConfig::default(),
+            InMemoryBlockStore::new(),
+            ParentLinkValidator,
+            LongestChainForkChoice,
+        )
+        .expect("empty store needs no replay");
+        let mut tx_pool = TestTxPool::new(Vec::new());
+        let proposer_id = dummy_account(1);
+        for timestamp in [1_700_000_000, 1_700_000_010, 1_700_000_020] {
+            engine
+                .propose_block(proposer_id, &mut tx_pool, timestamp)
+                .expect("context links every block to its parent");
+        }
+        let tip = engine.tip().unwrap().expect("tip");
+        let tip = engine.store().get_block(&tip).unwrap().expect("tip stored");
+        assert_eq!(tip.header.height, 2);
+    }
+
+    /// Rejects every block above genesis as carrying a fake artefact.
+    struct FakeArtefactValidator;
+
+    impl BlockValidator for FakeArtefactValidator {
+        fn validate(
+            &self,
+            block: &Block,
+            _ctx: &ValidationContext<'_>,
+        ) -> Result<(), ValidationError> {
+            if block.header.height == 0 {
+                return Ok(());
+            }
+            Err(ValidationError::MlRejected {
+                aid: Aid(dummy_hash(4)),
+                verdict: FraudVerdict {
+                    scheme_id: "wm-v1".to_string(),
+                    score: Some(0.1),
+                },
+            })
+        }
+    }
+
+    #[test]
+    fn ml_rejections_yield_one_fraud_proof_per_block() {
+        let reporter = Keypair::generate();
+        let mut engine = ConsensusEngine::new(
+            ConsensusConfig::default(),
+            InMemoryBlockStore::new(),
+            FakeArtefactValidator,
+            LongestChainForkChoice,
+        )
+        .expect("empty store needs no replay")
+        .with_fraud_reporter(reporter.clone());
+
+        let g = engine
+            .import_block(child_of(BlockHash(dummy_hash(0)), 0))
+            .expect("genesis imports");
+        let fake = child_of(g, 1);
+        let fake_hash = fake.compute_hash();
+        assert!(engine.import_block(fake.clone()).is_err());
+        assert!(engine.import_block(fake).is_err());
+
+        let proofs = engine.take_fraud_proofs();
+        assert_eq!(proofs.len(), 1);
+        assert_eq!(proofs[0].block_hash, fake_hash);
+        assert_eq!(proofs[0].height, 1);
+        assert_eq!(proofs[0].proposer, dummy_account(1));
+        assert_eq!(proofs[0].reporter, reporter.account_id());
+        assert_eq!(proofs[0].verify_signature(), Ok(()));
+        assert_eq!(proofs[0].verdict.scheme_id, "wm-v1");
+        assert!(engine.take_fraud_proofs().is_empty());
+
+        // Nobody reports their own block.
+        let mut own = child_of(g, 1);
+        own.header.proposer = reporter.account_id();
+        assert!(engine.import_block(own).is_err());
+        assert!(engine.take_fraud_proofs().is_empty());
+    }
+
+    #[test]
+    fn import_block_rejects_unknown_parent_and_bad_height() {
+        let mut engine = ConsensusEngine::new(
+            ConsensusConfig::default(),
+            InMemoryBlockStore::new(),
+            AcceptAllValidator,
+            LongestChainForkChoice,
+        )
+        .expect("empty store needs no replay");
+
+        let orphan = child_of(BlockHash(dummy_hash(9)), 1);
+        let err = engine.import_block(orphan).unwrap_err();
+        assert!(matches!(err, ConsensusError::Storage(_)));
+        assert_eq!(engine.orphan_count(), 0);
+
+        let genesis = child_of(BlockHash(dummy_hash(0)), 0);
+        let g = engine.import_block(genesis).expect("genesis imports");
+
+        let skip = child_of(g, 5);
+        let err = engine.import_block(skip).unwrap_err();
+        assert!(matches!(err, ConsensusError::Validation(_)));
+        assert_eq!(engine.tip().unwrap(), Some(g));
+    }
+
+    #[test]
+    fn import_block_rejects_timestamps_not_after_the_parent() {
+        let mut engine = ConsensusEngine::new(
+            ConsensusConfig::default(),
+            InMemoryBlockStore::new(),
+            AcceptAllValidator,
+            LongestChainForkChoice,
+        )
+        .expect("empty store needs no replay");
+        let g = engine
+            .import_block(child_of(BlockHash(dummy_hash(0)), 0))
+            .expect("genesis imports");
+
+        let mut stale = child_of(g, 1);
+        stale.header.timestamp = 1_700_000_000;
+        let err = engine.import_block(stale.clone()).unwrap_err();
+        assert!(matches!(err, ConsensusError::Validation(_)));
+
+        engine.config.monotonic_timestamps = false;
+        engine.import_block(stale).expect("check disabled");
+    }
+
+    #[test]
+    fn slot_timing_stamps_proposals_and_rejects_off_slot_blocks() {
+        let cfg = ConsensusConfig {
+            block_time_secs: 5,
+            slot_timing: true,
+            ..ConsensusConfig::default()
+        };
+        let genesis = GenesisConfig::default();
+        let mut engine = ConsensusEngine::with_genesis(
+            cfg,
+            &genesis,
+            InMemoryBlockStore::new(),
+            AcceptAllValidator,
+            LongestChainForkChoice,
+        )
+        .expect("fresh store accepts genesis");
+
+        // The wall-clock timestamp passed in is replaced by the slot time.
+        let mut pool = TestTxPool::new(Vec::new());
+        let (h1, b1) = engine
+            .propose_block(dummy_account(1), &mut pool, 1_700_000_123)
+            .expect("proposal imports");
+        assert_eq!(b1.header.timestamp, genesis.timestamp + 5);
+        assert_eq!(
+            engine.slot_timestamp(2).unwrap(),
+            Some(genesis.timestamp + 10)
+        );
+
+        let mut late = child_of(h1, 2);
+        late.header.timestamp = genesis.timestamp + 11;
+        let err = engine.import_block(late).unwrap_err();
+        assert!(matches!(err, ConsensusError::Validation(_)));
+
+        let mut on_slot = child_of(h1, 2);
+        on_slot.header.timestamp = genesis.timestamp + 10;
+        engine.import_block(on_slot).expect("on-slot block imports");
+    }
+
+    #[test]
+    fn import_block_surfaces_storage_write_failures() {
+        let mut engine = ConsensusEngine::new(
+            ConsensusConfig::default(),
+            InMemoryBlockStore::new(),
+            AcceptAllValidator,
+            LongestChainForkChoice,
+        )
+        .expect("empty store needs no replay");
+        let g = engine
+            .import_block(child_of(BlockHash(dummy_hash(0)), 0))
+            .expect("genesis imports");
+
+        engine.store_mut().fail_writes = true;
+        let err = engine.import_block(child_of(g, 1)).unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            "storage error: missing column family 'blocks'"
+        );
+        assert_eq!(engine.tip().unwrap(), Some(g));
+        assert_eq!(engine.proposer_stats(&dummy_account(1)).blocks_rejected, 1);
+    }
+
+    #[test]
+    fn orphans_are_imported_once_their_parent_arrives() {
+        let cfg = ConsensusConfig {
+            orphan_buffer_size: 8,
+            ..ConsensusConfig::default()
+        };
+        let metrics = ConsensusMetrics::register(
+            &prometheus::Registry::new(),
+            &crate::metrics::HistogramBuckets::default(),
+        )
+        .unwrap();
+        let mut engine = ConsensusEngine::new(
+            cfg,
+            InMemoryBlockStore::new(),
+            AcceptAllValidator,
+            LongestChainForkChoice,
+        )
+        .expect("empty store needs no replay")
+        .with_metrics(metrics.clone());
+
+        let b0 = child_of(BlockHash(dummy_hash(0)), 0);
+        let b1 = child_of(b0.compute_hash(), 1);
+        let b2 = child_of(b1.compute_hash(), 2);
+        let b2_hash = b2.compute_hash();
+
+        // Arrive in reverse order.
+        assert!(engine.import_block(b2).is_err());
+        assert!(engine.import_block(b1).is_err());
+        assert_eq!(engine.orphan_count(), 2);
+
+        engine.import_block(b0).expect("genesis imports");
+
+        assert_eq!(engine.orphan_count(), 0);
+        assert_eq!(engine.tip().unwrap(), Some(b2_hash));
+        assert_eq!(metrics.blocks_orphaned.get(), 2);
+        assert_eq!(metrics.blocks_imported.get(), 3);
+        assert_eq!(metrics.chain_height.get(), 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "consensus invariant violated: finalized block")]
+    fn invariant_checks_catch_reorg_below_finality() {
+        let mut engine = ConsensusEngine::new(
+            ConsensusConfig::default(),
+            InMemoryBlockStore::new(),
+            AcceptAllValidator,
+            LongestChainForkChoice,
+        )
+        .expect("empty store needs no replay")
+        .with_invariant_checks(&InvariantConfig {
+            enabled: true,
+            finality_depth: 1,
+        });
+
+        // Main chain 0..=2 finalizes block 1.
+        let b0 = child_of(BlockHash(dummy_hash(0)), 0);
+        let b0_hash = b0.compute_hash();
+        let mut parent = b0_hash;
+        engine.import_block(b0).unwrap();
+        for height in 1..=2 {
+            let block = child_of(parent, height);
+            parent = engine.import_block(block).unwrap();
+        }
+
+        // A longer branch from block 0 is fine for the fork choice but
+        // retracts the finalized block.
+        let mut parent = b0_hash;
+        for height in 1..=3 {
+            let mut block = child_of(parent, height);
+            block.header.timestamp += 100;
+            parent = engine.import_block(block).unwrap();
+        }
+    }
+
+    #[test]
+    fn with_genesis_initialises_store_and_state() {
+        use crate::consensus::genesis::{GenesisAccount, GenesisConfig};
+
+        let genesis = GenesisConfig {
+            accounts: vec![GenesisAccount {
+                account: dummy_account(1),
+                balance: 500,
+                stake: 0,
+            }],
+            ..GenesisConfig::default()
+        };
+        let mut engine = ConsensusEngine::with_genesis(
+            ConsensusConfig::default(),
+            &genesis,
+            InMemoryBlockStore::new(),
+            AcceptAllValidator,
+            LongestChainForkChoice,
+        )
+        .expect("empty store accepts genesis");
+
+        assert_eq!(engine.tip().unwrap(), Some(genesis.genesis_hash()));
+        assert_eq!(engine.state().account(&dummy_account(1)).balance, 500);
+
+        // The next proposal builds on genesis.
+        let (_, block) = engine
+            .propose_block(
+                dummy_account(1),
+                &mut TestTxPool::new(vec![]),
+                1_700_000_100,
+            )
+            .expect("proposal on genesis");
+        assert_eq!(block.header.height, 1);
+
+        // Any other height-0 block is rejected.
+        let err = engine
+            .import_block(child_of(BlockHash(dummy_hash(0)), 0))
+            .unwrap_err();
+        assert!(matches!(err, ConsensusError::Validation(_)));
+    }
+
+    #[test]
+    fn current_epoch_moves_on_after_the_epoch_end_block() {
+        use crate::consensus::genesis::{GenesisAccount, GenesisConfig};
+        use crate::execution::StakingConfig;
+        use crate::types::{Signature, TxStake};
+
+        let genesis = GenesisConfig {
+            accounts: vec![
+                GenesisAccount {
+                    account: dummy_account(1),
+                    balance: 0,
+                    stake: 100,
+                },
+                GenesisAccount {
+                    account: dummy_account(2),
+                    balance: 50,
+                    stake: 0,
+                },
+            ],
+            staking: StakingConfig {
+                epoch_length: 3,
+                ..StakingConfig::default()
+            },
+            ..GenesisConfig::default()
+        };
+        let mut engine = ConsensusEngine::with_genesis(
+            ConsensusConfig::default(),
+            &genesis,
+            InMemoryBlockStore::new(),
+            AcceptAllValidator,
+            LongestChainForkChoice,
+        )
+        .expect("empty store accepts genesis");
+        assert_eq!(engine.current_epoch().number, 0);
+        assert_eq!(
+            engine.current_epoch().validators.validators(),
+            &[dummy_account(1)]
+        );
+
+        let stake = Transaction::Stake(TxStake {
+            staker: dummy_account(2),
+            amount: 50,
+            fee: 0,
+            nonce: 0,
+            signature: Signature(Vec::new()),
+        });
+        let mut pool = TestTxPool::new(vec![stake]);
+        engine
+            .propose_block(dummy_account(1), &mut pool, 1_700_000_100)
+            .expect("block 1");
+        // Stake bonded mid-epoch waits for the epoch end.
+        assert_eq!(engine.current_epoch().number, 0);
+        assert_eq!(engine.current_epoch().validators.len(), 1);
+
+        engine
+            .propose_block(dummy_account(1), &mut pool, 1_700_000_105)
+            .expect("block 2");
+        let epoch = engine.current_epoch();
+        assert_eq!((epoch.number, epoch.start_height), (1, 3));
+        assert_eq!(
+            epoch.validators.validators(),
+            &[dummy_account(1), dummy_account(2)]
+        );
+    }
+
+    #[test]
+    fn prune_keeps_genesis_and_replays_from_checkpoint() {
+        use crate::consensus::genesis::GenesisConfig;
+        use crate::consensus::prune::{Pruner, PruningConfig};
+        use crate::storage::InMemoryBlockStore as PrunableStore;
+
+        let genesis = GenesisConfig::default();
+        let mut engine = ConsensusEngine::with_genesis(
+            ConsensusConfig::default(),
+            &genesis,
+            PrunableStore::new(),
+            AcceptAllValidator,
+            LongestChainForkChoice,
+        )
+        .expect("empty store accepts genesis");
+        for byte in 1..=6u8 {
+            let mut tx_pool = TestTxPool::new(vec![dummy_register_tx(byte, byte)]);
+            engine
+                .propose_block(
+                    dummy_account(1),
+                    &mut tx_pool,
+                    1_700_000_000 + u64::from(byte),
+                )
+                .expect("proposal should succeed");
+        }
+
+        let pruner = Pruner::from_config(&PruningConfig {
+            keep_blocks: 2,
+            finality_depth: 1,
+            ..PruningConfig::default()
+        });
+        let report = engine.prune(&pruner, 1_700_000_100).unwrap();
+        assert_eq!(report.blocks_pruned, 4);
+        assert_eq!(report.checkpoint_height, Some(5));
+
+        let store = engine.store();
+        assert!(store.get_block_by_height(0).unwrap().is_some());
+        assert!(store.get_block_by_height(4).unwrap().is_none());
+        assert!(store.get_block_by_height(5).unwrap().is_some());
+
+        // A restart rebuilds the same state without the pruned blocks.
+        let tip = engine.tip().unwrap().unwrap();
+        let replayed = replay_chain(store, &tip, &genesis.initial_state()).unwrap();
+        assert_eq!(replayed.artefacts().count(), 6);
+        assert_eq!(
+            replayed.artefacts().count(),
+            engine.state().artefacts().count()
+        );
+
+        // Nothing new to prune until the chain grows.
+        let again = engine.prune(&pruner, 1_700_000_100).unwrap();
+        assert_eq!(again.blocks_pruned, 0);
+    }
+
+    #[test]
+    fn with_genesis_rejects_store_from_another_network() {
+        use crate::consensus::genesis::GenesisConfig;
+
+        let ours = GenesisConfig::default();
+        let theirs = GenesisConfig {
+            chain_id: "other-net".to_string(),
+            ..GenesisConfig::default()
+        };
+
+        let mut store = InMemoryBlockStore::new();
+        let block = theirs.genesis_block();
+        let hash = block.compute_hash();
+        store.put_block(block).unwrap();
+        store.set_tip(hash).unwrap();
+
+        let result = ConsensusEngine::with_genesis(
+            ConsensusConfig::default(),
+            &ours,
+            store,
+            AcceptAllValidator,
+            LongestChainForkChoice,
+        );
+        assert!(matches!(result, Err(ConsensusError::Storage(_))));
+    }
+
+    #[test]
+    fn new_fails_when_stored_chain_does_not_replay() {
+        let mut store = InMemoryBlockStore::new();
+        let mut block = child_of(BlockHash(dummy_hash(0)), 0);
+        block.txs = vec![dummy_register_tx(1, 2), dummy_register_tx(1, 2)];
+        let hash = block.compute_hash();
+        store.put_block(block).unwrap();
+        store.set_tip(hash).unwrap();
+
+        let result = ConsensusEngine::new(
+            ConsensusConfig::default(),
+            store,
+            AcceptAllValidator,
+            LongestChainForkChoice,
+        );
+        assert!(matches!(result, Err(ConsensusError::Execution(_))));
+    }
+
+    #[test]
+    fn new_fails_when_transactions_cannot_be_indexed() {
+        let mut store = InMemoryBlockStore::new();
+        store.fail_scans = true;
+
+        let result = ConsensusEngine::new(
+            ConsensusConfig::default(),
+            store,
+            AcceptAllValidator,
+            LongestChainForkChoice,
+        );
+        assert!(matches!(result, Err(ConsensusError::Storage(_))));
+    }
 }
@@ -0,0 +1,112 @@
+//! Epochs: spans of blocks sharing one validator set and parameter set.
+//!
+//! Heights are split into epochs of `genesis.staking.epoch_length` blocks
+//! (see [`StakingConfig`](crate::execution::StakingConfig)). An [`Epoch`]
+//! snapshots what stays fixed for its whole span, computed from the state
+//! at the end of the previous epoch:
+//!
+//! - the validator set, i.e. the accounts holding active stake, which only
+//!   changes when the last block of an epoch activates bonded stake, and
+//! - the network parameters ([`ChainParams`]): the default watermark
+//!   profile and the minimum fee.
+//!
+//! The engine keeps the epoch of its next block current (see
+//! [`ConsensusEngine::current_epoch`](super::ConsensusEngine::current_epoch)),
+//! so other modules can key caches by [`Epoch::number`].
+
+use crate::execution::{ChainParams, ChainState};
+use crate::types::WmProfile;
+
+use super::schedule::ValidatorSet;
+
+/// Validator set and parameters in force for one epoch.
+#[derive(Clone, Debug)]
+pub struct Epoch {
+    /// Epoch number; epoch 0 starts at genesis.
+    pub number: u64,
+    /// Height of the epoch's first block.
+    pub start_height: u64,
+    /// Number of blocks in the epoch.
+    pub length: u64,
+    /// Accounts with active stake, in ascending [`AccountId`] order. Empty
+    /// if nobody has staked.
+    ///
+    /// [`AccountId`]: crate::types::AccountId
+    pub validators: ValidatorSet,
+    /// Network parameters in force.
+    pub params: ChainParams,
+}
+
+impl Epoch {
+    /// Returns the epoch containing `height`, given `state`, the state
+    /// after block `height - 1` (or the genesis state for height 0).
+    ///
+    /// The result is the same for any state within the same epoch, since
+    /// active stake and parameters only change at epoch boundaries.
+    pub fn at(height: u64, state: &ChainState) -> Self {
+        let staking = state.staking();
+        let length = staking.epoch_length.max(1);
+        let number = staking.epoch(height);
+        Self {
+            number,
+            start_height: number * length,
+            length,
+            validators: ValidatorSet::new(state.active_stakes().into_iter().map(|(id, _)| id)),
+            params: state.params().clone(),
+        }
+    }
+
+    /// Returns the height of the epoch's last block.
+    pub fn end_height(&self) -> u64 {
+        self.start_height + self.length - 1
+    }
+
+    /// Returns `true` if `height` falls within the epoch.
+    pub fn contains(&self, height: u64) -> bool {
+        (self.start_height..=self.end_height()).contains(&height)
+    }
+
+    /// Returns the default watermark profile for registrations.
+    pub fn wm_profile(&self) -> &WmProfile {
+        &self.params.wm_profile
+    }
+
+    /// Returns the minimum transaction fee.
+    pub fn min_fee(&self) -> u64 {
+        self.params.min_fee
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::execution::StakingConfig;
+    use crate::types::{AccountId, HASH_LEN, Hash256};
+
+    fn account(byte: u8) -> AccountId {
+        AccountId(Hash256([byte; HASH_LEN]))
+    }
+
+    #[test]
+    fn epoch_spans_its_blocks_and_lists_active_stakers() {
+        let mut state = ChainState::new();
+        state.set_staking(StakingConfig {
+            epoch_length: 10,
+            ..StakingConfig::default()
+        });
+        state.stake_mut(account(2)).bonded = 5;
+        state.stake_mut(account(1)).bonded = 7;
+        state.activate_stakes();
+        // Bonded during the epoch, so not yet a validator.
+        state.stake_mut(account(3)).bonded = 9;
+
+        let epoch = Epoch::at(25, &state);
+        assert_eq!(epoch.number, 2);
+        assert_eq!(epoch.start_height, 20);
+        assert_eq!(epoch.end_height(), 29);
+        assert!(epoch.contains(20) && epoch.contains(29));
+        assert!(!epoch.contains(30));
+        assert_eq!(epoch.validators.validators(), &[account(1), account(2)]);
+        assert_eq!(epoch.min_fee(), 0);
+    }
+}
@@ -1,12 +1,194 @@
 use std::fmt;
 
+use crate::execution::ExecutionError;
+use crate::storage::StorageError;
+use crate::types::{
+    AccountId, Aid, BlockHash, ChunkError, FraudVerdict, Hash256, SchemeError, TxHash,
+};
+
+use super::votes::VoteError;
+
 /// Error type returned when a block fails validation.
+///
+/// The validators in this crate report typed variants, so callers can tell
+/// failures apart without parsing messages (see [`kind`](Self::kind)).
+/// [`Invalid`](Self::Invalid) and [`Custom`](Self::Custom) remain for
+/// validators defined elsewhere.
 #[derive(Debug)]
 pub enum ValidationError {
     /// Block is invalid according to a validity predicate.
     Invalid(&'static str),
     /// Block is invalid with a dynamic error message.
     Custom(String),
+    /// A height-0 block that is not the network's genesis block.
+    NotGenesis,
+    /// Height does not follow on from the parent's.
+    BadHeight { parent_height: u64, height: u64 },
+    /// Timestamp is not strictly greater than the parent's.
+    TimestampNotAfterParent { timestamp: u64, parent: u64 },
+    /// Timestamp is more than `max_drift` seconds ahead of the local clock.
+    TimestampInFuture { timestamp: u64, max_drift: u64 },
+    /// Timestamp differs from the block's slot time under slot timing.
+    OffSlot { timestamp: u64, slot: u64 },
+    /// The proposer is not the slot leader.
+    WrongProposer {
+        proposer: AccountId,
+        leader: AccountId,
+    },
+    /// The proposer's eligibility proof (e.g. its VRF proof) is missing or
+    /// invalid.
+    IneligibleProposer(String),
+    /// More transactions than `max_block_txs`.
+    TooManyTxs { count: usize, max: usize },
+    /// Encoded block larger than `max_block_size_bytes`.
+    BlockTooLarge { size: usize, max: usize },
+    /// Header `txs_root` does not match the block's transactions.
+    TxsRootMismatch { header: Hash256, computed: Hash256 },
+    /// Header `state_root` does not match the post-state.
+    StateRootMismatch { header: Hash256, computed: Hash256 },
+    /// Header `receipts_root` does not match the block's receipts.
+    ReceiptsRootMismatch { header: Hash256, computed: Hash256 },
+    /// Two registrations of the same `Aid` in one block.
+    DuplicateAid(Aid),
+    /// Two byte-identical transactions in one block.
+    DuplicateTx(TxHash),
+    /// A sender's nonces do not increase within the block.
+    NonceNotIncreasing {
+        account: AccountId,
+        previous: u64,
+        got: u64,
+    },
+    /// Nonce differs from the one the account expects next.
+    BadNonce {
+        account: AccountId,
+        expected: u64,
+        got: u64,
+    },
+    /// Signer cannot cover the fee (plus amount, for transfers and stake).
+    InsufficientBalance {
+        account: AccountId,
+        needed: u64,
+        available: u64,
+    },
+    /// Fee outside the configured bounds.
+    FeeOutOfRange { fee: u64, min: u64, max: u64 },
+    /// Fee below the network's minimum.
+    FeeTooLow { fee: u64, min: u64 },
+    /// Missing or malformed signature.
+    BadSignature(String),
+    /// A transaction field is malformed (e.g. an empty task or a zero
+    /// amount).
+    MalformedTx(String),
+    /// Registration of an `Aid` that is already registered.
+    ArtefactExists(Aid),
+    /// Registration evidence rejected by the network's
+    /// [`SchemeRegistry`](crate::types::SchemeRegistry).
+    InvalidScheme(SchemeError),
+    /// Registration whose [`ChunkedAid`](crate::types::ChunkedAid) is
+    /// malformed or does not derive its `Aid`.
+    InvalidChunkedAid(ChunkError),
+    /// Reference to an `Aid` that is not registered.
+    UnknownArtefact(Aid),
+    /// Use or revocation of a revoked `Aid`.
+    RevokedArtefact(Aid),
+    /// Model transfer not signed by the artefact's owner.
+    NotArtefactOwner { aid: Aid, signer: AccountId },
+    /// Revocation signed by neither the owner nor a governance account.
+    RevocationNotAllowed { aid: Aid, signer: AccountId },
+    /// Unstake of more than the bonded stake.
+    InsufficientStake {
+        account: AccountId,
+        needed: u64,
+        bonded: u64,
+    },
+    /// Fraud report from an account with neither active stake nor
+    /// governance rights.
+    ReportNotAllowed(AccountId),
+    /// Fraud report for a block that has already been slashed.
+    AlreadySlashed(BlockHash),
+    /// Fraud proof that does not match the stored block it names.
+    InvalidFraudProof {
+        block: BlockHash,
+        reason: &'static str,
+    },
+    /// More distinct ML artefacts than `max_artefacts_per_block`.
+    TooManyArtefacts { count: usize, max: usize },
+    /// The ML verifier judged one of the block's artefacts inauthentic.
+    MlRejected { aid: Aid, verdict: FraudVerdict },
+    /// The ML verifier could not be reached or answered malformed, so the
+    /// block's artefacts could not be checked. This says nothing about
+    /// the block itself.
+    MlUnavailable(String),
+    /// The block's artefacts were not all judged within the per-block ML
+    /// verification budget. Like [`MlUnavailable`](Self::MlUnavailable),
+    /// this says nothing about the block itself.
+    MlBudgetExhausted { budget_ms: u64, artefacts: usize },
+    /// A named stage of a
+    /// [`ValidatorPipeline`](super::validator::ValidatorPipeline) failed.
+    Stage {
+        name: &'static str,
+        source: Box<ValidationError>,
+    },
+}
+
+impl ValidationError {
+    /// Returns a stable snake_case name for the failure, used as the
+    /// `reason` label of rejection metrics and the `code` of rejection
+    /// events. [`Stage`](ValidationError::Stage) reports its cause's kind.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            ValidationError::Invalid(_) | ValidationError::Custom(_) => "invalid",
+            ValidationError::NotGenesis => "not_genesis",
+            ValidationError::BadHeight { .. } => "bad_height",
+            ValidationError::TimestampNotAfterParent { .. } => "timestamp_not_after_parent",
+            ValidationError::TimestampInFuture { .. } => "timestamp_in_future",
+            ValidationError::OffSlot { .. } => "off_slot",
+            ValidationError::WrongProposer { .. } => "wrong_proposer",
+            ValidationError::IneligibleProposer(_) => "ineligible_proposer",
+            ValidationError::TooManyTxs { .. } => "too_many_txs",
+            ValidationError::BlockTooLarge { .. } => "block_too_large",
+            ValidationError::TxsRootMismatch { .. } => "txs_root_mismatch",
+            ValidationError::StateRootMismatch { .. } => "state_root_mismatch",
+            ValidationError::ReceiptsRootMismatch { .. } => "receipts_root_mismatch",
+            ValidationError::DuplicateAid(_) => "duplicate_aid",
+            ValidationError::DuplicateTx(_) => "duplicate_tx",
+            ValidationError::NonceNotIncreasing { .. } => "nonce_not_increasing",
+            ValidationError::BadNonce { .. } => "bad_nonce",
+            ValidationError::InsufficientBalance { .. } => "insufficient_balance",
+            ValidationError::FeeOutOfRange { .. } => "fee_out_of_range",
+            ValidationError::FeeTooLow { .. } => "fee_too_low",
+            ValidationError::BadSignature(_) => "bad_signature",
+            ValidationError::MalformedTx(_) => "malformed_tx",
+            ValidationError::ArtefactExists(_) => "artefact_exists",
+            ValidationError::InvalidScheme(SchemeError::Unknown(_)) => "unknown_scheme",
+            ValidationError::InvalidScheme(SchemeError::OutOfRange { .. }) => {
+                "profile_out_of_range"
+            }
+            ValidationError::InvalidChunkedAid(_) => "invalid_chunked_aid",
+            ValidationError::UnknownArtefact(_) => "unknown_artefact",
+            ValidationError::RevokedArtefact(_) => "revoked_artefact",
+            ValidationError::NotArtefactOwner { .. } => "not_artefact_owner",
+            ValidationError::RevocationNotAllowed { .. } => "revocation_not_allowed",
+            ValidationError::InsufficientStake { .. } => "insufficient_stake",
+            ValidationError::ReportNotAllowed(_) => "report_not_allowed",
+            ValidationError::AlreadySlashed(_) => "already_slashed",
+            ValidationError::InvalidFraudProof { .. } => "invalid_fraud_proof",
+            ValidationError::TooManyArtefacts { .. } => "too_many_artefacts",
+            ValidationError::MlRejected { .. } => "ml_rejected",
+            ValidationError::MlUnavailable(_) => "ml_unavailable",
+            ValidationError::MlBudgetExhausted { .. } => "ml_budget_exhausted",
+            ValidationError::Stage { source, .. } => source.kind(),
+        }
+    }
+
+    /// Returns the error the failing validator raised, looking through
+    /// any [`Stage`](ValidationError::Stage) wrappers.
+    pub fn root(&self) -> &ValidationError {
+        match self {
+            ValidationError::Stage { source, .. } => source.root(),
+            other => other,
+        }
+    }
 }
 
 /// High-level errors that can occur in the consensus engine.
@@ -14,38 +196,295 @@ pub enum ValidationError {
 pub enum ConsensusError {
     /// Underlying validation failure.
     Validation(ValidationError),
-    /// Storage-related failure, e.g. missing parent block.
+    /// Storage-related failure, e.g. missing parent block or a failed
+    /// read or write in the [`BlockStore`](super::store::BlockStore).
     Storage(String),
+    /// A transaction in the block failed to execute against chain state.
+    Execution(String),
+    /// A vote was not counted.
+    Vote(VoteError),
     /// Catch-all for other issues.
     Other(String),
 }
 
+impl ConsensusError {
+    /// Returns a stable snake_case name for the failure: the
+    /// [`ValidationError::kind`] of validation failures, or `storage`,
+    /// `execution`, `vote` or `other`.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            ConsensusError::Validation(e) => e.kind(),
+            ConsensusError::Storage(_) => "storage",
+            ConsensusError::Execution(_) => "execution",
+            ConsensusError::Vote(_) => "vote",
+            ConsensusError::Other(_) => "other",
+        }
+    }
+
+    /// Returns `true` if the block was rejected because the ML verifier
+    /// was unavailable rather than because it is invalid.
+    pub fn is_ml_unavailable(&self) -> bool {
+        matches!(
+            self,
+            ConsensusError::Validation(e) if matches!(e.root(), ValidationError::MlUnavailable(_))
+        )
+    }
+}
+
+impl From<SchemeError> for ValidationError {
+    fn from(err: SchemeError) -> Self {
+        ValidationError::InvalidScheme(err)
+    }
+}
+
+impl From<ChunkError> for ValidationError {
+    fn from(err: ChunkError) -> Self {
+        ValidationError::InvalidChunkedAid(err)
+    }
+}
+
 impl From<ValidationError> for ConsensusError {
     fn from(e: ValidationError) -> Self {
         ConsensusError::Validation(e)
     }
 }
 
+impl From<StorageError> for ConsensusError {
+    fn from(e: StorageError) -> Self {
+        ConsensusError::Storage(e.to_string())
+    }
+}
+
+impl From<VoteError> for ConsensusError {
+    fn from(e: VoteError) -> Self {
+        ConsensusError::Vote(e)
+    }
+}
+
+impl From<ExecutionError> for ConsensusError {
+    fn from(e: ExecutionError) -> Self {
+        match e {
+            ExecutionError::Storage(e) => e.into(),
+            other => ConsensusError::Execution(other.to_string()),
+        }
+    }
+}
+
 impl fmt::Display for ValidationError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             ValidationError::Invalid(msg) => write!(f, "invalid block: {msg}"),
             ValidationError::Custom(msg) => write!(f, "invalid block: {msg}"),
+            ValidationError::NotGenesis => {
+                write!(f, "invalid block: height-0 block is not the genesis block")
+            }
+            ValidationError::BadHeight {
+                parent_height,
+                height,
+            } => write!(
+                f,
+                "invalid block: height {height} does not follow parent height {parent_height}"
+            ),
+            ValidationError::TimestampNotAfterParent { timestamp, parent } => write!(
+                f,
+                "invalid block: timestamp {timestamp} is not after parent timestamp {parent}"
+            ),
+            ValidationError::TimestampInFuture {
+                timestamp,
+                max_drift,
+            } => write!(
+                f,
+                "invalid block: timestamp {timestamp} is more than max_future_drift_secs={max_drift} ahead of local time"
+            ),
+            ValidationError::OffSlot { timestamp, slot } => write!(
+                f,
+                "invalid block: timestamp {timestamp} does not match slot time {slot}"
+            ),
+            ValidationError::WrongProposer { proposer, leader } => write!(
+                f,
+                "invalid block: proposer {} is not the slot leader {}",
+                short(proposer.as_hash()),
+                short(leader.as_hash())
+            ),
+            ValidationError::IneligibleProposer(msg) => {
+                write!(f, "invalid block: proposer not eligible: {msg}")
+            }
+            ValidationError::TooManyTxs { count, max } => write!(
+                f,
+                "invalid block: block has {count} txs, exceeds max_block_txs={max}"
+            ),
+            ValidationError::BlockTooLarge { size, max } => write!(
+                f,
+                "invalid block: block size {size} bytes exceeds max_block_size_bytes={max}"
+            ),
+            ValidationError::TxsRootMismatch { header, computed } => write!(
+                f,
+                "invalid block: txs root {} does not match computed root {}",
+                hex::encode(header.as_bytes()),
+                hex::encode(computed.as_bytes())
+            ),
+            ValidationError::StateRootMismatch { header, computed } => write!(
+                f,
+                "invalid block: state root {} does not match post-state root {}",
+                hex::encode(header.as_bytes()),
+                hex::encode(computed.as_bytes())
+            ),
+            ValidationError::ReceiptsRootMismatch { header, computed } => write!(
+                f,
+                "invalid block: receipts root {} does not match computed root {}",
+                hex::encode(header.as_bytes()),
+                hex::encode(computed.as_bytes())
+            ),
+            ValidationError::DuplicateAid(aid) => write!(
+                f,
+                "invalid block: duplicate Aid {} in TxRegisterModel within the same block",
+                short(aid.as_hash())
+            ),
+            ValidationError::DuplicateTx(hash) => write!(
+                f,
+                "invalid block: duplicate transaction {} within the same block",
+                short(&hash.0)
+            ),
+            ValidationError::NonceNotIncreasing {
+                account,
+                previous,
+                got,
+            } => write!(
+                f,
+                "invalid block: nonce {got} of account {} does not follow its earlier nonce {previous} in the block",
+                short(account.as_hash())
+            ),
+            ValidationError::BadNonce {
+                account,
+                expected,
+                got,
+            } => write!(
+                f,
+                "invalid block: bad nonce for account {}: expected {expected}, got {got}",
+                short(account.as_hash())
+            ),
+            ValidationError::InsufficientBalance {
+                account,
+                needed,
+                available,
+            } => write!(
+                f,
+                "invalid block: account {} needs {needed} but has a balance of {available}",
+                short(account.as_hash())
+            ),
+            ValidationError::FeeOutOfRange { fee, min, max } => {
+                write!(f, "invalid block: fee {fee} is outside [{min}, {max}]")
+            }
+            ValidationError::FeeTooLow { fee, min } => write!(
+                f,
+                "invalid block: fee {fee} is below the network minimum of {min}"
+            ),
+            ValidationError::BadSignature(msg) => write!(f, "invalid block: bad signature: {msg}"),
+            ValidationError::MalformedTx(msg) => {
+                write!(f, "invalid block: malformed transaction: {msg}")
+            }
+            ValidationError::ArtefactExists(aid) => write!(
+                f,
+                "invalid block: artefact {} is already registered",
+                short(aid.as_hash())
+            ),
+            ValidationError::InvalidScheme(err) => write!(f, "invalid block: {err}"),
+            ValidationError::InvalidChunkedAid(err) => write!(f, "invalid block: {err}"),
+            ValidationError::UnknownArtefact(aid) => write!(
+                f,
+                "invalid block: artefact {} is not registered",
+                short(aid.as_hash())
+            ),
+            ValidationError::RevokedArtefact(aid) => write!(
+                f,
+                "invalid block: artefact {} has been revoked",
+                short(aid.as_hash())
+            ),
+            ValidationError::NotArtefactOwner { aid, signer } => write!(
+                f,
+                "invalid block: {} does not own artefact {}",
+                short(signer.as_hash()),
+                short(aid.as_hash())
+            ),
+            ValidationError::RevocationNotAllowed { aid, signer } => write!(
+                f,
+                "invalid block: {} may not revoke artefact {}; only its owner or a governance account may",
+                short(signer.as_hash()),
+                short(aid.as_hash())
+            ),
+            ValidationError::InsufficientStake {
+                account,
+                needed,
+                bonded,
+            } => write!(
+                f,
+                "invalid block: account {} cannot unstake {needed} with {bonded} bonded",
+                short(account.as_hash())
+            ),
+            ValidationError::ReportNotAllowed(account) => write!(
+                f,
+                "invalid block: {} is neither staked nor a governance account and may not report fraud",
+                short(account.as_hash())
+            ),
+            ValidationError::AlreadySlashed(block) => write!(
+                f,
+                "invalid block: block {} has already been slashed",
+                short(&block.0)
+            ),
+            ValidationError::InvalidFraudProof { block, reason } => write!(
+                f,
+                "invalid block: fraud proof against block {}: {reason}",
+                short(&block.0)
+            ),
+            ValidationError::TooManyArtefacts { count, max } => write!(
+                f,
+                "invalid block: block references {count} distinct ML artefacts, exceeds max_artefacts_per_block={max}"
+            ),
+            ValidationError::MlRejected { aid, verdict } => write!(
+                f,
+                "invalid block: ML authenticity check failed for artefact {} (scheme={}, score={:?})",
+                hex::encode(&aid.as_hash().as_bytes()[..8]),
+                verdict.scheme_id,
+                verdict.score
+            ),
+            ValidationError::MlUnavailable(msg) => write!(f, "ML verifier unavailable: {msg}"),
+            ValidationError::MlBudgetExhausted {
+                budget_ms,
+                artefacts,
+            } => write!(
+                f,
+                "ML verification of {artefacts} artefacts did not finish within verification_budget_ms={budget_ms}"
+            ),
+            ValidationError::Stage { name, source } => write!(f, "{source} (stage {name})"),
         }
     }
 }
 
+/// Returns the hex encoding of the first 8 bytes of `hash`.
+fn short(hash: &Hash256) -> String {
+    hex::encode(&hash.as_bytes()[..8])
+}
+
 impl fmt::Display for ConsensusError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             ConsensusError::Validation(e) => write!(f, "{e}"),
             ConsensusError::Storage(msg) => write!(f, "storage error: {msg}"),
+            ConsensusError::Execution(msg) => write!(f, "execution error: {msg}"),
+            ConsensusError::Vote(e) => write!(f, "vote rejected: {e}"),
             ConsensusError::Other(msg) => write!(f, "consensus error: {msg}"),
         }
     }
 }
 
-impl std::error::Error for ValidationError {}
+impl std::error::Error for ValidationError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ValidationError::Stage { source, .. } => Some(source.as_ref()),
+            _ => None,
+        }
+    }
+}
 impl std::error::Error for ConsensusError {}
 
 #[cfg(test)]
@@ -65,6 +504,44 @@ mod tests {
         assert_eq!(err.to_string(), "invalid block: height mismatch");
     }
 
+    #[test]
+    fn stage_error_names_the_stage_and_keeps_the_cause() {
+        let err = ValidationError::Stage {
+            name: "ml",
+            source: Box::new(ValidationError::MlUnavailable("timeout".to_string())),
+        };
+        assert_eq!(
+            err.to_string(),
+            "ML verifier unavailable: timeout (stage ml)"
+        );
+        assert!(matches!(err.root(), ValidationError::MlUnavailable(_)));
+        assert!(err.source().is_some());
+        assert!(ConsensusError::Validation(err).is_ml_unavailable());
+    }
+
+    #[test]
+    fn typed_errors_carry_values_and_stable_kinds() {
+        let err = ValidationError::BadNonce {
+            account: AccountId(Hash256([0xab; 32])),
+            expected: 3,
+            got: 5,
+        };
+        assert_eq!(
+            err.to_string(),
+            "invalid block: bad nonce for account abababababababab: expected 3, got 5"
+        );
+        assert_eq!(err.kind(), "bad_nonce");
+
+        let staged = ValidationError::Stage {
+            name: "state",
+            source: Box::new(err),
+        };
+        assert_eq!(staged.kind(), "bad_nonce");
+        assert_eq!(ConsensusError::Validation(staged).kind(), "bad_nonce");
+        assert_eq!(ValidationError::Invalid("x").kind(), "invalid");
+        assert_eq!(ConsensusError::Storage("x".into()).kind(), "storage");
+    }
+
     #[test]
     fn consensus_error_wraps_validation_and_uses_same_message() {
         let v = ValidationError::Invalid("parent not found");
@@ -78,6 +555,21 @@ mod tests {
         assert_eq!(e.to_string(), "storage error: missing parent block");
     }
 
+    #[test]
+    fn consensus_error_from_storage_error() {
+        let e: ConsensusError = StorageError::MissingColumnFamily("blocks").into();
+        assert_eq!(
+            e.to_string(),
+            "storage error: missing column family 'blocks'"
+        );
+    }
+
+    #[test]
+    fn consensus_error_display_execution() {
+        let e = ConsensusError::Execution("bad nonce".to_string());
+        assert_eq!(e.to_string(), "execution error: bad nonce");
+    }
+
     #[test]
     fn consensus_error_display_other() {
         let e = ConsensusError::Other("timer failed".to_string());
@@ -0,0 +1,85 @@
+//! Chain events published by the consensus engine.
+//!
+//! An engine built with [`ConsensusEngine::with_events`] sends a
+//! [`ChainEvent`] into a Tokio broadcast channel for every imported block,
+//! every tip change, every block rejected by validation or execution, and
+//! every block committed by a quorum of validator votes.
+//! Subscribers (e.g. a WebSocket endpoint) call `subscribe` on their copy
+//! of the sender; a subscriber that falls behind loses the oldest events
+//! rather than blocking the engine.
+//!
+//! [`ConsensusEngine::with_events`]: super::ConsensusEngine::with_events
+
+use std::sync::Arc;
+
+use crate::types::{AccountId, Block, BlockHash};
+
+use super::error::{ConsensusError, ValidationError};
+
+/// Why a block was rejected.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum RejectionKind {
+    /// The ML verifier judged one of the block's artefacts inauthentic.
+    MlRejected,
+    /// The ML verifier could not be reached.
+    MlUnavailable,
+    /// The ML verifier did not judge the block's artefacts within the
+    /// verification budget.
+    MlBudgetExhausted,
+    /// Any other validation or execution failure.
+    Invalid,
+}
+
+impl RejectionKind {
+    /// Classifies an import error.
+    pub fn of(err: &ConsensusError) -> Self {
+        match err {
+            ConsensusError::Validation(e) => match e.root() {
+                ValidationError::MlRejected { .. } => RejectionKind::MlRejected,
+                ValidationError::MlUnavailable(_) => RejectionKind::MlUnavailable,
+                ValidationError::MlBudgetExhausted { .. } => RejectionKind::MlBudgetExhausted,
+                _ => RejectionKind::Invalid,
+            },
+            _ => RejectionKind::Invalid,
+        }
+    }
+
+    /// Returns the name used in serialized events.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RejectionKind::MlRejected => "ml_rejected",
+            RejectionKind::MlUnavailable => "ml_unavailable",
+            RejectionKind::MlBudgetExhausted => "ml_budget_exhausted",
+            RejectionKind::Invalid => "invalid",
+        }
+    }
+}
+
+/// Something that happened to the chain.
+#[derive(Clone, Debug)]
+pub enum ChainEvent {
+    /// A block passed validation and was stored, on any branch.
+    BlockImported { hash: BlockHash, block: Arc<Block> },
+    /// The canonical tip moved. `reorg_depth` is set if blocks of the old
+    /// canonical chain were retracted.
+    NewTip {
+        hash: BlockHash,
+        height: u64,
+        reorg_depth: Option<u64>,
+    },
+    /// A block linked to a known parent but failed validation or
+    /// execution.
+    BlockRejected {
+        hash: BlockHash,
+        height: u64,
+        proposer: AccountId,
+        kind: RejectionKind,
+        /// Machine-readable reason, see [`ConsensusError::kind`].
+        ///
+        /// [`ConsensusError::kind`]: crate::consensus::ConsensusError::kind
+        code: &'static str,
+        reason: String,
+    },
+    /// A quorum of validators voted for a block, committing it.
+    BlockCommitted { hash: BlockHash, height: u64 },
+}
@@ -1,8 +1,13 @@
 //! Fork-choice rule for selecting the best chain.
+//!
+//! Also provides [`common_ancestor`], which the engine uses to measure
+//! reorgs, and the [`ReorgEvent`]s it reports.
 
+use crate::storage::StorageError;
 use crate::types::{Block, BlockHash};
 
 use super::store::BlockStore;
+use super::votes::QuorumCertificate;
 
 /// Abstraction over fork-choice rules.
 ///
@@ -15,15 +20,89 @@ pub trait ForkChoice {
         store: &dyn BlockStore,
         current_tip: Option<BlockHash>,
         candidate: &Block,
-    ) -> bool;
+    ) -> Result<bool, StorageError>;
+
+    /// Called by the engine when a quorum of validators commits a block.
+    ///
+    /// Rules that do not use votes ignore it, which is the default.
+    fn on_commit(&mut self, _commit: &QuorumCertificate) {}
+}
+
+/// The point where two branches meet.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct CommonAncestor {
+    /// Hash of the last block both branches share.
+    pub hash: BlockHash,
+    /// Height of that block.
+    pub height: u64,
+}
+
+/// A switch of the canonical tip to a block that does not extend the
+/// previous tip.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct ReorgEvent {
+    /// Tip before the switch.
+    pub old_tip: BlockHash,
+    /// Tip after the switch.
+    pub new_tip: BlockHash,
+    /// Number of blocks retracted from the old canonical chain.
+    pub depth: u64,
+}
+
+/// Walks the branches ending in `a` and `b` back to their common ancestor.
+///
+/// Neither block has to be stored yet, but their ancestors must be.
+/// Returns `None` if the branches share no stored block, e.g. because they
+/// start from different genesis blocks or an ancestor was pruned.
+pub fn common_ancestor(
+    store: &dyn BlockStore,
+    a: &Block,
+    b: &Block,
+) -> Result<Option<CommonAncestor>, StorageError> {
+    let mut a = (a.compute_hash(), a.header.clone());
+    let mut b = (b.compute_hash(), b.header.clone());
+    loop {
+        if a.0 == b.0 {
+            return Ok(Some(CommonAncestor {
+                hash: a.0,
+                height: a.1.height,
+            }));
+        }
+        // Step back on the higher branch, or on both at equal heights.
+        let (step_a, step_b) = match a.1.height.cmp(&b.1.height) {
+            std::cmp::Ordering::Greater => (true, false),
+            std::cmp::Ordering::Less => (false, true),
+            std::cmp::Ordering::Equal => (true, true),
+        };
+        for (step, cursor) in [(step_a, &mut a), (step_b, &mut b)] {
+            if !step {
+                continue;
+            }
+            if cursor.1.height == 0 {
+                return Ok(None);
+            }
+            let parent = cursor.1.parent;
+            let Some(block) = store.get_block(&parent)? else {
+                return Ok(None);
+            };
+            *cursor = (parent, block.header);
+        }
+    }
 }
 
-/// Simple "longest chain by height" fork choice.
+/// "Longest chain" fork choice.
+///
+/// Blocks carry no difficulty, so a branch's work is its length. The
+/// candidate's branch and the current tip's branch are walked back to
+/// their common ancestor, and the candidate wins only if its branch is
+/// strictly longer:
 ///
 /// - If there is no current tip, the candidate always becomes the tip.
-/// - If the candidate's height is strictly greater than the tip's height,
-///   the candidate becomes the tip.
-/// - If the heights are equal or lower, the tip remains unchanged.
+/// - If the candidate's branch is longer than the tip's, the candidate
+///   becomes the tip, even when that means switching branches (a reorg).
+/// - Ties keep the current tip.
+/// - If the branches share no stored ancestor, the heights are compared
+///   directly.
 #[derive(Clone, Copy, Debug, Default)]
 pub struct LongestChainForkChoice;
 
@@ -33,26 +112,28 @@ impl ForkChoice for LongestChainForkChoice {
         store: &dyn BlockStore,
         current_tip: Option<BlockHash>,
         candidate: &Block,
-    ) -> bool {
-        let new_height = candidate.header.height;
-
-        match current_tip {
-            None => true,
-            Some(tip_hash) => match store.get_block(&tip_hash) {
-                Some(tip_block) => new_height > tip_block.header.height,
-                None => {
-                    // Tip block missing: treat storage as corrupted and allow
-                    // the candidate to become the new tip.
-                    true
-                }
-            },
-        }
+    ) -> Result<bool, StorageError> {
+        let Some(tip_hash) = current_tip else {
+            return Ok(true);
+        };
+        let Some(tip_block) = store.get_block(&tip_hash)? else {
+            // Tip block missing: treat storage as corrupted and allow the
+            // candidate to become the new tip.
+            return Ok(true);
+        };
+
+        let base = common_ancestor(store, &tip_block, candidate)?.map_or(0, |a| a.height);
+        let tip_len = tip_block.header.height.saturating_sub(base);
+        let candidate_len = candidate.header.height.saturating_sub(base);
+        Ok(candidate_len > tip_len)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::storage::InMemoryBlockStore;
+    use crate::types::{AccountId, HASH_LEN, Hash256, Header};
 
     #[test]
     fn longest_chain_fork_choice_trait_bounds() {
@@ -66,6 +147,73 @@ mod tests {
         assert_eq!(core::mem::size_of::<LongestChainForkChoice>(), 0);
     }
 
+    fn block(parent: BlockHash, height: u64, salt: u8) -> Block {
+        Block {
+            header: Header {
+                parent,
+                height,
+                timestamp: u64::from(salt),
+                proposer: AccountId(Hash256([salt; HASH_LEN])),
+                txs_root: Hash256([0u8; HASH_LEN]),
+                state_root: Hash256([0u8; HASH_LEN]),
+                receipts_root: Hash256([0u8; HASH_LEN]),
+                pos_proof: None,
+            },
+            txs: Vec::new(),
+        }
+    }
+
+    /// Stores `len` blocks on top of `parent` and returns them.
+    fn extend(store: &mut InMemoryBlockStore, parent: &Block, len: u64, salt: u8) -> Vec<Block> {
+        let mut parent_hash = parent.compute_hash();
+        (parent.header.height + 1..=parent.header.height + len)
+            .map(|h| {
+                let b = block(parent_hash, h, salt);
+                parent_hash = b.compute_hash();
+                store.put_block(b.clone()).unwrap();
+                b
+            })
+            .collect()
+    }
+
+    #[test]
+    fn common_ancestor_of_diverging_branches() {
+        let mut store = InMemoryBlockStore::new();
+        let genesis = block(BlockHash(Hash256([0; HASH_LEN])), 0, 0);
+        store.put_block(genesis.clone()).unwrap();
+        let main = extend(&mut store, &genesis, 3, 1);
+        let side = extend(&mut store, &main[0], 4, 2);
+
+        let ancestor = common_ancestor(&store, &main[2], &side[3])
+            .unwrap()
+            .expect("branches share main[0]");
+        assert_eq!(ancestor.hash, main[0].compute_hash());
+        assert_eq!(ancestor.height, 1);
+
+        let own = common_ancestor(&store, &main[2], &main[1])
+            .unwrap()
+            .unwrap();
+        assert_eq!(own.hash, main[1].compute_hash());
+
+        let stranger = block(BlockHash(Hash256([0; HASH_LEN])), 0, 9);
+        assert_eq!(common_ancestor(&store, &main[2], &stranger).unwrap(), None);
+    }
+
+    #[test]
+    fn longer_side_branch_wins_and_ties_keep_tip() {
+        let mut store = InMemoryBlockStore::new();
+        let genesis = block(BlockHash(Hash256([0; HASH_LEN])), 0, 0);
+        store.put_block(genesis.clone()).unwrap();
+        let main = extend(&mut store, &genesis, 3, 1);
+        let tip = Some(main[2].compute_hash());
+        let side = extend(&mut store, &genesis, 4, 2);
+
+        let fc = LongestChainForkChoice;
+        assert!(!fc.should_update_tip(&store, tip, &side[2]).unwrap());
+        assert!(fc.should_update_tip(&store, tip, &side[3]).unwrap());
+        assert!(fc.should_update_tip(&store, None, &side[0]).unwrap());
+    }
+
     #[test]
     fn fork_choice_trait_is_object_safe() {
         // Sanity check: we can make a trait object for dynamic dispatch.
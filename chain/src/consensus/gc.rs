@@ -0,0 +1,267 @@
+//! Garbage collection of stale fork branches.
+//!
+//! Every imported block is persisted, including blocks that lose the
+//! fork-choice race. Without cleanup those side branches accumulate
+//! forever. The [`ForkGc`] collector scans the store, keeps the canonical
+//! chain plus any side branch that is still close to the tip, and deletes
+//! the rest.
+//!
+//! Blocks are removed through [`BlockStore::delete_block`], which also
+//! drops the receipts and ML verdict statistics stored with them.
+
+use std::collections::{HashMap, HashSet};
+
+use serde::Deserialize;
+
+use crate::storage::StorageError;
+use crate::types::BlockHash;
+
+use super::store::BlockStore;
+
+/// Configuration for the fork garbage collector.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GcConfig {
+    /// Whether the background GC task should run at all.
+    pub enabled: bool,
+    /// Side branches whose highest block is more than `depth` blocks below
+    /// the canonical tip are considered stale and removed.
+    pub depth: u64,
+    /// Interval between GC passes, in seconds.
+    pub interval_secs: u64,
+    /// Archive nodes keep every block ever imported; GC is a no-op.
+    pub archive: bool,
+}
+
+impl Default for GcConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            depth: 64,
+            interval_secs: 60,
+            archive: false,
+        }
+    }
+}
+
+/// Outcome of a single GC pass.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct GcReport {
+    /// Number of blocks deleted from the store.
+    pub blocks_removed: u64,
+    /// Sum of the canonical encoded sizes of the deleted blocks, in bytes.
+    pub bytes_reclaimed: u64,
+}
+
+/// Collector that removes blocks on stale fork branches.
+#[derive(Clone, Debug)]
+pub struct ForkGc {
+    depth: u64,
+    archive: bool,
+}
+
+/// Header fields the collector needs for each stored block.
+struct BlockInfo {
+    parent: BlockHash,
+    height: u64,
+    size: u64,
+}
+
+impl ForkGc {
+    /// Constructs a collector from a [`GcConfig`].
+    pub fn from_config(cfg: &GcConfig) -> Self {
+        Self {
+            depth: cfg.depth,
+            archive: cfg.archive,
+        }
+    }
+
+    /// Runs a single GC pass over `store`.
+    ///
+    /// A block is kept if it is on the canonical chain (reachable from the
+    /// tip via parent links), if it is an ancestor of some block whose
+    /// height is within `depth` of the tip, or if it is the genesis block
+    /// (which history pruning can leave disconnected from the tip).
+    /// Everything else is deleted.
+    ///
+    /// A storage error aborts the pass; blocks deleted before the error
+    /// stay deleted.
+    pub fn collect<S>(&self, store: &mut S) -> Result<GcReport, StorageError>
+    where
+        S: BlockStore + ?Sized,
+    {
+        if self.archive {
+            return Ok(GcReport::default());
+        }
+
+        let Some(tip) = store.tip()? else {
+            return Ok(GcReport::default());
+        };
+
+        let mut infos: HashMap<BlockHash, BlockInfo> = HashMap::new();
+        for hash in store.block_hashes()? {
+            if let Some(block) = store.get_block(&hash)? {
+                let info = BlockInfo {
+                    parent: block.header.parent,
+                    height: block.header.height,
+                    size: block.canonical_bytes().len() as u64,
+                };
+                infos.insert(hash, info);
+            }
+        }
+
+        let Some(tip_height) = infos.get(&tip).map(|i| i.height) else {
+            return Ok(GcReport::default());
+        };
+
+        // Canonical chain plus every ancestor of a recent block.
+        let mut keep: HashSet<BlockHash> = HashSet::new();
+        mark_ancestors(&infos, tip, &mut keep);
+
+        for (hash, info) in &infos {
+            if info.height.saturating_add(self.depth) >= tip_height {
+                mark_ancestors(&infos, *hash, &mut keep);
+            }
+        }
+
+        let mut report = GcReport::default();
+        for (hash, info) in &infos {
+            if keep.contains(hash) || info.height == 0 {
+                continue;
+            }
+            store.delete_block(hash)?;
+            report.blocks_removed += 1;
+            report.bytes_reclaimed += info.size;
+        }
+
+        Ok(report)
+    }
+}
+
+/// Marks `start` and all of its stored ancestors as kept.
+///
+/// Stops early once it reaches a block that is already marked, so repeated
+/// calls over overlapping branches stay linear in the number of blocks.
+fn mark_ancestors(
+    infos: &HashMap<BlockHash, BlockInfo>,
+    start: BlockHash,
+    keep: &mut HashSet<BlockHash>,
+) {
+    let mut cursor = start;
+    while let Some(info) = infos.get(&cursor) {
+        if !keep.insert(cursor) {
+            break;
+        }
+        cursor = info.parent;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::InMemoryBlockStore;
+    use crate::types::{AccountId, Block, HASH_LEN, Hash256, Header};
+
+    fn child_of(parent: BlockHash, height: u64, salt: u64) -> Block {
+        Block {
+            header: Header {
+                parent,
+                height,
+                timestamp: 1_700_000_000 + salt,
+                proposer: AccountId(Hash256([1u8; HASH_LEN])),
+                txs_root: Hash256([0u8; HASH_LEN]),
+                state_root: Hash256([0u8; HASH_LEN]),
+                receipts_root: Hash256([0u8; HASH_LEN]),
+                pos_proof: None,
+            },
+            txs: Vec::new(),
+        }
+    }
+
+    /// Builds a canonical chain of `len` blocks and returns their hashes.
+    fn canonical_chain(store: &mut InMemoryBlockStore, len: u64) -> Vec<BlockHash> {
+        let mut parent = BlockHash(Hash256([0u8; HASH_LEN]));
+        let mut hashes = Vec::new();
+        for height in 0..len {
+            let block = child_of(parent, height, height);
+            parent = block.compute_hash();
+            store.put_block(block).unwrap();
+            hashes.push(parent);
+        }
+        store.set_tip(parent).unwrap();
+        hashes
+    }
+
+    #[test]
+    fn removes_old_side_branch_and_keeps_canonical_chain() {
+        let mut store = InMemoryBlockStore::new();
+        let chain = canonical_chain(&mut store, 10);
+
+        // Side branch forking off height 1, two blocks long.
+        let side1 = child_of(chain[1], 2, 100);
+        let side1_hash = side1.compute_hash();
+        let side2 = child_of(side1_hash, 3, 101);
+        let side2_hash = side2.compute_hash();
+        store.put_block(side1).unwrap();
+        store.put_block(side2).unwrap();
+
+        let gc = ForkGc::from_config(&GcConfig {
+            depth: 3,
+            ..GcConfig::default()
+        });
+        let report = gc.collect(&mut store).unwrap();
+
+        assert_eq!(report.blocks_removed, 2);
+        assert!(report.bytes_reclaimed > 0);
+        assert!(store.get_block(&side1_hash).unwrap().is_none());
+        assert!(store.get_block(&side2_hash).unwrap().is_none());
+        for hash in &chain {
+            assert!(store.get_block(hash).unwrap().is_some());
+        }
+    }
+
+    #[test]
+    fn keeps_side_branch_whose_head_is_recent() {
+        let mut store = InMemoryBlockStore::new();
+        let chain = canonical_chain(&mut store, 10);
+
+        // Side branch forking off height 1 but extending up to height 8.
+        let mut parent = chain[1];
+        let mut side = Vec::new();
+        for height in 2..=8 {
+            let block = child_of(parent, height, 100 + height);
+            parent = block.compute_hash();
+            store.put_block(block).unwrap();
+            side.push(parent);
+        }
+
+        let gc = ForkGc::from_config(&GcConfig {
+            depth: 3,
+            ..GcConfig::default()
+        });
+        let report = gc.collect(&mut store).unwrap();
+
+        assert_eq!(report, GcReport::default());
+        for hash in &side {
+            assert!(store.get_block(hash).unwrap().is_some());
+        }
+    }
+
+    #[test]
+    fn archive_mode_never_deletes() {
+        let mut store = InMemoryBlockStore::new();
+        let chain = canonical_chain(&mut store, 10);
+        let stale = child_of(chain[0], 1, 100);
+        let stale_hash = stale.compute_hash();
+        store.put_block(stale).unwrap();
+
+        let gc = ForkGc::from_config(&GcConfig {
+            depth: 1,
+            archive: true,
+            ..GcConfig::default()
+        });
+
+        assert_eq!(gc.collect(&mut store).unwrap(), GcReport::default());
+        assert!(store.get_block(&stale_hash).unwrap().is_some());
+    }
+}
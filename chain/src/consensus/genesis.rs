@@ -0,0 +1,217 @@
+//! Genesis specification and the genesis block derived from it.
+//!
+//! Every node on a network must start from the same genesis block. A
+//! [`GenesisConfig`] describes the chain's initial conditions (chain id,
+//! genesis timestamp, funded and staked accounts, pre-registered artefacts,
+//! governance accounts, staking and network parameters) and
+//! deterministically derives:
+//!
+//! - the genesis block, whose `parent` field carries the hash of the
+//!   canonical spec encoding, so the genesis hash commits to every field of
+//!   the spec, and
+//! - the initial [`ChainState`] that the genesis block's (empty) body is
+//!   executed against.
+
+use serde::{Deserialize, Serialize};
+
+use crate::execution::{ChainParams, ChainState, StakingConfig};
+use crate::types::{
+    AccountId, Aid, ArtefactMetadata, Block, BlockHash, EMPTY_ROOT, EvidenceRef, HASH_LEN, Hash256,
+    Header,
+};
+
+/// Initial balance of a genesis account.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GenesisAccount {
+    /// Funded account.
+    pub account: AccountId,
+    /// Initial balance.
+    pub balance: u64,
+    /// Stake bonded at genesis, active from the first block on. Not taken
+    /// out of `balance`.
+    #[serde(default)]
+    pub stake: u64,
+}
+
+/// Artefact registered at genesis (height 0).
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GenesisArtefact {
+    /// Artefact identifier.
+    pub aid: Aid,
+    /// Initial owner.
+    pub owner: AccountId,
+    /// Authenticity evidence the artefact is registered with.
+    pub evidence: EvidenceRef,
+}
+
+/// Genesis specification shared by all nodes of a network.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GenesisConfig {
+    /// Network identifier; nodes with different chain ids never agree on
+    /// a genesis block.
+    pub chain_id: String,
+    /// Timestamp of the genesis block, in seconds since Unix epoch.
+    pub timestamp: u64,
+    /// Accounts funded at genesis.
+    pub accounts: Vec<GenesisAccount>,
+    /// Artefacts registered at genesis.
+    pub artefacts: Vec<GenesisArtefact>,
+    /// Accounts allowed to revoke any artefact with `TxRevokeModel`.
+    pub governance: Vec<AccountId>,
+    /// Epoch length and unbonding delay of the stake ledger.
+    pub staking: StakingConfig,
+    /// Network parameters (default watermark profile, minimum fee).
+    pub params: ChainParams,
+}
+
+impl Default for GenesisConfig {
+    fn default() -> Self {
+        Self {
+            chain_id: "mlsnitch-devnet".to_string(),
+            timestamp: 1_700_000_000,
+            accounts: Vec::new(),
+            artefacts: Vec::new(),
+            governance: Vec::new(),
+            staking: StakingConfig::default(),
+            params: ChainParams::default(),
+        }
+    }
+}
+
+impl GenesisConfig {
+    /// Returns the canonical byte representation of this spec.
+    ///
+    /// # Panics
+    ///
+    /// Panics if encoding fails, which would be a programming error.
+    pub fn canonical_bytes(&self) -> Vec<u8> {
+        let cfg = bincode::config::standard();
+        bincode::serde::encode_to_vec(self, cfg)
+            .expect("GenesisConfig should always be serializable with bincode 2 + serde")
+    }
+
+    /// Builds the genesis block.
+    ///
+    /// The block has height 0, no transactions (so the empty transactions
+    /// root), the all-zero proposer,
+    /// `parent` set to the hash of [`canonical_bytes`](Self::canonical_bytes),
+    /// and the state root of [`initial_state`](Self::initial_state).
+    pub fn genesis_block(&self) -> Block {
+        Block {
+            header: Header {
+                parent: BlockHash(Hash256::compute(&self.canonical_bytes())),
+                height: 0,
+                timestamp: self.timestamp,
+                proposer: AccountId(Hash256([0u8; HASH_LEN])),
+                txs_root: EMPTY_ROOT,
+                state_root: self.initial_state().state_root(),
+                receipts_root: EMPTY_ROOT,
+                pos_proof: None,
+            },
+            txs: Vec::new(),
+        }
+    }
+
+    /// Returns the hash of the genesis block.
+    pub fn genesis_hash(&self) -> BlockHash {
+        self.genesis_block().compute_hash()
+    }
+
+    /// Builds the state the genesis block is executed against.
+    ///
+    /// Balances and stakes of repeated accounts are summed; a repeated
+    /// artefact keeps its last entry.
+    pub fn initial_state(&self) -> ChainState {
+        let mut state = ChainState::new();
+        state.set_staking(self.staking);
+        state.set_params(self.params.clone());
+        for entry in &self.accounts {
+            let account = state.account_mut(entry.account);
+            account.balance = account.balance.saturating_add(entry.balance);
+            if entry.stake > 0 {
+                let stake = state.stake_mut(entry.account);
+                stake.bonded = stake.bonded.saturating_add(entry.stake);
+                stake.active = stake.bonded;
+            }
+        }
+        for artefact in &self.artefacts {
+            state.insert_artefact(ArtefactMetadata {
+                aid: artefact.aid,
+                chunked: None,
+                owner: artefact.owner,
+                evidence: artefact.evidence.clone(),
+                registered_at: 0,
+                revoked_at: None,
+            });
+        }
+        for account in &self.governance {
+            state.add_governance(*account);
+        }
+        state
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{EvidenceHash, WmProfile};
+
+    fn spec() -> GenesisConfig {
+        GenesisConfig {
+            accounts: vec![GenesisAccount {
+                account: AccountId(Hash256([1u8; HASH_LEN])),
+                balance: 1_000,
+                stake: 250,
+            }],
+            artefacts: vec![GenesisArtefact {
+                aid: Aid(Hash256([2u8; HASH_LEN])),
+                owner: AccountId(Hash256([1u8; HASH_LEN])),
+                evidence: EvidenceRef {
+                    scheme_id: "multi_factor_v1".to_string(),
+                    evidence_hash: EvidenceHash(Hash256([3u8; HASH_LEN])),
+                    wm_profile: WmProfile {
+                        tau_input: 0.9,
+                        tau_feat: 0.1,
+                        logit_band_low: 0.02,
+                        logit_band_high: 0.05,
+                    },
+                },
+            }],
+            governance: vec![AccountId(Hash256([4u8; HASH_LEN]))],
+            ..GenesisConfig::default()
+        }
+    }
+
+    #[test]
+    fn genesis_hash_is_deterministic_and_commits_to_the_spec() {
+        assert_eq!(spec().genesis_hash(), spec().genesis_hash());
+
+        let mut other = spec();
+        other.chain_id = "other-net".to_string();
+        assert_ne!(spec().genesis_hash(), other.genesis_hash());
+
+        let mut other = spec();
+        other.accounts[0].balance += 1;
+        assert_ne!(spec().genesis_hash(), other.genesis_hash());
+    }
+
+    #[test]
+    fn initial_state_funds_accounts_and_registers_artefacts() {
+        let state = spec().initial_state();
+
+        assert_eq!(
+            state.account(&AccountId(Hash256([1u8; HASH_LEN]))).balance,
+            1_000
+        );
+        assert_eq!(
+            state.active_stakes(),
+            vec![(AccountId(Hash256([1u8; HASH_LEN])), 250)]
+        );
+        let meta = state
+            .artefact(&Aid(Hash256([2u8; HASH_LEN])))
+            .expect("artefact registered at genesis");
+        assert_eq!(meta.registered_at, 0);
+        assert!(state.is_governance(&AccountId(Hash256([4u8; HASH_LEN]))));
+    }
+}
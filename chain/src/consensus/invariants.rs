@@ -0,0 +1,404 @@
+//! Consensus invariant checks.
+//!
+//! When enabled, the engine runs an [`InvariantChecker`] against its store
+//! after every committed block and every GC pass, and panics on the first
+//! violation. This is meant for debug builds and simulation runs, to catch
+//! logic bugs in fork choice, storage, or GC close to where they happen.
+//!
+//! Checked invariants:
+//!
+//! - **Monotonic tip**: unless a reorg retracted the previous tip, the tip
+//!   height never decreases.
+//! - **Consistent canonical index**: walking parent links down from the tip,
+//!   every block is the one indexed at its height, and nothing is indexed
+//!   above the tip. Only the top `finality_depth + 1` heights are walked;
+//!   lower heights were checked while they were in that window.
+//! - **Finality**: a block `finality_depth` blocks below the tip is
+//!   considered finalized and must stay canonical forever after.
+
+use std::fmt;
+
+use serde::Deserialize;
+
+use crate::storage::StorageError;
+use crate::types::BlockHash;
+
+use super::store::BlockStore;
+
+/// Configuration for consensus invariant checks.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct InvariantConfig {
+    /// Whether the engine checks invariants after each import. Defaults to
+    /// on in debug builds and off in release builds.
+    pub enabled: bool,
+    /// Blocks at least this far below the tip are treated as finalized.
+    pub finality_depth: u64,
+}
+
+impl Default for InvariantConfig {
+    fn default() -> Self {
+        Self {
+            enabled: cfg!(debug_assertions),
+            finality_depth: 64,
+        }
+    }
+}
+
+/// A broken consensus invariant.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum InvariantViolation {
+    /// The tip moved to a lower height although the previous tip is still
+    /// canonical.
+    TipHeightDecreased { previous: u64, current: u64 },
+    /// The height index disagrees with the parent links of the tip's chain.
+    IndexMismatch {
+        height: u64,
+        expected: BlockHash,
+        indexed: Option<BlockHash>,
+    },
+    /// The height index has an entry above the tip.
+    IndexAboveTip { height: u64 },
+    /// A block on the canonical chain is missing from the store.
+    MissingBlock(BlockHash),
+    /// A canonical block's header height does not match its position.
+    HeightMismatch {
+        hash: BlockHash,
+        expected: u64,
+        found: u64,
+    },
+    /// A finalized block is no longer canonical.
+    FinalizedRetracted {
+        height: u64,
+        finalized: BlockHash,
+        found: Option<BlockHash>,
+    },
+    /// The store could not be read, so nothing could be checked.
+    Storage(StorageError),
+}
+
+impl fmt::Display for InvariantViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let short = |h: &BlockHash| hex::encode(&h.0.as_bytes()[..8]);
+        let short_opt = |h: &Option<BlockHash>| h.as_ref().map_or("none".to_string(), short);
+        match self {
+            InvariantViolation::TipHeightDecreased { previous, current } => write!(
+                f,
+                "tip height decreased from {previous} to {current} without a reorg"
+            ),
+            InvariantViolation::IndexMismatch {
+                height,
+                expected,
+                indexed,
+            } => write!(
+                f,
+                "height index at {height} is {}, parent links give {}",
+                short_opt(indexed),
+                short(expected)
+            ),
+            InvariantViolation::IndexAboveTip { height } => {
+                write!(f, "height index has an entry at {height}, above the tip")
+            }
+            InvariantViolation::MissingBlock(hash) => {
+                write!(
+                    f,
+                    "canonical block {} is missing from the store",
+                    short(hash)
+                )
+            }
+            InvariantViolation::HeightMismatch {
+                hash,
+                expected,
+                found,
+            } => write!(
+                f,
+                "block {} sits at height {expected} but its header says {found}",
+                short(hash)
+            ),
+            InvariantViolation::FinalizedRetracted {
+                height,
+                finalized,
+                found,
+            } => write!(
+                f,
+                "finalized block {} at height {height} was retracted (now {})",
+                short(finalized),
+                short_opt(found)
+            ),
+            InvariantViolation::Storage(e) => write!(f, "failed to read the store: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for InvariantViolation {}
+
+impl From<StorageError> for InvariantViolation {
+    fn from(e: StorageError) -> Self {
+        InvariantViolation::Storage(e)
+    }
+}
+
+/// Tracks what has been observed so far and checks a store against it.
+#[derive(Clone, Debug)]
+pub struct InvariantChecker {
+    finality_depth: u64,
+    /// Tip and its height at the previous check.
+    last_tip: Option<(BlockHash, u64)>,
+    /// Highest finalized block seen so far.
+    finalized: Option<(u64, BlockHash)>,
+}
+
+impl InvariantChecker {
+    /// Creates a checker that treats blocks `finality_depth` below the tip
+    /// as finalized.
+    pub fn new(finality_depth: u64) -> Self {
+        Self {
+            finality_depth,
+            last_tip: None,
+            finalized: None,
+        }
+    }
+
+    /// Returns the highest finalized `(height, hash)` seen so far.
+    pub fn finalized(&self) -> Option<(u64, BlockHash)> {
+        self.finalized
+    }
+
+    /// Checks all invariants against the current contents of `store`.
+    pub fn check<S: BlockStore>(&mut self, store: &S) -> Result<(), InvariantViolation> {
+        let Some(tip) = store.tip()? else {
+            return Ok(());
+        };
+        let tip_height = store
+            .get_block(&tip)?
+            .ok_or(InvariantViolation::MissingBlock(tip))?
+            .header
+            .height;
+
+        if let Some((previous, previous_height)) = self.last_tip
+            && tip_height < previous_height
+            && store.get_hash_at_height(previous_height)? == Some(previous)
+        {
+            return Err(InvariantViolation::TipHeightDecreased {
+                previous: previous_height,
+                current: tip_height,
+            });
+        }
+
+        if store.get_hash_at_height(tip_height + 1)?.is_some() {
+            return Err(InvariantViolation::IndexAboveTip {
+                height: tip_height + 1,
+            });
+        }
+
+        let floor = tip_height.saturating_sub(self.finality_depth);
+        let mut cursor = tip;
+        let mut height = tip_height;
+        loop {
+            let indexed = store.get_hash_at_height(height)?;
+            if indexed != Some(cursor) {
+                return Err(InvariantViolation::IndexMismatch {
+                    height,
+                    expected: cursor,
+                    indexed,
+                });
+            }
+            let block = store
+                .get_block(&cursor)?
+                .ok_or(InvariantViolation::MissingBlock(cursor))?;
+            if block.header.height != height {
+                return Err(InvariantViolation::HeightMismatch {
+                    hash: cursor,
+                    expected: height,
+                    found: block.header.height,
+                });
+            }
+            if height == floor {
+                break;
+            }
+            cursor = block.header.parent;
+            height -= 1;
+        }
+
+        if let Some((height, finalized)) = self.finalized {
+            let found = store.get_hash_at_height(height)?;
+            if found != Some(finalized) {
+                return Err(InvariantViolation::FinalizedRetracted {
+                    height,
+                    finalized,
+                    found,
+                });
+            }
+        }
+
+        self.last_tip = Some((tip, tip_height));
+        if tip_height >= self.finality_depth
+            && self.finalized.is_none_or(|(height, _)| floor > height)
+        {
+            // `cursor` is the canonical block at `floor`, checked above.
+            self.finalized = Some((floor, cursor));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::InMemoryBlockStore;
+    use crate::types::{AccountId, Block, HASH_LEN, Hash256, Header};
+
+    fn block(parent: BlockHash, height: u64, salt: u8) -> Block {
+        Block {
+            header: Header {
+                parent,
+                height,
+                timestamp: u64::from(salt),
+                proposer: AccountId(Hash256([salt; HASH_LEN])),
+                txs_root: Hash256([0u8; HASH_LEN]),
+                state_root: Hash256([0u8; HASH_LEN]),
+                receipts_root: Hash256([0u8; HASH_LEN]),
+                pos_proof: None,
+            },
+            txs: Vec::new(),
+        }
+    }
+
+    /// Appends `len` blocks on top of `parent` (starting at `height`) and
+    /// returns their hashes.
+    fn extend(
+        store: &mut InMemoryBlockStore,
+        mut parent: BlockHash,
+        height: u64,
+        len: u64,
+        salt: u8,
+    ) -> Vec<BlockHash> {
+        (height..height + len)
+            .map(|h| {
+                let b = block(parent, h, salt);
+                parent = b.compute_hash();
+                store.put_block(b).unwrap();
+                parent
+            })
+            .collect()
+    }
+
+    #[test]
+    fn accepts_growth_and_shallow_reorgs() {
+        let mut store = InMemoryBlockStore::new();
+        let mut checker = InvariantChecker::new(2);
+
+        let main = extend(&mut store, BlockHash(Hash256([0; HASH_LEN])), 0, 4, 1);
+        for hash in &main {
+            store.set_tip(*hash).unwrap();
+            checker.check(&store).expect("linear growth is fine");
+        }
+        assert_eq!(checker.finalized(), Some((1, main[1])));
+
+        // Reorg replacing heights 2..=3 with a longer branch from height 1.
+        let fork = extend(&mut store, main[1], 2, 3, 2);
+        store.set_tip(fork[2]).unwrap();
+        checker.check(&store).expect("reorg above finality is fine");
+        assert_eq!(checker.finalized(), Some((2, fork[0])));
+    }
+
+    #[test]
+    fn detects_retracted_finalized_block() {
+        let mut store = InMemoryBlockStore::new();
+        let mut checker = InvariantChecker::new(1);
+
+        let main = extend(&mut store, BlockHash(Hash256([0; HASH_LEN])), 0, 3, 1);
+        store.set_tip(main[2]).unwrap();
+        checker.check(&store).unwrap();
+        assert_eq!(checker.finalized(), Some((1, main[1])));
+
+        // A longer branch forking below the finalized block.
+        let fork = extend(&mut store, main[0], 1, 3, 2);
+        store.set_tip(fork[2]).unwrap();
+        assert!(matches!(
+            checker.check(&store),
+            Err(InvariantViolation::FinalizedRetracted { height: 1, .. })
+        ));
+    }
+
+    /// Store whose tip can be moved without touching the height index,
+    /// simulating a backend that forgets to rewrite it.
+    struct StaleIndexStore {
+        inner: InMemoryBlockStore,
+        tip: Option<BlockHash>,
+    }
+
+    impl BlockStore for StaleIndexStore {
+        fn get_block(&self, hash: &BlockHash) -> Result<Option<Block>, StorageError> {
+            self.inner.get_block(hash)
+        }
+
+        fn put_block(&mut self, block: Block) -> Result<(), StorageError> {
+            self.inner.put_block(block)
+        }
+
+        fn tip(&self) -> Result<Option<BlockHash>, StorageError> {
+            Ok(self.tip)
+        }
+
+        fn set_tip(&mut self, hash: BlockHash) -> Result<(), StorageError> {
+            self.tip = Some(hash);
+            Ok(())
+        }
+
+        fn get_hash_at_height(&self, height: u64) -> Result<Option<BlockHash>, StorageError> {
+            self.inner.get_hash_at_height(height)
+        }
+
+        fn block_hashes(&self) -> Result<Vec<BlockHash>, StorageError> {
+            self.inner.block_hashes()
+        }
+
+        fn delete_block(&mut self, hash: &BlockHash) -> Result<(), StorageError> {
+            self.inner.delete_block(hash)
+        }
+    }
+
+    #[test]
+    fn detects_tip_height_decrease_with_stale_index() {
+        let mut inner = InMemoryBlockStore::new();
+        let main = extend(&mut inner, BlockHash(Hash256([0; HASH_LEN])), 0, 3, 1);
+        inner.set_tip(main[2]).unwrap();
+        let mut store = StaleIndexStore {
+            inner,
+            tip: Some(main[2]),
+        };
+        let mut checker = InvariantChecker::new(64);
+        checker.check(&store).unwrap();
+
+        store.set_tip(main[1]).unwrap();
+        assert_eq!(
+            checker.check(&store),
+            Err(InvariantViolation::TipHeightDecreased {
+                previous: 2,
+                current: 1
+            })
+        );
+    }
+
+    #[test]
+    fn detects_deleted_canonical_block() {
+        let mut store = InMemoryBlockStore::new();
+        let mut checker = InvariantChecker::new(64);
+
+        let main = extend(&mut store, BlockHash(Hash256([0; HASH_LEN])), 0, 3, 1);
+        store.set_tip(main[2]).unwrap();
+        checker.check(&store).unwrap();
+
+        store.delete_block(&main[1]).unwrap();
+        assert_eq!(
+            checker.check(&store),
+            Err(InvariantViolation::IndexMismatch {
+                height: 1,
+                expected: main[1],
+                indexed: None
+            })
+        );
+    }
+}
@@ -5,17 +5,54 @@
 //! - configuration parameters ([`config::ConsensusConfig`]),
 
 pub mod config;
+pub mod election;
 pub mod engine;
+pub mod epoch;
 pub mod error;
+pub mod events;
 pub mod fork_choice;
+pub mod gc;
+pub mod genesis;
+pub mod invariants;
+pub mod orphans;
 pub mod proposer;
+pub mod prune;
+pub mod reader;
+pub mod replay;
+pub mod report;
+pub mod schedule;
+pub mod stake;
 pub mod store;
+pub mod tx_index;
 pub mod validator;
+pub mod votes;
+pub mod watchdog;
 
 pub use config::ConsensusConfig;
+pub use election::{ElectionKind, LeaderElection, VrfKeypair, VrfPublicKey, VrfSelector};
 pub use engine::ConsensusEngine;
+pub use epoch::Epoch;
 pub use error::{ConsensusError, ValidationError};
-pub use fork_choice::{ForkChoice, LongestChainForkChoice};
-pub use proposer::{Proposer, TxPool};
-pub use store::BlockStore;
-pub use validator::{AcceptAllValidator, BlockValidator, CombinedValidator};
+pub use events::{ChainEvent, RejectionKind};
+pub use fork_choice::{
+    CommonAncestor, ForkChoice, LongestChainForkChoice, ReorgEvent, common_ancestor,
+};
+pub use gc::{ForkGc, GcConfig, GcReport};
+pub use genesis::{GenesisAccount, GenesisArtefact, GenesisConfig};
+pub use invariants::{InvariantChecker, InvariantConfig, InvariantViolation};
+pub use orphans::{OrphanBuffer, PendingBlock};
+pub use proposer::{PeekedTxs, Proposer, TxPool};
+pub use prune::{PrunePlan, PruneReport, Pruner, PruningConfig};
+pub use reader::ChainReader;
+pub use replay::{Divergence, ReplayMismatch, ReplayReport, Replayer};
+pub use report::{ProposerStats, ValidatorReport};
+pub use schedule::{ProposerSelector, RoundRobinSelector, ValidatorSet, ValidatorSetConfig};
+pub use stake::{StakeTable, StakeWeightedSelector};
+pub use store::{BlockIter, BlockStore, Checkpoint};
+pub use tx_index::{Registration, TxIndex, TxLocation};
+pub use validator::{
+    AcceptAllValidator, AsyncBlockValidator, BlockValidator, BoxedBlockValidator,
+    CombinedValidator, ReadStore, TxValidator, ValidationContext, ValidatorPipeline,
+};
+pub use votes::{QuorumCertificate, QuorumForkChoice, Vote, VoteAggregator, VoteError};
+pub use watchdog::{ProducerWatchdog, WatchdogConfig, WatchdogEvent};
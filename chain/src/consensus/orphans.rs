@@ -0,0 +1,177 @@
+//! Bounded buffer for blocks whose parent is not yet known.
+//!
+//! Blocks can arrive out of order (e.g. from the network). Instead of
+//! dropping a block whose parent is missing, the engine may park it here
+//! and retry once the parent has been imported. The buffer is bounded;
+//! when full, the oldest orphan is evicted.
+//!
+//! [`OrphanBuffer::pending`] lists the buffered blocks with the parent
+//! each one waits for and when it arrived, so a sync stuck on a missing
+//! parent can be diagnosed (see
+//! [`ChainReader::orphans`](super::ChainReader::orphans)).
+
+use std::collections::VecDeque;
+use std::time::{Duration, SystemTime};
+
+use crate::types::{Block, BlockHash};
+
+/// A block waiting to be imported, as listed for inspection.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct PendingBlock {
+    /// Hash of the block.
+    pub hash: BlockHash,
+    /// Height the block claims.
+    pub height: u64,
+    /// Parent the block waits for.
+    pub parent: BlockHash,
+    /// When the block was received.
+    pub received: SystemTime,
+}
+
+impl PendingBlock {
+    /// Returns how long the block has been waiting at `now`.
+    pub fn age(&self, now: SystemTime) -> Duration {
+        now.duration_since(self.received).unwrap_or_default()
+    }
+}
+
+/// FIFO buffer of orphan blocks.
+#[derive(Clone, Debug, Default)]
+pub struct OrphanBuffer {
+    capacity: usize,
+    blocks: VecDeque<(PendingBlock, Block)>,
+}
+
+impl OrphanBuffer {
+    /// Creates a buffer holding at most `capacity` blocks.
+    ///
+    /// A capacity of `0` disables buffering.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            blocks: VecDeque::new(),
+        }
+    }
+
+    /// Returns `true` if this buffer accepts blocks at all.
+    pub fn is_enabled(&self) -> bool {
+        self.capacity > 0
+    }
+
+    /// Number of blocks currently buffered.
+    pub fn len(&self) -> usize {
+        self.blocks.len()
+    }
+
+    /// Returns `true` if no blocks are buffered.
+    pub fn is_empty(&self) -> bool {
+        self.blocks.is_empty()
+    }
+
+    /// Returns `true` if a block with this hash is buffered.
+    pub fn contains(&self, hash: &BlockHash) -> bool {
+        self.blocks.iter().any(|(pending, _)| pending.hash == *hash)
+    }
+
+    /// Lists the buffered blocks, oldest first.
+    pub fn pending(&self) -> Vec<PendingBlock> {
+        self.blocks.iter().map(|(pending, _)| *pending).collect()
+    }
+
+    /// Buffers `block`, evicting the oldest orphan if the buffer is full.
+    ///
+    /// Returns `false` if buffering is disabled. Inserting a block that is
+    /// already buffered is a no-op.
+    pub fn insert(&mut self, block: Block) -> bool {
+        if !self.is_enabled() {
+            return false;
+        }
+
+        let hash = block.compute_hash();
+        if self.contains(&hash) {
+            return true;
+        }
+
+        if self.blocks.len() >= self.capacity {
+            self.blocks.pop_front();
+        }
+        let pending = PendingBlock {
+            hash,
+            height: block.header.height,
+            parent: block.header.parent,
+            received: SystemTime::now(),
+        };
+        self.blocks.push_back((pending, block));
+        true
+    }
+
+    /// Removes and returns every buffered block whose parent is `parent`,
+    /// in arrival order.
+    pub fn take_children(&mut self, parent: &BlockHash) -> Vec<Block> {
+        let mut children = Vec::new();
+        self.blocks.retain(|(_, block)| {
+            if block.header.parent == *parent {
+                children.push(block.clone());
+                false
+            } else {
+                true
+            }
+        });
+        children
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{AccountId, HASH_LEN, Hash256, Header};
+
+    fn block_with_parent(parent_byte: u8, height: u64) -> Block {
+        Block {
+            header: Header {
+                parent: BlockHash(Hash256([parent_byte; HASH_LEN])),
+                height,
+                timestamp: 1_700_000_000,
+                proposer: AccountId(Hash256([1u8; HASH_LEN])),
+                txs_root: Hash256([0u8; HASH_LEN]),
+                state_root: Hash256([0u8; HASH_LEN]),
+                receipts_root: Hash256([0u8; HASH_LEN]),
+                pos_proof: None,
+            },
+            txs: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn disabled_buffer_rejects_everything() {
+        let mut buf = OrphanBuffer::new(0);
+        assert!(!buf.insert(block_with_parent(1, 1)));
+        assert!(buf.is_empty());
+    }
+
+    #[test]
+    fn evicts_oldest_when_full_and_takes_children_by_parent() {
+        let mut buf = OrphanBuffer::new(2);
+        buf.insert(block_with_parent(1, 1));
+        buf.insert(block_with_parent(2, 1));
+        buf.insert(block_with_parent(2, 2));
+
+        assert_eq!(buf.len(), 2);
+        assert!(
+            buf.take_children(&BlockHash(Hash256([1u8; HASH_LEN])))
+                .is_empty()
+        );
+
+        let pending = buf.pending();
+        assert_eq!(pending.len(), 2);
+        assert_eq!(pending[0].parent, BlockHash(Hash256([2u8; HASH_LEN])));
+        assert_eq!((pending[0].height, pending[1].height), (1, 2));
+        assert!(pending[0].received <= pending[1].received);
+        assert!(buf.contains(&pending[1].hash));
+
+        let children = buf.take_children(&BlockHash(Hash256([2u8; HASH_LEN])));
+        assert_eq!(children.len(), 2);
+        assert_eq!(children[0].header.height, 1);
+        assert!(buf.is_empty());
+    }
+}
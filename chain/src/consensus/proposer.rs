@@ -3,7 +3,11 @@
 //! The proposer is responsible for assembling a candidate block on top of
 //! the current tip, given a view of the chain and a transaction pool.
 
-use crate::types::{AccountId, Block, BlockHash, HASH_LEN, Hash256, Header, Transaction};
+use crate::execution::EMPTY_STATE_ROOT;
+use crate::storage::StorageError;
+use crate::types::{
+    AccountId, Block, BlockHash, EMPTY_ROOT, HASH_LEN, Hash256, Header, Transaction, TxHash,
+};
 
 use super::config::ConsensusConfig;
 use super::store::BlockStore;
@@ -11,14 +15,97 @@ use super::store::BlockStore;
 /// Abstract transaction pool interface.
 ///
 /// Consensus does not care how transactions are stored or gossiped; it only
-/// needs a way to ask for a batch of transactions that fit into a block.
+/// needs a way to ask for a batch of transactions that fit into a block,
+/// and to hand back transactions that did not make it onto the canonical
+/// chain after all (a failed proposal, or a block retracted by a reorg).
 pub trait TxPool {
-    /// Selects a batch of transactions for inclusion in a block.
+    /// Selects a batch of transactions for inclusion in a block, removing
+    /// them from the pool.
     ///
     /// Implementations should respect the `max_txs` and `max_bytes` hints
     /// as soft limits (they may choose fewer transactions but should not
     /// exceed the size bound).
     fn select_for_block(&mut self, max_txs: usize, max_bytes: usize) -> Vec<Transaction>;
+
+    /// Returns the transactions [`select_for_block`](Self::select_for_block)
+    /// would select with the same limits, leaving them in the pool.
+    fn peek(&self, max_txs: usize, max_bytes: usize) -> Vec<Transaction>;
+
+    /// Removes the transaction with hash `tx_hash`, returning it if it was
+    /// pooled.
+    fn remove(&mut self, tx_hash: &TxHash) -> Option<Transaction>;
+
+    /// Puts back transactions that were taken out of the pool but are not
+    /// on the canonical chain, oldest first.
+    ///
+    /// Implementations should skip transactions they already hold and may
+    /// drop ones that no longer fit.
+    fn reinsert(&mut self, txs: Vec<Transaction>);
+}
+
+/// A batch of transactions peeked from a shared [`TxPool`], so a block
+/// can be proposed from it without holding the pool.
+///
+/// The pool keeps the transactions while the block is validated, so
+/// readers never see them vanish before the block is stored. Once the
+/// proposal is over, [`settle`](Self::settle) removes the ones that left
+/// for good (included, or dropped by a failed proposal) and reinserts the
+/// ones handed back (a retryable failure, or a reorg).
+#[derive(Clone, Debug, Default)]
+pub struct PeekedTxs {
+    txs: Vec<Transaction>,
+    selected: Vec<TxHash>,
+    returned: Vec<Transaction>,
+}
+
+impl PeekedTxs {
+    /// Peeks the batch a block proposed under `cfg` would select from
+    /// `pool`.
+    pub fn peek<P: TxPool>(pool: &P, cfg: &ConsensusConfig) -> Self {
+        Self {
+            txs: pool.peek(cfg.max_block_txs, cfg.max_block_size_bytes),
+            ..Self::default()
+        }
+    }
+
+    /// Applies the outcome of the proposal to `pool`.
+    pub fn settle<P: TxPool>(self, pool: &mut P) {
+        let returned: Vec<TxHash> = self
+            .returned
+            .iter()
+            .map(Transaction::compute_hash)
+            .collect();
+        for hash in self.selected.iter().filter(|hash| !returned.contains(hash)) {
+            pool.remove(hash);
+        }
+        pool.reinsert(self.returned);
+    }
+}
+
+impl TxPool for PeekedTxs {
+    fn select_for_block(&mut self, max_txs: usize, _max_bytes: usize) -> Vec<Transaction> {
+        let take = max_txs.min(self.txs.len());
+        let selected: Vec<Transaction> = self.txs.drain(..take).collect();
+        self.selected
+            .extend(selected.iter().map(Transaction::compute_hash));
+        selected
+    }
+
+    fn peek(&self, max_txs: usize, _max_bytes: usize) -> Vec<Transaction> {
+        self.txs.iter().take(max_txs).cloned().collect()
+    }
+
+    fn remove(&mut self, tx_hash: &TxHash) -> Option<Transaction> {
+        let index = self
+            .txs
+            .iter()
+            .position(|tx| tx.compute_hash() == *tx_hash)?;
+        Some(self.txs.remove(index))
+    }
+
+    fn reinsert(&mut self, txs: Vec<Transaction>) {
+        self.returned.extend(txs);
+    }
 }
 
 /// Configurable block proposer.
@@ -46,20 +133,23 @@ impl Proposer {
     ///
     /// This does not perform validation or persistence; callers should pass
     /// the resulting block into the consensus engine for validation and
-    /// import.
+    /// import. The header commits to the selected transactions through
+    /// `txs_root`. Its `state_root` is left empty because the proposer
+    /// has no view of chain state; the engine fills it in after executing
+    /// the block. Fails only if the tip cannot be read from `store`.
     pub fn build_block<S, P>(
         &self,
         store: &S,
         proposer: AccountId,
         tx_pool: &mut P,
         timestamp: u64,
-    ) -> Block
+    ) -> Result<Block, StorageError>
     where
         S: BlockStore,
         P: TxPool,
     {
-        let (parent_hash, next_height) = match store.tip() {
-            Some(tip_hash) => match store.get_block(&tip_hash) {
+        let (parent_hash, next_height) = match store.tip()? {
+            Some(tip_hash) => match store.get_block(&tip_hash)? {
                 Some(tip_block) => (tip_hash, tip_block.header.height + 1),
                 None => {
                     // Tip is set but block is missing: treat as no tip.
@@ -86,10 +176,15 @@ impl Proposer {
             height: next_height,
             timestamp,
             proposer,
+            txs_root: EMPTY_ROOT,
+            state_root: EMPTY_STATE_ROOT,
+            receipts_root: EMPTY_ROOT,
             pos_proof: None,
         };
 
-        Block { header, txs }
+        let mut block = Block { header, txs };
+        block.header.txs_root = block.compute_txs_root();
+        Ok(block)
     }
 }
 
@@ -104,6 +199,10 @@ mod tests {
             max_block_txs: 1234,
             max_block_size_bytes: 512_000,
             allow_empty_blocks: false,
+            orphan_buffer_size: 0,
+            slot_timing: false,
+            monotonic_timestamps: true,
+            max_future_drift_secs: 15,
         };
 
         let p = Proposer::from_config(&cfg);
@@ -113,6 +212,51 @@ mod tests {
         assert_eq!(p.allow_empty_blocks, cfg.allow_empty_blocks);
     }
 
+    #[test]
+    fn peeked_txs_stay_pooled_until_settled() {
+        use crate::execution::ChainState;
+        use crate::mempool::{Mempool, MempoolConfig};
+        use crate::types::{Signature, TxTransfer};
+
+        let transfer = |from: u8| {
+            Transaction::Transfer(TxTransfer {
+                from: AccountId(Hash256([from; HASH_LEN])),
+                to: AccountId(Hash256([0xff; HASH_LEN])),
+                amount: 1,
+                fee: u64::from(from),
+                nonce: 0,
+                signature: Signature(Vec::new()),
+            })
+        };
+        let state = ChainState::new();
+        let mut pool = Mempool::new(MempoolConfig::default());
+        for from in 1..=3 {
+            pool.insert(transfer(from), &state).unwrap();
+        }
+        let cfg = ConsensusConfig {
+            max_block_txs: 2,
+            ..ConsensusConfig::default()
+        };
+
+        // The two highest fees are selected; a failed proposal hands the
+        // first back and drops the second, and a reorg returns a fourth.
+        let mut peeked = PeekedTxs::peek(&pool, &cfg);
+        let selected = peeked.select_for_block(cfg.max_block_txs, cfg.max_block_size_bytes);
+        let hashes: Vec<TxHash> = selected.iter().map(Transaction::compute_hash).collect();
+        assert_eq!(
+            hashes,
+            vec![transfer(3).compute_hash(), transfer(2).compute_hash()]
+        );
+        assert_eq!(pool.len(), 3);
+        peeked.reinsert(vec![transfer(3), transfer(4)]);
+        peeked.settle(&mut pool);
+
+        assert!(pool.contains(&transfer(1).compute_hash()));
+        assert!(!pool.contains(&transfer(2).compute_hash()));
+        assert!(pool.contains(&transfer(3).compute_hash()));
+        assert!(pool.contains(&transfer(4).compute_hash()));
+    }
+
     #[test]
     fn proposer_trait_bounds() {
         fn assert_bounds<T: Clone + core::fmt::Debug>() {}
@@ -128,7 +272,7 @@ mod tests {
             S: BlockStore,
             P: TxPool,
         {
-            let _block: Block = proposer.build_block(store, id, tx_pool, ts);
+            let _block: Result<Block, StorageError> = proposer.build_block(store, id, tx_pool, ts);
         }
     }
 }
@@ -0,0 +1,278 @@
+//! History pruning for long-running nodes.
+//!
+//! Fork GC only removes side branches; the canonical chain itself still
+//! grows forever. A [`Pruner`] deletes old history instead: everything
+//! below a cut-off height chosen by [`PruningConfig`] (keep the last N
+//! blocks and/or the last N days), except the genesis block.
+//!
+//! The cut-off is never above the finalized height, so blocks on the
+//! canonical path that a reorg could still retract are always kept. The
+//! canonical block at the cut-off becomes the store's [`Checkpoint`]: its
+//! post-state is persisted next to it, and state replay starts from there
+//! instead of from genesis.
+//!
+//! [`Checkpoint`]: super::store::Checkpoint
+
+use serde::Deserialize;
+
+use crate::storage::StorageError;
+use crate::types::BlockHash;
+
+use super::store::BlockStore;
+
+/// Seconds in a day, for [`PruningConfig::keep_days`].
+const SECS_PER_DAY: u64 = 24 * 60 * 60;
+
+/// Configuration for history pruning.
+///
+/// Pruning is disabled while both `keep_blocks` and `keep_days` are 0.
+/// With both set, a block is kept if either rule keeps it.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PruningConfig {
+    /// Number of most recent canonical blocks to keep. 0 disables this
+    /// rule.
+    pub keep_blocks: u64,
+    /// Keep canonical blocks whose timestamp is within this many days of
+    /// the wall clock. 0 disables this rule.
+    pub keep_days: u64,
+    /// Blocks within this many heights of the tip are not final and are
+    /// never pruned.
+    pub finality_depth: u64,
+    /// Interval between pruning passes, in seconds.
+    pub interval_secs: u64,
+}
+
+impl Default for PruningConfig {
+    fn default() -> Self {
+        Self {
+            keep_blocks: 0,
+            keep_days: 0,
+            finality_depth: 64,
+            interval_secs: 3600,
+        }
+    }
+}
+
+impl PruningConfig {
+    /// Returns `true` if at least one retention rule is set.
+    pub fn enabled(&self) -> bool {
+        self.keep_blocks > 0 || self.keep_days > 0
+    }
+}
+
+/// Outcome of a single pruning pass.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct PruneReport {
+    /// Number of blocks deleted from the store.
+    pub blocks_pruned: u64,
+    /// Height of the store's checkpoint after the pass, if it has one.
+    pub checkpoint_height: Option<u64>,
+}
+
+/// Blocks a pruning pass will remove, and the block that replaces them.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PrunePlan {
+    /// Canonical block at the cut-off height; becomes the new checkpoint.
+    pub checkpoint: BlockHash,
+    /// Height of `checkpoint`.
+    pub checkpoint_height: u64,
+    /// Every stored block below the cut-off, except the genesis block.
+    pub pruned: Vec<BlockHash>,
+}
+
+/// Chooses which old blocks to prune.
+#[derive(Clone, Debug)]
+pub struct Pruner {
+    keep_blocks: u64,
+    keep_secs: u64,
+    finality_depth: u64,
+}
+
+impl Pruner {
+    /// Constructs a pruner from a [`PruningConfig`].
+    pub fn from_config(cfg: &PruningConfig) -> Self {
+        Self {
+            keep_blocks: cfg.keep_blocks,
+            keep_secs: cfg.keep_days.saturating_mul(SECS_PER_DAY),
+            finality_depth: cfg.finality_depth,
+        }
+    }
+
+    /// Returns the blocks to prune from `store` at wall-clock time `now`,
+    /// or `None` if there is nothing to do.
+    ///
+    /// The cut-off is the lowest height either retention rule keeps,
+    /// capped at `finalized_height` and at the tip minus the configured
+    /// finality depth.
+    pub fn plan<S>(
+        &self,
+        store: &S,
+        finalized_height: u64,
+        now: u64,
+    ) -> Result<Option<PrunePlan>, StorageError>
+    where
+        S: BlockStore + ?Sized,
+    {
+        if self.keep_blocks == 0 && self.keep_secs == 0 {
+            return Ok(None);
+        }
+        let Some(tip) = store.tip()? else {
+            return Ok(None);
+        };
+        let Some(tip_block) = store.get_block(&tip)? else {
+            return Ok(None);
+        };
+        let tip_height = tip_block.header.height;
+        let floor = store.checkpoint()?.map_or(0, |c| c.height);
+        let finalized = tip_height
+            .saturating_sub(self.finality_depth)
+            .min(finalized_height);
+
+        let mut cutoff = finalized;
+        if self.keep_blocks > 0 {
+            cutoff = cutoff.min((tip_height + 1).saturating_sub(self.keep_blocks));
+        }
+        if self.keep_secs > 0 {
+            let oldest = now.saturating_sub(self.keep_secs);
+            cutoff = cutoff.min(self.first_height_since(store, floor, cutoff, oldest)?);
+        }
+        if cutoff <= floor.max(1) {
+            return Ok(None);
+        }
+
+        let Some(checkpoint) = store.get_hash_at_height(cutoff)? else {
+            return Ok(None);
+        };
+        let mut pruned = Vec::new();
+        for hash in store.block_hashes()? {
+            if let Some(block) = store.get_block(&hash)?
+                && block.header.height > 0
+                && block.header.height < cutoff
+            {
+                pruned.push(hash);
+            }
+        }
+        if pruned.is_empty() {
+            return Ok(None);
+        }
+
+        Ok(Some(PrunePlan {
+            checkpoint,
+            checkpoint_height: cutoff,
+            pruned,
+        }))
+    }
+
+    /// Returns the lowest canonical height in `from..to` whose block is
+    /// no older than `oldest`, or `to` if every block in range is older.
+    fn first_height_since<S>(
+        &self,
+        store: &S,
+        from: u64,
+        to: u64,
+        oldest: u64,
+    ) -> Result<u64, StorageError>
+    where
+        S: BlockStore + ?Sized,
+    {
+        for block in store.iter_range(from..to) {
+            let block = block?;
+            if block.header.timestamp >= oldest {
+                return Ok(block.header.height);
+            }
+        }
+        Ok(to)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::InMemoryBlockStore;
+    use crate::types::{AccountId, Block, HASH_LEN, Hash256, Header};
+
+    fn chain(store: &mut InMemoryBlockStore, len: u64) -> Vec<BlockHash> {
+        let mut parent = BlockHash(Hash256([0u8; HASH_LEN]));
+        let mut hashes = Vec::new();
+        for height in 0..len {
+            let block = Block {
+                header: Header {
+                    parent,
+                    height,
+                    timestamp: 1_700_000_000 + height * SECS_PER_DAY,
+                    proposer: AccountId(Hash256([1u8; HASH_LEN])),
+                    txs_root: Hash256([0u8; HASH_LEN]),
+                    state_root: Hash256([0u8; HASH_LEN]),
+                    receipts_root: Hash256([0u8; HASH_LEN]),
+                    pos_proof: None,
+                },
+                txs: Vec::new(),
+            };
+            parent = block.compute_hash();
+            store.put_block(block).unwrap();
+            hashes.push(parent);
+        }
+        store.set_tip(parent).unwrap();
+        hashes
+    }
+
+    #[test]
+    fn keeps_recent_blocks_genesis_and_unfinalized_tail() {
+        let mut store = InMemoryBlockStore::new();
+        let hashes = chain(&mut store, 10);
+        let cfg = PruningConfig {
+            keep_blocks: 3,
+            finality_depth: 4,
+            ..PruningConfig::default()
+        };
+
+        // keep_blocks allows a cut at 7, finality only at 5.
+        let plan = Pruner::from_config(&cfg)
+            .plan(&store, u64::MAX, 0)
+            .unwrap()
+            .expect("old blocks to prune");
+        assert_eq!(plan.checkpoint, hashes[5]);
+        assert_eq!(plan.checkpoint_height, 5);
+        let mut pruned = plan.pruned;
+        pruned.sort_by_key(|h| h.0.0);
+        let mut expected = hashes[1..5].to_vec();
+        expected.sort_by_key(|h| h.0.0);
+        assert_eq!(pruned, expected);
+
+        // The caller's finalized height caps the cut too.
+        let plan = Pruner::from_config(&cfg)
+            .plan(&store, 3, 0)
+            .unwrap()
+            .unwrap();
+        assert_eq!(plan.checkpoint_height, 3);
+    }
+
+    #[test]
+    fn age_rule_keeps_recent_days_and_disabled_rules_prune_nothing() {
+        let mut store = InMemoryBlockStore::new();
+        let hashes = chain(&mut store, 10);
+        let now = 1_700_000_000 + 9 * SECS_PER_DAY;
+        let cfg = PruningConfig {
+            keep_days: 4,
+            finality_depth: 1,
+            ..PruningConfig::default()
+        };
+
+        let plan = Pruner::from_config(&cfg)
+            .plan(&store, u64::MAX, now)
+            .unwrap()
+            .unwrap();
+        assert_eq!(plan.checkpoint, hashes[5]);
+        assert_eq!(plan.pruned.len(), 4);
+
+        let off = PruningConfig::default();
+        assert!(!off.enabled());
+        assert_eq!(
+            Pruner::from_config(&off)
+                .plan(&store, u64::MAX, now)
+                .unwrap(),
+            None
+        );
+    }
+}
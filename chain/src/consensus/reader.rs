@@ -0,0 +1,282 @@
+//! Read access to the chain while the engine imports blocks.
+//!
+//! A [`ChainReader`] answers the queries an API serves (tip, blocks,
+//! receipts, state, transaction locations, per-account and per-scheme
+//! transaction lists, validator reports) without borrowing the
+//! [`ConsensusEngine`](super::ConsensusEngine), so callers that keep the
+//! engine behind a lock for imports do not wait for a slow validation to
+//! finish before they can read.
+//!
+//! Readers come from [`ConsensusEngine::reader`](super::ConsensusEngine::reader)
+//! on engines built over a [`SharedStore`]. Blocks and receipts are read
+//! from the shared store; the tip, its state, the epoch, the transaction
+//! index, proposer statistics and the orphan buffer come from a view the
+//! engine refreshes after every write. The view moves to a new tip only
+//! once the block is stored, so [`ChainReader::tip_block`] always finds
+//! the tip, though a block may be visible by hash shortly before it
+//! becomes the tip.
+
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock, RwLockReadGuard};
+
+use crate::execution::{Account, AccountSummary, ChainState, Stake, StateReader};
+use crate::storage::{SharedStore, StorageError};
+use crate::types::{AccountId, Aid, Block, BlockHash, TxHash};
+
+use super::epoch::Epoch;
+use super::orphans::PendingBlock;
+use super::report::{ProposerStats, ValidatorReport};
+use super::store::BlockStore;
+use super::tx_index::{Registration, TxIndex, TxLocation};
+
+/// Engine-maintained data readers need besides the store.
+#[derive(Debug)]
+pub(crate) struct ChainView {
+    /// Hash of the current tip, if any.
+    pub(crate) tip: Option<BlockHash>,
+    /// State after executing the tip.
+    pub(crate) state: Arc<ChainState>,
+    /// Epoch of the block after the tip.
+    pub(crate) epoch: Epoch,
+    /// Locations of the transactions of every stored block.
+    pub(crate) tx_index: TxIndex,
+    /// Import statistics per proposer since the engine was created.
+    pub(crate) proposer_stats: HashMap<AccountId, ProposerStats>,
+    /// Blocks in the engine's orphan buffer, oldest first.
+    pub(crate) orphans: Vec<PendingBlock>,
+}
+
+/// The engine's shared [`ChainView`].
+pub(crate) type SharedView = Arc<RwLock<ChainView>>;
+
+/// Cloneable, read-only handle to a [`ConsensusEngine`](super::ConsensusEngine)'s
+/// chain.
+pub struct ChainReader<S> {
+    store: SharedStore<S>,
+    view: SharedView,
+    genesis: Option<BlockHash>,
+    block_time_secs: u64,
+}
+
+impl<S> Clone for ChainReader<S> {
+    fn clone(&self) -> Self {
+        Self {
+            store: self.store.clone(),
+            view: Arc::clone(&self.view),
+            genesis: self.genesis,
+            block_time_secs: self.block_time_secs,
+        }
+    }
+}
+
+impl<S: BlockStore> ChainReader<S> {
+    pub(crate) fn new(
+        store: SharedStore<S>,
+        view: SharedView,
+        genesis: Option<BlockHash>,
+        block_time_secs: u64,
+    ) -> Self {
+        Self {
+            store,
+            view,
+            genesis,
+            block_time_secs,
+        }
+    }
+
+    fn view(&self) -> RwLockReadGuard<'_, ChainView> {
+        self.view.read().expect("chain view lock poisoned")
+    }
+
+    /// Returns the shared store, for block and receipt lookups.
+    pub fn store(&self) -> &SharedStore<S> {
+        &self.store
+    }
+
+    /// Returns the hash of the current tip of the best chain, if any.
+    pub fn tip(&self) -> Option<BlockHash> {
+        self.view().tip
+    }
+
+    /// Returns the tip block, if any.
+    pub fn tip_block(&self) -> Result<Option<Block>, StorageError> {
+        match self.tip() {
+            Some(hash) => self.store.get_block(&hash),
+            None => Ok(None),
+        }
+    }
+
+    /// Returns the chain state after executing the current tip.
+    pub fn state(&self) -> Arc<ChainState> {
+        Arc::clone(&self.view().state)
+    }
+
+    /// Returns the epoch of the block after the tip.
+    pub fn current_epoch(&self) -> Epoch {
+        self.view().epoch.clone()
+    }
+
+    /// Returns the hash of the configured genesis block, if any.
+    pub fn genesis_hash(&self) -> Option<BlockHash> {
+        self.genesis
+    }
+
+    /// Returns the blocks waiting in the engine's orphan buffer for an
+    /// unknown parent, oldest first.
+    pub fn orphans(&self) -> Vec<PendingBlock> {
+        self.view().orphans.clone()
+    }
+
+    /// Returns where transaction `hash` sits on the canonical chain, or
+    /// `None` if no canonical block includes it.
+    pub fn locate_tx(&self, hash: &TxHash) -> Result<Option<TxLocation>, StorageError> {
+        self.view().tx_index.canonical_location(&self.store, hash)
+    }
+
+    /// Returns up to `limit` canonical transactions involving `account`,
+    /// newest first, before `before` if given; see
+    /// [`TxIndex::canonical_account_txs`].
+    pub fn account_txs(
+        &self,
+        account: &AccountId,
+        before: Option<(u64, usize)>,
+        limit: usize,
+    ) -> Result<Vec<TxLocation>, StorageError> {
+        self.view()
+            .tx_index
+            .canonical_account_txs(&self.store, account, before, limit)
+    }
+
+    /// Returns the canonical model registrations under `scheme_id`, oldest
+    /// first.
+    pub fn registrations(&self, scheme_id: &str) -> Result<Vec<Registration>, StorageError> {
+        self.view()
+            .tx_index
+            .canonical_registrations(&self.store, scheme_id)
+    }
+
+    /// Returns import statistics for `proposer` since the engine started.
+    pub fn proposer_stats(&self, proposer: &AccountId) -> ProposerStats {
+        self.view()
+            .proposer_stats
+            .get(proposer)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Builds a performance report for `proposer`, like
+    /// [`ConsensusEngine::validator_report`](super::ConsensusEngine::validator_report).
+    pub fn validator_report(&self, proposer: &AccountId) -> Result<ValidatorReport, StorageError> {
+        ValidatorReport::build(
+            &self.store,
+            *proposer,
+            self.proposer_stats(proposer),
+            self.block_time_secs,
+        )
+    }
+}
+
+/// Account queries answered from the state at the reader's tip.
+impl<S: BlockStore> StateReader for ChainReader<S> {
+    fn account(&self, id: &AccountId) -> Account {
+        self.state().account(id)
+    }
+
+    fn stake(&self, id: &AccountId) -> Stake {
+        StateReader::stake(&*self.state(), id)
+    }
+
+    fn owned_artefacts(&self, owner: &AccountId) -> Vec<Aid> {
+        self.state().owned_artefacts(owner)
+    }
+
+    /// Reads the three parts from one tip state, even if a block is
+    /// imported meanwhile.
+    fn account_summary(&self, id: &AccountId) -> AccountSummary {
+        self.state().account_summary(id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::consensus::{
+        AcceptAllValidator, ConsensusConfig, ConsensusEngine, GenesisAccount, GenesisConfig,
+        LongestChainForkChoice, TxPool,
+    };
+    use crate::storage::InMemoryBlockStore;
+    use crate::types::{Hash256, Signature, Transaction, TxTransfer};
+
+    struct Txs(Vec<Transaction>);
+
+    impl TxPool for Txs {
+        fn select_for_block(&mut self, _max_txs: usize, _max_bytes: usize) -> Vec<Transaction> {
+            std::mem::take(&mut self.0)
+        }
+
+        fn peek(&self, _max_txs: usize, _max_bytes: usize) -> Vec<Transaction> {
+            self.0.clone()
+        }
+
+        fn remove(&mut self, _tx_hash: &TxHash) -> Option<Transaction> {
+            None
+        }
+
+        fn reinsert(&mut self, txs: Vec<Transaction>) {
+            self.0.extend(txs);
+        }
+    }
+
+    #[test]
+    fn reader_follows_imports_without_the_engine() {
+        let alice = AccountId(Hash256::compute(b"alice"));
+        let bob = AccountId(Hash256::compute(b"bob"));
+        let genesis = GenesisConfig {
+            accounts: vec![GenesisAccount {
+                account: alice,
+                balance: 1_000,
+                stake: 0,
+            }],
+            ..GenesisConfig::default()
+        };
+        let mut engine = ConsensusEngine::with_genesis(
+            ConsensusConfig::default(),
+            &genesis,
+            SharedStore::new(InMemoryBlockStore::new()),
+            AcceptAllValidator,
+            LongestChainForkChoice,
+        )
+        .unwrap();
+        let reader = engine.reader();
+        assert_eq!(reader.tip(), Some(genesis.genesis_hash()));
+        assert_eq!(reader.genesis_hash(), Some(genesis.genesis_hash()));
+
+        let transfer = Transaction::Transfer(TxTransfer {
+            from: alice,
+            to: bob,
+            amount: 100,
+            fee: 1,
+            nonce: 0,
+            signature: Signature(vec![1]),
+        });
+        let tx_hash = transfer.compute_hash();
+        let (hash, _) = engine
+            .propose_block(alice, &mut Txs(vec![transfer]), 1_700_000_100)
+            .unwrap();
+
+        // A clone on another thread sees the import without the engine.
+        let clone = reader.clone();
+        std::thread::spawn(move || {
+            assert_eq!(clone.tip(), Some(hash));
+            assert_eq!(clone.tip_block().unwrap().unwrap().header.height, 1);
+            assert_eq!(clone.state().account(&bob).balance, 100);
+            assert_eq!(clone.account_summary(&alice).account.nonce, 1);
+            let location = clone.locate_tx(&tx_hash).unwrap().unwrap();
+            assert_eq!((location.block_hash, location.height), (hash, 1));
+            assert_eq!(clone.proposer_stats(&alice).blocks_accepted, 1);
+        })
+        .join()
+        .unwrap();
+        assert_eq!(reader.current_epoch().number, engine.current_epoch().number);
+    }
+}
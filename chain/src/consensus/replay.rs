@@ -0,0 +1,509 @@
+//! Deterministic replay of the stored canonical chain.
+//!
+//! To debug a node whose state diverged from its peers', a [`Replayer`]
+//! rebuilds the state from genesis: it walks the canonical blocks of a
+//! [`BlockStore`] in height order, re-runs block validation and execution
+//! on each, and compares the result with what the store committed to:
+//!
+//! - the hash each block is indexed under at its height,
+//! - its height and its link to the canonical block below it,
+//! - the header's transactions, state and receipts roots,
+//! - the receipts stored for the block, if any.
+//!
+//! The replay stops at the first block that does not match and reports
+//! its height as a [`Divergence`]. A pruned store is replayed from its
+//! [`Checkpoint`](super::store::Checkpoint) instead of from genesis.
+//!
+//! Validation runs through any [`BlockValidator`]. ML verdicts are not
+//! deterministic, so a replay normally leaves the ML check out, or backs
+//! it with a [`MockMlVerifier`](crate::ml_client::MockMlVerifier),
+//! instead of calling the live service.
+
+use std::fmt;
+
+use crate::execution::{
+    ChainState, ExecutionError, Receipt, StateHandle, apply_block_with_receipts, receipts_root,
+};
+use crate::storage::StorageError;
+use crate::types::{Block, BlockHash, Hash256};
+
+use super::error::ValidationError;
+use super::genesis::GenesisConfig;
+use super::stake::StakeTable;
+use super::store::BlockStore;
+use super::validator::{AcceptAllValidator, BlockValidator, ValidationContext};
+
+/// Why a replayed block does not match the store.
+#[derive(Debug)]
+pub enum ReplayMismatch {
+    /// The height index has no entry below the tip.
+    MissingHeight,
+    /// The height index names a block the store does not hold.
+    MissingBlock,
+    /// The stored block could not be decoded.
+    CorruptedBlock,
+    /// The stored block hashes to `computed`, not to the hash it is
+    /// indexed under.
+    Hash { computed: BlockHash },
+    /// The block's header names another height than the one it is indexed
+    /// at.
+    Height { header: u64 },
+    /// The block's parent is not the canonical block below it.
+    Parent {
+        header: BlockHash,
+        expected: BlockHash,
+    },
+    /// A validator or a header root check rejected the block.
+    Invalid(ValidationError),
+    /// A transaction of the block failed to execute.
+    Execution(ExecutionError),
+    /// The receipts stored for the block differ from the recomputed ones.
+    Receipts { stored: Hash256, computed: Hash256 },
+}
+
+impl fmt::Display for ReplayMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReplayMismatch::MissingHeight => {
+                write!(f, "no canonical block is indexed at this height")
+            }
+            ReplayMismatch::MissingBlock => write!(f, "the indexed block is not stored"),
+            ReplayMismatch::CorruptedBlock => write!(f, "the stored block could not be decoded"),
+            ReplayMismatch::Hash { computed } => write!(
+                f,
+                "the stored block hashes to {}",
+                hex::encode(computed.0.as_bytes())
+            ),
+            ReplayMismatch::Height { header } => write!(f, "the header names height {header}"),
+            ReplayMismatch::Parent { header, expected } => write!(
+                f,
+                "parent is {}, expected the canonical block {}",
+                hex::encode(header.0.as_bytes()),
+                hex::encode(expected.0.as_bytes())
+            ),
+            ReplayMismatch::Invalid(e) => write!(f, "{e}"),
+            ReplayMismatch::Execution(e) => write!(f, "execution failed: {e}"),
+            ReplayMismatch::Receipts { stored, computed } => write!(
+                f,
+                "stored receipts root {} differs from the recomputed {}",
+                hex::encode(stored.as_bytes()),
+                hex::encode(computed.as_bytes())
+            ),
+        }
+    }
+}
+
+/// The first canonical block a replay could not reproduce.
+#[derive(Debug)]
+pub struct Divergence {
+    /// Height of the block.
+    pub height: u64,
+    /// Hash the height index names at `height`, if any.
+    pub hash: Option<BlockHash>,
+    /// What did not match.
+    pub mismatch: ReplayMismatch,
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "height {}", self.height)?;
+        if let Some(hash) = &self.hash {
+            write!(f, " (block {})", hex::encode(hash.0.as_bytes()))?;
+        }
+        write!(f, ": {}", self.mismatch)
+    }
+}
+
+/// Outcome of a [`Replayer::replay`].
+#[derive(Debug)]
+pub struct ReplayReport {
+    /// Height of the first replayed block: 0, or the block after the
+    /// pruning checkpoint.
+    pub from: u64,
+    /// Blocks that matched the store.
+    pub matched: u64,
+    /// Hash of the last block that matched, or of the checkpoint if none
+    /// did.
+    pub last: Option<BlockHash>,
+    /// State after the last block that matched.
+    pub state: ChainState,
+    /// The first block that did not match, if any.
+    pub divergence: Option<Divergence>,
+}
+
+impl ReplayReport {
+    /// Returns `true` if every block up to the tip matched.
+    pub fn is_consistent(&self) -> bool {
+        self.divergence.is_none()
+    }
+}
+
+/// Re-executes the canonical chain of a store and checks it against the
+/// stored commitments.
+///
+/// By default no validator runs; [`with_validator`](Self::with_validator)
+/// adds one. Genesis blocks are written at startup rather than imported,
+/// so, as on import, validators only see blocks above height 0.
+pub struct Replayer<V = AcceptAllValidator> {
+    genesis_state: ChainState,
+    genesis: Option<BlockHash>,
+    validator: V,
+    state_handle: Option<StateHandle>,
+    stake_table: Option<StakeTable>,
+}
+
+impl Replayer {
+    /// Replays on top of `genesis_state`, the state the genesis block is
+    /// executed against.
+    pub fn new(genesis_state: ChainState) -> Self {
+        Self {
+            genesis_state,
+            genesis: None,
+            validator: AcceptAllValidator,
+            state_handle: None,
+            stake_table: None,
+        }
+    }
+
+    /// Replays the chain of `genesis`: on top of its initial state, and
+    /// requiring the block at height 0 to be its genesis block.
+    pub fn from_genesis(genesis: &GenesisConfig) -> Self {
+        Self {
+            genesis: Some(genesis.genesis_hash()),
+            ..Self::new(genesis.initial_state())
+        }
+    }
+}
+
+impl<V> Replayer<V> {
+    /// Validates every replayed block above genesis with `validator`.
+    pub fn with_validator<W: BlockValidator>(self, validator: W) -> Replayer<W> {
+        Replayer {
+            genesis_state: self.genesis_state,
+            genesis: self.genesis,
+            validator,
+            state_handle: self.state_handle,
+            stake_table: self.stake_table,
+        }
+    }
+
+    /// Fills `handle` with the replayed state before each block is
+    /// validated, for validators such as
+    /// [`StatefulValidity`](crate::validation::StatefulValidity) sharing
+    /// it.
+    pub fn with_state_handle(mut self, handle: StateHandle) -> Self {
+        self.state_handle = Some(handle);
+        self
+    }
+
+    /// Fills `table` with the replayed active stakes before each block is
+    /// validated, for a
+    /// [`StakeWeightedSelector`](super::stake::StakeWeightedSelector)
+    /// sharing it.
+    pub fn with_stake_table(mut self, table: StakeTable) -> Self {
+        self.stake_table = Some(table);
+        self
+    }
+}
+
+impl<V: BlockValidator> Replayer<V> {
+    /// Replays the canonical chain of `store` from genesis (or its pruning
+    /// checkpoint) to its tip.
+    ///
+    /// A block that does not match the store ends the replay and is
+    /// reported in [`ReplayReport::divergence`]; only failures to read the
+    /// store are returned as errors.
+    pub fn replay<S>(&self, store: &S) -> Result<ReplayReport, StorageError>
+    where
+        S: BlockStore + Sync,
+    {
+        let mut report = ReplayReport {
+            from: 0,
+            matched: 0,
+            last: None,
+            state: self.genesis_state.clone(),
+            divergence: None,
+        };
+        let Some(tip) = store.tip()? else {
+            return Ok(report);
+        };
+        let tip_height = store
+            .get_block(&tip)?
+            .ok_or(StorageError::CorruptedMeta("tip block is missing"))?
+            .header
+            .height;
+
+        let mut parent = None;
+        if let Some(checkpoint) = store.checkpoint()? {
+            let Some(block) = store.get_block(&checkpoint.hash)? else {
+                report.divergence = Some(Divergence {
+                    height: checkpoint.height,
+                    hash: Some(checkpoint.hash),
+                    mismatch: ReplayMismatch::MissingBlock,
+                });
+                return Ok(report);
+            };
+            report.from = checkpoint.height + 1;
+            report.last = Some(checkpoint.hash);
+            report.state = checkpoint.state;
+            parent = Some(block);
+        }
+
+        for height in report.from..=tip_height {
+            let diverged = |hash, mismatch| Divergence {
+                height,
+                hash,
+                mismatch,
+            };
+            let Some(hash) = store.get_hash_at_height(height)? else {
+                report.divergence = Some(diverged(None, ReplayMismatch::MissingHeight));
+                break;
+            };
+            let block = match store.get_block(&hash) {
+                Ok(Some(block)) => block,
+                Ok(None) => {
+                    report.divergence = Some(diverged(Some(hash), ReplayMismatch::MissingBlock));
+                    break;
+                }
+                Err(StorageError::CorruptedBlock(_)) => {
+                    report.divergence = Some(diverged(Some(hash), ReplayMismatch::CorruptedBlock));
+                    break;
+                }
+                Err(e) => return Err(e),
+            };
+            let computed = block.compute_hash();
+            if computed != hash {
+                report.divergence = Some(diverged(Some(hash), ReplayMismatch::Hash { computed }));
+                break;
+            }
+            if block.header.height != height {
+                let header = block.header.height;
+                report.divergence = Some(diverged(Some(hash), ReplayMismatch::Height { header }));
+                break;
+            }
+            let receipts = store.get_receipts(&hash)?;
+
+            let replayed = self.replay_block(
+                store,
+                hash,
+                &block,
+                report.last.zip(parent.as_ref()),
+                &report.state,
+                receipts.as_deref(),
+            );
+            match replayed {
+                Ok(state) => {
+                    report.matched += 1;
+                    report.last = Some(hash);
+                    report.state = state;
+                    parent = Some(block);
+                }
+                Err(mismatch) => {
+                    report.divergence = Some(diverged(Some(hash), mismatch));
+                    break;
+                }
+            }
+        }
+        Ok(report)
+    }
+
+    /// Checks and executes the canonical `block`, stored under `hash`, on
+    /// top of `state`, the state after `parent`, and returns the
+    /// post-state.
+    fn replay_block<S>(
+        &self,
+        store: &S,
+        hash: BlockHash,
+        block: &Block,
+        parent: Option<(BlockHash, &Block)>,
+        state: &ChainState,
+        stored_receipts: Option<&[Receipt]>,
+    ) -> Result<ChainState, ReplayMismatch>
+    where
+        S: BlockStore + Sync,
+    {
+        match parent {
+            Some((expected, parent)) => {
+                if block.header.parent != expected {
+                    return Err(ReplayMismatch::Parent {
+                        header: block.header.parent,
+                        expected,
+                    });
+                }
+                if let Some(handle) = &self.state_handle {
+                    handle.update(expected, state);
+                }
+                if let Some(table) = &self.stake_table {
+                    table.update(state);
+                }
+                let ctx = ValidationContext::new(Some(parent), store);
+                self.validator
+                    .validate(block, &ctx)
+                    .map_err(ReplayMismatch::Invalid)?;
+            }
+            None => {
+                if self.genesis.is_some_and(|genesis| genesis != hash) {
+                    return Err(ReplayMismatch::Invalid(ValidationError::NotGenesis));
+                }
+            }
+        }
+        let txs_root = block.compute_txs_root();
+        if txs_root != block.header.txs_root {
+            return Err(ReplayMismatch::Invalid(ValidationError::TxsRootMismatch {
+                header: block.header.txs_root,
+                computed: txs_root,
+            }));
+        }
+
+        let (post_state, receipts) =
+            apply_block_with_receipts(state, block).map_err(ReplayMismatch::Execution)?;
+        let state_root = post_state.state_root();
+        if state_root != block.header.state_root {
+            return Err(ReplayMismatch::Invalid(
+                ValidationError::StateRootMismatch {
+                    header: block.header.state_root,
+                    computed: state_root,
+                },
+            ));
+        }
+        let computed = receipts_root(&receipts);
+        if computed != block.header.receipts_root {
+            return Err(ReplayMismatch::Invalid(
+                ValidationError::ReceiptsRootMismatch {
+                    header: block.header.receipts_root,
+                    computed,
+                },
+            ));
+        }
+        if let Some(stored) = stored_receipts.map(receipts_root)
+            && stored != computed
+        {
+            return Err(ReplayMismatch::Receipts { stored, computed });
+        }
+        Ok(post_state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::InMemoryBlockStore;
+    use crate::types::{AccountId, HASH_LEN, Header, Signature, Transaction, TxTransfer};
+
+    fn account(byte: u8) -> AccountId {
+        AccountId(Hash256([byte; HASH_LEN]))
+    }
+
+    fn transfer(nonce: u64) -> Transaction {
+        Transaction::Transfer(TxTransfer {
+            from: account(1),
+            to: account(2),
+            amount: 10,
+            fee: 1,
+            nonce,
+            signature: Signature(Vec::new()),
+        })
+    }
+
+    fn genesis_state() -> ChainState {
+        let mut state = ChainState::new();
+        state.account_mut(account(1)).balance = 1_000;
+        state
+    }
+
+    /// Builds the executed child of `parent` at `height`, with one
+    /// transfer, and returns it with its post-state.
+    fn executed(parent: BlockHash, height: u64, state: &ChainState) -> (Block, ChainState) {
+        let mut block = Block {
+            header: Header {
+                parent,
+                height,
+                timestamp: 1_700_000_000 + height,
+                proposer: account(9),
+                txs_root: Hash256([0u8; HASH_LEN]),
+                state_root: Hash256([0u8; HASH_LEN]),
+                receipts_root: Hash256([0u8; HASH_LEN]),
+                pos_proof: None,
+            },
+            txs: vec![transfer(height)],
+        };
+        block.header.txs_root = block.compute_txs_root();
+        let (post_state, receipts) = apply_block_with_receipts(state, &block).unwrap();
+        block.header.state_root = post_state.state_root();
+        block.header.receipts_root = receipts_root(&receipts);
+        (block, post_state)
+    }
+
+    /// Stores a canonical chain of `len` blocks and returns their hashes
+    /// and the state after the last one.
+    fn chain(store: &mut InMemoryBlockStore, len: u64) -> (Vec<BlockHash>, ChainState) {
+        let mut parent = BlockHash(Hash256([0u8; HASH_LEN]));
+        let mut state = genesis_state();
+        let mut hashes = Vec::new();
+        for height in 0..len {
+            let (block, post_state) = executed(parent, height, &state);
+            parent = block.compute_hash();
+            state = post_state;
+            store.put_block_and_set_tip(block).unwrap();
+            hashes.push(parent);
+        }
+        (hashes, state)
+    }
+
+    #[test]
+    fn replay_of_a_consistent_chain_reaches_the_tip_state() {
+        let mut store = InMemoryBlockStore::new();
+        let (hashes, state) = chain(&mut store, 4);
+
+        let report = Replayer::new(genesis_state()).replay(&store).unwrap();
+
+        assert!(report.is_consistent(), "{:?}", report.divergence);
+        assert_eq!(report.from, 0);
+        assert_eq!(report.matched, 4);
+        assert_eq!(report.last, hashes.last().copied());
+        assert_eq!(report.state.state_root(), state.state_root());
+    }
+
+    #[test]
+    fn replay_reports_the_first_block_whose_roots_do_not_match() {
+        let mut store = InMemoryBlockStore::new();
+        let (hashes, state) = chain(&mut store, 2);
+
+        // A block committing to a state root its transactions do not
+        // produce, followed by one that builds on it.
+        let (mut bad, bad_state) = executed(hashes[1], 2, &state);
+        bad.header.state_root = Hash256([7u8; HASH_LEN]);
+        let bad_hash = bad.compute_hash();
+        store.put_block_and_set_tip(bad).unwrap();
+        let (next, _) = executed(bad_hash, 3, &bad_state);
+        store.put_block_and_set_tip(next).unwrap();
+
+        let report = Replayer::new(genesis_state()).replay(&store).unwrap();
+
+        assert_eq!(report.matched, 2);
+        assert_eq!(report.last, Some(hashes[1]));
+        let divergence = report.divergence.expect("height 2 diverges");
+        assert_eq!(divergence.height, 2);
+        assert_eq!(divergence.hash, Some(bad_hash));
+        assert!(matches!(
+            divergence.mismatch,
+            ReplayMismatch::Invalid(ValidationError::StateRootMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn replay_from_another_genesis_diverges_at_height_zero() {
+        let mut store = InMemoryBlockStore::new();
+        chain(&mut store, 2);
+
+        let report = Replayer::new(ChainState::new())
+            .with_validator(AcceptAllValidator)
+            .replay(&store)
+            .unwrap();
+
+        assert_eq!(report.matched, 0);
+        let divergence = report.divergence.expect("genesis diverges");
+        assert_eq!(divergence.height, 0);
+        assert!(matches!(divergence.mismatch, ReplayMismatch::Execution(_)));
+    }
+}
@@ -0,0 +1,171 @@
+//! Per-proposer performance statistics.
+//!
+//! The engine records, for every block it validates, who proposed it,
+//! whether it was accepted, and how long validation (including the ML
+//! authenticity check) took. Together with a scan of the canonical chain
+//! this yields a [`ValidatorReport`] per proposer, used for accountability
+//! analysis in experiments.
+//!
+//! Import statistics are kept in memory and cover blocks seen since the
+//! engine was created; chain-derived figures cover the whole canonical
+//! chain.
+
+use std::time::Duration;
+
+use crate::storage::StorageError;
+use crate::types::AccountId;
+
+use super::store::BlockStore;
+
+/// Import statistics for a single proposer, accumulated by the engine.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct ProposerStats {
+    /// Blocks that passed validation and were committed.
+    pub blocks_accepted: u64,
+    /// Blocks that failed validation or execution.
+    pub blocks_rejected: u64,
+    /// Total time spent in the block validator for this proposer's blocks.
+    pub validation_time: Duration,
+}
+
+impl ProposerStats {
+    /// Records one validated block.
+    pub fn record(&mut self, validation_time: Duration, accepted: bool) {
+        if accepted {
+            self.blocks_accepted += 1;
+        } else {
+            self.blocks_rejected += 1;
+        }
+        self.validation_time += validation_time;
+    }
+
+    /// Average validation time per block, or `None` if no block was
+    /// validated.
+    pub fn average_validation_time(&self) -> Option<Duration> {
+        let blocks = self.blocks_accepted + self.blocks_rejected;
+        let blocks = u32::try_from(blocks).ok().filter(|&n| n > 0)?;
+        Some(self.validation_time / blocks)
+    }
+}
+
+/// Performance report for one proposer.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ValidatorReport {
+    /// Proposer the report is about.
+    pub proposer: AccountId,
+    /// Blocks by this proposer on the canonical chain.
+    pub canonical_blocks: u64,
+    /// Import statistics since the engine started.
+    pub stats: ProposerStats,
+    /// Slots of `block_time_secs` that elapsed without a block right before
+    /// one of this proposer's canonical blocks, i.e. how late the proposer
+    /// was in total.
+    pub missed_slots: u64,
+}
+
+impl ValidatorReport {
+    /// Builds the report by scanning the canonical chain in `store`.
+    pub fn build<S: BlockStore>(
+        store: &S,
+        proposer: AccountId,
+        stats: ProposerStats,
+        block_time_secs: u64,
+    ) -> Result<Self, StorageError> {
+        let mut canonical_blocks = 0;
+        let mut missed_slots = 0;
+        let mut parent_timestamp = None;
+
+        let mut height = 0;
+        while let Some(block) = store.get_block_by_height(height)? {
+            if block.header.proposer == proposer {
+                canonical_blocks += 1;
+                if let Some(parent_ts) = parent_timestamp
+                    && block_time_secs > 0
+                {
+                    let gap = block.header.timestamp.saturating_sub(parent_ts);
+                    missed_slots += (gap / block_time_secs).saturating_sub(1);
+                }
+            }
+            parent_timestamp = Some(block.header.timestamp);
+            height += 1;
+        }
+
+        Ok(Self {
+            proposer,
+            canonical_blocks,
+            stats,
+            missed_slots,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::InMemoryBlockStore;
+    use crate::types::{Block, BlockHash, HASH_LEN, Hash256, Header};
+
+    fn account(byte: u8) -> AccountId {
+        AccountId(Hash256([byte; HASH_LEN]))
+    }
+
+    /// Builds a canonical chain with the given `(proposer, timestamp)`
+    /// blocks.
+    fn chain(blocks: &[(u8, u64)]) -> InMemoryBlockStore {
+        let mut store = InMemoryBlockStore::new();
+        let mut parent = BlockHash(Hash256([0u8; HASH_LEN]));
+        for (height, &(proposer, timestamp)) in blocks.iter().enumerate() {
+            let block = Block {
+                header: Header {
+                    parent,
+                    height: height as u64,
+                    timestamp,
+                    proposer: account(proposer),
+                    txs_root: Hash256([0u8; HASH_LEN]),
+                    state_root: Hash256([0u8; HASH_LEN]),
+                    receipts_root: Hash256([0u8; HASH_LEN]),
+                    pos_proof: None,
+                },
+                txs: Vec::new(),
+            };
+            parent = block.compute_hash();
+            store.put_block(block).unwrap();
+            store.set_tip(parent).unwrap();
+        }
+        store
+    }
+
+    #[test]
+    fn counts_canonical_blocks_and_missed_slots() {
+        // Block time 5s: proposer 1 is on time at t=5, then 2 slots late at
+        // t=25 (after proposer 2's block at t=10).
+        let store = chain(&[(9, 0), (1, 5), (2, 10), (1, 25)]);
+
+        let report =
+            ValidatorReport::build(&store, account(1), ProposerStats::default(), 5).unwrap();
+
+        assert_eq!(report.canonical_blocks, 2);
+        assert_eq!(report.missed_slots, 2);
+
+        let other =
+            ValidatorReport::build(&store, account(3), ProposerStats::default(), 5).unwrap();
+        assert_eq!(other.canonical_blocks, 0);
+        assert_eq!(other.missed_slots, 0);
+    }
+
+    #[test]
+    fn average_validation_time_covers_accepted_and_rejected_blocks() {
+        let mut stats = ProposerStats::default();
+        assert_eq!(stats.average_validation_time(), None);
+
+        stats.record(Duration::from_millis(10), true);
+        stats.record(Duration::from_millis(30), false);
+
+        assert_eq!(stats.blocks_accepted, 1);
+        assert_eq!(stats.blocks_rejected, 1);
+        assert_eq!(
+            stats.average_validation_time(),
+            Some(Duration::from_millis(20))
+        );
+    }
+}
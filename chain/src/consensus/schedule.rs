@@ -0,0 +1,160 @@
+//! Slot-based proposer scheduling.
+//!
+//! Time is divided into slots of `block_time_secs`; the slot of a block is
+//! `timestamp / block_time_secs`. A [`ProposerSelector`] names the single
+//! validator allowed to propose in each slot. The engine's producer loops
+//! skip slots their proposer does not own, and
+//! [`ProposerValidity`](crate::validation::ProposerValidity) rejects blocks
+//! from anyone but the slot leader.
+//!
+//! An empty [`ValidatorSet`] disables scheduling: every node may propose in
+//! every slot, as before validator sets existed.
+//!
+//! Selectors whose leaders are private, such as the VRF lottery in
+//! [`election`](super::election), name no leader and check a proof carried
+//! in the block header instead.
+
+use serde::Deserialize;
+
+use crate::types::{AccountId, Header};
+
+use super::election::{ElectionKind, VrfPublicKey};
+
+/// Configuration for the validator set.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ValidatorSetConfig {
+    /// Validators in scheduling order. Empty disables proposer scheduling.
+    pub accounts: Vec<AccountId>,
+    /// How slot leaders are chosen.
+    pub election: ElectionKind,
+    /// VRF public keys of `accounts`, in the same order. Required by VRF
+    /// election.
+    pub vrf_keys: Vec<VrfPublicKey>,
+}
+
+/// Ordered set of validators eligible to propose blocks.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ValidatorSet {
+    validators: Vec<AccountId>,
+}
+
+impl ValidatorSet {
+    /// Creates a set from `validators`, keeping the first occurrence of any
+    /// duplicate.
+    pub fn new(validators: impl IntoIterator<Item = AccountId>) -> Self {
+        let mut set = Self::default();
+        for validator in validators {
+            if !set.contains(&validator) {
+                set.validators.push(validator);
+            }
+        }
+        set
+    }
+
+    /// Creates the set described by `cfg`.
+    pub fn from_config(cfg: &ValidatorSetConfig) -> Self {
+        Self::new(cfg.accounts.iter().copied())
+    }
+
+    /// Returns the validators in scheduling order.
+    pub fn validators(&self) -> &[AccountId] {
+        &self.validators
+    }
+
+    /// Returns the number of validators.
+    pub fn len(&self) -> usize {
+        self.validators.len()
+    }
+
+    /// Returns `true` if the set is empty, i.e. scheduling is disabled.
+    pub fn is_empty(&self) -> bool {
+        self.validators.is_empty()
+    }
+
+    /// Returns `true` if `account` is a validator.
+    pub fn contains(&self, account: &AccountId) -> bool {
+        self.validators.contains(account)
+    }
+}
+
+/// Decides which validator may propose at a given time.
+pub trait ProposerSelector {
+    /// Returns the validator allowed to propose a block stamped with
+    /// `timestamp`, or `None` if anyone may.
+    fn expected_proposer(&self, timestamp: u64) -> Option<AccountId>;
+
+    /// Checks the eligibility proof in `header`, if the schedule needs
+    /// one. The default accepts every header.
+    fn verify_eligibility(&self, _header: &Header) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+/// Round-robin schedule: slot `s` belongs to validator `s % len`.
+#[derive(Clone, Debug, Default)]
+pub struct RoundRobinSelector {
+    validators: ValidatorSet,
+    block_time_secs: u64,
+}
+
+impl RoundRobinSelector {
+    /// Creates a schedule over `validators` with slots of `block_time_secs`.
+    pub fn new(validators: ValidatorSet, block_time_secs: u64) -> Self {
+        Self {
+            validators,
+            block_time_secs,
+        }
+    }
+
+    /// Returns the validator set being scheduled.
+    pub fn validators(&self) -> &ValidatorSet {
+        &self.validators
+    }
+
+    /// Returns the slot containing `timestamp`.
+    pub fn slot(&self, timestamp: u64) -> u64 {
+        timestamp / self.block_time_secs.max(1)
+    }
+}
+
+impl ProposerSelector for RoundRobinSelector {
+    fn expected_proposer(&self, timestamp: u64) -> Option<AccountId> {
+        if self.validators.is_empty() {
+            return None;
+        }
+        let index = self.slot(timestamp) % self.validators.len() as u64;
+        Some(self.validators.validators[index as usize])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{HASH_LEN, Hash256};
+
+    fn account(byte: u8) -> AccountId {
+        AccountId(Hash256([byte; HASH_LEN]))
+    }
+
+    #[test]
+    fn validator_set_drops_duplicates_in_order() {
+        let set = ValidatorSet::new([account(2), account(1), account(2)]);
+        assert_eq!(set.validators(), &[account(2), account(1)]);
+        assert!(set.contains(&account(1)));
+        assert!(!set.contains(&account(3)));
+    }
+
+    #[test]
+    fn round_robin_rotates_by_slot() {
+        let selector = RoundRobinSelector::new(ValidatorSet::new([account(1), account(2)]), 5);
+
+        assert_eq!(selector.expected_proposer(0), Some(account(1)));
+        assert_eq!(selector.expected_proposer(4), Some(account(1)));
+        assert_eq!(selector.expected_proposer(5), Some(account(2)));
+        assert_eq!(selector.expected_proposer(10), Some(account(1)));
+
+        let open = RoundRobinSelector::new(ValidatorSet::default(), 5);
+        assert_eq!(open.expected_proposer(5), None);
+    }
+}
@@ -0,0 +1,175 @@
+//! Stake-weighted proposer selection.
+//!
+//! [`StakeWeightedSelector`] names one leader per slot, drawn with
+//! probability proportional to active stake (see
+//! [`crate::execution::stake`]). The draw hashes the network seed with the
+//! slot number, so every node computes the same leader from the same stake
+//! distribution.
+//!
+//! Validators only ever see blocks, never chain state, so the distribution
+//! lives in a shared [`StakeTable`] that the engine refreshes whenever its
+//! state changes (see
+//! [`ConsensusEngine::with_stake_table`](super::ConsensusEngine::with_stake_table)).
+//! Active stake only moves at epoch boundaries, so nodes at the same tip
+//! agree on the leader of every slot in an epoch.
+
+use std::sync::{Arc, RwLock};
+
+use crate::execution::ChainState;
+use crate::types::{AccountId, Hash256};
+
+use super::schedule::{ProposerSelector, ValidatorSet};
+
+/// Domain separator of the per-slot leader draw.
+const DRAW_CONTEXT: &[u8] = b"mlsnitch-stake-leader";
+
+/// Shared snapshot of the active stake distribution.
+///
+/// Clones share the same table.
+#[derive(Clone, Debug, Default)]
+pub struct StakeTable(Arc<RwLock<Vec<(AccountId, u64)>>>);
+
+impl StakeTable {
+    /// Creates an empty table: nobody has stake, so anyone may propose.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replaces the table with the active stakes of `state`.
+    pub fn update(&self, state: &ChainState) {
+        *self.0.write().expect("stake table lock poisoned") = state.active_stakes();
+    }
+
+    /// Returns the active stakes, in ascending [`AccountId`] order.
+    pub fn stakes(&self) -> Vec<(AccountId, u64)> {
+        self.0.read().expect("stake table lock poisoned").clone()
+    }
+}
+
+/// Stake-weighted schedule.
+///
+/// If the validator set is non-empty, only stake held by its members
+/// counts. Without any counted stake, scheduling is disabled, as for an
+/// empty [`RoundRobinSelector`](super::RoundRobinSelector).
+#[derive(Clone, Debug)]
+pub struct StakeWeightedSelector {
+    validators: ValidatorSet,
+    table: StakeTable,
+    block_time_secs: u64,
+    seed: Hash256,
+}
+
+impl StakeWeightedSelector {
+    /// Creates a schedule over the stake in `table`, restricted to
+    /// `validators`, with slots of `block_time_secs`.
+    ///
+    /// `seed` separates networks; nodes use the genesis block hash.
+    pub fn new(
+        validators: ValidatorSet,
+        table: StakeTable,
+        block_time_secs: u64,
+        seed: Hash256,
+    ) -> Self {
+        Self {
+            validators,
+            table,
+            block_time_secs,
+            seed,
+        }
+    }
+
+    /// Returns the validator set stake is restricted to.
+    pub fn validators(&self) -> &ValidatorSet {
+        &self.validators
+    }
+
+    /// Returns the stake table the schedule draws from.
+    pub fn table(&self) -> &StakeTable {
+        &self.table
+    }
+
+    /// Returns the slot containing `timestamp`.
+    pub fn slot(&self, timestamp: u64) -> u64 {
+        timestamp / self.block_time_secs.max(1)
+    }
+
+    /// Returns a uniform draw in `[0, total)` for `slot`.
+    fn draw(&self, slot: u64, total: u64) -> u64 {
+        let mut input = Vec::with_capacity(DRAW_CONTEXT.len() + 40);
+        input.extend_from_slice(DRAW_CONTEXT);
+        input.extend_from_slice(self.seed.as_bytes());
+        input.extend_from_slice(&slot.to_be_bytes());
+        let digest = Hash256::compute(&input);
+        let mut word = [0u8; 8];
+        word.copy_from_slice(&digest.as_bytes()[..8]);
+        ((u128::from(u64::from_be_bytes(word)) * u128::from(total)) >> 64) as u64
+    }
+}
+
+impl ProposerSelector for StakeWeightedSelector {
+    fn expected_proposer(&self, timestamp: u64) -> Option<AccountId> {
+        let stakes: Vec<_> = self
+            .table
+            .stakes()
+            .into_iter()
+            .filter(|(id, _)| self.validators.is_empty() || self.validators.contains(id))
+            .collect();
+        let total = stakes
+            .iter()
+            .fold(0u64, |sum, (_, stake)| sum.saturating_add(*stake));
+        if total == 0 {
+            return None;
+        }
+
+        let mut draw = self.draw(self.slot(timestamp), total);
+        for (id, stake) in stakes {
+            if draw < stake {
+                return Some(id);
+            }
+            draw -= stake;
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::execution::ChainState;
+    use crate::types::HASH_LEN;
+
+    fn account(byte: u8) -> AccountId {
+        AccountId(Hash256([byte; HASH_LEN]))
+    }
+
+    #[test]
+    fn leaders_are_drawn_in_proportion_to_active_stake() {
+        let mut state = ChainState::new();
+        state.stake_mut(account(1)).bonded = 300;
+        state.stake_mut(account(2)).bonded = 100;
+        state.stake_mut(account(3)).bonded = 50;
+        state.activate_stakes();
+        state.stake_mut(account(3)).bonded = 10_000;
+
+        let table = StakeTable::new();
+        let selector = StakeWeightedSelector::new(
+            ValidatorSet::new([account(1), account(2)]),
+            table.clone(),
+            1,
+            Hash256([9u8; HASH_LEN]),
+        );
+        assert_eq!(selector.expected_proposer(0), None);
+
+        table.update(&state);
+        let mut wins = [0u32; 4];
+        for slot in 0..4_000 {
+            let leader = selector.expected_proposer(slot).expect("stake is bonded");
+            wins[leader.0.as_bytes()[0] as usize] += 1;
+        }
+
+        // Account 3 is not a validator; 1 holds three quarters of the rest.
+        assert_eq!(wins[3], 0);
+        assert!((2_800..3_200).contains(&wins[1]), "{wins:?}");
+        assert_eq!(wins[1] + wins[2], 4_000);
+    }
+}
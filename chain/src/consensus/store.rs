@@ -1,24 +1,247 @@
 //! Storage abstraction used by the consensus engine.
 
-use crate::types::{Block, BlockHash};
+use std::ops::Range;
+
+use serde::{Deserialize, Serialize};
+
+use crate::execution::{ChainState, MlVerdictStats, Receipt};
+use crate::storage::StorageError;
+use crate::types::{Block, BlockHash, SealedBlock};
+
+/// Stand-in for pruned history: a canonical block and the state after it.
+///
+/// Once the blocks below a checkpoint have been pruned, state replay
+/// starts from [`state`](Self::state) instead of from genesis.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Checkpoint {
+    /// Hash of the checkpoint block.
+    pub hash: BlockHash,
+    /// Height of the checkpoint block.
+    pub height: u64,
+    /// State after executing the checkpoint block.
+    pub state: ChainState,
+}
+
+/// Iterator over canonical blocks, as returned by
+/// [`BlockStore::iter_range`] and [`BlockStore::iter_blocks`].
+pub type BlockIter<'a> = Box<dyn Iterator<Item = Result<Block, StorageError>> + 'a>;
 
 /// Abstract storage interface used by the consensus engine.
 ///
 /// Implementations can be backed by in-memory maps, RocksDB, etc. The
 /// interface is intentionally small: consensus only needs get/put and
 /// a notion of the current tip.
+///
+/// Every method reports backend failures as a [`StorageError`]; a missing
+/// block or index entry is `Ok(None)`, not an error.
 pub trait BlockStore {
     /// Fetches a block by hash, if present.
-    fn get_block(&self, hash: &BlockHash) -> Option<Block>;
+    fn get_block(&self, hash: &BlockHash) -> Result<Option<Block>, StorageError>;
 
     /// Persists a block.
-    fn put_block(&mut self, block: Block);
+    fn put_block(&mut self, block: Block) -> Result<(), StorageError>;
 
     /// Returns the hash of the current tip of the best chain, if any.
-    fn tip(&self) -> Option<BlockHash>;
+    fn tip(&self) -> Result<Option<BlockHash>, StorageError>;
 
     /// Updates the current tip of the best chain.
-    fn set_tip(&mut self, hash: BlockHash);
+    ///
+    /// Implementations must keep the height index consistent with the new
+    /// tip, i.e. after this call [`get_hash_at_height`](Self::get_hash_at_height)
+    /// answers for the chain ending at `hash`.
+    fn set_tip(&mut self, hash: BlockHash) -> Result<(), StorageError>;
+
+    /// Persists `block` and makes it the tip of the best chain.
+    ///
+    /// Backends that persist data should commit the block, its indexes and
+    /// the tip atomically, so a crash never leaves the tip pointing at a
+    /// missing block. The default implementation calls
+    /// [`put_block`](Self::put_block) and then [`set_tip`](Self::set_tip).
+    fn put_block_and_set_tip(&mut self, block: Block) -> Result<(), StorageError> {
+        let hash = block.compute_hash();
+        self.put_block(block)?;
+        self.set_tip(hash)
+    }
+
+    /// Persists a sealed block.
+    ///
+    /// Backends that store encoded blocks should override this to reuse
+    /// the cached bytes and hash. The default implementation calls
+    /// [`put_block`](Self::put_block).
+    fn put_sealed_block(&mut self, block: SealedBlock) -> Result<(), StorageError> {
+        self.put_block(block.into_block())
+    }
+
+    /// Sealed counterpart of [`put_block_and_set_tip`](Self::put_block_and_set_tip),
+    /// with the same atomicity requirement. The default implementation
+    /// calls [`put_block_and_set_tip`](Self::put_block_and_set_tip).
+    fn put_sealed_block_and_set_tip(&mut self, block: SealedBlock) -> Result<(), StorageError> {
+        self.put_block_and_set_tip(block.into_block())
+    }
+
+    /// Returns the hash of the canonical block at `height`, if any.
+    fn get_hash_at_height(&self, height: u64) -> Result<Option<BlockHash>, StorageError>;
+
+    /// Returns the canonical block at `height`, if any.
+    fn get_block_by_height(&self, height: u64) -> Result<Option<Block>, StorageError> {
+        match self.get_hash_at_height(height)? {
+            Some(hash) => self.get_block(&hash),
+            None => Ok(None),
+        }
+    }
+
+    /// Persists a sealed block together with the receipts and ML verdict
+    /// statistics of executing it, and makes it the tip if `set_tip`.
+    ///
+    /// Backends that persist data should commit all of it in one write, as
+    /// for [`put_block_and_set_tip`](Self::put_block_and_set_tip), so a
+    /// crash never leaves a stored block without its receipts.
+    /// [`delete_block`](Self::delete_block) removes the receipts and
+    /// statistics again. The default implementation does not keep them and
+    /// calls [`put_sealed_block_and_set_tip`](Self::put_sealed_block_and_set_tip)
+    /// or [`put_sealed_block`](Self::put_sealed_block).
+    fn put_executed_block(
+        &mut self,
+        block: SealedBlock,
+        receipts: &[Receipt],
+        ml_stats: &[MlVerdictStats],
+        set_tip: bool,
+    ) -> Result<(), StorageError> {
+        let _ = (receipts, ml_stats);
+        if set_tip {
+            self.put_sealed_block_and_set_tip(block)
+        } else {
+            self.put_sealed_block(block)
+        }
+    }
+
+    /// Returns the receipts of the block `hash`, in transaction order, if
+    /// stored.
+    fn get_receipts(&self, hash: &BlockHash) -> Result<Option<Vec<Receipt>>, StorageError> {
+        let _ = hash;
+        Ok(None)
+    }
+
+    /// Returns the ML verdict statistics of the block `hash`, in
+    /// transaction order, if stored.
+    fn get_ml_stats(&self, hash: &BlockHash) -> Result<Option<Vec<MlVerdictStats>>, StorageError> {
+        let _ = hash;
+        Ok(None)
+    }
+
+    /// Iterates over the canonical blocks at heights in `range`, in
+    /// ascending height order, reading each block as the iterator
+    /// advances.
+    ///
+    /// Heights without a canonical block, such as pruned ones, are
+    /// skipped, and the iteration ends at the tip. A height index entry
+    /// naming a block that is not stored yields
+    /// [`StorageError::CorruptedMeta`].
+    ///
+    /// The default implementation looks up each height of `range` up to
+    /// the tip; backends with an ordered height index should override it
+    /// to scan the index instead.
+    fn iter_range(&self, range: Range<u64>) -> BlockIter<'_> {
+        let tip = self
+            .tip()
+            .and_then(|tip| tip.map_or(Ok(None), |hash| self.get_block(&hash)));
+        let end = match tip {
+            Ok(Some(tip)) => range.end.min(tip.header.height.saturating_add(1)),
+            Ok(None) => range.start,
+            Err(e) => return Box::new(std::iter::once(Err(e))),
+        };
+        Box::new((range.start..end).filter_map(
+            move |height| match self.get_hash_at_height(height) {
+                Ok(Some(hash)) => Some(indexed_block(self, &hash)),
+                Ok(None) => None,
+                Err(e) => Some(Err(e)),
+            },
+        ))
+    }
+
+    /// Iterates over every canonical block, from genesis (or the lowest
+    /// unpruned height) to the tip; see [`iter_range`](Self::iter_range).
+    fn iter_blocks(&self) -> BlockIter<'_> {
+        self.iter_range(0..u64::MAX)
+    }
+
+    /// Returns the hashes of all stored blocks, in no particular order.
+    ///
+    /// This is used by maintenance tasks (e.g. fork garbage collection)
+    /// that need to scan the whole store rather than follow parent links.
+    fn block_hashes(&self) -> Result<Vec<BlockHash>, StorageError>;
+
+    /// Removes a block, and its receipts and ML verdict statistics, by
+    /// hash. Removing an unknown hash is a no-op.
+    fn delete_block(&mut self, hash: &BlockHash) -> Result<(), StorageError>;
+
+    /// Returns the checkpoint left by the last pruning pass, if any.
+    fn checkpoint(&self) -> Result<Option<Checkpoint>, StorageError> {
+        Ok(None)
+    }
+
+    /// Records `checkpoint` and deletes the `pruned` blocks below it.
+    ///
+    /// Backends that persist data should do both atomically, so a crash
+    /// never leaves pruned history without a checkpoint. The default
+    /// implementation does not support pruning.
+    fn prune_to(
+        &mut self,
+        checkpoint: Checkpoint,
+        pruned: &[BlockHash],
+    ) -> Result<(), StorageError> {
+        let _ = (checkpoint, pruned);
+        Err(StorageError::Unsupported("history pruning"))
+    }
+
+    /// Makes every committed write durable, e.g. before a node shuts down.
+    ///
+    /// Dropping the store afterwards closes it. The default implementation
+    /// has nothing buffered to flush.
+    fn flush(&mut self) -> Result<(), StorageError> {
+        Ok(())
+    }
+}
+
+/// Loads the block the height index names at some height, which a
+/// consistent store holds.
+pub(crate) fn indexed_block<S>(store: &S, hash: &BlockHash) -> Result<Block, StorageError>
+where
+    S: BlockStore + ?Sized,
+{
+    store.get_block(hash)?.ok_or(StorageError::CorruptedMeta(
+        "height index names a missing block",
+    ))
+}
+
+/// Returns the height-index entries that must be (re)written for the
+/// canonical chain to end at `new_tip`, from the tip downwards.
+///
+/// Walks parent links from `new_tip` until it reaches a block that `store`
+/// already indexes at its height (the fork point) or runs out of stored
+/// ancestors. Entries above the new tip's height are not included; callers
+/// remove those separately.
+pub(crate) fn height_index_updates<S>(
+    store: &S,
+    new_tip: &BlockHash,
+) -> Result<Vec<(u64, BlockHash)>, StorageError>
+where
+    S: BlockStore + ?Sized,
+{
+    let mut updates = Vec::new();
+    let mut cursor = *new_tip;
+    while let Some(block) = store.get_block(&cursor)? {
+        let height = block.header.height;
+        if store.get_hash_at_height(height)? == Some(cursor) {
+            break;
+        }
+        updates.push((height, cursor));
+        if height == 0 {
+            break;
+        }
+        cursor = block.header.parent;
+    }
+    Ok(updates)
 }
 
 #[cfg(test)]
@@ -34,20 +257,35 @@ mod tests {
     }
 
     impl BlockStore for DummyStore {
-        fn get_block(&self, _hash: &BlockHash) -> Option<Block> {
-            None
+        fn get_block(&self, _hash: &BlockHash) -> Result<Option<Block>, StorageError> {
+            Ok(None)
         }
 
-        fn put_block(&mut self, _block: Block) {
+        fn put_block(&mut self, _block: Block) -> Result<(), StorageError> {
             // no-op
+            Ok(())
         }
 
-        fn tip(&self) -> Option<BlockHash> {
-            self.tip
+        fn tip(&self) -> Result<Option<BlockHash>, StorageError> {
+            Ok(self.tip)
         }
 
-        fn set_tip(&mut self, hash: BlockHash) {
+        fn set_tip(&mut self, hash: BlockHash) -> Result<(), StorageError> {
             self.tip = Some(hash);
+            Ok(())
+        }
+
+        fn get_hash_at_height(&self, _height: u64) -> Result<Option<BlockHash>, StorageError> {
+            Ok(None)
+        }
+
+        fn block_hashes(&self) -> Result<Vec<BlockHash>, StorageError> {
+            Ok(Vec::new())
+        }
+
+        fn delete_block(&mut self, _hash: &BlockHash) -> Result<(), StorageError> {
+            // no-op
+            Ok(())
         }
     }
 
@@ -65,11 +303,11 @@ mod tests {
     #[test]
     fn dummy_store_tracks_tip_hash() {
         let mut store = DummyStore::default();
-        assert!(store.tip().is_none());
+        assert!(store.tip().unwrap().is_none());
 
         let zero_hash = BlockHash(Hash256([0u8; HASH_LEN]));
-        store.set_tip(zero_hash);
-        let tip = store.tip();
+        store.set_tip(zero_hash).unwrap();
+        let tip = store.tip().unwrap();
 
         assert!(tip.is_some());
         assert_eq!(tip.unwrap().0.0, [0u8; HASH_LEN]);
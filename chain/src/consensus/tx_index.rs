@@ -0,0 +1,447 @@
+//! Indexes from transaction hashes, accounts and watermark schemes to the
+//! blocks that include the matching transactions.
+//!
+//! The engine records every imported block here, canonical or not, so
+//! that a transaction's location survives reorgs without re-indexing: a
+//! lookup keeps only the locations whose block is canonical at its
+//! height. The index lives in memory and is rebuilt from the store when
+//! the engine starts.
+//!
+//! Besides point lookups by hash, the index serves explorer-style lists:
+//! the transactions an account sent or received, newest first, and the
+//! model registrations made under a watermark scheme.
+
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
+
+use crate::storage::StorageError;
+use crate::types::{AccountId, Aid, Block, BlockHash, Transaction, TxHash};
+
+use super::store::BlockStore;
+
+/// Where a transaction sits in a stored block.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct TxLocation {
+    /// Hash of the block holding the transaction.
+    pub block_hash: BlockHash,
+    /// Height of that block.
+    pub height: u64,
+    /// Position of the transaction in the block.
+    pub index: usize,
+}
+
+impl TxLocation {
+    /// Returns the position of the transaction on its chain, ordered by
+    /// height and then by index in the block.
+    pub fn position(&self) -> (u64, usize) {
+        (self.height, self.index)
+    }
+}
+
+/// A model registration found through [`TxIndex::canonical_registrations`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Registration {
+    /// Registered artefact.
+    pub aid: Aid,
+    /// Where the registering transaction sits.
+    pub location: TxLocation,
+}
+
+/// Locations of the transactions of all stored blocks.
+#[derive(Debug, Default)]
+pub struct TxIndex {
+    locations: HashMap<TxHash, Vec<TxLocation>>,
+    /// Transactions each account sent or received, see
+    /// [`Transaction::accounts`].
+    by_account: HashMap<AccountId, Vec<TxLocation>>,
+    /// Model registrations per watermark scheme id.
+    by_scheme: HashMap<String, Vec<Registration>>,
+}
+
+impl TxIndex {
+    /// Creates an empty index.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Indexes every block in `store`.
+    pub fn rebuild<S>(store: &S) -> Result<Self, StorageError>
+    where
+        S: BlockStore + ?Sized,
+    {
+        let mut index = Self::new();
+        for hash in store.block_hashes()? {
+            if let Some(block) = store.get_block(&hash)? {
+                index.insert_block(hash, &block);
+            }
+        }
+        Ok(index)
+    }
+
+    /// Records the transactions of `block`, stored under `hash`.
+    pub fn insert_block(&mut self, hash: BlockHash, block: &Block) {
+        for (index, tx) in block.txs.iter().enumerate() {
+            let location = TxLocation {
+                block_hash: hash,
+                height: block.header.height,
+                index,
+            };
+            let locations = self.locations.entry(tx.compute_hash()).or_default();
+            if locations.contains(&location) {
+                continue;
+            }
+            locations.push(location);
+            for account in tx.accounts() {
+                self.by_account.entry(account).or_default().push(location);
+            }
+            if let Transaction::RegisterModel(tx) = tx {
+                self.by_scheme
+                    .entry(tx.evidence.scheme_id.clone())
+                    .or_default()
+                    .push(Registration {
+                        aid: tx.aid,
+                        location,
+                    });
+            }
+        }
+    }
+
+    /// Returns the location of `tx_hash` on the canonical chain of
+    /// `store`, if it is included there.
+    pub fn canonical_location<S>(
+        &self,
+        store: &S,
+        tx_hash: &TxHash,
+    ) -> Result<Option<TxLocation>, StorageError>
+    where
+        S: BlockStore + ?Sized,
+    {
+        for location in self.locations.get(tx_hash).into_iter().flatten() {
+            if store.get_hash_at_height(location.height)? == Some(location.block_hash) {
+                return Ok(Some(*location));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Returns up to `limit` locations of canonical transactions involving
+    /// `account`, newest first.
+    ///
+    /// With `before`, only transactions strictly before that
+    /// [`position`](TxLocation::position) are returned, so passing the
+    /// position of the last location of one page yields the next.
+    pub fn canonical_account_txs<S>(
+        &self,
+        store: &S,
+        account: &AccountId,
+        before: Option<(u64, usize)>,
+        limit: usize,
+    ) -> Result<Vec<TxLocation>, StorageError>
+    where
+        S: BlockStore + ?Sized,
+    {
+        let mut candidates: Vec<&TxLocation> = self
+            .by_account
+            .get(account)
+            .into_iter()
+            .flatten()
+            .filter(|location| before.is_none_or(|before| location.position() < before))
+            .collect();
+        candidates.sort_by_key(|location| Reverse(location.position()));
+
+        let mut found = Vec::new();
+        for location in candidates {
+            if found.len() >= limit {
+                break;
+            }
+            if store.get_hash_at_height(location.height)? == Some(location.block_hash) {
+                found.push(*location);
+            }
+        }
+        Ok(found)
+    }
+
+    /// Returns the canonical registrations made under `scheme_id`, oldest
+    /// first.
+    ///
+    /// A registration whose transaction failed to execute is still
+    /// listed; callers that need the registry entry should check it
+    /// against the chain state.
+    pub fn canonical_registrations<S>(
+        &self,
+        store: &S,
+        scheme_id: &str,
+    ) -> Result<Vec<Registration>, StorageError>
+    where
+        S: BlockStore + ?Sized,
+    {
+        let mut found = Vec::new();
+        for registration in self.by_scheme.get(scheme_id).into_iter().flatten() {
+            let location = registration.location;
+            if store.get_hash_at_height(location.height)? == Some(location.block_hash) {
+                found.push(*registration);
+            }
+        }
+        found.sort_by_key(|registration| registration.location.position());
+        Ok(found)
+    }
+
+    /// Drops the locations of blocks no longer in `store`, e.g. after
+    /// fork garbage collection.
+    pub fn prune<S>(&mut self, store: &S) -> Result<(), StorageError>
+    where
+        S: BlockStore + ?Sized,
+    {
+        let mut stored = HashSet::new();
+        let mut removed = HashSet::new();
+        for location in self.locations.values().flatten() {
+            let hash = location.block_hash;
+            if stored.contains(&hash) || removed.contains(&hash) {
+                continue;
+            }
+            if store.get_hash_at_height(location.height)? == Some(hash)
+                || store.get_block(&hash)?.is_some()
+            {
+                stored.insert(hash);
+            } else {
+                removed.insert(hash);
+            }
+        }
+        if removed.is_empty() {
+            return Ok(());
+        }
+        self.locations.retain(|_, locations| {
+            locations.retain(|location| !removed.contains(&location.block_hash));
+            !locations.is_empty()
+        });
+        self.by_account.retain(|_, locations| {
+            locations.retain(|location| !removed.contains(&location.block_hash));
+            !locations.is_empty()
+        });
+        self.by_scheme.retain(|_, registrations| {
+            registrations.retain(|r| !removed.contains(&r.location.block_hash));
+            !registrations.is_empty()
+        });
+        Ok(())
+    }
+
+    /// Returns the number of indexed transaction hashes.
+    pub fn len(&self) -> usize {
+        self.locations.len()
+    }
+
+    /// Returns `true` if no transaction is indexed.
+    pub fn is_empty(&self) -> bool {
+        self.locations.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::InMemoryBlockStore;
+    use crate::types::{
+        AccountId, EvidenceHash, EvidenceRef, HASH_LEN, Hash256, Header, Signature, Transaction,
+        TxRegisterModel, TxTransfer, WmProfile,
+    };
+
+    fn transfer(amount: u64) -> Transaction {
+        Transaction::Transfer(TxTransfer {
+            from: AccountId(Hash256([1u8; HASH_LEN])),
+            to: AccountId(Hash256([2u8; HASH_LEN])),
+            amount,
+            fee: 1,
+            nonce: 0,
+            signature: Signature(Vec::new()),
+        })
+    }
+
+    fn register(owner: AccountId, model: &[u8], scheme_id: &str) -> Transaction {
+        Transaction::RegisterModel(TxRegisterModel {
+            owner,
+            aid: Aid(Hash256::compute(model)),
+            chunked: None,
+            evidence: EvidenceRef {
+                scheme_id: scheme_id.to_string(),
+                evidence_hash: EvidenceHash(Hash256::compute(model)),
+                wm_profile: WmProfile {
+                    tau_input: 0.9,
+                    tau_feat: 0.2,
+                    logit_band_low: -0.05,
+                    logit_band_high: 0.05,
+                },
+            },
+            fee: 1,
+            nonce: 0,
+            signature: Signature(Vec::new()),
+        })
+    }
+
+    fn positions(locations: &[TxLocation]) -> Vec<(u64, usize)> {
+        locations.iter().map(TxLocation::position).collect()
+    }
+
+    fn block(parent: BlockHash, height: u64, timestamp: u64, txs: Vec<Transaction>) -> Block {
+        Block {
+            header: Header {
+                parent,
+                height,
+                timestamp,
+                proposer: AccountId(Hash256([0u8; HASH_LEN])),
+                txs_root: Hash256([0u8; HASH_LEN]),
+                state_root: Hash256([0u8; HASH_LEN]),
+                receipts_root: Hash256([0u8; HASH_LEN]),
+                pos_proof: None,
+            },
+            txs,
+        }
+    }
+
+    fn store_block(store: &mut InMemoryBlockStore, index: &mut TxIndex, block: Block) -> BlockHash {
+        let hash = block.compute_hash();
+        index.insert_block(hash, &block);
+        store.put_block(block).unwrap();
+        store.set_tip(hash).unwrap();
+        hash
+    }
+
+    #[test]
+    fn lookups_follow_the_canonical_chain_across_reorgs() {
+        let mut store = InMemoryBlockStore::new();
+        let mut index = TxIndex::new();
+        let (a, b) = (transfer(5), transfer(6));
+
+        let genesis = store_block(
+            &mut store,
+            &mut index,
+            block(BlockHash(Hash256([0u8; HASH_LEN])), 0, 0, Vec::new()),
+        );
+        let main = store_block(
+            &mut store,
+            &mut index,
+            block(genesis, 1, 10, vec![a.clone(), b.clone()]),
+        );
+        let location = index.canonical_location(&store, &b.compute_hash()).unwrap();
+        assert_eq!(
+            location,
+            Some(TxLocation {
+                block_hash: main,
+                height: 1,
+                index: 1,
+            })
+        );
+
+        // A side branch including only `a` becomes canonical.
+        let side = store_block(
+            &mut store,
+            &mut index,
+            block(genesis, 1, 11, vec![a.clone()]),
+        );
+        store_block(&mut store, &mut index, block(side, 2, 21, Vec::new()));
+        assert_eq!(
+            index
+                .canonical_location(&store, &a.compute_hash())
+                .unwrap()
+                .map(|l| l.block_hash),
+            Some(side)
+        );
+        assert!(
+            index
+                .canonical_location(&store, &b.compute_hash())
+                .unwrap()
+                .is_none()
+        );
+
+        let rebuilt = TxIndex::rebuild(&store).unwrap();
+        assert_eq!(rebuilt.len(), 2);
+
+        store.delete_block(&main).unwrap();
+        index.prune(&store).unwrap();
+        assert_eq!(index.len(), 1);
+    }
+
+    #[test]
+    fn account_and_scheme_lists_follow_the_canonical_chain() {
+        let mut store = InMemoryBlockStore::new();
+        let mut index = TxIndex::new();
+        let alice = AccountId(Hash256([1u8; HASH_LEN]));
+        let bob = AccountId(Hash256([2u8; HASH_LEN]));
+
+        let genesis = store_block(
+            &mut store,
+            &mut index,
+            block(BlockHash(Hash256([0u8; HASH_LEN])), 0, 0, Vec::new()),
+        );
+        let first = store_block(
+            &mut store,
+            &mut index,
+            block(
+                genesis,
+                1,
+                10,
+                vec![transfer(5), register(bob, b"m1", "wm-a")],
+            ),
+        );
+        let second = store_block(
+            &mut store,
+            &mut index,
+            block(
+                first,
+                2,
+                20,
+                vec![transfer(6), register(alice, b"m2", "wm-b")],
+            ),
+        );
+
+        // Newest first, continued from the last position of a page.
+        let page = index
+            .canonical_account_txs(&store, &alice, None, 2)
+            .unwrap();
+        assert_eq!(positions(&page), vec![(2, 1), (2, 0)]);
+        let rest = index
+            .canonical_account_txs(&store, &alice, Some(page[1].position()), 2)
+            .unwrap();
+        assert_eq!(positions(&rest), vec![(1, 0)]);
+        // Recipients are indexed too.
+        assert_eq!(
+            positions(&index.canonical_account_txs(&store, &bob, None, 10).unwrap()),
+            vec![(2, 0), (1, 1), (1, 0)]
+        );
+
+        let registrations = index.canonical_registrations(&store, "wm-a").unwrap();
+        assert_eq!(
+            registrations,
+            vec![Registration {
+                aid: Aid(Hash256::compute(b"m1")),
+                location: TxLocation {
+                    block_hash: first,
+                    height: 1,
+                    index: 1,
+                },
+            }]
+        );
+
+        // An empty side branch replaces the second block.
+        let side = store_block(&mut store, &mut index, block(first, 2, 21, Vec::new()));
+        store_block(&mut store, &mut index, block(side, 3, 31, Vec::new()));
+        assert!(
+            index
+                .canonical_registrations(&store, "wm-b")
+                .unwrap()
+                .is_empty()
+        );
+        assert_eq!(
+            positions(
+                index
+                    .canonical_account_txs(&store, &alice, None, 10)
+                    .unwrap()
+            ),
+            vec![(1, 0)]
+        );
+
+        store.delete_block(&second).unwrap();
+        index.prune(&store).unwrap();
+        assert!(!index.by_scheme.contains_key("wm-b"));
+        assert_eq!(index.by_account[&alice].len(), 1);
+    }
+}
@@ -1,16 +1,177 @@
-//! Block validity predicates used by consensus.
+//! Block and transaction validity predicates used by consensus.
 
-use crate::types::Block;
+use std::future::Future;
+use std::thread;
+
+use crate::execution::Receipt;
+use crate::storage::StorageError;
+use crate::types::{Block, BlockHash, SealedBlock, Transaction};
 
 use super::error::ValidationError;
+use super::store::BlockStore;
+
+/// Read-only view of the stored chain, as seen by block validators.
+///
+/// Every [`BlockStore`] that is `Sync` is a `ReadStore`; the methods
+/// behave like their `BlockStore` namesakes. `Sync` is required because
+/// a [`ValidatorPipeline`] may run stages on several threads and async
+/// validators hold the view across `.await`s.
+pub trait ReadStore: Sync {
+    /// Fetches a block by hash, if present.
+    fn get_block(&self, hash: &BlockHash) -> Result<Option<Block>, StorageError>;
+
+    /// Returns the hash of the current tip of the best chain, if any.
+    fn tip(&self) -> Result<Option<BlockHash>, StorageError>;
+
+    /// Returns the hash of the canonical block at `height`, if any.
+    fn get_hash_at_height(&self, height: u64) -> Result<Option<BlockHash>, StorageError>;
+
+    /// Returns the canonical block at `height`, if any.
+    fn get_block_by_height(&self, height: u64) -> Result<Option<Block>, StorageError> {
+        match self.get_hash_at_height(height)? {
+            Some(hash) => self.get_block(&hash),
+            None => Ok(None),
+        }
+    }
+
+    /// Returns the receipts of the block `hash`, if stored.
+    fn get_receipts(&self, hash: &BlockHash) -> Result<Option<Vec<Receipt>>, StorageError>;
+}
+
+impl<S> ReadStore for S
+where
+    S: BlockStore + Sync,
+{
+    fn get_block(&self, hash: &BlockHash) -> Result<Option<Block>, StorageError> {
+        BlockStore::get_block(self, hash)
+    }
+
+    fn tip(&self) -> Result<Option<BlockHash>, StorageError> {
+        BlockStore::tip(self)
+    }
+
+    fn get_hash_at_height(&self, height: u64) -> Result<Option<BlockHash>, StorageError> {
+        BlockStore::get_hash_at_height(self, height)
+    }
+
+    fn get_block_by_height(&self, height: u64) -> Result<Option<Block>, StorageError> {
+        BlockStore::get_block_by_height(self, height)
+    }
+
+    fn get_receipts(&self, hash: &BlockHash) -> Result<Option<Vec<Receipt>>, StorageError> {
+        BlockStore::get_receipts(self, hash)
+    }
+}
+
+/// A store holding no blocks, behind detached contexts.
+struct NoBlocks;
+
+impl ReadStore for NoBlocks {
+    fn get_block(&self, _hash: &BlockHash) -> Result<Option<Block>, StorageError> {
+        Ok(None)
+    }
+
+    fn tip(&self) -> Result<Option<BlockHash>, StorageError> {
+        Ok(None)
+    }
+
+    fn get_hash_at_height(&self, _height: u64) -> Result<Option<BlockHash>, StorageError> {
+        Ok(None)
+    }
+
+    fn get_receipts(&self, _hash: &BlockHash) -> Result<Option<Vec<Receipt>>, StorageError> {
+        Ok(None)
+    }
+}
+
+/// What a block validator may consult besides the block itself.
+///
+/// The engine builds one for every block it imports, after checking that
+/// the block links to a stored parent.
+#[derive(Clone, Copy)]
+pub struct ValidationContext<'a> {
+    /// The block's parent, or `None` for a genesis block.
+    pub parent: Option<&'a Block>,
+    /// The stored chain, including side branches. The block itself is not
+    /// stored yet.
+    pub store: &'a dyn ReadStore,
+    /// Height the block must have: the parent's height plus one, or 0
+    /// without a parent.
+    pub height: u64,
+}
+
+impl<'a> ValidationContext<'a> {
+    /// Context for a block extending `parent` (or a genesis block if
+    /// `None`) in `store`.
+    pub fn new(parent: Option<&'a Block>, store: &'a dyn ReadStore) -> Self {
+        Self {
+            parent,
+            store,
+            height: parent.map_or(0, |p| p.header.height.saturating_add(1)),
+        }
+    }
+
+    /// Context for checking a block on its own, with no parent and an
+    /// empty store, e.g. in tests and benchmarks.
+    pub fn detached() -> ValidationContext<'static> {
+        ValidationContext::new(None, &NoBlocks)
+    }
+}
 
 /// Pluggable validity predicate for blocks.
 ///
 /// Implementations should be deterministic and side-effect free. They can
 /// encapsulate base validity (`V_base`) as well as extended ML validity
 /// (`V_cons`) by composing multiple checks into a single call.
+///
+/// Besides the block, validators get a [`ValidationContext`] with its
+/// parent and a read-only view of the stored chain, for checks that
+/// relate the block to its ancestry.
 pub trait BlockValidator {
-    fn validate(&self, block: &Block) -> Result<(), ValidationError>;
+    fn validate(&self, block: &Block, ctx: &ValidationContext<'_>) -> Result<(), ValidationError>;
+
+    /// Validates a block whose encoding and hash are already known.
+    ///
+    /// The engine calls this on import. Checks that need the block's bytes
+    /// or hash should override it to reuse the cached ones; the default
+    /// calls [`validate`](Self::validate).
+    fn validate_sealed(
+        &self,
+        block: &SealedBlock,
+        ctx: &ValidationContext<'_>,
+    ) -> Result<(), ValidationError> {
+        self.validate(block.block(), ctx)
+    }
+}
+
+/// Validity predicate for a single transaction.
+///
+/// Used to reject transactions on submission, before they are pooled, so
+/// that malformed ones never reach block validation. Implementations must
+/// not depend on chain state.
+pub trait TxValidator {
+    fn validate_tx(&self, tx: &Transaction) -> Result<(), ValidationError>;
+}
+
+/// Async validity predicate for blocks.
+///
+/// This is used when some checks (typically ML verification) need to await
+/// I/O. The same determinism requirements as [`BlockValidator`] apply.
+pub trait AsyncBlockValidator {
+    fn validate(
+        &self,
+        block: &Block,
+        ctx: &ValidationContext<'_>,
+    ) -> impl Future<Output = Result<(), ValidationError>> + Send;
+
+    /// Async counterpart of [`BlockValidator::validate_sealed`].
+    fn validate_sealed(
+        &self,
+        block: &SealedBlock,
+        ctx: &ValidationContext<'_>,
+    ) -> impl Future<Output = Result<(), ValidationError>> + Send {
+        self.validate(block.block(), ctx)
+    }
 }
 
 /// A trivial validator that accepts every block.
@@ -20,16 +181,39 @@ pub trait BlockValidator {
 pub struct AcceptAllValidator;
 
 impl BlockValidator for AcceptAllValidator {
-    fn validate(&self, _block: &Block) -> Result<(), ValidationError> {
+    fn validate(
+        &self,
+        _block: &Block,
+        _ctx: &ValidationContext<'_>,
+    ) -> Result<(), ValidationError> {
+        Ok(())
+    }
+}
+
+impl TxValidator for AcceptAllValidator {
+    fn validate_tx(&self, _tx: &Transaction) -> Result<(), ValidationError> {
+        Ok(())
+    }
+}
+
+impl AsyncBlockValidator for AcceptAllValidator {
+    async fn validate(
+        &self,
+        _block: &Block,
+        _ctx: &ValidationContext<'_>,
+    ) -> Result<(), ValidationError> {
         Ok(())
     }
 }
 
 /// A validator that composes two other validators.
 ///
-/// This is a convenience to keep base and ML-specific checks modular:
+/// For more than two checks, prefer a [`ValidatorPipeline`]. This is a convenience to keep base and ML-specific checks modular:
 /// `CombinedValidator { base, ml }` will run `base.validate` and then
 /// `ml.validate`, failing fast on the first error.
+///
+/// When `ml` is an [`AsyncBlockValidator`], the combination is async too:
+/// the synchronous base checks run first and the ML checks are awaited.
 pub struct CombinedValidator<B, M> {
     pub base: B,
     pub ml: M,
@@ -46,9 +230,271 @@ where
     B: BlockValidator,
     M: BlockValidator,
 {
-    fn validate(&self, block: &Block) -> Result<(), ValidationError> {
-        self.base.validate(block)?;
-        self.ml.validate(block)?;
+    fn validate(&self, block: &Block, ctx: &ValidationContext<'_>) -> Result<(), ValidationError> {
+        self.base.validate(block, ctx)?;
+        self.ml.validate(block, ctx)?;
         Ok(())
     }
+
+    fn validate_sealed(
+        &self,
+        block: &SealedBlock,
+        ctx: &ValidationContext<'_>,
+    ) -> Result<(), ValidationError> {
+        self.base.validate_sealed(block, ctx)?;
+        self.ml.validate_sealed(block, ctx)?;
+        Ok(())
+    }
+}
+
+impl<B, M> AsyncBlockValidator for CombinedValidator<B, M>
+where
+    B: BlockValidator + Sync,
+    M: AsyncBlockValidator + Sync,
+{
+    async fn validate(
+        &self,
+        block: &Block,
+        ctx: &ValidationContext<'_>,
+    ) -> Result<(), ValidationError> {
+        self.base.validate(block, ctx)?;
+        self.ml.validate(block, ctx).await?;
+        Ok(())
+    }
+
+    async fn validate_sealed(
+        &self,
+        block: &SealedBlock,
+        ctx: &ValidationContext<'_>,
+    ) -> Result<(), ValidationError> {
+        self.base.validate_sealed(block, ctx)?;
+        self.ml.validate_sealed(block, ctx).await?;
+        Ok(())
+    }
+}
+
+/// Block validator shared across threads, as held by a [`ValidatorPipeline`].
+pub type BoxedBlockValidator = Box<dyn BlockValidator + Send + Sync>;
+
+/// One named check of a [`ValidatorPipeline`].
+struct Stage {
+    name: &'static str,
+    validator: BoxedBlockValidator,
+    parallel: bool,
+}
+
+/// An ordered list of named block validators.
+///
+/// Stages run in the order they were added and the pipeline fails on the
+/// first stage that rejects the block, wrapping its error in
+/// [`ValidationError::Stage`] so callers can tell which check failed.
+///
+/// Stages added with [`with_parallel_stage`](Self::with_parallel_stage)
+/// declare that they do not depend on the stages around them: a run of
+/// consecutive parallel stages is checked on scoped threads at once. The
+/// reported error is still that of the first failing stage in pipeline
+/// order, so results do not depend on thread timing.
+///
+/// Async checks (ML verification) cannot be stages; combine the pipeline
+/// with them through [`CombinedValidator`].
+#[derive(Default)]
+pub struct ValidatorPipeline {
+    stages: Vec<Stage>,
+}
+
+impl ValidatorPipeline {
+    /// Creates an empty pipeline, which accepts every block.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends a stage that runs after every stage added before it.
+    pub fn with_stage<V>(self, name: &'static str, validator: V) -> Self
+    where
+        V: BlockValidator + Send + Sync + 'static,
+    {
+        self.push(name, Box::new(validator), false)
+    }
+
+    /// Appends a stage that may run concurrently with adjacent parallel
+    /// stages.
+    pub fn with_parallel_stage<V>(self, name: &'static str, validator: V) -> Self
+    where
+        V: BlockValidator + Send + Sync + 'static,
+    {
+        self.push(name, Box::new(validator), true)
+    }
+
+    fn push(mut self, name: &'static str, validator: BoxedBlockValidator, parallel: bool) -> Self {
+        self.stages.push(Stage {
+            name,
+            validator,
+            parallel,
+        });
+        self
+    }
+
+    /// Returns the stage names, in order.
+    pub fn stage_names(&self) -> Vec<&'static str> {
+        self.stages.iter().map(|stage| stage.name).collect()
+    }
+
+    /// Runs `check` on every stage, in order, failing on the first stage
+    /// that rejects the block.
+    fn run<C>(&self, check: C) -> Result<(), ValidationError>
+    where
+        C: Fn(&BoxedBlockValidator) -> Result<(), ValidationError> + Sync,
+    {
+        let mut rest = self.stages.as_slice();
+        while let Some(stage) = rest.first() {
+            let run = if stage.parallel {
+                rest.iter().take_while(|s| s.parallel).count()
+            } else {
+                1
+            };
+            if run > 1 {
+                Self::run_parallel(&rest[..run], &check)?;
+            } else {
+                check(&stage.validator).map_err(|e| stage_error(stage, e))?;
+            }
+            rest = &rest[run..];
+        }
+        Ok(())
+    }
+
+    /// Runs a run of parallel stages, returning the first error in order.
+    fn run_parallel<C>(stages: &[Stage], check: &C) -> Result<(), ValidationError>
+    where
+        C: Fn(&BoxedBlockValidator) -> Result<(), ValidationError> + Sync,
+    {
+        let results: Vec<_> = thread::scope(|scope| {
+            let handles: Vec<_> = stages
+                .iter()
+                .map(|stage| scope.spawn(|| check(&stage.validator)))
+                .collect();
+            handles
+                .into_iter()
+                .map(|handle| handle.join().expect("validator stage panicked"))
+                .collect()
+        });
+        for (stage, result) in stages.iter().zip(results) {
+            result.map_err(|e| stage_error(stage, e))?;
+        }
+        Ok(())
+    }
+}
+
+fn stage_error(stage: &Stage, source: ValidationError) -> ValidationError {
+    ValidationError::Stage {
+        name: stage.name,
+        source: Box::new(source),
+    }
+}
+
+impl BlockValidator for ValidatorPipeline {
+    fn validate(&self, block: &Block, ctx: &ValidationContext<'_>) -> Result<(), ValidationError> {
+        self.run(|validator| validator.validate(block, ctx))
+    }
+
+    fn validate_sealed(
+        &self,
+        block: &SealedBlock,
+        ctx: &ValidationContext<'_>,
+    ) -> Result<(), ValidationError> {
+        self.run(|validator| validator.validate_sealed(block, ctx))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+    use crate::types::{AccountId, BlockHash, HASH_LEN, Hash256, Header};
+
+    struct Reject(&'static str);
+
+    impl BlockValidator for Reject {
+        fn validate(
+            &self,
+            _block: &Block,
+            _ctx: &ValidationContext<'_>,
+        ) -> Result<(), ValidationError> {
+            Err(ValidationError::Invalid(self.0))
+        }
+    }
+
+    struct Count(Arc<AtomicUsize>);
+
+    impl BlockValidator for Count {
+        fn validate(
+            &self,
+            _block: &Block,
+            _ctx: &ValidationContext<'_>,
+        ) -> Result<(), ValidationError> {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            Ok(())
+        }
+    }
+
+    fn block() -> Block {
+        Block {
+            header: Header {
+                parent: BlockHash(Hash256([0u8; HASH_LEN])),
+                height: 1,
+                timestamp: 1_700_000_000,
+                proposer: AccountId(Hash256([1u8; HASH_LEN])),
+                txs_root: Hash256([0u8; HASH_LEN]),
+                state_root: Hash256([0u8; HASH_LEN]),
+                receipts_root: Hash256([0u8; HASH_LEN]),
+                pos_proof: None,
+            },
+            txs: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn pipeline_stops_at_the_first_failing_stage_and_names_it() {
+        let runs = Arc::new(AtomicUsize::new(0));
+        let pipeline = ValidatorPipeline::new()
+            .with_stage("count", Count(runs.clone()))
+            .with_stage("base", Reject("too big"))
+            .with_stage("after", Count(runs.clone()));
+        assert_eq!(pipeline.stage_names(), ["count", "base", "after"]);
+
+        let block = block();
+        let err = pipeline
+            .validate(&block, &ValidationContext::detached())
+            .unwrap_err();
+        assert!(
+            matches!(&err, ValidationError::Stage { name: "base", .. }),
+            "{err:?}"
+        );
+        assert!(matches!(err.root(), ValidationError::Invalid("too big")));
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn parallel_stages_all_run_and_report_the_first_failure_in_order() {
+        let runs = Arc::new(AtomicUsize::new(0));
+        let pipeline = ValidatorPipeline::new()
+            .with_parallel_stage("a", Count(runs.clone()))
+            .with_parallel_stage("b", Reject("b failed"))
+            .with_parallel_stage("c", Reject("c failed"))
+            .with_parallel_stage("d", Count(runs.clone()));
+
+        let block = block();
+        let ctx = ValidationContext::detached();
+        let err = pipeline.validate(&block, &ctx).unwrap_err();
+        assert!(
+            matches!(&err, ValidationError::Stage { name: "b", .. }),
+            "{err:?}"
+        );
+        assert_eq!(runs.load(Ordering::SeqCst), 2);
+
+        ValidatorPipeline::new()
+            .validate(&block, &ctx)
+            .expect("empty pipeline accepts");
+    }
 }
@@ -0,0 +1,419 @@
+//! BFT-style votes and the 2/3 quorum commit rule.
+//!
+//! Longest-chain fork choice never makes a block final: a long enough side
+//! branch can always retract it. With votes, every validator signs a
+//! [`Vote`] for the block it considers canonical at a height, and a block
+//! is *committed* once more than two thirds of the [`ValidatorSet`] have
+//! voted for it. A [`VoteAggregator`] collects the votes and produces a
+//! [`QuorumCertificate`] when a block crosses that threshold.
+//!
+//! Commits are enforced by [`QuorumForkChoice`], which wraps any other
+//! [`ForkChoice`] and refuses tips that do not descend from the last
+//! committed block. Engines built with
+//! [`ConsensusEngine::with_votes`](super::ConsensusEngine::with_votes)
+//! aggregate votes passed to
+//! [`ConsensusEngine::add_vote`](super::ConsensusEngine::add_vote) and
+//! report new commits to their fork choice.
+//!
+//! Vote signatures are carried but not checked here, like transaction
+//! signatures; [`Vote::signing_bytes`] is the message validators sign and
+//! [`Vote::verify_signature`] checks it, as the
+//! [`LightClient`](crate::light::LightClient) does for every vote it
+//! counts.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::storage::StorageError;
+use crate::types::{AccountId, Block, BlockHash, PublicKey, Signature, SignatureError};
+
+use super::fork_choice::ForkChoice;
+use super::schedule::ValidatorSet;
+use super::store::BlockStore;
+
+/// A validator's vote for the canonical block at `height`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Vote {
+    /// Block voted for.
+    pub block_hash: BlockHash,
+    /// Height of `block_hash`.
+    pub height: u64,
+    /// Voting validator.
+    pub validator: AccountId,
+    /// Public key of `validator`, which must hash to it.
+    pub validator_key: PublicKey,
+    /// Signature by `validator` over [`Vote::signing_bytes`].
+    pub signature: Signature,
+}
+
+impl Vote {
+    /// Returns the canonical encoding of the vote without its signature,
+    /// i.e. the message the validator signs.
+    pub fn signing_bytes(&self) -> Vec<u8> {
+        bincode::serde::encode_to_vec(
+            (&self.block_hash, self.height, &self.validator),
+            bincode::config::standard(),
+        )
+        .expect("Vote should always be serializable with bincode 2 + serde")
+    }
+
+    /// Checks that `validator_key` belongs to the validator and that the
+    /// signature over [`signing_bytes`](Self::signing_bytes) verifies
+    /// under it.
+    pub fn verify_signature(&self) -> Result<(), SignatureError> {
+        let key = AccountId::from_public_key(self.validator_key.as_bytes());
+        if key != self.validator {
+            return Err(SignatureError::KeyMismatch {
+                sender: self.validator,
+                key,
+            });
+        }
+        self.validator_key
+            .verify(&self.signing_bytes(), &self.signature)
+    }
+}
+
+/// Why a vote was not counted.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum VoteError {
+    /// The voter is not in the validator set.
+    UnknownValidator(AccountId),
+    /// The voter already voted for a different block at this height.
+    Equivocation {
+        validator: AccountId,
+        height: u64,
+        first: BlockHash,
+        second: BlockHash,
+    },
+    /// A block at or above this height is already committed.
+    Stale { height: u64, committed: u64 },
+}
+
+impl fmt::Display for VoteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let short = |h: &BlockHash| hex::encode(&h.0.as_bytes()[..8]);
+        match self {
+            VoteError::UnknownValidator(validator) => write!(
+                f,
+                "{} is not in the validator set",
+                hex::encode(validator.0.as_bytes())
+            ),
+            VoteError::Equivocation {
+                validator,
+                height,
+                first,
+                second,
+            } => write!(
+                f,
+                "{} voted for both {} and {} at height {height}",
+                hex::encode(validator.0.as_bytes()),
+                short(first),
+                short(second)
+            ),
+            VoteError::Stale { height, committed } => write!(
+                f,
+                "vote at height {height} is stale, height {committed} is committed"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for VoteError {}
+
+/// Proof that a block was committed: the votes of a quorum of validators.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct QuorumCertificate {
+    /// Committed block.
+    pub block_hash: BlockHash,
+    /// Height of `block_hash`.
+    pub height: u64,
+    /// The votes for `block_hash`, one per validator.
+    pub votes: Vec<Vote>,
+}
+
+/// Returns the number of votes that commit a block in a set of
+/// `validators`: strictly more than two thirds.
+pub fn quorum_size(validators: usize) -> usize {
+    validators * 2 / 3 + 1
+}
+
+/// Collects votes and detects quorums.
+///
+/// Votes are kept per height until a block at that height or above is
+/// committed.
+#[derive(Clone, Debug)]
+pub struct VoteAggregator {
+    validators: ValidatorSet,
+    /// Votes per height, by validator.
+    pending: BTreeMap<u64, HashMap<AccountId, Vote>>,
+    /// The most recent commit.
+    committed: Option<QuorumCertificate>,
+}
+
+impl VoteAggregator {
+    /// Creates an aggregator counting votes from `validators`.
+    pub fn new(validators: ValidatorSet) -> Self {
+        Self {
+            validators,
+            pending: BTreeMap::new(),
+            committed: None,
+        }
+    }
+
+    /// Returns the validator set votes are counted from.
+    pub fn validators(&self) -> &ValidatorSet {
+        &self.validators
+    }
+
+    /// Returns the number of votes that commit a block.
+    pub fn quorum(&self) -> usize {
+        quorum_size(self.validators.len())
+    }
+
+    /// Returns the most recent commit, if any.
+    pub fn committed(&self) -> Option<&QuorumCertificate> {
+        self.committed.as_ref()
+    }
+
+    /// Returns how many validators voted for `block_hash` at `height`.
+    pub fn votes_for(&self, height: u64, block_hash: &BlockHash) -> usize {
+        self.pending.get(&height).map_or(0, |votes| {
+            votes
+                .values()
+                .filter(|vote| vote.block_hash == *block_hash)
+                .count()
+        })
+    }
+
+    /// Counts `vote`, returning a certificate if it completes a quorum.
+    ///
+    /// Repeating a vote is harmless; voting for two blocks at the same
+    /// height is rejected as [`VoteError::Equivocation`], and the first
+    /// vote keeps counting.
+    pub fn add_vote(&mut self, vote: Vote) -> Result<Option<QuorumCertificate>, VoteError> {
+        if !self.validators.contains(&vote.validator) {
+            return Err(VoteError::UnknownValidator(vote.validator));
+        }
+        if let Some(committed) = &self.committed
+            && vote.height <= committed.height
+        {
+            return Err(VoteError::Stale {
+                height: vote.height,
+                committed: committed.height,
+            });
+        }
+
+        let votes = self.pending.entry(vote.height).or_default();
+        if let Some(previous) = votes.get(&vote.validator) {
+            if previous.block_hash == vote.block_hash {
+                return Ok(None);
+            }
+            return Err(VoteError::Equivocation {
+                validator: vote.validator,
+                height: vote.height,
+                first: previous.block_hash,
+                second: vote.block_hash,
+            });
+        }
+        let (height, block_hash) = (vote.height, vote.block_hash);
+        votes.insert(vote.validator, vote);
+
+        if self.votes_for(height, &block_hash) < self.quorum() {
+            return Ok(None);
+        }
+        let votes = self.pending.remove(&height).unwrap_or_default();
+        let certificate = QuorumCertificate {
+            block_hash,
+            height,
+            votes: votes
+                .into_values()
+                .filter(|vote| vote.block_hash == block_hash)
+                .collect(),
+        };
+        self.pending.retain(|&h, _| h > height);
+        self.committed = Some(certificate.clone());
+        Ok(Some(certificate))
+    }
+}
+
+/// Returns the hash of `block`'s ancestor at `height`, or `None` if
+/// `height` is above the block or an ancestor is missing from `store`.
+fn ancestor_at(
+    store: &dyn BlockStore,
+    block: &Block,
+    height: u64,
+) -> Result<Option<BlockHash>, StorageError> {
+    if height > block.header.height {
+        return Ok(None);
+    }
+    let mut hash = block.compute_hash();
+    let mut header = block.header.clone();
+    while header.height > height {
+        let Some(parent) = store.get_block(&header.parent)? else {
+            return Ok(None);
+        };
+        hash = header.parent;
+        header = parent.header;
+    }
+    Ok(Some(hash))
+}
+
+/// Fork choice that never leaves the last committed block.
+///
+/// Candidates descending from the committed block are judged by the
+/// wrapped rule `F`; every other candidate is refused, however long its
+/// branch. The committed block itself always becomes the tip when it
+/// arrives, so a node that followed a losing branch switches over. Until
+/// the first commit, `F` decides alone.
+#[derive(Clone, Debug, Default)]
+pub struct QuorumForkChoice<F> {
+    inner: F,
+    committed: Option<(BlockHash, u64)>,
+}
+
+impl<F> QuorumForkChoice<F> {
+    /// Wraps `inner` with the commit rule.
+    pub fn new(inner: F) -> Self {
+        Self {
+            inner,
+            committed: None,
+        }
+    }
+
+    /// Returns the committed block and its height, if any.
+    pub fn committed(&self) -> Option<(BlockHash, u64)> {
+        self.committed
+    }
+}
+
+impl<F> ForkChoice for QuorumForkChoice<F>
+where
+    F: ForkChoice,
+{
+    fn should_update_tip(
+        &self,
+        store: &dyn BlockStore,
+        current_tip: Option<BlockHash>,
+        candidate: &Block,
+    ) -> Result<bool, StorageError> {
+        let Some((committed, height)) = self.committed else {
+            return self.inner.should_update_tip(store, current_tip, candidate);
+        };
+        if candidate.compute_hash() == committed {
+            return Ok(true);
+        }
+        if candidate.header.height <= height {
+            // Ancestors of a committed block that has not arrived yet may
+            // still be needed to reach it.
+            if store.get_block(&committed)?.is_some() {
+                return Ok(false);
+            }
+            return self.inner.should_update_tip(store, current_tip, candidate);
+        }
+        if ancestor_at(store, candidate, height)? != Some(committed) {
+            return Ok(false);
+        }
+        self.inner.should_update_tip(store, current_tip, candidate)
+    }
+
+    fn on_commit(&mut self, commit: &QuorumCertificate) {
+        self.committed = Some((commit.block_hash, commit.height));
+        self.inner.on_commit(commit);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::consensus::LongestChainForkChoice;
+    use crate::storage::InMemoryBlockStore;
+    use crate::types::{HASH_LEN, Hash256, Header};
+
+    fn account(byte: u8) -> AccountId {
+        AccountId(Hash256([byte; HASH_LEN]))
+    }
+
+    fn vote(block: BlockHash, height: u64, validator: u8) -> Vote {
+        Vote {
+            block_hash: block,
+            height,
+            validator: account(validator),
+            validator_key: PublicKey(vec![]),
+            signature: Signature(vec![]),
+        }
+    }
+
+    fn child(parent: BlockHash, height: u64, salt: u64) -> Block {
+        Block {
+            header: Header {
+                parent,
+                height,
+                timestamp: 1_700_000_000 + height + salt,
+                proposer: account(1),
+                txs_root: Hash256([0u8; HASH_LEN]),
+                state_root: Hash256([0u8; HASH_LEN]),
+                receipts_root: Hash256([0u8; HASH_LEN]),
+                pos_proof: None,
+            },
+            txs: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn two_thirds_plus_one_commits_and_equivocation_is_rejected() {
+        let mut votes = VoteAggregator::new(ValidatorSet::new((1..=4).map(account)));
+        assert_eq!(votes.quorum(), 3);
+        let a = BlockHash(Hash256([0xAA; HASH_LEN]));
+        let b = BlockHash(Hash256([0xBB; HASH_LEN]));
+
+        assert!(votes.add_vote(vote(a, 5, 1)).unwrap().is_none());
+        assert!(votes.add_vote(vote(a, 5, 1)).unwrap().is_none());
+        assert!(matches!(
+            votes.add_vote(vote(b, 5, 1)),
+            Err(VoteError::Equivocation { .. })
+        ));
+        assert_eq!(
+            votes.add_vote(vote(a, 5, 9)).unwrap_err(),
+            VoteError::UnknownValidator(account(9))
+        );
+        assert!(votes.add_vote(vote(b, 5, 2)).unwrap().is_none());
+        assert!(votes.add_vote(vote(a, 5, 3)).unwrap().is_none());
+        assert_eq!(votes.votes_for(5, &a), 2);
+
+        let cert = votes.add_vote(vote(a, 5, 4)).unwrap().expect("quorum");
+        assert_eq!((cert.block_hash, cert.height), (a, 5));
+        assert_eq!(cert.votes.len(), 3);
+        assert!(matches!(
+            votes.add_vote(vote(b, 4, 2)),
+            Err(VoteError::Stale { committed: 5, .. })
+        ));
+    }
+
+    #[test]
+    fn quorum_fork_choice_refuses_branches_off_the_committed_block() {
+        let mut store = InMemoryBlockStore::new();
+        let g = child(BlockHash(Hash256([0u8; HASH_LEN])), 0, 0);
+        let a1 = child(g.compute_hash(), 1, 0);
+        let b1 = child(g.compute_hash(), 1, 100);
+        let b2 = child(b1.compute_hash(), 2, 100);
+        let a2 = child(a1.compute_hash(), 2, 0);
+        for block in [g.clone(), a1.clone(), b1.clone()] {
+            store.put_block(block).unwrap();
+        }
+        store.set_tip(a1.compute_hash()).unwrap();
+
+        let mut fc = QuorumForkChoice::new(LongestChainForkChoice);
+        let tip = store.tip().unwrap();
+        assert!(fc.should_update_tip(&store, tip, &b2).unwrap());
+
+        fc.on_commit(&QuorumCertificate {
+            block_hash: a1.compute_hash(),
+            height: 1,
+            votes: Vec::new(),
+        });
+        assert!(!fc.should_update_tip(&store, tip, &b2).unwrap());
+        assert!(fc.should_update_tip(&store, tip, &a2).unwrap());
+    }
+}
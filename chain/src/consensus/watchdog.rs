@@ -0,0 +1,171 @@
+//! Safety watchdog for block production.
+//!
+//! When the ML verifier is down every proposal carrying a registration is
+//! rejected, and a producer loop would retry (and log) on every slot. The
+//! [`ProducerWatchdog`] counts consecutive proposals rejected with
+//! [`ValidationError::MlUnavailable`] and pauses production after
+//! `max_ml_failures` of them. While paused, only one proposal per
+//! `probe_interval_secs` is attempted as a probe; the first probe that is
+//! not rejected for ML unavailability resumes normal production.
+//!
+//! [`ValidationError::MlUnavailable`]: super::error::ValidationError::MlUnavailable
+
+use std::time::{Duration, Instant};
+
+use serde::Deserialize;
+
+use super::error::ConsensusError;
+
+/// Configuration for the producer watchdog.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WatchdogConfig {
+    /// Consecutive ML-unavailable rejections that pause production; 0
+    /// disables the watchdog.
+    pub max_ml_failures: u32,
+    /// Interval between probe proposals while paused, in seconds.
+    pub probe_interval_secs: u64,
+}
+
+impl Default for WatchdogConfig {
+    fn default() -> Self {
+        Self {
+            max_ml_failures: 3,
+            probe_interval_secs: 30,
+        }
+    }
+}
+
+/// State change reported by the watchdog.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum WatchdogEvent {
+    /// Production paused after `failures` consecutive ML failures.
+    Paused { failures: u32 },
+    /// A probe succeeded and production resumed.
+    Resumed,
+}
+
+/// Tracks ML verifier failures of a producer loop; see the module docs.
+#[derive(Clone, Debug)]
+pub struct ProducerWatchdog {
+    max_failures: u32,
+    probe_interval: Duration,
+    consecutive_failures: u32,
+    /// Earliest time of the next probe, while paused.
+    next_probe: Option<Instant>,
+}
+
+impl ProducerWatchdog {
+    /// Creates a running watchdog.
+    pub fn new(cfg: &WatchdogConfig) -> Self {
+        Self {
+            max_failures: cfg.max_ml_failures,
+            probe_interval: Duration::from_secs(cfg.probe_interval_secs),
+            consecutive_failures: 0,
+            next_probe: None,
+        }
+    }
+
+    /// Returns `true` while production is paused.
+    pub fn is_paused(&self) -> bool {
+        self.next_probe.is_some()
+    }
+
+    /// Returns the number of consecutive ML-unavailable rejections.
+    pub fn consecutive_failures(&self) -> u32 {
+        self.consecutive_failures
+    }
+
+    /// Returns whether the producer should attempt a proposal at `now`:
+    /// always while running, and once per probe interval while paused.
+    pub fn should_propose(&self, now: Instant) -> bool {
+        self.next_probe.is_none_or(|probe| now >= probe)
+    }
+
+    /// Records a successful proposal.
+    pub fn observe_success(&mut self) -> Option<WatchdogEvent> {
+        self.consecutive_failures = 0;
+        self.next_probe.take().map(|_| WatchdogEvent::Resumed)
+    }
+
+    /// Records a failed proposal.
+    ///
+    /// Failures for other reasons than ML unavailability break a streak
+    /// while running, but do not end a pause: they say nothing about the
+    /// verifier.
+    pub fn observe_error(&mut self, err: &ConsensusError, now: Instant) -> Option<WatchdogEvent> {
+        if !err.is_ml_unavailable() {
+            if self.is_paused() {
+                self.next_probe = Some(now + self.probe_interval);
+            } else {
+                self.consecutive_failures = 0;
+            }
+            return None;
+        }
+
+        self.consecutive_failures = self.consecutive_failures.saturating_add(1);
+        if self.max_failures == 0 {
+            return None;
+        }
+        let was_paused = self.is_paused();
+        if was_paused || self.consecutive_failures >= self.max_failures {
+            self.next_probe = Some(now + self.probe_interval);
+        }
+        (!was_paused && self.is_paused()).then_some(WatchdogEvent::Paused {
+            failures: self.consecutive_failures,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::consensus::ValidationError;
+
+    fn ml_down() -> ConsensusError {
+        ValidationError::MlUnavailable("connection refused".to_string()).into()
+    }
+
+    #[test]
+    fn pauses_after_consecutive_ml_failures_and_resumes_on_probe() {
+        let mut watchdog = ProducerWatchdog::new(&WatchdogConfig {
+            max_ml_failures: 2,
+            probe_interval_secs: 30,
+        });
+        let t0 = Instant::now();
+
+        assert_eq!(watchdog.observe_error(&ml_down(), t0), None);
+        let other = ConsensusError::Execution("bad nonce".to_string());
+        assert_eq!(watchdog.observe_error(&other, t0), None);
+        assert_eq!(watchdog.observe_error(&ml_down(), t0), None);
+        assert_eq!(
+            watchdog.observe_error(&ml_down(), t0),
+            Some(WatchdogEvent::Paused { failures: 2 })
+        );
+        assert!(watchdog.is_paused());
+        assert!(!watchdog.should_propose(t0 + Duration::from_secs(29)));
+        assert!(watchdog.should_propose(t0 + Duration::from_secs(30)));
+
+        // A failed probe waits another interval.
+        let t1 = t0 + Duration::from_secs(30);
+        assert_eq!(watchdog.observe_error(&ml_down(), t1), None);
+        assert!(!watchdog.should_propose(t1 + Duration::from_secs(1)));
+
+        assert_eq!(watchdog.observe_success(), Some(WatchdogEvent::Resumed));
+        assert!(!watchdog.is_paused());
+        assert_eq!(watchdog.consecutive_failures(), 0);
+        assert!(watchdog.should_propose(t1));
+    }
+
+    #[test]
+    fn zero_threshold_disables_the_watchdog() {
+        let mut watchdog = ProducerWatchdog::new(&WatchdogConfig {
+            max_ml_failures: 0,
+            ..WatchdogConfig::default()
+        });
+        for _ in 0..10 {
+            assert_eq!(watchdog.observe_error(&ml_down(), Instant::now()), None);
+        }
+        assert!(!watchdog.is_paused());
+    }
+}
@@ -0,0 +1,919 @@
+//! Transaction and block execution.
+//!
+//! Rules applied per transaction:
+//!
+//! - the signer's nonce must equal [`Account::nonce`] and is then bumped,
+//! - the signer pays `fee` (plus `amount` for transfers) from its balance,
+//! - fees are credited to the block proposer,
+//! - `TxRegisterModel` inserts a new [`ArtefactMetadata`] entry and fails
+//!   if the `Aid` is already registered,
+//! - `TxUseModel` requires the `Aid` to be registered and not revoked,
+//! - `TxTransferModel` requires the `Aid` to be registered and signed by
+//!   its current owner, and then records the new owner,
+//! - `TxRevokeModel` requires the `Aid` to be registered and not yet
+//!   revoked, and signed by its owner or a governance account; it records
+//!   the revocation height,
+//! - `TxStake` moves `amount` from the balance into bonded stake,
+//! - `TxUnstake` requires `amount` of bonded stake and schedules it for
+//!   release after [`StakingConfig::unbonding_epochs`] epochs,
+//! - `TxSlash` must carry a fraud proof signed by its reporter, an account
+//!   with active stake or a governance account, against a staked proposer
+//!   and a block not slashed before; it burns
+//!   [`StakingConfig::slash_percent`] of the proposer's stake and records
+//!   the block as slashed.
+//!
+//! Each transaction yields a [`Receipt`] listing the fee it paid and the
+//! state changes it made (see [`super::receipt`]).
+//!
+//! Around the transactions of each block, unbonded stake due by the
+//! block's epoch is returned to its owners first, and the last block of an
+//! epoch activates the bonded stake for the next one (see
+//! [`super::stake`]).
+//!
+//! [`StakingConfig::unbonding_epochs`]: super::stake::StakingConfig::unbonding_epochs
+//! [`StakingConfig::slash_percent`]: super::stake::StakingConfig::slash_percent
+
+use std::fmt;
+
+use crate::consensus::store::BlockStore;
+use crate::storage::StorageError;
+use crate::types::{
+    AccountId, Aid, ArtefactMetadata, Block, BlockHash, SignatureError, Transaction,
+};
+
+use super::receipt::{Receipt, ReceiptEvent};
+use super::stake::Unbonding;
+use super::state::{Account, ChainState};
+
+/// Errors raised while executing a transaction against chain state.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ExecutionError {
+    /// Transaction nonce does not match the signer's expected nonce.
+    BadNonce {
+        account: AccountId,
+        expected: u64,
+        got: u64,
+    },
+    /// Signer cannot cover the fee (and transfer amount, if any).
+    InsufficientBalance {
+        account: AccountId,
+        needed: u64,
+        available: u64,
+    },
+    /// Crediting an account would overflow its balance.
+    BalanceOverflow(AccountId),
+    /// `TxRegisterModel` for an `Aid` that is already registered.
+    DuplicateArtefact(Aid),
+    /// `TxUseModel`, `TxTransferModel` or `TxRevokeModel` for an `Aid` that
+    /// has never been registered.
+    UnknownArtefact(Aid),
+    /// `TxTransferModel` signed by an account that does not own the `Aid`.
+    NotArtefactOwner {
+        aid: Aid,
+        owner: AccountId,
+        signer: AccountId,
+    },
+    /// `TxUseModel` or `TxRevokeModel` for a revoked `Aid`.
+    RevokedArtefact(Aid),
+    /// `TxRevokeModel` signed by neither the owner nor a governance account.
+    RevocationNotAllowed { aid: Aid, signer: AccountId },
+    /// `TxUnstake` for more than the signer's bonded stake.
+    InsufficientStake {
+        account: AccountId,
+        needed: u64,
+        bonded: u64,
+    },
+    /// `TxSlash` whose fraud proof is not signed by its reporter.
+    BadFraudSignature(SignatureError),
+    /// `TxSlash` reported by an account with neither active stake nor
+    /// governance rights.
+    ReportNotAllowed(AccountId),
+    /// `TxSlash` against a proposer without any stake.
+    NoStake(AccountId),
+    /// `TxSlash` for a block that has already been slashed.
+    AlreadySlashed(BlockHash),
+    /// Reading a block to replay failed.
+    Storage(StorageError),
+}
+
+impl fmt::Display for ExecutionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExecutionError::BadNonce {
+                account,
+                expected,
+                got,
+            } => write!(
+                f,
+                "bad nonce for account {}: expected {expected}, got {got}",
+                hex::encode(account.as_hash().as_bytes())
+            ),
+            ExecutionError::InsufficientBalance {
+                account,
+                needed,
+                available,
+            } => write!(
+                f,
+                "insufficient balance for account {}: needed {needed}, available {available}",
+                hex::encode(account.as_hash().as_bytes())
+            ),
+            ExecutionError::BalanceOverflow(account) => write!(
+                f,
+                "balance overflow for account {}",
+                hex::encode(account.as_hash().as_bytes())
+            ),
+            ExecutionError::DuplicateArtefact(aid) => write!(
+                f,
+                "artefact {} is already registered",
+                hex::encode(aid.as_hash().as_bytes())
+            ),
+            ExecutionError::UnknownArtefact(aid) => write!(
+                f,
+                "artefact {} is not registered",
+                hex::encode(aid.as_hash().as_bytes())
+            ),
+            ExecutionError::NotArtefactOwner { aid, owner, signer } => write!(
+                f,
+                "account {} does not own artefact {} (owner is {})",
+                hex::encode(signer.as_hash().as_bytes()),
+                hex::encode(aid.as_hash().as_bytes()),
+                hex::encode(owner.as_hash().as_bytes())
+            ),
+            ExecutionError::RevokedArtefact(aid) => write!(
+                f,
+                "artefact {} has been revoked",
+                hex::encode(aid.as_hash().as_bytes())
+            ),
+            ExecutionError::RevocationNotAllowed { aid, signer } => write!(
+                f,
+                "account {} may not revoke artefact {}",
+                hex::encode(signer.as_hash().as_bytes()),
+                hex::encode(aid.as_hash().as_bytes())
+            ),
+            ExecutionError::InsufficientStake {
+                account,
+                needed,
+                bonded,
+            } => write!(
+                f,
+                "insufficient stake for account {}: needed {needed}, bonded {bonded}",
+                hex::encode(account.as_hash().as_bytes())
+            ),
+            ExecutionError::BadFraudSignature(e) => {
+                write!(f, "fraud proof signature rejected: {e}")
+            }
+            ExecutionError::ReportNotAllowed(reporter) => write!(
+                f,
+                "account {} may not report fraud",
+                hex::encode(reporter.as_hash().as_bytes())
+            ),
+            ExecutionError::NoStake(account) => write!(
+                f,
+                "account {} has no stake to slash",
+                hex::encode(account.as_hash().as_bytes())
+            ),
+            ExecutionError::AlreadySlashed(hash) => write!(
+                f,
+                "block {} has already been slashed",
+                hex::encode(hash.0.as_bytes())
+            ),
+            ExecutionError::Storage(e) => write!(f, "failed to read block: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for ExecutionError {}
+
+impl From<StorageError> for ExecutionError {
+    fn from(e: StorageError) -> Self {
+        ExecutionError::Storage(e)
+    }
+}
+
+/// Checks the nonce and debits `cost` from `signer`, bumping its nonce.
+fn charge(
+    state: &mut ChainState,
+    signer: AccountId,
+    nonce: u64,
+    cost: u64,
+) -> Result<(), ExecutionError> {
+    let account: &mut Account = state.account_mut(signer);
+
+    if nonce != account.nonce {
+        return Err(ExecutionError::BadNonce {
+            account: signer,
+            expected: account.nonce,
+            got: nonce,
+        });
+    }
+
+    if account.balance < cost {
+        return Err(ExecutionError::InsufficientBalance {
+            account: signer,
+            needed: cost,
+            available: account.balance,
+        });
+    }
+
+    account.balance -= cost;
+    account.nonce += 1;
+    Ok(())
+}
+
+/// Credits `amount` to `id`.
+fn credit(state: &mut ChainState, id: AccountId, amount: u64) -> Result<(), ExecutionError> {
+    let account = state.account_mut(id);
+    account.balance = account
+        .balance
+        .checked_add(amount)
+        .ok_or(ExecutionError::BalanceOverflow(id))?;
+    Ok(())
+}
+
+/// Applies a single transaction in the context of block `height` and
+/// returns the state changes it made.
+///
+/// Fees are credited to `proposer`. On error, `state` may have been
+/// partially modified; use [`apply_block`] for all-or-nothing semantics.
+pub fn apply_tx(
+    state: &mut ChainState,
+    tx: &Transaction,
+    height: u64,
+    proposer: AccountId,
+) -> Result<Vec<ReceiptEvent>, ExecutionError> {
+    match tx {
+        Transaction::RegisterModel(reg) => {
+            if state.artefact(&reg.aid).is_some() {
+                return Err(ExecutionError::DuplicateArtefact(reg.aid));
+            }
+            charge(state, reg.owner, reg.nonce, reg.fee)?;
+            state.insert_artefact(ArtefactMetadata {
+                aid: reg.aid,
+                chunked: reg.chunked,
+                owner: reg.owner,
+                evidence: reg.evidence.clone(),
+                registered_at: height,
+                revoked_at: None,
+            });
+            credit(state, proposer, reg.fee)?;
+            Ok(vec![ReceiptEvent::ArtefactRegistered {
+                aid: reg.aid,
+                owner: reg.owner,
+            }])
+        }
+        Transaction::UseModel(tx_use) => {
+            let meta = state
+                .artefact(&tx_use.aid)
+                .ok_or(ExecutionError::UnknownArtefact(tx_use.aid))?;
+            if meta.is_revoked() {
+                return Err(ExecutionError::RevokedArtefact(tx_use.aid));
+            }
+            charge(state, tx_use.caller, tx_use.nonce, tx_use.fee)?;
+            credit(state, proposer, tx_use.fee)?;
+            Ok(vec![ReceiptEvent::ModelUsed {
+                aid: tx_use.aid,
+                caller: tx_use.caller,
+            }])
+        }
+        Transaction::Transfer(transfer) => {
+            let cost = transfer.amount.checked_add(transfer.fee).ok_or(
+                ExecutionError::InsufficientBalance {
+                    account: transfer.from,
+                    needed: u64::MAX,
+                    available: state.account(&transfer.from).balance,
+                },
+            )?;
+            charge(state, transfer.from, transfer.nonce, cost)?;
+            credit(state, transfer.to, transfer.amount)?;
+            credit(state, proposer, transfer.fee)?;
+            Ok(vec![ReceiptEvent::Transferred {
+                from: transfer.from,
+                to: transfer.to,
+                amount: transfer.amount,
+            }])
+        }
+        Transaction::TransferModel(transfer) => {
+            let owner = state
+                .artefact(&transfer.aid)
+                .ok_or(ExecutionError::UnknownArtefact(transfer.aid))?
+                .owner;
+            if owner != transfer.current_owner {
+                return Err(ExecutionError::NotArtefactOwner {
+                    aid: transfer.aid,
+                    owner,
+                    signer: transfer.current_owner,
+                });
+            }
+            charge(state, transfer.current_owner, transfer.nonce, transfer.fee)?;
+            if let Some(meta) = state.artefact_mut(&transfer.aid) {
+                meta.owner = transfer.new_owner;
+            }
+            credit(state, proposer, transfer.fee)?;
+            Ok(vec![ReceiptEvent::ArtefactTransferred {
+                aid: transfer.aid,
+                from: transfer.current_owner,
+                to: transfer.new_owner,
+            }])
+        }
+        Transaction::RevokeModel(revoke) => {
+            let meta = state
+                .artefact(&revoke.aid)
+                .ok_or(ExecutionError::UnknownArtefact(revoke.aid))?;
+            if meta.is_revoked() {
+                return Err(ExecutionError::RevokedArtefact(revoke.aid));
+            }
+            if meta.owner != revoke.signer && !state.is_governance(&revoke.signer) {
+                return Err(ExecutionError::RevocationNotAllowed {
+                    aid: revoke.aid,
+                    signer: revoke.signer,
+                });
+            }
+            charge(state, revoke.signer, revoke.nonce, revoke.fee)?;
+            if let Some(meta) = state.artefact_mut(&revoke.aid) {
+                meta.revoked_at = Some(height);
+            }
+            credit(state, proposer, revoke.fee)?;
+            Ok(vec![ReceiptEvent::ArtefactRevoked {
+                aid: revoke.aid,
+                by: revoke.signer,
+            }])
+        }
+        Transaction::Stake(stake) => {
+            let cost =
+                stake
+                    .amount
+                    .checked_add(stake.fee)
+                    .ok_or(ExecutionError::InsufficientBalance {
+                        account: stake.staker,
+                        needed: u64::MAX,
+                        available: state.account(&stake.staker).balance,
+                    })?;
+            charge(state, stake.staker, stake.nonce, cost)?;
+            let record = state.stake_mut(stake.staker);
+            record.bonded = record
+                .bonded
+                .checked_add(stake.amount)
+                .ok_or(ExecutionError::BalanceOverflow(stake.staker))?;
+            credit(state, proposer, stake.fee)?;
+            Ok(vec![ReceiptEvent::Bonded {
+                staker: stake.staker,
+                amount: stake.amount,
+            }])
+        }
+        Transaction::Unstake(unstake) => {
+            let bonded = state.stake(&unstake.staker).map_or(0, |s| s.bonded);
+            if bonded < unstake.amount {
+                return Err(ExecutionError::InsufficientStake {
+                    account: unstake.staker,
+                    needed: unstake.amount,
+                    bonded,
+                });
+            }
+            charge(state, unstake.staker, unstake.nonce, unstake.fee)?;
+            let staking = *state.staking();
+            let record = state.stake_mut(unstake.staker);
+            let release_epoch = staking.epoch(height) + staking.unbonding_epochs;
+            record.bonded -= unstake.amount;
+            record.unbonding.push(Unbonding {
+                amount: unstake.amount,
+                release_epoch,
+            });
+            credit(state, proposer, unstake.fee)?;
+            Ok(vec![ReceiptEvent::UnbondingStarted {
+                staker: unstake.staker,
+                amount: unstake.amount,
+                release_epoch,
+            }])
+        }
+        Transaction::Slash(slash) => {
+            let proof = &slash.proof;
+            proof
+                .verify_signature()
+                .map_err(ExecutionError::BadFraudSignature)?;
+            let reporter_staked = state
+                .stake(&proof.reporter)
+                .is_some_and(|stake| stake.active > 0);
+            if !reporter_staked && !state.is_governance(&proof.reporter) {
+                return Err(ExecutionError::ReportNotAllowed(proof.reporter));
+            }
+            if state.is_slashed(&proof.block_hash) {
+                return Err(ExecutionError::AlreadySlashed(proof.block_hash));
+            }
+            if state.stake(&proof.proposer).is_none() {
+                return Err(ExecutionError::NoStake(proof.proposer));
+            }
+            charge(state, proof.reporter, slash.nonce, slash.fee)?;
+            let percent = state.staking().slash_percent;
+            let burned = state.stake_mut(proof.proposer).slash(percent);
+            state.record_slash(proof.block_hash);
+            credit(state, proposer, slash.fee)?;
+            Ok(vec![ReceiptEvent::Slashed {
+                proposer: proof.proposer,
+                block_hash: proof.block_hash,
+                burned,
+            }])
+        }
+    }
+}
+
+/// Executes all transactions of `block` on top of `parent_state`.
+///
+/// Unbonded stake due by the block's epoch is returned before the
+/// transactions run, and stake is activated after them if the block ends
+/// an epoch. Returns the post-state, or the first error encountered.
+/// `parent_state` is never modified.
+pub fn apply_block(parent_state: &ChainState, block: &Block) -> Result<ChainState, ExecutionError> {
+    apply_block_with_receipts(parent_state, block).map(|(state, _)| state)
+}
+
+/// Like [`apply_block`], but also returns the [`Receipt`] of every
+/// transaction, in block order.
+pub fn apply_block_with_receipts(
+    parent_state: &ChainState,
+    block: &Block,
+) -> Result<(ChainState, Vec<Receipt>), ExecutionError> {
+    let mut state = parent_state.clone();
+    let height = block.header.height;
+    let staking = *state.staking();
+    for (id, amount) in state.release_unbonded(staking.epoch(height)) {
+        credit(&mut state, id, amount)?;
+    }
+    let mut receipts = Vec::with_capacity(block.txs.len());
+    for (index, tx) in block.txs.iter().enumerate() {
+        let events = apply_tx(&mut state, tx, height, block.header.proposer)?;
+        receipts.push(Receipt {
+            tx_hash: tx.compute_hash(),
+            index: index as u32,
+            payer: tx.sender(),
+            fee: tx.fee(),
+            events,
+        });
+    }
+    if staking.is_epoch_end(height) {
+        state.activate_stakes();
+    }
+    Ok((state, receipts))
+}
+
+/// Rebuilds the state at `head` by re-executing its ancestry.
+///
+/// Ancestors are found by following parent links in `store` until a parent
+/// is missing (the genesis block's parent is never stored), and are then
+/// executed on top of `genesis_state`. If the walk reaches the store's
+/// pruning [`Checkpoint`], execution starts from the checkpoint state
+/// instead. If `head` itself is unknown, `genesis_state` is returned
+/// unchanged.
+///
+/// [`Checkpoint`]: crate::consensus::store::Checkpoint
+pub fn replay_chain<S>(
+    store: &S,
+    head: &BlockHash,
+    genesis_state: &ChainState,
+) -> Result<ChainState, ExecutionError>
+where
+    S: BlockStore + ?Sized,
+{
+    let mut checkpoint = store.checkpoint()?;
+    let mut base = None;
+    let mut ancestry = Vec::new();
+    let mut cursor = *head;
+    loop {
+        if checkpoint.as_ref().is_some_and(|c| c.hash == cursor) {
+            base = checkpoint.take().map(|c| c.state);
+            break;
+        }
+        let Some(block) = store.get_block(&cursor)? else {
+            break;
+        };
+        cursor = block.header.parent;
+        ancestry.push(block);
+    }
+
+    let mut state = base.unwrap_or_else(|| genesis_state.clone());
+    for block in ancestry.iter().rev() {
+        state = apply_block(&state, block)?;
+    }
+    Ok(state)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::execution::{StakingConfig, receipts_root};
+    use crate::types::{
+        EvidenceHash, EvidenceRef, FraudProof, FraudVerdict, HASH_LEN, Hash256, Header, Keypair,
+        ModelUseMetadata, PublicKey, Signature, TxRegisterModel, TxRevokeModel, TxSlash, TxStake,
+        TxTransfer, TxTransferModel, TxUnstake, TxUseModel, WmProfile,
+    };
+
+    fn account(byte: u8) -> AccountId {
+        AccountId(Hash256([byte; HASH_LEN]))
+    }
+
+    fn register(owner: AccountId, aid_byte: u8, nonce: u64, fee: u64) -> Transaction {
+        Transaction::RegisterModel(TxRegisterModel {
+            owner,
+            aid: Aid(Hash256([aid_byte; HASH_LEN])),
+            chunked: None,
+            evidence: EvidenceRef {
+                scheme_id: "wm-test".to_string(),
+                evidence_hash: EvidenceHash(Hash256([3u8; HASH_LEN])),
+                wm_profile: WmProfile {
+                    tau_input: 0.9,
+                    tau_feat: 0.1,
+                    logit_band_low: 0.02,
+                    logit_band_high: 0.05,
+                },
+            },
+            fee,
+            nonce,
+            signature: Signature(vec![]),
+        })
+    }
+
+    fn use_model(caller: AccountId, aid_byte: u8, nonce: u64) -> Transaction {
+        Transaction::UseModel(TxUseModel {
+            caller,
+            aid: Aid(Hash256([aid_byte; HASH_LEN])),
+            metadata: ModelUseMetadata {
+                task: "image_classification".to_string(),
+                version: None,
+            },
+            fee: 0,
+            nonce,
+            signature: Signature(vec![]),
+        })
+    }
+
+    fn transfer(from: AccountId, to: AccountId, amount: u64, fee: u64, nonce: u64) -> Transaction {
+        Transaction::Transfer(TxTransfer {
+            from,
+            to,
+            amount,
+            fee,
+            nonce,
+            signature: Signature(vec![]),
+        })
+    }
+
+    fn transfer_model(current_owner: AccountId, new_owner: AccountId, nonce: u64) -> Transaction {
+        Transaction::TransferModel(TxTransferModel {
+            current_owner,
+            new_owner,
+            aid: Aid(Hash256([2u8; HASH_LEN])),
+            fee: 0,
+            nonce,
+            signature: Signature(vec![]),
+        })
+    }
+
+    fn revoke_model(signer: AccountId, nonce: u64) -> Transaction {
+        Transaction::RevokeModel(TxRevokeModel {
+            signer,
+            aid: Aid(Hash256([2u8; HASH_LEN])),
+            fee: 0,
+            nonce,
+            signature: Signature(vec![]),
+        })
+    }
+
+    fn stake(staker: AccountId, amount: u64, nonce: u64) -> Transaction {
+        Transaction::Stake(TxStake {
+            staker,
+            amount,
+            fee: 0,
+            nonce,
+            signature: Signature(vec![]),
+        })
+    }
+
+    fn unstake(staker: AccountId, amount: u64, nonce: u64) -> Transaction {
+        Transaction::Unstake(TxUnstake {
+            staker,
+            amount,
+            fee: 0,
+            nonce,
+            signature: Signature(vec![]),
+        })
+    }
+
+    fn slash(reporter: &Keypair, offender: AccountId, block_byte: u8, nonce: u64) -> Transaction {
+        let mut proof = FraudProof {
+            block_hash: BlockHash(Hash256([block_byte; HASH_LEN])),
+            height: 1,
+            proposer: offender,
+            aid: Aid(Hash256([2u8; HASH_LEN])),
+            verdict: FraudVerdict {
+                scheme_id: "wm-test".to_string(),
+                score: Some(0.1),
+            },
+            reporter: reporter.account_id(),
+            reporter_key: PublicKey(vec![]),
+            signature: Signature(vec![]),
+        };
+        reporter.sign_fraud_proof(&mut proof);
+        Transaction::Slash(TxSlash {
+            proof,
+            fee: 0,
+            nonce,
+            signature: Signature(vec![]),
+        })
+    }
+
+    fn block(height: u64, proposer: AccountId, txs: Vec<Transaction>) -> Block {
+        Block {
+            header: Header {
+                parent: BlockHash(Hash256([0u8; HASH_LEN])),
+                height,
+                timestamp: 1_700_000_000,
+                proposer,
+                txs_root: Hash256([0u8; HASH_LEN]),
+                state_root: Hash256([0u8; HASH_LEN]),
+                receipts_root: Hash256([0u8; HASH_LEN]),
+                pos_proof: None,
+            },
+            txs,
+        }
+    }
+
+    #[test]
+    fn register_then_use_updates_registry_and_nonces() {
+        let owner = account(1);
+        let b = block(
+            7,
+            account(9),
+            vec![register(owner, 2, 0, 0), use_model(owner, 2, 1)],
+        );
+
+        let state = apply_block(&ChainState::new(), &b).expect("block executes");
+
+        let meta = state
+            .artefact(&Aid(Hash256([2u8; HASH_LEN])))
+            .expect("artefact registered");
+        assert_eq!(meta.owner, owner);
+        assert_eq!(meta.registered_at, 7);
+        assert_eq!(state.account(&owner).nonce, 2);
+    }
+
+    #[test]
+    fn transfer_moves_balance_and_pays_proposer() {
+        let (alice, bob, proposer) = (account(1), account(2), account(9));
+        let mut genesis = ChainState::new();
+        genesis.account_mut(alice).balance = 100;
+
+        let b = block(1, proposer, vec![transfer(alice, bob, 60, 5, 0)]);
+        let state = apply_block(&genesis, &b).expect("transfer executes");
+
+        assert_eq!(state.account(&alice).balance, 35);
+        assert_eq!(state.account(&bob).balance, 60);
+        assert_eq!(state.account(&proposer).balance, 5);
+    }
+
+    #[test]
+    fn receipts_record_fees_and_events_in_block_order() {
+        let (alice, bob, proposer) = (account(1), account(2), account(9));
+        let mut genesis = ChainState::new();
+        genesis.account_mut(alice).balance = 100;
+
+        let b = block(
+            1,
+            proposer,
+            vec![transfer(alice, bob, 60, 5, 0), register(alice, 3, 1, 2)],
+        );
+        let (state, receipts) = apply_block_with_receipts(&genesis, &b).expect("block executes");
+        assert_eq!(
+            state.state_root(),
+            apply_block(&genesis, &b).unwrap().state_root()
+        );
+
+        assert_eq!(receipts.len(), 2);
+        assert_eq!(receipts[0].tx_hash, b.txs[0].compute_hash());
+        assert_eq!((receipts[0].payer, receipts[0].fee), (alice, 5));
+        assert_eq!(
+            receipts[0].events,
+            vec![ReceiptEvent::Transferred {
+                from: alice,
+                to: bob,
+                amount: 60,
+            }]
+        );
+        assert_eq!((receipts[1].index, receipts[1].fee), (1, 2));
+        assert_eq!(
+            receipts[1].events,
+            vec![ReceiptEvent::ArtefactRegistered {
+                aid: Aid(Hash256([3; HASH_LEN])),
+                owner: alice,
+            }]
+        );
+        assert_ne!(receipts_root(&receipts), receipts_root(&receipts[..1]));
+    }
+
+    #[test]
+    fn failing_block_leaves_parent_state_untouched() {
+        let owner = account(1);
+        let b = block(
+            1,
+            account(9),
+            vec![register(owner, 2, 0, 0), register(owner, 3, 5, 0)],
+        );
+
+        let parent = ChainState::new();
+        let err = apply_block(&parent, &b).unwrap_err();
+
+        assert_eq!(
+            err,
+            ExecutionError::BadNonce {
+                account: owner,
+                expected: 1,
+                got: 5
+            }
+        );
+        assert_eq!(parent.artefacts().count(), 0);
+    }
+
+    #[test]
+    fn rejects_duplicate_and_unknown_artefacts_and_unfunded_fees() {
+        let owner = account(1);
+        let state = apply_block(
+            &ChainState::new(),
+            &block(0, account(9), vec![register(owner, 2, 0, 0)]),
+        )
+        .unwrap();
+
+        let dup = apply_block(
+            &state,
+            &block(1, account(9), vec![register(owner, 2, 1, 0)]),
+        );
+        assert!(matches!(dup, Err(ExecutionError::DuplicateArtefact(_))));
+
+        let unknown = apply_block(&state, &block(1, account(9), vec![use_model(owner, 4, 1)]));
+        assert!(matches!(unknown, Err(ExecutionError::UnknownArtefact(_))));
+
+        let unfunded = apply_block(
+            &state,
+            &block(1, account(9), vec![register(owner, 5, 1, 1)]),
+        );
+        assert!(matches!(
+            unfunded,
+            Err(ExecutionError::InsufficientBalance { .. })
+        ));
+    }
+
+    #[test]
+    fn only_the_current_owner_may_transfer_a_model() {
+        let (alice, bob, carol) = (account(1), account(2), account(3));
+        let state = apply_block(
+            &ChainState::new(),
+            &block(0, account(9), vec![register(alice, 2, 0, 0)]),
+        )
+        .unwrap();
+
+        let state = apply_block(
+            &state,
+            &block(1, account(9), vec![transfer_model(alice, bob, 1)]),
+        )
+        .expect("owner transfers the model");
+        let meta = state.artefact(&Aid(Hash256([2u8; HASH_LEN]))).unwrap();
+        assert_eq!(meta.owner, bob);
+        assert_eq!(meta.registered_at, 0);
+        assert_eq!(state.account(&alice).nonce, 2);
+
+        let stolen = apply_block(
+            &state,
+            &block(2, account(9), vec![transfer_model(alice, carol, 2)]),
+        );
+        assert_eq!(
+            stolen.unwrap_err(),
+            ExecutionError::NotArtefactOwner {
+                aid: Aid(Hash256([2u8; HASH_LEN])),
+                owner: bob,
+                signer: alice,
+            }
+        );
+
+        let unknown = apply_block(
+            &ChainState::new(),
+            &block(1, account(9), vec![transfer_model(alice, bob, 0)]),
+        );
+        assert!(matches!(unknown, Err(ExecutionError::UnknownArtefact(_))));
+    }
+
+    #[test]
+    fn revoked_models_cannot_be_used() {
+        let (owner, caller, governor) = (account(1), account(2), account(5));
+        let mut genesis = ChainState::new();
+        genesis.add_governance(governor);
+        let state = apply_block(
+            &genesis,
+            &block(0, account(9), vec![register(owner, 2, 0, 0)]),
+        )
+        .unwrap();
+
+        let denied = apply_block(&state, &block(1, account(9), vec![revoke_model(caller, 0)]));
+        assert!(matches!(
+            denied,
+            Err(ExecutionError::RevocationNotAllowed { .. })
+        ));
+
+        for signer in [owner, governor] {
+            let nonce = state.account(&signer).nonce;
+            let revoked = apply_block(
+                &state,
+                &block(3, account(9), vec![revoke_model(signer, nonce)]),
+            )
+            .expect("owner and governance may revoke");
+            let meta = revoked.artefact(&Aid(Hash256([2u8; HASH_LEN]))).unwrap();
+            assert_eq!(meta.revoked_at, Some(3));
+
+            let used = apply_block(
+                &revoked,
+                &block(4, account(9), vec![use_model(caller, 2, 0)]),
+            );
+            assert!(matches!(used, Err(ExecutionError::RevokedArtefact(_))));
+        }
+    }
+
+    #[test]
+    fn stake_activates_at_epoch_end_and_unbonds_after_the_delay() {
+        let (alice, proposer) = (account(1), account(9));
+        let mut genesis = ChainState::new();
+        genesis.set_staking(StakingConfig {
+            epoch_length: 2,
+            unbonding_epochs: 1,
+            ..StakingConfig::default()
+        });
+        genesis.account_mut(alice).balance = 100;
+
+        // Epoch 0: bonded stake only becomes active once the epoch ends.
+        let state = apply_block(&genesis, &block(0, proposer, vec![stake(alice, 60, 0)])).unwrap();
+        assert_eq!(state.account(&alice).balance, 40);
+        assert_eq!(state.stake(&alice).unwrap().active, 0);
+        let state = apply_block(&state, &block(1, proposer, vec![])).unwrap();
+        assert_eq!(state.active_stakes(), vec![(alice, 60)]);
+
+        // Epoch 1: unbond 50, released at the start of epoch 2.
+        let overdrawn = apply_block(&state, &block(2, proposer, vec![unstake(alice, 70, 1)]));
+        assert!(matches!(
+            overdrawn,
+            Err(ExecutionError::InsufficientStake { .. })
+        ));
+        let state = apply_block(&state, &block(2, proposer, vec![unstake(alice, 50, 1)])).unwrap();
+        let state = apply_block(&state, &block(3, proposer, vec![])).unwrap();
+        assert_eq!(state.active_stakes(), vec![(alice, 10)]);
+        assert_eq!(state.account(&alice).balance, 40);
+
+        let state = apply_block(&state, &block(4, proposer, vec![])).unwrap();
+        assert_eq!(state.account(&alice).balance, 90);
+        assert!(state.stake(&alice).unwrap().unbonding.is_empty());
+    }
+
+    #[test]
+    fn slashing_burns_offender_stake_once_per_block() {
+        let (validator_keys, outsider_keys) = (Keypair::generate(), Keypair::generate());
+        let (validator, offender, outsider) = (
+            validator_keys.account_id(),
+            account(2),
+            outsider_keys.account_id(),
+        );
+        let mut genesis = ChainState::new();
+        for (id, amount) in [(validator, 100), (offender, 1_000)] {
+            let stake = genesis.stake_mut(id);
+            stake.bonded = amount;
+            stake.active = amount;
+        }
+
+        let denied = apply_block(
+            &genesis,
+            &block(1, account(9), vec![slash(&outsider_keys, offender, 7, 0)]),
+        );
+        assert_eq!(
+            denied.unwrap_err(),
+            ExecutionError::ReportNotAllowed(outsider)
+        );
+
+        let state = apply_block(
+            &genesis,
+            &block(1, account(9), vec![slash(&validator_keys, offender, 7, 0)]),
+        )
+        .expect("a staked validator may report fraud");
+        assert_eq!(state.stake(&offender).unwrap().bonded, 900);
+        assert!(state.is_slashed(&BlockHash(Hash256([7u8; HASH_LEN]))));
+        assert_ne!(state.state_root(), genesis.state_root());
+
+        let again = apply_block(
+            &state,
+            &block(2, account(9), vec![slash(&validator_keys, offender, 7, 1)]),
+        );
+        assert!(matches!(again, Err(ExecutionError::AlreadySlashed(_))));
+
+        let mut forged = slash(&validator_keys, offender, 8, 1);
+        if let Transaction::Slash(tx) = &mut forged {
+            tx.proof.height = 2;
+        }
+        let forged = apply_block(&state, &block(2, account(9), vec![forged]));
+        assert_eq!(
+            forged.unwrap_err(),
+            ExecutionError::BadFraudSignature(SignatureError::Invalid)
+        );
+    }
+}
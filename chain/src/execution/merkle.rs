@@ -0,0 +1,201 @@
+//! Merkle commitment to chain state.
+//!
+//! The state root committed in every block header is the root of a binary
+//! Merkle tree over the sorted state entries:
+//!
+//! 1. one leaf per account that differs from [`Account::default`], in
+//!    ascending [`AccountId`] order, then
+//! 2. one leaf per registered artefact, in ascending [`Aid`] order, then
+//! 3. one leaf per stake record, in ascending [`AccountId`] order, then
+//! 4. one leaf per slashed block, in ascending [`BlockHash`] order.
+//!
+//! The tree itself is the generic one in [`crate::types::merkle`]; the
+//! empty state has the all-zero root.
+//!
+//! A [`MerkleProof`] shows that a single entry is part of a given root,
+//! which is enough for a light client to check a balance or an artefact
+//! registration against a block header. Proofs of absence are not
+//! supported.
+
+use crate::types::merkle::{EMPTY_ROOT, LEAF_PREFIX, MerkleProof, merkle_root};
+use crate::types::{AccountId, Aid, ArtefactMetadata, BlockHash, Hash256};
+
+use super::stake::Stake;
+use super::state::{Account, ChainState};
+
+/// Tags distinguishing the kinds of state entry.
+const ACCOUNT_TAG: u8 = 0x00;
+const ARTEFACT_TAG: u8 = 0x01;
+const STAKE_TAG: u8 = 0x02;
+const SLASH_TAG: u8 = 0x03;
+
+/// Root of the empty state.
+pub const EMPTY_STATE_ROOT: Hash256 = EMPTY_ROOT;
+
+/// Returns the leaf hash committing to `account` under `id`.
+pub fn account_leaf(id: &AccountId, account: &Account) -> Hash256 {
+    let mut preimage = vec![LEAF_PREFIX, ACCOUNT_TAG];
+    preimage.extend_from_slice(id.0.as_bytes());
+    preimage.extend_from_slice(&account.balance.to_le_bytes());
+    preimage.extend_from_slice(&account.nonce.to_le_bytes());
+    Hash256::compute(&preimage)
+}
+
+/// Returns the leaf hash committing to a registered artefact.
+///
+/// # Panics
+///
+/// Panics if encoding `meta` fails, which would be a programming error.
+pub fn artefact_leaf(meta: &ArtefactMetadata) -> Hash256 {
+    let encoded = bincode::serde::encode_to_vec(meta, bincode::config::standard())
+        .expect("ArtefactMetadata should always be serializable with bincode 2 + serde");
+    let mut preimage = vec![LEAF_PREFIX, ARTEFACT_TAG];
+    preimage.extend_from_slice(meta.aid.0.as_bytes());
+    preimage.extend_from_slice(&encoded);
+    Hash256::compute(&preimage)
+}
+
+/// Returns the leaf hash committing to the stake record of `id`.
+///
+/// # Panics
+///
+/// Panics if encoding `stake` fails, which would be a programming error.
+pub fn stake_leaf(id: &AccountId, stake: &Stake) -> Hash256 {
+    let encoded = bincode::serde::encode_to_vec(stake, bincode::config::standard())
+        .expect("Stake should always be serializable with bincode 2 + serde");
+    let mut preimage = vec![LEAF_PREFIX, STAKE_TAG];
+    preimage.extend_from_slice(id.0.as_bytes());
+    preimage.extend_from_slice(&encoded);
+    Hash256::compute(&preimage)
+}
+
+/// Returns the leaf hash recording that the proposer of `block` was
+/// slashed.
+pub fn slash_leaf(block: &BlockHash) -> Hash256 {
+    let mut preimage = vec![LEAF_PREFIX, SLASH_TAG];
+    preimage.extend_from_slice(block.0.as_bytes());
+    Hash256::compute(&preimage)
+}
+
+impl ChainState {
+    /// Returns the state leaves in commitment order.
+    fn state_leaves(&self) -> Vec<Hash256> {
+        let mut accounts: Vec<_> = self
+            .accounts()
+            .filter(|(_, account)| **account != Account::default())
+            .collect();
+        accounts.sort_by_key(|(id, _)| id.0.0);
+        let mut artefacts: Vec<_> = self.artefacts().map(|(_, meta)| meta).collect();
+        artefacts.sort_by_key(|meta| meta.aid.0.0);
+        let mut stakes: Vec<_> = self
+            .stakes()
+            .filter(|(_, stake)| **stake != Stake::default())
+            .collect();
+        stakes.sort_by_key(|(id, _)| id.0.0);
+        let mut slashed: Vec<_> = self.slashed().collect();
+        slashed.sort_by_key(|hash| hash.0.0);
+
+        accounts
+            .into_iter()
+            .map(|(id, account)| account_leaf(id, account))
+            .chain(artefacts.into_iter().map(artefact_leaf))
+            .chain(stakes.into_iter().map(|(id, stake)| stake_leaf(id, stake)))
+            .chain(slashed.into_iter().map(slash_leaf))
+            .collect()
+    }
+
+    /// Returns the Merkle root committing to this state.
+    pub fn state_root(&self) -> Hash256 {
+        merkle_root(&self.state_leaves())
+    }
+
+    /// Returns a proof that account `id` holds its current record, or
+    /// `None` if the account is untouched (reads as the default).
+    pub fn account_proof(&self, id: &AccountId) -> Option<MerkleProof> {
+        let leaf = account_leaf(id, &self.account(id));
+        let leaves = self.state_leaves();
+        let index = leaves.iter().position(|l| *l == leaf)?;
+        MerkleProof::build(&leaves, index)
+    }
+
+    /// Returns a proof that artefact `aid` is registered, or `None` if it
+    /// is not.
+    pub fn artefact_proof(&self, aid: &Aid) -> Option<MerkleProof> {
+        let leaf = artefact_leaf(self.artefact(aid)?);
+        let leaves = self.state_leaves();
+        let index = leaves.iter().position(|l| *l == leaf)?;
+        MerkleProof::build(&leaves, index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{EvidenceHash, EvidenceRef, HASH_LEN, WmProfile};
+
+    fn account(byte: u8) -> AccountId {
+        AccountId(Hash256([byte; HASH_LEN]))
+    }
+
+    fn state_with(accounts: &[(u8, u64)]) -> ChainState {
+        let mut state = ChainState::new();
+        for (byte, balance) in accounts {
+            state.account_mut(account(*byte)).balance = *balance;
+        }
+        state
+    }
+
+    #[test]
+    fn root_ignores_insertion_order_and_untouched_accounts() {
+        let a = state_with(&[(1, 10), (2, 20), (3, 30)]);
+        let mut b = state_with(&[(3, 30), (1, 10), (2, 20)]);
+        b.account_mut(account(9));
+
+        assert_eq!(a.state_root(), b.state_root());
+        assert_ne!(
+            a.state_root(),
+            state_with(&[(1, 10), (2, 21), (3, 30)]).state_root()
+        );
+        assert_eq!(ChainState::new().state_root(), EMPTY_STATE_ROOT);
+    }
+
+    #[test]
+    fn proofs_verify_every_entry_against_the_root() {
+        let mut state = state_with(&[(1, 10), (2, 20), (3, 30), (4, 40), (5, 50)]);
+        let aid = Aid(Hash256([7u8; HASH_LEN]));
+        state.insert_artefact(ArtefactMetadata {
+            aid,
+            chunked: None,
+            owner: account(1),
+            evidence: EvidenceRef {
+                scheme_id: "wm-test".to_string(),
+                evidence_hash: EvidenceHash(Hash256([3u8; HASH_LEN])),
+                wm_profile: WmProfile {
+                    tau_input: 0.9,
+                    tau_feat: 0.1,
+                    logit_band_low: 0.02,
+                    logit_band_high: 0.05,
+                },
+            },
+            registered_at: 4,
+            revoked_at: None,
+        });
+        let root = state.state_root();
+
+        for byte in 1..=5 {
+            let id = account(byte);
+            let proof = state.account_proof(&id).expect("account is committed");
+            assert!(proof.verify(account_leaf(&id, &state.account(&id)), &root));
+
+            let forged = Account {
+                balance: 1_000_000,
+                nonce: 0,
+            };
+            assert!(!proof.verify(account_leaf(&id, &forged), &root));
+        }
+
+        let proof = state.artefact_proof(&aid).expect("artefact is committed");
+        assert!(proof.verify(artefact_leaf(state.artefact(&aid).unwrap()), &root));
+        assert!(state.account_proof(&account(9)).is_none());
+    }
+}
@@ -0,0 +1,40 @@
+//! Execution layer: applies transactions to chain state.
+//!
+//! Blocks are validated by the consensus layer and then executed here to
+//! evolve the chain state:
+//!
+//! - account balances and nonces ([`state::Account`]),
+//! - the artefact registry keyed by [`crate::types::Aid`]
+//!   ([`crate::types::ArtefactMetadata`] entries),
+//! - the proposer stake ledger ([`stake::Stake`] entries), and
+//! - the network parameters ([`params::ChainParams`]).
+//!
+//! Execution is a pure function of `(parent state, block)`: [`apply_block`]
+//! never mutates its input and either returns the full post-state or the
+//! first error encountered, so a failing block leaves no partial effects.
+//!
+//! Every block header commits to its post-state through a Merkle root
+//! ([`ChainState::state_root`]), so single entries can be proven against a
+//! header with a [`MerkleProof`](crate::types::MerkleProof). Execution also
+//! yields a [`Receipt`] per transaction, committed to by the header's
+//! `receipts_root` in the same way.
+//!
+//! Account queries (balance, nonce, stake, owned artefacts) go through the
+//! [`StateReader`] trait, so APIs need not care whether they read a
+//! [`ChainState`] or a live [`ChainReader`](crate::consensus::ChainReader).
+
+pub mod apply;
+pub mod merkle;
+pub mod params;
+pub mod reader;
+pub mod receipt;
+pub mod stake;
+pub mod state;
+
+pub use apply::{ExecutionError, apply_block, apply_block_with_receipts, apply_tx, replay_chain};
+pub use merkle::EMPTY_STATE_ROOT;
+pub use params::ChainParams;
+pub use reader::{AccountSummary, StateReader};
+pub use receipt::{MlStatsLog, MlVerdictStats, Receipt, ReceiptEvent, receipts_root};
+pub use stake::{Stake, StakingConfig, Unbonding};
+pub use state::{Account, ChainState, StateHandle};
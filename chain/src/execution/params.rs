@@ -0,0 +1,39 @@
+//! Network parameters held in chain state.
+//!
+//! [`ChainParams`] are fixed by the genesis spec and carried in
+//! [`ChainState`](super::ChainState), so every node reads the same values
+//! at the same height. The consensus layer snapshots them per epoch (see
+//! [`Epoch`](crate::consensus::Epoch)), which is where later parameter
+//! changes would take effect.
+
+use serde::{Deserialize, Serialize};
+
+use crate::types::{SchemeRegistry, WmProfile};
+
+/// Network-wide parameters.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ChainParams {
+    /// Watermark profile used for registrations that do not bring their
+    /// own.
+    pub wm_profile: WmProfile,
+    /// Lowest fee a transaction may pay to be admitted to the mempool.
+    pub min_fee: u64,
+    /// Watermark schemes registrations may use; empty accepts any.
+    pub schemes: SchemeRegistry,
+}
+
+impl Default for ChainParams {
+    fn default() -> Self {
+        Self {
+            wm_profile: WmProfile {
+                tau_input: 0.9,
+                tau_feat: 0.2,
+                logit_band_low: -0.05,
+                logit_band_high: 0.05,
+            },
+            min_fee: 0,
+            schemes: SchemeRegistry::default(),
+        }
+    }
+}
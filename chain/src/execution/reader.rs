@@ -0,0 +1,131 @@
+//! Read-only account queries over chain state.
+//!
+//! [`StateReader`] is the interface APIs answer account questions with
+//! (balance, nonce, stake, owned artefacts), so a client can check its
+//! funds and pick a nonce before submitting a transaction. It is
+//! implemented by [`ChainState`] and by
+//! [`ChainReader`](crate::consensus::ChainReader), which answers from the
+//! state at its tip.
+
+use serde::{Deserialize, Serialize};
+
+use crate::types::{AccountId, Aid};
+
+use super::stake::Stake;
+use super::state::{Account, ChainState};
+
+/// Everything [`StateReader`] knows about one account.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct AccountSummary {
+    /// Balance and the nonce of the next transaction to execute.
+    pub account: Account,
+    /// Stake record; all zero if the account never staked.
+    pub stake: Stake,
+    /// Artefacts the account currently owns, revoked ones included, in
+    /// ascending `Aid` order.
+    pub owned: Vec<Aid>,
+}
+
+/// Read access to account state.
+pub trait StateReader {
+    /// Returns the balance and nonce of `id`; unknown accounts read as
+    /// [`Account::default`].
+    fn account(&self, id: &AccountId) -> Account;
+
+    /// Returns the stake record of `id`, or the default if it never
+    /// staked.
+    fn stake(&self, id: &AccountId) -> Stake;
+
+    /// Returns the artefacts `owner` currently owns, revoked ones
+    /// included, in ascending `Aid` order.
+    fn owned_artefacts(&self, owner: &AccountId) -> Vec<Aid>;
+
+    /// Returns the account, stake and owned artefacts of `id` together.
+    ///
+    /// Implementations over a changing state should override this so the
+    /// three parts come from the same snapshot.
+    fn account_summary(&self, id: &AccountId) -> AccountSummary {
+        AccountSummary {
+            account: self.account(id),
+            stake: self.stake(id),
+            owned: self.owned_artefacts(id),
+        }
+    }
+}
+
+impl StateReader for ChainState {
+    fn account(&self, id: &AccountId) -> Account {
+        ChainState::account(self, id)
+    }
+
+    fn stake(&self, id: &AccountId) -> Stake {
+        ChainState::stake(self, id).cloned().unwrap_or_default()
+    }
+
+    fn owned_artefacts(&self, owner: &AccountId) -> Vec<Aid> {
+        let mut owned: Vec<Aid> = self
+            .artefacts()
+            .filter(|(_, meta)| meta.owner == *owner)
+            .map(|(aid, _)| *aid)
+            .collect();
+        owned.sort_by_key(|aid| aid.0.0);
+        owned
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{ArtefactMetadata, EvidenceHash, EvidenceRef, HASH_LEN, Hash256, WmProfile};
+
+    fn artefact(seed: u8, owner: AccountId, revoked_at: Option<u64>) -> ArtefactMetadata {
+        ArtefactMetadata {
+            aid: Aid(Hash256([seed; HASH_LEN])),
+            owner,
+            evidence: EvidenceRef {
+                scheme_id: "wm-test".to_string(),
+                evidence_hash: EvidenceHash(Hash256([seed; HASH_LEN])),
+                wm_profile: WmProfile {
+                    tau_input: 0.9,
+                    tau_feat: 0.2,
+                    logit_band_low: -0.05,
+                    logit_band_high: 0.05,
+                },
+            },
+            registered_at: 1,
+            revoked_at,
+            chunked: None,
+        }
+    }
+
+    #[test]
+    fn summary_collects_balance_stake_and_owned_artefacts() {
+        let alice = AccountId(Hash256([1u8; HASH_LEN]));
+        let bob = AccountId(Hash256([2u8; HASH_LEN]));
+        let mut state = ChainState::new();
+        state.account_mut(alice).balance = 500;
+        state.account_mut(alice).nonce = 3;
+        state.stake_mut(alice).bonded = 100;
+        state.insert_artefact(artefact(9, alice, None));
+        state.insert_artefact(artefact(7, alice, Some(4)));
+        state.insert_artefact(artefact(8, bob, None));
+
+        let summary = state.account_summary(&alice);
+        assert_eq!(
+            summary.account,
+            Account {
+                balance: 500,
+                nonce: 3
+            }
+        );
+        assert_eq!(summary.stake.bonded, 100);
+        assert_eq!(
+            summary.owned,
+            vec![Aid(Hash256([7u8; HASH_LEN])), Aid(Hash256([9u8; HASH_LEN]))]
+        );
+
+        // Unknown accounts read as all defaults.
+        let carol = AccountId(Hash256([3u8; HASH_LEN]));
+        assert_eq!(state.account_summary(&carol), AccountSummary::default());
+    }
+}
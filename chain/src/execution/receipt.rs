@@ -0,0 +1,173 @@
+//! Transaction receipts.
+//!
+//! Executing a block yields one [`Receipt`] per transaction, in block
+//! order, recording the fee charged and the state changes the transaction
+//! made as a list of [`ReceiptEvent`]s. Every block header commits to its
+//! receipts through [`Header::receipts_root`](crate::types::Header::receipts_root),
+//! a Merkle root over [`Receipt::leaf`] values, so a receipt served by a
+//! node can be checked against the header like a transaction.
+//!
+//! Receipts are a pure function of the parent state and the block, so
+//! every node derives the same ones. ML verdicts are not part of them:
+//! they come from the off-chain verifier and may differ between nodes.
+//! The [`MlVerdictStats`] of each registration are stored next to the
+//! block's receipts instead, outside `receipts_root`, after the ML
+//! validator hands them to the engine through an [`MlStatsLog`].
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+use serde::{Deserialize, Serialize};
+
+use crate::types::merkle::{LEAF_PREFIX, merkle_root};
+use crate::types::{AccountId, Aid, BlockHash, Hash256, TxHash};
+
+/// Tag distinguishing receipt leaves from other leaf kinds.
+const RECEIPT_TAG: u8 = 0x10;
+
+/// State change made by a transaction.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum ReceiptEvent {
+    /// An artefact was added to the registry.
+    ArtefactRegistered { aid: Aid, owner: AccountId },
+    /// A usage record was accepted for an artefact.
+    ModelUsed { aid: Aid, caller: AccountId },
+    /// Value moved between accounts.
+    Transferred {
+        from: AccountId,
+        to: AccountId,
+        amount: u64,
+    },
+    /// Ownership of an artefact changed.
+    ArtefactTransferred {
+        aid: Aid,
+        from: AccountId,
+        to: AccountId,
+    },
+    /// An artefact was revoked.
+    ArtefactRevoked { aid: Aid, by: AccountId },
+    /// Balance was bonded as stake.
+    Bonded { staker: AccountId, amount: u64 },
+    /// Bonded stake started unbonding.
+    UnbondingStarted {
+        staker: AccountId,
+        amount: u64,
+        release_epoch: u64,
+    },
+    /// A proposer's stake was slashed for a block.
+    Slashed {
+        proposer: AccountId,
+        block_hash: BlockHash,
+        burned: u64,
+    },
+}
+
+/// Outcome of executing one transaction.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Receipt {
+    /// Hash of the transaction.
+    pub tx_hash: TxHash,
+    /// Position of the transaction in its block.
+    pub index: u32,
+    /// Account charged the fee.
+    pub payer: AccountId,
+    /// Fee charged and credited to the block proposer.
+    pub fee: u64,
+    /// State changes made by the transaction, in order.
+    pub events: Vec<ReceiptEvent>,
+}
+
+impl Receipt {
+    /// Returns the leaf committing to this receipt in a block's receipts
+    /// tree.
+    ///
+    /// # Panics
+    ///
+    /// Panics if encoding the receipt fails, which would be a programming
+    /// error.
+    pub fn leaf(&self) -> Hash256 {
+        let encoded = bincode::serde::encode_to_vec(self, bincode::config::standard())
+            .expect("Receipt should always be serializable with bincode 2 + serde");
+        let mut preimage = vec![LEAF_PREFIX, RECEIPT_TAG];
+        preimage.extend_from_slice(&encoded);
+        Hash256::compute(&preimage)
+    }
+}
+
+/// Returns the Merkle root over the leaves of `receipts`, in order. A block
+/// without transactions has the all-zero root.
+pub fn receipts_root(receipts: &[Receipt]) -> Hash256 {
+    let leaves: Vec<Hash256> = receipts.iter().map(Receipt::leaf).collect();
+    merkle_root(&leaves)
+}
+
+/// How the ML verifier judged the artefact registered by one transaction.
+///
+/// Recorded by this node's verifier; not committed to by any header.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MlVerdictStats {
+    /// Hash of the registering transaction.
+    pub tx_hash: TxHash,
+    /// Position of the transaction in its block.
+    pub index: u32,
+    /// Artefact the transaction registers.
+    pub aid: Aid,
+    /// Watermarking scheme of the artefact's evidence.
+    pub scheme_id: String,
+    /// `accepted`, or `unverified` / `deferred` when the block was
+    /// accepted during a verifier outage, as in the decision log.
+    pub decision: String,
+    /// Scores reported by the verifier, if it answered.
+    pub score: Option<f32>,
+    pub trigger_acc: Option<f32>,
+    pub feat_dist: Option<f32>,
+    pub logit_stat: Option<f32>,
+    /// Time the verifier call took, in milliseconds.
+    pub latency_ms: Option<u64>,
+}
+
+/// Blocks whose statistics an [`MlStatsLog`] keeps until the engine takes
+/// them; statistics of blocks that are never imported are dropped oldest
+/// first.
+const ML_STATS_LOG_BLOCKS: usize = 256;
+
+/// Shared log of the [`MlVerdictStats`] of validated blocks.
+///
+/// The ML validator records the statistics of every block it accepts (see
+/// `MlValidity::with_stats_log`), and the consensus engine takes them when
+/// it persists the block (see [`ConsensusEngine::with_ml_stats`]). Clones
+/// share the same log.
+///
+/// [`ConsensusEngine::with_ml_stats`]: crate::consensus::ConsensusEngine::with_ml_stats
+#[derive(Clone, Debug, Default)]
+pub struct MlStatsLog(Arc<Mutex<VecDeque<(BlockHash, Vec<MlVerdictStats>)>>>);
+
+impl MlStatsLog {
+    /// Creates an empty log.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records `stats` for the block `hash`, replacing earlier ones.
+    pub fn record(&self, hash: BlockHash, stats: Vec<MlVerdictStats>) {
+        let mut blocks = self.0.lock().expect("ML stats log lock poisoned");
+        blocks.retain(|(h, _)| *h != hash);
+        if blocks.len() == ML_STATS_LOG_BLOCKS {
+            blocks.pop_front();
+        }
+        blocks.push_back((hash, stats));
+    }
+
+    /// Removes and returns the statistics recorded for the block `hash`;
+    /// empty if none were.
+    pub fn take(&self, hash: &BlockHash) -> Vec<MlVerdictStats> {
+        let mut blocks = self.0.lock().expect("ML stats log lock poisoned");
+        match blocks.iter().position(|(h, _)| h == hash) {
+            Some(pos) => blocks
+                .remove(pos)
+                .map(|(_, stats)| stats)
+                .unwrap_or_default(),
+            None => Vec::new(),
+        }
+    }
+}
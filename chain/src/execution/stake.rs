@@ -0,0 +1,136 @@
+//! Proposer stake ledger.
+//!
+//! Accounts bond part of their balance as stake with `TxStake` and unbond it
+//! with `TxUnstake`. Height is divided into epochs of
+//! [`StakingConfig::epoch_length`] blocks, and stake changes only count for
+//! proposer selection from the next epoch on: when the last block of an
+//! epoch is executed, every account's [`Stake::active`] amount is reset to
+//! its [`Stake::bonded`] amount. Nodes at the same tip therefore agree on
+//! the stake distribution for the whole epoch.
+//!
+//! Unbonded stake is held for [`StakingConfig::unbonding_epochs`] epochs
+//! before it returns to the balance, so a proposer cannot withdraw its
+//! stake right after misbehaving: a `TxSlash` burns
+//! [`StakingConfig::slash_percent`] of both the bonded and the unbonding
+//! stake.
+
+use serde::{Deserialize, Serialize};
+
+/// Staking parameters, fixed by the genesis spec.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StakingConfig {
+    /// Number of blocks per epoch.
+    pub epoch_length: u64,
+    /// Number of epochs unbonded stake is held before it is returned.
+    pub unbonding_epochs: u64,
+    /// Percentage of a proposer's stake burned per slashed block.
+    pub slash_percent: u64,
+}
+
+impl Default for StakingConfig {
+    fn default() -> Self {
+        Self {
+            epoch_length: 100,
+            unbonding_epochs: 2,
+            slash_percent: 10,
+        }
+    }
+}
+
+impl StakingConfig {
+    /// Returns the epoch containing `height`.
+    pub fn epoch(&self, height: u64) -> u64 {
+        height / self.epoch_length.max(1)
+    }
+
+    /// Returns `true` if `height` is the last block of its epoch.
+    pub fn is_epoch_end(&self, height: u64) -> bool {
+        (height + 1).is_multiple_of(self.epoch_length.max(1))
+    }
+}
+
+/// Stake waiting out the unbonding delay.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Unbonding {
+    /// Amount being unbonded.
+    pub amount: u64,
+    /// First epoch in which the amount is returned to the balance.
+    pub release_epoch: u64,
+}
+
+/// Per-account stake record.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Stake {
+    /// Currently bonded stake, including changes made this epoch.
+    pub bonded: u64,
+    /// Stake counted for proposer selection during the current epoch.
+    pub active: u64,
+    /// Unbonded stake not yet returned, oldest first.
+    pub unbonding: Vec<Unbonding>,
+}
+
+impl Stake {
+    /// Returns the total amount still unbonding.
+    pub fn unbonding_total(&self) -> u64 {
+        self.unbonding.iter().map(|u| u.amount).sum()
+    }
+
+    /// Burns `percent` (capped at 100) of the bonded, active and unbonding
+    /// stake, returning the amount burned.
+    pub fn slash(&mut self, percent: u64) -> u64 {
+        let cut = |amount: u64| (u128::from(amount) * u128::from(percent.min(100)) / 100) as u64;
+        let mut burned = cut(self.bonded);
+        self.bonded -= burned;
+        self.active -= cut(self.active);
+        for entry in &mut self.unbonding {
+            let burn = cut(entry.amount);
+            entry.amount -= burn;
+            burned += burn;
+        }
+        self.unbonding.retain(|entry| entry.amount > 0);
+        burned
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn epochs_partition_heights() {
+        let cfg = StakingConfig {
+            epoch_length: 10,
+            ..StakingConfig::default()
+        };
+
+        assert_eq!(cfg.epoch(0), 0);
+        assert_eq!(cfg.epoch(9), 0);
+        assert_eq!(cfg.epoch(10), 1);
+        assert!(cfg.is_epoch_end(9));
+        assert!(!cfg.is_epoch_end(10));
+    }
+
+    #[test]
+    fn slashing_burns_bonded_and_unbonding_stake() {
+        let mut stake = Stake {
+            bonded: 1_000,
+            active: 1_200,
+            unbonding: vec![
+                Unbonding {
+                    amount: 200,
+                    release_epoch: 3,
+                },
+                Unbonding {
+                    amount: 5,
+                    release_epoch: 4,
+                },
+            ],
+        };
+
+        assert_eq!(stake.slash(10), 120);
+        assert_eq!(stake.bonded, 900);
+        assert_eq!(stake.active, 1_080);
+        assert_eq!(stake.unbonding_total(), 185);
+    }
+}
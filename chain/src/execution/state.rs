@@ -0,0 +1,263 @@
+//! Chain state: accounts, registered artefacts, governance accounts, and
+//! the proposer stake ledger.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, RwLock};
+
+use serde::{Deserialize, Serialize};
+
+use crate::types::{AccountId, Aid, ArtefactMetadata, BlockHash};
+
+use super::params::ChainParams;
+use super::stake::{Stake, StakingConfig};
+
+/// Per-account state record.
+///
+/// Accounts are created implicitly: an account that has never been touched
+/// reads as [`Account::default`] (zero balance, nonce 0).
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Account {
+    /// Fungible balance used to pay fees and transfers.
+    pub balance: u64,
+    /// Nonce expected on the next transaction signed by this account.
+    ///
+    /// This equals the number of transactions from the account that have
+    /// been executed so far.
+    pub nonce: u64,
+}
+
+/// In-memory chain state at a given block.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ChainState {
+    accounts: HashMap<AccountId, Account>,
+    artefacts: HashMap<Aid, ArtefactMetadata>,
+    /// Accounts allowed to revoke any artefact, fixed by the genesis spec.
+    governance: HashSet<AccountId>,
+    stakes: HashMap<AccountId, Stake>,
+    /// Blocks whose proposer has been slashed by a `TxSlash`.
+    slashed: HashSet<BlockHash>,
+    /// Staking parameters, fixed by the genesis spec.
+    staking: StakingConfig,
+    /// Network parameters, fixed by the genesis spec.
+    params: ChainParams,
+}
+
+impl ChainState {
+    /// Creates an empty state (no accounts, no artefacts).
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the account record for `id`, or the default if unknown.
+    pub fn account(&self, id: &AccountId) -> Account {
+        self.accounts.get(id).copied().unwrap_or_default()
+    }
+
+    /// Returns the metadata of a registered artefact, if any.
+    pub fn artefact(&self, aid: &Aid) -> Option<&ArtefactMetadata> {
+        self.artefacts.get(aid)
+    }
+
+    /// Returns `true` if `id` is a governance account.
+    pub fn is_governance(&self, id: &AccountId) -> bool {
+        self.governance.contains(id)
+    }
+
+    /// Iterates over all accounts that have been touched so far.
+    pub fn accounts(&self) -> impl Iterator<Item = (&AccountId, &Account)> {
+        self.accounts.iter()
+    }
+
+    /// Iterates over all registered artefacts.
+    pub fn artefacts(&self) -> impl Iterator<Item = (&Aid, &ArtefactMetadata)> {
+        self.artefacts.iter()
+    }
+
+    /// Returns the stake record of `id`, if it has ever staked.
+    pub fn stake(&self, id: &AccountId) -> Option<&Stake> {
+        self.stakes.get(id)
+    }
+
+    /// Iterates over all stake records.
+    pub fn stakes(&self) -> impl Iterator<Item = (&AccountId, &Stake)> {
+        self.stakes.iter()
+    }
+
+    /// Returns `true` if the proposer of `block` has been slashed for it.
+    pub fn is_slashed(&self, block: &BlockHash) -> bool {
+        self.slashed.contains(block)
+    }
+
+    /// Iterates over all slashed blocks.
+    pub fn slashed(&self) -> impl Iterator<Item = &BlockHash> {
+        self.slashed.iter()
+    }
+
+    /// Returns the staking parameters.
+    pub fn staking(&self) -> &StakingConfig {
+        &self.staking
+    }
+
+    /// Returns the network parameters.
+    pub fn params(&self) -> &ChainParams {
+        &self.params
+    }
+
+    /// Returns the non-zero active stakes, in ascending [`AccountId`]
+    /// order.
+    pub fn active_stakes(&self) -> Vec<(AccountId, u64)> {
+        let mut active: Vec<_> = self
+            .stakes
+            .iter()
+            .filter(|(_, stake)| stake.active > 0)
+            .map(|(id, stake)| (*id, stake.active))
+            .collect();
+        active.sort_by_key(|(id, _)| id.0.0);
+        active
+    }
+
+    /// Returns a mutable account record, creating it if missing.
+    pub(crate) fn account_mut(&mut self, id: AccountId) -> &mut Account {
+        self.accounts.entry(id).or_default()
+    }
+
+    /// Returns mutable metadata of a registered artefact, if any.
+    pub(crate) fn artefact_mut(&mut self, aid: &Aid) -> Option<&mut ArtefactMetadata> {
+        self.artefacts.get_mut(aid)
+    }
+
+    /// Adds a governance account.
+    pub(crate) fn add_governance(&mut self, id: AccountId) {
+        self.governance.insert(id);
+    }
+
+    /// Inserts artefact metadata, keyed by its `aid`.
+    pub(crate) fn insert_artefact(&mut self, meta: ArtefactMetadata) {
+        self.artefacts.insert(meta.aid, meta);
+    }
+
+    /// Returns a mutable stake record, creating it if missing.
+    pub(crate) fn stake_mut(&mut self, id: AccountId) -> &mut Stake {
+        self.stakes.entry(id).or_default()
+    }
+
+    /// Records that the proposer of `block` has been slashed.
+    pub(crate) fn record_slash(&mut self, block: BlockHash) {
+        self.slashed.insert(block);
+    }
+
+    /// Sets the staking parameters.
+    pub(crate) fn set_staking(&mut self, staking: StakingConfig) {
+        self.staking = staking;
+    }
+
+    /// Sets the network parameters.
+    pub(crate) fn set_params(&mut self, params: ChainParams) {
+        self.params = params;
+    }
+
+    /// Makes every account's bonded stake its active stake.
+    pub(crate) fn activate_stakes(&mut self) {
+        for stake in self.stakes.values_mut() {
+            stake.active = stake.bonded;
+        }
+    }
+
+    /// Removes unbonding entries due by `epoch`, returning the amount owed
+    /// to each account in ascending [`AccountId`] order. Records left empty
+    /// are dropped.
+    /// Returns the unbonding stake of `id` due for release by `epoch`.
+    pub fn unbonded_due(&self, id: &AccountId, epoch: u64) -> u64 {
+        self.stake(id).map_or(0, |stake| {
+            stake
+                .unbonding
+                .iter()
+                .filter(|u| u.release_epoch <= epoch)
+                .map(|u| u.amount)
+                .sum()
+        })
+    }
+
+    pub(crate) fn release_unbonded(&mut self, epoch: u64) -> Vec<(AccountId, u64)> {
+        let mut released = Vec::new();
+        for (id, stake) in &mut self.stakes {
+            let due: u64 = stake
+                .unbonding
+                .iter()
+                .filter(|u| u.release_epoch <= epoch)
+                .map(|u| u.amount)
+                .sum();
+            if due > 0 {
+                stake.unbonding.retain(|u| u.release_epoch > epoch);
+                released.push((*id, due));
+            }
+        }
+        self.stakes.retain(|_, stake| *stake != Stake::default());
+        released.sort_by_key(|(id, _)| id.0.0);
+        released
+    }
+}
+
+/// Shared read handle to the state after the canonical tip.
+///
+/// The consensus engine refreshes it whenever the tip changes (see
+/// [`ConsensusEngine::with_state_handle`]), so validators can check blocks
+/// against chain state without holding the engine. Clones share the same
+/// snapshot.
+///
+/// [`ConsensusEngine::with_state_handle`]: crate::consensus::ConsensusEngine::with_state_handle
+#[derive(Clone, Debug, Default)]
+pub struct StateHandle(Arc<RwLock<Option<TipState>>>);
+
+/// Tip hash and the state after it.
+type TipState = (BlockHash, Arc<ChainState>);
+
+impl StateHandle {
+    /// Creates a handle with no state yet.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replaces the snapshot with `state`, the state after block `tip`.
+    pub fn update(&self, tip: BlockHash, state: &ChainState) {
+        *self.0.write().expect("state handle lock poisoned") = Some((tip, Arc::new(state.clone())));
+    }
+
+    /// Returns the tip hash and the state after it, if any has been
+    /// published.
+    pub fn snapshot(&self) -> Option<TipState> {
+        self.0.read().expect("state handle lock poisoned").clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{HASH_LEN, Hash256};
+
+    #[test]
+    fn unknown_account_reads_as_default() {
+        let state = ChainState::new();
+        let id = AccountId(Hash256([1u8; HASH_LEN]));
+
+        assert_eq!(state.account(&id), Account::default());
+        assert_eq!(state.accounts().count(), 0);
+    }
+
+    #[test]
+    fn account_mut_creates_and_updates_record() {
+        let mut state = ChainState::new();
+        let id = AccountId(Hash256([1u8; HASH_LEN]));
+
+        state.account_mut(id).balance = 10;
+        state.account_mut(id).nonce += 1;
+
+        assert_eq!(
+            state.account(&id),
+            Account {
+                balance: 10,
+                nonce: 1
+            }
+        );
+    }
+}
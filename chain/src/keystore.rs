@@ -0,0 +1,465 @@
+//! Encrypted on-disk storage for ML-DSA key pairs.
+//!
+//! Each key pair lives in its own JSON key file. The public key and the
+//! account it controls are stored in the clear; the secret key is sealed
+//! with ChaCha20-Poly1305 under a key derived from a password with
+//! Argon2id. The public key is bound to the ciphertext as associated data,
+//! so a key file whose halves have been swapped fails to decrypt.
+//!
+//! A [`Keystore`] manages a directory of key files named after their
+//! account (`<account hex>.json`). A node's own identity is a single key
+//! file at [`ChainConfig::node_key_path`](crate::config::ChainConfig::node_key_path),
+//! loaded or created with [`load_or_generate`] using the password in
+//! [`NODE_KEY_PASSWORD_ENV`] (see
+//! [`ChainConfig::node_keypair`](crate::config::ChainConfig::node_keypair)).
+
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use argon2::{Algorithm, Argon2, Params, Version};
+use chacha20poly1305::aead::{Aead, AeadCore, OsRng, Payload, rand_core::RngCore};
+use chacha20poly1305::{ChaCha20Poly1305, Key, KeyInit, Nonce};
+use serde::{Deserialize, Serialize};
+use zeroize::Zeroizing;
+
+use crate::types::{AccountId, Hash256, Keypair, SignatureError};
+
+/// Environment variable holding the password for the node key file.
+pub const NODE_KEY_PASSWORD_ENV: &str = "CHAIN_NODE_KEY_PASSWORD";
+
+/// Version of the key file format written by this module.
+const KEY_FILE_VERSION: u32 = 1;
+
+const KDF_NAME: &str = "argon2id";
+const CIPHER_NAME: &str = "chacha20poly1305";
+const SALT_LEN: usize = 16;
+
+/// Errors produced by the keystore.
+#[derive(Debug)]
+pub enum KeystoreError {
+    /// Underlying filesystem error.
+    Io(std::io::Error),
+    /// The key file is not valid JSON or is missing fields.
+    Malformed(String),
+    /// The key file was written by an unknown format version.
+    UnsupportedVersion(u32),
+    /// The key file uses a KDF or cipher this module does not know.
+    UnsupportedScheme(String),
+    /// The KDF parameters are out of range.
+    Kdf(String),
+    /// The password is wrong, or the key file has been tampered with.
+    WrongPassword,
+    /// The decrypted keys are not a valid ML-DSA-65 key pair.
+    Key(SignatureError),
+    /// The stored account does not match the stored public key.
+    AccountMismatch { stored: AccountId, key: AccountId },
+    /// No key file exists for the account.
+    NotFound(AccountId),
+    /// The password environment variable is not set.
+    MissingPassword(&'static str),
+}
+
+impl From<std::io::Error> for KeystoreError {
+    fn from(e: std::io::Error) -> Self {
+        KeystoreError::Io(e)
+    }
+}
+
+impl From<SignatureError> for KeystoreError {
+    fn from(e: SignatureError) -> Self {
+        KeystoreError::Key(e)
+    }
+}
+
+impl fmt::Display for KeystoreError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KeystoreError::Io(e) => write!(f, "io: {e}"),
+            KeystoreError::Malformed(msg) => write!(f, "malformed key file: {msg}"),
+            KeystoreError::UnsupportedVersion(v) => {
+                write!(f, "unsupported key file version {v}")
+            }
+            KeystoreError::UnsupportedScheme(name) => {
+                write!(f, "unsupported key file scheme {name:?}")
+            }
+            KeystoreError::Kdf(msg) => write!(f, "key derivation failed: {msg}"),
+            KeystoreError::WrongPassword => {
+                write!(f, "wrong password or corrupted key file")
+            }
+            KeystoreError::Key(e) => write!(f, "invalid key pair: {e}"),
+            KeystoreError::AccountMismatch { stored, key } => write!(
+                f,
+                "key file is labelled {} but holds the key for {}",
+                hex::encode(stored.as_hash().as_bytes()),
+                hex::encode(key.as_hash().as_bytes())
+            ),
+            KeystoreError::NotFound(account) => write!(
+                f,
+                "no key file for account {}",
+                hex::encode(account.as_hash().as_bytes())
+            ),
+            KeystoreError::MissingPassword(var) => {
+                write!(f, "key file password not set; export {var}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for KeystoreError {}
+
+/// Argon2id cost parameters used when writing key files.
+///
+/// They are stored in each key file, so changing them only affects files
+/// written afterwards.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct KdfParams {
+    /// Memory cost, in KiB.
+    pub m_cost_kib: u32,
+    /// Number of passes.
+    pub t_cost: u32,
+    /// Degree of parallelism.
+    pub p_cost: u32,
+}
+
+impl Default for KdfParams {
+    /// The OWASP-recommended Argon2id baseline: 19 MiB, two passes.
+    fn default() -> Self {
+        Self {
+            m_cost_kib: 19 * 1024,
+            t_cost: 2,
+            p_cost: 1,
+        }
+    }
+}
+
+impl KdfParams {
+    /// Derives a 32-byte cipher key from `password` and `salt`.
+    fn derive(&self, password: &[u8], salt: &[u8]) -> Result<Zeroizing<[u8; 32]>, KeystoreError> {
+        let params = Params::new(self.m_cost_kib, self.t_cost, self.p_cost, Some(32))
+            .map_err(|e| KeystoreError::Kdf(e.to_string()))?;
+        let mut key = Zeroizing::new([0u8; 32]);
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+            .hash_password_into(password, salt, key.as_mut())
+            .map_err(|e| KeystoreError::Kdf(e.to_string()))?;
+        Ok(key)
+    }
+}
+
+/// On-disk JSON layout of a key file.
+#[derive(Serialize, Deserialize)]
+struct KeyFile {
+    version: u32,
+    account: String,
+    public_key: String,
+    kdf: KdfSection,
+    cipher: CipherSection,
+}
+
+#[derive(Serialize, Deserialize)]
+struct KdfSection {
+    name: String,
+    #[serde(flatten)]
+    params: KdfParams,
+    salt: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct CipherSection {
+    name: String,
+    nonce: String,
+    ciphertext: String,
+}
+
+fn decode_hex(field: &str, value: &str) -> Result<Vec<u8>, KeystoreError> {
+    hex::decode(value).map_err(|e| KeystoreError::Malformed(format!("{field}: {e}")))
+}
+
+/// Seals `keypair` under `password` and returns the key file bytes.
+fn seal(keypair: &Keypair, password: &[u8], params: &KdfParams) -> Result<Vec<u8>, KeystoreError> {
+    let mut salt = [0u8; SALT_LEN];
+    OsRng.fill_bytes(&mut salt);
+    let key = params.derive(password, &salt)?;
+
+    let public_key = keypair.public_key();
+    let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let ciphertext = ChaCha20Poly1305::new(Key::from_slice(key.as_ref()))
+        .encrypt(
+            &nonce,
+            Payload {
+                msg: keypair.secret_key_bytes(),
+                aad: public_key.as_bytes(),
+            },
+        )
+        .expect("ChaCha20-Poly1305 encryption of an in-memory buffer cannot fail");
+
+    let file = KeyFile {
+        version: KEY_FILE_VERSION,
+        account: hex::encode(keypair.account_id().as_hash().as_bytes()),
+        public_key: hex::encode(public_key.as_bytes()),
+        kdf: KdfSection {
+            name: KDF_NAME.to_string(),
+            params: *params,
+            salt: hex::encode(salt),
+        },
+        cipher: CipherSection {
+            name: CIPHER_NAME.to_string(),
+            nonce: hex::encode(nonce),
+            ciphertext: hex::encode(ciphertext),
+        },
+    };
+    Ok(serde_json::to_vec_pretty(&file).expect("KeyFile should always serialize to JSON"))
+}
+
+/// Opens key file `bytes` with `password`.
+fn open(bytes: &[u8], password: &[u8]) -> Result<Keypair, KeystoreError> {
+    let file: KeyFile =
+        serde_json::from_slice(bytes).map_err(|e| KeystoreError::Malformed(e.to_string()))?;
+    if file.version != KEY_FILE_VERSION {
+        return Err(KeystoreError::UnsupportedVersion(file.version));
+    }
+    if file.kdf.name != KDF_NAME {
+        return Err(KeystoreError::UnsupportedScheme(file.kdf.name));
+    }
+    if file.cipher.name != CIPHER_NAME {
+        return Err(KeystoreError::UnsupportedScheme(file.cipher.name));
+    }
+
+    let public_key = decode_hex("public_key", &file.public_key)?;
+    let salt = decode_hex("kdf.salt", &file.kdf.salt)?;
+    let nonce = decode_hex("cipher.nonce", &file.cipher.nonce)?;
+    let ciphertext = decode_hex("cipher.ciphertext", &file.cipher.ciphertext)?;
+    if nonce.len() != 12 {
+        return Err(KeystoreError::Malformed(
+            "cipher.nonce: expected 12 bytes".to_string(),
+        ));
+    }
+
+    let key = file.kdf.params.derive(password, &salt)?;
+    let secret = Zeroizing::new(
+        ChaCha20Poly1305::new(Key::from_slice(key.as_ref()))
+            .decrypt(
+                Nonce::from_slice(&nonce),
+                Payload {
+                    msg: &ciphertext,
+                    aad: &public_key,
+                },
+            )
+            .map_err(|_| KeystoreError::WrongPassword)?,
+    );
+
+    let keypair = Keypair::from_bytes(&public_key, &secret)?;
+    let stored = parse_account(&file.account)?;
+    if stored != keypair.account_id() {
+        return Err(KeystoreError::AccountMismatch {
+            stored,
+            key: keypair.account_id(),
+        });
+    }
+    Ok(keypair)
+}
+
+fn parse_account(value: &str) -> Result<AccountId, KeystoreError> {
+    let bytes: [u8; 32] = decode_hex("account", value)?
+        .try_into()
+        .map_err(|_| KeystoreError::Malformed("account: expected 32 bytes".to_string()))?;
+    Ok(AccountId(Hash256(bytes)))
+}
+
+/// Writes `keypair` to the key file at `path`, sealed under `password`.
+///
+/// The file is written to a temporary path and renamed into place, and is
+/// readable only by its owner on Unix.
+pub fn write_key_file(
+    path: &Path,
+    keypair: &Keypair,
+    password: &[u8],
+    params: &KdfParams,
+) -> Result<(), KeystoreError> {
+    let bytes = seal(keypair, password, params)?;
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)?;
+    }
+
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    std::io::Write::write_all(&mut options.open(&tmp)?, &bytes)?;
+    fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+/// Reads the key file at `path` and decrypts it with `password`.
+pub fn read_key_file(path: &Path, password: &[u8]) -> Result<Keypair, KeystoreError> {
+    open(&fs::read(path)?, password)
+}
+
+/// Loads the key pair at `path`, or generates and writes a new one if the
+/// file does not exist.
+///
+/// Returns the key pair and whether it was newly generated.
+pub fn load_or_generate(path: &Path, password: &[u8]) -> Result<(Keypair, bool), KeystoreError> {
+    match fs::read(path) {
+        Ok(bytes) => Ok((open(&bytes, password)?, false)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            let keypair = Keypair::generate();
+            write_key_file(path, &keypair, password, &KdfParams::default())?;
+            Ok((keypair, true))
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Reads the node key file password from [`NODE_KEY_PASSWORD_ENV`].
+pub fn node_key_password() -> Result<Zeroizing<String>, KeystoreError> {
+    std::env::var(NODE_KEY_PASSWORD_ENV)
+        .map(Zeroizing::new)
+        .map_err(|_| KeystoreError::MissingPassword(NODE_KEY_PASSWORD_ENV))
+}
+
+/// A directory of encrypted key files, one per account.
+#[derive(Clone, Debug)]
+pub struct Keystore {
+    dir: PathBuf,
+    kdf: KdfParams,
+}
+
+impl Keystore {
+    /// Opens the keystore at `dir`, creating the directory if needed.
+    pub fn open(dir: impl Into<PathBuf>) -> Result<Self, KeystoreError> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+        Ok(Self {
+            dir,
+            kdf: KdfParams::default(),
+        })
+    }
+
+    /// Sets the Argon2id parameters used for newly written key files.
+    pub fn with_kdf_params(mut self, kdf: KdfParams) -> Self {
+        self.kdf = kdf;
+        self
+    }
+
+    /// Returns the directory holding the key files.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Returns the path of `account`'s key file.
+    pub fn path_of(&self, account: &AccountId) -> PathBuf {
+        self.dir.join(format!(
+            "{}.json",
+            hex::encode(account.as_hash().as_bytes())
+        ))
+    }
+
+    /// Generates a key pair and stores it under `password`.
+    pub fn generate(&self, password: &[u8]) -> Result<Keypair, KeystoreError> {
+        let keypair = Keypair::generate();
+        self.store(&keypair, password)?;
+        Ok(keypair)
+    }
+
+    /// Stores `keypair` under `password`, replacing any existing key file
+    /// for its account.
+    pub fn store(&self, keypair: &Keypair, password: &[u8]) -> Result<PathBuf, KeystoreError> {
+        let path = self.path_of(&keypair.account_id());
+        write_key_file(&path, keypair, password, &self.kdf)?;
+        Ok(path)
+    }
+
+    /// Loads and decrypts `account`'s key pair.
+    pub fn load(&self, account: &AccountId, password: &[u8]) -> Result<Keypair, KeystoreError> {
+        match read_key_file(&self.path_of(account), password) {
+            Err(KeystoreError::Io(e)) if e.kind() == std::io::ErrorKind::NotFound => {
+                Err(KeystoreError::NotFound(*account))
+            }
+            result => result,
+        }
+    }
+
+    /// Lists the accounts with a key file in the keystore, in sorted order.
+    pub fn accounts(&self) -> Result<Vec<AccountId>, KeystoreError> {
+        let mut accounts = Vec::new();
+        for entry in fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|ext| ext == "json")
+                && let Some(account) = path
+                    .file_stem()
+                    .and_then(|stem| stem.to_str())
+                    .and_then(|stem| parse_account(stem).ok())
+            {
+                accounts.push(account);
+            }
+        }
+        accounts.sort_by_key(|account| *account.as_hash().as_bytes());
+        Ok(accounts)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    /// Cheap parameters so the tests do not spend seconds in Argon2.
+    const TEST_KDF: KdfParams = KdfParams {
+        m_cost_kib: 64,
+        t_cost: 1,
+        p_cost: 1,
+    };
+
+    #[test]
+    fn keystore_roundtrips_keys_and_rejects_wrong_passwords() {
+        let dir = TempDir::new().unwrap();
+        let keystore = Keystore::open(dir.path().join("keys"))
+            .unwrap()
+            .with_kdf_params(TEST_KDF);
+
+        let keypair = keystore.generate(b"hunter2").unwrap();
+        let account = keypair.account_id();
+        assert_eq!(keystore.accounts().unwrap(), vec![account]);
+
+        let loaded = keystore.load(&account, b"hunter2").unwrap();
+        assert_eq!(
+            loaded.public_key().as_bytes(),
+            keypair.public_key().as_bytes()
+        );
+        assert_eq!(loaded.secret_key_bytes(), keypair.secret_key_bytes());
+
+        assert!(matches!(
+            keystore.load(&account, b"hunter3"),
+            Err(KeystoreError::WrongPassword)
+        ));
+        let missing = AccountId(Hash256([9; 32]));
+        assert!(matches!(
+            keystore.load(&missing, b"hunter2"),
+            Err(KeystoreError::NotFound(a)) if a == missing
+        ));
+
+        // The secret key never appears in the clear.
+        let file = fs::read_to_string(keystore.path_of(&account)).unwrap();
+        assert!(!file.contains(&hex::encode(keypair.secret_key_bytes())));
+    }
+
+    #[test]
+    fn swapping_the_public_key_breaks_decryption() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("node.json");
+        let keypair = Keypair::generate();
+        write_key_file(&path, &keypair, b"pw", &TEST_KDF).unwrap();
+
+        let mut file: KeyFile = serde_json::from_slice(&fs::read(&path).unwrap()).unwrap();
+        file.public_key = hex::encode(Keypair::generate().public_key().as_bytes());
+        fs::write(&path, serde_json::to_vec(&file).unwrap()).unwrap();
+
+        assert!(matches!(
+            read_key_file(&path, b"pw"),
+            Err(KeystoreError::WrongPassword)
+        ));
+    }
+}
@@ -4,67 +4,171 @@
 //! consensus mechanism that "bakes in" ML authenticity checks:
 //!
 //! - strongly-typed domain types (`types`),
+//! - content-addressed storage of artefact bytes (`artefact_store`),
 //! - a modular consensus engine (`consensus`),
-//! - block validity predicates (`validation`),
+//! - block and transaction validity predicates (`validation`),
+//! - a transaction execution layer and chain state (`execution`),
+//! - a fee-priority transaction mempool (`mempool`),
+//! - encrypted on-disk key storage (`keystore`),
 //! - storage backends (`storage`),
 //! - ML verification clients (`ml_client`),
+//! - a shared outbound HTTP transport stack and inbound listeners
+//!   (`transport`),
+//! - the peer-to-peer gossip, block-fetch and sync protocol (`network`),
+//! - header-first light client verification (`light`),
 //! - Prometheus-based metrics (`metrics`),
+//! - declarative scenarios and an in-process multi-node simulator (`sim`),
+//! - cooperative shutdown of node tasks (`shutdown`),
 //! - and a top-level node configuration (`config`).
 //!
 //! Higher-level binaries can compose these pieces to build validator
 //! nodes, simulators, and experiment harnesses.
 
+pub mod artefact_store;
 pub mod config;
 pub mod consensus;
+pub mod execution;
+pub mod keystore;
+pub mod light;
+pub mod mempool;
 pub mod metrics;
 pub mod ml_client;
+pub mod network;
+pub mod shutdown;
+pub mod sim;
 pub mod storage;
+pub mod transport;
 pub mod types;
 pub mod validation;
 
+// Re-export the artefact blob store.
+pub use artefact_store::{
+    ArtefactStoreConfig, ArtefactStoreError, BlobReader, BlobUpload, FsArtefactStore, StoredBlob,
+};
+
 // Re-export top-level configuration types.
-pub use config::{ChainConfig, MetricsConfig, MlClientConfig};
+pub use config::{
+    ChainConfig, ConfigError, GrpcClientConfig, GrpcTlsConfig, LocalModelConfig, MetricsConfig,
+    MlClientConfig, MlProtocol,
+};
 
 // Re-export "core" consensus types and traits.
 pub use consensus::{
-    AcceptAllValidator, BlockStore, BlockValidator, CombinedValidator, ConsensusConfig,
-    ConsensusEngine, ConsensusError, ForkChoice, LongestChainForkChoice, Proposer, TxPool,
-    ValidationError,
+    AcceptAllValidator, AsyncBlockValidator, BlockIter, BlockStore, BlockValidator,
+    BoxedBlockValidator, ChainEvent, ChainReader, Checkpoint, CombinedValidator, CommonAncestor,
+    ConsensusConfig, ConsensusEngine, ConsensusError, Divergence, ElectionKind, Epoch, ForkChoice,
+    ForkGc, GcConfig, GcReport, GenesisAccount, GenesisArtefact, GenesisConfig, InvariantChecker,
+    InvariantConfig, InvariantViolation, LeaderElection, LongestChainForkChoice, OrphanBuffer,
+    PeekedTxs, PendingBlock, ProducerWatchdog, Proposer, ProposerSelector, ProposerStats,
+    PruneReport, Pruner, PruningConfig, QuorumCertificate, QuorumForkChoice, ReadStore,
+    Registration, RejectionKind, ReorgEvent, ReplayMismatch, ReplayReport, Replayer,
+    RoundRobinSelector, StakeTable, StakeWeightedSelector, TxIndex, TxLocation, TxPool,
+    TxValidator, ValidationContext, ValidationError, ValidatorPipeline, ValidatorReport,
+    ValidatorSet, ValidatorSetConfig, Vote, VoteAggregator, VoteError, VrfKeypair, VrfPublicKey,
+    VrfSelector, WatchdogConfig, WatchdogEvent, common_ancestor,
+};
+
+// Re-export execution layer and chain state.
+pub use execution::{
+    Account, AccountSummary, ChainParams, ChainState, EMPTY_STATE_ROOT, ExecutionError, MlStatsLog,
+    MlVerdictStats, Receipt, ReceiptEvent, Stake, StakingConfig, StateHandle, StateReader,
+    Unbonding, apply_block, apply_block_with_receipts, receipts_root,
+};
+
+// Re-export the encrypted keystore.
+pub use keystore::{KdfParams, Keystore, KeystoreError};
+
+// Re-export the light client.
+pub use light::{ArtefactProof, LightClient, LightError, RegistrationProof, Verified};
+
+// Re-export the transaction mempool.
+pub use mempool::{
+    Inclusion, InclusionStats, InclusionTracker, Mempool, MempoolConfig, MempoolError,
 };
 
 // Re-export storage backends.
-pub use storage::{InMemoryBlockStore, RocksDbBlockStore, RocksDbConfig, StorageError};
+#[cfg(feature = "sqlite")]
+pub use storage::SqliteBlockStore;
+pub use storage::{
+    ChainFileError, ChainFileHeader, ChainImportInfo, ColumnFamilyStats, InMemoryBlockStore,
+    PersistentStore, RocksDbBlockStore, RocksDbStats, SharedStore, SnapshotError, SnapshotInfo,
+    StorageBackend, StorageConfig, StorageError, StorageMetricsConfig, export_chain, import_chain,
+};
 
-// Re-export ML verification interfaces and the HTTP client.
-pub use ml_client::HttpMlVerifier;
-pub use validation::{BaseValidity, MlConfig, MlError, MlValidity, MlVerifier};
+// Re-export ML verification interfaces and the HTTP/gRPC clients.
+#[cfg(feature = "local-onnx")]
+pub use ml_client::LocalOnnxVerifier;
+pub use ml_client::{
+    CircuitBreakerConfig, GrpcMlVerifier, HttpMlVerifier, MlClient, MockMlVerifier, MockOutcome,
+    QuorumMlVerifier, QuorumPolicy,
+};
+pub use network::{
+    BlockRequest, BlockResponse, BlockSync, Compression, Direction, FrameStats, GossipHandler,
+    GossipMessage, HeadersRequest, HeadersResponse, InboundGossip, NetworkConfig, NetworkError,
+    NetworkEvent, P2pConfig, P2pHandle, P2pNetwork, PeerCodec, PeerHello, PeerId, RemotePeer,
+    SyncConfig, SyncError, SyncHandle, SyncHeader, SyncPeer, SyncStatus, Topic,
+};
+pub use shutdown::Shutdown;
+pub use sim::{
+    Assertion, AssertionFailure, Comparison, Metric, MlVerifierSpec, NetworkSpec, NodeGroup,
+    NodeRole, PartitionSpec, RunRecorder, RunReport, Scenario, Simulator, Topology, WorkloadSpec,
+};
+pub use transport::{
+    Connection, HttpTransport, Listener, ListenerConfig, ListenerError, ListenerTlsConfig,
+    TransportConfig, TransportError,
+};
+pub use validation::{
+    AsyncMlValidity, AsyncMlVerifier, BaseValidity, DECISION_LOG_TARGET, DecisionLogConfig,
+    LateRejection, MlConfig, MlError, MlOutagePolicy, MlValidity, MlVerifier, ProposerValidity,
+    QuorumVotes, RevalidationConfig, RevalidationQueue, RevalidationSummary, StatefulTxValidity,
+    StatefulValidity, StatelessTxValidity, TxValidityConfig, VerdictBounds, check_fraud_proof,
+};
 
 // Re-export metrics registry and consensus metrics.
-pub use metrics::{ConsensusMetrics, MetricsRegistry, run_prometheus_http_server};
+pub use metrics::{
+    ConsensusMetrics, HistogramBuckets, MetricsMode, MetricsPusher, MetricsRegistry, OtlpConfig,
+    OtlpTracing, PushConfig, PushError, PushTarget, StorageMetrics, collect_storage_metrics,
+    run_prometheus_http_server, serve_prometheus,
+};
 
 // Re-export domain types at the crate root for convenience.
 pub use types::*;
 
 /// Type alias for the default block validator stack used by a "typical" node.
 ///
-/// This composes:
+/// This runs a [`ValidatorPipeline`] of synchronous stages,
+///
+/// - `base`: [`BaseValidity`] for cheap structural checks,
+/// - `proposer`: [`ProposerValidity`] for the slot-leader check
+///   (round-robin, VRF or stake-weighted, see [`LeaderElection`]), and
+/// - `state`: [`StatefulValidity`] for balance, nonce and registry checks
+///   against the tip state,
 ///
-/// - [`BaseValidity`] for cheap structural checks, and
-/// - [`MlValidity<HttpMlVerifier>`] for ML authenticity checks.
-pub type DefaultBlockValidator = CombinedValidator<BaseValidity, MlValidity<HttpMlVerifier>>;
+/// followed by [`AsyncMlValidity<MlClient>`] for ML authenticity checks
+/// over HTTP or gRPC.
+///
+/// Because the ML checks are async, engines using this validator import
+/// blocks through [`ConsensusEngine::import_block_async`].
+pub type DefaultBlockValidator = CombinedValidator<ValidatorPipeline, AsyncMlValidity<MlClient>>;
 
 /// Type alias for the default fork-choice rule.
 pub type DefaultForkChoice = LongestChainForkChoice;
 
-/// Type alias for the default block store backend.
-pub type DefaultBlockStore = RocksDbBlockStore;
+/// Type alias for the default block store backend, shared so that
+/// [`DefaultChainReader`]s can read it while the engine imports.
+pub type DefaultBlockStore = SharedStore<PersistentStore>;
 
 /// Type alias for the default consensus engine stack.
 ///
 /// This uses:
 ///
-/// - [`DefaultBlockStore`] (RocksDB),
-/// - [`DefaultBlockValidator`] (base + ML),
+/// - [`DefaultBlockStore`] (RocksDB, or SQLite with the `sqlite` feature,
+///   behind a [`SharedStore`]),
+/// - [`DefaultBlockValidator`] (validator pipeline + ML),
 /// - [`DefaultForkChoice`] (longest-chain-by-height).
 pub type DefaultConsensusEngine =
     ConsensusEngine<DefaultBlockStore, DefaultBlockValidator, DefaultForkChoice>;
+
+/// Read handle to a [`DefaultConsensusEngine`]'s chain, see
+/// [`ConsensusEngine::reader`].
+pub type DefaultChainReader = ChainReader<PersistentStore>;
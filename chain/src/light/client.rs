@@ -0,0 +1,426 @@
+//! Header chain tracking and proof verification.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use crate::consensus::schedule::{ProposerSelector, ValidatorSet};
+use crate::consensus::votes::{QuorumCertificate, quorum_size};
+use crate::types::{BlockHash, Header};
+
+use super::proof::{ArtefactProof, RegistrationProof};
+
+/// Why the light client rejected a header, certificate or proof.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum LightError {
+    /// The block (or a header's parent) has not been verified yet.
+    UnknownBlock(BlockHash),
+    /// A header's height is not one above its parent's.
+    BadHeight { expected: u64, got: u64 },
+    /// A header's proposer was not allowed to propose it.
+    Proposer(String),
+    /// A quorum certificate does not commit its block.
+    Finality(String),
+    /// A Merkle proof does not match the header's root.
+    InvalidProof(&'static str),
+}
+
+impl fmt::Display for LightError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LightError::UnknownBlock(hash) => {
+                write!(f, "unknown block {}", hex::encode(hash.0.as_bytes()))
+            }
+            LightError::BadHeight { expected, got } => {
+                write!(f, "expected height {expected}, got {got}")
+            }
+            LightError::Proposer(msg) => write!(f, "invalid proposer: {msg}"),
+            LightError::Finality(msg) => write!(f, "invalid quorum certificate: {msg}"),
+            LightError::InvalidProof(msg) => write!(f, "invalid proof: {msg}"),
+        }
+    }
+}
+
+impl std::error::Error for LightError {}
+
+/// A proof accepted by the light client.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Verified {
+    /// Block the proof was checked against.
+    pub block_hash: BlockHash,
+    /// Height of `block_hash`.
+    pub height: u64,
+    /// Whether `block_hash` is at or below the last finalized block.
+    pub finalized: bool,
+}
+
+/// Light client following a header chain from a trusted header.
+///
+/// Headers are accepted on any branch that links to a verified header;
+/// the tip is the highest one. Proposers are checked with a
+/// [`ProposerSelector`] (a stake-weighted one needs a
+/// [`StakeTable`](crate::consensus::StakeTable) filled from a trusted
+/// source, otherwise it lets anyone propose), and finality with the
+/// validator set.
+#[derive(Debug)]
+pub struct LightClient<P> {
+    selector: P,
+    validators: ValidatorSet,
+    headers: HashMap<BlockHash, Header>,
+    trusted: BlockHash,
+    tip: BlockHash,
+    finalized: Option<BlockHash>,
+}
+
+impl<P: ProposerSelector> LightClient<P> {
+    /// Creates a client trusting `trusted` (usually the genesis header),
+    /// checking proposers with `selector` and finality against
+    /// `validators`.
+    pub fn new(trusted: Header, selector: P, validators: ValidatorSet) -> Self {
+        let hash = trusted.compute_hash();
+        Self {
+            selector,
+            validators,
+            headers: HashMap::from([(hash, trusted)]),
+            trusted: hash,
+            tip: hash,
+            finalized: None,
+        }
+    }
+
+    /// Returns the verified header `hash`, if any.
+    pub fn header(&self, hash: &BlockHash) -> Option<&Header> {
+        self.headers.get(hash)
+    }
+
+    /// Returns the hash of the highest verified header.
+    pub fn tip(&self) -> BlockHash {
+        self.tip
+    }
+
+    /// Returns the last block shown to be committed by a quorum, if any.
+    pub fn finalized(&self) -> Option<BlockHash> {
+        self.finalized
+    }
+
+    /// Verifies `headers`, in order, and adds them to the chain.
+    ///
+    /// Each header must extend an already verified header by one height
+    /// and come from an eligible proposer. Stops at the first invalid
+    /// header; the ones before it stay verified. Returns the new tip.
+    pub fn import_headers(
+        &mut self,
+        headers: impl IntoIterator<Item = Header>,
+    ) -> Result<BlockHash, LightError> {
+        for header in headers {
+            let parent = self
+                .headers
+                .get(&header.parent)
+                .ok_or(LightError::UnknownBlock(header.parent))?;
+            if header.height != parent.height + 1 {
+                return Err(LightError::BadHeight {
+                    expected: parent.height + 1,
+                    got: header.height,
+                });
+            }
+            if let Some(expected) = self
+                .selector
+                .expected_proposer(header.timestamp)
+                .filter(|expected| *expected != header.proposer)
+            {
+                return Err(LightError::Proposer(format!(
+                    "slot belongs to {}, not {}",
+                    hex::encode(&expected.0.as_bytes()[..8]),
+                    hex::encode(&header.proposer.0.as_bytes()[..8])
+                )));
+            }
+            self.selector
+                .verify_eligibility(&header)
+                .map_err(LightError::Proposer)?;
+
+            let hash = header.compute_hash();
+            if header.height > self.headers[&self.tip].height {
+                self.tip = hash;
+            }
+            self.headers.insert(hash, header);
+        }
+        Ok(self.tip)
+    }
+
+    /// Marks the certificate's block as finalized if more than two thirds
+    /// of the validators voted for it.
+    ///
+    /// Only votes whose signature verifies under the validator's key (see
+    /// [`Vote::verify_signature`](crate::consensus::votes::Vote::verify_signature))
+    /// are counted.
+    pub fn verify_finality(&mut self, qc: &QuorumCertificate) -> Result<(), LightError> {
+        let header = self.known(&qc.block_hash)?;
+        if header.height != qc.height {
+            return Err(LightError::Finality(format!(
+                "certificate height {} does not match header height {}",
+                qc.height, header.height
+            )));
+        }
+        if self.validators.is_empty() {
+            return Err(LightError::Finality("no validator set".to_string()));
+        }
+        let voters: HashSet<_> = qc
+            .votes
+            .iter()
+            .filter(|vote| vote.block_hash == qc.block_hash && vote.height == qc.height)
+            .filter(|vote| self.validators.contains(&vote.validator))
+            .filter(|vote| vote.verify_signature().is_ok())
+            .map(|vote| vote.validator)
+            .collect();
+        let needed = quorum_size(self.validators.len());
+        if voters.len() < needed {
+            return Err(LightError::Finality(format!(
+                "{} of {needed} required validator votes",
+                voters.len()
+            )));
+        }
+
+        let newer = self
+            .finalized
+            .is_none_or(|f| self.headers[&f].height < qc.height);
+        if newer {
+            self.finalized = Some(qc.block_hash);
+        }
+        Ok(())
+    }
+
+    /// Checks that `proof.tx` is included in a verified block.
+    pub fn verify_registration(&self, proof: &RegistrationProof) -> Result<Verified, LightError> {
+        let header = self.known(&proof.block_hash)?;
+        if !proof.proof.verify(proof.leaf(), &header.txs_root) {
+            return Err(LightError::InvalidProof(
+                "registration does not match the block's txs_root",
+            ));
+        }
+        Ok(self.verified(proof.block_hash))
+    }
+
+    /// Checks that `proof.artefact` is part of a verified block's
+    /// post-state.
+    pub fn verify_artefact(&self, proof: &ArtefactProof) -> Result<Verified, LightError> {
+        let header = self.known(&proof.block_hash)?;
+        if !proof.proof.verify(proof.leaf(), &header.state_root) {
+            return Err(LightError::InvalidProof(
+                "artefact entry does not match the block's state_root",
+            ));
+        }
+        Ok(self.verified(proof.block_hash))
+    }
+
+    fn known(&self, hash: &BlockHash) -> Result<&Header, LightError> {
+        self.headers
+            .get(hash)
+            .ok_or(LightError::UnknownBlock(*hash))
+    }
+
+    /// Returns whether `hash` is an ancestor of (or equal to) the
+    /// finalized block, or the trusted header.
+    fn is_final(&self, hash: BlockHash) -> bool {
+        if hash == self.trusted {
+            return true;
+        }
+        let height = self.headers[&hash].height;
+        let mut cursor = self.finalized;
+        while let Some(current) = cursor {
+            if current == hash {
+                return true;
+            }
+            match self.headers.get(&current) {
+                Some(header) if header.height > height => cursor = Some(header.parent),
+                _ => return false,
+            }
+        }
+        false
+    }
+
+    fn verified(&self, block_hash: BlockHash) -> Verified {
+        Verified {
+            block_hash,
+            height: self.headers[&block_hash].height,
+            finalized: self.is_final(block_hash),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::consensus::genesis::GenesisConfig;
+    use crate::consensus::schedule::RoundRobinSelector;
+    use crate::consensus::votes::Vote;
+    use crate::execution::{ChainState, apply_block};
+    use crate::light::proof::{ArtefactProof, RegistrationProof};
+    use crate::types::{
+        AccountId, Aid, Block, EvidenceHash, EvidenceRef, HASH_LEN, Hash256, Keypair, Signature,
+        Transaction, TxRegisterModel, WmProfile,
+    };
+
+    fn account(byte: u8) -> AccountId {
+        AccountId(Hash256([byte; HASH_LEN]))
+    }
+
+    fn register(aid: Aid, nonce: u64) -> Transaction {
+        Transaction::RegisterModel(TxRegisterModel {
+            owner: account(9),
+            aid,
+            chunked: None,
+            evidence: EvidenceRef {
+                scheme_id: "wm-test".to_string(),
+                evidence_hash: EvidenceHash(Hash256([3u8; HASH_LEN])),
+                wm_profile: WmProfile {
+                    tau_input: 0.9,
+                    tau_feat: 0.2,
+                    logit_band_low: -0.05,
+                    logit_band_high: 0.05,
+                },
+            },
+            fee: 0,
+            nonce,
+            signature: Signature(Vec::new()),
+        })
+    }
+
+    /// Builds `len` blocks on `parent`, one registration each, proposed by
+    /// `proposer` one second apart.
+    fn build_chain(
+        parent: &Block,
+        state: &ChainState,
+        proposer: AccountId,
+        len: u8,
+    ) -> Vec<(Block, ChainState)> {
+        let mut chain: Vec<(Block, ChainState)> = Vec::new();
+        for i in 0..len {
+            let (prev, prev_state) = chain
+                .last()
+                .map_or((parent, state), |(block, state)| (block, state));
+            let mut block = Block {
+                header: Header {
+                    parent: prev.compute_hash(),
+                    height: prev.header.height + 1,
+                    timestamp: prev.header.timestamp + 1,
+                    proposer,
+                    txs_root: Hash256([0u8; HASH_LEN]),
+                    state_root: Hash256([0u8; HASH_LEN]),
+                    receipts_root: Hash256([0u8; HASH_LEN]),
+                    pos_proof: None,
+                },
+                txs: vec![register(Aid(Hash256([i + 1; HASH_LEN])), u64::from(i))],
+            };
+            block.header.txs_root = block.compute_txs_root();
+            let post = apply_block(prev_state, &block).expect("block applies");
+            block.header.state_root = post.state_root();
+            chain.push((block, post));
+        }
+        chain
+    }
+
+    fn vote(block: &Block, keys: &Keypair) -> Vote {
+        let mut vote = Vote {
+            block_hash: block.compute_hash(),
+            height: block.header.height,
+            validator: keys.account_id(),
+            validator_key: keys.public_key(),
+            signature: Signature(Vec::new()),
+        };
+        vote.signature = keys.sign(&vote.signing_bytes());
+        vote
+    }
+
+    #[test]
+    fn verifies_registration_and_artefact_state_along_a_finalized_chain() {
+        let genesis = GenesisConfig::default();
+        let genesis_block = genesis.genesis_block();
+        let keys: Vec<_> = (0..3).map(|_| Keypair::generate()).collect();
+        let validators = ValidatorSet::new(keys.iter().map(Keypair::account_id));
+        let mut client = LightClient::new(
+            genesis_block.header.clone(),
+            RoundRobinSelector::new(ValidatorSet::new([account(1)]), 1),
+            validators,
+        );
+        let chain = build_chain(&genesis_block, &genesis.initial_state(), account(1), 3);
+        let tip = client
+            .import_headers(chain.iter().map(|(b, _)| b.header.clone()))
+            .expect("headers verify");
+        assert_eq!(tip, chain[2].0.compute_hash());
+
+        let aid = Aid(Hash256([2u8; HASH_LEN]));
+        let (block, state) = &chain[1];
+        let registration = RegistrationProof::build(block, &aid).expect("block registers aid");
+        let artefact =
+            ArtefactProof::build(block.compute_hash(), state, &aid).expect("aid is registered");
+        let verified = client.verify_registration(&registration).unwrap();
+        assert_eq!((verified.height, verified.finalized), (2, false));
+        assert!(client.verify_artefact(&artefact).is_ok());
+
+        // Two of three votes do not commit; three do, and finality covers
+        // the ancestors.
+        let mut qc = QuorumCertificate {
+            block_hash: chain[2].0.compute_hash(),
+            height: 3,
+            votes: vec![vote(&chain[2].0, &keys[0]), vote(&chain[2].0, &keys[1])],
+        };
+        assert!(matches!(
+            client.verify_finality(&qc),
+            Err(LightError::Finality(_))
+        ));
+
+        // Votes that are unsigned, signed by another key or signed for
+        // another block do not count.
+        let mut unsigned = vote(&chain[2].0, &keys[2]);
+        unsigned.signature = Signature(Vec::new());
+        let mut wrong_key = vote(&chain[2].0, &keys[0]);
+        wrong_key.validator = keys[2].account_id();
+        let mut replayed = vote(&chain[1].0, &keys[2]);
+        replayed.block_hash = chain[2].0.compute_hash();
+        replayed.height = 3;
+        for forged in [unsigned, wrong_key, replayed] {
+            let mut forged_qc = qc.clone();
+            forged_qc.votes.push(forged);
+            assert!(matches!(
+                client.verify_finality(&forged_qc),
+                Err(LightError::Finality(_))
+            ));
+        }
+        assert_eq!(client.finalized(), None);
+
+        qc.votes.push(vote(&chain[2].0, &keys[2]));
+        client.verify_finality(&qc).expect("quorum commits");
+        assert!(client.verify_registration(&registration).unwrap().finalized);
+
+        // Tampered proofs fail.
+        let mut forged = artefact.clone();
+        forged.artefact.owner = account(7);
+        assert!(matches!(
+            client.verify_artefact(&forged),
+            Err(LightError::InvalidProof(_))
+        ));
+    }
+
+    #[test]
+    fn rejects_unlinked_headers_and_wrong_proposers() {
+        let genesis = GenesisConfig::default();
+        let genesis_block = genesis.genesis_block();
+        let mut client = LightClient::new(
+            genesis_block.header.clone(),
+            RoundRobinSelector::new(ValidatorSet::new([account(1)]), 1),
+            ValidatorSet::default(),
+        );
+
+        let by_outsider = build_chain(&genesis_block, &genesis.initial_state(), account(5), 1);
+        assert!(matches!(
+            client.import_headers([by_outsider[0].0.header.clone()]),
+            Err(LightError::Proposer(_))
+        ));
+
+        let chain = build_chain(&genesis_block, &genesis.initial_state(), account(1), 2);
+        assert!(matches!(
+            client.import_headers([chain[1].0.header.clone()]),
+            Err(LightError::UnknownBlock(_))
+        ));
+        assert_eq!(client.tip(), genesis_block.compute_hash());
+    }
+}
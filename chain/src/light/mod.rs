@@ -0,0 +1,32 @@
+//! Header-first light client verification.
+//!
+//! An auditor who wants to know whether a model was registered does not
+//! need a full node: block headers are small, and every header commits to
+//! its transactions (`txs_root`) and to the post-state (`state_root`). A
+//! [`LightClient`] starts from a trusted header (usually the genesis
+//! block's) and then:
+//!
+//! - follows a chain of headers, checking parent links, heights and the
+//!   proposer schedule (slot leader and VRF eligibility proofs), without
+//!   ever downloading a block body,
+//! - marks headers as final once shown a [`QuorumCertificate`] with
+//!   signed votes from more than two thirds of the validator set, and
+//! - checks proofs served by a full node against the verified headers: a
+//!   [`RegistrationProof`] that a `TxRegisterModel` for an `Aid` was
+//!   included in a block, and an [`ArtefactProof`] that the `Aid`'s
+//!   registry entry is part of a block's post-state.
+//!
+//! Everything here is a plain library API with no I/O: how headers and
+//! proofs reach the client (the gateway, a peer, a file) is up to the
+//! caller.
+//!
+//! Like the rest of the chain, vote signatures are carried but not
+//! checked yet; finality counts distinct validators only.
+//!
+//! [`QuorumCertificate`]: crate::consensus::QuorumCertificate
+
+pub mod client;
+pub mod proof;
+
+pub use client::{LightClient, LightError, Verified};
+pub use proof::{ArtefactProof, RegistrationProof};
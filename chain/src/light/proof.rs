@@ -0,0 +1,72 @@
+//! Inclusion proofs a full node serves to light clients.
+
+use serde::{Deserialize, Serialize};
+
+use crate::execution::ChainState;
+use crate::execution::merkle::artefact_leaf;
+use crate::types::{
+    Aid, ArtefactMetadata, Block, BlockHash, Hash256, MerkleProof, Transaction, TxRegisterModel,
+};
+
+/// Proof that a `TxRegisterModel` was included in a block.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RegistrationProof {
+    /// Block containing the registration.
+    pub block_hash: BlockHash,
+    /// The registration transaction.
+    pub tx: TxRegisterModel,
+    /// Path from the transaction's leaf to the header's `txs_root`.
+    pub proof: MerkleProof,
+}
+
+impl RegistrationProof {
+    /// Builds the proof for the registration of `aid` in `block`, or
+    /// `None` if the block does not register it.
+    pub fn build(block: &Block, aid: &Aid) -> Option<Self> {
+        let (index, tx) = block.txs.iter().enumerate().find_map(|(i, tx)| match tx {
+            Transaction::RegisterModel(reg) if reg.aid == *aid => Some((i, reg)),
+            _ => None,
+        })?;
+        Some(Self {
+            block_hash: block.compute_hash(),
+            tx: tx.clone(),
+            proof: block.tx_proof(index)?,
+        })
+    }
+
+    /// Returns the transactions tree leaf the proof starts from.
+    pub fn leaf(&self) -> Hash256 {
+        Transaction::RegisterModel(self.tx.clone())
+            .compute_hash()
+            .merkle_leaf()
+    }
+}
+
+/// Proof that an artefact's registry entry is part of a block's
+/// post-state.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ArtefactProof {
+    /// Block whose `state_root` the entry is proven against.
+    pub block_hash: BlockHash,
+    /// The registry entry.
+    pub artefact: ArtefactMetadata,
+    /// Path from the entry's leaf to the header's `state_root`.
+    pub proof: MerkleProof,
+}
+
+impl ArtefactProof {
+    /// Builds the proof for `aid` in `state`, the post-state of
+    /// `block_hash`, or `None` if `aid` is not registered there.
+    pub fn build(block_hash: BlockHash, state: &ChainState, aid: &Aid) -> Option<Self> {
+        Some(Self {
+            block_hash,
+            artefact: state.artefact(aid)?.clone(),
+            proof: state.artefact_proof(aid)?,
+        })
+    }
+
+    /// Returns the state tree leaf the proof starts from.
+    pub fn leaf(&self) -> Hash256 {
+        artefact_leaf(&self.artefact)
+    }
+}
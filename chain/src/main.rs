@@ -3,97 +3,303 @@
 // Minimal demo node that wires up the chain library:
 //
 // - RocksDB-backed storage
-// - Base + ML validity (with HTTP ML verifier)
+// - Base + proposer + ML validity (with HTTP ML verifier)
 // - Longest-chain fork choice
-// - Prometheus metrics exporter on /metrics
-// - Simple loop that proposes (currently empty) blocks at a fixed interval.
+// - Periodic garbage collection of stale fork branches
+// - Prometheus metrics exporter on /metrics, or pushes to a Pushgateway /
+//   remote-write endpoint in push mode (`metrics.mode = "push"`)
+// - Fee-priority mempool feeding the proposer
+// - Simple loop that proposes blocks at a fixed interval, skipping slots
+//   owned by other validators.
+// - ML decision log (`mlsnitch::decisions` tracing target) on stderr.
+// - Optional OTLP export of consensus and ML spans (`metrics.otlp`).
+// - Graceful shutdown on Ctrl-C: the block being proposed is finished,
+//   background tasks stop, and the block store is flushed before exit.
+//
+// The command line is parsed with clap. Without a subcommand the node is
+// run; `chain init` writes a node key and a single-validator config with a
+// fresh genesis, `chain keygen` writes an encrypted ML-DSA key file,
+// `chain inspect block <hash|height>` prints a stored block as JSON,
+// `chain snapshot export|import <file>` writes or loads a portable block
+// store snapshot, `chain export` / `chain import` stream a range of the
+// canonical chain to or from a file, `chain replay` re-executes the stored
+// chain and reports the first height whose hashes or roots do not match,
+// and `chain sim` runs a simulator scenario. `chain --help` lists the
+// options of each.
+//
+// Configuration comes from `--config <file>` (TOML or YAML) or the
+// `CHAIN_CONFIG` environment variable, falling back to the defaults, with
+// `CHAIN_<SECTION>_<FIELD>` environment variables applied on top.
 
 use std::{
+    path::{Path, PathBuf},
+    str::FromStr,
     sync::Arc,
     time::{Duration, SystemTime, UNIX_EPOCH},
 };
 
+use clap::{Parser, Subcommand, ValueEnum};
+use tracing_subscriber::{EnvFilter, Layer, layer::SubscriberExt, util::SubscriberInitExt};
+
 use chain::{
     // Domain types
     AccountId,
     // Validation stack
+    AsyncMlValidity,
     BaseValidity,
+    // Block lookups for `inspect`
+    BlockHash,
+    // Storage flush on shutdown
+    BlockStore,
     // Top-level config
     ChainConfig,
     CombinedValidator,
     // Consensus engine + fork choice
     ConsensusEngine,
     DefaultForkChoice,
+    // Fork garbage collection
+    ForkGc,
+    // Genesis written by `init`
+    GenesisAccount,
+    GenesisConfig,
     Hash256,
-    HttpMlVerifier,
-    // Metrics
+    // Key generation
+    KdfParams,
+    Keypair,
+    // Proposer scheduling
+    LeaderElection,
+    // Inbound listeners (plain or TLS)
+    Listener,
+    // Transaction mempool
+    Mempool,
+    // Metrics + trace export
+    MetricsMode,
+    MetricsPusher,
     MetricsRegistry,
-    MlConfig,
+    // ML verification client
+    MlClient,
+    MlOutagePolicy,
+    MlStatsLog,
     MlValidity,
+    MockMlVerifier,
+    OtlpTracing,
     // Storage backend
-    RocksDbBlockStore,
-    Transaction,
-    TxPool,
-    run_prometheus_http_server,
+    PersistentStore,
+    // Block producer watchdog
+    ProducerWatchdog,
+    // Slot leader checks
+    ProposerSelector,
+    ProposerValidity,
+    // History pruning
+    Pruner,
+    // Chain re-execution for `replay`
+    Replayer,
+    // Multi-node simulator
+    Scenario,
+    // Storage handle validators can read concurrently
+    SharedStore,
+    // Graceful shutdown
+    Shutdown,
+    Simulator,
+    // Block checks against the tip state
+    StateHandle,
+    StatefulValidity,
+    // Transaction submission checks
+    StatelessTxValidity,
+    // Block check stages
+    ValidatorPipeline,
+    VrfKeypair,
+    WatchdogEvent,
+    // RocksDB gauges
+    collect_storage_metrics,
+    // Chain export files
+    export_chain,
+    import_chain,
+    serve_prometheus,
 };
+// Node key files
+use chain::keystore;
 
 #[tokio::main]
 async fn main() {
-    if let Err(err) = run_node().await {
+    let cli = Cli::parse();
+    if let Err(err) = dispatch(cli).await {
         eprintln!("fatal error: {err}");
         std::process::exit(1);
     }
 }
 
-async fn run_node() -> Result<(), String> {
-    // For now, just use defaults. Later you can load from a file/CLI/env.
-    let cfg = ChainConfig::default();
+/// Runs the subcommand of `cli`, or the node if there is none.
+async fn dispatch(cli: Cli) -> Result<(), String> {
+    let config = cli.config.as_deref();
+    match cli.command.unwrap_or(Command::Run) {
+        Command::Run => run_node(config).await,
+        Command::Init {
+            dir,
+            chain_id,
+            balance,
+            stake,
+            force,
+        } => run_init(&dir, chain_id, balance, stake, force),
+        Command::Keygen { out, force } => {
+            let out = match out {
+                Some(out) => out,
+                None => load_config(config)?
+                    .node_key_path
+                    .map(PathBuf::from)
+                    .ok_or("keygen needs --out when no node_key_path is configured")?,
+            };
+            run_keygen(&out, force)
+        }
+        Command::Inspect { target } => run_inspect(&load_config(config)?, target),
+        Command::Snapshot { action, file } => run_snapshot(&load_config(config)?, action, &file),
+        Command::Export { from, to, output } => {
+            run_export(&load_config(config)?, from, to, &output)
+        }
+        Command::Import { file } => run_import(&load_config(config)?, &file),
+        Command::Replay { ml } => run_replay(&load_config(config)?, ml),
+        Command::Sim { scenario } => run_sim(&scenario),
+    }
+}
+
+/// Loads the configuration from `path`, or the defaults, with environment
+/// overrides applied.
+fn load_config(path: Option<&Path>) -> Result<ChainConfig, String> {
+    ChainConfig::load(path).map_err(|e| e.to_string())
+}
+
+async fn run_node(config_path: Option<&Path>) -> Result<(), String> {
+    let cfg = load_config(config_path)?;
+
+    // General node output goes through eprintln!; on stderr, tracing only
+    // carries the ML decision log, so it is filtered by `decision_log`
+    // alone. Spans go to the OTLP exporter, if enabled, with its own filter.
+    let otlp = OtlpTracing::init(&cfg.metrics.otlp, "mlsnitch-chain")
+        .map_err(|e| format!("failed to start OTLP exporter: {e}"))?;
+    tracing_subscriber::registry()
+        .with(
+            tracing_subscriber::fmt::layer()
+                .with_writer(std::io::stderr)
+                .with_filter(EnvFilter::new(cfg.decision_log.filter_directive())),
+        )
+        .with(otlp.as_ref().map(OtlpTracing::layer))
+        .init();
+
+    if let Some(path) = config_path {
+        eprintln!("loaded configuration from {}", path.display());
+    }
+
+    // Every background task and the proposal loop stop on Ctrl-C.
+    let shutdown = Shutdown::on_ctrl_c();
 
     // ---------------------------
     // Metrics registry + exporter
     // ---------------------------
 
     let metrics = Arc::new(
-        MetricsRegistry::new()
+        MetricsRegistry::from_config(&cfg.metrics)
             .map_err(|e| format!("failed to initialise metrics registry: {e}"))?,
     );
 
-    if cfg.metrics.enabled {
-        let metrics_clone = metrics.clone();
-        let addr = cfg.metrics.listen_addr;
-        tokio::spawn(async move {
-            if let Err(e) = run_prometheus_http_server(metrics_clone, addr).await {
-                eprintln!("metrics HTTP server error: {e}");
-            }
-        });
-        eprintln!("metrics exporter listening on http://{}/metrics", addr);
+    if cfg.metrics.enabled && cfg.metrics.mode == MetricsMode::Pull {
+        let listeners = Listener::bind_all(&cfg.metrics.listeners)
+            .await
+            .map_err(|e| format!("failed to start metrics exporter: {e}"))?;
+        for listener in listeners {
+            eprintln!(
+                "metrics exporter listening on {}://{}/metrics",
+                listener.scheme(),
+                listener.local_addr()
+            );
+            let metrics_clone = metrics.clone();
+            let shutdown = shutdown.clone();
+            tokio::spawn(async move {
+                if let Err(e) = serve_prometheus(metrics_clone, listener, shutdown).await {
+                    eprintln!("metrics HTTP server error: {e}");
+                }
+            });
+        }
     }
 
+    // In push mode, the last push happens after the store is flushed, so
+    // it includes every imported block.
+    let pusher = if cfg.metrics.enabled && cfg.metrics.mode == MetricsMode::Push {
+        let pusher = MetricsPusher::new(metrics.clone(), &cfg.metrics.push, "mlsnitch-chain")
+            .map_err(|e| format!("failed to start metrics pusher: {e}"))?;
+        eprintln!(
+            "pushing metrics to {} every {}s",
+            cfg.metrics.push.url, cfg.metrics.push.interval_secs
+        );
+        tokio::spawn(pusher.clone().run(shutdown.clone()));
+        Some(pusher)
+    } else {
+        None
+    };
+
     // ---------------------------
-    // Storage backend (RocksDB)
+    // Storage backend (RocksDB or SQLite)
     // ---------------------------
 
-    let store = RocksDbBlockStore::open(&cfg.storage).map_err(|e| {
-        format!(
-            "failed to open RocksDB store at {}: {e:?}",
-            cfg.storage.path
-        )
-    })?;
+    // Shared, because validators read the store from several threads.
+    let store = PersistentStore::open(&cfg.storage)
+        .map(SharedStore::new)
+        .map_err(|e| {
+            format!(
+                "failed to open {:?} store at {}: {e:?}",
+                cfg.storage.backend, cfg.storage.path
+            )
+        })?;
+
+    if cfg.storage.metrics.enabled {
+        let interval = Duration::from_secs(cfg.storage.metrics.interval_secs.max(1));
+        tokio::spawn(collect_storage_metrics(
+            store.clone(),
+            metrics.storage.clone(),
+            interval,
+            shutdown.clone(),
+        ));
+    }
 
     // ---------------------------
-    // ML verifier client (HTTP)
+    // ML verifier client (HTTP or gRPC)
     // ---------------------------
 
-    let ml_verifier = HttpMlVerifier::new(cfg.ml_client.base_url.clone(), cfg.ml_client.timeout)
-        .map_err(|e| format!("failed to create HttpMlVerifier: {e:?}"))?;
+    let ml_verifier = MlClient::from_config(
+        &cfg.ml_client,
+        Some(metrics.consensus.outbound_requests.clone()),
+    )
+    .map_err(|e| format!("failed to create ML service client: {e:?}"))?;
 
     // ---------------------------
-    // Block validators (base + ML)
+    // Block validators (base + proposer + ML)
     // ---------------------------
 
-    let base_validity = BaseValidity::new(&cfg.consensus);
-    let ml_validity = MlValidity::new(ml_verifier, MlConfig::default());
-    let validator = CombinedValidator::new(base_validity, ml_validity);
+    let selector = LeaderElection::from_config(
+        &cfg.validators,
+        cfg.consensus.block_time_secs,
+        cfg.genesis.genesis_block().compute_hash().0,
+    );
+    let schemes = &cfg.genesis.params.schemes;
+    let base_validity = BaseValidity::new(&cfg.consensus)
+        .with_signatures_required(cfg.tx_validity.require_signatures)
+        .with_scheme_registry(schemes.clone());
+    let proposer_validity = ProposerValidity::new(selector.clone());
+    // Filled with the tip state once the engine is built.
+    let state_handle = StateHandle::new();
+    let stateful_validity = StatefulValidity::new(state_handle.clone());
+    // Verdict statistics handed from the ML stage to the engine, which
+    // stores them with the block's receipts.
+    let ml_stats = MlStatsLog::new();
+    let ml_cfg = cfg.ml.clone().with_scheme_registry(schemes);
+    let ml_validity = AsyncMlValidity::new(ml_verifier, ml_cfg.clone())
+        .with_metrics(metrics.consensus.clone())
+        .with_stats_log(ml_stats.clone());
+    let revalidation = ml_validity.revalidation_queue().clone();
+    let pipeline = ValidatorPipeline::new()
+        .with_stage("base", base_validity)
+        .with_stage("proposer", proposer_validity)
+        .with_stage("state", stateful_validity);
+    let validator = CombinedValidator::new(pipeline, ml_validity);
 
     // ---------------------------
     // Fork choice + engine
@@ -101,40 +307,143 @@ async fn run_node() -> Result<(), String> {
 
     let fork_choice = DefaultForkChoice::default();
 
-    let mut engine: ConsensusEngine<_, _, _> =
-        ConsensusEngine::new(cfg.consensus.clone(), store, validator, fork_choice);
+    let mut engine: ConsensusEngine<_, _, _> = ConsensusEngine::with_genesis(
+        cfg.consensus.clone(),
+        &cfg.genesis,
+        store,
+        validator,
+        fork_choice,
+    )
+    .map_err(|e| format!("failed to initialise consensus engine: {e}"))?
+    .with_invariant_checks(&cfg.invariants)
+    .with_metrics(metrics.consensus.clone())
+    .with_state_handle(state_handle)
+    .with_ml_stats(ml_stats)
+    .with_reorg_listener(|event| {
+        eprintln!(
+            "reorg: tip {} -> {} ({} block(s) retracted)",
+            hex::encode(event.old_tip.0.as_bytes()),
+            hex::encode(event.new_tip.0.as_bytes()),
+            event.depth
+        );
+    });
 
-    // ---------------------------
-    // Proposer identity (demo)
-    // ---------------------------
+    // In a real node the VRF key would be loaded alongside the Dilithium
+    // keypair; the demo derives it from a fixed byte string.
+    let vrf_key = VrfKeypair::from_seed(&Hash256::compute(b"demo-proposer-vrf-key").0);
+    match &selector {
+        LeaderElection::Vrf(vrf) => {
+            eprintln!("VRF public key {}", hex::encode(vrf_key.public().0));
+            engine = engine.with_vrf_key(vrf_key.clone(), *vrf.seed());
+        }
+        // Stake-weighted leaders follow the stake bonded on the tip.
+        LeaderElection::Stake(stake) => engine = engine.with_stake_table(stake.table().clone()),
+        LeaderElection::RoundRobin(_) => {}
+    }
 
-    // In a real node, this would come from a Dilithium keypair. For now we
-    // derive a deterministic AccountId from a fixed byte string.
-    let proposer_id = {
-        let seed = b"demo-proposer-public-key";
-        AccountId(Hash256::compute(seed))
-    };
+    // Seed chain-derived gauges from the replayed chain.
+    metrics
+        .consensus
+        .backfill(engine.state(), engine.store())
+        .map_err(|e| format!("failed to backfill chain metrics: {e}"))?;
 
     // ---------------------------
-    // Simple transaction pool (empty)
+    // Proposer identity
     // ---------------------------
 
-    struct EmptyTxPool;
-
-    impl TxPool for EmptyTxPool {
-        fn select_for_block(&mut self, _max_txs: usize, _max_bytes: usize) -> Vec<Transaction> {
-            Vec::new()
+    // The proposer account comes from the node's ML-DSA key file when one is
+    // configured; otherwise fall back to a fixed demo identity.
+    let proposer_id = match cfg
+        .node_keypair()
+        .map_err(|e| format!("failed to load node key: {e}"))?
+    {
+        Some((keypair, created)) => {
+            eprintln!(
+                "{} node key for account {}",
+                if created { "generated" } else { "loaded" },
+                hex::encode(keypair.account_id().0.as_bytes())
+            );
+            keypair.account_id()
+        }
+        None => {
+            eprintln!("no node_key_path configured; using the demo proposer identity");
+            AccountId(Hash256::compute(b"demo-proposer-public-key"))
         }
+    };
+    if !selector.validators().is_empty() && !selector.validators().contains(&proposer_id) {
+        eprintln!(
+            "proposer {} is not in the validator set; this node will never propose",
+            hex::encode(proposer_id.0.as_bytes())
+        );
     }
 
-    let mut tx_pool = EmptyTxPool;
+    // ---------------------------
+    // Transaction mempool
+    // ---------------------------
+
+    // Nothing submits transactions to this demo node yet, so the pool
+    // stays empty and blocks are empty.
+    let mut tx_pool = Mempool::with_validator(
+        cfg.mempool.clone(),
+        StatelessTxValidity::new(&cfg.tx_validity).with_scheme_registry(schemes.clone()),
+    )
+    .with_metrics(&metrics.consensus);
     let block_interval = cfg.consensus.block_time_secs;
 
     eprintln!(
-        "starting node with block_time_secs={} (empty TxPool)",
-        block_interval
+        "starting node with block_time_secs={} (mempool max_txs={})",
+        block_interval, cfg.mempool.max_txs
     );
 
+    // ---------------------------
+    // Fork garbage collection
+    // ---------------------------
+
+    let fork_gc = ForkGc::from_config(&cfg.gc);
+    let gc_interval = Duration::from_secs(cfg.gc.interval_secs.max(1));
+    let mut last_gc = std::time::Instant::now();
+
+    // ---------------------------
+    // History pruning
+    // ---------------------------
+
+    let pruner = Pruner::from_config(&cfg.storage.pruning);
+    let prune_interval = Duration::from_secs(cfg.storage.pruning.interval_secs.max(1));
+    let mut last_prune = std::time::Instant::now();
+
+    // ---------------------------
+    // ML revalidation
+    // ---------------------------
+
+    // Blocks accepted during an ML outage are re-checked in the background;
+    // inauthentic artefacts found this way can only be reported.
+    if cfg.ml.outage_policy == MlOutagePolicy::DeferToRevalidationQueue {
+        let verifier = MlClient::from_config(
+            &cfg.ml_client,
+            Some(metrics.consensus.outbound_requests.clone()),
+        )
+        .map_err(|e| format!("failed to create ML revalidation client: {e:?}"))?;
+        let interval = Duration::from_secs(ml_cfg.revalidation.interval_secs.max(1));
+        let shutdown = shutdown.clone();
+        tokio::spawn(async move {
+            while shutdown.sleep(interval).await {
+                let summary = revalidation.revalidate(&verifier, &ml_cfg).await;
+                for late in summary.rejections {
+                    eprintln!(
+                        "block {} (height {}) failed ML revalidation: artefact {} ({})",
+                        hex::encode(late.block_hash.0.as_bytes()),
+                        late.height,
+                        hex::encode(late.aid.0.as_bytes()),
+                        late.verdict.scheme_id
+                    );
+                }
+            }
+        });
+    }
+
+    // Pauses proposals while the ML verifier keeps failing.
+    let mut watchdog = ProducerWatchdog::new(&cfg.watchdog);
+
     // ---------------------------
     // Main proposal loop
     // ---------------------------
@@ -143,23 +452,622 @@ async fn run_node() -> Result<(), String> {
         let start = std::time::Instant::now();
         let timestamp = current_unix_timestamp();
 
-        match engine.propose_block(proposer_id, &mut tx_pool, timestamp) {
-            Ok((hash, block)) => {
-                let elapsed = start.elapsed().as_secs_f64();
-                metrics.consensus.block_validation_seconds.observe(elapsed);
-
-                println!(
-                    "proposed block height={} hash={}",
-                    block.header.height,
-                    hex::encode(hash.0.as_bytes()),
-                );
+        // Only the slot leader proposes; everyone else waits for the next
+        // slot.
+        let skipped_slot = match engine.next_block_timestamp(timestamp) {
+            Ok(slot_time) if !selector.may_propose(&proposer_id, &vrf_key, slot_time) => {
+                Some(selector.expected_proposer(slot_time))
             }
+            Ok(_) => None,
             Err(e) => {
-                eprintln!("failed to propose block: {e}");
+                eprintln!("failed to read chain tip: {e}");
+                None
+            }
+        };
+
+        if let Some(leader) = skipped_slot {
+            match leader {
+                Some(leader) => eprintln!(
+                    "skipping slot owned by {}",
+                    hex::encode(leader.0.as_bytes())
+                ),
+                None => eprintln!("not elected for this slot"),
+            }
+        } else if !watchdog.should_propose(start) {
+            // Paused; wait for the next probe.
+        } else {
+            match engine
+                .propose_block_async(proposer_id, &mut tx_pool, timestamp)
+                .await
+            {
+                Ok((hash, block)) => {
+                    let elapsed = start.elapsed().as_secs_f64();
+                    metrics.consensus.block_validation_seconds.observe(elapsed);
+                    if let Err(e) = metrics.consensus.backfill(engine.state(), engine.store()) {
+                        eprintln!("failed to update chain metrics: {e}");
+                    }
+
+                    println!(
+                        "proposed block height={} hash={}",
+                        block.header.height,
+                        hex::encode(hash.0.as_bytes()),
+                    );
+                    if let Some(WatchdogEvent::Resumed) = watchdog.observe_success() {
+                        eprintln!("ML verifier probe succeeded; block production resumed");
+                    }
+                }
+                Err(e) => {
+                    let paused = watchdog.is_paused();
+                    match watchdog.observe_error(&e, std::time::Instant::now()) {
+                        Some(WatchdogEvent::Paused { failures }) => eprintln!(
+                            "pausing block production after {failures} ML verifier failures: {e}"
+                        ),
+                        _ if paused => {}
+                        _ => eprintln!("failed to propose block: {e}"),
+                    }
+                }
             }
         }
+        tx_pool.prune(engine.state());
+
+        if cfg.gc.enabled && last_gc.elapsed() >= gc_interval {
+            match engine.collect_stale_forks(&fork_gc) {
+                Ok(report) => {
+                    metrics
+                        .consensus
+                        .gc_blocks_pruned
+                        .inc_by(report.blocks_removed);
+                    metrics
+                        .consensus
+                        .gc_reclaimed_bytes
+                        .inc_by(report.bytes_reclaimed);
+                    if report.blocks_removed > 0 {
+                        println!(
+                            "fork gc removed {} blocks ({} bytes)",
+                            report.blocks_removed, report.bytes_reclaimed
+                        );
+                    }
+                }
+                Err(e) => eprintln!("fork gc failed: {e}"),
+            }
+            last_gc = std::time::Instant::now();
+        }
+
+        if cfg.storage.pruning.enabled() && last_prune.elapsed() >= prune_interval {
+            match engine.prune(&pruner, current_unix_timestamp()) {
+                Ok(report) => {
+                    metrics.consensus.pruned_blocks.inc_by(report.blocks_pruned);
+                    if report.blocks_pruned > 0 {
+                        println!(
+                            "pruned {} blocks below height {}",
+                            report.blocks_pruned,
+                            report.checkpoint_height.unwrap_or_default()
+                        );
+                    }
+                }
+                Err(e) => eprintln!("history pruning failed: {e}"),
+            }
+            last_prune = std::time::Instant::now();
+        }
+
+        if !shutdown.sleep(Duration::from_secs(block_interval)).await {
+            break;
+        }
+    }
+
+    // The loop only stops between proposals, so every imported block is
+    // already in the store; make it durable before the store is closed.
+    eprintln!("shutdown signal received; flushing block store");
+    engine
+        .store_mut()
+        .flush()
+        .map_err(|e| format!("failed to flush block store: {e}"))?;
+    drop(engine);
+
+    if let Some(pusher) = pusher {
+        let pushed = pusher.push().await;
+        if let Err(e) = pushed {
+            eprintln!("final metrics push failed: {e}");
+        }
+    }
+
+    if let Some(otlp) = otlp {
+        // Flushing blocks until the collector has answered.
+        let _ = tokio::task::spawn_blocking(move || otlp.shutdown()).await;
+    }
+    Ok(())
+}
+
+/// Plays the scenario file at `path` with the in-process simulator, prints
+/// the report as JSON, and fails if any of its assertions does not hold.
+fn run_sim(path: &Path) -> Result<(), String> {
+    let scenario = Scenario::from_file(path).map_err(|e| e.to_string())?;
+    let report = Simulator::new(scenario.clone())
+        .run()
+        .map_err(|e| format!("simulation {} failed: {e}", scenario.name))?;
+    println!("{}", report.to_json());
+
+    report.check(&scenario.assertions).map_err(|failures| {
+        let lines: Vec<String> = failures.iter().map(|f| format!("  {f}")).collect();
+        format!(
+            "{} of {} assertion(s) failed:\n{}",
+            failures.len(),
+            scenario.assertions.len(),
+            lines.join("\n")
+        )
+    })
+}
 
-        tokio::time::sleep(Duration::from_secs(block_interval)).await;
+/// Exports the configured store to, or bootstraps it from, the snapshot
+/// at `path`.
+fn run_snapshot(cfg: &ChainConfig, action: SnapshotAction, path: &Path) -> Result<(), String> {
+    let mut store = PersistentStore::open(&cfg.storage).map_err(|e| {
+        format!(
+            "failed to open {:?} store at {}: {e:?}",
+            cfg.storage.backend, cfg.storage.path
+        )
+    })?;
+
+    let (info, done) = match action {
+        SnapshotAction::Export => (store.export_snapshot(path), "exported to"),
+        SnapshotAction::Import => (store.import_snapshot(path), "imported from"),
+    };
+    let info = info.map_err(|e| format!("snapshot {}: {e}", path.display()))?;
+    println!(
+        "{} blocks (tip height {}) {done} {}",
+        info.blocks,
+        info.tip_height
+            .map_or_else(|| "none".to_string(), |h| h.to_string()),
+        path.display()
+    );
+    Ok(())
+}
+
+/// Streams the canonical blocks at heights `from..=to` of the configured
+/// store to `output`. `from` defaults to genesis and `to` to the tip.
+fn run_export(
+    cfg: &ChainConfig,
+    from: Option<u64>,
+    to: Option<u64>,
+    output: &Path,
+) -> Result<(), String> {
+    let store = PersistentStore::open(&cfg.storage).map_err(|e| {
+        format!(
+            "failed to open {:?} store at {}: {e:?}",
+            cfg.storage.backend, cfg.storage.path
+        )
+    })?;
+    let to = match to {
+        Some(to) => to,
+        None => store
+            .tip()
+            .and_then(|tip| tip.map_or(Ok(None), |hash| store.get_block(&hash)))
+            .map_err(|e| format!("failed to read the tip: {e}"))?
+            .map(|tip| tip.header.height)
+            .ok_or("the store has no blocks to export")?,
+    };
+
+    // Written next to `output` first, so a failed export leaves no
+    // partial file behind.
+    let mut tmp = output.as_os_str().to_owned();
+    tmp.push(".tmp");
+    let file = std::fs::File::create(&tmp)
+        .map_err(|e| format!("failed to create {}: {e}", output.display()))?;
+    let exported = export_chain(
+        &store,
+        &cfg.genesis.chain_id,
+        cfg.genesis.genesis_hash(),
+        from.unwrap_or(0),
+        to,
+        file,
+    )
+    .and_then(|header| {
+        std::fs::rename(&tmp, output)?;
+        Ok(header)
+    });
+    let header = exported.map_err(|e| {
+        let _ = std::fs::remove_file(&tmp);
+        format!("export to {}: {e}", output.display())
+    })?;
+    println!(
+        "{} blocks (heights {} to {}) exported to {}",
+        header.blocks(),
+        header.from,
+        header.to,
+        output.display()
+    );
+    Ok(())
+}
+
+/// Appends the blocks of the export file at `path` to the configured
+/// store, executing each one against the configured genesis state.
+fn run_import(cfg: &ChainConfig, path: &Path) -> Result<(), String> {
+    let mut store = PersistentStore::open(&cfg.storage).map_err(|e| {
+        format!(
+            "failed to open {:?} store at {}: {e:?}",
+            cfg.storage.backend, cfg.storage.path
+        )
+    })?;
+    let file =
+        std::fs::File::open(path).map_err(|e| format!("failed to open {}: {e}", path.display()))?;
+    let info = import_chain(
+        &mut store,
+        &cfg.genesis.chain_id,
+        cfg.genesis.genesis_hash(),
+        &cfg.genesis.initial_state(),
+        file,
+    )
+    .map_err(|e| format!("import from {}: {e}", path.display()))?;
+    store
+        .flush()
+        .map_err(|e| format!("failed to flush block store: {e}"))?;
+    println!(
+        "{} blocks imported and {} already stored (heights {} to {}) from {}",
+        info.imported,
+        info.skipped,
+        info.header.from,
+        info.header.to,
+        path.display()
+    );
+    Ok(())
+}
+
+/// Re-executes the canonical chain of the configured store from genesis,
+/// with the node's block validators, and fails at the first block whose
+/// hash, links, or roots do not match the stored ones.
+fn run_replay(cfg: &ChainConfig, ml: ReplayMl) -> Result<(), String> {
+    let store = PersistentStore::open(&cfg.storage)
+        .map(SharedStore::new)
+        .map_err(|e| {
+            format!(
+                "failed to open {:?} store at {}: {e:?}",
+                cfg.storage.backend, cfg.storage.path
+            )
+        })?;
+
+    let selector = LeaderElection::from_config(
+        &cfg.validators,
+        cfg.consensus.block_time_secs,
+        cfg.genesis.genesis_hash().0,
+    );
+    let schemes = &cfg.genesis.params.schemes;
+    let state_handle = StateHandle::new();
+    let mut pipeline = ValidatorPipeline::new()
+        .with_stage(
+            "base",
+            BaseValidity::new(&cfg.consensus)
+                .with_signatures_required(cfg.tx_validity.require_signatures)
+                .with_scheme_registry(schemes.clone()),
+        )
+        .with_stage("proposer", ProposerValidity::new(selector.clone()))
+        .with_stage("state", StatefulValidity::new(state_handle.clone()));
+    if let ReplayMl::Mock = ml {
+        let ml_cfg = cfg.ml.clone().with_scheme_registry(schemes);
+        pipeline = pipeline.with_stage("ml", MlValidity::new(MockMlVerifier::accepting(), ml_cfg));
+    }
+    let mut replayer = Replayer::from_genesis(&cfg.genesis)
+        .with_validator(pipeline)
+        .with_state_handle(state_handle);
+    if let LeaderElection::Stake(stake) = &selector {
+        replayer = replayer.with_stake_table(stake.table().clone());
+    }
+
+    let report = replayer
+        .replay(&store)
+        .map_err(|e| format!("replay failed: {e}"))?;
+    if let Some(divergence) = &report.divergence {
+        return Err(format!(
+            "{} block(s) from height {} matched; first mismatch at {divergence}",
+            report.matched, report.from
+        ));
+    }
+    println!(
+        "{} block(s) from height {} replayed without a mismatch (state root {})",
+        report.matched,
+        report.from,
+        hex::encode(report.state.state_root().as_bytes())
+    );
+    Ok(())
+}
+
+/// Writes a node key and a config for a single-validator network to `dir`.
+///
+/// The genesis funds (and optionally stakes) the node's account, which is
+/// also the only validator. An existing key file is reused, so rerunning
+/// `init --force` with a new chain id keeps the node's identity.
+fn run_init(
+    dir: &Path,
+    chain_id: String,
+    balance: u64,
+    stake: u64,
+    force: bool,
+) -> Result<(), String> {
+    let config_path = dir.join("chain.toml");
+    if config_path.exists() && !force {
+        return Err(format!(
+            "{} already exists; pass --force to overwrite it",
+            config_path.display()
+        ));
+    }
+
+    let password = keystore::node_key_password().map_err(|e| e.to_string())?;
+    let key_path = dir.join("node-key.json");
+    let (keypair, created) = keystore::load_or_generate(&key_path, password.as_bytes())
+        .map_err(|e| format!("node key {}: {e}", key_path.display()))?;
+    let account = keypair.account_id();
+
+    let genesis = GenesisConfig {
+        chain_id,
+        timestamp: current_unix_timestamp(),
+        accounts: vec![GenesisAccount {
+            account,
+            balance,
+            stake,
+        }],
+        ..GenesisConfig::default()
+    };
+
+    // Staking and network parameters are left out, so they keep their
+    // defaults (and the file its readable watermark thresholds).
+    let mut genesis_table =
+        toml::Table::try_from(&genesis).map_err(|e| format!("failed to encode genesis: {e}"))?;
+    genesis_table.remove("staking");
+    genesis_table.remove("params");
+    let mut storage = toml::Table::new();
+    storage.insert(
+        "path".to_string(),
+        dir.join("db").display().to_string().into(),
+    );
+    let mut validators = toml::Table::new();
+    validators.insert(
+        "accounts".to_string(),
+        vec![toml::Value::from(account.0.to_string())].into(),
+    );
+    let mut config = toml::Table::new();
+    config.insert(
+        "node_key_path".to_string(),
+        key_path.display().to_string().into(),
+    );
+    config.insert("storage".to_string(), storage.into());
+    config.insert("validators".to_string(), validators.into());
+    config.insert("genesis".to_string(), genesis_table.into());
+    let body =
+        toml::to_string_pretty(&config).map_err(|e| format!("failed to encode config: {e}"))?;
+
+    std::fs::write(
+        &config_path,
+        format!(
+            "# Written by `chain init`. Omitted fields keep their defaults; see\n\
+             # configs/devnet.toml for every option.\n\n{body}"
+        ),
+    )
+    .map_err(|e| format!("failed to write {}: {e}", config_path.display()))?;
+
+    // Loading the file back checks that the node will accept it.
+    let cfg = load_config(Some(&config_path))?;
+    println!(
+        "{} node key for account {} at {}",
+        if created { "generated" } else { "reused" },
+        account.0,
+        key_path.display()
+    );
+    println!(
+        "wrote {} (chain id {}, genesis {})",
+        config_path.display(),
+        cfg.genesis.chain_id,
+        cfg.genesis.genesis_hash().0
+    );
+    println!(
+        "start the node with: chain --config {}",
+        config_path.display()
+    );
+    Ok(())
+}
+
+/// Generates an ML-DSA key pair and writes it to `out`, sealed under the
+/// node key password.
+fn run_keygen(out: &Path, force: bool) -> Result<(), String> {
+    if out.exists() && !force {
+        return Err(format!(
+            "{} already exists; pass --force to overwrite it",
+            out.display()
+        ));
+    }
+    let password = keystore::node_key_password().map_err(|e| e.to_string())?;
+    let keypair = Keypair::generate();
+    keystore::write_key_file(out, &keypair, password.as_bytes(), &KdfParams::default())
+        .map_err(|e| format!("failed to write {}: {e}", out.display()))?;
+    println!("account {}", keypair.account_id().0);
+    println!("key file {}", out.display());
+    Ok(())
+}
+
+/// Prints what `target` refers to in the configured store as JSON.
+fn run_inspect(cfg: &ChainConfig, target: InspectTarget) -> Result<(), String> {
+    let store = PersistentStore::open(&cfg.storage).map_err(|e| {
+        format!(
+            "failed to open {:?} store at {}: {e:?}",
+            cfg.storage.backend, cfg.storage.path
+        )
+    })?;
+    let InspectTarget::Block { block } = target;
+
+    let found = match block {
+        BlockRef::Height(height) => store.get_block_by_height(height),
+        BlockRef::Hash(hash) => store.get_block(&hash),
+    };
+    let block = found
+        .map_err(|e| format!("failed to read block: {e}"))?
+        .ok_or_else(|| format!("block {block} not found"))?;
+    let hash = block.compute_hash();
+    let canonical = store
+        .get_hash_at_height(block.header.height)
+        .map_err(|e| format!("failed to read the height index: {e}"))?
+        == Some(hash);
+    let receipts = store
+        .get_receipts(&hash)
+        .map_err(|e| format!("failed to read receipts: {e}"))?;
+
+    let json = serde_json::json!({
+        "hash": hash,
+        "canonical": canonical,
+        "block": block,
+        "receipts": receipts,
+    });
+    let json = serde_json::to_string_pretty(&json).map_err(|e| e.to_string())?;
+    println!("{json}");
+    Ok(())
+}
+
+/// ML-aware chain node and its maintenance commands.
+///
+/// Without a subcommand, runs the node.
+#[derive(Parser)]
+#[command(
+    name = "chain",
+    version,
+    after_help = "Any config field can be overridden with CHAIN_<SECTION>_<FIELD> environment \
+                  variables, e.g. CHAIN_CONSENSUS_BLOCK_TIME_SECS=2. Key files are sealed \
+                  under the password in CHAIN_NODE_KEY_PASSWORD."
+)]
+struct Cli {
+    /// Load configuration from a .toml, .yaml, or .yml file (default:
+    /// built-in defaults)
+    #[arg(long, value_name = "PATH", env = "CHAIN_CONFIG", global = true)]
+    config: Option<PathBuf>,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+/// What the command line asked the binary to do.
+#[derive(Subcommand)]
+enum Command {
+    /// Run the node (the default)
+    Run,
+    /// Write a node key and a single-validator config with a fresh genesis
+    Init {
+        /// Directory the config, key file, and block store go in
+        #[arg(long, default_value = "data/node")]
+        dir: PathBuf,
+        /// Network identifier of the new genesis
+        #[arg(long, default_value = "mlsnitch-devnet")]
+        chain_id: String,
+        /// Genesis balance of the node's account
+        #[arg(long, default_value_t = 1_000_000)]
+        balance: u64,
+        /// Stake the node's account bonds at genesis
+        #[arg(long, default_value_t = 0)]
+        stake: u64,
+        /// Overwrite an existing config
+        #[arg(long)]
+        force: bool,
+    },
+    /// Generate an encrypted ML-DSA (Dilithium) key file
+    Keygen {
+        /// Key file to write (default: the configured node_key_path)
+        #[arg(short, long, value_name = "FILE")]
+        out: Option<PathBuf>,
+        /// Overwrite an existing key file
+        #[arg(long)]
+        force: bool,
+    },
+    /// Print stored data as JSON
+    Inspect {
+        #[command(subcommand)]
+        target: InspectTarget,
+    },
+    /// Export the block store to, or bootstrap an empty one from, a
+    /// snapshot file
+    Snapshot {
+        action: SnapshotAction,
+        file: PathBuf,
+    },
+    /// Stream canonical blocks to a file under a header with the chain id
+    /// and genesis hash
+    Export {
+        /// First height to export (default: genesis)
+        #[arg(long)]
+        from: Option<u64>,
+        /// Last height to export, inclusive (default: the tip)
+        #[arg(long)]
+        to: Option<u64>,
+        /// File to write
+        #[arg(short, long, value_name = "FILE")]
+        output: PathBuf,
+    },
+    /// Append the blocks of an export file to the store, skipping those
+    /// already stored
+    Import { file: PathBuf },
+    /// Re-execute the stored canonical chain from genesis and report the
+    /// first height whose hashes or roots do not match the stored ones
+    Replay {
+        /// How ML authenticity checks are replayed; verdicts are not
+        /// deterministic, so the ML service is never called
+        #[arg(long, value_enum, default_value = "off")]
+        ml: ReplayMl,
+    },
+    /// Run a .toml or .json simulator scenario and print its report as
+    /// JSON; fails if any of its assertions does not hold
+    Sim { scenario: PathBuf },
+}
+
+/// What an `inspect` command prints.
+#[derive(Subcommand)]
+enum InspectTarget {
+    /// A stored block with its hash, receipts, and whether it is canonical
+    Block {
+        /// Block hash (64 hex characters) or canonical height
+        #[arg(value_name = "HASH|HEIGHT")]
+        block: BlockRef,
+    },
+}
+
+/// Direction of a `snapshot` command.
+#[derive(Clone, Copy, ValueEnum)]
+enum SnapshotAction {
+    /// Write every stored block, the tip, and the pruning checkpoint
+    Export,
+    /// Load a snapshot into the configured (empty) store
+    Import,
+}
+
+/// ML checks of a `replay` command.
+#[derive(Clone, Copy, ValueEnum)]
+enum ReplayMl {
+    /// Skip ML checks
+    Off,
+    /// Check artefact limits and schemes, with a mock verifier accepting
+    /// every artefact
+    Mock,
+}
+
+/// A block given on the command line, by hash or canonical height.
+#[derive(Clone, Copy, Debug)]
+enum BlockRef {
+    Height(u64),
+    Hash(BlockHash),
+}
+
+impl FromStr for BlockRef {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Ok(height) = s.parse() {
+            return Ok(BlockRef::Height(height));
+        }
+        Hash256::from_str(s)
+            .map(|hash| BlockRef::Hash(BlockHash(hash)))
+            .map_err(|_| "expected a block height or a 64-character hex hash".to_string())
+    }
+}
+
+impl std::fmt::Display for BlockRef {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BlockRef::Height(height) => write!(f, "at height {height}"),
+            BlockRef::Hash(hash) => write!(f, "{}", hash.0),
+        }
     }
 }
 
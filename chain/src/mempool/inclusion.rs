@@ -0,0 +1,219 @@
+//! Inclusion-delay tracking for model registrations.
+//!
+//! The [`InclusionTracker`] remembers the tip height at which each
+//! `RegisterModel` transaction was admitted to the mempool and, when a
+//! block containing it is imported, records how many blocks it waited.
+//! A transaction admitted while the tip is at height `h` and included in
+//! the block at `h + 1` has a delay of one block, the minimum.
+//!
+//! Delays are aggregated per owner, so that registration latency under ML
+//! verification load can be broken down by who submitted the artefact.
+
+use std::collections::HashMap;
+
+use crate::types::{AccountId, Block, Transaction, TxHash};
+
+/// Inclusion-delay statistics for one owner.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct InclusionStats {
+    /// Registrations included in a block.
+    pub included: u64,
+    /// Sum of the inclusion delays of those registrations, in blocks.
+    pub total_delay_blocks: u64,
+    /// Longest inclusion delay seen, in blocks.
+    pub max_delay_blocks: u64,
+}
+
+impl InclusionStats {
+    /// Returns the mean inclusion delay in blocks, or `None` if no
+    /// registration was included yet.
+    pub fn average_delay_blocks(&self) -> Option<f64> {
+        (self.included > 0).then(|| self.total_delay_blocks as f64 / self.included as f64)
+    }
+}
+
+/// A registration that made it into a block.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Inclusion {
+    /// Hash of the registration transaction.
+    pub tx_hash: TxHash,
+    /// Owner of the registered artefact.
+    pub owner: AccountId,
+    /// Blocks between admission and inclusion.
+    pub delay_blocks: u64,
+}
+
+/// Tracks pending registrations and per-owner inclusion delays.
+#[derive(Debug, Default)]
+pub struct InclusionTracker {
+    /// Pending registrations: owner and tip height at admission.
+    pending: HashMap<TxHash, (AccountId, u64)>,
+    by_owner: HashMap<AccountId, InclusionStats>,
+}
+
+impl InclusionTracker {
+    /// Creates an empty tracker.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records that `tx` was admitted while the tip was at `tip_height`.
+    /// Only `RegisterModel` transactions are tracked.
+    pub fn record_admission(&mut self, tx: &Transaction, tx_hash: TxHash, tip_height: u64) {
+        if let Transaction::RegisterModel(reg) = tx {
+            self.pending.insert(tx_hash, (reg.owner, tip_height));
+        }
+    }
+
+    /// Resolves the pending registrations included in `block`, updates the
+    /// per-owner statistics, and returns the observed delays.
+    pub fn observe_block(&mut self, block: &Block) -> Vec<Inclusion> {
+        let mut included = Vec::new();
+        for tx in &block.txs {
+            if !matches!(tx, Transaction::RegisterModel(_)) {
+                continue;
+            }
+            let tx_hash = tx.compute_hash();
+            let Some((owner, admitted_at)) = self.pending.remove(&tx_hash) else {
+                continue;
+            };
+            let delay_blocks = block.header.height.saturating_sub(admitted_at);
+            let stats = self.by_owner.entry(owner).or_default();
+            stats.included += 1;
+            stats.total_delay_blocks += delay_blocks;
+            stats.max_delay_blocks = stats.max_delay_blocks.max(delay_blocks);
+            included.push(Inclusion {
+                tx_hash,
+                owner,
+                delay_blocks,
+            });
+        }
+        included
+    }
+
+    /// Stops tracking pending registrations for which `keep` returns
+    /// `false`, e.g. those evicted from or pruned out of the mempool.
+    pub fn retain_pending(&mut self, mut keep: impl FnMut(&TxHash) -> bool) {
+        self.pending.retain(|hash, _| keep(hash));
+    }
+
+    /// Returns the number of tracked registrations not yet included.
+    pub fn pending_len(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Returns the number of `owner`'s registrations not yet included.
+    pub fn pending_for(&self, owner: &AccountId) -> u64 {
+        self.pending.values().filter(|(o, _)| o == owner).count() as u64
+    }
+
+    /// Returns the inclusion statistics of `owner`; all zero if none of
+    /// their registrations were included yet.
+    pub fn owner_stats(&self, owner: &AccountId) -> InclusionStats {
+        self.by_owner.get(owner).copied().unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{
+        Aid, BlockHash, EvidenceHash, EvidenceRef, HASH_LEN, Hash256, Header, Signature,
+        TxRegisterModel, TxTransfer, WmProfile,
+    };
+
+    fn account(byte: u8) -> AccountId {
+        AccountId(Hash256([byte; HASH_LEN]))
+    }
+
+    fn register(owner: u8, aid: u8) -> Transaction {
+        Transaction::RegisterModel(TxRegisterModel {
+            owner: account(owner),
+            aid: Aid(Hash256([aid; HASH_LEN])),
+            chunked: None,
+            evidence: EvidenceRef {
+                scheme_id: "wm".to_string(),
+                evidence_hash: EvidenceHash(Hash256([0u8; HASH_LEN])),
+                wm_profile: WmProfile {
+                    tau_input: 0.9,
+                    tau_feat: 0.1,
+                    logit_band_low: 0.0,
+                    logit_band_high: 0.1,
+                },
+            },
+            fee: 0,
+            nonce: 0,
+            signature: Signature(Vec::new()),
+        })
+    }
+
+    fn block(height: u64, txs: Vec<Transaction>) -> Block {
+        Block {
+            header: Header {
+                parent: BlockHash(Hash256([0u8; HASH_LEN])),
+                height,
+                timestamp: 0,
+                proposer: account(9),
+                txs_root: Hash256([0u8; HASH_LEN]),
+                state_root: Hash256([0u8; HASH_LEN]),
+                receipts_root: Hash256([0u8; HASH_LEN]),
+                pos_proof: None,
+            },
+            txs,
+        }
+    }
+
+    #[test]
+    fn records_delays_per_owner() {
+        let mut tracker = InclusionTracker::new();
+        let a1 = register(1, 1);
+        let a2 = register(1, 2);
+        let b1 = register(2, 3);
+        let transfer = Transaction::Transfer(TxTransfer {
+            from: account(1),
+            to: account(2),
+            amount: 1,
+            fee: 0,
+            nonce: 0,
+            signature: Signature(Vec::new()),
+        });
+        for tx in [&a1, &a2, &b1, &transfer] {
+            tracker.record_admission(tx, tx.compute_hash(), 4);
+        }
+        assert_eq!(tracker.pending_len(), 3);
+
+        let seen = tracker.observe_block(&block(5, vec![a1, transfer]));
+        assert_eq!(seen.len(), 1);
+        assert_eq!(seen[0].delay_blocks, 1);
+        tracker.observe_block(&block(8, vec![a2, b1.clone()]));
+
+        let owner1 = tracker.owner_stats(&account(1));
+        assert_eq!(owner1.included, 2);
+        assert_eq!(owner1.max_delay_blocks, 4);
+        assert_eq!(owner1.average_delay_blocks(), Some(2.5));
+        assert_eq!(tracker.owner_stats(&account(2)).total_delay_blocks, 4);
+        assert_eq!(
+            tracker.owner_stats(&account(3)).average_delay_blocks(),
+            None
+        );
+
+        // Already resolved: seeing it again (e.g. after a reorg) is ignored.
+        assert!(tracker.observe_block(&block(9, vec![b1])).is_empty());
+        assert_eq!(tracker.pending_len(), 0);
+    }
+
+    #[test]
+    fn dropped_registrations_stop_being_tracked() {
+        let mut tracker = InclusionTracker::new();
+        let kept = register(1, 1);
+        let dropped = register(1, 2);
+        tracker.record_admission(&kept, kept.compute_hash(), 0);
+        tracker.record_admission(&dropped, dropped.compute_hash(), 0);
+
+        let kept_hash = kept.compute_hash();
+        tracker.retain_pending(|hash| *hash == kept_hash);
+
+        assert_eq!(tracker.pending_for(&account(1)), 1);
+        assert!(tracker.observe_block(&block(1, vec![dropped])).is_empty());
+    }
+}
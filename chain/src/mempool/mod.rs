@@ -0,0 +1,16 @@
+//! Transaction mempool.
+//!
+//! The [`Mempool`] holds transactions that were submitted to this node but
+//! are not yet part of a block. It implements
+//! [`TxPool`](crate::consensus::TxPool) so the proposer can pull
+//! fee-ordered, nonce-consistent batches from it, and it stays bounded in
+//! both transaction count and total encoded size.
+//!
+//! The [`InclusionTracker`] measures how many blocks model registrations
+//! wait between admission and inclusion.
+
+pub mod inclusion;
+pub mod pool;
+
+pub use inclusion::{Inclusion, InclusionStats, InclusionTracker};
+pub use pool::{Mempool, MempoolConfig, MempoolError};
@@ -0,0 +1,799 @@
+//! Fee-priority mempool with per-account nonce queues.
+//!
+//! Every submitted transaction first goes through the pool's
+//! [`TxValidator`] and then [`StatefulTxValidity`] against the given chain
+//! state, so invalid transactions are rejected on submission rather than at
+//! block validation time.
+//!
+//! Transactions are kept in one queue per sender, keyed by nonce. A
+//! transaction is *ready* when every lower nonce of its sender is either
+//! already on chain or ready itself; transactions behind a nonce gap are
+//! held until the gap is filled, but never selected.
+//!
+//! Block selection repeatedly takes the highest-fee ready transaction
+//! across all senders (ties go to the earlier submission), so a sender's
+//! transactions always leave the pool in nonce order. When the pool is
+//! over its count or byte limit, the lowest-fee transaction among the
+//! *last* queued transaction of every sender is evicted, which never opens
+//! a new nonce gap.
+//!
+//! Transactions handed back through [`TxPool::reinsert`], after a failed
+//! proposal or a reorg, rejoin their sender's queue at their nonce and
+//! become ready again; [`Mempool::prune`] then drops any the new tip has
+//! already used up.
+//!
+//! With [`Mempool::with_metrics`], the pool's transaction count and byte
+//! size are published as gauges after every change.
+
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BinaryHeap, HashMap};
+use std::fmt;
+
+use prometheus::IntGauge;
+use serde::Deserialize;
+
+use crate::consensus::{TxPool, TxValidator};
+use crate::execution::ChainState;
+use crate::metrics::ConsensusMetrics;
+use crate::types::{AccountId, Block, Transaction, TxHash};
+use crate::validation::{StatefulTxValidity, StatelessTxValidity};
+
+/// Configuration for [`Mempool`].
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MempoolConfig {
+    /// Maximum number of pooled transactions.
+    pub max_txs: usize,
+    /// Maximum total canonical encoded size of pooled transactions, in
+    /// bytes.
+    pub max_bytes: usize,
+    /// Maximum number of queued transactions per sender. Nonces at or
+    /// beyond `account nonce + max_txs_per_account` are rejected.
+    pub max_txs_per_account: u64,
+}
+
+impl Default for MempoolConfig {
+    fn default() -> Self {
+        Self {
+            max_txs: 10_000,
+            max_bytes: 16 * 1024 * 1024,
+            max_txs_per_account: 64,
+        }
+    }
+}
+
+/// Reasons a transaction is not admitted to the pool.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MempoolError {
+    /// The transaction failed stateless or stateful validation.
+    Invalid(String),
+    /// The same transaction is already pooled.
+    Duplicate(TxHash),
+    /// The nonce is already used on chain.
+    NonceTooLow { expected: u64, got: u64 },
+    /// The nonce is too far ahead of the sender's on-chain nonce.
+    NonceTooHigh { max: u64, got: u64 },
+    /// A transaction with the same sender and nonce is pooled with a fee
+    /// at least as high.
+    Underpriced { existing_fee: u64, fee: u64 },
+    /// The transaction alone exceeds the pool's byte limit.
+    TooLarge { size: usize, max: usize },
+    /// The pool is full and the transaction's fee is too low to evict
+    /// anything.
+    Full,
+}
+
+impl fmt::Display for MempoolError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MempoolError::Invalid(reason) => write!(f, "invalid transaction: {reason}"),
+            MempoolError::Duplicate(hash) => write!(
+                f,
+                "transaction {} is already pooled",
+                hex::encode(hash.0.as_bytes())
+            ),
+            MempoolError::NonceTooLow { expected, got } => {
+                write!(f, "nonce {got} is already used; next nonce is {expected}")
+            }
+            MempoolError::NonceTooHigh { max, got } => {
+                write!(f, "nonce {got} is too far ahead; highest accepted is {max}")
+            }
+            MempoolError::Underpriced { existing_fee, fee } => write!(
+                f,
+                "fee {fee} does not exceed the pooled transaction's fee {existing_fee}"
+            ),
+            MempoolError::TooLarge { size, max } => {
+                write!(f, "transaction of {size} bytes exceeds the limit of {max}")
+            }
+            MempoolError::Full => write!(f, "mempool is full"),
+        }
+    }
+}
+
+impl std::error::Error for MempoolError {}
+
+/// A pooled transaction with its cached metadata.
+#[derive(Clone, Debug)]
+struct PooledTx {
+    tx: Transaction,
+    hash: TxHash,
+    fee: u64,
+    size: usize,
+    /// Submission order, used to break fee ties.
+    seq: u64,
+}
+
+/// Queued transactions of one sender.
+#[derive(Debug, Default)]
+struct AccountQueue {
+    /// Next nonce the sender can use on chain, as last observed.
+    base_nonce: u64,
+    txs: BTreeMap<u64, PooledTx>,
+}
+
+/// Next ready transaction of a sender, as ordered during block selection:
+/// higher fee first, then earlier submission.
+#[derive(Debug, Eq, PartialEq)]
+struct ReadyTx {
+    fee: u64,
+    seq: u64,
+    sender: AccountId,
+    nonce: u64,
+}
+
+impl ReadyTx {
+    fn new(sender: AccountId, nonce: u64, entry: &PooledTx) -> Self {
+        Self {
+            fee: entry.fee,
+            seq: entry.seq,
+            sender,
+            nonce,
+        }
+    }
+}
+
+impl Ord for ReadyTx {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        // `seq` is unique, so this is a total order.
+        (self.fee, Reverse(self.seq)).cmp(&(other.fee, Reverse(other.seq)))
+    }
+}
+
+impl PartialOrd for ReadyTx {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Bounded, fee-ordered transaction pool.
+///
+/// `V` checks each transaction before it is admitted.
+#[derive(Debug)]
+pub struct Mempool<V = StatelessTxValidity> {
+    cfg: MempoolConfig,
+    validator: V,
+    accounts: HashMap<AccountId, AccountQueue>,
+    by_hash: HashMap<TxHash, (AccountId, u64)>,
+    total_bytes: usize,
+    next_seq: u64,
+    /// Gauges for the pool's transaction count and byte size, if any.
+    gauges: Option<(IntGauge, IntGauge)>,
+}
+
+impl Mempool {
+    /// Creates an empty pool with the default [`StatelessTxValidity`]
+    /// checks.
+    pub fn new(cfg: MempoolConfig) -> Self {
+        Self::with_validator(cfg, StatelessTxValidity::default())
+    }
+}
+
+impl<V: TxValidator> Mempool<V> {
+    /// Creates an empty pool that admits only transactions accepted by
+    /// `validator`.
+    pub fn with_validator(cfg: MempoolConfig, validator: V) -> Self {
+        Self {
+            cfg,
+            validator,
+            accounts: HashMap::new(),
+            by_hash: HashMap::new(),
+            total_bytes: 0,
+            next_seq: 0,
+            gauges: None,
+        }
+    }
+
+    /// Keeps `metrics.mempool_transactions` and `metrics.mempool_bytes` in
+    /// line with the pool's contents.
+    pub fn with_metrics(mut self, metrics: &ConsensusMetrics) -> Self {
+        self.gauges = Some((
+            metrics.mempool_transactions.clone(),
+            metrics.mempool_bytes.clone(),
+        ));
+        self.report_size();
+        self
+    }
+
+    /// Publishes the pool's size to the gauges, if any.
+    fn report_size(&self) {
+        if let Some((txs, bytes)) = &self.gauges {
+            txs.set(self.len() as i64);
+            bytes.set(self.total_bytes as i64);
+        }
+    }
+
+    /// Returns the number of pooled transactions.
+    pub fn len(&self) -> usize {
+        self.by_hash.len()
+    }
+
+    /// Returns `true` if the pool holds no transactions.
+    pub fn is_empty(&self) -> bool {
+        self.by_hash.is_empty()
+    }
+
+    /// Returns the total canonical size of pooled transactions, in bytes.
+    pub fn total_bytes(&self) -> usize {
+        self.total_bytes
+    }
+
+    /// Returns `true` if a transaction with this hash is pooled.
+    pub fn contains(&self, hash: &TxHash) -> bool {
+        self.by_hash.contains_key(hash)
+    }
+
+    /// Returns the pooled transaction with this hash, if any.
+    pub fn get(&self, hash: &TxHash) -> Option<&Transaction> {
+        let (sender, nonce) = self.by_hash.get(hash)?;
+        self.accounts
+            .get(sender)
+            .and_then(|queue| queue.txs.get(nonce))
+            .map(|entry| &entry.tx)
+    }
+
+    /// Returns the nonce a new transaction from `sender` should use: the
+    /// sender's on-chain nonce, advanced past every consecutive pooled
+    /// transaction.
+    pub fn next_nonce(&self, sender: &AccountId, state: &ChainState) -> u64 {
+        let mut nonce = state.account(sender).nonce;
+        if let Some(queue) = self.accounts.get(sender) {
+            nonce = nonce.max(queue.base_nonce);
+            while queue.txs.contains_key(&nonce) {
+                nonce += 1;
+            }
+        }
+        nonce
+    }
+
+    /// Admits `tx`, checking it with the pool's validator, then with
+    /// [`StatefulTxValidity`] and its nonce against `state`.
+    ///
+    /// A transaction with the same sender and nonce as a pooled one
+    /// replaces it only if it pays a strictly higher fee. If admitting the
+    /// transaction pushes the pool over its limits, lower-fee transactions
+    /// are evicted; if `tx` itself would be the one evicted, it is rejected
+    /// with [`MempoolError::Full`].
+    pub fn insert(&mut self, tx: Transaction, state: &ChainState) -> Result<TxHash, MempoolError> {
+        // Rejections may still drop stale or evicted transactions.
+        let result = self.admit(tx, state);
+        self.report_size();
+        result
+    }
+
+    /// Does the work of [`insert`](Self::insert).
+    fn admit(&mut self, tx: Transaction, state: &ChainState) -> Result<TxHash, MempoolError> {
+        self.validator
+            .validate_tx(&tx)
+            .map_err(|e| MempoolError::Invalid(e.to_string()))?;
+        StatefulTxValidity
+            .validate_tx(&tx, state)
+            .map_err(|e| MempoolError::Invalid(e.to_string()))?;
+        let hash = tx.compute_hash();
+        if self.by_hash.contains_key(&hash) {
+            return Err(MempoolError::Duplicate(hash));
+        }
+        let size = tx.canonical_bytes().len();
+        if size > self.cfg.max_bytes {
+            return Err(MempoolError::TooLarge {
+                size,
+                max: self.cfg.max_bytes,
+            });
+        }
+
+        let sender = tx.sender();
+        let nonce = tx.nonce();
+        let fee = tx.fee();
+        let chain_nonce = state.account(&sender).nonce;
+        self.advance_base(sender, chain_nonce);
+
+        let base = self
+            .accounts
+            .get(&sender)
+            .map_or(chain_nonce, |q| q.base_nonce);
+        if nonce < base {
+            return Err(MempoolError::NonceTooLow {
+                expected: base,
+                got: nonce,
+            });
+        }
+        let max = base.saturating_add(self.cfg.max_txs_per_account.max(1)) - 1;
+        if nonce > max {
+            return Err(MempoolError::NonceTooHigh { max, got: nonce });
+        }
+
+        let existing = self
+            .accounts
+            .get(&sender)
+            .and_then(|queue| queue.txs.get(&nonce))
+            .map(|entry| (entry.hash, entry.fee));
+        if let Some((replaced, existing_fee)) = existing {
+            if fee <= existing_fee {
+                return Err(MempoolError::Underpriced { existing_fee, fee });
+            }
+            self.remove_by_hash(&replaced);
+        }
+
+        let seq = self.next_seq;
+        self.next_seq += 1;
+        self.accounts
+            .entry(sender)
+            .or_insert_with(|| AccountQueue {
+                base_nonce: base,
+                txs: BTreeMap::new(),
+            })
+            .txs
+            .insert(
+                nonce,
+                PooledTx {
+                    tx,
+                    hash,
+                    fee,
+                    size,
+                    seq,
+                },
+            );
+        self.by_hash.insert(hash, (sender, nonce));
+        self.total_bytes += size;
+
+        while self.len() > self.cfg.max_txs || self.total_bytes > self.cfg.max_bytes {
+            let Some(victim) = self.eviction_candidate() else {
+                break;
+            };
+            self.remove_by_hash(&victim);
+            if victim == hash {
+                return Err(MempoolError::Full);
+            }
+        }
+
+        Ok(hash)
+    }
+
+    /// Removes the transactions of an imported block from the pool.
+    ///
+    /// Also drops any pooled transaction whose nonce the block used up,
+    /// e.g. a competing transaction with the same sender and nonce.
+    pub fn remove_included(&mut self, block: &Block) {
+        for tx in &block.txs {
+            self.remove_by_hash(&tx.compute_hash());
+            self.advance_base(tx.sender(), tx.nonce().saturating_add(1));
+        }
+        self.report_size();
+    }
+
+    /// Re-synchronises every sender's queue with `state`.
+    ///
+    /// Transactions whose nonce is already used on chain are dropped. Call
+    /// this after the tip changes, including after a failed proposal or a
+    /// reorg, so that nonce gaps left by dropped transactions can be
+    /// refilled.
+    pub fn prune(&mut self, state: &ChainState) {
+        let senders: Vec<AccountId> = self.accounts.keys().copied().collect();
+        for sender in senders {
+            let chain_nonce = state.account(&sender).nonce;
+            self.advance_base(sender, chain_nonce);
+            if let Some(queue) = self.accounts.get_mut(&sender) {
+                queue.base_nonce = chain_nonce;
+            }
+        }
+        self.report_size();
+    }
+
+    /// Raises `sender`'s base nonce to `nonce`, dropping transactions below
+    /// it.
+    fn advance_base(&mut self, sender: AccountId, nonce: u64) {
+        let Some(queue) = self.accounts.get_mut(&sender) else {
+            return;
+        };
+        if nonce <= queue.base_nonce {
+            return;
+        }
+        queue.base_nonce = nonce;
+        let kept = queue.txs.split_off(&nonce);
+        let stale = std::mem::replace(&mut queue.txs, kept);
+        for entry in stale.into_values() {
+            self.by_hash.remove(&entry.hash);
+            self.total_bytes -= entry.size;
+        }
+        if queue.txs.is_empty() {
+            self.accounts.remove(&sender);
+        }
+    }
+
+    /// Removes a single transaction, returning it if it was pooled.
+    fn remove_by_hash(&mut self, hash: &TxHash) -> Option<Transaction> {
+        let (sender, nonce) = self.by_hash.remove(hash)?;
+        let queue = self.accounts.get_mut(&sender)?;
+        let entry = queue.txs.remove(&nonce)?;
+        self.total_bytes -= entry.size;
+        if queue.txs.is_empty() {
+            self.accounts.remove(&sender);
+        }
+        Some(entry.tx)
+    }
+
+    /// Returns the `(sender, nonce)` of the transactions a block would
+    /// take, in selection order: repeatedly the highest-fee ready
+    /// transaction, within the `max_txs` / `max_bytes` limits.
+    fn plan_block(&self, max_txs: usize, max_bytes: usize) -> Vec<(AccountId, u64)> {
+        // Max-heap over each sender's next ready transaction.
+        let mut heap: BinaryHeap<ReadyTx> = self
+            .accounts
+            .iter()
+            .filter_map(|(sender, queue)| {
+                let entry = queue.txs.get(&queue.base_nonce)?;
+                Some(ReadyTx::new(*sender, queue.base_nonce, entry))
+            })
+            .collect();
+
+        let mut planned = Vec::new();
+        let mut bytes = 0usize;
+        while planned.len() < max_txs {
+            let Some(ReadyTx { sender, nonce, .. }) = heap.pop() else {
+                break;
+            };
+            let Some(queue) = self.accounts.get(&sender) else {
+                continue;
+            };
+            let Some(entry) = queue.txs.get(&nonce) else {
+                continue;
+            };
+            // Skip the sender: its later nonces cannot be included
+            // without this transaction.
+            if bytes + entry.size > max_bytes {
+                continue;
+            }
+            bytes += entry.size;
+            if let Some(next) = queue.txs.get(&(nonce + 1)) {
+                heap.push(ReadyTx::new(sender, nonce + 1, next));
+            }
+            planned.push((sender, nonce));
+        }
+        planned
+    }
+
+    /// Does the work of [`TxPool::reinsert`] for one transaction.
+    fn restore(&mut self, tx: Transaction) {
+        if self.validator.validate_tx(&tx).is_err() {
+            return;
+        }
+        let hash = tx.compute_hash();
+        let size = tx.canonical_bytes().len();
+        if self.by_hash.contains_key(&hash) || size > self.cfg.max_bytes {
+            return;
+        }
+        let sender = tx.sender();
+        let nonce = tx.nonce();
+        let fee = tx.fee();
+        let queue = self.accounts.entry(sender).or_insert_with(|| AccountQueue {
+            base_nonce: nonce,
+            txs: BTreeMap::new(),
+        });
+        if queue.txs.contains_key(&nonce) {
+            return;
+        }
+        queue.base_nonce = queue.base_nonce.min(nonce);
+        let seq = self.next_seq;
+        self.next_seq += 1;
+        queue.txs.insert(
+            nonce,
+            PooledTx {
+                tx,
+                hash,
+                fee,
+                size,
+                seq,
+            },
+        );
+        self.by_hash.insert(hash, (sender, nonce));
+        self.total_bytes += size;
+
+        while self.len() > self.cfg.max_txs || self.total_bytes > self.cfg.max_bytes {
+            let Some(victim) = self.eviction_candidate() else {
+                break;
+            };
+            self.remove_by_hash(&victim);
+        }
+    }
+
+    /// Picks the transaction to evict: the lowest-fee last transaction of
+    /// any sender, preferring the most recent submission on ties.
+    fn eviction_candidate(&self) -> Option<TxHash> {
+        self.accounts
+            .values()
+            .filter_map(|queue| queue.txs.values().next_back())
+            .min_by_key(|entry| (entry.fee, Reverse(entry.seq)))
+            .map(|entry| entry.hash)
+    }
+}
+
+impl<V: TxValidator> TxPool for Mempool<V> {
+    /// Removes and returns the highest-fee ready transactions, respecting
+    /// per-sender nonce order and the `max_txs` / `max_bytes` limits.
+    fn select_for_block(&mut self, max_txs: usize, max_bytes: usize) -> Vec<Transaction> {
+        let mut selected = Vec::new();
+        for (sender, nonce) in self.plan_block(max_txs, max_bytes) {
+            let Some(hash) = self
+                .accounts
+                .get(&sender)
+                .and_then(|queue| queue.txs.get(&nonce))
+                .map(|entry| entry.hash)
+            else {
+                continue;
+            };
+            if let Some(tx) = self.remove_by_hash(&hash) {
+                selected.push(tx);
+            }
+            if let Some(queue) = self.accounts.get_mut(&sender) {
+                queue.base_nonce = nonce + 1;
+            }
+        }
+        self.report_size();
+        selected
+    }
+
+    fn peek(&self, max_txs: usize, max_bytes: usize) -> Vec<Transaction> {
+        self.plan_block(max_txs, max_bytes)
+            .into_iter()
+            .filter_map(|(sender, nonce)| {
+                let entry = self.accounts.get(&sender)?.txs.get(&nonce)?;
+                Some(entry.tx.clone())
+            })
+            .collect()
+    }
+
+    /// Removes one transaction. Later nonces of its sender stay pooled but
+    /// are held until the nonce is filled again.
+    fn remove(&mut self, tx_hash: &TxHash) -> Option<Transaction> {
+        let removed = self.remove_by_hash(tx_hash);
+        self.report_size();
+        removed
+    }
+
+    /// Restores transactions that left the pool through block selection,
+    /// lowering their senders' base nonces so they are ready again.
+    ///
+    /// They are checked with the pool's validator only; call
+    /// [`prune`](Mempool::prune) with the tip state afterwards to drop
+    /// those whose nonce is used on chain. A transaction whose sender and
+    /// nonce are taken by a pooled one is skipped, and the pool's limits
+    /// are enforced by the usual eviction.
+    fn reinsert(&mut self, txs: Vec<Transaction>) {
+        for tx in txs {
+            self.restore(tx);
+        }
+        self.report_size();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{HASH_LEN, Hash256, Signature, TxTransfer};
+
+    fn account(byte: u8) -> AccountId {
+        AccountId(Hash256([byte; HASH_LEN]))
+    }
+
+    fn transfer(from: u8, nonce: u64, fee: u64) -> Transaction {
+        Transaction::Transfer(TxTransfer {
+            from: account(from),
+            to: account(0xff),
+            amount: 1,
+            fee,
+            nonce,
+            signature: Signature(Vec::new()),
+        })
+    }
+
+    fn pool(max_txs: usize) -> Mempool {
+        Mempool::new(MempoolConfig {
+            max_txs,
+            ..MempoolConfig::default()
+        })
+    }
+
+    fn fees_and_nonces(txs: &[Transaction]) -> Vec<(u8, u64, u64)> {
+        txs.iter()
+            .map(|tx| (tx.sender().0.0[0], tx.nonce(), tx.fee()))
+            .collect()
+    }
+
+    #[test]
+    fn selects_by_fee_while_keeping_nonce_order() {
+        let state = ChainState::new();
+        let mut pool = pool(100);
+        pool.insert(transfer(1, 0, 1), &state).unwrap();
+        pool.insert(transfer(1, 1, 50), &state).unwrap();
+        pool.insert(transfer(2, 0, 10), &state).unwrap();
+
+        let selected = pool.select_for_block(10, usize::MAX);
+
+        assert_eq!(
+            fees_and_nonces(&selected),
+            vec![(2, 0, 10), (1, 0, 1), (1, 1, 50)]
+        );
+        assert!(pool.is_empty());
+        assert_eq!(pool.total_bytes(), 0);
+    }
+
+    #[test]
+    fn nonce_gaps_are_held_until_filled() {
+        let state = ChainState::new();
+        let mut pool = pool(100);
+        pool.insert(transfer(1, 1, 5), &state).unwrap();
+
+        assert!(pool.select_for_block(10, usize::MAX).is_empty());
+        assert_eq!(pool.next_nonce(&account(1), &state), 0);
+
+        pool.insert(transfer(1, 0, 5), &state).unwrap();
+        assert_eq!(pool.next_nonce(&account(1), &state), 2);
+        assert_eq!(pool.select_for_block(10, usize::MAX).len(), 2);
+    }
+
+    #[test]
+    fn rejects_duplicates_bad_nonces_and_underpriced_replacements() {
+        let mut state = ChainState::new();
+        state.account_mut(account(1)).nonce = 3;
+        let mut pool = pool(100);
+
+        let hash = pool.insert(transfer(1, 3, 5), &state).unwrap();
+        assert_eq!(
+            pool.insert(transfer(1, 3, 5), &state),
+            Err(MempoolError::Duplicate(hash))
+        );
+        assert_eq!(
+            pool.insert(transfer(1, 2, 5), &state),
+            Err(MempoolError::NonceTooLow {
+                expected: 3,
+                got: 2
+            })
+        );
+        assert!(matches!(
+            pool.insert(transfer(1, 3 + 64, 5), &state),
+            Err(MempoolError::NonceTooHigh { max: 66, .. })
+        ));
+        assert!(matches!(
+            pool.insert(transfer(1, 3, 4), &state),
+            Err(MempoolError::Underpriced { .. })
+        ));
+
+        let bumped = pool.insert(transfer(1, 3, 6), &state).unwrap();
+        assert!(!pool.contains(&hash));
+        assert!(pool.contains(&bumped));
+        assert_eq!(pool.get(&bumped).map(Transaction::fee), Some(6));
+        assert_eq!(pool.len(), 1);
+    }
+
+    #[test]
+    fn rejects_transactions_failing_validation() {
+        let state = ChainState::new();
+        let mut pool = pool(100);
+
+        let self_transfer = match transfer(1, 0, 5) {
+            Transaction::Transfer(mut tx) => {
+                tx.to = tx.from;
+                Transaction::Transfer(tx)
+            }
+            _ => unreachable!(),
+        };
+        assert!(matches!(
+            pool.insert(self_transfer, &state),
+            Err(MempoolError::Invalid(_))
+        ));
+        assert!(pool.is_empty());
+    }
+
+    #[test]
+    fn full_pool_evicts_lowest_fee_tail() {
+        let state = ChainState::new();
+        let metrics = ConsensusMetrics::register(
+            &prometheus::Registry::new(),
+            &crate::metrics::HistogramBuckets::default(),
+        )
+        .unwrap();
+        let mut pool = pool(2).with_metrics(&metrics);
+        pool.insert(transfer(1, 0, 10), &state).unwrap();
+        let cheap = pool.insert(transfer(2, 0, 1), &state).unwrap();
+
+        assert_eq!(
+            pool.insert(transfer(3, 0, 1), &state),
+            Err(MempoolError::Full)
+        );
+        pool.insert(transfer(3, 0, 5), &state).unwrap();
+
+        assert!(!pool.contains(&cheap));
+        assert_eq!(pool.len(), 2);
+        assert_eq!(metrics.mempool_transactions.get(), 2);
+        assert_eq!(metrics.mempool_bytes.get(), pool.total_bytes() as i64);
+    }
+
+    #[test]
+    fn included_and_stale_transactions_are_removed() {
+        let mut state = ChainState::new();
+        let mut pool = pool(100);
+        pool.insert(transfer(1, 0, 1), &state).unwrap();
+        pool.insert(transfer(1, 1, 1), &state).unwrap();
+        pool.insert(transfer(2, 0, 1), &state).unwrap();
+
+        let block = Block {
+            header: crate::types::Header {
+                parent: crate::types::BlockHash(Hash256([0u8; HASH_LEN])),
+                height: 1,
+                timestamp: 0,
+                proposer: account(9),
+                txs_root: Hash256([0u8; HASH_LEN]),
+                state_root: Hash256([0u8; HASH_LEN]),
+                receipts_root: Hash256([0u8; HASH_LEN]),
+                pos_proof: None,
+            },
+            // A competing transaction with the same nonce as a pooled one.
+            txs: vec![transfer(1, 0, 7)],
+        };
+        pool.remove_included(&block);
+        assert_eq!(pool.len(), 2);
+
+        // State after the block, plus a block elsewhere that used account 2's
+        // nonce 0.
+        state.account_mut(account(1)).nonce = 1;
+        state.account_mut(account(2)).nonce = 1;
+        pool.prune(&state);
+        assert_eq!(pool.len(), 1);
+        assert_eq!(
+            fees_and_nonces(&pool.select_for_block(10, usize::MAX)),
+            vec![(1, 1, 1)]
+        );
+    }
+
+    #[test]
+    fn peek_remove_and_reinsert() {
+        let state = ChainState::new();
+        let mut pool = pool(100);
+        pool.insert(transfer(1, 0, 5), &state).unwrap();
+        pool.insert(transfer(1, 1, 5), &state).unwrap();
+        pool.insert(transfer(2, 0, 3), &state).unwrap();
+
+        let peeked = fees_and_nonces(&pool.peek(2, usize::MAX));
+        assert_eq!(pool.len(), 3);
+        let selected = pool.select_for_block(2, usize::MAX);
+        assert_eq!(fees_and_nonces(&selected), peeked);
+        assert_eq!(pool.len(), 1);
+
+        // A failed proposal hands its transactions back; they are ready
+        // again, still in nonce order.
+        pool.reinsert(selected);
+        assert_eq!(pool.len(), 3);
+        assert_eq!(
+            fees_and_nonces(&pool.peek(10, usize::MAX)),
+            vec![(1, 0, 5), (1, 1, 5), (2, 0, 3)]
+        );
+
+        // Removing account 1's nonce 0 holds its nonce 1 behind the gap.
+        let removed = pool.remove(&transfer(1, 0, 5).compute_hash()).unwrap();
+        assert_eq!(removed.nonce(), 0);
+        assert!(pool.remove(&removed.compute_hash()).is_none());
+        assert_eq!(
+            fees_and_nonces(&pool.select_for_block(10, usize::MAX)),
+            vec![(2, 0, 3)]
+        );
+    }
+}
@@ -1,26 +1,41 @@
 //! Metrics and instrumentation for the chain.
 //!
 //! This module defines Prometheus-compatible metrics for the consensus
-//! engine and exposes a small HTTP exporter that serves `/metrics` in
-//! Prometheus text format.
+//! engine and the block store, and exposes a small HTTP exporter that serves `/metrics` in
+//! Prometheus text format. [`push`] instead pushes the metrics to a
+//! Pushgateway or remote-write endpoint, for runs too short to be scraped.
+//! [`otlp`] optionally exports tracing spans to an OpenTelemetry collector.
 //!
 //! Typical usage in a node:
 //!
 //! ```ignore
 //! use std::net::SocketAddr;
 //! use std::sync::Arc;
+//! use chain::Shutdown;
 //! use chain::metrics::{MetricsRegistry, run_prometheus_http_server};
 //!
 //! let registry = Arc::new(MetricsRegistry::new()?);
 //! let addr: SocketAddr = "127.0.0.1:9898".parse()?;
 //!
 //! // Spawn the HTTP exporter in the background:
-//! tokio::spawn(run_prometheus_http_server(registry.clone(), addr));
+//! let shutdown = Shutdown::on_ctrl_c();
+//! tokio::spawn(run_prometheus_http_server(registry.clone(), addr, shutdown.clone()));
+//!
+//! // Or serve it on an already bound (possibly TLS) listener:
+//! let listener = Listener::bind(&listener_cfg).await?;
+//! tokio::spawn(serve_prometheus(registry.clone(), listener, shutdown.clone()));
 //!
 //! // Elsewhere in the code:
 //! registry.consensus.block_validation_seconds.observe(duration_secs);
 //! ```
 
+pub mod otlp;
 pub mod prometheus;
+pub mod push;
 
-pub use prometheus::{ConsensusMetrics, MetricsRegistry, run_prometheus_http_server};
+pub use otlp::{OtlpConfig, OtlpTracing, inject_trace_context};
+pub use prometheus::{
+    ConsensusMetrics, HistogramBuckets, MetricsRegistry, StorageMetrics, collect_storage_metrics,
+    run_prometheus_http_server, serve_prometheus,
+};
+pub use push::{MetricsMode, MetricsPusher, PushConfig, PushError, PushTarget};
@@ -0,0 +1,181 @@
+//! OpenTelemetry trace export over OTLP.
+//!
+//! The engine, the ML validators, and the HTTP ML client open `tracing`
+//! spans for every proposal, import, validation, and verifier call, with
+//! the block hash, height, and `Aid` as fields. With `metrics.otlp.enabled`,
+//! [`OtlpTracing::init`] builds a [`tracing_subscriber`] layer that exports
+//! those spans to an OTLP collector (Jaeger, Tempo, ...) over gRPC, so a
+//! block's lifecycle shows up as a single trace.
+//!
+//! The W3C trace-context propagator is installed at the same time, and
+//! [`inject_trace_context`] adds a `traceparent` header to outgoing ML
+//! service requests, so spans opened by the service join the node's trace.
+
+use opentelemetry::propagation::Injector;
+use opentelemetry::trace::TracerProvider as _;
+use opentelemetry_otlp::WithExportConfig;
+use opentelemetry_sdk::Resource;
+use opentelemetry_sdk::propagation::TraceContextPropagator;
+use opentelemetry_sdk::trace::{Sampler, SdkTracerProvider};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use serde::Deserialize;
+use tracing::Subscriber;
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+use tracing_subscriber::Layer;
+use tracing_subscriber::filter::{LevelFilter, Targets};
+use tracing_subscriber::registry::LookupSpan;
+
+/// Configuration for the OTLP trace exporter.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct OtlpConfig {
+    /// Whether spans are exported.
+    pub enabled: bool,
+    /// gRPC endpoint of the OTLP collector.
+    pub endpoint: String,
+    /// `service.name` reported with every span. Empty uses the binary's
+    /// own name.
+    pub service_name: String,
+    /// Fraction of new traces that are sampled, in `[0, 1]`. Traces
+    /// continued from a sampled parent are always sampled.
+    pub sample_ratio: f64,
+}
+
+impl Default for OtlpConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            endpoint: "http://127.0.0.1:4317".to_string(),
+            service_name: String::new(),
+            sample_ratio: 1.0,
+        }
+    }
+}
+
+/// Crate targets whose spans are exported.
+const EXPORTED_TARGETS: [&str; 2] = ["chain", "api_gateway"];
+
+/// A running OTLP exporter.
+///
+/// Keep it for the lifetime of the process and call
+/// [`shutdown`](Self::shutdown) before exiting to flush buffered spans.
+pub struct OtlpTracing {
+    provider: SdkTracerProvider,
+}
+
+impl OtlpTracing {
+    /// Starts the exporter described by `cfg`, or returns `None` if it is
+    /// disabled. `default_service_name` is used when
+    /// [`OtlpConfig::service_name`] is empty.
+    ///
+    /// Must be called from inside a Tokio runtime.
+    pub fn init(
+        cfg: &OtlpConfig,
+        default_service_name: &str,
+    ) -> Result<Option<Self>, opentelemetry_otlp::ExporterBuildError> {
+        if !cfg.enabled {
+            return Ok(None);
+        }
+
+        let exporter = opentelemetry_otlp::SpanExporter::builder()
+            .with_tonic()
+            .with_endpoint(cfg.endpoint.clone())
+            .build()?;
+        let service_name = if cfg.service_name.is_empty() {
+            default_service_name
+        } else {
+            &cfg.service_name
+        };
+        let provider = SdkTracerProvider::builder()
+            .with_batch_exporter(exporter)
+            .with_sampler(Sampler::ParentBased(Box::new(Sampler::TraceIdRatioBased(
+                cfg.sample_ratio,
+            ))))
+            .with_resource(
+                Resource::builder()
+                    .with_service_name(service_name.to_string())
+                    .build(),
+            )
+            .build();
+        opentelemetry::global::set_text_map_propagator(TraceContextPropagator::new());
+
+        Ok(Some(Self { provider }))
+    }
+
+    /// Returns a layer exporting the spans of this workspace's crates.
+    pub fn layer<S>(&self) -> impl Layer<S> + use<S>
+    where
+        S: Subscriber + for<'span> LookupSpan<'span>,
+    {
+        let targets = EXPORTED_TARGETS
+            .iter()
+            .fold(Targets::new(), |targets, target| {
+                targets.with_target(*target, LevelFilter::INFO)
+            });
+        tracing_opentelemetry::layer()
+            .with_tracer(self.provider.tracer("mlsnitch"))
+            .with_filter(targets)
+    }
+
+    /// Flushes buffered spans and stops the exporter.
+    pub fn shutdown(self) {
+        if let Err(e) = self.provider.shutdown() {
+            eprintln!("failed to shut down OTLP exporter: {e}");
+        }
+    }
+}
+
+/// Adds the current span's trace context to `headers` as a W3C
+/// `traceparent` header. Does nothing unless [`OtlpTracing::init`] started
+/// an exporter.
+pub fn inject_trace_context(headers: &mut HeaderMap) {
+    let cx = tracing::Span::current().context();
+    opentelemetry::global::get_text_map_propagator(|propagator| {
+        propagator.inject_context(&cx, &mut HeaderInjector(headers));
+    });
+}
+
+/// Writes propagated fields into HTTP headers.
+struct HeaderInjector<'a>(&'a mut HeaderMap);
+
+impl Injector for HeaderInjector<'_> {
+    fn set(&mut self, key: &str, value: String) {
+        if let (Ok(name), Ok(value)) = (
+            HeaderName::from_bytes(key.as_bytes()),
+            HeaderValue::from_str(&value),
+        ) {
+            self.0.insert(name, value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    #[test]
+    fn injects_traceparent_of_the_current_span() {
+        opentelemetry::global::set_text_map_propagator(TraceContextPropagator::new());
+        let tracing = OtlpTracing {
+            provider: SdkTracerProvider::builder().build(),
+        };
+        let subscriber = tracing_subscriber::registry().with(tracing.layer());
+
+        let mut headers = HeaderMap::new();
+        tracing::subscriber::with_default(subscriber, || {
+            let span = tracing::info_span!("import_block", height = 1);
+            let _entered = span.enter();
+            inject_trace_context(&mut headers);
+        });
+
+        let traceparent = headers
+            .get("traceparent")
+            .expect("traceparent header")
+            .to_str()
+            .unwrap();
+        // version-traceid-spanid-flags, sampled
+        assert_eq!(traceparent.split('-').count(), 4);
+        assert!(traceparent.ends_with("-01"), "{traceparent}");
+    }
+}
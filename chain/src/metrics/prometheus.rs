@@ -1,10 +1,22 @@
 //! Prometheus-backed metrics and HTTP exporter.
 //!
 //! This module defines a [`MetricsRegistry`] that owns a Prometheus
-//! registry and a set of strongly-typed consensus metrics, and an
-//! async HTTP exporter that serves `/metrics` using `hyper`.
+//! registry and a set of strongly-typed consensus and storage metrics, and
+//! an async HTTP exporter that serves `/metrics` using `hyper` on one or
+//! more [`Listener`]s. [`collect_storage_metrics`] keeps the storage gauges
+//! current from a background task.
+//!
+//! [`MetricsRegistry::from_config`] applies the namespace, constant labels
+//! and [`HistogramBuckets`] of a [`MetricsConfig`]; the defaults are the
+//! `chain` namespace, no constant labels and the built-in buckets.
 
-use std::{convert::Infallible, net::SocketAddr, sync::Arc};
+use std::{
+    collections::HashMap,
+    convert::Infallible,
+    net::SocketAddr,
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
 
 use bytes::Bytes;
 use http_body_util::Full;
@@ -13,12 +25,59 @@ use hyper::{
     service::service_fn,
 };
 use hyper_util::rt::TokioIo;
-use tokio::net::TcpListener;
 
 use prometheus::{
-    self, Encoder, Histogram, HistogramOpts, IntCounter, Opts, Registry, TextEncoder,
+    self, Encoder, Gauge, Histogram, HistogramOpts, HistogramVec, IntCounter, IntCounterVec,
+    IntGauge, IntGaugeVec, Opts, Registry, TextEncoder, proto::MetricFamily,
 };
 
+use serde::Deserialize;
+
+use crate::config::MetricsConfig;
+use crate::consensus::{BlockStore, PendingBlock, ReorgEvent};
+use crate::execution::ChainState;
+use crate::network::{Compression, Direction, FrameStats};
+use crate::shutdown::Shutdown;
+use crate::storage::{PersistentStore, RocksDbStats, SharedStore, StorageError};
+use crate::transport::{Listener, ListenerConfig};
+use crate::types::Header;
+
+/// Bucket overrides for the histograms of [`ConsensusMetrics`], each
+/// given as strictly increasing upper bounds. Unset histograms keep their
+/// built-in buckets.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HistogramBuckets {
+    /// Buckets of `consensus_block_validation_seconds`.
+    pub block_validation_seconds: Option<Vec<f64>>,
+    /// Buckets of `consensus_ml_auth_seconds`.
+    pub ml_auth_seconds: Option<Vec<f64>>,
+    /// Buckets of `consensus_reorg_depth`.
+    pub reorg_depth: Option<Vec<f64>>,
+    /// Buckets of `mempool_registration_inclusion_delay_blocks`.
+    pub registration_inclusion_delay_blocks: Option<Vec<f64>>,
+    /// Buckets of `network_gossip_compression_ratio`.
+    pub gossip_compression_ratio: Option<Vec<f64>>,
+}
+
+impl HistogramBuckets {
+    /// Returns every override, keyed by its field name.
+    pub fn overrides(&self) -> impl Iterator<Item = (&'static str, &[f64])> {
+        [
+            ("block_validation_seconds", &self.block_validation_seconds),
+            ("ml_auth_seconds", &self.ml_auth_seconds),
+            ("reorg_depth", &self.reorg_depth),
+            (
+                "registration_inclusion_delay_blocks",
+                &self.registration_inclusion_delay_blocks,
+            ),
+            ("gossip_compression_ratio", &self.gossip_compression_ratio),
+        ]
+        .into_iter()
+        .filter_map(|(name, buckets)| Some((name, buckets.as_deref()?)))
+    }
+}
+
 /// Consensus-related Prometheus metrics.
 ///
 /// These are registered into a [`Registry`] and can be updated from
@@ -27,44 +86,156 @@ use prometheus::{
 pub struct ConsensusMetrics {
     /// Latency of full block validation (base + ML), in seconds.
     pub block_validation_seconds: Histogram,
-    /// Time spent in ML authenticity checks (`V_auth`), in seconds.
-    pub ml_auth_seconds: Histogram,
+    /// Time the ML verifier took to judge an artefact, in seconds,
+    /// labelled by watermark `scheme` and `outcome`, see
+    /// [`ConsensusMetrics::observe_ml_verdict`].
+    pub ml_auth_seconds: HistogramVec,
+    /// ML verdicts, labelled by watermark `scheme` and `outcome`.
+    pub ml_verdicts: IntCounterVec,
     /// Ratio of ML cache hits over total ML lookups (0–1).
     ///
     /// This is intended to be updated periodically by whatever component
     /// manages the ML verdict cache.
     pub ml_cache_hit_ratio: prometheus::Gauge,
+    /// Configured ML outage policy: 1 for the active `policy` label, see
+    /// [`MlOutagePolicy`](crate::validation::MlOutagePolicy).
+    pub ml_outage_policy: IntGaugeVec,
+    /// Blocks checked while the ML service was unreachable, labelled by
+    /// the outage `policy` applied to them.
+    pub ml_outage_blocks: IntCounterVec,
+    /// Blocks waiting in the
+    /// [`RevalidationQueue`](crate::validation::RevalidationQueue).
+    pub ml_revalidation_queue: IntGauge,
+    /// Deferred blocks re-checked, labelled by `outcome` (`accepted`,
+    /// `rejected`, `retried`, or `dropped`).
+    pub ml_revalidations: IntCounterVec,
     /// Number of blocks rejected due to ML authenticity failures.
     pub blocks_rejected_ml: IntCounter,
+    /// Number of blocks rejected by validation or execution, by
+    /// [`ConsensusError::kind`](crate::consensus::ConsensusError::kind).
+    pub blocks_rejected: IntCounterVec,
+    /// Number of blocks deleted by stale fork garbage collection.
+    pub gc_blocks_pruned: IntCounter,
+    /// Bytes reclaimed by stale fork garbage collection.
+    pub gc_reclaimed_bytes: IntCounter,
+    /// Old canonical-history blocks deleted by history pruning.
+    pub pruned_blocks: IntCounter,
+    /// Number of tip switches to a side branch.
+    pub reorgs: IntCounter,
+    /// Blocks retracted per reorg.
+    pub reorg_depth: Histogram,
+    /// Outbound HTTP requests, labelled by `client` and `outcome`.
+    pub outbound_requests: IntCounterVec,
+    /// Height of the canonical tip.
+    pub chain_height: IntGauge,
+    /// Timestamp of the canonical tip, in Unix seconds.
+    pub chain_tip_timestamp_seconds: IntGauge,
+    /// Height of the last block committed by a quorum of validator votes.
+    pub committed_height: IntGauge,
+    /// Seconds between the canonical tip's timestamp and the last scrape,
+    /// see [`ConsensusMetrics::refresh_tip_age`].
+    pub chain_tip_age_seconds: prometheus::Gauge,
+    /// Number of blocks proposed by this node and imported.
+    pub blocks_proposed: IntCounter,
+    /// Number of blocks imported, whether or not they became the tip.
+    pub blocks_imported: IntCounter,
+    /// Number of blocks parked in the orphan buffer.
+    pub blocks_orphaned: IntCounter,
+    /// Blocks currently waiting in the orphan buffer.
+    pub orphan_blocks: IntGauge,
+    /// Arrival time of the oldest buffered orphan, in Unix seconds; zero
+    /// while the buffer is empty.
+    pub orphan_oldest_received_seconds: IntGauge,
+    /// Seconds the oldest buffered orphan has waited at the last scrape,
+    /// see [`ConsensusMetrics::refresh_pending_ages`].
+    pub orphan_oldest_age_seconds: Gauge,
+    /// Number of transactions in the mempool.
+    pub mempool_transactions: IntGauge,
+    /// Total canonical size of the transactions in the mempool, in bytes.
+    pub mempool_bytes: IntGauge,
+    /// Number of registered artefacts in chain state.
+    pub registered_artefacts: IntGauge,
+    /// Number of registered artefacts, labelled by watermark `scheme`.
+    pub registered_artefacts_by_scheme: IntGaugeVec,
+    /// Timestamp of the block holding the most recent registration, in Unix
+    /// seconds, labelled by watermark `scheme`.
+    pub last_registration_timestamp_seconds: IntGaugeVec,
+    /// Blocks between a registration's mempool admission and its
+    /// inclusion, see [`InclusionTracker`](crate::mempool::InclusionTracker).
+    pub registration_inclusion_delay_blocks: Histogram,
+    /// Network message bytes, labelled by `direction` and `encoding`
+    /// (`payload` before compression, `wire` as framed).
+    pub gossip_bytes: IntCounterVec,
+    /// Wire size over payload size of compressed frames, labelled by
+    /// `direction`.
+    pub gossip_compression_ratio: HistogramVec,
+    /// Tip height of the peer being synced from, see
+    /// [`BlockSync`](crate::network::BlockSync).
+    pub sync_target_height: IntGauge,
+    /// Blocks downloaded and imported by block sync.
+    pub sync_blocks_imported: IntCounter,
+    /// Peers connected over the libp2p transport.
+    pub p2p_peers: IntGauge,
+    /// Announced blocks block sync has not imported yet.
+    pub sync_queue_blocks: IntGauge,
+    /// Announcement time of the oldest block in the sync import queue, in
+    /// Unix seconds; zero while the queue is empty.
+    pub sync_queue_oldest_received_seconds: IntGauge,
+    /// Seconds the oldest block in the sync import queue has waited at the
+    /// last scrape, see [`ConsensusMetrics::refresh_pending_ages`].
+    pub sync_queue_oldest_age_seconds: Gauge,
 }
 
 impl ConsensusMetrics {
-    /// Registers consensus metrics into the given `Registry`.
-    pub fn register(registry: &Registry) -> Result<Self, prometheus::Error> {
+    /// Registers consensus metrics into the given `Registry`, with the
+    /// histogram buckets overridden by `buckets`.
+    pub fn register(
+        registry: &Registry,
+        buckets: &HistogramBuckets,
+    ) -> Result<Self, prometheus::Error> {
+        let or_default =
+            |custom: &Option<Vec<f64>>, default: Vec<f64>| custom.clone().unwrap_or(default);
+
         // Block validation latency.
         let block_validation_seconds = Histogram::with_opts(
             HistogramOpts::new(
                 "consensus_block_validation_seconds",
                 "Time to validate a block (base + ML) in seconds",
             )
-            .buckets(vec![
-                0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0,
-            ]),
+            .buckets(or_default(
+                &buckets.block_validation_seconds,
+                vec![
+                    0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0,
+                ],
+            )),
         )?;
         registry.register(Box::new(block_validation_seconds.clone()))?;
 
-        // ML authenticity latency.
-        let ml_auth_seconds = Histogram::with_opts(
+        // ML authenticity latency and verdicts per scheme.
+        let ml_auth_seconds = HistogramVec::new(
             HistogramOpts::new(
                 "consensus_ml_auth_seconds",
-                "Time spent in ML authenticity checks (V_auth) per block in seconds",
+                "Time spent in ML authenticity checks (V_auth) per artefact in seconds by watermark scheme and outcome",
             )
-            .buckets(vec![
-                0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
-            ]),
+            .buckets(or_default(
+                &buckets.ml_auth_seconds,
+                vec![
+                    0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+                ],
+            )),
+            &["scheme", "outcome"],
         )?;
         registry.register(Box::new(ml_auth_seconds.clone()))?;
 
+        let ml_verdicts = IntCounterVec::new(
+            Opts::new(
+                "consensus_ml_verdicts_total",
+                "Total number of ML verdicts by watermark scheme and outcome",
+            ),
+            &["scheme", "outcome"],
+        )?;
+        registry.register(Box::new(ml_verdicts.clone()))?;
+
         // ML cache hit ratio.
         let ml_cache_hit_ratio = prometheus::Gauge::with_opts(Opts::new(
             "consensus_ml_cache_hit_ratio",
@@ -72,6 +243,40 @@ impl ConsensusMetrics {
         ))?;
         registry.register(Box::new(ml_cache_hit_ratio.clone()))?;
 
+        // ML outage handling and background revalidation.
+        let ml_outage_policy = IntGaugeVec::new(
+            Opts::new(
+                "consensus_ml_outage_policy",
+                "Configured ML outage policy (1 for the active policy)",
+            ),
+            &["policy"],
+        )?;
+        registry.register(Box::new(ml_outage_policy.clone()))?;
+
+        let ml_outage_blocks = IntCounterVec::new(
+            Opts::new(
+                "consensus_ml_outage_blocks_total",
+                "Total number of blocks checked while the ML service was unreachable, by outage policy",
+            ),
+            &["policy"],
+        )?;
+        registry.register(Box::new(ml_outage_blocks.clone()))?;
+
+        let ml_revalidation_queue = IntGauge::with_opts(Opts::new(
+            "consensus_ml_revalidation_queue_len",
+            "Number of blocks waiting for an ML re-check",
+        ))?;
+        registry.register(Box::new(ml_revalidation_queue.clone()))?;
+
+        let ml_revalidations = IntCounterVec::new(
+            Opts::new(
+                "consensus_ml_revalidations_total",
+                "Total number of deferred blocks re-checked, by outcome",
+            ),
+            &["outcome"],
+        )?;
+        registry.register(Box::new(ml_revalidations.clone()))?;
+
         // Blocks rejected due to ML authenticity failures.
         let blocks_rejected_ml = IntCounter::with_opts(Opts::new(
             "consensus_blocks_rejected_ml",
@@ -79,16 +284,582 @@ impl ConsensusMetrics {
         ))?;
         registry.register(Box::new(blocks_rejected_ml.clone()))?;
 
+        let blocks_rejected = IntCounterVec::new(
+            Opts::new(
+                "consensus_blocks_rejected_total",
+                "Total number of blocks rejected by validation or execution, by reason",
+            ),
+            &["reason"],
+        )?;
+        registry.register(Box::new(blocks_rejected.clone()))?;
+
+        // Stale fork garbage collection.
+        let gc_blocks_pruned = IntCounter::with_opts(Opts::new(
+            "consensus_gc_blocks_pruned",
+            "Total number of stale fork blocks deleted by garbage collection",
+        ))?;
+        registry.register(Box::new(gc_blocks_pruned.clone()))?;
+
+        let gc_reclaimed_bytes = IntCounter::with_opts(Opts::new(
+            "consensus_gc_reclaimed_bytes",
+            "Total bytes of block data reclaimed by stale fork garbage collection",
+        ))?;
+        registry.register(Box::new(gc_reclaimed_bytes.clone()))?;
+
+        let pruned_blocks = IntCounter::with_opts(Opts::new(
+            "consensus_pruned_blocks",
+            "Total number of old blocks deleted by history pruning",
+        ))?;
+        registry.register(Box::new(pruned_blocks.clone()))?;
+
+        // Chain reorganisations, see `ConsensusMetrics::observe_reorg`.
+        let reorgs = IntCounter::with_opts(Opts::new(
+            "consensus_reorgs",
+            "Total number of tip switches to a side branch",
+        ))?;
+        registry.register(Box::new(reorgs.clone()))?;
+
+        let reorg_depth = Histogram::with_opts(
+            HistogramOpts::new(
+                "consensus_reorg_depth",
+                "Number of canonical blocks retracted per reorg",
+            )
+            .buckets(or_default(
+                &buckets.reorg_depth,
+                vec![1.0, 2.0, 3.0, 5.0, 10.0, 20.0, 50.0, 100.0],
+            )),
+        )?;
+        registry.register(Box::new(reorg_depth.clone()))?;
+
+        // Outbound HTTP requests made through `transport::HttpTransport`.
+        let outbound_requests = IntCounterVec::new(
+            Opts::new(
+                "transport_outbound_requests",
+                "Total number of outbound HTTP requests by client and outcome",
+            ),
+            &["client", "outcome"],
+        )?;
+        registry.register(Box::new(outbound_requests.clone()))?;
+
+        // Chain-derived statistics, see `ConsensusMetrics::backfill`.
+        let chain_height = IntGauge::with_opts(Opts::new(
+            "consensus_chain_height",
+            "Height of the canonical tip",
+        ))?;
+        registry.register(Box::new(chain_height.clone()))?;
+
+        let committed_height = IntGauge::with_opts(Opts::new(
+            "consensus_committed_height",
+            "Height of the last block committed by a 2/3 quorum of validator votes",
+        ))?;
+        registry.register(Box::new(committed_height.clone()))?;
+
+        let registered_artefacts = IntGauge::with_opts(Opts::new(
+            "consensus_registered_artefacts",
+            "Number of registered ML artefacts in chain state",
+        ))?;
+        registry.register(Box::new(registered_artefacts.clone()))?;
+
+        let registered_artefacts_by_scheme = IntGaugeVec::new(
+            Opts::new(
+                "consensus_registered_artefacts_by_scheme",
+                "Number of registered ML artefacts by watermark scheme",
+            ),
+            &["scheme"],
+        )?;
+        registry.register(Box::new(registered_artefacts_by_scheme.clone()))?;
+
+        let last_registration_timestamp_seconds = IntGaugeVec::new(
+            Opts::new(
+                "consensus_last_registration_timestamp_seconds",
+                "Unix timestamp of the block holding the latest artefact registration by watermark scheme",
+            ),
+            &["scheme"],
+        )?;
+        registry.register(Box::new(last_registration_timestamp_seconds.clone()))?;
+
+        // Tip and import progress, updated by the engine, see
+        // `ConsensusEngine::with_metrics`.
+        let chain_tip_timestamp_seconds = IntGauge::with_opts(Opts::new(
+            "consensus_chain_tip_timestamp_seconds",
+            "Unix timestamp of the canonical tip",
+        ))?;
+        registry.register(Box::new(chain_tip_timestamp_seconds.clone()))?;
+
+        let chain_tip_age_seconds = prometheus::Gauge::with_opts(Opts::new(
+            "consensus_chain_tip_age_seconds",
+            "Seconds since the timestamp of the canonical tip",
+        ))?;
+        registry.register(Box::new(chain_tip_age_seconds.clone()))?;
+
+        let blocks_proposed = IntCounter::with_opts(Opts::new(
+            "consensus_blocks_proposed",
+            "Total number of blocks proposed by this node and imported",
+        ))?;
+        registry.register(Box::new(blocks_proposed.clone()))?;
+
+        let blocks_imported = IntCounter::with_opts(Opts::new(
+            "consensus_blocks_imported",
+            "Total number of blocks imported, on the canonical chain or a side branch",
+        ))?;
+        registry.register(Box::new(blocks_imported.clone()))?;
+
+        let blocks_orphaned = IntCounter::with_opts(Opts::new(
+            "consensus_blocks_orphaned",
+            "Total number of blocks buffered because their parent was unknown",
+        ))?;
+        registry.register(Box::new(blocks_orphaned.clone()))?;
+
+        // Orphan buffer contents, updated by the engine.
+        let orphan_blocks = IntGauge::with_opts(Opts::new(
+            "consensus_orphan_blocks",
+            "Number of blocks waiting in the orphan buffer for an unknown parent",
+        ))?;
+        registry.register(Box::new(orphan_blocks.clone()))?;
+
+        let orphan_oldest_received_seconds = IntGauge::with_opts(Opts::new(
+            "consensus_orphan_oldest_received_timestamp_seconds",
+            "Arrival time of the oldest buffered orphan in Unix seconds, 0 if none",
+        ))?;
+        registry.register(Box::new(orphan_oldest_received_seconds.clone()))?;
+
+        let orphan_oldest_age_seconds = Gauge::with_opts(Opts::new(
+            "consensus_orphan_oldest_age_seconds",
+            "Seconds the oldest buffered orphan has waited for its parent",
+        ))?;
+        registry.register(Box::new(orphan_oldest_age_seconds.clone()))?;
+
+        // Mempool size, updated by `Mempool::with_metrics`.
+        let mempool_transactions = IntGauge::with_opts(Opts::new(
+            "mempool_transactions",
+            "Number of transactions in the mempool",
+        ))?;
+        registry.register(Box::new(mempool_transactions.clone()))?;
+
+        let mempool_bytes = IntGauge::with_opts(Opts::new(
+            "mempool_bytes",
+            "Total canonical size of the transactions in the mempool in bytes",
+        ))?;
+        registry.register(Box::new(mempool_bytes.clone()))?;
+
+        // Registration inclusion delay.
+        let registration_inclusion_delay_blocks = Histogram::with_opts(
+            HistogramOpts::new(
+                "mempool_registration_inclusion_delay_blocks",
+                "Blocks between a model registration's mempool admission and its inclusion",
+            )
+            .buckets(or_default(
+                &buckets.registration_inclusion_delay_blocks,
+                vec![1.0, 2.0, 3.0, 5.0, 10.0, 20.0, 50.0, 100.0],
+            )),
+        )?;
+        registry.register(Box::new(registration_inclusion_delay_blocks.clone()))?;
+
+        // Network framing, see `ConsensusMetrics::observe_gossip_frame`.
+        let gossip_bytes = IntCounterVec::new(
+            Opts::new(
+                "network_gossip_bytes",
+                "Total network message bytes by direction, before compression (payload) and as framed (wire)",
+            ),
+            &["direction", "encoding"],
+        )?;
+        registry.register(Box::new(gossip_bytes.clone()))?;
+
+        let gossip_compression_ratio = HistogramVec::new(
+            HistogramOpts::new(
+                "network_gossip_compression_ratio",
+                "Wire size over payload size of compressed network messages",
+            )
+            .buckets(or_default(
+                &buckets.gossip_compression_ratio,
+                vec![0.05, 0.1, 0.2, 0.3, 0.4, 0.5, 0.6, 0.7, 0.8, 0.9, 1.0],
+            )),
+            &["direction"],
+        )?;
+        registry.register(Box::new(gossip_compression_ratio.clone()))?;
+
+        // Block sync progress, see `network::BlockSync`.
+        let sync_target_height = IntGauge::with_opts(Opts::new(
+            "network_sync_target_height",
+            "Tip height of the peer the node is syncing from",
+        ))?;
+        registry.register(Box::new(sync_target_height.clone()))?;
+
+        let sync_blocks_imported = IntCounter::with_opts(Opts::new(
+            "network_sync_blocks_imported",
+            "Total blocks downloaded from peers and imported by block sync",
+        ))?;
+        registry.register(Box::new(sync_blocks_imported.clone()))?;
+
+        // Connected peers, updated by `P2pNetwork::with_metrics`.
+        let p2p_peers = IntGauge::with_opts(Opts::new(
+            "network_p2p_peers",
+            "Number of peers connected over the libp2p transport",
+        ))?;
+        registry.register(Box::new(p2p_peers.clone()))?;
+
+        let sync_queue_blocks = IntGauge::with_opts(Opts::new(
+            "network_sync_queue_blocks",
+            "Number of announced blocks block sync has not imported yet",
+        ))?;
+        registry.register(Box::new(sync_queue_blocks.clone()))?;
+
+        let sync_queue_oldest_received_seconds = IntGauge::with_opts(Opts::new(
+            "network_sync_queue_oldest_received_timestamp_seconds",
+            "Announcement time of the oldest block in the sync import queue in Unix seconds, 0 if none",
+        ))?;
+        registry.register(Box::new(sync_queue_oldest_received_seconds.clone()))?;
+
+        let sync_queue_oldest_age_seconds = Gauge::with_opts(Opts::new(
+            "network_sync_queue_oldest_age_seconds",
+            "Seconds the oldest block in the sync import queue has waited",
+        ))?;
+        registry.register(Box::new(sync_queue_oldest_age_seconds.clone()))?;
+
         Ok(Self {
             block_validation_seconds,
             ml_auth_seconds,
+            ml_verdicts,
             ml_cache_hit_ratio,
+            ml_outage_policy,
+            ml_outage_blocks,
+            ml_revalidation_queue,
+            ml_revalidations,
             blocks_rejected_ml,
+            blocks_rejected,
+            gc_blocks_pruned,
+            gc_reclaimed_bytes,
+            pruned_blocks,
+            reorgs,
+            reorg_depth,
+            outbound_requests,
+            chain_height,
+            chain_tip_timestamp_seconds,
+            committed_height,
+            chain_tip_age_seconds,
+            blocks_proposed,
+            blocks_imported,
+            blocks_orphaned,
+            orphan_blocks,
+            orphan_oldest_received_seconds,
+            orphan_oldest_age_seconds,
+            mempool_transactions,
+            mempool_bytes,
+            registered_artefacts,
+            registered_artefacts_by_scheme,
+            last_registration_timestamp_seconds,
+            registration_inclusion_delay_blocks,
+            gossip_bytes,
+            gossip_compression_ratio,
+            sync_target_height,
+            sync_blocks_imported,
+            p2p_peers,
+            sync_queue_blocks,
+            sync_queue_oldest_received_seconds,
+            sync_queue_oldest_age_seconds,
+        })
+    }
+
+    /// Records the ML verdict for one artefact of watermark `scheme`.
+    ///
+    /// `outcome` is `accepted`, `rejected`, `error` (the verifier failed),
+    /// or `timeout` (the block's verification budget ran out), and
+    /// `latency` is how long the verifier call took.
+    pub fn observe_ml_verdict(&self, scheme: &str, outcome: &str, latency: Duration) {
+        self.ml_verdicts.with_label_values(&[scheme, outcome]).inc();
+        self.ml_auth_seconds
+            .with_label_values(&[scheme, outcome])
+            .observe(latency.as_secs_f64());
+    }
+
+    /// Marks `policy` as the active ML outage policy.
+    pub fn observe_ml_outage_policy(&self, policy: &str) {
+        self.ml_outage_policy.reset();
+        self.ml_outage_policy.with_label_values(&[policy]).set(1);
+    }
+
+    /// Records a reorg reported by the engine's reorg listener.
+    pub fn observe_reorg(&self, event: &ReorgEvent) {
+        self.reorgs.inc();
+        self.reorg_depth.observe(event.depth as f64);
+    }
+
+    /// Records `tip` as the new canonical tip.
+    pub fn observe_tip(&self, tip: &Header) {
+        self.chain_height.set(tip.height as i64);
+        self.chain_tip_timestamp_seconds.set(tip.timestamp as i64);
+    }
+
+    /// Sets the tip age gauge from the tip timestamp and `now`, in Unix
+    /// seconds. Left at zero until a tip has been recorded.
+    pub fn refresh_tip_age(&self, now: u64) {
+        let tip_timestamp = self.chain_tip_timestamp_seconds.get();
+        if tip_timestamp > 0 {
+            self.chain_tip_age_seconds
+                .set(now.saturating_sub(tip_timestamp as u64) as f64);
+        }
+    }
+
+    /// Sets the orphan gauges from the buffer's contents.
+    pub fn observe_orphans(&self, orphans: &[PendingBlock]) {
+        observe_pending(
+            orphans,
+            &self.orphan_blocks,
+            &self.orphan_oldest_received_seconds,
+            &self.orphan_oldest_age_seconds,
+        );
+    }
+
+    /// Sets the sync import queue gauges from the queue's contents.
+    pub fn observe_sync_queue(&self, queue: &[PendingBlock]) {
+        observe_pending(
+            queue,
+            &self.sync_queue_blocks,
+            &self.sync_queue_oldest_received_seconds,
+            &self.sync_queue_oldest_age_seconds,
+        );
+    }
+
+    /// Sets the oldest-age gauges of the orphan buffer and the sync import
+    /// queue from their oldest arrival times and `now`, in Unix seconds.
+    pub fn refresh_pending_ages(&self, now: u64) {
+        for (received, age) in [
+            (
+                &self.orphan_oldest_received_seconds,
+                &self.orphan_oldest_age_seconds,
+            ),
+            (
+                &self.sync_queue_oldest_received_seconds,
+                &self.sync_queue_oldest_age_seconds,
+            ),
+        ] {
+            let received = received.get();
+            age.set(if received > 0 {
+                now.saturating_sub(received as u64) as f64
+            } else {
+                0.0
+            });
+        }
+    }
+
+    /// Records one network frame encoded or decoded by a
+    /// [`PeerCodec`](crate::network::PeerCodec).
+    pub fn observe_gossip_frame(&self, direction: Direction, stats: &FrameStats) {
+        let direction = direction.as_str();
+        self.gossip_bytes
+            .with_label_values(&[direction, "payload"])
+            .inc_by(stats.payload_bytes as u64);
+        self.gossip_bytes
+            .with_label_values(&[direction, "wire"])
+            .inc_by(stats.wire_bytes as u64);
+        if stats.compression == Compression::Zstd {
+            self.gossip_compression_ratio
+                .with_label_values(&[direction])
+                .observe(stats.ratio());
+        }
+    }
+
+    /// Sets the chain-derived gauges from the current chain state and
+    /// canonical chain.
+    ///
+    /// Called once on startup, after the engine has replayed the stored
+    /// chain, so dashboards show the registered artefacts straight away
+    /// instead of starting from zero; calling it again after each imported
+    /// block keeps the gauges current.
+    pub fn backfill<S: BlockStore>(
+        &self,
+        state: &ChainState,
+        store: &S,
+    ) -> Result<(), StorageError> {
+        if let Some(tip) = store.tip()?
+            && let Some(block) = store.get_block(&tip)?
+        {
+            self.observe_tip(&block.header);
+        } else {
+            self.chain_height.set(0);
+        }
+
+        // scheme -> (artefact count, latest registration height)
+        let mut schemes: HashMap<&str, (i64, u64)> = HashMap::new();
+        let mut total = 0;
+        for (_, meta) in state.artefacts() {
+            let entry = schemes.entry(meta.evidence.scheme_id.as_str()).or_default();
+            entry.0 += 1;
+            entry.1 = entry.1.max(meta.registered_at);
+            total += 1;
+        }
+        self.registered_artefacts.set(total);
+
+        self.registered_artefacts_by_scheme.reset();
+        self.last_registration_timestamp_seconds.reset();
+        for (scheme, (count, height)) in schemes {
+            self.registered_artefacts_by_scheme
+                .with_label_values(&[scheme])
+                .set(count);
+            if let Some(block) = store.get_block_by_height(height)? {
+                self.last_registration_timestamp_seconds
+                    .with_label_values(&[scheme])
+                    .set(block.header.timestamp as i64);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// RocksDB internals as Prometheus gauges, refreshed by
+/// [`collect_storage_metrics`].
+#[derive(Clone)]
+pub struct StorageMetrics {
+    /// Estimated number of keys, labelled by column family `cf`.
+    pub rocksdb_estimated_keys: IntGaugeVec,
+    /// Total size of the SST files in bytes, labelled by column family
+    /// `cf`.
+    pub rocksdb_sst_bytes: IntGaugeVec,
+    /// Bytes compaction still has to rewrite, labelled by column family
+    /// `cf`.
+    pub rocksdb_pending_compaction_bytes: IntGaugeVec,
+    /// Block cache hits since the database was opened.
+    pub rocksdb_block_cache_hits: IntGauge,
+    /// Block cache misses since the database was opened.
+    pub rocksdb_block_cache_misses: IntGauge,
+    /// Share of block cache lookups that hit (0–1).
+    pub rocksdb_block_cache_hit_ratio: Gauge,
+}
+
+impl StorageMetrics {
+    /// Registers all storage metrics into `registry`.
+    pub fn register(registry: &Registry) -> Result<Self, prometheus::Error> {
+        let rocksdb_estimated_keys = IntGaugeVec::new(
+            Opts::new(
+                "storage_rocksdb_estimated_keys",
+                "Estimated number of keys per RocksDB column family",
+            ),
+            &["cf"],
+        )?;
+        registry.register(Box::new(rocksdb_estimated_keys.clone()))?;
+
+        let rocksdb_sst_bytes = IntGaugeVec::new(
+            Opts::new(
+                "storage_rocksdb_sst_bytes",
+                "Total size of the SST files per RocksDB column family, in bytes",
+            ),
+            &["cf"],
+        )?;
+        registry.register(Box::new(rocksdb_sst_bytes.clone()))?;
+
+        let rocksdb_pending_compaction_bytes = IntGaugeVec::new(
+            Opts::new(
+                "storage_rocksdb_pending_compaction_bytes",
+                "Estimated bytes pending compaction per RocksDB column family",
+            ),
+            &["cf"],
+        )?;
+        registry.register(Box::new(rocksdb_pending_compaction_bytes.clone()))?;
+
+        let rocksdb_block_cache_hits = IntGauge::with_opts(Opts::new(
+            "storage_rocksdb_block_cache_hits",
+            "RocksDB block cache hits since the database was opened",
+        ))?;
+        registry.register(Box::new(rocksdb_block_cache_hits.clone()))?;
+
+        let rocksdb_block_cache_misses = IntGauge::with_opts(Opts::new(
+            "storage_rocksdb_block_cache_misses",
+            "RocksDB block cache misses since the database was opened",
+        ))?;
+        registry.register(Box::new(rocksdb_block_cache_misses.clone()))?;
+
+        let rocksdb_block_cache_hit_ratio = Gauge::with_opts(Opts::new(
+            "storage_rocksdb_block_cache_hit_ratio",
+            "Share of RocksDB block cache lookups that hit (0-1)",
+        ))?;
+        registry.register(Box::new(rocksdb_block_cache_hit_ratio.clone()))?;
+
+        Ok(Self {
+            rocksdb_estimated_keys,
+            rocksdb_sst_bytes,
+            rocksdb_pending_compaction_bytes,
+            rocksdb_block_cache_hits,
+            rocksdb_block_cache_misses,
+            rocksdb_block_cache_hit_ratio,
         })
     }
+
+    /// Sets the RocksDB gauges from `stats`.
+    pub fn observe_rocksdb(&self, stats: &RocksDbStats) {
+        let gauge = |value: u64| i64::try_from(value).unwrap_or(i64::MAX);
+        for cf in &stats.column_families {
+            self.rocksdb_estimated_keys
+                .with_label_values(&[cf.name])
+                .set(gauge(cf.estimated_keys));
+            self.rocksdb_sst_bytes
+                .with_label_values(&[cf.name])
+                .set(gauge(cf.sst_bytes));
+            self.rocksdb_pending_compaction_bytes
+                .with_label_values(&[cf.name])
+                .set(gauge(cf.pending_compaction_bytes));
+        }
+        self.rocksdb_block_cache_hits
+            .set(gauge(stats.block_cache_hits));
+        self.rocksdb_block_cache_misses
+            .set(gauge(stats.block_cache_misses));
+        if let Some(ratio) = stats.block_cache_hit_ratio() {
+            self.rocksdb_block_cache_hit_ratio.set(ratio);
+        }
+    }
+}
+
+/// Sets a count gauge, an oldest-arrival gauge and its age gauge from
+/// `pending`.
+fn observe_pending(
+    pending: &[PendingBlock],
+    count: &IntGauge,
+    oldest_received: &IntGauge,
+    oldest_age: &Gauge,
+) {
+    count.set(pending.len() as i64);
+    match pending.iter().map(|block| block.received).min() {
+        Some(received) => {
+            let secs = received
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |d| d.as_secs());
+            oldest_received.set(secs as i64);
+            oldest_age.set(
+                SystemTime::now()
+                    .duration_since(received)
+                    .map_or(0.0, |d| d.as_secs_f64()),
+            );
+        }
+        None => {
+            oldest_received.set(0);
+            oldest_age.set(0.0);
+        }
+    }
+}
+
+/// Refreshes `metrics` from `store` every `interval` until `shutdown`.
+///
+/// Does nothing for backends other than RocksDB. A failed read is logged
+/// and retried on the next tick.
+pub async fn collect_storage_metrics(
+    store: SharedStore<PersistentStore>,
+    metrics: StorageMetrics,
+    interval: Duration,
+    shutdown: Shutdown,
+) {
+    loop {
+        match store.lock().rocksdb_stats() {
+            Ok(Some(stats)) => metrics.observe_rocksdb(&stats),
+            Ok(None) => return,
+            Err(e) => tracing::warn!(error = ?e, "failed to read RocksDB statistics"),
+        }
+        if !shutdown.sleep(interval).await {
+            return;
+        }
+    }
 }
 
-/// Wrapper around a Prometheus registry and the consensus metrics.
+/// Wrapper around a Prometheus registry and the consensus and storage
+/// metrics.
 ///
 /// This is the main handle you pass around in the node. It can be wrapped
 /// in an [`Arc`] and shared across threads/tasks.
@@ -96,23 +867,48 @@ impl ConsensusMetrics {
 pub struct MetricsRegistry {
     registry: Registry,
     pub consensus: ConsensusMetrics,
+    pub storage: StorageMetrics,
 }
 
 impl MetricsRegistry {
     /// Creates a new `MetricsRegistry` with a fresh underlying `Registry`
-    /// and registers the consensus metrics.
+    /// and registers the consensus and storage metrics, using the default
+    /// [`MetricsConfig`].
     pub fn new() -> Result<Self, prometheus::Error> {
-        let registry = Registry::new_custom(Some("chain".to_string()), None)?;
-        let consensus = ConsensusMetrics::register(&registry)?;
+        Self::from_config(&MetricsConfig::default())
+    }
+
+    /// Like [`new`](Self::new), with `cfg`'s namespace (no prefix if
+    /// empty), constant labels and histogram buckets.
+    pub fn from_config(cfg: &MetricsConfig) -> Result<Self, prometheus::Error> {
+        let namespace = (!cfg.namespace.is_empty()).then(|| cfg.namespace.clone());
+        let labels = (!cfg.const_labels.is_empty()).then(|| cfg.const_labels.clone());
+        let registry = Registry::new_custom(namespace, labels)?;
+        let consensus = ConsensusMetrics::register(&registry, &cfg.buckets)?;
+        let storage = StorageMetrics::register(&registry)?;
         Ok(Self {
             registry,
             consensus,
+            storage,
         })
     }
 
+    /// Collects all metrics in this registry.
+    ///
+    /// Refreshes the tip, orphan and sync queue age gauges first, so they
+    /// are current at every scrape or push.
+    pub fn gather(&self) -> Vec<MetricFamily> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+        self.consensus.refresh_tip_age(now);
+        self.consensus.refresh_pending_ages(now);
+        self.registry.gather()
+    }
+
     /// Encodes all metrics in this registry into the Prometheus text format.
     pub fn gather_text(&self) -> String {
-        let metric_families = self.registry.gather();
+        let metric_families = self.gather();
         let mut buffer = Vec::new();
         let encoder = TextEncoder::new();
         if let Err(e) = encoder.encode(&metric_families, &mut buffer) {
@@ -125,8 +921,9 @@ impl MetricsRegistry {
 
 /// Runs an HTTP server that exposes Prometheus metrics.
 ///
-/// The server listens on `addr` and serves `GET /metrics` with the
-/// Prometheus text exposition format. All other paths return 404.
+/// The server listens on `addr` (plain HTTP) and serves `GET /metrics` with the
+/// Prometheus text exposition format. All other paths return 404. It stops
+/// accepting connections and returns `Ok(())` once `shutdown` is triggered.
 ///
 /// This function is `async` and is intended to be spawned onto a Tokio
 /// runtime, e.g.:
@@ -134,17 +931,32 @@ impl MetricsRegistry {
 /// ```ignore
 /// let registry = Arc::new(MetricsRegistry::new()?);
 /// let addr: SocketAddr = "127.0.0.1:9898".parse()?;
-/// tokio::spawn(run_prometheus_http_server(registry.clone(), addr));
+/// tokio::spawn(run_prometheus_http_server(registry.clone(), addr, shutdown.clone()));
 /// ```
 pub async fn run_prometheus_http_server(
     metrics: Arc<MetricsRegistry>,
     addr: SocketAddr,
+    shutdown: Shutdown,
 ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-    let listener = TcpListener::bind(addr).await?;
+    let listener = Listener::bind(&ListenerConfig::plain(addr)).await?;
+    serve_prometheus(metrics, listener, shutdown).await
+}
 
+/// Serves Prometheus metrics on an already bound `listener`.
+///
+/// Like [`run_prometheus_http_server`], but the listener may use TLS. Bind
+/// one listener per configured address and spawn one server for each.
+pub async fn serve_prometheus(
+    metrics: Arc<MetricsRegistry>,
+    mut listener: Listener,
+    shutdown: Shutdown,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     loop {
-        let (stream, _) = listener.accept().await?;
-        let io = TokioIo::new(stream);
+        let (conn, _) = tokio::select! {
+            accepted = listener.accept() => accepted?,
+            () = shutdown.wait() => return Ok(()),
+        };
+        let io = TokioIo::new(conn);
         let metrics = metrics.clone();
 
         tokio::spawn(async move {
@@ -188,12 +1000,24 @@ mod tests {
     #[test]
     fn consensus_metrics_register_and_record() {
         let registry = Registry::new();
-        let metrics = ConsensusMetrics::register(&registry).expect("register metrics");
+        let metrics = ConsensusMetrics::register(&registry, &HistogramBuckets::default())
+            .expect("register metrics");
 
         metrics.block_validation_seconds.observe(0.123);
-        metrics.ml_auth_seconds.observe(0.045);
+        metrics.observe_ml_verdict("multi_factor_v1", "accepted", Duration::from_millis(45));
         metrics.ml_cache_hit_ratio.set(0.75);
         metrics.blocks_rejected_ml.inc();
+        metrics.gc_blocks_pruned.inc_by(2);
+        metrics.gc_reclaimed_bytes.inc_by(512);
+        metrics.pruned_blocks.inc_by(3);
+        metrics.observe_gossip_frame(
+            Direction::Outbound,
+            &FrameStats {
+                compression: Compression::Zstd,
+                payload_bytes: 4_000,
+                wire_bytes: 1_000,
+            },
+        );
 
         let metric_families = registry.gather();
         assert!(!metric_families.is_empty());
@@ -203,7 +1027,140 @@ mod tests {
     fn metrics_registry_gather_text_works() {
         let registry = MetricsRegistry::new().expect("create metrics registry");
         registry.consensus.block_validation_seconds.observe(0.01);
+        registry.consensus.observe_ml_verdict(
+            "multi_factor_v1",
+            "rejected",
+            Duration::from_millis(20),
+        );
         let text = registry.gather_text();
         assert!(text.contains("consensus_block_validation_seconds"));
+        assert!(text.contains(
+            r#"chain_consensus_ml_verdicts_total{outcome="rejected",scheme="multi_factor_v1"} 1"#
+        ));
+    }
+
+    #[test]
+    fn metrics_registry_applies_config_overrides() {
+        let cfg = MetricsConfig {
+            namespace: "mlsnitch".to_string(),
+            const_labels: HashMap::from([("network".to_string(), "devnet".to_string())]),
+            buckets: HistogramBuckets {
+                reorg_depth: Some(vec![7.0]),
+                ..HistogramBuckets::default()
+            },
+            ..MetricsConfig::default()
+        };
+        let registry = MetricsRegistry::from_config(&cfg).expect("create metrics registry");
+        registry.consensus.reorg_depth.observe(3.0);
+
+        let text = registry.gather_text();
+        let bucket = text
+            .lines()
+            .find(|line| line.starts_with("mlsnitch_consensus_reorg_depth_bucket{"))
+            .expect("reorg depth bucket");
+        assert!(bucket.contains(r#"network="devnet""#), "{bucket}");
+        assert!(bucket.contains(r#"le="7""#), "{bucket}");
+        assert!(!text.contains("chain_consensus_"));
+    }
+
+    #[test]
+    fn storage_metrics_publish_rocksdb_stats() {
+        use crate::storage::ColumnFamilyStats;
+
+        let registry = MetricsRegistry::new().expect("create metrics registry");
+        registry.storage.observe_rocksdb(&RocksDbStats {
+            column_families: vec![ColumnFamilyStats {
+                name: "blocks",
+                estimated_keys: 3,
+                sst_bytes: 4_096,
+                pending_compaction_bytes: 0,
+            }],
+            block_cache_hits: 3,
+            block_cache_misses: 1,
+        });
+
+        let text = registry.gather_text();
+        assert!(text.contains(r#"chain_storage_rocksdb_estimated_keys{cf="blocks"} 3"#));
+        assert!(text.contains(r#"chain_storage_rocksdb_sst_bytes{cf="blocks"} 4096"#));
+        assert!(text.contains("chain_storage_rocksdb_block_cache_hit_ratio 0.75"));
+    }
+
+    #[test]
+    fn backfill_sets_gauges_from_chain() {
+        use crate::storage::InMemoryBlockStore;
+        use crate::types::{
+            AccountId, Aid, ArtefactMetadata, Block, BlockHash, EvidenceHash, EvidenceRef,
+            HASH_LEN, Hash256, Header, WmProfile,
+        };
+
+        let mut store = InMemoryBlockStore::new();
+        let mut parent = BlockHash(Hash256([0u8; HASH_LEN]));
+        for height in 0..3 {
+            let block = Block {
+                header: Header {
+                    parent,
+                    height,
+                    timestamp: 1_000 + height * 10,
+                    proposer: AccountId(Hash256([9u8; HASH_LEN])),
+                    txs_root: Hash256([0u8; HASH_LEN]),
+                    state_root: Hash256([0u8; HASH_LEN]),
+                    receipts_root: Hash256([0u8; HASH_LEN]),
+                    pos_proof: None,
+                },
+                txs: Vec::new(),
+            };
+            parent = block.compute_hash();
+            store.put_block(block).unwrap();
+            store.set_tip(parent).unwrap();
+        }
+
+        let mut state = ChainState::new();
+        for (byte, scheme, registered_at) in [
+            (1u8, "multi_factor_v1", 1),
+            (2, "multi_factor_v1", 2),
+            (3, "other", 0),
+        ] {
+            state.insert_artefact(ArtefactMetadata {
+                aid: Aid(Hash256([byte; HASH_LEN])),
+                chunked: None,
+                owner: AccountId(Hash256([byte; HASH_LEN])),
+                evidence: EvidenceRef {
+                    scheme_id: scheme.to_string(),
+                    evidence_hash: EvidenceHash(Hash256([byte; HASH_LEN])),
+                    wm_profile: WmProfile {
+                        tau_input: 0.9,
+                        tau_feat: 0.1,
+                        logit_band_low: 0.02,
+                        logit_band_high: 0.05,
+                    },
+                },
+                registered_at,
+                revoked_at: None,
+            });
+        }
+
+        let metrics = ConsensusMetrics::register(&Registry::new(), &HistogramBuckets::default())
+            .expect("register metrics");
+        metrics.backfill(&state, &store).unwrap();
+
+        assert_eq!(metrics.chain_height.get(), 2);
+        metrics.refresh_tip_age(1_050);
+        assert_eq!(metrics.chain_tip_age_seconds.get(), 30.0);
+        assert_eq!(metrics.registered_artefacts.get(), 3);
+        let by_scheme = |scheme: &str| {
+            metrics
+                .registered_artefacts_by_scheme
+                .with_label_values(&[scheme])
+                .get()
+        };
+        assert_eq!(by_scheme("multi_factor_v1"), 2);
+        assert_eq!(by_scheme("other"), 1);
+        assert_eq!(
+            metrics
+                .last_registration_timestamp_seconds
+                .with_label_values(&["multi_factor_v1"])
+                .get(),
+            1_020
+        );
     }
 }
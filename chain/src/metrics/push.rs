@@ -0,0 +1,548 @@
+//! Push-mode metrics export.
+//!
+//! Prometheus scrapes a node's `/metrics` endpoint on its own schedule, so
+//! a short run can finish before it is scraped once. With
+//! `metrics.mode = "push"`, a [`MetricsPusher`] sends the registry to a
+//! collector instead, every `metrics.push.interval_secs` and once more on
+//! graceful shutdown.
+//!
+//! Two [`PushTarget`]s are supported:
+//!
+//! - a Prometheus Pushgateway, which receives the text exposition format
+//!   with `PUT {url}/metrics/job/{job}[/instance/{instance}]`, replacing
+//!   the group's previous push;
+//! - a remote-write endpoint (Prometheus, Mimir, VictoriaMetrics, ...),
+//!   which receives a snappy-compressed protobuf `WriteRequest` with
+//!   `POST {url}`, every sample stamped with the push time and labelled
+//!   with `job` and `instance`.
+
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use prometheus::proto::{Metric, MetricFamily, MetricType};
+use prost::Message;
+use reqwest::{Method, StatusCode, Url, header};
+use serde::Deserialize;
+
+use crate::metrics::MetricsRegistry;
+use crate::shutdown::Shutdown;
+use crate::transport::{HttpTransport, TransportConfig, TransportError};
+
+/// How metrics leave the node.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MetricsMode {
+    /// Serve `/metrics` on `metrics.listeners` for Prometheus to scrape.
+    #[default]
+    Pull,
+    /// Push to `metrics.push.url` with a [`MetricsPusher`].
+    Push,
+}
+
+impl FromStr for MetricsMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "pull" => Ok(MetricsMode::Pull),
+            "push" => Ok(MetricsMode::Push),
+            _ => Err("expected \"pull\" or \"push\"".to_string()),
+        }
+    }
+}
+
+/// Kind of collector metrics are pushed to.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PushTarget {
+    /// A Prometheus Pushgateway.
+    #[default]
+    Pushgateway,
+    /// A Prometheus remote-write receiver.
+    RemoteWrite,
+}
+
+impl FromStr for PushTarget {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "pushgateway" => Ok(PushTarget::Pushgateway),
+            "remote_write" => Ok(PushTarget::RemoteWrite),
+            _ => Err("expected \"pushgateway\" or \"remote_write\"".to_string()),
+        }
+    }
+}
+
+/// Configuration for push-mode metrics export.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PushConfig {
+    /// Kind of collector at `url`.
+    pub target: PushTarget,
+    /// Base URL of the Pushgateway, or the full remote-write URL, e.g.
+    /// `"http://prometheus:9090/api/v1/write"`.
+    pub url: String,
+    /// `job` the metrics are pushed under. Empty uses the binary's own
+    /// name.
+    pub job: String,
+    /// `instance` the metrics are pushed under; omitted if empty.
+    pub instance: String,
+    /// Seconds between pushes.
+    pub interval_secs: u64,
+    /// Timeout for a single push attempt, in milliseconds.
+    pub timeout_ms: u64,
+    /// Bearer token sent with every push, if any.
+    pub auth_token: Option<String>,
+}
+
+impl Default for PushConfig {
+    fn default() -> Self {
+        Self {
+            target: PushTarget::Pushgateway,
+            url: "http://127.0.0.1:9091".to_string(),
+            job: String::new(),
+            instance: String::new(),
+            interval_secs: 15,
+            timeout_ms: 5_000,
+            auth_token: None,
+        }
+    }
+}
+
+/// Errors returned by [`MetricsPusher`].
+#[derive(Debug)]
+pub enum PushError {
+    /// `url` is not a valid base URL.
+    InvalidUrl(String),
+    /// The request could not be sent.
+    Transport(TransportError),
+    /// The collector answered with a non-success status.
+    Rejected { status: StatusCode, body: String },
+}
+
+impl fmt::Display for PushError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PushError::InvalidUrl(msg) => write!(f, "invalid push URL: {msg}"),
+            PushError::Transport(e) => write!(f, "{e}"),
+            PushError::Rejected { status, body } => {
+                write!(f, "collector answered {status}: {body}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for PushError {}
+
+impl From<TransportError> for PushError {
+    fn from(e: TransportError) -> Self {
+        PushError::Transport(e)
+    }
+}
+
+/// Pushes a [`MetricsRegistry`] to the collector of a [`PushConfig`].
+///
+/// Cheap to clone; clones share the HTTP client.
+#[derive(Clone)]
+pub struct MetricsPusher {
+    registry: Arc<MetricsRegistry>,
+    transport: HttpTransport,
+    target: PushTarget,
+    url: Url,
+    job: String,
+    instance: String,
+    interval: Duration,
+}
+
+impl MetricsPusher {
+    /// Creates a pusher for `registry`. `default_job` is used when
+    /// [`PushConfig::job`] is empty.
+    ///
+    /// Pushes are counted in the registry's outbound request counter
+    /// under the client name `metrics_push`.
+    pub fn new(
+        registry: Arc<MetricsRegistry>,
+        cfg: &PushConfig,
+        default_job: &str,
+    ) -> Result<Self, PushError> {
+        let url = Url::parse(&cfg.url).map_err(|e| PushError::InvalidUrl(e.to_string()))?;
+        if url.cannot_be_a_base() {
+            return Err(PushError::InvalidUrl(format!(
+                "{} is not a base URL",
+                cfg.url
+            )));
+        }
+        let transport = HttpTransport::new(
+            "metrics_push",
+            &TransportConfig {
+                timeout: Duration::from_millis(cfg.timeout_ms),
+                max_retries: 1,
+                bearer_token: cfg.auth_token.clone(),
+                ..TransportConfig::default()
+            },
+            Some(registry.consensus.outbound_requests.clone()),
+        )?;
+        let job = if cfg.job.is_empty() {
+            default_job
+        } else {
+            &cfg.job
+        };
+        Ok(Self {
+            registry,
+            transport,
+            target: cfg.target,
+            url,
+            job: job.to_string(),
+            instance: cfg.instance.clone(),
+            interval: Duration::from_secs(cfg.interval_secs.max(1)),
+        })
+    }
+
+    /// Pushes the current value of every metric once.
+    pub async fn push(&self) -> Result<(), PushError> {
+        let request = match self.target {
+            PushTarget::Pushgateway => self
+                .transport
+                .request(Method::PUT, self.pushgateway_url().as_str())
+                .header(header::CONTENT_TYPE, prometheus::TEXT_FORMAT)
+                .body(self.registry.gather_text()),
+            PushTarget::RemoteWrite => {
+                let timestamp_ms = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map_or(0, |d| d.as_millis() as i64);
+                let write = WriteRequest {
+                    timeseries: time_series(
+                        &self.registry.gather(),
+                        &self.identity_labels(),
+                        timestamp_ms,
+                    ),
+                };
+                self.transport
+                    .request(Method::POST, self.url.as_str())
+                    .header(header::CONTENT_TYPE, "application/x-protobuf")
+                    .header(header::CONTENT_ENCODING, "snappy")
+                    .header("X-Prometheus-Remote-Write-Version", "0.1.0")
+                    .body(snappy_encode(&write.encode_to_vec()))
+            }
+        };
+
+        let resp = self.transport.send(request).await?;
+        let status = resp.status();
+        if status.is_success() {
+            return Ok(());
+        }
+        let body = resp.text().await.unwrap_or_default();
+        Err(PushError::Rejected { status, body })
+    }
+
+    /// Pushes every interval until `shutdown`.
+    ///
+    /// A failed push is logged and the next one goes ahead as scheduled.
+    /// The push on shutdown is left to the caller, so it can run after
+    /// the last block has been recorded.
+    pub async fn run(self, shutdown: Shutdown) {
+        while shutdown.sleep(self.interval).await {
+            if let Err(e) = self.push().await {
+                tracing::warn!(error = %e, "failed to push metrics");
+            }
+        }
+    }
+
+    /// Returns `{url}/metrics/job/{job}`, followed by
+    /// `/instance/{instance}` if set.
+    fn pushgateway_url(&self) -> Url {
+        let mut url = self.url.clone();
+        if let Ok(mut segments) = url.path_segments_mut() {
+            segments
+                .pop_if_empty()
+                .extend(["metrics", "job", self.job.as_str()]);
+            if !self.instance.is_empty() {
+                segments.extend(["instance", self.instance.as_str()]);
+            }
+        }
+        url
+    }
+
+    /// Returns the `job` and `instance` labels added to remote-write
+    /// series.
+    fn identity_labels(&self) -> Vec<(&str, &str)> {
+        let mut labels = vec![("job", self.job.as_str())];
+        if !self.instance.is_empty() {
+            labels.push(("instance", self.instance.as_str()));
+        }
+        labels
+    }
+}
+
+/// Remote-write `WriteRequest` (`prometheus/prompb/remote.proto`),
+/// without the optional metadata.
+#[derive(Clone, PartialEq, Message)]
+struct WriteRequest {
+    #[prost(message, repeated, tag = "1")]
+    timeseries: Vec<TimeSeries>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+struct TimeSeries {
+    /// Sorted by name, starting with `__name__`.
+    #[prost(message, repeated, tag = "1")]
+    labels: Vec<Label>,
+    #[prost(message, repeated, tag = "2")]
+    samples: Vec<Sample>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+struct Label {
+    #[prost(string, tag = "1")]
+    name: String,
+    #[prost(string, tag = "2")]
+    value: String,
+}
+
+#[derive(Clone, PartialEq, Message)]
+struct Sample {
+    #[prost(double, tag = "1")]
+    value: f64,
+    #[prost(int64, tag = "2")]
+    timestamp: i64,
+}
+
+/// Flattens `families` into one series per sample, the way the text
+/// format would expose them: histograms become `_bucket` (including
+/// `le="+Inf"`), `_sum` and `_count` series, summaries `quantile`, `_sum`
+/// and `_count` series.
+///
+/// `identity` labels are added to every series that does not already
+/// carry a label of the same name.
+fn time_series(
+    families: &[MetricFamily],
+    identity: &[(&str, &str)],
+    timestamp_ms: i64,
+) -> Vec<TimeSeries> {
+    let mut series = Vec::new();
+    for family in families {
+        let name = family.name();
+        for metric in family.get_metric() {
+            let mut push = |suffix: &str, extra: Option<(&str, String)>, value: f64| {
+                series.push(TimeSeries {
+                    labels: series_labels(&format!("{name}{suffix}"), metric, identity, extra),
+                    samples: vec![Sample {
+                        value,
+                        timestamp: timestamp_ms,
+                    }],
+                });
+            };
+            match family.get_field_type() {
+                MetricType::COUNTER => push("", None, metric.get_counter().value()),
+                MetricType::GAUGE => push("", None, metric.get_gauge().value()),
+                MetricType::UNTYPED => push("", None, metric.untyped.value()),
+                MetricType::SUMMARY => {
+                    let summary = metric.get_summary();
+                    for q in summary.get_quantile() {
+                        push("", Some(("quantile", q.quantile().to_string())), q.value());
+                    }
+                    push("_sum", None, summary.sample_sum());
+                    push("_count", None, summary.sample_count() as f64);
+                }
+                MetricType::HISTOGRAM => {
+                    let histogram = metric.get_histogram();
+                    let mut inf_seen = false;
+                    for bucket in histogram.get_bucket() {
+                        let bound = bucket.upper_bound();
+                        inf_seen |= bound == f64::INFINITY;
+                        push(
+                            "_bucket",
+                            Some(("le", format_bound(bound))),
+                            bucket.cumulative_count() as f64,
+                        );
+                    }
+                    if !inf_seen {
+                        push(
+                            "_bucket",
+                            Some(("le", format_bound(f64::INFINITY))),
+                            histogram.get_sample_count() as f64,
+                        );
+                    }
+                    push("_sum", None, histogram.get_sample_sum());
+                    push("_count", None, histogram.get_sample_count() as f64);
+                }
+            }
+        }
+    }
+    series
+}
+
+/// Returns the sorted labels of one series of `metric`.
+fn series_labels(
+    name: &str,
+    metric: &Metric,
+    identity: &[(&str, &str)],
+    extra: Option<(&str, String)>,
+) -> Vec<Label> {
+    let mut labels: Vec<Label> = metric
+        .get_label()
+        .iter()
+        .map(|pair| Label {
+            name: pair.name().to_string(),
+            value: pair.value().to_string(),
+        })
+        .collect();
+    for (label, value) in identity {
+        if !labels.iter().any(|l| l.name == *label) {
+            labels.push(Label {
+                name: label.to_string(),
+                value: value.to_string(),
+            });
+        }
+    }
+    if let Some((label, value)) = extra {
+        labels.push(Label {
+            name: label.to_string(),
+            value,
+        });
+    }
+    labels.push(Label {
+        name: "__name__".to_string(),
+        value: name.to_string(),
+    });
+    labels.sort_by(|a, b| a.name.cmp(&b.name));
+    labels
+}
+
+/// Formats a bucket bound like the text format does.
+fn format_bound(bound: f64) -> String {
+    if bound == f64::INFINITY {
+        "+Inf".to_string()
+    } else {
+        bound.to_string()
+    }
+}
+
+/// Encodes `data` in the snappy block format that remote-write requires.
+///
+/// Only literal chunks are emitted: the payload is not compressed, but
+/// any snappy decoder reads it, and no compression library is needed.
+fn snappy_encode(data: &[u8]) -> Vec<u8> {
+    /// Longest literal a chunk header with a two-byte length can describe.
+    const MAX_LITERAL: usize = 1 << 16;
+
+    let mut out = Vec::with_capacity(data.len() + data.len() / MAX_LITERAL * 3 + 13);
+    prost::encoding::encode_varint(data.len() as u64, &mut out);
+    for chunk in data.chunks(MAX_LITERAL) {
+        let n = chunk.len() - 1;
+        if n < 60 {
+            out.push((n as u8) << 2);
+        } else if n < 1 << 8 {
+            out.push(60 << 2);
+            out.push(n as u8);
+        } else {
+            out.push(61 << 2);
+            out.extend_from_slice(&(n as u16).to_le_bytes());
+        }
+        out.extend_from_slice(chunk);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pusher(cfg: &PushConfig) -> MetricsPusher {
+        let registry = Arc::new(MetricsRegistry::new().expect("registry"));
+        MetricsPusher::new(registry, cfg, "mlsnitch-chain").expect("pusher")
+    }
+
+    #[test]
+    fn pushgateway_url_names_job_and_instance() {
+        let url = pusher(&PushConfig {
+            url: "http://gateway:9091/".to_string(),
+            instance: "node 1".to_string(),
+            ..PushConfig::default()
+        })
+        .pushgateway_url();
+        assert_eq!(
+            url.as_str(),
+            "http://gateway:9091/metrics/job/mlsnitch-chain/instance/node%201"
+        );
+
+        let url = pusher(&PushConfig {
+            job: "devnet".to_string(),
+            ..PushConfig::default()
+        })
+        .pushgateway_url();
+        assert_eq!(url.as_str(), "http://127.0.0.1:9091/metrics/job/devnet");
+    }
+
+    #[test]
+    fn pusher_rejects_urls_that_are_not_bases() {
+        let registry = Arc::new(MetricsRegistry::new().expect("registry"));
+        for url in ["not a url", "mailto:ops@example.com"] {
+            let cfg = PushConfig {
+                url: url.to_string(),
+                ..PushConfig::default()
+            };
+            assert!(matches!(
+                MetricsPusher::new(registry.clone(), &cfg, "chain"),
+                Err(PushError::InvalidUrl(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn time_series_expand_histograms_and_label_every_sample() {
+        let registry = MetricsRegistry::new().expect("registry");
+        registry.consensus.blocks_proposed.inc_by(3);
+        registry.consensus.block_validation_seconds.observe(0.02);
+
+        let series = time_series(
+            &registry.gather(),
+            &[("job", "chain"), ("instance", "n1")],
+            1_700_000_000_000,
+        );
+        let find = |name: &str, le: Option<&str>| {
+            series.iter().find(|s| {
+                s.labels
+                    .iter()
+                    .any(|l| l.name == "__name__" && l.value == name)
+                    && le.is_none_or(|le| s.labels.iter().any(|l| l.name == "le" && l.value == le))
+            })
+        };
+
+        let proposed = find("chain_consensus_blocks_proposed", None).expect("counter");
+        assert_eq!(proposed.samples[0].value, 3.0);
+        assert_eq!(proposed.samples[0].timestamp, 1_700_000_000_000);
+        let names: Vec<&str> = proposed.labels.iter().map(|l| l.name.as_str()).collect();
+        assert_eq!(names, ["__name__", "instance", "job"]);
+
+        let inf = find(
+            "chain_consensus_block_validation_seconds_bucket",
+            Some("+Inf"),
+        )
+        .expect("+Inf bucket");
+        assert_eq!(inf.samples[0].value, 1.0);
+        let count = find("chain_consensus_block_validation_seconds_count", None).expect("count");
+        assert_eq!(count.samples[0].value, 1.0);
+    }
+
+    #[test]
+    fn snappy_encode_emits_literal_chunks() {
+        assert_eq!(snappy_encode(b""), [0]);
+        assert_eq!(snappy_encode(b"abc"), [3, 2 << 2, b'a', b'b', b'c']);
+
+        let long = vec![7u8; 100];
+        let encoded = snappy_encode(&long);
+        assert_eq!(&encoded[..3], [100, 60 << 2, 99]);
+        assert_eq!(&encoded[3..], &long[..]);
+
+        let huge = vec![1u8; (1 << 16) + 1];
+        let encoded = snappy_encode(&huge);
+        // Varint length, then a full 64 KiB literal and a one-byte one.
+        assert_eq!(&encoded[..3], [0x81, 0x80, 0x04]);
+        assert_eq!(&encoded[3..6], [61 << 2, 0xff, 0xff]);
+        assert_eq!(&encoded[6 + (1 << 16)..], [0, 1]);
+    }
+}
@@ -0,0 +1,165 @@
+//! Circuit breaker for ML service clients.
+//!
+//! The breaker starts *closed* and lets every call through. After
+//! `failure_threshold` consecutive failures it *opens*: calls fail fast
+//! with [`MlError::CircuitOpen`] instead of waiting on a service that is
+//! down. Once `open_secs` have passed it is *half-open* and admits a single
+//! probe call; a successful probe closes the breaker again, a failed one
+//! re-opens it for another `open_secs`.
+//!
+//! Only failures that say the service is unreachable or broken (transport
+//! errors, timeouts, `5xx`) count. A verdict rejecting an artefact is a
+//! success as far as the breaker is concerned.
+
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use serde::Deserialize;
+
+use crate::validation::MlError;
+
+/// Configuration for [`CircuitBreaker`].
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CircuitBreakerConfig {
+    /// Consecutive failures that open the breaker. `0` disables it.
+    pub failure_threshold: u32,
+    /// How long the breaker stays open before admitting a probe.
+    pub open_secs: u64,
+}
+
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        Self {
+            failure_threshold: 5,
+            open_secs: 30,
+        }
+    }
+}
+
+#[derive(Debug)]
+enum State {
+    Closed { failures: u32 },
+    Open { until: Instant },
+    HalfOpen { probing: bool },
+}
+
+/// Thread-safe circuit breaker; see the module docs.
+#[derive(Debug)]
+pub struct CircuitBreaker {
+    failure_threshold: u32,
+    open_for: Duration,
+    state: Mutex<State>,
+}
+
+impl CircuitBreaker {
+    /// Creates a closed breaker.
+    pub fn new(cfg: &CircuitBreakerConfig) -> Self {
+        Self {
+            failure_threshold: cfg.failure_threshold,
+            open_for: Duration::from_secs(cfg.open_secs),
+            state: Mutex::new(State::Closed { failures: 0 }),
+        }
+    }
+
+    /// Asks to make a call. Fails with [`MlError::CircuitOpen`] while the
+    /// breaker is open, or half-open with a probe already in flight.
+    pub fn acquire(&self) -> Result<(), MlError> {
+        self.acquire_at(Instant::now())
+    }
+
+    /// Records the outcome of a call admitted by [`acquire`](Self::acquire).
+    pub fn record(&self, success: bool) {
+        self.record_at(success, Instant::now());
+    }
+
+    fn acquire_at(&self, now: Instant) -> Result<(), MlError> {
+        let mut state = self.state.lock().expect("circuit breaker lock poisoned");
+        match *state {
+            State::Closed { .. } => Ok(()),
+            State::Open { until } if now >= until => {
+                *state = State::HalfOpen { probing: true };
+                Ok(())
+            }
+            State::HalfOpen { probing: false } => {
+                *state = State::HalfOpen { probing: true };
+                Ok(())
+            }
+            State::Open { until } => Err(MlError::CircuitOpen(format!(
+                "ML service circuit open for another {}ms",
+                until.saturating_duration_since(now).as_millis()
+            ))),
+            State::HalfOpen { probing: true } => Err(MlError::CircuitOpen(
+                "ML service circuit half-open, probe in flight".to_string(),
+            )),
+        }
+    }
+
+    fn record_at(&self, success: bool, now: Instant) {
+        if self.failure_threshold == 0 {
+            return;
+        }
+        let mut state = self.state.lock().expect("circuit breaker lock poisoned");
+        *state = match (&*state, success) {
+            (_, true) => State::Closed { failures: 0 },
+            (State::Closed { failures }, false) if failures + 1 < self.failure_threshold => {
+                State::Closed {
+                    failures: failures + 1,
+                }
+            }
+            (_, false) => State::Open {
+                until: now + self.open_for,
+            },
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn breaker(failure_threshold: u32) -> CircuitBreaker {
+        CircuitBreaker::new(&CircuitBreakerConfig {
+            failure_threshold,
+            open_secs: 10,
+        })
+    }
+
+    #[test]
+    fn opens_after_consecutive_failures_and_probes_when_half_open() {
+        let b = breaker(2);
+        let t0 = Instant::now();
+
+        b.record_at(false, t0);
+        b.record_at(true, t0);
+        b.record_at(false, t0);
+        assert!(b.acquire_at(t0).is_ok(), "a success resets the count");
+
+        b.record_at(false, t0);
+        assert!(matches!(b.acquire_at(t0), Err(MlError::CircuitOpen(_))));
+
+        // Half-open: one probe at a time.
+        let later = t0 + Duration::from_secs(10);
+        assert!(b.acquire_at(later).is_ok());
+        assert!(matches!(b.acquire_at(later), Err(MlError::CircuitOpen(_))));
+
+        // A failed probe re-opens, a successful one closes.
+        b.record_at(false, later);
+        assert!(b.acquire_at(later).is_err());
+        let later = later + Duration::from_secs(10);
+        assert!(b.acquire_at(later).is_ok());
+        b.record_at(true, later);
+        assert!(b.acquire_at(later).is_ok());
+        assert!(b.acquire_at(later).is_ok());
+    }
+
+    #[test]
+    fn zero_threshold_never_opens() {
+        let b = breaker(0);
+        let t0 = Instant::now();
+        for _ in 0..10 {
+            b.record_at(false, t0);
+        }
+        assert!(b.acquire_at(t0).is_ok());
+    }
+}
@@ -0,0 +1,348 @@
+//! gRPC-based ML verifier client.
+//!
+//! This implementation of [`crate::validation::AsyncMlVerifier`] talks to
+//! the ML service over gRPC using the `mlsnitch.ml.v1.MlVerifier` service
+//! defined in `chain/proto/ml_verifier.proto`. Compared to the JSON API it
+//! sends raw hashes instead of hex strings, and
+//! [`AsyncMlVerifier::verify_batch`] uses the streaming `VerifyBatch` call
+//! so a whole block's artefacts are checked over a single stream.
+//!
+//! Timeouts, TLS, and the bearer token come from
+//! [`MlClientConfig`](crate::config::MlClientConfig). Unlike the HTTP
+//! client, failed calls are not retried.
+
+use std::time::Duration;
+
+use tonic::codegen::InterceptedService;
+use tonic::metadata::{AsciiMetadataValue, MetadataValue};
+use tonic::service::Interceptor;
+use tonic::transport::{Certificate, Channel, ClientTlsConfig, Endpoint, Identity};
+use tonic::{Code, Request, Status};
+
+use crate::config::MlClientConfig;
+use crate::types::{Aid, EvidenceRef, WmProfile};
+use crate::validation::{AsyncMlVerifier, MlError, MlVerdict};
+
+/// Protobuf messages and the generated client/server for
+/// `proto/ml_verifier.proto`.
+pub mod proto {
+    tonic::include_proto!("mlsnitch.ml.v1");
+}
+
+use proto::ml_verifier_client::MlVerifierClient;
+
+/// Adds the bearer token (if any) to every outgoing call.
+#[derive(Clone)]
+struct BearerAuth(Option<AsciiMetadataValue>);
+
+impl Interceptor for BearerAuth {
+    fn call(&mut self, mut req: Request<()>) -> Result<Request<()>, Status> {
+        if let Some(token) = &self.0 {
+            req.metadata_mut().insert("authorization", token.clone());
+        }
+        Ok(req)
+    }
+}
+
+/// gRPC-based ML verifier.
+///
+/// Like [`HttpMlVerifier`](super::HttpMlVerifier) this is `Send + Sync` and
+/// can be shared across validators; calls must be awaited from inside a
+/// Tokio runtime.
+pub struct GrpcMlVerifier {
+    client: MlVerifierClient<InterceptedService<Channel, BearerAuth>>,
+    timeout: Duration,
+}
+
+impl GrpcMlVerifier {
+    /// Constructs a verifier for `cfg.grpc.endpoint`.
+    ///
+    /// The connection is established lazily on the first call, so this
+    /// only fails on invalid settings (bad endpoint URI, unreadable TLS
+    /// files, malformed token). Must be called from inside a Tokio runtime.
+    pub fn new(cfg: &MlClientConfig) -> Result<Self, MlError> {
+        let grpc = &cfg.grpc;
+        let mut endpoint = Endpoint::from_shared(grpc.endpoint.clone())
+            .map_err(|e| MlError::Transport(format!("invalid gRPC endpoint: {e}")))?
+            .connect_timeout(cfg.timeout);
+
+        if grpc.endpoint.starts_with("https://") {
+            let read = |path: &std::path::Path| {
+                std::fs::read(path).map_err(|e| {
+                    MlError::Transport(format!("failed to read {}: {e}", path.display()))
+                })
+            };
+
+            let mut tls = ClientTlsConfig::new();
+            tls = match &grpc.tls.ca_cert {
+                Some(path) => tls.ca_certificate(Certificate::from_pem(read(path)?)),
+                None => tls.with_webpki_roots(),
+            };
+            if let Some(domain) = &grpc.tls.domain_name {
+                tls = tls.domain_name(domain.clone());
+            }
+            if let (Some(cert), Some(key)) = (&grpc.tls.client_cert, &grpc.tls.client_key) {
+                tls = tls.identity(Identity::from_pem(read(cert)?, read(key)?));
+            }
+            endpoint = endpoint
+                .tls_config(tls)
+                .map_err(|e| MlError::Transport(format!("invalid gRPC TLS settings: {e}")))?;
+        }
+
+        let token = cfg
+            .auth_token
+            .as_ref()
+            .map(|token| MetadataValue::try_from(format!("Bearer {token}")))
+            .transpose()
+            .map_err(|e| MlError::Transport(format!("invalid auth token: {e}")))?;
+
+        Ok(Self::with_channel(
+            endpoint.connect_lazy(),
+            token,
+            cfg.timeout,
+        ))
+    }
+
+    /// Constructs a verifier on top of an existing `channel`.
+    ///
+    /// `token` is sent as the `authorization` metadata value on every call;
+    /// `timeout` bounds each verification.
+    pub fn with_channel(
+        channel: Channel,
+        token: Option<AsciiMetadataValue>,
+        timeout: Duration,
+    ) -> Self {
+        Self {
+            client: MlVerifierClient::with_interceptor(channel, BearerAuth(token)),
+            timeout,
+        }
+    }
+}
+
+impl AsyncMlVerifier for GrpcMlVerifier {
+    async fn verify(&self, aid: &Aid, evidence: &EvidenceRef) -> Result<MlVerdict, MlError> {
+        let mut client = self.client.clone();
+        let request = verify_request(0, aid, evidence);
+
+        let response = tokio::time::timeout(self.timeout, client.verify(request))
+            .await
+            .map_err(|_| MlError::Transport("gRPC Verify timed out".to_string()))?
+            .map_err(status_to_error)?;
+
+        Ok(response.into_inner().into())
+    }
+
+    /// Verifies several artefacts over one `VerifyBatch` stream.
+    ///
+    /// Verdicts are returned in the order of `items`, whatever order the
+    /// service answers in. The call is bounded by the per-verification
+    /// timeout multiplied by the number of items.
+    async fn verify_batch(&self, items: &[(Aid, EvidenceRef)]) -> Result<Vec<MlVerdict>, MlError> {
+        if items.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let requests: Vec<_> = items
+            .iter()
+            .enumerate()
+            .map(|(i, (aid, evidence))| verify_request(i as u64, aid, evidence))
+            .collect();
+        let mut client = self.client.clone();
+        let budget = self
+            .timeout
+            .saturating_mul(u32::try_from(items.len()).unwrap_or(u32::MAX));
+
+        let collect = async {
+            let mut stream = client
+                .verify_batch(tokio_stream::iter(requests))
+                .await
+                .map_err(status_to_error)?
+                .into_inner();
+
+            let mut verdicts: Vec<Option<MlVerdict>> = vec![None; items.len()];
+            while let Some(verdict) = stream.message().await.map_err(status_to_error)? {
+                let slot = usize::try_from(verdict.request_id)
+                    .ok()
+                    .and_then(|i| verdicts.get_mut(i))
+                    .ok_or_else(|| {
+                        MlError::Protocol(format!("unknown request_id {}", verdict.request_id))
+                    })?;
+                if slot.replace(verdict.into()).is_some() {
+                    return Err(MlError::Protocol(
+                        "duplicate verdict in batch response".to_string(),
+                    ));
+                }
+            }
+
+            verdicts
+                .into_iter()
+                .collect::<Option<Vec<_>>>()
+                .ok_or_else(|| MlError::Protocol("batch response is missing verdicts".to_string()))
+        };
+
+        tokio::time::timeout(budget, collect)
+            .await
+            .map_err(|_| MlError::Transport("gRPC VerifyBatch timed out".to_string()))?
+    }
+}
+
+fn verify_request(request_id: u64, aid: &Aid, evidence: &EvidenceRef) -> proto::VerifyRequest {
+    proto::VerifyRequest {
+        request_id,
+        aid: aid.as_hash().as_bytes().to_vec(),
+        scheme_id: evidence.scheme_id.clone(),
+        evidence_hash: evidence.evidence_hash.as_hash().as_bytes().to_vec(),
+        wm_profile: Some(evidence.wm_profile.clone().into()),
+    }
+}
+
+impl From<WmProfile> for proto::WmProfile {
+    fn from(p: WmProfile) -> Self {
+        Self {
+            tau_input: p.tau_input,
+            tau_feat: p.tau_feat,
+            logit_band_low: p.logit_band_low,
+            logit_band_high: p.logit_band_high,
+        }
+    }
+}
+
+impl From<proto::Verdict> for MlVerdict {
+    fn from(v: proto::Verdict) -> Self {
+        MlVerdict {
+            ok: v.ok,
+            score: v.score,
+            trigger_acc: v.trigger_acc,
+            feat_dist: v.feat_dist,
+            logit_stat: v.logit_stat,
+            latency_ms: v.latency_ms,
+            quorum: None,
+        }
+    }
+}
+
+/// Maps a gRPC status to an [`MlError`]: connectivity problems are
+/// transport errors, everything else is the service refusing the request.
+fn status_to_error(status: Status) -> MlError {
+    match status.code() {
+        Code::Unavailable | Code::DeadlineExceeded | Code::Cancelled | Code::Unknown => {
+            MlError::Transport(format!("gRPC call failed: {status}"))
+        }
+        Code::Internal | Code::DataLoss | Code::Unimplemented => {
+            MlError::Service(format!("ML service error: {status}"))
+        }
+        _ => MlError::Service(format!("ML service refused the request: {status}")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::pin::Pin;
+
+    use tokio_stream::wrappers::TcpListenerStream;
+    use tokio_stream::{Stream, StreamExt};
+    use tonic::{Response, Streaming};
+
+    use super::proto::ml_verifier_server::{MlVerifier, MlVerifierServer};
+    use super::*;
+    use crate::types::{EvidenceHash, HASH_LEN, Hash256};
+
+    /// Accepts artefacts whose first `aid` byte is even and answers batches
+    /// in reverse order.
+    struct ParityVerifier;
+
+    fn parity_verdict(req: &proto::VerifyRequest) -> proto::Verdict {
+        proto::Verdict {
+            request_id: req.request_id,
+            ok: req.aid.first().is_some_and(|b| b % 2 == 0),
+            score: Some(0.5),
+            latency_ms: Some(1),
+            ..Default::default()
+        }
+    }
+
+    #[tonic::async_trait]
+    impl MlVerifier for ParityVerifier {
+        async fn verify(
+            &self,
+            request: Request<proto::VerifyRequest>,
+        ) -> Result<Response<proto::Verdict>, Status> {
+            if request.metadata().get("authorization").is_none() {
+                return Err(Status::unauthenticated("missing token"));
+            }
+            Ok(Response::new(parity_verdict(request.get_ref())))
+        }
+
+        type VerifyBatchStream = Pin<Box<dyn Stream<Item = Result<proto::Verdict, Status>> + Send>>;
+
+        async fn verify_batch(
+            &self,
+            request: Request<Streaming<proto::VerifyRequest>>,
+        ) -> Result<Response<Self::VerifyBatchStream>, Status> {
+            let mut requests = request.into_inner();
+            let mut verdicts = Vec::new();
+            while let Some(req) = requests.next().await {
+                verdicts.push(Ok(parity_verdict(&req?)));
+            }
+            verdicts.reverse();
+            Ok(Response::new(Box::pin(tokio_stream::iter(verdicts))))
+        }
+    }
+
+    fn artefact(byte: u8) -> (Aid, EvidenceRef) {
+        let evidence = EvidenceRef {
+            scheme_id: "multi_factor_v1".to_string(),
+            evidence_hash: EvidenceHash(Hash256([0xEE; HASH_LEN])),
+            wm_profile: WmProfile {
+                tau_input: 0.9,
+                tau_feat: 0.1,
+                logit_band_low: 0.02,
+                logit_band_high: 0.05,
+            },
+        };
+        (Aid(Hash256([byte; HASH_LEN])), evidence)
+    }
+
+    async fn spawn_server() -> Channel {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("bind test listener");
+        let addr = listener.local_addr().expect("listener address");
+        tokio::spawn(
+            tonic::transport::Server::builder()
+                .add_service(MlVerifierServer::new(ParityVerifier))
+                .serve_with_incoming(TcpListenerStream::new(listener)),
+        );
+        Endpoint::from_shared(format!("http://{addr}"))
+            .expect("valid endpoint")
+            .connect_lazy()
+    }
+
+    #[tokio::test]
+    async fn verify_and_batch_roundtrip_through_grpc() {
+        let channel = spawn_server().await;
+        let token = MetadataValue::from_static("Bearer secret");
+        let verifier = GrpcMlVerifier::with_channel(channel, Some(token), Duration::from_secs(5));
+
+        let (aid, evidence) = artefact(2);
+        let verdict = verifier.verify(&aid, &evidence).await.expect("verify");
+        assert!(verdict.ok);
+        assert_eq!(verdict.score, Some(0.5));
+        assert_eq!(verdict.trigger_acc, None);
+
+        let items: Vec<_> = [1, 2, 3, 4].into_iter().map(artefact).collect();
+        let verdicts = verifier.verify_batch(&items).await.expect("verify_batch");
+        let oks: Vec<bool> = verdicts.iter().map(|v| v.ok).collect();
+        assert_eq!(oks, [false, true, false, true]);
+    }
+
+    #[tokio::test]
+    async fn service_errors_map_to_ml_errors() {
+        let channel = spawn_server().await;
+        let verifier = GrpcMlVerifier::with_channel(channel, None, Duration::from_secs(5));
+
+        let (aid, evidence) = artefact(2);
+        let err = verifier.verify(&aid, &evidence).await.unwrap_err();
+        assert!(matches!(err, MlError::Service(_)), "got {err:?}");
+    }
+}
@@ -1,6 +1,6 @@
 //! HTTP-based ML verifier client.
 //!
-//! This implementation of [`crate::validation::MlVerifier`] talks to a
+//! This implementation of [`crate::validation::AsyncMlVerifier`] talks to a
 //! Python + PyTorch watermarking service over HTTP. It assumes the
 //! service exposes a JSON API of the form:
 //!
@@ -21,6 +21,7 @@
 //! Response:
 //! {
 //!   "ok": true,
+//!   "score": 0.91,
 //!   "trigger_acc": 0.94,
 //!   "feat_dist": 0.07,
 //!   "logit_stat": 0.031,
@@ -28,46 +29,114 @@
 //! }
 //! ```
 //!
+//! The verifier remembers the last verdict per `(aid, evidence_hash)`. When
+//! it asks about a pair it has already seen, it adds a cache hint to the
+//! request:
+//!
+//! ```json
+//! "cache_hint": { "evidence_hash": "hex-encoded-evidence-hash" }
+//! ```
+//!
+//! If the service still holds a verdict for that pair it may skip the
+//! detector and answer with `{ "cached": true }` (optionally with a
+//! `latency_ms`), in which case the remembered verdict is reused.
+//!
+//! Blocks are verified with one `POST /verify_batch` call carrying
+//! `{ "items": [<verify request>, ...] }` and answered with
+//! `{ "verdicts": [<verify response>, ...] }` in the same order.
+//!
 //! The exact schema can be evolved alongside the Python service, as long
 //! as it remains compatible with the request/response types defined here.
+//!
+//! Timeouts, retries, and auth are handled by the shared
+//! [`HttpTransport`] stack. On top of that, verification calls go through a
+//! [`CircuitBreaker`]: once the service keeps failing after retries, calls
+//! fail fast with [`MlError::CircuitOpen`] until a probe gets through.
+//! Transport failures and `5xx` statuses surface as [`MlError::Transport`];
+//! [`MlError::Service`] is reserved for the service refusing a request.
+//!
+//! Calls run in `ml_verify` / `ml_verify_batch` `tracing` spans, and each
+//! request carries the span's W3C `traceparent` header when OTLP export is
+//! enabled (see [`crate::metrics::otlp`]).
 
+use std::collections::HashMap;
+use std::sync::Mutex;
 use std::time::Duration;
 
-use reqwest::blocking::Client;
+use reqwest::Method;
+use reqwest::header::HeaderMap;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 
+use super::breaker::{CircuitBreaker, CircuitBreakerConfig};
+use crate::metrics::inject_trace_context;
+use crate::transport::{HttpTransport, TransportConfig};
 use crate::types::{Aid, EvidenceHash, EvidenceRef, Hash256, WmProfile};
-use crate::validation::{MlError, MlVerdict, MlVerifier};
+use crate::validation::{AsyncMlVerifier, MlError, MlVerdict};
+
+/// Maximum number of verdicts remembered for cache hints. The whole cache
+/// is dropped when it fills up.
+const VERDICT_CACHE_CAPACITY: usize = 4096;
 
 /// HTTP-based ML verifier.
 ///
 /// This client is thread-safe (`Send + Sync`) and can be shared across
-/// validators. It uses the blocking `reqwest` client internally; higher
-/// layers can wrap calls in dedicated threads or spawn blocking tasks
-/// in a Tokio runtime if needed.
-#[allow(dead_code)]
+/// validators. It uses the non-blocking `reqwest` client internally, so
+/// calls must be awaited from inside a Tokio runtime.
 pub struct HttpMlVerifier {
     base_url: String,
-    client: Client,
-    timeout: Duration,
+    transport: HttpTransport,
+    breaker: CircuitBreaker,
+    /// Last verdict per `(aid, evidence_hash)`, used for cache hints.
+    verdicts: Mutex<HashMap<(Aid, EvidenceHash), MlVerdict>>,
 }
 
 impl HttpMlVerifier {
     /// Constructs a new HTTP ML verifier pointing at `base_url`.
     ///
     /// `base_url` should be the root of the ML service, e.g.
-    /// `"http://127.0.0.1:8080"` (without a trailing slash).
+    /// `"http://127.0.0.1:8080"` (without a trailing slash). Uses the
+    /// default retry policy and no metrics; see
+    /// [`with_transport`](Self::with_transport) for full control.
     pub fn new(base_url: impl Into<String>, timeout: Duration) -> Result<Self, MlError> {
-        let client = Client::builder()
-            .timeout(timeout)
-            .build()
-            .map_err(|e| MlError::Transport(format!("failed to build HTTP client: {e}")))?;
+        let cfg = TransportConfig {
+            timeout,
+            ..TransportConfig::default()
+        };
+        let transport = HttpTransport::new("ml_service", &cfg, None)
+            .map_err(|e| MlError::Transport(e.to_string()))?;
+
+        Ok(Self::with_transport(base_url, transport))
+    }
 
-        Ok(Self {
+    /// Constructs a verifier that sends requests through `transport`,
+    /// with the default circuit breaker settings.
+    pub fn with_transport(base_url: impl Into<String>, transport: HttpTransport) -> Self {
+        Self {
             base_url: base_url.into(),
-            client,
-            timeout,
-        })
+            transport,
+            breaker: CircuitBreaker::new(&CircuitBreakerConfig::default()),
+            verdicts: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Replaces the circuit breaker settings.
+    pub fn with_circuit_breaker(mut self, cfg: &CircuitBreakerConfig) -> Self {
+        self.breaker = CircuitBreaker::new(cfg);
+        self
+    }
+
+    fn cached_verdict(&self, key: &(Aid, EvidenceHash)) -> Option<MlVerdict> {
+        let verdicts = self.verdicts.lock().expect("verdict cache lock poisoned");
+        verdicts.get(key).cloned()
+    }
+
+    fn remember_verdict(&self, key: (Aid, EvidenceHash), verdict: MlVerdict) {
+        let mut verdicts = self.verdicts.lock().expect("verdict cache lock poisoned");
+        if verdicts.len() >= VERDICT_CACHE_CAPACITY && !verdicts.contains_key(&key) {
+            verdicts.clear();
+        }
+        verdicts.insert(key, verdict);
     }
 
     fn endpoint(&self, path: &str) -> String {
@@ -83,13 +152,13 @@ impl HttpMlVerifier {
     ///
     /// Sends a GET request to `/health` expecting a JSON:
     /// `{ "status": "ok" }`
-    pub fn health(&self) -> Result<bool, MlError> {
+    pub async fn health(&self) -> Result<bool, MlError> {
         let url = self.endpoint("/health");
 
         let resp = self
-            .client
-            .get(&url)
-            .send()
+            .transport
+            .send(self.transport.request(Method::GET, &url))
+            .await
             .map_err(|e| MlError::Transport(format!("HTTP GET {url} failed: {e}")))?;
 
         let status = resp.status();
@@ -106,6 +175,7 @@ impl HttpMlVerifier {
 
         let body: HealthResp = resp
             .json()
+            .await
             .map_err(|e| MlError::Protocol(format!("failed to parse health response: {e}")))?;
 
         Ok(body.status.to_lowercase() == "ok")
@@ -123,18 +193,78 @@ struct VerifyRequest {
     evidence_hash: String,
     /// Tuning profile for the watermark detector.
     wm_profile: WmProfile,
+    /// Present if the client already holds a verdict for this artefact.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cache_hint: Option<CacheHint>,
+}
+
+/// Internal request payload for `POST /verify_batch`.
+#[derive(Debug, Serialize)]
+struct VerifyBatchRequest {
+    items: Vec<VerifyRequest>,
+}
+
+/// Internal response payload for `POST /verify_batch`: one response per
+/// request item, in the same order.
+#[derive(Debug, Deserialize)]
+struct VerifyBatchResponse {
+    verdicts: Vec<VerifyResponse>,
+}
+
+/// Tells the service which verdict the client already has, so it can skip
+/// recomputation if its own verdict is still current.
+#[derive(Debug, Serialize)]
+struct CacheHint {
+    /// Hex-encoded evidence hash of the verdict the client holds.
+    evidence_hash: String,
 }
 
 /// Internal response payload returned by the ML service.
+///
+/// `Cached` is tried first; it only matches bodies consisting of the
+/// `cached` flag and an optional latency, so full verdicts always fall
+/// through to `Verdict`.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum VerifyResponse {
+    Cached(CachedResponse),
+    Verdict(VerdictResponse),
+}
+
+/// The service confirmed that the verdict named in the cache hint is still
+/// valid.
 #[derive(Debug, Deserialize)]
-struct VerifyResponse {
+#[serde(deny_unknown_fields)]
+struct CachedResponse {
+    cached: bool,
+    latency_ms: Option<u64>,
+}
+
+/// A freshly computed (or server-cached) verdict.
+#[derive(Debug, Deserialize)]
+struct VerdictResponse {
     ok: bool,
+    score: Option<f32>,
     trigger_acc: Option<f32>,
     feat_dist: Option<f32>,
     logit_stat: Option<f32>,
     latency_ms: Option<u64>,
 }
 
+impl From<VerdictResponse> for MlVerdict {
+    fn from(body: VerdictResponse) -> Self {
+        MlVerdict {
+            ok: body.ok,
+            score: body.score,
+            trigger_acc: body.trigger_acc,
+            feat_dist: body.feat_dist,
+            logit_stat: body.logit_stat,
+            latency_ms: body.latency_ms,
+            quorum: None,
+        }
+    }
+}
+
 fn hash256_to_hex(h: &Hash256) -> String {
     hex::encode(h.as_bytes())
 }
@@ -147,23 +277,67 @@ fn evidence_hash_to_hex(eh: &EvidenceHash) -> String {
     hash256_to_hex(eh.as_hash())
 }
 
-impl MlVerifier for HttpMlVerifier {
-    fn verify(&self, aid: &Aid, evidence: &EvidenceRef) -> Result<MlVerdict, MlError> {
-        let url = self.endpoint("/verify");
-
-        let req_body = VerifyRequest {
+impl HttpMlVerifier {
+    /// Builds the request for one artefact, adding a cache hint if a
+    /// verdict for it is remembered. Returns the remembered verdict too.
+    fn verify_request(
+        &self,
+        aid: &Aid,
+        evidence: &EvidenceRef,
+    ) -> (VerifyRequest, Option<MlVerdict>) {
+        let known = self.cached_verdict(&(*aid, evidence.evidence_hash));
+        let req = VerifyRequest {
             aid: aid_to_hex(aid),
             scheme_id: evidence.scheme_id.clone(),
             evidence_hash: evidence_hash_to_hex(&evidence.evidence_hash),
             wm_profile: evidence.wm_profile.clone(),
+            cache_hint: known.as_ref().map(|_| CacheHint {
+                evidence_hash: evidence_hash_to_hex(&evidence.evidence_hash),
+            }),
         };
+        (req, known)
+    }
 
-        let resp = self
-            .client
-            .post(&url)
-            .json(&req_body)
-            .send()
-            .map_err(|e| MlError::Transport(format!("HTTP POST {url} failed: {e}")))?;
+    /// POSTs `body` as JSON to `path` and parses the JSON response.
+    ///
+    /// The call is gated by the circuit breaker, which counts transport
+    /// errors and `5xx` statuses (after retries) as failures.
+    async fn post_json<B, R>(&self, path: &str, body: &B) -> Result<R, MlError>
+    where
+        B: Serialize + ?Sized,
+        R: DeserializeOwned,
+    {
+        self.breaker.acquire()?;
+        let url = self.endpoint(path);
+
+        let mut headers = HeaderMap::new();
+        inject_trace_context(&mut headers);
+        let sent = self
+            .transport
+            .send(
+                self.transport
+                    .request(Method::POST, &url)
+                    .headers(headers)
+                    .json(body),
+            )
+            .await;
+        let resp = match sent {
+            Ok(resp) if resp.status().is_server_error() => {
+                self.breaker.record(false);
+                return Err(MlError::Transport(format!(
+                    "ML service returned HTTP status {}",
+                    resp.status()
+                )));
+            }
+            Ok(resp) => {
+                self.breaker.record(true);
+                resp
+            }
+            Err(e) => {
+                self.breaker.record(false);
+                return Err(MlError::Transport(format!("HTTP POST {url} failed: {e}")));
+            }
+        };
 
         let status = resp.status();
         if !status.is_success() {
@@ -172,17 +346,78 @@ impl MlVerifier for HttpMlVerifier {
             )));
         }
 
-        let body = resp
-            .json::<VerifyResponse>()
-            .map_err(|e| MlError::Protocol(format!("failed to parse JSON response: {e}")))?;
+        resp.json::<R>()
+            .await
+            .map_err(|e| MlError::Protocol(format!("failed to parse JSON response: {e}")))
+    }
+}
 
-        Ok(MlVerdict {
-            ok: body.ok,
-            trigger_acc: body.trigger_acc,
-            feat_dist: body.feat_dist,
-            logit_stat: body.logit_stat,
-            latency_ms: body.latency_ms,
-        })
+impl AsyncMlVerifier for HttpMlVerifier {
+    #[tracing::instrument(
+        name = "ml_verify",
+        skip_all,
+        fields(aid = %aid_to_hex(aid), scheme = %evidence.scheme_id)
+    )]
+    async fn verify(&self, aid: &Aid, evidence: &EvidenceRef) -> Result<MlVerdict, MlError> {
+        let (req_body, known) = self.verify_request(aid, evidence);
+        let body: VerifyResponse = self.post_json("/verify", &req_body).await?;
+
+        let verdict = resolve_response(body, known)?;
+        self.remember_verdict((*aid, evidence.evidence_hash), verdict.clone());
+        Ok(verdict)
+    }
+
+    /// Verifies all `items` with a single `POST /verify_batch`.
+    #[tracing::instrument(name = "ml_verify_batch", skip_all, fields(artefacts = items.len()))]
+    async fn verify_batch(&self, items: &[(Aid, EvidenceRef)]) -> Result<Vec<MlVerdict>, MlError> {
+        if items.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let (requests, known): (Vec<_>, Vec<_>) = items
+            .iter()
+            .map(|(aid, evidence)| self.verify_request(aid, evidence))
+            .unzip();
+        let body: VerifyBatchResponse = self
+            .post_json("/verify_batch", &VerifyBatchRequest { items: requests })
+            .await?;
+
+        if body.verdicts.len() != items.len() {
+            return Err(MlError::Protocol(format!(
+                "batch response has {} verdicts for {} items",
+                body.verdicts.len(),
+                items.len()
+            )));
+        }
+
+        let mut verdicts = Vec::with_capacity(items.len());
+        for ((response, known), (aid, evidence)) in body.verdicts.into_iter().zip(known).zip(items)
+        {
+            let verdict = resolve_response(response, known)?;
+            self.remember_verdict((*aid, evidence.evidence_hash), verdict.clone());
+            verdicts.push(verdict);
+        }
+        Ok(verdicts)
+    }
+}
+
+/// Turns a service response into a verdict, reusing `known` for cached
+/// responses.
+fn resolve_response(body: VerifyResponse, known: Option<MlVerdict>) -> Result<MlVerdict, MlError> {
+    match body {
+        VerifyResponse::Verdict(body) => Ok(body.into()),
+        VerifyResponse::Cached(CachedResponse { cached: false, .. }) => Err(MlError::Protocol(
+            "response has `cached: false` but no verdict".to_string(),
+        )),
+        VerifyResponse::Cached(CachedResponse { latency_ms, .. }) => match known {
+            Some(verdict) => Ok(MlVerdict {
+                latency_ms: latency_ms.or(verdict.latency_ms),
+                ..verdict
+            }),
+            None => Err(MlError::Protocol(
+                "service returned a cached verdict but no cache hint was sent".to_string(),
+            )),
+        },
     }
 }
 
@@ -223,6 +458,7 @@ mod tests {
         let json = r#"
         {
           "ok": true,
+          "score": 0.9,
           "trigger_acc": 0.96,
           "feat_dist": 0.04,
           "logit_stat": 0.01,
@@ -231,10 +467,97 @@ mod tests {
         "#;
 
         let resp: VerifyResponse = serde_json::from_str(json).expect("VerifyResponse should parse");
+        let VerifyResponse::Verdict(resp) = resp else {
+            panic!("expected a full verdict, got {resp:?}");
+        };
         assert!(resp.ok);
+        assert_eq!(resp.score, Some(0.9));
         assert_eq!(resp.trigger_acc, Some(0.96));
         assert_eq!(resp.feat_dist, Some(0.04));
         assert_eq!(resp.logit_stat, Some(0.01));
         assert_eq!(resp.latency_ms, Some(142));
     }
+
+    #[test]
+    fn verify_request_only_carries_cache_hint_when_set() {
+        let profile = WmProfile {
+            tau_input: 0.9,
+            tau_feat: 0.1,
+            logit_band_low: 0.02,
+            logit_band_high: 0.05,
+        };
+        let mut req = VerifyRequest {
+            aid: "aa".to_string(),
+            scheme_id: "multi_factor_v1".to_string(),
+            evidence_hash: "bb".to_string(),
+            wm_profile: profile,
+            cache_hint: None,
+        };
+
+        let json = serde_json::to_value(&req).unwrap();
+        assert!(json.get("cache_hint").is_none());
+
+        req.cache_hint = Some(CacheHint {
+            evidence_hash: "bb".to_string(),
+        });
+        let json = serde_json::to_value(&req).unwrap();
+        assert_eq!(json["cache_hint"]["evidence_hash"], "bb");
+    }
+
+    #[test]
+    fn verify_batch_response_can_be_deserialized() {
+        let json = r#"
+        {
+          "verdicts": [
+            { "ok": true, "score": 0.9 },
+            { "cached": true },
+            { "ok": false }
+          ]
+        }
+        "#;
+
+        let resp: VerifyBatchResponse =
+            serde_json::from_str(json).expect("VerifyBatchResponse should parse");
+        assert_eq!(resp.verdicts.len(), 3);
+        assert!(matches!(resp.verdicts[0], VerifyResponse::Verdict(_)));
+        assert!(matches!(resp.verdicts[1], VerifyResponse::Cached(_)));
+        assert!(matches!(
+            &resp.verdicts[2],
+            VerifyResponse::Verdict(v) if !v.ok
+        ));
+    }
+
+    #[test]
+    fn cached_response_reuses_known_verdict() {
+        let known = MlVerdict {
+            ok: true,
+            score: Some(0.8),
+            trigger_acc: None,
+            feat_dist: None,
+            logit_stat: None,
+            latency_ms: Some(150),
+            quorum: None,
+        };
+
+        let body: VerifyResponse =
+            serde_json::from_str(r#"{ "cached": true, "latency_ms": 1 }"#).unwrap();
+        let verdict = resolve_response(body, Some(known.clone())).unwrap();
+        assert!(verdict.ok);
+        assert_eq!(verdict.score, Some(0.8));
+        assert_eq!(verdict.latency_ms, Some(1));
+
+        // A cached answer to a request without a hint is a protocol error.
+        let body: VerifyResponse = serde_json::from_str(r#"{ "cached": true }"#).unwrap();
+        assert!(matches!(
+            resolve_response(body, None),
+            Err(MlError::Protocol(_))
+        ));
+
+        // A full verdict that also reports `cached` is still a verdict.
+        let body: VerifyResponse =
+            serde_json::from_str(r#"{ "ok": false, "cached": true }"#).unwrap();
+        let verdict = resolve_response(body, Some(known)).unwrap();
+        assert!(!verdict.ok);
+        assert_eq!(verdict.score, None);
+    }
 }
@@ -0,0 +1,220 @@
+//! In-process ML verifier backed by an ONNX detector model.
+//!
+//! [`LocalOnnxVerifier`] removes the need for the Python service on
+//! devnets and in CI: it loads a watermark detector exported to ONNX and
+//! runs it through ONNX Runtime inside the node. The runtime library is
+//! loaded at start-up from `ORT_DYLIB_PATH` (or the platform's default
+//! search path), so it does not have to be present at build time.
+//!
+//! The detector takes one `f32` input of shape `[n, 68]`, one row per
+//! artefact:
+//!
+//! - 32 values: the `Aid` bytes scaled to `[0, 1]`,
+//! - 32 values: the evidence hash bytes scaled to `[0, 1]`,
+//! - 4 values: the `WmProfile` (`tau_input`, `tau_feat`, `logit_band_low`,
+//!   `logit_band_high`),
+//!
+//! and returns, as its first output, `f32` statistics of shape `[n, 3]`:
+//! trigger-set accuracy, feature distance, and logit statistic.
+//!
+//! Verdicts mirror the Python service: the trigger accuracy is the score,
+//! and an artefact passes the multi-factor test against its `WmProfile`,
+//! unless its scheme has an entry in `thresholds`, in which case it passes
+//! iff the score reaches the threshold.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Instant;
+
+use ort::environment::Environment;
+use ort::session::Session;
+use ort::value::Tensor;
+
+use crate::config::LocalModelConfig;
+use crate::types::{Aid, EvidenceRef, HASH_LEN, WmProfile};
+use crate::validation::{MlError, MlVerdict, MlVerifier};
+
+/// Number of input features per artefact.
+const FEATURES: usize = 2 * HASH_LEN + 4;
+
+/// Number of statistics the detector returns per artefact.
+const STATS: usize = 3;
+
+/// ONNX-based ML verifier running in the node process.
+pub struct LocalOnnxVerifier {
+    /// Kept alive for as long as the session uses it.
+    _environment: Environment,
+    session: Mutex<Session>,
+    thresholds: HashMap<String, f32>,
+}
+
+impl LocalOnnxVerifier {
+    /// Loads the detector model named by `cfg.model_path`.
+    pub fn new(cfg: &LocalModelConfig) -> Result<Self, MlError> {
+        let environment = ort::init()
+            .with_name("mlsnitch")
+            .build()
+            .map_err(|e| MlError::Service(format!("failed to start ONNX Runtime: {e}")))?;
+        let session = Session::builder(&environment)
+            .and_then(|mut builder| builder.commit_from_file(&cfg.model_path))
+            .map_err(|e| {
+                MlError::Service(format!(
+                    "failed to load detector {}: {e}",
+                    cfg.model_path.display()
+                ))
+            })?;
+
+        Ok(Self {
+            _environment: environment,
+            session: Mutex::new(session),
+            thresholds: cfg.thresholds.clone(),
+        })
+    }
+
+    /// Runs the detector on `items` and returns one row of statistics each.
+    fn detect(&self, items: &[(Aid, EvidenceRef)]) -> Result<Vec<[f32; STATS]>, MlError> {
+        let input = Tensor::from_array(([items.len(), FEATURES], encode_features(items)))
+            .map_err(|e| MlError::Protocol(format!("failed to build detector input: {e}")))?;
+
+        let mut session = self.session.lock().expect("ONNX session lock poisoned");
+        let outputs = session
+            .run(ort::inputs![input])
+            .map_err(|e| MlError::Service(format!("detector inference failed: {e}")))?;
+        if outputs.len() == 0 {
+            return Err(MlError::Protocol(
+                "detector returned no outputs".to_string(),
+            ));
+        }
+        let (_, stats) = outputs[0]
+            .try_extract_tensor::<f32>()
+            .map_err(|e| MlError::Protocol(format!("unexpected detector output: {e}")))?;
+        if stats.len() != items.len() * STATS {
+            return Err(MlError::Protocol(format!(
+                "detector returned {} values for {} artefacts",
+                stats.len(),
+                items.len()
+            )));
+        }
+
+        Ok(stats
+            .chunks_exact(STATS)
+            .map(|row| [row[0], row[1], row[2]])
+            .collect())
+    }
+}
+
+/// Lays out the detector input rows for `items` (see the module docs).
+fn encode_features(items: &[(Aid, EvidenceRef)]) -> Vec<f32> {
+    let mut features = Vec::with_capacity(items.len() * FEATURES);
+    for (aid, evidence) in items {
+        let bytes = aid
+            .as_hash()
+            .as_bytes()
+            .iter()
+            .chain(evidence.evidence_hash.as_hash().as_bytes());
+        features.extend(bytes.map(|b| f32::from(*b) / 255.0));
+        let WmProfile {
+            tau_input,
+            tau_feat,
+            logit_band_low,
+            logit_band_high,
+        } = evidence.wm_profile;
+        features.extend([tau_input, tau_feat, logit_band_low, logit_band_high]);
+    }
+    features
+}
+
+/// Turns one row of detector statistics into a verdict.
+fn decide(
+    thresholds: &HashMap<String, f32>,
+    evidence: &EvidenceRef,
+    [trigger_acc, feat_dist, logit_stat]: [f32; STATS],
+    latency_ms: u64,
+) -> MlVerdict {
+    let profile = &evidence.wm_profile;
+    let ok = match thresholds.get(&evidence.scheme_id) {
+        Some(threshold) => trigger_acc >= *threshold,
+        None => {
+            trigger_acc >= profile.tau_input
+                && feat_dist <= profile.tau_feat
+                && (profile.logit_band_low..=profile.logit_band_high).contains(&logit_stat)
+        }
+    };
+
+    MlVerdict {
+        ok,
+        score: Some(trigger_acc),
+        trigger_acc: Some(trigger_acc),
+        feat_dist: Some(feat_dist),
+        logit_stat: Some(logit_stat),
+        latency_ms: Some(latency_ms),
+        quorum: None,
+    }
+}
+
+impl MlVerifier for LocalOnnxVerifier {
+    fn verify(&self, aid: &Aid, evidence: &EvidenceRef) -> Result<MlVerdict, MlError> {
+        let mut verdicts = self.verify_batch(&[(*aid, evidence.clone())])?;
+        Ok(verdicts.remove(0))
+    }
+
+    /// Runs the whole batch through the detector in one inference call.
+    fn verify_batch(&self, items: &[(Aid, EvidenceRef)]) -> Result<Vec<MlVerdict>, MlError> {
+        if items.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let started = Instant::now();
+        let stats = self.detect(items)?;
+        let latency_ms = u64::try_from(started.elapsed().as_millis()).unwrap_or(u64::MAX);
+        Ok(items
+            .iter()
+            .zip(stats)
+            .map(|((_, evidence), row)| decide(&self.thresholds, evidence, row, latency_ms))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{EvidenceHash, Hash256};
+
+    fn evidence(scheme_id: &str) -> EvidenceRef {
+        EvidenceRef {
+            scheme_id: scheme_id.to_string(),
+            evidence_hash: EvidenceHash(Hash256([0xFF; HASH_LEN])),
+            wm_profile: WmProfile {
+                tau_input: 0.9,
+                tau_feat: 0.1,
+                logit_band_low: 0.02,
+                logit_band_high: 0.05,
+            },
+        }
+    }
+
+    #[test]
+    fn features_follow_the_documented_layout() {
+        let items = [(Aid(Hash256([0; HASH_LEN])), evidence("wm-1"))];
+        let features = encode_features(&items);
+
+        assert_eq!(features.len(), FEATURES);
+        assert_eq!(features[0], 0.0);
+        assert_eq!(features[HASH_LEN], 1.0);
+        assert_eq!(&features[2 * HASH_LEN..], &[0.9, 0.1, 0.02, 0.05]);
+    }
+
+    #[test]
+    fn decide_uses_profile_unless_scheme_has_threshold() {
+        let mut thresholds = HashMap::new();
+        let stats = [0.95, 0.05, 0.03];
+
+        assert!(decide(&thresholds, &evidence("wm-1"), stats, 1).ok);
+        assert!(!decide(&thresholds, &evidence("wm-1"), [0.95, 0.2, 0.03], 1).ok);
+
+        thresholds.insert("wm-1".to_string(), 0.97);
+        let verdict = decide(&thresholds, &evidence("wm-1"), stats, 1);
+        assert!(!verdict.ok);
+        assert_eq!(verdict.score, Some(0.95));
+    }
+}
@@ -0,0 +1,399 @@
+//! Scriptable in-memory ML verifier for tests and simulations.
+//!
+//! [`MockMlVerifier`] implements both [`MlVerifier`] and
+//! [`AsyncMlVerifier`] without any service behind it. By default it
+//! answers every artefact with one [`MockOutcome`]; on top of that:
+//!
+//! - [`with_script`](MockMlVerifier::with_script) queues outcomes for a
+//!   single `(Aid, EvidenceHash)`, consumed one per call before falling
+//!   back to the default,
+//! - [`with_latency`](MockMlVerifier::with_latency) delays every call,
+//! - [`with_flakiness`](MockMlVerifier::with_flakiness) fails a fraction of
+//!   calls with [`MlError::Transport`].
+//!
+//! All randomness is derived from a seed, so a run with the same seed and
+//! the same calls sees the same verdicts. [`MockMlVerifier::from_spec`]
+//! builds a verifier matching a simulator [`MlVerifierSpec`], and
+//! [`MockOutcome::sequence`] generates scripted outcome sequences.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::sim::MlVerifierSpec;
+use crate::types::{Aid, EvidenceHash, EvidenceRef};
+use crate::validation::{AsyncMlVerifier, MlError, MlVerdict, MlVerifier};
+
+/// What the mock answers for one artefact.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MockOutcome {
+    /// A passing verdict with this score.
+    Accept(f32),
+    /// A failing verdict with this score.
+    Reject(f32),
+    /// A verifier error instead of a verdict; fails the whole call.
+    Error,
+}
+
+impl MockOutcome {
+    /// Generates `len` outcomes from `seed`: each is an error with
+    /// probability `error_rate`, otherwise a rejection with probability
+    /// `reject_rate`, otherwise an acceptance.
+    pub fn sequence(seed: u64, len: usize, reject_rate: f64, error_rate: f64) -> Vec<Self> {
+        (0..len as u64)
+            .map(|i| {
+                let draw = unit(&[b"sequence", &seed.to_le_bytes(), &i.to_le_bytes()]);
+                if draw < error_rate {
+                    MockOutcome::Error
+                } else if draw < error_rate + (1.0 - error_rate) * reject_rate {
+                    MockOutcome::Reject(0.1)
+                } else {
+                    MockOutcome::Accept(0.9)
+                }
+            })
+            .collect()
+    }
+
+    fn verdict(self, latency: Duration) -> Result<MlVerdict, MlError> {
+        let (ok, score) = match self {
+            MockOutcome::Accept(score) => (true, score),
+            MockOutcome::Reject(score) => (false, score),
+            MockOutcome::Error => {
+                return Err(MlError::Service("scripted mock error".to_string()));
+            }
+        };
+        Ok(MlVerdict {
+            ok,
+            score: Some(score),
+            trigger_acc: None,
+            feat_dist: None,
+            logit_stat: None,
+            latency_ms: Some(u64::try_from(latency.as_millis()).unwrap_or(u64::MAX)),
+            quorum: None,
+        })
+    }
+}
+
+/// Maps a hash of `parts` to a number in `[0, 1)`.
+fn unit(parts: &[&[u8]]) -> f64 {
+    let mut hasher = blake3::Hasher::new();
+    for part in parts {
+        hasher.update(part);
+    }
+    let bytes: [u8; 8] = hasher.finalize().as_bytes()[..8]
+        .try_into()
+        .expect("blake3 output is 32 bytes");
+    (u64::from_le_bytes(bytes) >> 11) as f64 / (1u64 << 53) as f64
+}
+
+/// How artefacts without a script are judged.
+#[derive(Clone, Copy, Debug)]
+enum DefaultOutcome {
+    Fixed(MockOutcome),
+    /// Rejects a seeded, stable fraction of artefacts.
+    RejectRate {
+        rate: f64,
+        seed: u64,
+    },
+}
+
+#[derive(Debug, Default)]
+struct MockState {
+    scripts: HashMap<(Aid, EvidenceHash), VecDeque<MockOutcome>>,
+    calls: Vec<(Aid, EvidenceHash)>,
+    /// Number of calls drawn for flakiness and jitter so far.
+    draws: u64,
+}
+
+/// Scriptable ML verifier; see the module docs.
+#[derive(Debug)]
+pub struct MockMlVerifier {
+    default: DefaultOutcome,
+    latency: Duration,
+    jitter: Duration,
+    flakiness: f64,
+    seed: u64,
+    state: Mutex<MockState>,
+}
+
+impl MockMlVerifier {
+    /// A verifier answering every unscripted artefact with `outcome`.
+    pub fn new(outcome: MockOutcome) -> Self {
+        Self {
+            default: DefaultOutcome::Fixed(outcome),
+            latency: Duration::ZERO,
+            jitter: Duration::ZERO,
+            flakiness: 0.0,
+            seed: 0,
+            state: Mutex::new(MockState::default()),
+        }
+    }
+
+    /// A verifier accepting every unscripted artefact.
+    pub fn accepting() -> Self {
+        Self::new(MockOutcome::Accept(1.0))
+    }
+
+    /// A verifier rejecting every unscripted artefact.
+    pub fn rejecting() -> Self {
+        Self::new(MockOutcome::Reject(0.0))
+    }
+
+    /// A verifier behaving like the simulator's `spec`: every call takes
+    /// `latency_ms` plus up to `jitter_ms`, fails with probability
+    /// `error_rate`, and a fixed `reject_rate` share of artefacts (chosen
+    /// by `seed`) is rejected on every call.
+    pub fn from_spec(spec: &MlVerifierSpec, seed: u64) -> Self {
+        Self {
+            default: DefaultOutcome::RejectRate {
+                rate: spec.reject_rate,
+                seed,
+            },
+            latency: Duration::from_millis(spec.latency_ms),
+            jitter: Duration::from_millis(spec.jitter_ms),
+            flakiness: spec.error_rate,
+            seed,
+            state: Mutex::new(MockState::default()),
+        }
+    }
+
+    /// Queues `outcomes` for the artefact `(aid, evidence_hash)`, one per
+    /// call, after any already queued.
+    pub fn with_script(
+        self,
+        aid: Aid,
+        evidence_hash: EvidenceHash,
+        outcomes: impl IntoIterator<Item = MockOutcome>,
+    ) -> Self {
+        self.script(aid, evidence_hash, outcomes);
+        self
+    }
+
+    /// Delays every call by `latency`.
+    pub fn with_latency(mut self, latency: Duration) -> Self {
+        self.latency = latency;
+        self
+    }
+
+    /// Fails each call with probability `rate`, drawn from `seed`.
+    pub fn with_flakiness(mut self, rate: f64, seed: u64) -> Self {
+        self.flakiness = rate;
+        self.seed = seed;
+        self
+    }
+
+    /// Like [`with_script`](Self::with_script), on a shared verifier.
+    pub fn script(
+        &self,
+        aid: Aid,
+        evidence_hash: EvidenceHash,
+        outcomes: impl IntoIterator<Item = MockOutcome>,
+    ) {
+        let mut state = self.state.lock().expect("mock verifier lock poisoned");
+        state
+            .scripts
+            .entry((aid, evidence_hash))
+            .or_default()
+            .extend(outcomes);
+    }
+
+    /// Returns every artefact verified so far, in call order.
+    pub fn calls(&self) -> Vec<(Aid, EvidenceHash)> {
+        let state = self.state.lock().expect("mock verifier lock poisoned");
+        state.calls.clone()
+    }
+
+    /// Decides a whole call up front: its delay and its verdicts.
+    fn answer(&self, items: &[(Aid, EvidenceRef)]) -> (Duration, Result<Vec<MlVerdict>, MlError>) {
+        let mut state = self.state.lock().expect("mock verifier lock poisoned");
+        let draw = state.draws.to_le_bytes();
+        state.draws += 1;
+
+        let seed = self.seed.to_le_bytes();
+        let jitter = self.jitter.mul_f64(unit(&[b"jitter", &seed, &draw]));
+        let latency = self.latency + jitter;
+        if unit(&[b"flaky", &seed, &draw]) < self.flakiness {
+            return (
+                latency,
+                Err(MlError::Transport("injected mock failure".to_string())),
+            );
+        }
+
+        let mut verdicts = Vec::with_capacity(items.len());
+        for (aid, evidence) in items {
+            let key = (*aid, evidence.evidence_hash);
+            state.calls.push(key);
+            let outcome = state
+                .scripts
+                .get_mut(&key)
+                .and_then(VecDeque::pop_front)
+                .unwrap_or_else(|| self.default_outcome(&key));
+            match outcome.verdict(latency) {
+                Ok(verdict) => verdicts.push(verdict),
+                Err(e) => return (latency, Err(e)),
+            }
+        }
+        (latency, Ok(verdicts))
+    }
+
+    fn default_outcome(&self, (aid, evidence_hash): &(Aid, EvidenceHash)) -> MockOutcome {
+        match self.default {
+            DefaultOutcome::Fixed(outcome) => outcome,
+            DefaultOutcome::RejectRate { rate, seed } => {
+                let draw = unit(&[
+                    b"reject",
+                    &seed.to_le_bytes(),
+                    aid.as_hash().as_bytes(),
+                    evidence_hash.as_hash().as_bytes(),
+                ]);
+                if draw < rate {
+                    MockOutcome::Reject(0.1)
+                } else {
+                    MockOutcome::Accept(0.9)
+                }
+            }
+        }
+    }
+}
+
+impl MlVerifier for MockMlVerifier {
+    fn verify(&self, aid: &Aid, evidence: &EvidenceRef) -> Result<MlVerdict, MlError> {
+        let mut verdicts = MlVerifier::verify_batch(self, &[(*aid, evidence.clone())])?;
+        Ok(verdicts.remove(0))
+    }
+
+    fn verify_batch(&self, items: &[(Aid, EvidenceRef)]) -> Result<Vec<MlVerdict>, MlError> {
+        let (latency, result) = self.answer(items);
+        std::thread::sleep(latency);
+        result
+    }
+
+    /// Stops waiting at `deadline`, as a client with a request timeout
+    /// would.
+    fn verify_batch_until(
+        &self,
+        items: &[(Aid, EvidenceRef)],
+        deadline: Instant,
+    ) -> Result<Vec<MlVerdict>, MlError> {
+        let (latency, result) = self.answer(items);
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if latency > remaining {
+            std::thread::sleep(remaining);
+            return Err(MlError::DeadlineExceeded(format!(
+                "mock answer takes {latency:?}, deadline in {remaining:?}"
+            )));
+        }
+        std::thread::sleep(latency);
+        result
+    }
+}
+
+impl AsyncMlVerifier for MockMlVerifier {
+    async fn verify(&self, aid: &Aid, evidence: &EvidenceRef) -> Result<MlVerdict, MlError> {
+        let mut verdicts = AsyncMlVerifier::verify_batch(self, &[(*aid, evidence.clone())]).await?;
+        Ok(verdicts.remove(0))
+    }
+
+    async fn verify_batch(&self, items: &[(Aid, EvidenceRef)]) -> Result<Vec<MlVerdict>, MlError> {
+        let (latency, result) = self.answer(items);
+        tokio::time::sleep(latency).await;
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{HASH_LEN, Hash256, WmProfile};
+
+    fn item(byte: u8) -> (Aid, EvidenceRef) {
+        let evidence = EvidenceRef {
+            scheme_id: "wm-test-1".to_string(),
+            evidence_hash: EvidenceHash(Hash256([byte; HASH_LEN])),
+            wm_profile: WmProfile {
+                tau_input: 0.9,
+                tau_feat: 0.1,
+                logit_band_low: 0.02,
+                logit_band_high: 0.05,
+            },
+        };
+        (Aid(Hash256([byte; HASH_LEN])), evidence)
+    }
+
+    #[test]
+    fn scripts_are_consumed_before_the_default() {
+        let (aid, evidence) = item(1);
+        let mock = MockMlVerifier::accepting().with_script(
+            aid,
+            evidence.evidence_hash,
+            [MockOutcome::Reject(0.2), MockOutcome::Error],
+        );
+
+        let first = MlVerifier::verify(&mock, &aid, &evidence).unwrap();
+        assert!(!first.ok);
+        assert_eq!(first.score, Some(0.2));
+        assert!(matches!(
+            MlVerifier::verify(&mock, &aid, &evidence),
+            Err(MlError::Service(_))
+        ));
+        assert!(MlVerifier::verify(&mock, &aid, &evidence).unwrap().ok);
+        assert!(
+            MlVerifier::verify(&mock, &item(2).0, &item(2).1)
+                .unwrap()
+                .ok
+        );
+        assert_eq!(mock.calls().len(), 4);
+    }
+
+    #[tokio::test]
+    async fn flakiness_and_rejections_are_reproducible_from_the_seed() {
+        let spec = MlVerifierSpec {
+            latency_ms: 0,
+            jitter_ms: 0,
+            reject_rate: 0.5,
+            error_rate: 0.3,
+        };
+        let items: Vec<_> = (0..32).map(item).collect();
+        let (spec, items) = (&spec, &items);
+
+        let run = |seed| async move {
+            let mock = MockMlVerifier::from_spec(spec, seed);
+            let mut outcomes = Vec::new();
+            for _ in 0..20 {
+                let result = AsyncMlVerifier::verify_batch(&mock, items).await;
+                outcomes.push(
+                    result
+                        .map(|v| v.iter().map(|v| v.ok).collect::<Vec<_>>())
+                        .ok(),
+                );
+            }
+            outcomes
+        };
+
+        let a = run(7).await;
+        assert_eq!(a, run(7).await);
+        assert!(a.iter().any(Option::is_none), "some calls should fail");
+        let verdicts = a
+            .iter()
+            .flatten()
+            .next()
+            .expect("some calls should succeed");
+        assert!(verdicts.contains(&true) && verdicts.contains(&false));
+        // An artefact's verdict does not change between calls.
+        assert!(a.iter().flatten().all(|v| v == verdicts));
+    }
+
+    #[test]
+    fn generated_sequences_are_deterministic() {
+        let a = MockOutcome::sequence(3, 100, 0.2, 0.1);
+        assert_eq!(a, MockOutcome::sequence(3, 100, 0.2, 0.1));
+        assert_ne!(a, MockOutcome::sequence(4, 100, 0.2, 0.1));
+        assert!(a.contains(&MockOutcome::Error));
+        assert!(a.iter().any(|o| matches!(o, MockOutcome::Reject(_))));
+        assert!(
+            MockOutcome::sequence(3, 100, 0.0, 0.0)
+                .iter()
+                .all(|o| matches!(o, MockOutcome::Accept(_)))
+        );
+    }
+}
@@ -1,10 +1,117 @@
 //! Clients for the external ML verification service.
 //!
 //! This module provides concrete implementations of the generic
-//! [`crate::validation::MlVerifier`] trait. These clients are responsible
+//! [`crate::validation::AsyncMlVerifier`] trait. These clients are responsible
 //! for talking to the Python + PyTorch watermarking service over HTTP/gRPC
 //! and translating responses into [`crate::validation::MlVerdict`] values.
+//! With the `local-onnx` feature, `LocalOnnxVerifier` runs an ONNX
+//! detector in-process instead.
+//!
+//! [`MlClient`] picks one of them at runtime according to
+//! [`MlClientConfig::protocol`]. [`QuorumMlVerifier`] combines several
+//! verifiers into a single k-of-n verdict, and [`MockMlVerifier`] answers
+//! with scripted verdicts for tests and simulations.
 
+pub mod breaker;
+pub mod grpc;
 pub mod http;
+#[cfg(feature = "local-onnx")]
+pub mod local;
+pub mod mock;
+pub mod quorum;
 
+pub use breaker::{CircuitBreaker, CircuitBreakerConfig};
+pub use grpc::GrpcMlVerifier;
 pub use http::HttpMlVerifier;
+#[cfg(feature = "local-onnx")]
+pub use local::LocalOnnxVerifier;
+pub use mock::{MockMlVerifier, MockOutcome};
+pub use quorum::{QuorumMlVerifier, QuorumPolicy};
+
+#[cfg(feature = "local-onnx")]
+use std::sync::Arc;
+
+use prometheus::IntCounterVec;
+
+use crate::config::{MlClientConfig, MlProtocol};
+use crate::transport::HttpTransport;
+use crate::types::{Aid, EvidenceRef};
+use crate::validation::{AsyncMlVerifier, MlError, MlVerdict};
+
+/// ML verifier selected by configuration.
+pub enum MlClient {
+    Http(HttpMlVerifier),
+    Grpc(GrpcMlVerifier),
+    /// Shared so that blocking inference can run on Tokio's blocking pool.
+    #[cfg(feature = "local-onnx")]
+    Local(Arc<LocalOnnxVerifier>),
+}
+
+impl MlClient {
+    /// Builds the client described by `cfg`.
+    ///
+    /// `outbound_requests` is passed to the HTTP transport for request
+    /// metrics. Must be called from inside a Tokio runtime.
+    pub fn from_config(
+        cfg: &MlClientConfig,
+        outbound_requests: Option<IntCounterVec>,
+    ) -> Result<Self, MlError> {
+        match cfg.protocol {
+            MlProtocol::Http => {
+                let transport =
+                    HttpTransport::new("ml_service", &cfg.transport(), outbound_requests)
+                        .map_err(|e| MlError::Transport(e.to_string()))?;
+                Ok(MlClient::Http(
+                    HttpMlVerifier::with_transport(cfg.base_url.clone(), transport)
+                        .with_circuit_breaker(&cfg.circuit_breaker),
+                ))
+            }
+            MlProtocol::Grpc => GrpcMlVerifier::new(cfg).map(MlClient::Grpc),
+            #[cfg(feature = "local-onnx")]
+            MlProtocol::Local => LocalOnnxVerifier::new(&cfg.local)
+                .map(|verifier| MlClient::Local(Arc::new(verifier))),
+            #[cfg(not(feature = "local-onnx"))]
+            MlProtocol::Local => Err(MlError::Service(
+                "the local ML client requires the local-onnx feature".to_string(),
+            )),
+        }
+    }
+}
+
+impl AsyncMlVerifier for MlClient {
+    async fn verify(&self, aid: &Aid, evidence: &EvidenceRef) -> Result<MlVerdict, MlError> {
+        match self {
+            MlClient::Http(client) => client.verify(aid, evidence).await,
+            MlClient::Grpc(client) => client.verify(aid, evidence).await,
+            #[cfg(feature = "local-onnx")]
+            MlClient::Local(client) => {
+                let items = vec![(*aid, evidence.clone())];
+                let mut verdicts = run_blocking(client, items).await?;
+                Ok(verdicts.remove(0))
+            }
+        }
+    }
+
+    async fn verify_batch(&self, items: &[(Aid, EvidenceRef)]) -> Result<Vec<MlVerdict>, MlError> {
+        match self {
+            MlClient::Http(client) => client.verify_batch(items).await,
+            MlClient::Grpc(client) => client.verify_batch(items).await,
+            #[cfg(feature = "local-onnx")]
+            MlClient::Local(client) => run_blocking(client, items.to_vec()).await,
+        }
+    }
+}
+
+/// Runs local inference on the blocking thread pool.
+#[cfg(feature = "local-onnx")]
+async fn run_blocking(
+    client: &Arc<LocalOnnxVerifier>,
+    items: Vec<(Aid, EvidenceRef)>,
+) -> Result<Vec<MlVerdict>, MlError> {
+    use crate::validation::MlVerifier;
+
+    let client = Arc::clone(client);
+    tokio::task::spawn_blocking(move || client.verify_batch(&items))
+        .await
+        .map_err(|e| MlError::Service(format!("local ML verification panicked: {e}")))?
+}
@@ -0,0 +1,377 @@
+//! k-of-n combinator over several ML verifiers.
+//!
+//! [`QuorumMlVerifier`] sends every verification to all of its inner
+//! verifiers at once (e.g. independent watermark services) and decides by
+//! a [`QuorumPolicy`]:
+//!
+//! - if at least `k` verifiers accept, the artefact is accepted;
+//! - if so many reject that `k` acceptances are no longer possible, it is
+//!   rejected;
+//! - otherwise too many verifiers failed to decide either way, and the
+//!   first inner error is returned, so an outage is still reported as an
+//!   [`MlError`] rather than a rejection.
+//!
+//! The aggregated [`MlVerdict`] carries the median of the reported scores
+//! and statistics, the slowest latency, and the vote breakdown in
+//! [`MlVerdict::quorum`], which shows when the verifiers disagreed.
+
+use std::thread;
+use std::time::Instant;
+
+use futures_util::future::join_all;
+
+use crate::types::{Aid, EvidenceRef};
+use crate::validation::{AsyncMlVerifier, MlError, MlVerdict, MlVerifier, QuorumVotes};
+
+/// How many inner verifiers must accept an artefact.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum QuorumPolicy {
+    /// More than half of the verifiers.
+    Majority,
+    /// Every verifier.
+    Unanimous,
+    /// At least this many verifiers (capped at the number of verifiers).
+    AtLeast(usize),
+}
+
+impl QuorumPolicy {
+    /// Returns the number of acceptances required out of `n` verifiers.
+    pub fn required(&self, n: usize) -> usize {
+        match *self {
+            QuorumPolicy::Majority => n / 2 + 1,
+            QuorumPolicy::Unanimous => n,
+            QuorumPolicy::AtLeast(k) => k.clamp(1, n.max(1)),
+        }
+    }
+}
+
+/// ML verifier that aggregates the verdicts of several inner verifiers.
+///
+/// Implements [`AsyncMlVerifier`] when the inner verifiers do (calls are
+/// awaited concurrently) and [`MlVerifier`] when they do (calls run on
+/// scoped threads).
+pub struct QuorumMlVerifier<V> {
+    verifiers: Vec<V>,
+    policy: QuorumPolicy,
+}
+
+impl<V> QuorumMlVerifier<V> {
+    /// Constructs a quorum over `verifiers`.
+    ///
+    /// Panics if `verifiers` is empty.
+    pub fn new(verifiers: Vec<V>, policy: QuorumPolicy) -> Self {
+        assert!(
+            !verifiers.is_empty(),
+            "QuorumMlVerifier needs at least one verifier"
+        );
+        Self { verifiers, policy }
+    }
+
+    /// Returns the inner verifiers.
+    pub fn verifiers(&self) -> &[V] {
+        &self.verifiers
+    }
+
+    /// Combines one result per inner verifier into a single verdict.
+    fn aggregate(&self, results: Vec<Result<MlVerdict, MlError>>) -> Result<MlVerdict, MlError> {
+        let required = self.policy.required(results.len());
+        let mut votes = QuorumVotes::default();
+        let mut verdicts = Vec::new();
+        let mut first_error = None;
+        for result in results {
+            match result {
+                Ok(verdict) => {
+                    if verdict.ok {
+                        votes.accepted += 1;
+                    } else {
+                        votes.rejected += 1;
+                    }
+                    verdicts.push(verdict);
+                }
+                Err(e) => {
+                    votes.failed += 1;
+                    first_error.get_or_insert(e);
+                }
+            }
+        }
+
+        let ok = votes.accepted >= required;
+        let decided = ok || votes.accepted + votes.failed < required;
+        if !decided && let Some(e) = first_error {
+            return Err(e);
+        }
+
+        Ok(MlVerdict {
+            ok,
+            score: median(verdicts.iter().map(|v| v.score)),
+            trigger_acc: median(verdicts.iter().map(|v| v.trigger_acc)),
+            feat_dist: median(verdicts.iter().map(|v| v.feat_dist)),
+            logit_stat: median(verdicts.iter().map(|v| v.logit_stat)),
+            latency_ms: verdicts.iter().filter_map(|v| v.latency_ms).max(),
+            quorum: Some(votes),
+        })
+    }
+
+    /// Aggregates per-verifier batch results item by item. A failed batch
+    /// counts as a failure for every item.
+    fn aggregate_batch(
+        &self,
+        len: usize,
+        batches: Vec<Result<Vec<MlVerdict>, MlError>>,
+    ) -> Result<Vec<MlVerdict>, MlError> {
+        let mut per_item: Vec<Vec<Result<MlVerdict, MlError>>> = (0..len)
+            .map(|_| Vec::with_capacity(batches.len()))
+            .collect();
+        for batch in batches {
+            match batch {
+                Ok(verdicts) if verdicts.len() == len => {
+                    for (item, verdict) in per_item.iter_mut().zip(verdicts) {
+                        item.push(Ok(verdict));
+                    }
+                }
+                Ok(verdicts) => {
+                    let msg = format!("batch returned {} verdicts for {len} items", verdicts.len());
+                    for item in &mut per_item {
+                        item.push(Err(MlError::Protocol(msg.clone())));
+                    }
+                }
+                Err(e) => {
+                    let msg = format!("{e:?}");
+                    for item in &mut per_item {
+                        item.push(Err(clone_error(&e, &msg)));
+                    }
+                }
+            }
+        }
+        per_item
+            .into_iter()
+            .map(|results| self.aggregate(results))
+            .collect()
+    }
+}
+
+/// Copies an [`MlError`] (which is not `Clone`) for each batch item.
+fn clone_error(e: &MlError, msg: &str) -> MlError {
+    let msg = msg.to_string();
+    match e {
+        MlError::Transport(_) => MlError::Transport(msg),
+        MlError::Protocol(_) => MlError::Protocol(msg),
+        MlError::Service(_) => MlError::Service(msg),
+        MlError::CircuitOpen(_) => MlError::CircuitOpen(msg),
+        MlError::DeadlineExceeded(_) => MlError::DeadlineExceeded(msg),
+    }
+}
+
+/// Median of the reported values, or `None` if no verifier reported one.
+fn median(values: impl Iterator<Item = Option<f32>>) -> Option<f32> {
+    let mut values: Vec<f32> = values.flatten().collect();
+    if values.is_empty() {
+        return None;
+    }
+    values.sort_by(f32::total_cmp);
+    let mid = values.len() / 2;
+    Some(if values.len().is_multiple_of(2) {
+        (values[mid - 1] + values[mid]) / 2.0
+    } else {
+        values[mid]
+    })
+}
+
+impl<V: AsyncMlVerifier> AsyncMlVerifier for QuorumMlVerifier<V> {
+    async fn verify(&self, aid: &Aid, evidence: &EvidenceRef) -> Result<MlVerdict, MlError> {
+        let results = join_all(self.verifiers.iter().map(|v| v.verify(aid, evidence))).await;
+        self.aggregate(results)
+    }
+
+    async fn verify_batch(&self, items: &[(Aid, EvidenceRef)]) -> Result<Vec<MlVerdict>, MlError> {
+        let batches = join_all(self.verifiers.iter().map(|v| v.verify_batch(items))).await;
+        self.aggregate_batch(items.len(), batches)
+    }
+}
+
+impl<V: MlVerifier> MlVerifier for QuorumMlVerifier<V> {
+    fn verify(&self, aid: &Aid, evidence: &EvidenceRef) -> Result<MlVerdict, MlError> {
+        let results = thread::scope(|s| {
+            let handles: Vec<_> = self
+                .verifiers
+                .iter()
+                .map(|v| s.spawn(move || v.verify(aid, evidence)))
+                .collect();
+            handles
+                .into_iter()
+                .map(|h| h.join().expect("ML verifier thread panicked"))
+                .collect()
+        });
+        self.aggregate(results)
+    }
+
+    fn verify_batch(&self, items: &[(Aid, EvidenceRef)]) -> Result<Vec<MlVerdict>, MlError> {
+        let batches = thread::scope(|s| {
+            let handles: Vec<_> = self
+                .verifiers
+                .iter()
+                .map(|v| s.spawn(move || v.verify_batch(items)))
+                .collect();
+            handles
+                .into_iter()
+                .map(|h| h.join().expect("ML verifier thread panicked"))
+                .collect()
+        });
+        self.aggregate_batch(items.len(), batches)
+    }
+
+    /// Passes `deadline` on to every verifier.
+    fn verify_batch_until(
+        &self,
+        items: &[(Aid, EvidenceRef)],
+        deadline: Instant,
+    ) -> Result<Vec<MlVerdict>, MlError> {
+        let batches = thread::scope(|s| {
+            let handles: Vec<_> = self
+                .verifiers
+                .iter()
+                .map(|v| s.spawn(move || v.verify_batch_until(items, deadline)))
+                .collect();
+            handles
+                .into_iter()
+                .map(|h| h.join().expect("ML verifier thread panicked"))
+                .collect()
+        });
+        self.aggregate_batch(items.len(), batches)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{EvidenceHash, HASH_LEN, Hash256, WmProfile};
+
+    /// Answers every call with a fixed verdict, or fails if `ok` is `None`.
+    struct Fixed {
+        ok: Option<bool>,
+        score: f32,
+    }
+
+    impl MlVerifier for Fixed {
+        fn verify(&self, _aid: &Aid, _evidence: &EvidenceRef) -> Result<MlVerdict, MlError> {
+            match self.ok {
+                Some(ok) => Ok(MlVerdict {
+                    ok,
+                    score: Some(self.score),
+                    trigger_acc: None,
+                    feat_dist: None,
+                    logit_stat: None,
+                    latency_ms: Some((self.score * 100.0) as u64),
+                    quorum: None,
+                }),
+                None => Err(MlError::Transport("connection refused".to_string())),
+            }
+        }
+    }
+
+    impl AsyncMlVerifier for Fixed {
+        async fn verify(&self, aid: &Aid, evidence: &EvidenceRef) -> Result<MlVerdict, MlError> {
+            MlVerifier::verify(self, aid, evidence)
+        }
+    }
+
+    fn accept(score: f32) -> Fixed {
+        Fixed {
+            ok: Some(true),
+            score,
+        }
+    }
+
+    fn reject(score: f32) -> Fixed {
+        Fixed {
+            ok: Some(false),
+            score,
+        }
+    }
+
+    fn down() -> Fixed {
+        Fixed {
+            ok: None,
+            score: 0.0,
+        }
+    }
+
+    fn item() -> (Aid, EvidenceRef) {
+        let evidence = EvidenceRef {
+            scheme_id: "wm-test-1".to_string(),
+            evidence_hash: EvidenceHash(Hash256([2u8; HASH_LEN])),
+            wm_profile: WmProfile {
+                tau_input: 0.9,
+                tau_feat: 0.1,
+                logit_band_low: 0.02,
+                logit_band_high: 0.05,
+            },
+        };
+        (Aid(Hash256([1u8; HASH_LEN])), evidence)
+    }
+
+    #[test]
+    fn policy_required_counts() {
+        assert_eq!(QuorumPolicy::Majority.required(3), 2);
+        assert_eq!(QuorumPolicy::Majority.required(4), 3);
+        assert_eq!(QuorumPolicy::Unanimous.required(3), 3);
+        assert_eq!(QuorumPolicy::AtLeast(5).required(3), 3);
+        assert_eq!(QuorumPolicy::AtLeast(0).required(3), 1);
+    }
+
+    #[test]
+    fn majority_accepts_and_reports_the_split() {
+        let q = QuorumMlVerifier::new(
+            vec![accept(0.9), reject(0.2), accept(0.8)],
+            QuorumPolicy::Majority,
+        );
+        let (aid, evidence) = item();
+        let verdict = MlVerifier::verify(&q, &aid, &evidence).unwrap();
+
+        assert!(verdict.ok);
+        assert_eq!(verdict.score, Some(0.8));
+        assert_eq!(verdict.latency_ms, Some(90));
+        let votes = verdict.quorum.unwrap();
+        assert_eq!((votes.accepted, votes.rejected, votes.failed), (2, 1, 0));
+        assert!(votes.is_split());
+    }
+
+    #[test]
+    fn failures_are_errors_only_when_they_leave_the_vote_open() {
+        let (aid, evidence) = item();
+
+        // Two rejections out of three: acceptance is impossible.
+        let q = QuorumMlVerifier::new(
+            vec![reject(0.1), down(), reject(0.2)],
+            QuorumPolicy::Majority,
+        );
+        let verdict = MlVerifier::verify(&q, &aid, &evidence).unwrap();
+        assert!(!verdict.ok);
+        assert_eq!(verdict.quorum.unwrap().failed, 1);
+
+        // One vote each way plus an outage: undecided.
+        let q = QuorumMlVerifier::new(
+            vec![accept(0.9), down(), reject(0.2)],
+            QuorumPolicy::Majority,
+        );
+        assert!(matches!(
+            MlVerifier::verify(&q, &aid, &evidence),
+            Err(MlError::Transport(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn async_batch_aggregates_per_item() {
+        let q = QuorumMlVerifier::new(
+            vec![accept(1.0), accept(0.5), down()],
+            QuorumPolicy::AtLeast(2),
+        );
+        let items = vec![item(), item()];
+        let verdicts = AsyncMlVerifier::verify_batch(&q, &items).await.unwrap();
+
+        assert_eq!(verdicts.len(), 2);
+        assert!(verdicts.iter().all(|v| v.ok));
+        assert_eq!(verdicts[0].score, Some(0.75));
+        assert_eq!(verdicts[1].quorum.as_ref().unwrap().failed, 1);
+    }
+}
@@ -0,0 +1,306 @@
+//! Per-peer framing and compression of network messages.
+//!
+//! Blocks carrying many registrations are dominated by watermark evidence
+//! and compress well, so large messages may be sent zstd-compressed. Each
+//! peer announces the compressions it accepts in a [`PeerHello`] when a
+//! connection opens; [`PeerCodec::negotiate`] picks zstd only if both
+//! sides accept it, so nodes without compression keep interoperating.
+//!
+//! Every encoded message is a *frame*: one tag byte ([`Compression`])
+//! followed by the payload, raw or compressed. A sender only compresses
+//! payloads of at least `compression_min_bytes` and only keeps the
+//! compressed form if it is actually smaller. Uncompressed frames are
+//! always accepted; zstd frames only from peers that negotiated it.
+//!
+//! Gossip de-duplication must run on the decoded payload, since the same
+//! message reaches a node in a different frame from each peer:
+//!
+//! ```ignore
+//! let (payload, stats) = codec.decode(&frame)?;
+//! metrics.consensus.observe_gossip_frame(Direction::Inbound, &stats);
+//! handler.on_message(topic, &payload)?;
+//! ```
+
+use serde::{Deserialize, Serialize};
+
+use super::message::NetworkError;
+use super::p2p::P2pConfig;
+use super::sync::SyncConfig;
+
+/// Configuration for peer-to-peer messaging and its transport.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct NetworkConfig {
+    /// Whether to offer zstd compression to peers.
+    pub compression: bool,
+    /// Smallest payload, in bytes, worth compressing.
+    pub compression_min_bytes: usize,
+    /// zstd compression level.
+    pub compression_level: i32,
+    /// Largest payload, in bytes, accepted after decompression.
+    pub max_message_bytes: usize,
+    /// Block sync batching and parallelism.
+    pub sync: SyncConfig,
+    /// libp2p transport: listen and bootstrap addresses, timeouts.
+    pub p2p: P2pConfig,
+}
+
+impl Default for NetworkConfig {
+    fn default() -> Self {
+        Self {
+            compression: true,
+            compression_min_bytes: 1024,
+            compression_level: 3,
+            max_message_bytes: 16 * 1024 * 1024,
+            sync: SyncConfig::default(),
+            p2p: P2pConfig::default(),
+        }
+    }
+}
+
+impl NetworkConfig {
+    /// Returns the capabilities this node announces to its peers.
+    pub fn hello(&self) -> PeerHello {
+        let mut compression = vec![Compression::None];
+        if self.compression {
+            compression.push(Compression::Zstd);
+        }
+        PeerHello { compression }
+    }
+}
+
+/// Encoding of a frame's payload; the discriminant is the frame tag.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub enum Compression {
+    /// Raw payload.
+    None = 0,
+    /// zstd-compressed payload.
+    Zstd = 1,
+}
+
+impl Compression {
+    fn from_tag(tag: u8) -> Option<Self> {
+        match tag {
+            0 => Some(Compression::None),
+            1 => Some(Compression::Zstd),
+            _ => None,
+        }
+    }
+}
+
+/// Capabilities a node announces when a peer connection opens.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct PeerHello {
+    /// Frame encodings the node accepts.
+    pub compression: Vec<Compression>,
+}
+
+impl PeerHello {
+    /// Encodes the hello with the canonical bincode configuration.
+    pub fn encode(&self) -> Vec<u8> {
+        bincode::serde::encode_to_vec(self, bincode::config::standard())
+            .expect("PeerHello should always be serializable with bincode 2 + serde")
+    }
+
+    /// Decodes a peer's hello.
+    pub fn decode(bytes: &[u8]) -> Result<Self, NetworkError> {
+        let (hello, _) = bincode::serde::decode_from_slice(bytes, bincode::config::standard())
+            .map_err(|e| NetworkError::Decode(e.to_string()))?;
+        Ok(hello)
+    }
+}
+
+/// Direction of a frame, as labelled in metrics.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Direction {
+    Inbound,
+    Outbound,
+}
+
+impl Direction {
+    /// Returns the metrics label value.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Direction::Inbound => "inbound",
+            Direction::Outbound => "outbound",
+        }
+    }
+}
+
+/// Sizes of one encoded or decoded frame.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct FrameStats {
+    /// Encoding the payload was sent with.
+    pub compression: Compression,
+    /// Size of the raw payload, in bytes.
+    pub payload_bytes: usize,
+    /// Size of the frame on the wire, in bytes.
+    pub wire_bytes: usize,
+}
+
+impl FrameStats {
+    /// Returns `wire_bytes / payload_bytes`; below 1 when compression
+    /// saved bandwidth.
+    pub fn ratio(&self) -> f64 {
+        if self.payload_bytes == 0 {
+            return 1.0;
+        }
+        self.wire_bytes as f64 / self.payload_bytes as f64
+    }
+}
+
+/// Frame encoder/decoder for one peer connection.
+#[derive(Clone, Debug)]
+pub struct PeerCodec {
+    compression: Compression,
+    min_bytes: usize,
+    level: i32,
+    max_message_bytes: usize,
+}
+
+impl PeerCodec {
+    /// Creates the codec for a peer that announced `remote`.
+    pub fn negotiate(cfg: &NetworkConfig, remote: &PeerHello) -> Self {
+        let compression = if cfg.compression && remote.compression.contains(&Compression::Zstd) {
+            Compression::Zstd
+        } else {
+            Compression::None
+        };
+        Self {
+            compression,
+            min_bytes: cfg.compression_min_bytes,
+            level: cfg.compression_level,
+            max_message_bytes: cfg.max_message_bytes,
+        }
+    }
+
+    /// Returns the compression negotiated with the peer.
+    pub fn compression(&self) -> Compression {
+        self.compression
+    }
+
+    /// Frames `payload` (e.g. [`GossipMessage::encode`]) for this peer.
+    ///
+    /// [`GossipMessage::encode`]: super::GossipMessage::encode
+    pub fn encode(&self, payload: &[u8]) -> (Vec<u8>, FrameStats) {
+        let compressed = (self.compression == Compression::Zstd && payload.len() >= self.min_bytes)
+            .then(|| zstd::bulk::compress(payload, self.level).ok())
+            .flatten()
+            .filter(|compressed| compressed.len() < payload.len());
+
+        let (compression, body) = match &compressed {
+            Some(compressed) => (Compression::Zstd, compressed.as_slice()),
+            None => (Compression::None, payload),
+        };
+        let mut frame = Vec::with_capacity(1 + body.len());
+        frame.push(compression as u8);
+        frame.extend_from_slice(body);
+        let stats = FrameStats {
+            compression,
+            payload_bytes: payload.len(),
+            wire_bytes: frame.len(),
+        };
+        (frame, stats)
+    }
+
+    /// Unwraps a frame received from this peer into its payload.
+    pub fn decode(&self, frame: &[u8]) -> Result<(Vec<u8>, FrameStats), NetworkError> {
+        let (&tag, body) = frame
+            .split_first()
+            .ok_or_else(|| NetworkError::Decode("empty frame".to_string()))?;
+        let compression = Compression::from_tag(tag)
+            .ok_or_else(|| NetworkError::Decode(format!("unknown frame tag {tag}")))?;
+
+        let payload = match compression {
+            Compression::None => body.to_vec(),
+            Compression::Zstd if self.compression != Compression::Zstd => {
+                return Err(NetworkError::Decode(
+                    "zstd frame from a peer that did not negotiate compression".to_string(),
+                ));
+            }
+            Compression::Zstd => zstd::bulk::decompress(body, self.max_message_bytes)
+                .map_err(|e| NetworkError::Decode(format!("zstd: {e}")))?,
+        };
+        if payload.len() > self.max_message_bytes {
+            return Err(NetworkError::Decode(format!(
+                "payload of {} bytes exceeds {}",
+                payload.len(),
+                self.max_message_bytes
+            )));
+        }
+        let stats = FrameStats {
+            compression,
+            payload_bytes: payload.len(),
+            wire_bytes: frame.len(),
+        };
+        Ok((payload, stats))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn codecs(local: &NetworkConfig, remote: &NetworkConfig) -> (PeerCodec, PeerCodec) {
+        (
+            PeerCodec::negotiate(local, &remote.hello()),
+            PeerCodec::negotiate(remote, &local.hello()),
+        )
+    }
+
+    #[test]
+    fn large_payloads_are_compressed_when_both_peers_agree() {
+        let cfg = NetworkConfig::default();
+        let (sender, receiver) = codecs(&cfg, &cfg);
+        let payload = b"wm-evidence ".repeat(1_000);
+
+        let (frame, sent) = sender.encode(&payload);
+        assert_eq!(sent.compression, Compression::Zstd);
+        assert!(sent.ratio() < 0.5, "ratio {}", sent.ratio());
+
+        let (decoded, received) = receiver.decode(&frame).expect("decodes");
+        assert_eq!(decoded, payload);
+        assert_eq!(received, sent);
+
+        let (small, stats) = sender.encode(b"tiny");
+        assert_eq!(stats.compression, Compression::None);
+        assert_eq!(receiver.decode(&small).unwrap().0, b"tiny");
+    }
+
+    #[test]
+    fn peers_without_compression_exchange_raw_frames() {
+        let plain = NetworkConfig {
+            compression: false,
+            ..NetworkConfig::default()
+        };
+        let (zstd_side, plain_side) = codecs(&NetworkConfig::default(), &plain);
+        assert_eq!(zstd_side.compression(), Compression::None);
+        assert_eq!(plain_side.compression(), Compression::None);
+
+        let payload = b"wm-evidence ".repeat(1_000);
+        let (frame, stats) = zstd_side.encode(&payload);
+        assert_eq!(stats.compression, Compression::None);
+        assert_eq!(plain_side.decode(&frame).unwrap().0, payload);
+
+        let (compressed, _) =
+            PeerCodec::negotiate(&NetworkConfig::default(), &NetworkConfig::default().hello())
+                .encode(&payload);
+        assert!(plain_side.decode(&compressed).is_err());
+    }
+
+    #[test]
+    fn decompression_is_bounded() {
+        let cfg = NetworkConfig::default();
+        let sender = PeerCodec::negotiate(&cfg, &cfg.hello());
+        let small = NetworkConfig {
+            max_message_bytes: 4_096,
+            ..cfg.clone()
+        };
+        let receiver = PeerCodec::negotiate(&small, &cfg.hello());
+
+        let (frame, _) = sender.encode(&[0u8; 64 * 1024]);
+        assert!(receiver.decode(&frame).is_err());
+        assert!(receiver.decode(&[]).is_err());
+        assert!(receiver.decode(&[9, 1, 2]).is_err());
+    }
+}
@@ -0,0 +1,262 @@
+//! Network message types and their wire encoding.
+//!
+//! All messages use the same bincode-2 `standard()` configuration as
+//! [`Block::canonical_bytes`].
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::consensus::store::BlockStore;
+use crate::consensus::votes::Vote;
+use crate::storage::StorageError;
+use crate::types::{Block, BlockHash, Header, Transaction};
+
+/// Most headers returned for one [`HeadersRequest`].
+pub const MAX_HEADERS_PER_REQUEST: u64 = 2_048;
+
+/// Gossip topics.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum Topic {
+    /// Newly proposed blocks.
+    Blocks,
+    /// Transactions waiting to be included in a block.
+    Transactions,
+    /// Validator votes for canonical blocks.
+    Votes,
+}
+
+impl Topic {
+    /// Returns the topic name used on the wire.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Topic::Blocks => "/mlsnitch/blocks/1",
+            Topic::Transactions => "/mlsnitch/txs/1",
+            Topic::Votes => "/mlsnitch/votes/1",
+        }
+    }
+}
+
+/// Errors raised while decoding or handling network messages.
+#[derive(Debug)]
+pub enum NetworkError {
+    /// Message bytes could not be decoded.
+    Decode(String),
+    /// Message was received on a topic that does not carry its type.
+    WrongTopic(Topic),
+    /// The transport could not be set up.
+    Transport(String),
+    /// A request to a peer failed or timed out.
+    Request(String),
+    /// The transport has stopped.
+    Closed,
+}
+
+impl fmt::Display for NetworkError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NetworkError::Decode(msg) => write!(f, "failed to decode message: {msg}"),
+            NetworkError::WrongTopic(topic) => {
+                write!(f, "unexpected message on topic {}", topic.as_str())
+            }
+            NetworkError::Transport(msg) => write!(f, "failed to start transport: {msg}"),
+            NetworkError::Request(msg) => write!(f, "request failed: {msg}"),
+            NetworkError::Closed => write!(f, "network stopped"),
+        }
+    }
+}
+
+impl std::error::Error for NetworkError {}
+
+/// Encodes a message with the canonical bincode configuration.
+pub(super) fn encode<T: Serialize>(msg: &T) -> Vec<u8> {
+    bincode::serde::encode_to_vec(msg, bincode::config::standard())
+        .expect("network messages should always be serializable with bincode 2 + serde")
+}
+
+/// Decodes a message with the canonical bincode configuration.
+pub(super) fn decode<T: for<'de> Deserialize<'de>>(bytes: &[u8]) -> Result<T, NetworkError> {
+    let (msg, _) = bincode::serde::decode_from_slice(bytes, bincode::config::standard())
+        .map_err(|e| NetworkError::Decode(e.to_string()))?;
+    Ok(msg)
+}
+
+/// Payload published on a gossip topic.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum GossipMessage {
+    /// A block, published on [`Topic::Blocks`].
+    Block(Block),
+    /// A transaction, published on [`Topic::Transactions`].
+    Tx(Transaction),
+    /// A validator vote, published on [`Topic::Votes`].
+    Vote(Vote),
+}
+
+impl GossipMessage {
+    /// Returns the topic this message is published on.
+    pub fn topic(&self) -> Topic {
+        match self {
+            GossipMessage::Block(_) => Topic::Blocks,
+            GossipMessage::Tx(_) => Topic::Transactions,
+            GossipMessage::Vote(_) => Topic::Votes,
+        }
+    }
+
+    /// Encodes the message for publishing.
+    pub fn encode(&self) -> Vec<u8> {
+        encode(self)
+    }
+
+    /// Decodes a message received on `topic`.
+    pub fn decode(topic: Topic, bytes: &[u8]) -> Result<Self, NetworkError> {
+        let msg: Self = decode(bytes)?;
+        if msg.topic() != topic {
+            return Err(NetworkError::WrongTopic(topic));
+        }
+        Ok(msg)
+    }
+}
+
+/// Request for a single block by hash.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BlockRequest {
+    /// Hash of the requested block.
+    pub hash: BlockHash,
+}
+
+impl BlockRequest {
+    /// Encodes the request.
+    pub fn encode(&self) -> Vec<u8> {
+        encode(self)
+    }
+
+    /// Decodes a request.
+    pub fn decode(bytes: &[u8]) -> Result<Self, NetworkError> {
+        decode(bytes)
+    }
+
+    /// Answers this request from `store`.
+    pub fn respond<S>(&self, store: &S) -> Result<BlockResponse, StorageError>
+    where
+        S: BlockStore + ?Sized,
+    {
+        Ok(BlockResponse {
+            block: store.get_block(&self.hash)?,
+        })
+    }
+}
+
+/// Response to a [`BlockRequest`]; `None` if the peer does not have it.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BlockResponse {
+    /// The requested block, if known.
+    pub block: Option<Block>,
+}
+
+impl BlockResponse {
+    /// Encodes the response.
+    pub fn encode(&self) -> Vec<u8> {
+        encode(self)
+    }
+
+    /// Decodes a response.
+    pub fn decode(bytes: &[u8]) -> Result<Self, NetworkError> {
+        decode(bytes)
+    }
+
+    /// Returns the block only if it hashes to `expected` and its
+    /// transactions match the header's `txs_root`.
+    ///
+    /// Peers are untrusted; a response carrying a different block (or the
+    /// right header with other transactions) is treated like a missing one.
+    pub fn into_verified(self, expected: &BlockHash) -> Option<Block> {
+        self.block
+            .filter(|b| b.compute_hash() == *expected && b.has_valid_txs_root())
+    }
+}
+
+/// Request for canonical headers by height range.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct HeadersRequest {
+    /// Height of the first requested header.
+    pub start_height: u64,
+    /// Number of headers requested; capped at [`MAX_HEADERS_PER_REQUEST`].
+    pub count: u64,
+}
+
+impl HeadersRequest {
+    /// Encodes the request.
+    pub fn encode(&self) -> Vec<u8> {
+        encode(self)
+    }
+
+    /// Decodes a request.
+    pub fn decode(bytes: &[u8]) -> Result<Self, NetworkError> {
+        decode(bytes)
+    }
+
+    /// Answers this request from the canonical chain in `store`.
+    ///
+    /// The response stops early at the tip (or at a pruned height), so it
+    /// may hold fewer headers than requested.
+    pub fn respond<S>(&self, store: &S) -> Result<HeadersResponse, StorageError>
+    where
+        S: BlockStore + ?Sized,
+    {
+        let tip_height = match store.tip()? {
+            Some(tip) => store.get_block(&tip)?.map_or(0, |b| b.header.height),
+            None => 0,
+        };
+        let count = self.count.min(MAX_HEADERS_PER_REQUEST);
+        let mut headers = Vec::new();
+        for height in self.start_height..self.start_height.saturating_add(count) {
+            let Some(hash) = store.get_hash_at_height(height)? else {
+                break;
+            };
+            let Some(block) = store.get_block(&hash)? else {
+                break;
+            };
+            headers.push(SyncHeader {
+                hash,
+                header: block.header,
+            });
+        }
+        Ok(HeadersResponse {
+            tip_height,
+            headers,
+        })
+    }
+}
+
+/// A canonical header together with the hash of its block.
+///
+/// Block hashes cover the body, so a header alone does not determine its
+/// hash; the claimed hash is checked once the body arrives.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SyncHeader {
+    /// Claimed hash of the block.
+    pub hash: BlockHash,
+    /// The block's header.
+    pub header: Header,
+}
+
+/// Response to a [`HeadersRequest`].
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct HeadersResponse {
+    /// Height of the responder's tip.
+    pub tip_height: u64,
+    /// Consecutive canonical headers from the requested height on.
+    pub headers: Vec<SyncHeader>,
+}
+
+impl HeadersResponse {
+    /// Encodes the response.
+    pub fn encode(&self) -> Vec<u8> {
+        encode(self)
+    }
+
+    /// Decodes a response.
+    pub fn decode(bytes: &[u8]) -> Result<Self, NetworkError> {
+        decode(bytes)
+    }
+}
@@ -0,0 +1,37 @@
+//! Peer-to-peer networking.
+//!
+//! This module defines the wire protocol a multi-node deployment speaks:
+//!
+//! - gossip topics for blocks and transactions ([`Topic`]),
+//! - the gossip payload encoding ([`GossipMessage`]),
+//! - per-peer message framing with negotiated zstd compression
+//!   ([`PeerCodec`]),
+//! - request/response messages for fetching a block by hash
+//!   ([`BlockRequest`], [`BlockResponse`]) and canonical headers by height
+//!   range ([`HeadersRequest`], [`HeadersResponse`]),
+//! - a [`GossipHandler`] that de-duplicates inbound gossip and feeds
+//!   blocks into [`ConsensusEngine::import_block`], and
+//! - [`BlockSync`], which catches a lagging node up with a peer over those
+//!   requests and reports its progress as a [`SyncStatus`].
+//!
+//! [`P2pNetwork`] carries it between nodes: a libp2p swarm with gossipsub
+//! for the topics and a request-response protocol for block fetch. Other
+//! transports only have to move the encoded bytes and implement
+//! [`SyncPeer`].
+//!
+//! [`ConsensusEngine::import_block`]: crate::consensus::ConsensusEngine::import_block
+
+pub mod codec;
+pub mod gossip;
+pub mod message;
+pub mod p2p;
+pub mod sync;
+
+pub use codec::{Compression, Direction, FrameStats, NetworkConfig, PeerCodec, PeerHello};
+pub use gossip::{GossipHandler, InboundGossip};
+pub use message::{
+    BlockRequest, BlockResponse, GossipMessage, HeadersRequest, HeadersResponse,
+    MAX_HEADERS_PER_REQUEST, NetworkError, SyncHeader, Topic,
+};
+pub use p2p::{NetworkEvent, P2pConfig, P2pHandle, P2pNetwork, PeerId, RemotePeer};
+pub use sync::{BlockSync, SyncConfig, SyncError, SyncHandle, SyncPeer, SyncStatus};
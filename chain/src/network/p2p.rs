@@ -0,0 +1,810 @@
+//! libp2p transport for the network protocol.
+//!
+//! [`P2pNetwork`] runs a libp2p swarm (TCP or DNS, Noise, Yamux) carrying the
+//! messages of this module between nodes:
+//!
+//! - gossipsub on the [`Topic`]s, for blocks, transactions and votes, and
+//! - a request/response protocol answering [`BlockRequest`]s and
+//!   [`HeadersRequest`]s from the local store, so [`BlockSync`] can catch
+//!   up from any connected peer through a [`RemotePeer`].
+//!
+//! The swarm runs in its own task ([`P2pNetwork::run`]). A cloneable
+//! [`P2pHandle`] publishes gossip and sends requests into it; new
+//! connections and inbound gossip come out as [`NetworkEvent`]s on the
+//! channel given to [`P2pNetwork::new`], for the node to import into its
+//! engine and mempool.
+//!
+//! Gossipsub forwards the same bytes to every subscriber, so gossip frames
+//! are never compressed. Block fetch negotiates compression per peer
+//! instead: nodes with `network.compression` offer the
+//! `/mlsnitch/sync/1/zstd` protocol before `/mlsnitch/sync/1`, and frames
+//! are zstd-compressed only when both sides agree on the former.
+//!
+//! The node identity is generated at start-up, so peers are dialled by
+//! address (`network.p2p.bootstrap_peers`), without a `/p2p/<id>` suffix.
+//!
+//! [`BlockSync`]: super::BlockSync
+
+use std::collections::HashMap;
+use std::io;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use futures_util::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, StreamExt};
+use libp2p::gossipsub::{self, IdentTopic, MessageAuthenticity, MessageId, PublishError};
+use libp2p::request_response::{self, OutboundRequestId, ProtocolSupport};
+use libp2p::swarm::{NetworkBehaviour, SwarmEvent};
+use libp2p::{Multiaddr, StreamProtocol, Swarm, SwarmBuilder, noise, tcp, yamux};
+use serde::{Deserialize, Serialize};
+use tokio::sync::{mpsc, oneshot};
+
+use crate::consensus::store::BlockStore;
+use crate::metrics::ConsensusMetrics;
+use crate::shutdown::Shutdown;
+use crate::types::Hash256;
+
+use super::codec::{Compression, Direction, NetworkConfig, PeerCodec, PeerHello};
+use super::gossip::{GossipHandler, InboundGossip};
+use super::message::{
+    self, BlockRequest, BlockResponse, GossipMessage, HeadersRequest, HeadersResponse,
+    NetworkError, Topic,
+};
+use super::sync::SyncPeer;
+
+pub use libp2p::PeerId;
+
+/// Block fetch protocol with zstd-compressed frames.
+const SYNC_PROTOCOL_ZSTD: StreamProtocol = StreamProtocol::new("/mlsnitch/sync/1/zstd");
+
+/// Block fetch protocol with raw frames.
+const SYNC_PROTOCOL: StreamProtocol = StreamProtocol::new("/mlsnitch/sync/1");
+
+/// Commands queued by [`P2pHandle`]s before the swarm task picks them up.
+const COMMAND_BUFFER: usize = 256;
+
+/// Configuration of the libp2p transport.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct P2pConfig {
+    /// Whether the node runs the transport at all.
+    pub enabled: bool,
+    /// Multiaddrs to listen on, e.g. `/ip4/0.0.0.0/tcp/7600`.
+    pub listen_addrs: Vec<String>,
+    /// Multiaddrs of peers dialled at start-up.
+    pub bootstrap_peers: Vec<String>,
+    /// Seconds to wait for a peer to answer a request.
+    pub request_timeout_secs: u64,
+    /// Events queued for the node; gossip arriving while the queue is full
+    /// is dropped.
+    pub event_buffer: usize,
+}
+
+impl Default for P2pConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            listen_addrs: vec!["/ip4/0.0.0.0/tcp/7600".to_string()],
+            bootstrap_peers: Vec::new(),
+            request_timeout_secs: 10,
+            event_buffer: 1024,
+        }
+    }
+}
+
+/// Something the transport reports to the node.
+#[derive(Debug)]
+pub enum NetworkEvent {
+    /// A first connection to `peer` opened; the node may sync from it.
+    PeerConnected(PeerId),
+    /// New gossip, forwarded by `source`. Repeats are filtered out.
+    Gossip {
+        source: PeerId,
+        message: InboundGossip,
+    },
+}
+
+/// Request sent over the block fetch protocol.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+enum SyncRequest {
+    Block(BlockRequest),
+    Headers(HeadersRequest),
+}
+
+/// Response sent over the block fetch protocol.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+enum SyncResponse {
+    Block(BlockResponse),
+    Headers(HeadersResponse),
+}
+
+/// Work handed from a [`P2pHandle`] to the swarm task.
+enum Command {
+    Publish(GossipMessage),
+    Request {
+        peer: PeerId,
+        request: SyncRequest,
+        reply: oneshot::Sender<Result<SyncResponse, NetworkError>>,
+    },
+}
+
+/// Frames block fetch messages, compressed on the zstd protocol.
+#[derive(Clone)]
+struct SyncCodec {
+    raw: PeerCodec,
+    zstd: PeerCodec,
+    max_frame_bytes: u64,
+    metrics: Option<ConsensusMetrics>,
+}
+
+impl SyncCodec {
+    fn new(cfg: &NetworkConfig) -> Self {
+        let raw_hello = PeerHello {
+            compression: vec![Compression::None],
+        };
+        Self {
+            raw: PeerCodec::negotiate(cfg, &raw_hello),
+            zstd: PeerCodec::negotiate(cfg, &cfg.hello()),
+            // One tag byte in front of at most `max_message_bytes`.
+            max_frame_bytes: cfg.max_message_bytes as u64 + 1,
+            metrics: None,
+        }
+    }
+
+    fn frames(&self, protocol: &StreamProtocol) -> &PeerCodec {
+        if *protocol == SYNC_PROTOCOL_ZSTD {
+            &self.zstd
+        } else {
+            &self.raw
+        }
+    }
+
+    async fn read<T, M>(&self, protocol: &StreamProtocol, io: &mut T) -> io::Result<M>
+    where
+        T: AsyncRead + Unpin + Send,
+        M: for<'de> Deserialize<'de>,
+    {
+        // One message per stream: the sender closes it after the frame.
+        let mut frame = Vec::new();
+        io.take(self.max_frame_bytes)
+            .read_to_end(&mut frame)
+            .await?;
+        let (payload, stats) = self.frames(protocol).decode(&frame).map_err(invalid_data)?;
+        if let Some(metrics) = &self.metrics {
+            metrics.observe_gossip_frame(Direction::Inbound, &stats);
+        }
+        message::decode(&payload).map_err(invalid_data)
+    }
+
+    async fn write<T, M>(&self, protocol: &StreamProtocol, io: &mut T, msg: &M) -> io::Result<()>
+    where
+        T: AsyncWrite + Unpin + Send,
+        M: Serialize,
+    {
+        let (frame, stats) = self.frames(protocol).encode(&message::encode(msg));
+        if let Some(metrics) = &self.metrics {
+            metrics.observe_gossip_frame(Direction::Outbound, &stats);
+        }
+        io.write_all(&frame).await?;
+        io.close().await
+    }
+}
+
+fn transport_error(e: impl std::fmt::Display) -> NetworkError {
+    NetworkError::Transport(e.to_string())
+}
+
+fn invalid_data(e: NetworkError) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, e.to_string())
+}
+
+#[async_trait]
+impl request_response::Codec for SyncCodec {
+    type Protocol = StreamProtocol;
+    type Request = SyncRequest;
+    type Response = SyncResponse;
+
+    async fn read_request<T>(
+        &mut self,
+        protocol: &StreamProtocol,
+        io: &mut T,
+    ) -> io::Result<SyncRequest>
+    where
+        T: AsyncRead + Unpin + Send,
+    {
+        self.read(protocol, io).await
+    }
+
+    async fn read_response<T>(
+        &mut self,
+        protocol: &StreamProtocol,
+        io: &mut T,
+    ) -> io::Result<SyncResponse>
+    where
+        T: AsyncRead + Unpin + Send,
+    {
+        self.read(protocol, io).await
+    }
+
+    async fn write_request<T>(
+        &mut self,
+        protocol: &StreamProtocol,
+        io: &mut T,
+        req: SyncRequest,
+    ) -> io::Result<()>
+    where
+        T: AsyncWrite + Unpin + Send,
+    {
+        self.write(protocol, io, &req).await
+    }
+
+    async fn write_response<T>(
+        &mut self,
+        protocol: &StreamProtocol,
+        io: &mut T,
+        res: SyncResponse,
+    ) -> io::Result<()>
+    where
+        T: AsyncWrite + Unpin + Send,
+    {
+        self.write(protocol, io, &res).await
+    }
+}
+
+#[derive(NetworkBehaviour)]
+struct Behaviour {
+    gossipsub: gossipsub::Behaviour,
+    sync: request_response::Behaviour<SyncCodec>,
+}
+
+/// Returns the gossipsub topic carrying `topic`.
+fn gossip_topic(topic: Topic) -> IdentTopic {
+    IdentTopic::new(topic.as_str())
+}
+
+/// Cloneable handle publishing gossip and sending requests through a
+/// running [`P2pNetwork`].
+#[derive(Clone, Debug)]
+pub struct P2pHandle {
+    local_peer_id: PeerId,
+    commands: mpsc::Sender<Command>,
+}
+
+impl P2pHandle {
+    /// Returns this node's peer id.
+    pub fn local_peer_id(&self) -> PeerId {
+        self.local_peer_id
+    }
+
+    /// Publishes `msg` on its topic.
+    ///
+    /// Fails only once the network has stopped; a message no peer is
+    /// subscribed for yet is dropped by the swarm and logged.
+    pub async fn publish(&self, msg: GossipMessage) -> Result<(), NetworkError> {
+        self.commands
+            .send(Command::Publish(msg))
+            .await
+            .map_err(|_| NetworkError::Closed)
+    }
+
+    /// Returns a [`SyncPeer`] sending requests to `peer`.
+    pub fn peer(&self, peer: PeerId) -> RemotePeer {
+        RemotePeer {
+            handle: self.clone(),
+            peer,
+        }
+    }
+
+    async fn request(
+        &self,
+        peer: PeerId,
+        request: SyncRequest,
+    ) -> Result<SyncResponse, NetworkError> {
+        let (reply, response) = oneshot::channel();
+        self.commands
+            .send(Command::Request {
+                peer,
+                request,
+                reply,
+            })
+            .await
+            .map_err(|_| NetworkError::Closed)?;
+        response.await.map_err(|_| NetworkError::Closed)?
+    }
+}
+
+/// A connected peer, reached through a [`P2pHandle`].
+#[derive(Clone, Debug)]
+pub struct RemotePeer {
+    handle: P2pHandle,
+    peer: PeerId,
+}
+
+impl RemotePeer {
+    /// Returns the peer's id.
+    pub fn id(&self) -> PeerId {
+        self.peer
+    }
+}
+
+impl SyncPeer for RemotePeer {
+    async fn headers(&self, request: HeadersRequest) -> Result<HeadersResponse, NetworkError> {
+        match self
+            .handle
+            .request(self.peer, SyncRequest::Headers(request))
+            .await?
+        {
+            SyncResponse::Headers(response) => Ok(response),
+            SyncResponse::Block(_) => Err(NetworkError::Request(
+                "peer answered a headers request with a block".to_string(),
+            )),
+        }
+    }
+
+    async fn block(&self, request: BlockRequest) -> Result<BlockResponse, NetworkError> {
+        match self
+            .handle
+            .request(self.peer, SyncRequest::Block(request))
+            .await?
+        {
+            SyncResponse::Block(response) => Ok(response),
+            SyncResponse::Headers(_) => Err(NetworkError::Request(
+                "peer answered a block request with headers".to_string(),
+            )),
+        }
+    }
+}
+
+/// libp2p swarm serving the network protocol from a block store.
+pub struct P2pNetwork<S> {
+    swarm: Swarm<Behaviour>,
+    store: S,
+    commands: mpsc::Receiver<Command>,
+    events: mpsc::Sender<NetworkEvent>,
+    pending: HashMap<OutboundRequestId, oneshot::Sender<Result<SyncResponse, NetworkError>>>,
+    handler: GossipHandler,
+    frames: PeerCodec,
+    metrics: Option<ConsensusMetrics>,
+}
+
+impl<S: BlockStore> P2pNetwork<S> {
+    /// Builds the swarm, starts listening on `cfg.p2p.listen_addrs` and
+    /// dials `cfg.p2p.bootstrap_peers`.
+    ///
+    /// Requests from peers are answered from `store`; new connections and
+    /// inbound gossip are sent to `events`. Nothing happens until
+    /// [`run`](Self::run) is polled.
+    pub fn new(
+        cfg: &NetworkConfig,
+        store: S,
+        events: mpsc::Sender<NetworkEvent>,
+    ) -> Result<(Self, P2pHandle), NetworkError> {
+        Self::build(cfg, store, events, None)
+    }
+
+    /// Like [`new`](Self::new), recording every frame sent or received in
+    /// the `network_gossip_*` metrics and the number of connected peers in
+    /// `network_p2p_peers`.
+    pub fn with_metrics(
+        cfg: &NetworkConfig,
+        store: S,
+        events: mpsc::Sender<NetworkEvent>,
+        metrics: &ConsensusMetrics,
+    ) -> Result<(Self, P2pHandle), NetworkError> {
+        Self::build(cfg, store, events, Some(metrics.clone()))
+    }
+
+    fn build(
+        cfg: &NetworkConfig,
+        store: S,
+        events: mpsc::Sender<NetworkEvent>,
+        metrics: Option<ConsensusMetrics>,
+    ) -> Result<(Self, P2pHandle), NetworkError> {
+        let mut codec = SyncCodec::new(cfg);
+        codec.metrics = metrics.clone();
+        let protocols = if cfg.compression {
+            vec![
+                (SYNC_PROTOCOL_ZSTD, ProtocolSupport::Full),
+                (SYNC_PROTOCOL, ProtocolSupport::Full),
+            ]
+        } else {
+            vec![(SYNC_PROTOCOL, ProtocolSupport::Full)]
+        };
+        let request_timeout = Duration::from_secs(cfg.p2p.request_timeout_secs.max(1));
+        let gossip_config = gossipsub::ConfigBuilder::default()
+            .validation_mode(gossipsub::ValidationMode::Strict)
+            // Content-addressed ids let gossipsub drop repeats itself.
+            .message_id_fn(|msg| MessageId::from(Hash256::compute(&msg.data).0.to_vec()))
+            .max_transmit_size(cfg.max_message_bytes + 1)
+            .build()
+            .map_err(transport_error)?;
+
+        let mut swarm = SwarmBuilder::with_new_identity()
+            .with_tokio()
+            .with_tcp(
+                tcp::Config::default(),
+                noise::Config::new,
+                yamux::Config::default,
+            )
+            .map_err(transport_error)?
+            .with_dns()
+            .map_err(transport_error)?
+            .with_behaviour(|key| {
+                let gossipsub = gossipsub::Behaviour::new(
+                    MessageAuthenticity::Signed(key.clone()),
+                    gossip_config,
+                )?;
+                let sync = request_response::Behaviour::with_codec(
+                    codec,
+                    protocols,
+                    request_response::Config::default().with_request_timeout(request_timeout),
+                );
+                Ok::<_, Box<dyn std::error::Error + Send + Sync>>(Behaviour { gossipsub, sync })
+            })
+            .map_err(transport_error)?
+            .with_swarm_config(|c| c.with_idle_connection_timeout(Duration::from_secs(60)))
+            .build();
+
+        for topic in [Topic::Blocks, Topic::Transactions, Topic::Votes] {
+            swarm
+                .behaviour_mut()
+                .gossipsub
+                .subscribe(&gossip_topic(topic))
+                .map_err(transport_error)?;
+        }
+        for addr in &cfg.p2p.listen_addrs {
+            let addr: Multiaddr = addr.parse().map_err(transport_error)?;
+            swarm.listen_on(addr).map_err(transport_error)?;
+        }
+        for addr in &cfg.p2p.bootstrap_peers {
+            let addr: Multiaddr = addr.parse().map_err(transport_error)?;
+            if let Err(e) = swarm.dial(addr.clone()) {
+                tracing::warn!(%addr, error = %e, "failed to dial bootstrap peer");
+            }
+        }
+
+        let (commands_tx, commands) = mpsc::channel(COMMAND_BUFFER);
+        let handle = P2pHandle {
+            local_peer_id: *swarm.local_peer_id(),
+            commands: commands_tx,
+        };
+        let raw_hello = PeerHello {
+            compression: vec![Compression::None],
+        };
+        let network = Self {
+            swarm,
+            store,
+            commands,
+            events,
+            pending: HashMap::new(),
+            handler: GossipHandler::default(),
+            frames: PeerCodec::negotiate(cfg, &raw_hello),
+            metrics,
+        };
+        Ok((network, handle))
+    }
+
+    /// Drives the swarm until `shutdown` is triggered or every
+    /// [`P2pHandle`] is dropped.
+    pub async fn run(mut self, shutdown: Shutdown) {
+        tracing::info!(peer_id = %self.swarm.local_peer_id(), "p2p network running");
+        loop {
+            tokio::select! {
+                _ = shutdown.wait() => break,
+                command = self.commands.recv() => match command {
+                    Some(command) => self.on_command(command),
+                    None => break,
+                },
+                event = self.swarm.select_next_some() => self.on_swarm_event(event),
+            }
+        }
+        tracing::info!("p2p network stopped");
+    }
+
+    fn on_command(&mut self, command: Command) {
+        match command {
+            Command::Publish(msg) => {
+                let topic = msg.topic();
+                let (frame, stats) = self.frames.encode(&msg.encode());
+                if let Some(metrics) = &self.metrics {
+                    metrics.observe_gossip_frame(Direction::Outbound, &stats);
+                }
+                match self
+                    .swarm
+                    .behaviour_mut()
+                    .gossipsub
+                    .publish(gossip_topic(topic), frame)
+                {
+                    Ok(_) => {}
+                    Err(PublishError::InsufficientPeers) => {
+                        tracing::debug!(topic = topic.as_str(), "no peers to gossip to");
+                    }
+                    Err(e) => {
+                        tracing::warn!(topic = topic.as_str(), error = %e, "failed to publish")
+                    }
+                }
+            }
+            Command::Request {
+                peer,
+                request,
+                reply,
+            } => {
+                let id = self.swarm.behaviour_mut().sync.send_request(&peer, request);
+                self.pending.insert(id, reply);
+            }
+        }
+    }
+
+    fn on_swarm_event(&mut self, event: SwarmEvent<BehaviourEvent>) {
+        match event {
+            SwarmEvent::Behaviour(BehaviourEvent::Gossipsub(gossipsub::Event::Message {
+                propagation_source,
+                message,
+                ..
+            })) => self.on_gossip(propagation_source, &message),
+            SwarmEvent::Behaviour(BehaviourEvent::Sync(event)) => self.on_sync_event(event),
+            SwarmEvent::NewListenAddr { address, .. } => {
+                tracing::info!(%address, "p2p listening");
+            }
+            SwarmEvent::ConnectionEstablished {
+                peer_id,
+                num_established,
+                ..
+            } => {
+                self.observe_peers();
+                if num_established.get() == 1 {
+                    tracing::info!(peer = %peer_id, "peer connected");
+                    self.emit(NetworkEvent::PeerConnected(peer_id));
+                }
+            }
+            SwarmEvent::ConnectionClosed {
+                peer_id,
+                num_established: 0,
+                ..
+            } => {
+                self.observe_peers();
+                tracing::info!(peer = %peer_id, "peer disconnected");
+            }
+            SwarmEvent::OutgoingConnectionError { peer_id, error, .. } => {
+                tracing::warn!(peer = ?peer_id, error = %error, "failed to connect to peer");
+            }
+            _ => {}
+        }
+    }
+
+    fn on_gossip(&mut self, source: PeerId, message: &gossipsub::Message) {
+        let Some(topic) = [Topic::Blocks, Topic::Transactions, Topic::Votes]
+            .into_iter()
+            .find(|topic| gossip_topic(*topic).hash() == message.topic)
+        else {
+            return;
+        };
+        let decoded = self
+            .frames
+            .decode(&message.data)
+            .and_then(|(payload, stats)| {
+                if let Some(metrics) = &self.metrics {
+                    metrics.observe_gossip_frame(Direction::Inbound, &stats);
+                }
+                self.handler.on_message(topic, &payload)
+            });
+        match decoded {
+            Ok(InboundGossip::Duplicate) => {}
+            Ok(message) => self.emit(NetworkEvent::Gossip { source, message }),
+            Err(e) => {
+                tracing::debug!(peer = %source, topic = topic.as_str(), error = %e, "dropping gossip");
+            }
+        }
+    }
+
+    fn on_sync_event(&mut self, event: request_response::Event<SyncRequest, SyncResponse>) {
+        match event {
+            request_response::Event::Message {
+                peer,
+                message:
+                    request_response::Message::Request {
+                        request, channel, ..
+                    },
+            } => {
+                let response = match &request {
+                    SyncRequest::Block(request) => {
+                        request.respond(&self.store).map(SyncResponse::Block)
+                    }
+                    SyncRequest::Headers(request) => {
+                        request.respond(&self.store).map(SyncResponse::Headers)
+                    }
+                };
+                match response {
+                    // The peer may have hung up meanwhile; nothing to do.
+                    Ok(response) => {
+                        let _ = self
+                            .swarm
+                            .behaviour_mut()
+                            .sync
+                            .send_response(channel, response);
+                    }
+                    // Dropping the channel fails the peer's request.
+                    Err(e) => tracing::warn!(%peer, error = %e, "failed to answer sync request"),
+                }
+            }
+            request_response::Event::Message {
+                message:
+                    request_response::Message::Response {
+                        request_id,
+                        response,
+                    },
+                ..
+            } => {
+                if let Some(reply) = self.pending.remove(&request_id) {
+                    let _ = reply.send(Ok(response));
+                }
+            }
+            request_response::Event::OutboundFailure {
+                request_id, error, ..
+            } => {
+                if let Some(reply) = self.pending.remove(&request_id) {
+                    let _ = reply.send(Err(NetworkError::Request(error.to_string())));
+                }
+            }
+            request_response::Event::InboundFailure { peer, error, .. } => {
+                tracing::debug!(%peer, error = %error, "inbound sync request failed");
+            }
+            request_response::Event::ResponseSent { .. } => {}
+        }
+    }
+
+    /// Hands `event` to the node, dropping it if the node is behind.
+    fn emit(&self, event: NetworkEvent) {
+        match self.events.try_send(event) {
+            Ok(()) | Err(mpsc::error::TrySendError::Closed(_)) => {}
+            Err(mpsc::error::TrySendError::Full(_)) => {
+                tracing::warn!("network event queue full; dropping event");
+            }
+        }
+    }
+
+    fn observe_peers(&self) {
+        if let Some(metrics) = &self.metrics {
+            metrics
+                .p2p_peers
+                .set(self.swarm.connected_peers().count() as i64);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::consensus::{
+        AcceptAllValidator, ConsensusConfig, ConsensusEngine, LongestChainForkChoice,
+    };
+    use crate::network::{BlockSync, SyncConfig};
+    use crate::storage::{InMemoryBlockStore, SharedStore};
+    use crate::types::{AccountId, Block, BlockHash, HASH_LEN, Header};
+
+    type TestEngine = ConsensusEngine<
+        SharedStore<InMemoryBlockStore>,
+        AcceptAllValidator,
+        LongestChainForkChoice,
+    >;
+
+    fn engine() -> TestEngine {
+        ConsensusEngine::new(
+            ConsensusConfig::default(),
+            SharedStore::new(InMemoryBlockStore::new()),
+            AcceptAllValidator,
+            LongestChainForkChoice,
+        )
+        .expect("empty store needs no replay")
+    }
+
+    fn next_block(engine: &TestEngine) -> Block {
+        let (parent, height) = match engine.tip_block().unwrap() {
+            Some(block) => (block.compute_hash(), block.header.height + 1),
+            None => (BlockHash(Hash256([0u8; HASH_LEN])), 0),
+        };
+        Block {
+            header: Header {
+                parent,
+                height,
+                timestamp: 1_700_000_000 + height,
+                proposer: AccountId(Hash256([1u8; HASH_LEN])),
+                txs_root: Hash256([0u8; HASH_LEN]),
+                state_root: Hash256([0u8; HASH_LEN]),
+                receipts_root: Hash256([0u8; HASH_LEN]),
+                pos_proof: None,
+            },
+            txs: Vec::new(),
+        }
+    }
+
+    /// Config listening on a free local port, dialling `bootstrap`.
+    fn local_config(compression: bool, bootstrap: Option<&str>) -> (NetworkConfig, String) {
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .and_then(|listener| listener.local_addr())
+            .expect("free port")
+            .port();
+        let addr = format!("/ip4/127.0.0.1/tcp/{port}");
+        let cfg = NetworkConfig {
+            compression,
+            p2p: P2pConfig {
+                enabled: true,
+                listen_addrs: vec![addr.clone()],
+                bootstrap_peers: bootstrap.map(str::to_string).into_iter().collect(),
+                ..P2pConfig::default()
+            },
+            ..NetworkConfig::default()
+        };
+        (cfg, addr)
+    }
+
+    #[tokio::test]
+    async fn nodes_sync_and_gossip_blocks_over_tcp() {
+        let shutdown = Shutdown::new();
+        let mut source = engine();
+        for _ in 0..5 {
+            let block = next_block(&source);
+            source.import_block(block).expect("block imports");
+        }
+
+        // Only the source offers compression; block fetch falls back to
+        // raw frames.
+        let (source_cfg, source_addr) = local_config(true, None);
+        let (events, _source_events) = mpsc::channel(16);
+        let (network, source_handle) =
+            P2pNetwork::new(&source_cfg, source.store().clone(), events).expect("starts");
+        tokio::spawn(network.run(shutdown.clone()));
+
+        let mut target = engine();
+        let (target_cfg, _) = local_config(false, Some(&source_addr));
+        let (events, mut target_events) = mpsc::channel(16);
+        let (network, target_handle) =
+            P2pNetwork::new(&target_cfg, target.store().clone(), events).expect("starts");
+        tokio::spawn(network.run(shutdown.clone()));
+
+        let wait = Duration::from_secs(10);
+        let peer = tokio::time::timeout(wait, async {
+            loop {
+                if let Some(NetworkEvent::PeerConnected(peer)) = target_events.recv().await {
+                    return peer;
+                }
+            }
+        })
+        .await
+        .expect("peers connect");
+        assert_eq!(peer, source_handle.local_peer_id());
+
+        let imported = BlockSync::new(SyncConfig::default())
+            .sync(&mut target, &target_handle.peer(peer))
+            .await
+            .expect("sync succeeds");
+        assert_eq!(imported, 5);
+        assert_eq!(target.tip().unwrap(), source.tip().unwrap());
+
+        // Subscriptions take a moment to propagate; publish until the
+        // block arrives.
+        let block = next_block(&source);
+        let (from, received) = tokio::time::timeout(wait, async {
+            loop {
+                source_handle
+                    .publish(GossipMessage::Block(block.clone()))
+                    .await
+                    .expect("network runs");
+                let event =
+                    tokio::time::timeout(Duration::from_millis(300), target_events.recv()).await;
+                if let Ok(Some(NetworkEvent::Gossip {
+                    source,
+                    message: InboundGossip::Block(block),
+                })) = event
+                {
+                    return (source, block);
+                }
+            }
+        })
+        .await
+        .expect("gossip arrives");
+        assert_eq!(from, peer);
+        assert_eq!(received.compute_hash(), block.compute_hash());
+
+        shutdown.trigger();
+    }
+}
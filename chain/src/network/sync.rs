@@ -0,0 +1,532 @@
+//! Block sync: catching up with a peer's canonical chain.
+//!
+//! Gossip only carries new blocks, so a node that was offline (or starts
+//! from an empty store) has to fetch the blocks it missed. [`BlockSync`]
+//! does this in rounds against one [`SyncPeer`]:
+//!
+//! 1. request up to [`SyncConfig::header_batch`] headers above the local
+//!    tip ([`HeadersRequest`]),
+//! 2. check that they form a chain extending the local tip,
+//! 3. download the bodies by hash ([`BlockRequest`]), up to
+//!    [`SyncConfig::parallel_bodies`] at a time, and
+//! 4. import them in height order through
+//!    [`ConsensusEngine::import_block_async`], so every block is fully
+//!    validated and executed as if it had been gossiped.
+//!
+//! Rounds repeat until the peer returns no more headers. Peers are
+//! untrusted: bodies that do not hash to their announced header are
+//! treated as missing, and any error ends the run. A peer on a different
+//! branch fails step 2; resolving forks below the local tip is left to
+//! gossip and fork choice.
+//!
+//! Progress is published in a shared [`SyncHandle`] (served by the API
+//! gateway as `GET /sync`) and, with
+//! [`BlockSync::with_metrics`], in the `network_sync_*` metrics. Both
+//! include the import queue: the announced blocks not imported yet, with
+//! the time they were announced, so a run stuck on a body that never
+//! arrives shows up.
+
+use std::fmt;
+use std::future::Future;
+use std::sync::{Arc, RwLock};
+use std::time::SystemTime;
+
+use futures_util::stream::{self, StreamExt};
+use serde::Deserialize;
+
+use crate::consensus::ConsensusEngine;
+use crate::consensus::error::ConsensusError;
+use crate::consensus::fork_choice::ForkChoice;
+use crate::consensus::orphans::PendingBlock;
+use crate::consensus::store::BlockStore;
+use crate::consensus::validator::AsyncBlockValidator;
+use crate::metrics::ConsensusMetrics;
+use crate::types::{Block, BlockHash};
+
+use super::message::{
+    BlockRequest, BlockResponse, HeadersRequest, HeadersResponse, NetworkError, SyncHeader,
+};
+
+/// Configuration for block sync.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SyncConfig {
+    /// Headers requested per round.
+    pub header_batch: u64,
+    /// Block bodies downloaded concurrently.
+    pub parallel_bodies: usize,
+}
+
+impl Default for SyncConfig {
+    fn default() -> Self {
+        Self {
+            header_batch: 512,
+            parallel_bodies: 16,
+        }
+    }
+}
+
+/// Request/response transport to one peer.
+///
+/// Implementations send the encoded request over the peer connection and
+/// decode the answer; the peer answers with [`HeadersRequest::respond`]
+/// and [`BlockRequest::respond`].
+pub trait SyncPeer {
+    /// Fetches canonical headers from the peer.
+    fn headers(
+        &self,
+        request: HeadersRequest,
+    ) -> impl Future<Output = Result<HeadersResponse, NetworkError>> + Send;
+
+    /// Fetches one block from the peer.
+    fn block(
+        &self,
+        request: BlockRequest,
+    ) -> impl Future<Output = Result<BlockResponse, NetworkError>> + Send;
+}
+
+/// Errors that end a sync run.
+#[derive(Debug)]
+pub enum SyncError {
+    /// The peer could not be reached or sent an undecodable message.
+    Network(NetworkError),
+    /// The peer's headers do not form a chain extending the local tip.
+    InvalidHeaders(String),
+    /// The peer did not deliver the body of an announced block.
+    MissingBlock(BlockHash),
+    /// A downloaded block failed to import.
+    Consensus(ConsensusError),
+}
+
+impl fmt::Display for SyncError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SyncError::Network(e) => write!(f, "sync network error: {e}"),
+            SyncError::InvalidHeaders(msg) => write!(f, "invalid sync headers: {msg}"),
+            SyncError::MissingBlock(hash) => write!(
+                f,
+                "peer did not deliver block {}",
+                hex::encode(hash.0.as_bytes())
+            ),
+            SyncError::Consensus(e) => write!(f, "failed to import synced block: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for SyncError {}
+
+impl From<NetworkError> for SyncError {
+    fn from(e: NetworkError) -> Self {
+        SyncError::Network(e)
+    }
+}
+
+impl From<ConsensusError> for SyncError {
+    fn from(e: ConsensusError) -> Self {
+        SyncError::Consensus(e)
+    }
+}
+
+/// Progress of the current (or last) sync run.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct SyncStatus {
+    /// Whether a run is in progress.
+    pub syncing: bool,
+    /// Local tip height when the run started.
+    pub start_height: u64,
+    /// Local tip height reached so far.
+    pub current_height: u64,
+    /// Tip height last reported by the peer.
+    pub target_height: u64,
+    /// Blocks imported by the run.
+    pub blocks_imported: u64,
+    /// Blocks announced by the peer and not imported yet, in height
+    /// order. Empty between runs.
+    pub queue: Vec<PendingBlock>,
+    /// Error that ended the last run, if any.
+    pub last_error: Option<String>,
+}
+
+impl SyncStatus {
+    /// Returns the number of blocks still to be imported.
+    pub fn remaining(&self) -> u64 {
+        self.target_height.saturating_sub(self.current_height)
+    }
+}
+
+/// Shared, readable view of a [`BlockSync`]'s progress.
+///
+/// Clones share the same status.
+#[derive(Clone, Debug, Default)]
+pub struct SyncHandle(Arc<RwLock<SyncStatus>>);
+
+impl SyncHandle {
+    /// Creates a handle reporting an idle node at height 0.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns a snapshot of the progress.
+    pub fn status(&self) -> SyncStatus {
+        self.0.read().expect("sync status lock poisoned").clone()
+    }
+
+    fn update(&self, f: impl FnOnce(&mut SyncStatus)) {
+        f(&mut self.0.write().expect("sync status lock poisoned"));
+    }
+}
+
+/// Downloads and imports missing blocks from a peer.
+pub struct BlockSync {
+    cfg: SyncConfig,
+    handle: SyncHandle,
+    metrics: Option<ConsensusMetrics>,
+}
+
+impl BlockSync {
+    /// Creates a sync service with its own [`SyncHandle`].
+    pub fn new(cfg: SyncConfig) -> Self {
+        Self {
+            cfg,
+            handle: SyncHandle::new(),
+            metrics: None,
+        }
+    }
+
+    /// Reports progress into `handle` instead of a private one.
+    pub fn with_handle(mut self, handle: SyncHandle) -> Self {
+        self.handle = handle;
+        self
+    }
+
+    /// Keeps the `network_sync_*` metrics current.
+    pub fn with_metrics(mut self, metrics: &ConsensusMetrics) -> Self {
+        self.metrics = Some(metrics.clone());
+        self
+    }
+
+    /// Returns the handle progress is reported into.
+    pub fn handle(&self) -> &SyncHandle {
+        &self.handle
+    }
+
+    /// Imports blocks from `peer` until the local tip reaches the peer's.
+    ///
+    /// Returns the number of blocks imported. Blocks imported before an
+    /// error stay imported.
+    pub async fn sync<S, V, F, P>(
+        &self,
+        engine: &mut ConsensusEngine<S, V, F>,
+        peer: &P,
+    ) -> Result<u64, SyncError>
+    where
+        S: BlockStore + Sync,
+        V: AsyncBlockValidator,
+        F: ForkChoice,
+        P: SyncPeer,
+    {
+        let (tip, height) = local_tip(engine)?;
+        self.handle.update(|status| {
+            *status = SyncStatus {
+                syncing: true,
+                start_height: height,
+                current_height: height,
+                target_height: height,
+                ..SyncStatus::default()
+            };
+        });
+
+        let result = self.run(engine, peer, tip, height).await;
+        self.handle.update(|status| {
+            status.syncing = false;
+            status.queue.clear();
+            status.last_error = result.as_ref().err().map(ToString::to_string);
+        });
+        if let Some(metrics) = &self.metrics {
+            metrics.observe_sync_queue(&[]);
+        }
+        result
+    }
+
+    async fn run<S, V, F, P>(
+        &self,
+        engine: &mut ConsensusEngine<S, V, F>,
+        peer: &P,
+        mut tip: Option<BlockHash>,
+        mut height: u64,
+    ) -> Result<u64, SyncError>
+    where
+        S: BlockStore + Sync,
+        V: AsyncBlockValidator,
+        F: ForkChoice,
+        P: SyncPeer,
+    {
+        let mut imported = 0;
+        loop {
+            let start_height = if tip.is_some() { height + 1 } else { 0 };
+            let response = peer
+                .headers(HeadersRequest {
+                    start_height,
+                    count: self.cfg.header_batch.max(1),
+                })
+                .await?;
+            self.handle
+                .update(|status| status.target_height = response.tip_height);
+            if let Some(metrics) = &self.metrics {
+                metrics.sync_target_height.set(response.tip_height as i64);
+            }
+            if response.headers.is_empty() {
+                return Ok(imported);
+            }
+            check_headers(&response.headers, tip, start_height)?;
+
+            let received = SystemTime::now();
+            let queue: Vec<PendingBlock> = response
+                .headers
+                .iter()
+                .map(|announced| PendingBlock {
+                    hash: announced.hash,
+                    height: announced.header.height,
+                    parent: announced.header.parent,
+                    received,
+                })
+                .collect();
+            if let Some(metrics) = &self.metrics {
+                metrics.observe_sync_queue(&queue);
+            }
+            self.handle.update(|status| status.queue = queue);
+
+            let mut bodies = stream::iter(response.headers)
+                .map(|announced| async move {
+                    let block = peer
+                        .block(BlockRequest {
+                            hash: announced.hash,
+                        })
+                        .await?
+                        .into_verified(&announced.hash);
+                    block.ok_or(SyncError::MissingBlock(announced.hash))
+                })
+                .buffered(self.cfg.parallel_bodies.max(1));
+
+            while let Some(block) = bodies.next().await {
+                let block: Block = block?;
+                height = block.header.height;
+                tip = Some(engine.import_block_async(block).await?);
+                imported += 1;
+                self.handle.update(|status| {
+                    status.current_height = height;
+                    status.blocks_imported = imported;
+                    status.queue.retain(|queued| queued.height > height);
+                    if let Some(metrics) = &self.metrics {
+                        metrics.observe_sync_queue(&status.queue);
+                    }
+                });
+                if let Some(metrics) = &self.metrics {
+                    metrics.sync_blocks_imported.inc();
+                }
+            }
+        }
+    }
+}
+
+/// Returns the local tip hash and height (0 without a tip).
+fn local_tip<S, V, F>(
+    engine: &ConsensusEngine<S, V, F>,
+) -> Result<(Option<BlockHash>, u64), SyncError>
+where
+    S: BlockStore,
+    F: ForkChoice,
+{
+    let tip = engine
+        .tip_block()
+        .map_err(|e| SyncError::Consensus(e.into()))?;
+    Ok(match tip {
+        Some(block) => (Some(block.compute_hash()), block.header.height),
+        None => (None, 0),
+    })
+}
+
+/// Checks that `headers` are consecutive from `start_height` and link up
+/// to `tip`.
+fn check_headers(
+    headers: &[SyncHeader],
+    tip: Option<BlockHash>,
+    start_height: u64,
+) -> Result<(), SyncError> {
+    let mut parent = tip;
+    for (offset, announced) in headers.iter().enumerate() {
+        let expected_height = start_height + offset as u64;
+        if announced.header.height != expected_height {
+            return Err(SyncError::InvalidHeaders(format!(
+                "expected height {expected_height}, got {}",
+                announced.header.height
+            )));
+        }
+        if parent.is_some_and(|parent| announced.header.parent != parent) {
+            return Err(SyncError::InvalidHeaders(format!(
+                "header at height {expected_height} does not extend the local chain"
+            )));
+        }
+        parent = Some(announced.hash);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::consensus::{AcceptAllValidator, ConsensusConfig, LongestChainForkChoice};
+    use crate::storage::InMemoryBlockStore;
+    use crate::types::{AccountId, HASH_LEN, Hash256, Header};
+
+    type TestEngine =
+        ConsensusEngine<InMemoryBlockStore, AcceptAllValidator, LongestChainForkChoice>;
+
+    fn engine() -> TestEngine {
+        ConsensusEngine::new(
+            ConsensusConfig::default(),
+            InMemoryBlockStore::new(),
+            AcceptAllValidator,
+            LongestChainForkChoice,
+        )
+        .expect("empty store needs no replay")
+    }
+
+    /// Peer serving requests straight from another engine's store.
+    struct StorePeer<'a>(&'a InMemoryBlockStore);
+
+    impl SyncPeer for StorePeer<'_> {
+        async fn headers(&self, request: HeadersRequest) -> Result<HeadersResponse, NetworkError> {
+            Ok(request.respond(self.0).expect("store reads succeed"))
+        }
+
+        async fn block(&self, request: BlockRequest) -> Result<BlockResponse, NetworkError> {
+            Ok(request.respond(self.0).expect("store reads succeed"))
+        }
+    }
+
+    fn extend(engine: &mut TestEngine, blocks: u64) {
+        for _ in 0..blocks {
+            let parent = engine.tip_block().unwrap();
+            let (parent_hash, height) = match &parent {
+                Some(block) => (block.compute_hash(), block.header.height + 1),
+                None => (BlockHash(Hash256([0u8; HASH_LEN])), 0),
+            };
+            let block = Block {
+                header: Header {
+                    parent: parent_hash,
+                    height,
+                    timestamp: 1_700_000_000 + height,
+                    proposer: AccountId(Hash256([1u8; HASH_LEN])),
+                    txs_root: Hash256([0u8; HASH_LEN]),
+                    state_root: Hash256([0u8; HASH_LEN]),
+                    receipts_root: Hash256([0u8; HASH_LEN]),
+                    pos_proof: None,
+                },
+                txs: Vec::new(),
+            };
+            engine.import_block(block).expect("block imports");
+        }
+    }
+
+    #[tokio::test]
+    async fn sync_catches_up_in_batches_and_reports_progress() {
+        let mut source = engine();
+        extend(&mut source, 10);
+        let mut target = engine();
+        extend(&mut target, 3);
+
+        let sync = BlockSync::new(SyncConfig {
+            header_batch: 4,
+            parallel_bodies: 3,
+        });
+        let imported = sync
+            .sync(&mut target, &StorePeer(source.store()))
+            .await
+            .expect("sync succeeds");
+
+        assert_eq!(imported, 7);
+        assert_eq!(target.tip().unwrap(), source.tip().unwrap());
+        let status = sync.handle().status();
+        assert!(!status.syncing);
+        assert_eq!((status.start_height, status.current_height), (2, 9));
+        assert_eq!(status.target_height, 9);
+        assert_eq!(status.remaining(), 0);
+        assert!(status.queue.is_empty());
+        assert_eq!(status.last_error, None);
+    }
+
+    /// Peer recording the sync queue each time a body is requested.
+    struct QueueWatchingPeer<'a> {
+        inner: StorePeer<'a>,
+        handle: SyncHandle,
+        seen: std::sync::Mutex<Vec<Vec<u64>>>,
+    }
+
+    impl SyncPeer for QueueWatchingPeer<'_> {
+        async fn headers(&self, request: HeadersRequest) -> Result<HeadersResponse, NetworkError> {
+            self.inner.headers(request).await
+        }
+
+        async fn block(&self, request: BlockRequest) -> Result<BlockResponse, NetworkError> {
+            let queue = self.handle.status().queue;
+            self.seen
+                .lock()
+                .unwrap()
+                .push(queue.iter().map(|queued| queued.height).collect());
+            self.inner.block(request).await
+        }
+    }
+
+    #[tokio::test]
+    async fn sync_lists_announced_blocks_until_they_are_imported() {
+        let mut source = engine();
+        extend(&mut source, 6);
+        let mut target = engine();
+        extend(&mut target, 1);
+
+        let handle = SyncHandle::new();
+        let sync = BlockSync::new(SyncConfig {
+            header_batch: 8,
+            parallel_bodies: 1,
+        })
+        .with_handle(handle.clone());
+        let peer = QueueWatchingPeer {
+            inner: StorePeer(source.store()),
+            handle,
+            seen: std::sync::Mutex::new(Vec::new()),
+        };
+        sync.sync(&mut target, &peer).await.expect("sync succeeds");
+
+        let seen = peer.seen.into_inner().unwrap();
+        assert_eq!(seen.first(), Some(&vec![1, 2, 3, 4, 5]));
+        assert!(seen.iter().all(|queue| !queue.is_empty()));
+        assert!(sync.handle().status().queue.is_empty());
+    }
+
+    #[tokio::test]
+    async fn sync_rejects_headers_from_another_branch() {
+        let mut source = engine();
+        extend(&mut source, 5);
+        let mut target = engine();
+        extend(&mut target, 2);
+        // Replace the target's block 1 by a sibling: the chains fork at
+        // genesis.
+        let mut fork = target.tip_block().unwrap().unwrap();
+        fork.header.timestamp += 100;
+        target.import_block(fork.clone()).expect("fork imports");
+        fork.header.parent = fork.compute_hash();
+        fork.header.height = 2;
+        fork.header.timestamp += 1;
+        target.import_block(fork).expect("fork extends");
+
+        let sync = BlockSync::new(SyncConfig::default());
+        let err = sync
+            .sync(&mut target, &StorePeer(source.store()))
+            .await
+            .unwrap_err();
+
+        assert!(matches!(err, SyncError::InvalidHeaders(_)), "{err}");
+        assert!(sync.handle().status().last_error.is_some());
+    }
+}
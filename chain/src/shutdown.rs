@@ -0,0 +1,99 @@
+//! Cooperative shutdown for node tasks.
+//!
+//! A [`Shutdown`] token is cloned into every long-running task of a node
+//! (the block producer, maintenance loops, metrics exporters). Tasks check
+//! it between units of work rather than being aborted, so a block that is
+//! being proposed when Ctrl-C arrives is still imported and stored, and
+//! the binary can flush the block store once every task has returned.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::sync::watch;
+
+/// Cloneable token that tells tasks to stop.
+///
+/// All clones share one flag: triggering any of them wakes every
+/// [`wait`](Shutdown::wait) and [`sleep`](Shutdown::sleep) in progress,
+/// and every later call returns immediately.
+#[derive(Clone, Debug)]
+pub struct Shutdown(Arc<watch::Sender<bool>>);
+
+impl Shutdown {
+    /// Creates a token that has not been triggered.
+    pub fn new() -> Self {
+        Self(Arc::new(watch::channel(false).0))
+    }
+
+    /// Spawns a task onto the current Tokio runtime that triggers the
+    /// token on Ctrl-C, and returns the token.
+    ///
+    /// Once the handler is installed Ctrl-C no longer kills the process,
+    /// so the caller must return after the token is triggered.
+    pub fn on_ctrl_c() -> Self {
+        let shutdown = Self::new();
+        let trigger = shutdown.clone();
+        tokio::spawn(async move {
+            if tokio::signal::ctrl_c().await.is_ok() {
+                trigger.trigger();
+            }
+        });
+        shutdown
+    }
+
+    /// Asks every task holding a clone to stop. Triggering twice is a no-op.
+    pub fn trigger(&self) {
+        self.0.send_replace(true);
+    }
+
+    /// Returns `true` once the token has been triggered.
+    pub fn is_triggered(&self) -> bool {
+        *self.0.borrow()
+    }
+
+    /// Waits until the token is triggered.
+    pub async fn wait(&self) {
+        // The sender lives as long as `self`, so this cannot fail.
+        let _ = self.0.subscribe().wait_for(|&triggered| triggered).await;
+    }
+
+    /// Sleeps for `duration` unless the token is triggered first.
+    ///
+    /// Returns `true` if the whole `duration` elapsed and `false` on
+    /// shutdown, so loops can be written as
+    /// `while shutdown.sleep(interval).await { ... }`.
+    pub async fn sleep(&self, duration: Duration) -> bool {
+        tokio::select! {
+            () = tokio::time::sleep(duration) => !self.is_triggered(),
+            () = self.wait() => false,
+        }
+    }
+}
+
+impl Default for Shutdown {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn trigger_wakes_every_clone() {
+        let shutdown = Shutdown::new();
+        assert!(!shutdown.is_triggered());
+        assert!(shutdown.sleep(Duration::from_millis(1)).await);
+
+        let clone = shutdown.clone();
+        let waiter = tokio::spawn(async move { clone.sleep(Duration::from_secs(3_600)).await });
+        shutdown.trigger();
+        assert!(!waiter.await.unwrap());
+
+        // Later calls return at once.
+        assert!(shutdown.is_triggered());
+        shutdown.wait().await;
+        assert!(!shutdown.sleep(Duration::from_secs(3_600)).await);
+    }
+}
@@ -0,0 +1,820 @@
+//! In-process multi-node simulator.
+//!
+//! [`Simulator::run`] plays a [`Scenario`] as a discrete-event simulation
+//! on a virtual millisecond clock, so a run takes as long as the
+//! computation and is fully determined by the scenario and its seed.
+//!
+//! Every node is a real [`ConsensusEngine`] over an [`InMemoryBlockStore`]
+//! with the usual validator stack (`base`, `proposer` and `state` stages
+//! plus [`MlValidity`]), a quorum fork choice, and its own [`Mempool`].
+//! Validators are scheduled round-robin and propose once per slot.
+//!
+//! The simulated network carries blocks, block requests, votes and
+//! transactions between linked nodes:
+//!
+//! - each message is delayed by its link's latency plus jitter, or lost
+//!   with probability `drop_rate`, or cut by an active partition (both
+//!   decided when it is sent);
+//! - a node relays a block only once it has imported it, and asks the
+//!   sender for the parent of a block it cannot link;
+//! - validators vote for every new tip height they reach, and every node
+//!   counts the flooded votes towards quorum commits.
+//!
+//! ML verdicts come from a [`MockMlVerifier`] built from the scenario's
+//! `[ml_verifier]` section. All nodes share its seed, so they agree on
+//! which artefacts are inauthentic, while verifier errors strike each
+//! node independently. Verification latency is modelled on the virtual
+//! clock: a block carrying registrations is imported, or relayed by its
+//! proposer, only after the verifier's delay. A leader whose proposal is
+//! rejected by the verifier retries in the same slot without the rejected
+//! registration.
+//!
+//! The resulting [`RunReport`] is measured against the highest node's
+//! final chain: forked blocks are proposals left off it, and registration
+//! latency is read from its blocks.
+
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+
+use crate::consensus::{
+    BlockStore, CombinedValidator, ConsensusConfig, ConsensusEngine, ConsensusError,
+    GenesisAccount, GenesisConfig, LeaderElection, LongestChainForkChoice, ProposerSelector,
+    QuorumForkChoice, ValidationError, ValidatorPipeline, ValidatorSet, ValidatorSetConfig, Vote,
+};
+use crate::execution::StateHandle;
+use crate::mempool::{InclusionTracker, Mempool, MempoolConfig};
+use crate::ml_client::MockMlVerifier;
+use crate::storage::InMemoryBlockStore;
+use crate::types::{
+    AccountId, Aid, Block, BlockHash, EvidenceHash, EvidenceRef, Hash256, ModelUseMetadata,
+    PublicKey, Signature, Transaction, TxHash, TxRegisterModel, TxTransfer, TxUseModel,
+};
+use crate::validation::{BaseValidity, MlConfig, MlValidity, ProposerValidity, StatefulValidity};
+
+use super::report::{RunRecorder, RunReport};
+use super::scenario::{MlVerifierSpec, NodeRole, Scenario};
+
+/// Balance every workload account starts with.
+const CLIENT_BALANCE: u64 = 1_000_000_000;
+
+/// Blocks with an unknown parent each node keeps while fetching it.
+const ORPHAN_BUFFER_SIZE: usize = 256;
+
+type SimValidator = CombinedValidator<ValidatorPipeline, MlValidity<MockMlVerifier>>;
+type SimEngine =
+    ConsensusEngine<InMemoryBlockStore, SimValidator, QuorumForkChoice<LongestChainForkChoice>>;
+
+/// Runs a [`Scenario`] across in-process nodes.
+#[derive(Clone, Debug)]
+pub struct Simulator {
+    scenario: Scenario,
+}
+
+impl Simulator {
+    /// Creates a simulator for `scenario`, which should have passed
+    /// [`Scenario::validate`].
+    pub fn new(scenario: Scenario) -> Self {
+        Self { scenario }
+    }
+
+    /// Returns the scenario being simulated.
+    pub fn scenario(&self) -> &Scenario {
+        &self.scenario
+    }
+
+    /// Plays the scenario to its end and returns the report.
+    ///
+    /// Fails only if a node cannot be set up or its store fails; blocks
+    /// and transactions rejected along the way are part of the run.
+    pub fn run(&self) -> Result<RunReport, ConsensusError> {
+        Run::new(&self.scenario)?.play()
+    }
+}
+
+/// Deterministic random numbers derived from the scenario seed.
+#[derive(Debug)]
+struct SimRng {
+    seed: u64,
+    draws: u64,
+}
+
+impl SimRng {
+    fn new(seed: u64) -> Self {
+        Self { seed, draws: 0 }
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let mut hasher = blake3::Hasher::new();
+        hasher.update(b"sim");
+        hasher.update(&self.seed.to_le_bytes());
+        hasher.update(&self.draws.to_le_bytes());
+        self.draws += 1;
+        let bytes: [u8; 8] = hasher.finalize().as_bytes()[..8]
+            .try_into()
+            .expect("blake3 output is 32 bytes");
+        u64::from_le_bytes(bytes)
+    }
+
+    /// Returns a draw from `[0, 1)`.
+    fn unit(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Returns a draw from `0..=max`.
+    fn up_to(&mut self, max: u64) -> u64 {
+        self.next_u64() % max.saturating_add(1)
+    }
+
+    /// Returns an exponentially distributed gap, in milliseconds, between
+    /// events arriving at `per_sec`.
+    fn gap_ms(&mut self, per_sec: f64) -> u64 {
+        let gap_secs = -(1.0 - self.unit()).ln() / per_sec;
+        ((gap_secs * 1000.0) as u64).max(1)
+    }
+}
+
+/// What one node sends another.
+#[derive(Clone, Debug)]
+enum Message {
+    Block(Block),
+    GetBlock(BlockHash),
+    Vote(Vote),
+    Tx(Transaction),
+}
+
+/// Client transaction kinds of the workload.
+#[derive(Clone, Copy, Debug)]
+enum TxKind {
+    Register,
+    Use,
+    Transfer,
+}
+
+#[derive(Clone, Debug)]
+enum Event {
+    /// Start of slot `n`; its leader proposes.
+    Slot(u64),
+    Deliver {
+        from: usize,
+        to: usize,
+        message: Message,
+    },
+    /// `node` finished ML verification of a block received from `from`.
+    Import {
+        node: usize,
+        from: usize,
+        block: Block,
+    },
+    Submit(TxKind),
+}
+
+/// An event due at `at`; `seq` keeps simultaneous events in FIFO order.
+#[derive(Debug)]
+struct Scheduled {
+    at: u64,
+    seq: u64,
+    event: Event,
+}
+
+impl PartialEq for Scheduled {
+    fn eq(&self, other: &Self) -> bool {
+        (self.at, self.seq) == (other.at, other.seq)
+    }
+}
+
+impl Eq for Scheduled {}
+
+impl PartialOrd for Scheduled {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Scheduled {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.at, self.seq).cmp(&(other.at, other.seq))
+    }
+}
+
+struct Node {
+    /// Validator account, or `None` for full nodes.
+    account: Option<AccountId>,
+    engine: SimEngine,
+    pool: Mempool,
+    peers: Vec<usize>,
+    /// Blocks waiting for ML verification.
+    importing: HashSet<BlockHash>,
+    relayed: HashSet<BlockHash>,
+    seen_txs: HashSet<TxHash>,
+    seen_votes: HashSet<(AccountId, u64)>,
+    voted_height: u64,
+}
+
+/// State of one simulation run.
+struct Run<'a> {
+    scenario: &'a Scenario,
+    genesis_timestamp: u64,
+    election: LeaderElection,
+    nodes: Vec<Node>,
+    clients: Vec<AccountId>,
+    queue: BinaryHeap<Reverse<Scheduled>>,
+    seq: u64,
+    now_ms: u64,
+    rng: SimRng,
+    recorder: Arc<Mutex<RunRecorder>>,
+    tracker: InclusionTracker,
+    /// Virtual time at which each block was proposed.
+    proposed: HashMap<BlockHash, u64>,
+    artefacts_submitted: u64,
+}
+
+impl<'a> Run<'a> {
+    fn new(scenario: &'a Scenario) -> Result<Self, ConsensusError> {
+        let roles = scenario.roles();
+        let validator_accounts: Vec<Option<AccountId>> = roles
+            .iter()
+            .enumerate()
+            .map(|(i, role)| {
+                (*role == NodeRole::Validator)
+                    .then(|| AccountId(Hash256::compute(format!("sim-validator-{i}").as_bytes())))
+            })
+            .collect();
+        let clients: Vec<AccountId> = (0..scenario.workload.accounts)
+            .map(|i| AccountId(Hash256::compute(format!("sim-client-{i}").as_bytes())))
+            .collect();
+
+        let genesis = GenesisConfig {
+            chain_id: format!("sim-{}", scenario.name),
+            accounts: clients
+                .iter()
+                .map(|account| GenesisAccount {
+                    account: *account,
+                    balance: CLIENT_BALANCE,
+                    stake: 0,
+                })
+                .collect(),
+            ..GenesisConfig::default()
+        };
+        let validators = ValidatorSetConfig {
+            accounts: validator_accounts.iter().flatten().copied().collect(),
+            ..ValidatorSetConfig::default()
+        };
+        let election = LeaderElection::from_config(
+            &validators,
+            scenario.block_time_secs,
+            genesis.genesis_hash().0,
+        );
+        let consensus = ConsensusConfig {
+            block_time_secs: scenario.block_time_secs,
+            orphan_buffer_size: ORPHAN_BUFFER_SIZE,
+            // Block timestamps follow the virtual clock, not the wall clock.
+            max_future_drift_secs: 0,
+            ..ConsensusConfig::default()
+        };
+        // Latency is modelled on the virtual clock, so the mock answers
+        // at once.
+        let ml_spec = MlVerifierSpec {
+            latency_ms: 0,
+            jitter_ms: 0,
+            ..scenario.ml_verifier.clone()
+        };
+
+        let mut peers = vec![Vec::new(); roles.len()];
+        for (a, b) in scenario.network.topology.links(roles.len()) {
+            peers[a].push(b);
+            peers[b].push(a);
+        }
+
+        let recorder = Arc::new(Mutex::new(RunRecorder::new()));
+        let mut nodes = Vec::with_capacity(roles.len());
+        for (account, peers) in validator_accounts.into_iter().zip(peers) {
+            let state_handle = StateHandle::new();
+            let pipeline = ValidatorPipeline::new()
+                .with_stage("base", BaseValidity::new(&consensus))
+                .with_stage("proposer", ProposerValidity::new(election.clone()))
+                .with_stage("state", StatefulValidity::new(state_handle.clone()));
+            let ml = MlValidity::new(
+                MockMlVerifier::from_spec(&ml_spec, scenario.seed),
+                MlConfig::default(),
+            );
+            let reorgs = Arc::clone(&recorder);
+            let engine = ConsensusEngine::with_genesis(
+                consensus.clone(),
+                &genesis,
+                InMemoryBlockStore::new(),
+                CombinedValidator::new(pipeline, ml),
+                QuorumForkChoice::new(LongestChainForkChoice),
+            )?
+            .with_state_handle(state_handle)
+            .with_votes(ValidatorSet::from_config(&validators))
+            .with_reorg_listener(move |event| {
+                reorgs
+                    .lock()
+                    .expect("sim recorder lock poisoned")
+                    .observe_reorg(event);
+            });
+            nodes.push(Node {
+                account,
+                engine,
+                pool: Mempool::new(MempoolConfig::default()),
+                peers,
+                importing: HashSet::new(),
+                relayed: HashSet::new(),
+                seen_txs: HashSet::new(),
+                seen_votes: HashSet::new(),
+                voted_height: 0,
+            });
+        }
+
+        Ok(Self {
+            scenario,
+            genesis_timestamp: genesis.timestamp,
+            election,
+            nodes,
+            clients,
+            queue: BinaryHeap::new(),
+            seq: 0,
+            now_ms: 0,
+            rng: SimRng::new(scenario.seed),
+            recorder,
+            tracker: InclusionTracker::new(),
+            proposed: HashMap::new(),
+            artefacts_submitted: 0,
+        })
+    }
+
+    fn play(mut self) -> Result<RunReport, ConsensusError> {
+        let scenario = self.scenario;
+        let end_ms = scenario.duration_secs * 1000;
+        self.schedule(self.slot_ms(), Event::Slot(1));
+        if !self.clients.is_empty() {
+            let workload = &scenario.workload;
+            for (kind, per_sec) in [
+                (TxKind::Register, workload.registrations_per_sec),
+                (TxKind::Use, workload.uses_per_sec),
+                (TxKind::Transfer, workload.transfers_per_sec),
+            ] {
+                if per_sec > 0.0 {
+                    let gap = self.rng.gap_ms(per_sec);
+                    self.schedule(gap, Event::Submit(kind));
+                }
+            }
+        }
+
+        // After the end, nothing new is proposed or submitted, but messages
+        // in flight are still delivered.
+        while let Some(Reverse(next)) = self.queue.pop() {
+            if next.at > end_ms && matches!(next.event, Event::Slot(_) | Event::Submit(_)) {
+                continue;
+            }
+            self.now_ms = next.at;
+            self.handle(next.event)?;
+        }
+        self.finish()
+    }
+
+    fn slot_ms(&self) -> u64 {
+        self.scenario.block_time_secs * 1000
+    }
+
+    fn schedule(&mut self, delay_ms: u64, event: Event) {
+        self.seq += 1;
+        self.queue.push(Reverse(Scheduled {
+            at: self.now_ms + delay_ms,
+            seq: self.seq,
+            event,
+        }));
+    }
+
+    /// Sends `message` over the link, `after_ms` from now.
+    fn send(&mut self, from: usize, to: usize, message: Message, after_ms: u64) {
+        let network = &self.scenario.network;
+        if network.partitioned(from, to, self.now_ms) || self.rng.unit() < network.drop_rate {
+            return;
+        }
+        let delay = after_ms + network.latency(from, to) + self.rng.up_to(network.jitter_ms);
+        self.schedule(delay, Event::Deliver { from, to, message });
+    }
+
+    /// Sends `message` to every peer of `from` but `except`.
+    fn broadcast(&mut self, from: usize, except: Option<usize>, message: Message, after_ms: u64) {
+        for to in self.nodes[from].peers.clone() {
+            if Some(to) != except {
+                self.send(from, to, message.clone(), after_ms);
+            }
+        }
+    }
+
+    /// Returns how long verifying `block`'s artefacts takes.
+    fn ml_delay_ms(&mut self, block: &Block) -> u64 {
+        if block.ml_pairs().is_empty() {
+            return 0;
+        }
+        self.ml_call_ms()
+    }
+
+    fn ml_call_ms(&mut self) -> u64 {
+        let spec = &self.scenario.ml_verifier;
+        spec.latency_ms + self.rng.up_to(spec.jitter_ms)
+    }
+
+    fn handle(&mut self, event: Event) -> Result<(), ConsensusError> {
+        match event {
+            Event::Slot(slot) => {
+                let next = self.slot_ms();
+                self.schedule(next, Event::Slot(slot + 1));
+                self.propose(slot)
+            }
+            Event::Deliver { from, to, message } => self.deliver(from, to, message),
+            Event::Import { node, from, block } => self.import(node, from, block),
+            Event::Submit(kind) => {
+                let workload = &self.scenario.workload;
+                let per_sec = match kind {
+                    TxKind::Register => workload.registrations_per_sec,
+                    TxKind::Use => workload.uses_per_sec,
+                    TxKind::Transfer => workload.transfers_per_sec,
+                };
+                let gap = self.rng.gap_ms(per_sec);
+                self.schedule(gap, Event::Submit(kind));
+                self.submit(kind)
+            }
+        }
+    }
+
+    fn propose(&mut self, slot: u64) -> Result<(), ConsensusError> {
+        let timestamp = self.genesis_timestamp + slot * self.scenario.block_time_secs;
+        let Some(leader) = self.election.expected_proposer(timestamp) else {
+            return Ok(());
+        };
+        let Some(index) = self.nodes.iter().position(|n| n.account == Some(leader)) else {
+            return Ok(());
+        };
+
+        // An ML rejection drops the rejected registration from the pool and
+        // returns the block's other transactions, so the leader retries
+        // without it after the wasted verifier call.
+        let mut delay = 0;
+        loop {
+            let node = &mut self.nodes[index];
+            let result = node.engine.propose_block(leader, &mut node.pool, timestamp);
+            node.pool.prune(node.engine.state());
+            match result {
+                Ok((hash, block)) => {
+                    self.proposed.insert(hash, self.now_ms);
+                    self.observe_ml(Some(&block), None);
+                    delay += self.ml_delay_ms(&block);
+                    return self.settle(index, &block, delay);
+                }
+                Err(e) => {
+                    if !self.observe_ml(None, Some(&e)) {
+                        return Ok(());
+                    }
+                    delay += self.ml_call_ms();
+                }
+            }
+        }
+    }
+
+    fn deliver(&mut self, from: usize, to: usize, message: Message) -> Result<(), ConsensusError> {
+        match message {
+            Message::Block(block) => {
+                let hash = block.compute_hash();
+                let node = &self.nodes[to];
+                if node.importing.contains(&hash) || node.engine.store().get_block(&hash)?.is_some()
+                {
+                    return Ok(());
+                }
+                self.nodes[to].importing.insert(hash);
+                let delay = self.ml_delay_ms(&block);
+                self.schedule(
+                    delay,
+                    Event::Import {
+                        node: to,
+                        from,
+                        block,
+                    },
+                );
+            }
+            Message::GetBlock(hash) => {
+                if let Some(block) = self.nodes[to].engine.store().get_block(&hash)? {
+                    self.send(to, from, Message::Block(block), 0);
+                }
+            }
+            Message::Vote(vote) => self.count_vote(to, Some(from), vote),
+            Message::Tx(tx) => {
+                let node = &mut self.nodes[to];
+                if node.seen_txs.insert(tx.compute_hash())
+                    && node.pool.insert(tx.clone(), node.engine.state()).is_ok()
+                {
+                    self.broadcast(to, Some(from), Message::Tx(tx), 0);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn import(&mut self, index: usize, from: usize, block: Block) -> Result<(), ConsensusError> {
+        let hash = block.compute_hash();
+        let node = &mut self.nodes[index];
+        node.importing.remove(&hash);
+        if node.engine.store().get_block(&hash)?.is_some() {
+            return Ok(());
+        }
+
+        match node.engine.import_block(block.clone()) {
+            Ok(_) => {
+                self.observe_ml(Some(&block), None);
+                self.settle(index, &block, 0)
+            }
+            Err(ConsensusError::Storage(_))
+                if self.nodes[index]
+                    .engine
+                    .store()
+                    .get_block(&block.header.parent)?
+                    .is_none() =>
+            {
+                // Unknown parent: the block waits in the orphan buffer
+                // while the parent is fetched from the sender.
+                self.send(index, from, Message::GetBlock(block.header.parent), 0);
+                Ok(())
+            }
+            Err(e) => {
+                self.observe_ml(None, Some(&e));
+                Ok(())
+            }
+        }
+    }
+
+    /// Relays a block `index` just imported, and the new tip if it
+    /// differs, then prunes the pool and votes for a new tip height.
+    fn settle(&mut self, index: usize, block: &Block, after_ms: u64) -> Result<(), ConsensusError> {
+        let hash = block.compute_hash();
+        if self.nodes[index].relayed.insert(hash) {
+            self.broadcast(index, None, Message::Block(block.clone()), after_ms);
+        }
+
+        let node = &mut self.nodes[index];
+        let Some(tip) = node.engine.tip_block()? else {
+            return Ok(());
+        };
+        let tip_hash = tip.compute_hash();
+        let tip_height = tip.header.height;
+        node.pool.prune(node.engine.state());
+        if node.relayed.insert(tip_hash) {
+            self.broadcast(index, None, Message::Block(tip), after_ms);
+        }
+
+        let node = &mut self.nodes[index];
+        if let Some(validator) = node.account
+            && tip_height > node.voted_height
+        {
+            node.voted_height = tip_height;
+            let vote = Vote {
+                block_hash: tip_hash,
+                height: tip_height,
+                validator,
+                validator_key: PublicKey(Vec::new()),
+                signature: Signature(Vec::new()),
+            };
+            self.count_vote(index, None, vote);
+        }
+        Ok(())
+    }
+
+    /// Counts `vote` at `index` and floods it on, once per validator and
+    /// height.
+    fn count_vote(&mut self, index: usize, from: Option<usize>, vote: Vote) {
+        let node = &mut self.nodes[index];
+        if !node.seen_votes.insert((vote.validator, vote.height)) {
+            return;
+        }
+        // Stale and equivocating votes are expected around forks.
+        if let Ok(Some(commit)) = node.engine.add_vote(vote.clone()) {
+            node.pool.prune(node.engine.state());
+            if let Some(proposed_at) = self.proposed.get(&commit.block_hash) {
+                self.recorder
+                    .lock()
+                    .expect("sim recorder lock poisoned")
+                    .observe_finality(self.now_ms - proposed_at);
+            }
+        }
+        self.broadcast(index, from, Message::Vote(vote), 0);
+    }
+
+    /// Records the ML outcome of validating a block: `block` if it was
+    /// accepted, or the error it was rejected with. Returns `true` if the
+    /// verifier rejected an artefact.
+    fn observe_ml(&self, block: Option<&Block>, error: Option<&ConsensusError>) -> bool {
+        let mut recorder = self.recorder.lock().expect("sim recorder lock poisoned");
+        if block.is_some_and(|b| !b.ml_pairs().is_empty()) {
+            recorder.observe_ml_check();
+        }
+        match error {
+            Some(e) if e.is_ml_unavailable() => recorder.observe_ml_error(),
+            Some(ConsensusError::Validation(e))
+                if matches!(e.root(), ValidationError::MlRejected { .. }) =>
+            {
+                recorder.observe_ml_check();
+                recorder.observe_ml_rejections(1);
+                return true;
+            }
+            _ => {}
+        }
+        false
+    }
+
+    fn submit(&mut self, kind: TxKind) -> Result<(), ConsensusError> {
+        let client = self.rng.up_to(self.clients.len() as u64 - 1) as usize;
+        let sender = self.clients[client];
+        // Each client talks to one node, so its nonces stay consistent.
+        let index = client % self.nodes.len();
+        let node = &self.nodes[index];
+        let nonce = node.pool.next_nonce(&sender, node.engine.state());
+        let wm_profile = node.engine.state().params().wm_profile.clone();
+        let mut aids: Vec<Aid> = node.engine.state().artefacts().map(|(a, _)| *a).collect();
+
+        let tx = match kind {
+            TxKind::Register => {
+                self.artefacts_submitted += 1;
+                let n = self.artefacts_submitted.to_le_bytes();
+                let seed = self.scenario.seed.to_le_bytes();
+                let aid = Aid(Hash256::compute(&[&b"sim-aid"[..], &seed, &n].concat()));
+                let evidence = EvidenceRef {
+                    scheme_id: "wm-sim".to_string(),
+                    evidence_hash: EvidenceHash(Hash256::compute(
+                        &[&b"sim-evidence"[..], &seed, &n].concat(),
+                    )),
+                    wm_profile,
+                };
+                Transaction::RegisterModel(TxRegisterModel {
+                    owner: sender,
+                    aid,
+                    chunked: None,
+                    evidence,
+                    fee: 0,
+                    nonce,
+                    signature: Signature(Vec::new()),
+                })
+            }
+            TxKind::Use => {
+                if aids.is_empty() {
+                    return Ok(());
+                }
+                aids.sort_unstable_by_key(|aid| *aid.as_hash().as_bytes());
+                let aid = aids[self.rng.up_to(aids.len() as u64 - 1) as usize];
+                Transaction::UseModel(TxUseModel {
+                    caller: sender,
+                    aid,
+                    metadata: ModelUseMetadata {
+                        task: "sim".to_string(),
+                        version: None,
+                    },
+                    fee: 0,
+                    nonce,
+                    signature: Signature(Vec::new()),
+                })
+            }
+            TxKind::Transfer => {
+                let to = self.rng.up_to(self.clients.len() as u64 - 1) as usize;
+                Transaction::Transfer(TxTransfer {
+                    from: sender,
+                    to: self.clients[to],
+                    amount: 1,
+                    fee: 0,
+                    nonce,
+                    signature: Signature(Vec::new()),
+                })
+            }
+        };
+
+        let node = &mut self.nodes[index];
+        let tip_height = node.engine.tip_block()?.map_or(0, |b| b.header.height);
+        let hash = tx.compute_hash();
+        node.seen_txs.insert(hash);
+        if node.pool.insert(tx.clone(), node.engine.state()).is_err() {
+            return Ok(());
+        }
+        self.tracker.record_admission(&tx, hash, tip_height);
+        self.broadcast(index, None, Message::Tx(tx), 0);
+        Ok(())
+    }
+
+    fn finish(mut self) -> Result<RunReport, ConsensusError> {
+        let mut tips = Vec::with_capacity(self.nodes.len());
+        for node in &self.nodes {
+            let tip = node
+                .engine
+                .tip_block()?
+                .expect("sim nodes start from genesis");
+            tips.push((tip.header.height, tip.compute_hash()));
+        }
+
+        // Measure against the highest chain; the lowest index wins ties.
+        let reference = tips
+            .iter()
+            .enumerate()
+            .max_by_key(|(i, (height, _))| (*height, Reverse(*i)))
+            .map_or(0, |(i, _)| i);
+        let store = self.nodes[reference].engine.store();
+        let mut chain = Vec::new();
+        let mut next = Some(tips[reference].1);
+        while let Some(hash) = next {
+            let Some(block) = store.get_block(&hash)? else {
+                break;
+            };
+            next = (block.header.height > 0).then_some(block.header.parent);
+            chain.push((hash, block));
+        }
+        let canonical: HashSet<BlockHash> = chain.iter().map(|(hash, _)| *hash).collect();
+
+        let mut recorder =
+            std::mem::take(&mut *self.recorder.lock().expect("sim recorder lock poisoned"));
+        let forked = self
+            .proposed
+            .keys()
+            .filter(|hash| !canonical.contains(hash))
+            .count();
+        recorder.observe_forked_blocks(forked as u64);
+        for (_, block) in chain.iter().rev() {
+            for inclusion in self.tracker.observe_block(block) {
+                recorder.observe_inclusion(&inclusion);
+            }
+        }
+        Ok(recorder.finish(&self.scenario.name, self.scenario.seed, &tips))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sim::scenario::NodeGroup;
+
+    fn scenario(toml: &str) -> Scenario {
+        let scenario: Scenario = toml::from_str(toml).unwrap();
+        scenario.validate().unwrap();
+        scenario
+    }
+
+    #[test]
+    fn healthy_network_converges_and_commits() {
+        let scenario = scenario(
+            r#"
+            name = "healthy"
+            seed = 7
+            duration_secs = 60
+
+            [[nodes]]
+            role = "validator"
+            count = 4
+
+            [[nodes]]
+            role = "full"
+            count = 1
+
+            [ml_verifier]
+            reject_rate = 0.3
+            "#,
+        );
+        let report = Simulator::new(scenario.clone()).run().unwrap();
+
+        assert!(report.converged, "{}", report.to_json());
+        assert!(report.min_height >= 10, "{}", report.to_json());
+        assert!(report.registrations_included > 0);
+        assert!(report.mean_time_to_finality_ms.is_some());
+        assert!(report.ml_rejections > 0);
+        assert!(report.ml_rejection_rate.is_some_and(|rate| rate < 1.0));
+
+        // Same scenario and seed, same run.
+        assert_eq!(Simulator::new(scenario).run().unwrap(), report);
+    }
+
+    #[test]
+    fn partitioned_network_forks_and_heals() {
+        let mut scenario = scenario(
+            r#"
+            name = "split"
+            seed = 3
+            duration_secs = 90
+
+            [workload]
+            registrations_per_sec = 0.0
+            uses_per_sec = 0.0
+            transfers_per_sec = 0.0
+
+            [[network.partitions]]
+            start_secs = 10
+            end_secs = 40
+            groups = [[0, 1]]
+            "#,
+        );
+        scenario.nodes = vec![NodeGroup {
+            role: NodeRole::Validator,
+            count: 4,
+        }];
+        let report = Simulator::new(scenario).run().unwrap();
+
+        assert!(report.forked_blocks > 0, "{}", report.to_json());
+        assert!(report.converged, "{}", report.to_json());
+    }
+}
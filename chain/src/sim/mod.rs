@@ -0,0 +1,25 @@
+//! Multi-node simulation.
+//!
+//! Experiments are described declaratively by a [`Scenario`]: how many
+//! nodes run and in which role, how they are connected and how slow the
+//! links are, how the ML verifier behaves, which transactions clients
+//! submit, and for how long. Scenario files are plain TOML or JSON and
+//! live next to the experiment scripts (see `expts/scenarios/`), so a run
+//! can be versioned and repeated from its file and seed alone.
+//!
+//! A [`Simulator`] plays a scenario across in-process nodes on a virtual
+//! clock. Each run ends with a [`RunReport`] (convergence, forks, time to
+//! finality, registration latency, ML rejections) that is checked against
+//! the scenario's assertions.
+
+pub mod harness;
+pub mod report;
+pub mod scenario;
+
+pub use harness::Simulator;
+
+pub use report::{Assertion, AssertionFailure, Comparison, Metric, RunRecorder, RunReport};
+pub use scenario::{
+    MlVerifierSpec, NetworkSpec, NodeGroup, NodeRole, PartitionSpec, Scenario, Topology,
+    WorkloadSpec,
+};
@@ -0,0 +1,412 @@
+//! End-of-run results and scenario assertions.
+//!
+//! While a simulation runs, a [`RunRecorder`] is fed the events that
+//! matter for consensus health: reorgs, abandoned fork blocks, commit
+//! latencies, registration inclusions, and ML verdicts. [`RunRecorder::finish`] turns them and the nodes' final tips
+//! into a [`RunReport`], which serializes to JSON for result archives.
+//!
+//! A scenario may list `[[assertions]]` over the report's metrics:
+//!
+//! ```toml
+//! [[assertions]]
+//! metric = "max_fork_depth"
+//! op = "<="
+//! value = 2
+//! ```
+//!
+//! [`RunReport::check`] evaluates them and returns every failure, so an
+//! experiment runner can exit non-zero when consensus behaviour regresses.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::consensus::ReorgEvent;
+use crate::mempool::{Inclusion, InclusionStats};
+use crate::types::BlockHash;
+
+/// Metric of a [`RunReport`] an assertion can test.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Metric {
+    /// 1 if every node ended on the same tip, 0 otherwise.
+    Converged,
+    /// Lowest final tip height across nodes.
+    MinHeight,
+    /// Number of reorgs across all nodes.
+    Reorgs,
+    /// Deepest reorg seen on any node, in blocks.
+    MaxForkDepth,
+    /// Proposed blocks that ended off the final canonical chain.
+    ForkedBlocks,
+    /// Mean time from proposal to a quorum commit, in milliseconds;
+    /// undefined if nothing was committed.
+    MeanTimeToFinalityMs,
+    /// Registrations included in a block.
+    RegistrationsIncluded,
+    /// Mean blocks between a registration's admission and inclusion;
+    /// undefined if none was included.
+    MeanRegistrationLatencyBlocks,
+    /// Artefacts the ML verifier judged inauthentic.
+    MlRejections,
+    /// ML verifier calls that failed with an error.
+    MlErrors,
+    /// Share of answered ML verifier calls that rejected an artefact;
+    /// undefined if the verifier was never asked.
+    MlRejectionRate,
+}
+
+impl Metric {
+    /// Returns the name used in scenario files and reports.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Metric::Converged => "converged",
+            Metric::MinHeight => "min_height",
+            Metric::Reorgs => "reorgs",
+            Metric::MaxForkDepth => "max_fork_depth",
+            Metric::ForkedBlocks => "forked_blocks",
+            Metric::MeanTimeToFinalityMs => "mean_time_to_finality_ms",
+            Metric::RegistrationsIncluded => "registrations_included",
+            Metric::MeanRegistrationLatencyBlocks => "mean_registration_latency_blocks",
+            Metric::MlRejections => "ml_rejections",
+            Metric::MlErrors => "ml_errors",
+            Metric::MlRejectionRate => "ml_rejection_rate",
+        }
+    }
+}
+
+/// Comparison applied by an [`Assertion`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum Comparison {
+    #[serde(rename = "<")]
+    Lt,
+    #[serde(rename = "<=")]
+    Le,
+    #[serde(rename = "==")]
+    Eq,
+    #[serde(rename = ">=")]
+    Ge,
+    #[serde(rename = ">")]
+    Gt,
+}
+
+impl Comparison {
+    fn holds(&self, actual: f64, expected: f64) -> bool {
+        match self {
+            Comparison::Lt => actual < expected,
+            Comparison::Le => actual <= expected,
+            Comparison::Eq => actual == expected,
+            Comparison::Ge => actual >= expected,
+            Comparison::Gt => actual > expected,
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            Comparison::Lt => "<",
+            Comparison::Le => "<=",
+            Comparison::Eq => "==",
+            Comparison::Ge => ">=",
+            Comparison::Gt => ">",
+        }
+    }
+}
+
+/// A pass/fail rule over one report metric: `metric op value`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Assertion {
+    pub metric: Metric,
+    pub op: Comparison,
+    pub value: f64,
+}
+
+impl fmt::Display for Assertion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {}",
+            self.metric.as_str(),
+            self.op.as_str(),
+            self.value
+        )
+    }
+}
+
+/// An assertion that did not hold.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct AssertionFailure {
+    pub assertion: Assertion,
+    /// The metric's value, or `None` if it was undefined for the run.
+    pub actual: Option<f64>,
+}
+
+impl fmt::Display for AssertionFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.actual {
+            Some(actual) => write!(f, "expected {}, got {actual}", self.assertion),
+            None => write!(
+                f,
+                "expected {}, but {} is undefined",
+                self.assertion,
+                self.assertion.metric.as_str()
+            ),
+        }
+    }
+}
+
+/// Structured summary of one simulation run.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct RunReport {
+    /// Name of the scenario that was run.
+    pub scenario: String,
+    /// Seed the run used.
+    pub seed: u64,
+    /// Whether every node ended on the same tip.
+    pub converged: bool,
+    /// Lowest final tip height across nodes.
+    pub min_height: u64,
+    /// Highest final tip height across nodes.
+    pub max_height: u64,
+    /// Number of reorgs across all nodes.
+    pub reorgs: u64,
+    /// Deepest reorg seen on any node, in blocks.
+    pub max_fork_depth: u64,
+    /// Proposed blocks that ended off the final canonical chain.
+    pub forked_blocks: u64,
+    /// Quorum commits observed, counted once per node and block.
+    pub commits: u64,
+    /// Mean time from proposal to a node's quorum commit, in milliseconds.
+    pub mean_time_to_finality_ms: Option<f64>,
+    /// Longest time from proposal to a node's quorum commit, in
+    /// milliseconds.
+    pub max_time_to_finality_ms: u64,
+    /// Registrations included in a block.
+    pub registrations_included: u64,
+    /// Mean registration inclusion delay, in blocks.
+    pub mean_registration_latency_blocks: Option<f64>,
+    /// Longest registration inclusion delay, in blocks.
+    pub max_registration_latency_blocks: u64,
+    /// Artefacts the ML verifier judged inauthentic.
+    pub ml_rejections: u64,
+    /// ML verifier calls that failed with an error.
+    pub ml_errors: u64,
+    /// ML verifier calls that returned verdicts.
+    pub ml_checks: u64,
+    /// `ml_rejections / ml_checks`.
+    pub ml_rejection_rate: Option<f64>,
+}
+
+impl RunReport {
+    /// Returns the value of `metric`, or `None` if it is undefined.
+    pub fn metric(&self, metric: Metric) -> Option<f64> {
+        match metric {
+            Metric::Converged => Some(if self.converged { 1.0 } else { 0.0 }),
+            Metric::MinHeight => Some(self.min_height as f64),
+            Metric::Reorgs => Some(self.reorgs as f64),
+            Metric::MaxForkDepth => Some(self.max_fork_depth as f64),
+            Metric::ForkedBlocks => Some(self.forked_blocks as f64),
+            Metric::MeanTimeToFinalityMs => self.mean_time_to_finality_ms,
+            Metric::RegistrationsIncluded => Some(self.registrations_included as f64),
+            Metric::MeanRegistrationLatencyBlocks => self.mean_registration_latency_blocks,
+            Metric::MlRejections => Some(self.ml_rejections as f64),
+            Metric::MlErrors => Some(self.ml_errors as f64),
+            Metric::MlRejectionRate => self.ml_rejection_rate,
+        }
+    }
+
+    /// Evaluates `assertions` against this report, returning every one
+    /// that failed. An assertion over an undefined metric fails.
+    pub fn check(&self, assertions: &[Assertion]) -> Result<(), Vec<AssertionFailure>> {
+        let failures: Vec<_> = assertions
+            .iter()
+            .filter_map(|assertion| {
+                let actual = self.metric(assertion.metric);
+                let holds = actual.is_some_and(|a| assertion.op.holds(a, assertion.value));
+                (!holds).then(|| AssertionFailure {
+                    assertion: assertion.clone(),
+                    actual,
+                })
+            })
+            .collect();
+        if failures.is_empty() {
+            Ok(())
+        } else {
+            Err(failures)
+        }
+    }
+
+    /// Encodes the report as pretty-printed JSON.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("RunReport should always serialize to JSON")
+    }
+}
+
+/// Accumulates run events into a [`RunReport`].
+#[derive(Debug, Default)]
+pub struct RunRecorder {
+    reorgs: u64,
+    max_fork_depth: u64,
+    forked_blocks: u64,
+    commits: u64,
+    total_finality_ms: u64,
+    max_finality_ms: u64,
+    registrations: InclusionStats,
+    ml_rejections: u64,
+    ml_errors: u64,
+    ml_checks: u64,
+}
+
+impl RunRecorder {
+    /// Creates an empty recorder.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records a reorg on any node.
+    pub fn observe_reorg(&mut self, event: &ReorgEvent) {
+        self.reorgs += 1;
+        self.max_fork_depth = self.max_fork_depth.max(event.depth);
+    }
+
+    /// Records `count` proposed blocks left off the final canonical chain.
+    pub fn observe_forked_blocks(&mut self, count: u64) {
+        self.forked_blocks += count;
+    }
+
+    /// Records a node committing a block `latency_ms` after it was
+    /// proposed.
+    pub fn observe_finality(&mut self, latency_ms: u64) {
+        self.commits += 1;
+        self.total_finality_ms += latency_ms;
+        self.max_finality_ms = self.max_finality_ms.max(latency_ms);
+    }
+
+    /// Records a registration inclusion, e.g. from
+    /// [`InclusionTracker::observe_block`].
+    ///
+    /// [`InclusionTracker::observe_block`]: crate::mempool::InclusionTracker::observe_block
+    pub fn observe_inclusion(&mut self, inclusion: &Inclusion) {
+        let stats = &mut self.registrations;
+        stats.included += 1;
+        stats.total_delay_blocks += inclusion.delay_blocks;
+        stats.max_delay_blocks = stats.max_delay_blocks.max(inclusion.delay_blocks);
+    }
+
+    /// Records `count` artefacts rejected by the ML verifier.
+    pub fn observe_ml_rejections(&mut self, count: u64) {
+        self.ml_rejections += count;
+    }
+
+    /// Records an ML verifier call that failed with an error.
+    pub fn observe_ml_error(&mut self) {
+        self.ml_errors += 1;
+    }
+
+    /// Records an ML verifier call that returned verdicts, whether or not
+    /// it rejected anything.
+    pub fn observe_ml_check(&mut self) {
+        self.ml_checks += 1;
+    }
+
+    /// Builds the report for `scenario` from the final `(height, hash)`
+    /// tip of every node.
+    pub fn finish(self, scenario: &str, seed: u64, tips: &[(u64, BlockHash)]) -> RunReport {
+        let converged = tips.windows(2).all(|pair| pair[0].1 == pair[1].1);
+        RunReport {
+            scenario: scenario.to_string(),
+            seed,
+            converged,
+            min_height: tips.iter().map(|(h, _)| *h).min().unwrap_or(0),
+            max_height: tips.iter().map(|(h, _)| *h).max().unwrap_or(0),
+            reorgs: self.reorgs,
+            max_fork_depth: self.max_fork_depth,
+            forked_blocks: self.forked_blocks,
+            commits: self.commits,
+            mean_time_to_finality_ms: (self.commits > 0)
+                .then(|| self.total_finality_ms as f64 / self.commits as f64),
+            max_time_to_finality_ms: self.max_finality_ms,
+            registrations_included: self.registrations.included,
+            mean_registration_latency_blocks: self.registrations.average_delay_blocks(),
+            max_registration_latency_blocks: self.registrations.max_delay_blocks,
+            ml_rejections: self.ml_rejections,
+            ml_errors: self.ml_errors,
+            ml_checks: self.ml_checks,
+            ml_rejection_rate: (self.ml_checks > 0)
+                .then(|| self.ml_rejections as f64 / self.ml_checks as f64),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{AccountId, HASH_LEN, Hash256, TxHash};
+
+    fn hash(byte: u8) -> BlockHash {
+        BlockHash(Hash256([byte; HASH_LEN]))
+    }
+
+    fn inclusion(delay_blocks: u64) -> Inclusion {
+        Inclusion {
+            tx_hash: TxHash(Hash256([delay_blocks as u8; HASH_LEN])),
+            owner: AccountId(Hash256([1u8; HASH_LEN])),
+            delay_blocks,
+        }
+    }
+
+    fn assertion(metric: Metric, op: Comparison, value: f64) -> Assertion {
+        Assertion { metric, op, value }
+    }
+
+    #[test]
+    fn recorder_summarises_the_run() {
+        let mut recorder = RunRecorder::new();
+        for depth in [1, 3] {
+            recorder.observe_reorg(&ReorgEvent {
+                old_tip: hash(1),
+                new_tip: hash(2),
+                depth,
+            });
+        }
+        recorder.observe_inclusion(&inclusion(1));
+        recorder.observe_inclusion(&inclusion(4));
+        recorder.observe_ml_rejections(2);
+        recorder.observe_ml_error();
+        for _ in 0..8 {
+            recorder.observe_ml_check();
+        }
+        recorder.observe_finality(400);
+        recorder.observe_finality(600);
+
+        let report = recorder.finish("test", 7, &[(10, hash(9)), (10, hash(9)), (9, hash(8))]);
+        assert!(!report.converged);
+        assert_eq!((report.min_height, report.max_height), (9, 10));
+        assert_eq!((report.reorgs, report.max_fork_depth), (2, 3));
+        assert_eq!(report.mean_registration_latency_blocks, Some(2.5));
+        assert_eq!(report.max_registration_latency_blocks, 4);
+        assert_eq!((report.ml_rejections, report.ml_errors), (2, 1));
+        assert_eq!(report.ml_rejection_rate, Some(0.25));
+        assert_eq!(report.mean_time_to_finality_ms, Some(500.0));
+        assert_eq!(report.max_time_to_finality_ms, 600);
+        assert!(report.to_json().contains("\"max_fork_depth\": 3"));
+    }
+
+    #[test]
+    fn check_reports_every_failed_assertion() {
+        let report = RunRecorder::new().finish("test", 0, &[(5, hash(1)), (5, hash(1))]);
+        assert!(report.converged);
+
+        let assertions = [
+            assertion(Metric::Converged, Comparison::Eq, 1.0),
+            assertion(Metric::MaxForkDepth, Comparison::Le, 2.0),
+            assertion(Metric::MinHeight, Comparison::Gt, 5.0),
+            assertion(Metric::MeanRegistrationLatencyBlocks, Comparison::Lt, 3.0),
+        ];
+        let failures = report.check(&assertions).unwrap_err();
+        assert_eq!(failures.len(), 2);
+        assert_eq!(failures[0].to_string(), "expected min_height > 5, got 5");
+        assert_eq!(failures[1].actual, None);
+        assert!(report.check(&assertions[..2]).is_ok());
+    }
+}
@@ -0,0 +1,543 @@
+//! Declarative scenario files for the simulator.
+//!
+//! A scenario fixes everything a simulated run depends on:
+//!
+//! - the seed, run duration, and block time,
+//! - node groups (`[[nodes]]`: a role and a count); nodes are numbered
+//!   from 0 in declaration order,
+//! - the network (`[network]`: topology, per-link latency and jitter, an
+//!   optional full latency matrix, message drop rate, timed partitions),
+//! - the ML verifier (`[ml_verifier]`: latency and the fractions of
+//!   artefacts rejected or failing with an error),
+//! - the client workload (`[workload]`: submissions per second by
+//!   transaction kind),
+//! - pass/fail rules over the end-of-run report (`[[assertions]]`, see
+//!   [`super::report`]).
+//!
+//! Files are TOML (`.toml`) or JSON (`.json`) with the same layout; see
+//! `expts/scenarios/baseline.toml`. Like [`ChainConfig`], every section
+//! and field except `name` falls back to its default and unknown keys are
+//! rejected. [`Scenario::from_file`] validates what it loads.
+//!
+//! [`ChainConfig`]: crate::config::ChainConfig
+
+use std::path::Path;
+
+use serde::Deserialize;
+
+use crate::config::{ConfigError, parse_config_file};
+
+use super::report::Assertion;
+
+/// A complete, reproducible simulation setup.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Scenario {
+    /// Short identifier, used to label results.
+    pub name: String,
+    /// Free-form description of what the scenario exercises.
+    #[serde(default)]
+    pub description: String,
+    /// Seed for every random choice made during the run.
+    #[serde(default)]
+    pub seed: u64,
+    /// Simulated run time, in seconds.
+    #[serde(default = "default_duration_secs")]
+    pub duration_secs: u64,
+    /// Slot length, in seconds; the slot leader proposes once per slot.
+    #[serde(default = "default_block_time_secs")]
+    pub block_time_secs: u64,
+    /// Node groups, in numbering order.
+    #[serde(default = "default_nodes")]
+    pub nodes: Vec<NodeGroup>,
+    /// Links between nodes.
+    #[serde(default)]
+    pub network: NetworkSpec,
+    /// Behaviour of the ML verifier every node calls.
+    #[serde(default)]
+    pub ml_verifier: MlVerifierSpec,
+    /// Transactions submitted by clients.
+    #[serde(default)]
+    pub workload: WorkloadSpec,
+    /// Rules the run's [`RunReport`] must satisfy.
+    ///
+    /// [`RunReport`]: super::RunReport
+    #[serde(default)]
+    pub assertions: Vec<Assertion>,
+}
+
+fn default_duration_secs() -> u64 {
+    60
+}
+
+fn default_block_time_secs() -> u64 {
+    5
+}
+
+fn default_nodes() -> Vec<NodeGroup> {
+    vec![NodeGroup {
+        role: NodeRole::Validator,
+        count: 4,
+    }]
+}
+
+/// What a simulated node does.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NodeRole {
+    /// Member of the validator set; proposes blocks in its slots.
+    Validator,
+    /// Imports and relays blocks but never proposes.
+    Full,
+}
+
+/// `count` nodes sharing a role.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct NodeGroup {
+    pub role: NodeRole,
+    pub count: usize,
+}
+
+/// Which pairs of nodes are directly connected. Links are bidirectional.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case", deny_unknown_fields)]
+pub enum Topology {
+    /// Every node is connected to every other node.
+    #[default]
+    FullMesh,
+    /// Node `i` is connected to nodes `i - 1` and `i + 1`, wrapping around.
+    Ring,
+    /// Every node is connected to `hub` only.
+    Star { hub: usize },
+    /// Exactly the listed node pairs are connected.
+    Explicit { links: Vec<(usize, usize)> },
+}
+
+impl Topology {
+    /// Returns the links among `nodes` nodes as `(lower, higher)` pairs,
+    /// sorted and without duplicates.
+    pub fn links(&self, nodes: usize) -> Vec<(usize, usize)> {
+        let mut links: Vec<(usize, usize)> = match self {
+            Topology::FullMesh => (0..nodes)
+                .flat_map(|a| (a + 1..nodes).map(move |b| (a, b)))
+                .collect(),
+            Topology::Ring if nodes < 2 => Vec::new(),
+            Topology::Ring => (0..nodes).map(|a| (a, (a + 1) % nodes)).collect(),
+            Topology::Star { hub } => (0..nodes).map(|n| (*hub, n)).collect(),
+            Topology::Explicit { links } => links.clone(),
+        };
+        links = links
+            .into_iter()
+            .filter(|(a, b)| a != b)
+            .map(|(a, b)| (a.min(b), a.max(b)))
+            .collect();
+        links.sort_unstable();
+        links.dedup();
+        links
+    }
+}
+
+/// Network links between simulated nodes.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct NetworkSpec {
+    pub topology: Topology,
+    /// One-way delay of every link, in milliseconds.
+    pub latency_ms: u64,
+    /// Maximum random delay added to each message, in milliseconds.
+    pub jitter_ms: u64,
+    /// Per-pair one-way delays overriding `latency_ms`; row `i`, column
+    /// `j` is the delay from node `i` to node `j`.
+    pub latency_matrix_ms: Option<Vec<Vec<u64>>>,
+    /// Fraction of messages silently dropped, in `[0, 1]`.
+    pub drop_rate: f64,
+    /// Timed network splits; see [`PartitionSpec`].
+    pub partitions: Vec<PartitionSpec>,
+}
+
+/// A network split: while it lasts, messages only flow between nodes of
+/// the same group.
+///
+/// Nodes not listed in any group form one more group together.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PartitionSpec {
+    /// Start of the split, in seconds from the start of the run.
+    pub start_secs: u64,
+    /// End of the split (exclusive), in seconds from the start of the run.
+    pub end_secs: u64,
+    /// Groups of nodes that can still reach each other.
+    pub groups: Vec<Vec<usize>>,
+}
+
+impl PartitionSpec {
+    /// Returns `true` if the split cuts `from` off from `to` at `at_ms`
+    /// milliseconds into the run.
+    pub fn separates(&self, from: usize, to: usize, at_ms: u64) -> bool {
+        let active = (self.start_secs * 1000..self.end_secs * 1000).contains(&at_ms);
+        active && self.group_of(from) != self.group_of(to)
+    }
+
+    fn group_of(&self, node: usize) -> Option<usize> {
+        self.groups.iter().position(|group| group.contains(&node))
+    }
+}
+
+impl Default for NetworkSpec {
+    fn default() -> Self {
+        Self {
+            topology: Topology::FullMesh,
+            latency_ms: 50,
+            jitter_ms: 10,
+            latency_matrix_ms: None,
+            drop_rate: 0.0,
+            partitions: Vec::new(),
+        }
+    }
+}
+
+impl NetworkSpec {
+    /// Returns the base one-way delay from node `from` to node `to`,
+    /// before jitter.
+    pub fn latency(&self, from: usize, to: usize) -> u64 {
+        self.latency_matrix_ms
+            .as_ref()
+            .and_then(|matrix| matrix.get(from)?.get(to).copied())
+            .unwrap_or(self.latency_ms)
+    }
+
+    /// Returns `true` if a partition cuts `from` off from `to` at `at_ms`
+    /// milliseconds into the run.
+    pub fn partitioned(&self, from: usize, to: usize, at_ms: u64) -> bool {
+        self.partitions
+            .iter()
+            .any(|partition| partition.separates(from, to, at_ms))
+    }
+}
+
+/// Behaviour of the simulated ML verifier.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MlVerifierSpec {
+    /// Time to verify one batch, in milliseconds.
+    pub latency_ms: u64,
+    /// Maximum random delay added to each batch, in milliseconds.
+    pub jitter_ms: u64,
+    /// Fraction of artefacts judged inauthentic, in `[0, 1]`.
+    pub reject_rate: f64,
+    /// Fraction of batches failing with a verifier error, in `[0, 1]`.
+    pub error_rate: f64,
+}
+
+impl Default for MlVerifierSpec {
+    fn default() -> Self {
+        Self {
+            latency_ms: 100,
+            jitter_ms: 20,
+            reject_rate: 0.0,
+            error_rate: 0.0,
+        }
+    }
+}
+
+/// Client transaction submissions, in transactions per second across all
+/// nodes.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WorkloadSpec {
+    /// Number of client accounts submitting transactions.
+    pub accounts: usize,
+    pub registrations_per_sec: f64,
+    pub uses_per_sec: f64,
+    pub transfers_per_sec: f64,
+}
+
+impl Default for WorkloadSpec {
+    fn default() -> Self {
+        Self {
+            accounts: 16,
+            registrations_per_sec: 1.0,
+            uses_per_sec: 5.0,
+            transfers_per_sec: 2.0,
+        }
+    }
+}
+
+impl Scenario {
+    /// Loads and validates a scenario file.
+    ///
+    /// The format is chosen by the file extension: `.json` for JSON, and
+    /// the extensions accepted by [`parse_config_file`] otherwise.
+    pub fn from_file(path: &Path) -> Result<Self, ConfigError> {
+        let is_json = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("json"));
+        let scenario: Scenario = if is_json {
+            let contents = std::fs::read_to_string(path).map_err(|source| ConfigError::Read {
+                path: path.to_path_buf(),
+                source,
+            })?;
+            serde_json::from_str(&contents).map_err(|e| ConfigError::Parse {
+                path: path.to_path_buf(),
+                message: e.to_string(),
+            })?
+        } else {
+            parse_config_file(path)?
+        };
+        scenario.validate()?;
+        Ok(scenario)
+    }
+
+    /// Returns the total number of nodes.
+    pub fn node_count(&self) -> usize {
+        self.nodes.iter().map(|group| group.count).sum()
+    }
+
+    /// Returns the role of every node, indexed by node number.
+    pub fn roles(&self) -> Vec<NodeRole> {
+        self.nodes
+            .iter()
+            .flat_map(|group| std::iter::repeat_n(group.role, group.count))
+            .collect()
+    }
+
+    /// Checks the scenario for values that cannot be simulated, reporting
+    /// every problem found.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let mut problems = Vec::new();
+        let nodes = self.node_count();
+
+        if self.name.trim().is_empty() {
+            problems.push("name must not be empty".to_string());
+        }
+        if self.duration_secs == 0 {
+            problems.push("duration_secs must be greater than 0".to_string());
+        }
+        if self.block_time_secs == 0 {
+            problems.push("block_time_secs must be greater than 0".to_string());
+        }
+        if !self.roles().contains(&NodeRole::Validator) {
+            problems.push("nodes must include at least one validator".to_string());
+        }
+
+        match &self.network.topology {
+            Topology::Star { hub } if *hub >= nodes => {
+                problems.push(format!(
+                    "network.topology.hub {hub} is not a node (have {nodes})"
+                ));
+            }
+            Topology::Explicit { links } => {
+                for (a, b) in links {
+                    if *a >= nodes || *b >= nodes {
+                        problems.push(format!(
+                            "network.topology.links ({a}, {b}) names a node >= {nodes}"
+                        ));
+                    }
+                }
+            }
+            _ => {}
+        }
+        if let Some(matrix) = &self.network.latency_matrix_ms
+            && (matrix.len() != nodes || matrix.iter().any(|row| row.len() != nodes))
+        {
+            problems.push(format!(
+                "network.latency_matrix_ms must be {nodes}x{nodes}, one row and column per node"
+            ));
+        }
+
+        for (i, partition) in self.network.partitions.iter().enumerate() {
+            if partition.end_secs <= partition.start_secs {
+                problems.push(format!("network.partitions[{i}] must end after it starts"));
+            }
+            if let Some(node) = partition.groups.iter().flatten().find(|n| **n >= nodes) {
+                problems.push(format!(
+                    "network.partitions[{i}] names node {node}, have {nodes}"
+                ));
+            }
+        }
+
+        let rates = [
+            ("network.drop_rate", self.network.drop_rate),
+            ("ml_verifier.reject_rate", self.ml_verifier.reject_rate),
+            ("ml_verifier.error_rate", self.ml_verifier.error_rate),
+        ];
+        for (field, rate) in rates {
+            if !(0.0..=1.0).contains(&rate) {
+                problems.push(format!("{field} must be between 0 and 1 (got {rate})"));
+            }
+        }
+
+        let workload = &self.workload;
+        let tx_rates = [
+            ("registrations_per_sec", workload.registrations_per_sec),
+            ("uses_per_sec", workload.uses_per_sec),
+            ("transfers_per_sec", workload.transfers_per_sec),
+        ];
+        for (field, rate) in tx_rates {
+            if !rate.is_finite() || rate < 0.0 {
+                problems.push(format!(
+                    "workload.{field} must be a non-negative number (got {rate})"
+                ));
+            }
+        }
+        if workload.accounts == 0 {
+            problems.push("workload.accounts must be greater than 0".to_string());
+        }
+        for assertion in &self.assertions {
+            if !assertion.value.is_finite() {
+                problems.push(format!(
+                    "assertion {assertion} must compare with a finite value"
+                ));
+            }
+        }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(ConfigError::Invalid(problems))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sim::report::Metric;
+
+    fn write_scenario(name: &str, contents: &str) -> (tempfile::TempDir, std::path::PathBuf) {
+        let dir = tempfile::tempdir().expect("create temp dir");
+        let path = dir.path().join(name);
+        std::fs::write(&path, contents).expect("write scenario file");
+        (dir, path)
+    }
+
+    #[test]
+    fn baseline_scenario_file_parses() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("../expts/scenarios/baseline.toml");
+        let scenario = Scenario::from_file(&path).expect("baseline.toml should load");
+        assert_eq!(scenario.name, "baseline");
+        assert!(scenario.node_count() > 0);
+    }
+
+    #[test]
+    fn toml_and_json_describe_the_same_scenario() {
+        let (_dir, toml_path) = write_scenario(
+            "ring.toml",
+            r#"
+                name = "ring"
+                seed = 7
+
+                [[nodes]]
+                role = "validator"
+                count = 2
+
+                [[nodes]]
+                role = "full"
+                count = 1
+
+                [network]
+                topology = { kind = "ring" }
+                latency_matrix_ms = [[0, 10, 80], [10, 0, 30], [80, 30, 0]]
+
+                [ml_verifier]
+                reject_rate = 0.1
+
+                [[assertions]]
+                metric = "max_fork_depth"
+                op = "<="
+                value = 2
+            "#,
+        );
+        let (_dir, json_path) = write_scenario(
+            "ring.json",
+            r#"{
+                "name": "ring",
+                "seed": 7,
+                "nodes": [
+                    { "role": "validator", "count": 2 },
+                    { "role": "full", "count": 1 }
+                ],
+                "network": {
+                    "topology": { "kind": "ring" },
+                    "latency_matrix_ms": [[0, 10, 80], [10, 0, 30], [80, 30, 0]]
+                },
+                "ml_verifier": { "reject_rate": 0.1 },
+                "assertions": [{ "metric": "max_fork_depth", "op": "<=", "value": 2 }]
+            }"#,
+        );
+
+        for path in [toml_path, json_path] {
+            let scenario = Scenario::from_file(&path).expect("scenario loads");
+            assert_eq!(
+                scenario.roles(),
+                [NodeRole::Validator, NodeRole::Validator, NodeRole::Full]
+            );
+            assert_eq!(
+                scenario.network.topology.links(scenario.node_count()),
+                [(0, 1), (0, 2), (1, 2)]
+            );
+            assert_eq!(scenario.network.latency(0, 2), 80);
+            assert_eq!(scenario.ml_verifier.reject_rate, 0.1);
+            assert_eq!(scenario.duration_secs, 60);
+            assert_eq!(scenario.assertions[0].metric, Metric::MaxForkDepth);
+        }
+    }
+
+    #[test]
+    fn topologies_produce_normalised_links() {
+        assert_eq!(Topology::FullMesh.links(3), [(0, 1), (0, 2), (1, 2)]);
+        assert_eq!(Topology::Ring.links(2), [(0, 1)]);
+        assert_eq!(Topology::Star { hub: 1 }.links(3), [(0, 1), (1, 2)]);
+        let explicit = Topology::Explicit {
+            links: vec![(2, 0), (0, 2), (1, 1)],
+        };
+        assert_eq!(explicit.links(3), [(0, 2)]);
+    }
+
+    #[test]
+    fn partitions_separate_groups_only_while_active() {
+        let partition = PartitionSpec {
+            start_secs: 10,
+            end_secs: 20,
+            groups: vec![vec![0, 1]],
+        };
+        assert!(!partition.separates(0, 1, 15_000));
+        assert!(partition.separates(1, 2, 15_000));
+        // Unlisted nodes share a group.
+        assert!(!partition.separates(2, 3, 15_000));
+        assert!(!partition.separates(1, 2, 9_999));
+        assert!(!partition.separates(1, 2, 20_000));
+    }
+
+    #[test]
+    fn validate_reports_every_problem() {
+        let (_dir, path) = write_scenario(
+            "bad.toml",
+            r#"
+                name = "bad"
+                duration_secs = 0
+
+                [[nodes]]
+                role = "full"
+                count = 2
+
+                [network]
+                topology = { kind = "star", hub = 5 }
+                latency_matrix_ms = [[0]]
+                drop_rate = 1.5
+            "#,
+        );
+        match Scenario::from_file(&path) {
+            Err(ConfigError::Invalid(problems)) => assert_eq!(problems.len(), 5, "{problems:?}"),
+            other => panic!("expected Invalid, got {other:?}"),
+        }
+
+        let (_dir, path) = write_scenario("typo.toml", "name = \"x\"\nduration = 5\n");
+        assert!(matches!(
+            Scenario::from_file(&path),
+            Err(ConfigError::Parse { .. })
+        ));
+    }
+}
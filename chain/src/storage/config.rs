@@ -0,0 +1,87 @@
+//! Storage configuration shared by the persistent backends.
+
+use std::str::FromStr;
+
+use serde::Deserialize;
+
+use crate::consensus::prune::PruningConfig;
+
+/// Persistent block store implementation.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StorageBackend {
+    /// RocksDB ([`RocksDbBlockStore`](super::RocksDbBlockStore)); `path`
+    /// is the database directory.
+    #[default]
+    RocksDb,
+    /// SQLite (`SqliteBlockStore`); `path` is the database file. Requires
+    /// the `sqlite` feature.
+    Sqlite,
+}
+
+impl FromStr for StorageBackend {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "rocksdb" => Ok(StorageBackend::RocksDb),
+            "sqlite" => Ok(StorageBackend::Sqlite),
+            _ => Err("expected \"rocksdb\" or \"sqlite\"".to_string()),
+        }
+    }
+}
+
+/// Configuration for the persistent block store.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StorageConfig {
+    /// Backend holding the blocks.
+    pub backend: StorageBackend,
+    /// Filesystem path to the database: a directory for RocksDB, a file
+    /// for SQLite.
+    pub path: String,
+    /// Whether to create the database and missing column families (or
+    /// tables) if they do not yet exist.
+    pub create_if_missing: bool,
+    /// History pruning policy. Disabled by default, i.e. every block is
+    /// kept.
+    pub pruning: PruningConfig,
+    /// Export of backend internals as Prometheus gauges.
+    pub metrics: StorageMetricsConfig,
+}
+
+impl Default for StorageConfig {
+    fn default() -> Self {
+        Self {
+            backend: StorageBackend::RocksDb,
+            path: "data/chain-db".to_string(),
+            create_if_missing: true,
+            pruning: PruningConfig::default(),
+            metrics: StorageMetricsConfig::default(),
+        }
+    }
+}
+
+/// Configuration for the storage metrics collector.
+///
+/// Only RocksDB reports internals; the collector does nothing for other
+/// backends.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StorageMetricsConfig {
+    /// Whether RocksDB keeps statistics (block cache hits and misses) and
+    /// a background task publishes them with the per-column-family
+    /// properties. Statistics cost some read and write throughput.
+    pub enabled: bool,
+    /// Interval between collections, in seconds.
+    pub interval_secs: u64,
+}
+
+impl Default for StorageMetricsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            interval_secs: 15,
+        }
+    }
+}
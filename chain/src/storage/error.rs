@@ -0,0 +1,54 @@
+//! Storage-level error type shared by all [`BlockStore`] backends.
+//!
+//! [`BlockStore`]: crate::consensus::store::BlockStore
+
+use std::fmt;
+
+use crate::types::BlockHash;
+
+/// Storage-level error type.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum StorageError {
+    /// Underlying RocksDB error.
+    RocksDb(rocksdb::Error),
+    /// Underlying SQLite error.
+    Sqlite(String),
+    /// Required column family was not found.
+    MissingColumnFamily(&'static str),
+    /// Corrupted or malformed metadata (e.g. tip hash with wrong length).
+    CorruptedMeta(&'static str),
+    /// A stored block could not be decoded.
+    CorruptedBlock(BlockHash),
+    /// The backend does not implement the requested operation.
+    Unsupported(&'static str),
+}
+
+impl From<rocksdb::Error> for StorageError {
+    fn from(e: rocksdb::Error) -> Self {
+        StorageError::RocksDb(e)
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl From<rusqlite::Error> for StorageError {
+    fn from(e: rusqlite::Error) -> Self {
+        StorageError::Sqlite(e.to_string())
+    }
+}
+
+impl fmt::Display for StorageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StorageError::RocksDb(e) => write!(f, "rocksdb: {e}"),
+            StorageError::Sqlite(e) => write!(f, "sqlite: {e}"),
+            StorageError::MissingColumnFamily(cf) => write!(f, "missing column family '{cf}'"),
+            StorageError::CorruptedMeta(what) => write!(f, "corrupted metadata: {what}"),
+            StorageError::CorruptedBlock(hash) => {
+                write!(f, "corrupted block {}", hex::encode(hash.0.as_bytes()))
+            }
+            StorageError::Unsupported(what) => write!(f, "{what} is not supported by this store"),
+        }
+    }
+}
+
+impl std::error::Error for StorageError {}
@@ -0,0 +1,593 @@
+//! Portable chain export files.
+//!
+//! Unlike a [snapshot](super::snapshot), which captures a whole store to
+//! bootstrap a node, an export file holds a range of canonical blocks and
+//! is written and read as a stream. A recorded chain can be moved between
+//! machines and storage backends without holding it in memory, and
+//! appended to an existing chain a range at a time.
+//!
+//! The layout is CAR-like:
+//!
+//! - the 8-byte magic `MLCHAIN` plus a format version,
+//! - one frame holding the bincode 2 encoding of a [`ChainFileHeader`]:
+//!   the chain id, the genesis hash, the exported height range, and the
+//!   hash of its last block,
+//! - one frame per block in height order, holding its canonical bytes.
+//!
+//! A frame is a 4-byte big-endian length followed by that many bytes, as
+//! in the gateway's `GET /export/blocks?format=binary`.
+//!
+//! [`import_chain`] checks the header against the importing node's chain
+//! id and genesis, and every block's height, parent link, and
+//! transactions root, so a truncated, reordered, or edited file is
+//! rejected at the first bad block. It then executes every block it
+//! writes and rejects one whose state or receipts root differs from its
+//! header, so the imported chain can be replayed by the engine.
+
+use std::fmt;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+
+use serde::{Deserialize, Serialize};
+
+use crate::consensus::store::BlockStore;
+use crate::execution::{
+    ChainState, ExecutionError, apply_block_with_receipts, receipts_root, replay_chain,
+};
+use crate::types::{Block, BlockHash};
+
+use super::StorageError;
+
+/// File magic followed by the format version.
+const MAGIC: &[u8; 8] = b"MLCHAIN\x01";
+
+/// Largest frame [`import_chain`] reads, so a corrupted length cannot
+/// make it allocate without bound.
+const MAX_FRAME_BYTES: usize = 64 << 20;
+
+/// Errors returned by chain export and import.
+#[derive(Debug)]
+pub enum ChainFileError {
+    /// Reading or writing the file failed.
+    Io(io::Error),
+    /// The block store failed.
+    Storage(StorageError),
+    /// The file is not a chain export, or it is truncated or corrupted.
+    Format(String),
+    /// The requested height range is empty or not fully stored.
+    Range(String),
+    /// The file or the store belongs to another chain.
+    WrongChain(String),
+    /// A block does not extend the chain below it.
+    Linkage { height: u64, reason: String },
+    /// A block fails to execute, or executes to other roots than its
+    /// header's.
+    Execution { height: u64, reason: String },
+}
+
+impl From<io::Error> for ChainFileError {
+    fn from(e: io::Error) -> Self {
+        ChainFileError::Io(e)
+    }
+}
+
+impl From<StorageError> for ChainFileError {
+    fn from(e: StorageError) -> Self {
+        ChainFileError::Storage(e)
+    }
+}
+
+impl fmt::Display for ChainFileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChainFileError::Io(e) => write!(f, "chain file I/O: {e}"),
+            ChainFileError::Storage(e) => write!(f, "storage: {e}"),
+            ChainFileError::Format(msg) => write!(f, "invalid chain file: {msg}"),
+            ChainFileError::Range(msg) => write!(f, "invalid export range: {msg}"),
+            ChainFileError::WrongChain(msg) => write!(f, "wrong chain: {msg}"),
+            ChainFileError::Linkage { height, reason } => {
+                write!(f, "block at height {height} rejected: {reason}")
+            }
+            ChainFileError::Execution { height, reason } => {
+                write!(f, "block at height {height} does not execute: {reason}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ChainFileError {}
+
+/// Header of an export file.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct ChainFileHeader {
+    /// `genesis.chain_id` of the exporting node.
+    pub chain_id: String,
+    /// Hash of the chain's genesis block.
+    pub genesis: BlockHash,
+    /// Height of the first block in the file.
+    pub from: u64,
+    /// Height of the last block in the file.
+    pub to: u64,
+    /// Hash of the block at `to`.
+    pub last: BlockHash,
+}
+
+impl ChainFileHeader {
+    /// Returns the number of blocks in the file.
+    pub fn blocks(&self) -> u64 {
+        self.to - self.from + 1
+    }
+}
+
+/// Summary of an imported file.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ChainImportInfo {
+    /// Header of the imported file.
+    pub header: ChainFileHeader,
+    /// Blocks written to the store.
+    pub imported: u64,
+    /// Blocks the store already had at their height.
+    pub skipped: u64,
+}
+
+/// Writes the canonical blocks of `store` at heights `from..=to` to
+/// `writer`, under a header naming `chain_id` and `genesis`.
+///
+/// Blocks are read and written one at a time; `writer` is buffered
+/// internally. Fails before writing anything if the range ends above the
+/// tip or starts below pruned history.
+pub fn export_chain<S, W>(
+    store: &S,
+    chain_id: &str,
+    genesis: BlockHash,
+    from: u64,
+    to: u64,
+    writer: W,
+) -> Result<ChainFileHeader, ChainFileError>
+where
+    S: BlockStore + ?Sized,
+    W: Write,
+{
+    if from > to {
+        return Err(ChainFileError::Range(format!(
+            "first height {from} is above last height {to}"
+        )));
+    }
+    if store
+        .get_hash_at_height(0)?
+        .is_some_and(|hash| hash != genesis)
+    {
+        return Err(ChainFileError::WrongChain(format!(
+            "store genesis differs from {}",
+            genesis.0
+        )));
+    }
+    let Some(last) = store.get_hash_at_height(to)? else {
+        return Err(ChainFileError::Range(format!(
+            "height {to} is above the tip"
+        )));
+    };
+    if store.get_block_by_height(from)?.is_none() {
+        return Err(ChainFileError::Range(format!(
+            "block at height {from} is not stored; it may have been pruned"
+        )));
+    }
+    let header = ChainFileHeader {
+        chain_id: chain_id.to_string(),
+        genesis,
+        from,
+        to,
+        last,
+    };
+
+    let mut writer = BufWriter::new(writer);
+    writer.write_all(MAGIC)?;
+    let encoded = bincode::serde::encode_to_vec(&header, bincode::config::standard())
+        .expect("ChainFileHeader should always be serializable with bincode 2 + serde");
+    write_frame(&mut writer, &encoded)?;
+    let mut next = from;
+    for block in store.iter_range(from..to + 1) {
+        let block = block?;
+        if block.header.height != next {
+            break;
+        }
+        write_frame(&mut writer, &block.canonical_bytes())?;
+        next += 1;
+    }
+    if next <= to {
+        return Err(ChainFileError::Range(format!(
+            "no canonical block is stored at height {next}"
+        )));
+    }
+    writer.flush()?;
+
+    Ok(header)
+}
+
+/// Appends the blocks of the export file read from `reader` to `store`.
+///
+/// The file must be for `chain_id` and `genesis`, and its first block
+/// must be the genesis block or have its parent stored at the height
+/// below. Blocks the store already has at their height are skipped, so an
+/// interrupted import can be retried, and a file overlapping the stored
+/// chain can be imported to extend it. Every other block must extend the
+/// store's tip, and is executed on top of its parent's state before it is
+/// written with its receipts and made the new tip. That state is rebuilt
+/// from `genesis_state` by replaying the stored chain once, before the
+/// first block written.
+pub fn import_chain<S, R>(
+    store: &mut S,
+    chain_id: &str,
+    genesis: BlockHash,
+    genesis_state: &ChainState,
+    reader: R,
+) -> Result<ChainImportInfo, ChainFileError>
+where
+    S: BlockStore + ?Sized,
+    R: Read,
+{
+    let mut reader = BufReader::new(reader);
+    let header = read_header(&mut reader)?;
+    if header.chain_id != chain_id {
+        return Err(ChainFileError::WrongChain(format!(
+            "file is for chain {:?}, not {chain_id:?}",
+            header.chain_id
+        )));
+    }
+    if header.genesis != genesis {
+        return Err(ChainFileError::WrongChain(format!(
+            "file has genesis {}, not {}",
+            header.genesis.0, genesis.0
+        )));
+    }
+    if store
+        .get_hash_at_height(0)?
+        .is_some_and(|hash| hash != genesis)
+    {
+        return Err(ChainFileError::WrongChain(format!(
+            "store genesis differs from {}",
+            genesis.0
+        )));
+    }
+
+    let mut parent =
+        match header.from {
+            0 => None,
+            from => Some(store.get_hash_at_height(from - 1)?.ok_or_else(|| {
+                ChainFileError::Linkage {
+                    height: from,
+                    reason: format!("the store has no block at height {}", from - 1),
+                }
+            })?),
+        };
+    let mut info = ChainImportInfo {
+        header: header.clone(),
+        imported: 0,
+        skipped: 0,
+    };
+    // State after `parent`, once a block has to be executed on top of it.
+    let mut state = None;
+    for height in header.from..=header.to {
+        let rejected = |reason: &str| ChainFileError::Linkage {
+            height,
+            reason: reason.to_string(),
+        };
+        let frame = read_frame(&mut reader)?;
+        let block = Block::from_canonical_bytes(&frame).ok_or_else(|| {
+            ChainFileError::Format(format!("block at height {height} does not decode"))
+        })?;
+        let hash = block.compute_hash();
+
+        if block.header.height != height {
+            return Err(rejected(&format!(
+                "block claims height {}",
+                block.header.height
+            )));
+        }
+        match parent {
+            None if hash != genesis => return Err(rejected("not the genesis block")),
+            Some(parent) if block.header.parent != parent => {
+                return Err(rejected("parent is not the block below it"));
+            }
+            _ => {}
+        }
+        if !block.has_valid_txs_root() {
+            return Err(rejected("transactions do not match the header's txs_root"));
+        }
+        if height == header.to && hash != header.last {
+            return Err(rejected("last block does not match the file header"));
+        }
+
+        match store.get_hash_at_height(height)? {
+            Some(stored) if stored == hash => {
+                state = None;
+                info.skipped += 1;
+            }
+            Some(_) => {
+                return Err(rejected("the store has a different block at this height"));
+            }
+            None => {
+                if store.tip()? != parent {
+                    return Err(rejected("the block below it is not the store's tip"));
+                }
+                let parent_state = match (state.take(), parent) {
+                    (Some(state), _) => state,
+                    (None, None) => genesis_state.clone(),
+                    (None, Some(parent)) => replay_chain(&*store, &parent, genesis_state)
+                        .map_err(|e| not_executed(height, e))?,
+                };
+                let (post_state, receipts) = apply_block_with_receipts(&parent_state, &block)
+                    .map_err(|e| not_executed(height, e))?;
+                if post_state.state_root() != block.header.state_root {
+                    return Err(ChainFileError::Execution {
+                        height,
+                        reason: "state root does not match the header".to_string(),
+                    });
+                }
+                if receipts_root(&receipts) != block.header.receipts_root {
+                    return Err(ChainFileError::Execution {
+                        height,
+                        reason: "receipts root does not match the header".to_string(),
+                    });
+                }
+                store.put_executed_block(block.seal(), &receipts, &[], true)?;
+                state = Some(post_state);
+                info.imported += 1;
+            }
+        }
+        parent = Some(hash);
+    }
+
+    if reader.read(&mut [0u8])? != 0 {
+        return Err(ChainFileError::Format(
+            "trailing data after the last block".to_string(),
+        ));
+    }
+    Ok(info)
+}
+
+/// Reports a block that fails to execute, or a failed store read while
+/// replaying the chain below it.
+fn not_executed(height: u64, e: ExecutionError) -> ChainFileError {
+    match e {
+        ExecutionError::Storage(e) => ChainFileError::Storage(e),
+        e => ChainFileError::Execution {
+            height,
+            reason: e.to_string(),
+        },
+    }
+}
+
+/// Reads the magic and the header of an export file.
+fn read_header(reader: &mut impl Read) -> Result<ChainFileHeader, ChainFileError> {
+    let mut magic = [0u8; MAGIC.len()];
+    reader.read_exact(&mut magic).map_err(truncated)?;
+    if &magic != MAGIC {
+        return Err(ChainFileError::Format(
+            "unknown magic or version".to_string(),
+        ));
+    }
+    let frame = read_frame(reader)?;
+    let (header, _): (ChainFileHeader, usize) =
+        bincode::serde::decode_from_slice(&frame, bincode::config::standard())
+            .map_err(|e| ChainFileError::Format(format!("header: {e}")))?;
+    if header.from > header.to {
+        return Err(ChainFileError::Format(format!(
+            "header range {}..={} is empty",
+            header.from, header.to
+        )));
+    }
+    Ok(header)
+}
+
+fn write_frame(writer: &mut impl Write, bytes: &[u8]) -> Result<(), ChainFileError> {
+    let len = u32::try_from(bytes.len())
+        .map_err(|_| ChainFileError::Format(format!("{}-byte frame is too large", bytes.len())))?;
+    writer.write_all(&len.to_be_bytes())?;
+    writer.write_all(bytes)?;
+    Ok(())
+}
+
+fn read_frame(reader: &mut impl Read) -> Result<Vec<u8>, ChainFileError> {
+    let mut len = [0u8; 4];
+    reader.read_exact(&mut len).map_err(truncated)?;
+    let len = u32::from_be_bytes(len) as usize;
+    if len > MAX_FRAME_BYTES {
+        return Err(ChainFileError::Format(format!(
+            "{len}-byte frame exceeds the {MAX_FRAME_BYTES}-byte limit"
+        )));
+    }
+    let mut frame = vec![0u8; len];
+    reader.read_exact(&mut frame).map_err(truncated)?;
+    Ok(frame)
+}
+
+/// Reports an unexpected end of file as a truncated file.
+fn truncated(e: io::Error) -> ChainFileError {
+    if e.kind() == io::ErrorKind::UnexpectedEof {
+        ChainFileError::Format("file is truncated".to_string())
+    } else {
+        ChainFileError::Io(e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::{InMemoryBlockStore, RocksDbBlockStore, StorageConfig};
+    use crate::types::{AccountId, HASH_LEN, Hash256, Header};
+    use tempfile::TempDir;
+
+    const CHAIN_ID: &str = "export-test";
+
+    /// Returns an empty block at `height` on top of `parent`, with the
+    /// roots it executes to from an empty genesis state.
+    fn empty_block(parent: BlockHash, height: u64) -> Block {
+        let mut block = Block {
+            header: Header {
+                parent,
+                height,
+                timestamp: 1_700_000_000 + height,
+                proposer: AccountId(Hash256([1u8; HASH_LEN])),
+                txs_root: Hash256([0u8; HASH_LEN]),
+                state_root: Hash256([0u8; HASH_LEN]),
+                receipts_root: Hash256([0u8; HASH_LEN]),
+                pos_proof: None,
+            },
+            txs: Vec::new(),
+        };
+        block.header.txs_root = block.compute_txs_root();
+        let (state, receipts) = apply_block_with_receipts(&ChainState::new(), &block).unwrap();
+        block.header.state_root = state.state_root();
+        block.header.receipts_root = receipts_root(&receipts);
+        block
+    }
+
+    /// Stores a chain of `len` empty blocks and returns its hashes.
+    fn chain(store: &mut InMemoryBlockStore, len: u64) -> Vec<BlockHash> {
+        let mut hashes = Vec::new();
+        let mut parent = BlockHash(Hash256([0u8; HASH_LEN]));
+        for height in 0..len {
+            let block = empty_block(parent, height);
+            parent = block.compute_hash();
+            store.put_block_and_set_tip(block).unwrap();
+            hashes.push(parent);
+        }
+        hashes
+    }
+
+    fn import(
+        store: &mut impl BlockStore,
+        chain_id: &str,
+        genesis: BlockHash,
+        bytes: &[u8],
+    ) -> Result<ChainImportInfo, ChainFileError> {
+        import_chain(store, chain_id, genesis, &ChainState::new(), bytes)
+    }
+
+    fn export(store: &InMemoryBlockStore, genesis: BlockHash, from: u64, to: u64) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        export_chain(store, CHAIN_ID, genesis, from, to, &mut bytes).unwrap();
+        bytes
+    }
+
+    #[test]
+    fn export_round_trips_into_rocksdb_in_ranges() {
+        let tmp = TempDir::new().expect("create temp dir");
+        let mut source = InMemoryBlockStore::new();
+        let hashes = chain(&mut source, 6);
+        let genesis = hashes[0];
+
+        let cfg = StorageConfig {
+            path: tmp.path().join("db").to_string_lossy().to_string(),
+            ..StorageConfig::default()
+        };
+        let mut target = RocksDbBlockStore::open(&cfg).expect("open RocksDB");
+
+        let first = export(&source, genesis, 0, 3);
+        let info = import(&mut target, CHAIN_ID, genesis, &first).unwrap();
+        assert_eq!((info.imported, info.skipped), (4, 0));
+        assert_eq!(info.header.last, hashes[3]);
+        assert_eq!(target.tip().unwrap(), Some(hashes[3]));
+
+        // Overlaps the blocks already imported, then extends the tip.
+        let second = export(&source, genesis, 2, 5);
+        let info = import(&mut target, CHAIN_ID, genesis, &second).unwrap();
+        assert_eq!((info.imported, info.skipped), (2, 2));
+        assert_eq!(target.tip().unwrap(), Some(hashes[5]));
+        assert_eq!(target.get_hash_at_height(4).unwrap(), Some(hashes[4]));
+    }
+
+    #[test]
+    fn import_rejects_other_chains_and_gaps() {
+        let mut source = InMemoryBlockStore::new();
+        let hashes = chain(&mut source, 4);
+        let genesis = hashes[0];
+        let bytes = export(&source, genesis, 2, 3);
+
+        let mut target = InMemoryBlockStore::new();
+        let err = import(&mut target, "other-net", genesis, &bytes).unwrap_err();
+        assert!(matches!(err, ChainFileError::WrongChain(_)), "{err}");
+
+        let err = import(&mut target, CHAIN_ID, genesis, &bytes).unwrap_err();
+        assert!(
+            matches!(err, ChainFileError::Linkage { height: 2, .. }),
+            "{err}"
+        );
+        assert!(target.is_empty());
+
+        let err = export_chain(&source, CHAIN_ID, genesis, 2, 9, Vec::new()).unwrap_err();
+        assert!(matches!(err, ChainFileError::Range(_)), "{err}");
+    }
+
+    #[test]
+    fn import_rejects_edited_and_truncated_files() {
+        let mut source = InMemoryBlockStore::new();
+        let hashes = chain(&mut source, 3);
+        let genesis = hashes[0];
+        let bytes = export(&source, genesis, 0, 2);
+
+        // The last block ends with its proposer, four roots, `pos_proof`
+        // and the transaction count; flip a byte of the proposer. Its
+        // parent link still holds, but its hash no longer matches.
+        let mut edited = bytes.clone();
+        let proposer = edited.len() - 2 - HASH_LEN * 3 - 1;
+        edited[proposer] ^= 0x01;
+        let mut target = InMemoryBlockStore::new();
+        let err = import(&mut target, CHAIN_ID, genesis, &edited).unwrap_err();
+        assert!(
+            matches!(err, ChainFileError::Linkage { height: 2, .. }),
+            "{err}"
+        );
+
+        let mut target = InMemoryBlockStore::new();
+        let cut = &bytes[..bytes.len() - 1];
+        let err = import(&mut target, CHAIN_ID, genesis, cut).unwrap_err();
+        assert!(matches!(err, ChainFileError::Format(_)), "{err}");
+        assert_eq!(target.tip().unwrap(), Some(hashes[1]));
+    }
+
+    #[test]
+    fn import_executes_blocks_and_rejects_wrong_state_roots() {
+        let mut source = InMemoryBlockStore::new();
+        let hashes = chain(&mut source, 2);
+        let genesis = hashes[0];
+        let mut block = empty_block(hashes[1], 2);
+        block.header.state_root = Hash256([7u8; HASH_LEN]);
+        source.put_block_and_set_tip(block).unwrap();
+        let bytes = export(&source, genesis, 0, 2);
+
+        let mut target = InMemoryBlockStore::new();
+        let err = import(&mut target, CHAIN_ID, genesis, &bytes).unwrap_err();
+        assert!(
+            matches!(err, ChainFileError::Execution { height: 2, .. }),
+            "{err}"
+        );
+        assert_eq!(target.tip().unwrap(), Some(hashes[1]));
+        assert_eq!(target.get_receipts(&hashes[1]).unwrap(), Some(Vec::new()));
+    }
+
+    #[test]
+    fn import_requires_the_parent_to_be_the_tip() {
+        let mut source = InMemoryBlockStore::new();
+        let hashes = chain(&mut source, 4);
+        let genesis = hashes[0];
+
+        let mut target = InMemoryBlockStore::new();
+        import(
+            &mut target,
+            CHAIN_ID,
+            genesis,
+            &export(&source, genesis, 0, 1),
+        )
+        .unwrap();
+        target.set_tip(BlockHash(Hash256([9u8; HASH_LEN]))).unwrap();
+
+        let bytes = export(&source, genesis, 2, 3);
+        let err = import(&mut target, CHAIN_ID, genesis, &bytes).unwrap_err();
+        assert!(
+            matches!(err, ChainFileError::Linkage { height: 2, .. }),
+            "{err}"
+        );
+        assert_eq!(target.get_hash_at_height(2).unwrap(), None);
+    }
+}
@@ -2,18 +2,31 @@
 //!
 //! This implementation is useful for unit tests, benchmarks, and small
 //! devnets. It keeps all blocks in a `HashMap` keyed by `BlockHash` and
-//! tracks the current tip separately.
+//! tracks the current tip, a height -> hash index of the canonical chain
+//! and the receipts and ML verdict statistics of each block separately.
+//! The index is kept sorted, so [`BlockStore::iter_range`] reads it as a
+//! range.
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::ops::Range;
 
-use crate::consensus::store::BlockStore;
-use crate::types::{Block, BlockHash};
+use crate::consensus::store::{
+    BlockIter, BlockStore, Checkpoint, height_index_updates, indexed_block,
+};
+use crate::execution::{MlVerdictStats, Receipt};
+use crate::types::{Block, BlockHash, SealedBlock};
+
+use super::StorageError;
 
 /// In-memory implementation of [`BlockStore`].
 #[derive(Default)]
 pub struct InMemoryBlockStore {
     blocks: HashMap<BlockHash, Block>,
     tip: Option<BlockHash>,
+    heights: BTreeMap<u64, BlockHash>,
+    receipts: HashMap<BlockHash, Vec<Receipt>>,
+    ml_stats: HashMap<BlockHash, Vec<MlVerdictStats>>,
+    checkpoint: Option<Checkpoint>,
 }
 
 impl InMemoryBlockStore {
@@ -34,21 +47,111 @@ impl InMemoryBlockStore {
 }
 
 impl BlockStore for InMemoryBlockStore {
-    fn get_block(&self, hash: &BlockHash) -> Option<Block> {
-        self.blocks.get(hash).cloned()
+    fn get_block(&self, hash: &BlockHash) -> Result<Option<Block>, StorageError> {
+        Ok(self.blocks.get(hash).cloned())
     }
 
-    fn put_block(&mut self, block: Block) {
+    fn put_block(&mut self, block: Block) -> Result<(), StorageError> {
         let hash = block.compute_hash();
         self.blocks.insert(hash, block);
+        Ok(())
+    }
+
+    fn put_sealed_block(&mut self, block: SealedBlock) -> Result<(), StorageError> {
+        self.blocks.insert(block.hash(), block.into_block());
+        Ok(())
     }
 
-    fn tip(&self) -> Option<BlockHash> {
-        self.tip
+    fn put_sealed_block_and_set_tip(&mut self, block: SealedBlock) -> Result<(), StorageError> {
+        let hash = block.hash();
+        self.put_sealed_block(block)?;
+        self.set_tip(hash)
     }
 
-    fn set_tip(&mut self, hash: BlockHash) {
+    fn tip(&self) -> Result<Option<BlockHash>, StorageError> {
+        Ok(self.tip)
+    }
+
+    fn set_tip(&mut self, hash: BlockHash) -> Result<(), StorageError> {
+        if let Some(block) = self.blocks.get(&hash) {
+            self.heights.split_off(&(block.header.height + 1));
+            for (height, h) in height_index_updates(self, &hash)? {
+                self.heights.insert(height, h);
+            }
+        }
         self.tip = Some(hash);
+        Ok(())
+    }
+
+    fn get_hash_at_height(&self, height: u64) -> Result<Option<BlockHash>, StorageError> {
+        Ok(self.heights.get(&height).copied())
+    }
+
+    fn iter_range(&self, range: Range<u64>) -> BlockIter<'_> {
+        if range.is_empty() {
+            return Box::new(std::iter::empty());
+        }
+        Box::new(
+            self.heights
+                .range(range)
+                .map(move |(_, hash)| indexed_block(self, hash)),
+        )
+    }
+
+    fn put_executed_block(
+        &mut self,
+        block: SealedBlock,
+        receipts: &[Receipt],
+        ml_stats: &[MlVerdictStats],
+        set_tip: bool,
+    ) -> Result<(), StorageError> {
+        let hash = block.hash();
+        self.receipts.insert(hash, receipts.to_vec());
+        self.ml_stats.insert(hash, ml_stats.to_vec());
+        if set_tip {
+            self.put_sealed_block_and_set_tip(block)
+        } else {
+            self.put_sealed_block(block)
+        }
+    }
+
+    fn get_receipts(&self, hash: &BlockHash) -> Result<Option<Vec<Receipt>>, StorageError> {
+        Ok(self.receipts.get(hash).cloned())
+    }
+
+    fn get_ml_stats(&self, hash: &BlockHash) -> Result<Option<Vec<MlVerdictStats>>, StorageError> {
+        Ok(self.ml_stats.get(hash).cloned())
+    }
+
+    fn block_hashes(&self) -> Result<Vec<BlockHash>, StorageError> {
+        Ok(self.blocks.keys().copied().collect())
+    }
+
+    fn delete_block(&mut self, hash: &BlockHash) -> Result<(), StorageError> {
+        self.receipts.remove(hash);
+        self.ml_stats.remove(hash);
+        if let Some(block) = self.blocks.remove(hash)
+            && self.heights.get(&block.header.height) == Some(hash)
+        {
+            self.heights.remove(&block.header.height);
+        }
+        Ok(())
+    }
+
+    fn checkpoint(&self) -> Result<Option<Checkpoint>, StorageError> {
+        Ok(self.checkpoint.clone())
+    }
+
+    fn prune_to(
+        &mut self,
+        checkpoint: Checkpoint,
+        pruned: &[BlockHash],
+    ) -> Result<(), StorageError> {
+        self.checkpoint = Some(checkpoint);
+        for hash in pruned {
+            self.delete_block(hash)?;
+        }
+        Ok(())
     }
 }
 
@@ -62,13 +165,20 @@ mod tests {
     }
 
     fn dummy_block(height: u64) -> Block {
+        child_of(BlockHash(dummy_hash(0)), height, 0)
+    }
+
+    fn child_of(parent: BlockHash, height: u64, salt: u64) -> Block {
         use crate::types::{AccountId, Block};
 
         let header = Header {
-            parent: BlockHash(dummy_hash(0)),
+            parent,
             height,
-            timestamp: 1_700_000_000 + height,
+            timestamp: 1_700_000_000 + height + salt,
             proposer: AccountId(dummy_hash(1)),
+            txs_root: Hash256([0u8; HASH_LEN]),
+            state_root: Hash256([0u8; HASH_LEN]),
+            receipts_root: Hash256([0u8; HASH_LEN]),
             pos_proof: None,
         };
 
@@ -84,8 +194,11 @@ mod tests {
         let block = dummy_block(0);
         let hash = block.compute_hash();
 
-        store.put_block(block.clone());
-        let fetched = store.get_block(&hash).expect("block should be present");
+        store.put_block(block.clone()).unwrap();
+        let fetched = store
+            .get_block(&hash)
+            .unwrap()
+            .expect("block should be present");
 
         assert_eq!(fetched.header.height, 0);
         assert_eq!(store.len(), 1);
@@ -97,11 +210,80 @@ mod tests {
         let block = dummy_block(5);
         let hash = block.compute_hash();
 
-        store.put_block(block);
-        assert!(store.tip().is_none());
+        store.put_block(block).unwrap();
+        assert!(store.tip().unwrap().is_none());
 
-        store.set_tip(hash);
-        let tip = store.tip().expect("tip should be set");
+        store.set_tip(hash).unwrap();
+        let tip = store.tip().unwrap().expect("tip should be set");
         assert_eq!(tip.0.as_bytes(), hash.0.as_bytes());
     }
+
+    #[test]
+    fn height_index_follows_tip_across_reorg() {
+        let mut store = InMemoryBlockStore::new();
+
+        let b0 = dummy_block(0);
+        let h0 = b0.compute_hash();
+        let a1 = child_of(h0, 1, 0);
+        let a1_hash = a1.compute_hash();
+        let a2 = child_of(a1_hash, 2, 0);
+        let a2_hash = a2.compute_hash();
+        let b1 = child_of(h0, 1, 100);
+        let b1_hash = b1.compute_hash();
+        for block in [b0, a1, a2, b1] {
+            store.put_block(block).unwrap();
+        }
+
+        store.set_tip(a2_hash).unwrap();
+        assert_eq!(store.get_hash_at_height(0).unwrap(), Some(h0));
+        assert_eq!(store.get_hash_at_height(2).unwrap(), Some(a2_hash));
+        assert_eq!(
+            store
+                .get_block_by_height(1)
+                .unwrap()
+                .map(|b| b.compute_hash()),
+            Some(a1_hash)
+        );
+
+        // Reorg to the shorter branch: height 1 is rewritten, height 2 dropped.
+        store.set_tip(b1_hash).unwrap();
+        assert_eq!(store.get_hash_at_height(0).unwrap(), Some(h0));
+        assert_eq!(store.get_hash_at_height(1).unwrap(), Some(b1_hash));
+        assert!(store.get_hash_at_height(2).unwrap().is_none());
+    }
+
+    #[test]
+    fn iteration_follows_the_height_index_in_order() {
+        let mut store = InMemoryBlockStore::new();
+        let mut parent = BlockHash(dummy_hash(0));
+        let mut hashes = Vec::new();
+        for height in 0..5 {
+            let block = child_of(parent, height, 0);
+            parent = block.compute_hash();
+            store.put_block(block).unwrap();
+            hashes.push(parent);
+        }
+        // A side branch is stored but never canonical.
+        store.put_block(child_of(hashes[1], 2, 100)).unwrap();
+        store.set_tip(hashes[4]).unwrap();
+
+        let heights = |iter: BlockIter<'_>| -> Vec<u64> {
+            iter.map(|block| block.unwrap().header.height).collect()
+        };
+        assert_eq!(heights(store.iter_blocks()), vec![0, 1, 2, 3, 4]);
+        assert_eq!(heights(store.iter_range(1..3)), vec![1, 2]);
+        assert_eq!(heights(store.iter_range(3..100)), vec![3, 4]);
+        assert!(heights(store.iter_range(2..2)).is_empty());
+        assert_eq!(
+            store
+                .iter_range(2..3)
+                .map(|block| block.unwrap().compute_hash())
+                .collect::<Vec<_>>(),
+            vec![hashes[2]]
+        );
+
+        // Deleted (e.g. pruned) heights are skipped.
+        store.delete_block(&hashes[1]).unwrap();
+        assert_eq!(heights(store.iter_range(0..3)), vec![0, 2]);
+    }
 }
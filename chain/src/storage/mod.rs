@@ -5,10 +5,37 @@
 //!
 //! - an in-memory store ([`mem::InMemoryBlockStore`]) suitable for tests,
 //! - a RocksDB-backed store ([`rocksdb::RocksDbBlockStore`]) for persistent
-//!   validator nodes.
+//!   validator nodes,
+//! - a SQLite-backed store (`sqlite::SqliteBlockStore`, behind the `sqlite`
+//!   feature) with the same layout, for machines without a RocksDB build.
+//!
+//! [`PersistentStore`] opens whichever persistent backend
+//! [`StorageConfig::backend`] selects, and [`SharedStore`] lets the
+//! consensus engine and readers share one store.
+//!
+//! Both report failures as [`StorageError`]. Any store can be exported to
+//! and bootstrapped from a portable [`snapshot`], and ranges of its
+//! canonical chain can be streamed to and appended from an [`export`]
+//! file.
 
+pub mod config;
+pub mod error;
+pub mod export;
 pub mod mem;
+pub mod persistent;
 pub mod rocksdb;
+pub mod shared;
+pub mod snapshot;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
 
+pub use config::{StorageBackend, StorageConfig, StorageMetricsConfig};
+pub use error::StorageError;
+pub use export::{ChainFileError, ChainFileHeader, ChainImportInfo, export_chain, import_chain};
 pub use mem::InMemoryBlockStore;
-pub use rocksdb::{RocksDbBlockStore, RocksDbConfig, StorageError};
+pub use persistent::PersistentStore;
+pub use rocksdb::{ColumnFamilyStats, RocksDbBlockStore, RocksDbStats};
+pub use shared::SharedStore;
+pub use snapshot::{SnapshotError, SnapshotInfo, export_snapshot, import_snapshot};
+#[cfg(feature = "sqlite")]
+pub use sqlite::SqliteBlockStore;
@@ -0,0 +1,153 @@
+//! Persistent block store selected at runtime from [`StorageConfig`].
+
+use std::ops::Range;
+use std::path::Path;
+
+use crate::consensus::store::{BlockIter, BlockStore, Checkpoint};
+use crate::execution::{MlVerdictStats, Receipt};
+use crate::types::{Block, BlockHash, SealedBlock};
+
+#[cfg(feature = "sqlite")]
+use super::SqliteBlockStore;
+use super::snapshot::{self, SnapshotError, SnapshotInfo};
+use super::{RocksDbBlockStore, RocksDbStats, StorageBackend, StorageConfig, StorageError};
+
+/// Persistent block store, one variant per [`StorageBackend`].
+pub enum PersistentStore {
+    RocksDb(RocksDbBlockStore),
+    #[cfg(feature = "sqlite")]
+    Sqlite(SqliteBlockStore),
+}
+
+/// Evaluates `$body` with `$store` bound to the wrapped backend.
+macro_rules! with_store {
+    ($self:expr, $store:ident => $body:expr) => {
+        match $self {
+            PersistentStore::RocksDb($store) => $body,
+            #[cfg(feature = "sqlite")]
+            PersistentStore::Sqlite($store) => $body,
+        }
+    };
+}
+
+impl PersistentStore {
+    /// Opens the backend selected by `cfg.backend` at `cfg.path`.
+    pub fn open(cfg: &StorageConfig) -> Result<Self, StorageError> {
+        match cfg.backend {
+            StorageBackend::RocksDb => RocksDbBlockStore::open(cfg).map(PersistentStore::RocksDb),
+            #[cfg(feature = "sqlite")]
+            StorageBackend::Sqlite => SqliteBlockStore::open(cfg).map(PersistentStore::Sqlite),
+            #[cfg(not(feature = "sqlite"))]
+            StorageBackend::Sqlite => Err(StorageError::Unsupported(
+                "the sqlite backend without the sqlite feature",
+            )),
+        }
+    }
+
+    /// Writes a portable snapshot of this store to `path`.
+    ///
+    /// See [`snapshot::export_snapshot`].
+    pub fn export_snapshot(&self, path: impl AsRef<Path>) -> Result<SnapshotInfo, SnapshotError> {
+        snapshot::export_snapshot(self, path.as_ref())
+    }
+
+    /// Bootstraps this (empty) store from the snapshot at `path`.
+    ///
+    /// See [`snapshot::import_snapshot`].
+    pub fn import_snapshot(
+        &mut self,
+        path: impl AsRef<Path>,
+    ) -> Result<SnapshotInfo, SnapshotError> {
+        snapshot::import_snapshot(self, path.as_ref())
+    }
+
+    /// Returns RocksDB's internals (see [`RocksDbBlockStore::stats`]), or
+    /// `None` for other backends.
+    pub fn rocksdb_stats(&self) -> Result<Option<RocksDbStats>, StorageError> {
+        match self {
+            PersistentStore::RocksDb(store) => store.stats().map(Some),
+            #[cfg(feature = "sqlite")]
+            PersistentStore::Sqlite(_) => Ok(None),
+        }
+    }
+}
+
+impl BlockStore for PersistentStore {
+    fn get_block(&self, hash: &BlockHash) -> Result<Option<Block>, StorageError> {
+        with_store!(self, store => store.get_block(hash))
+    }
+
+    fn put_block(&mut self, block: Block) -> Result<(), StorageError> {
+        with_store!(self, store => store.put_block(block))
+    }
+
+    fn tip(&self) -> Result<Option<BlockHash>, StorageError> {
+        with_store!(self, store => store.tip())
+    }
+
+    fn set_tip(&mut self, hash: BlockHash) -> Result<(), StorageError> {
+        with_store!(self, store => store.set_tip(hash))
+    }
+
+    fn put_block_and_set_tip(&mut self, block: Block) -> Result<(), StorageError> {
+        with_store!(self, store => store.put_block_and_set_tip(block))
+    }
+
+    fn put_sealed_block(&mut self, block: SealedBlock) -> Result<(), StorageError> {
+        with_store!(self, store => store.put_sealed_block(block))
+    }
+
+    fn put_sealed_block_and_set_tip(&mut self, block: SealedBlock) -> Result<(), StorageError> {
+        with_store!(self, store => store.put_sealed_block_and_set_tip(block))
+    }
+
+    fn get_hash_at_height(&self, height: u64) -> Result<Option<BlockHash>, StorageError> {
+        with_store!(self, store => store.get_hash_at_height(height))
+    }
+
+    fn put_executed_block(
+        &mut self,
+        block: SealedBlock,
+        receipts: &[Receipt],
+        ml_stats: &[MlVerdictStats],
+        set_tip: bool,
+    ) -> Result<(), StorageError> {
+        with_store!(self, store => store.put_executed_block(block, receipts, ml_stats, set_tip))
+    }
+
+    fn get_receipts(&self, hash: &BlockHash) -> Result<Option<Vec<Receipt>>, StorageError> {
+        with_store!(self, store => store.get_receipts(hash))
+    }
+
+    fn get_ml_stats(&self, hash: &BlockHash) -> Result<Option<Vec<MlVerdictStats>>, StorageError> {
+        with_store!(self, store => store.get_ml_stats(hash))
+    }
+
+    fn iter_range(&self, range: Range<u64>) -> BlockIter<'_> {
+        with_store!(self, store => store.iter_range(range))
+    }
+
+    fn block_hashes(&self) -> Result<Vec<BlockHash>, StorageError> {
+        with_store!(self, store => store.block_hashes())
+    }
+
+    fn delete_block(&mut self, hash: &BlockHash) -> Result<(), StorageError> {
+        with_store!(self, store => store.delete_block(hash))
+    }
+
+    fn checkpoint(&self) -> Result<Option<Checkpoint>, StorageError> {
+        with_store!(self, store => store.checkpoint())
+    }
+
+    fn prune_to(
+        &mut self,
+        checkpoint: Checkpoint,
+        pruned: &[BlockHash],
+    ) -> Result<(), StorageError> {
+        with_store!(self, store => store.prune_to(checkpoint, pruned))
+    }
+
+    fn flush(&mut self) -> Result<(), StorageError> {
+        with_store!(self, store => store.flush())
+    }
+}
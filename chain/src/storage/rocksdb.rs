@@ -3,79 +3,145 @@
 //! This implementation persists blocks and tip metadata in a RocksDB
 //! instance with dedicated column families:
 //!
-//! - `"blocks"`: maps `BlockHash` (32 bytes) -> canonical block bytes,
-//! - `"meta"`:   stores the current tip under a fixed key `"tip"`.
+//! - `"blocks"`:  maps `BlockHash` (32 bytes) -> canonical block bytes,
+//! - `"meta"`:    stores the current tip under a fixed key `"tip"` and the
+//!   pruning checkpoint, if any, under `"checkpoint"`,
+//! - `"heights"`: maps big-endian `u64` height -> `BlockHash` of the
+//!   canonical block at that height,
+//! - `"txs"`:     maps `TxHash` ++ `BlockHash` -> big-endian `u64` height
+//!   ++ big-endian `u32` index, one entry per stored block including the
+//!   transaction,
+//! - `"receipts"`: maps `BlockHash` -> the block's receipts, bincode-encoded
+//!   in transaction order,
+//! - `"ml_stats"`: maps `BlockHash` -> the ML verdict statistics of the
+//!   block's registrations, bincode-encoded in transaction order.
+//!
+//! Every mutation is staged in a single `WriteBatch`: a block's bytes and
+//! its transaction index entries land together, and
+//! [`put_block_and_set_tip`](BlockStore::put_block_and_set_tip) adds the
+//! tip and height index to the same batch, so a crash never leaves the tip
+//! pointing at a missing block or the indexes disagreeing with the stored
+//! blocks. [`put_executed_block`](BlockStore::put_executed_block) adds the
+//! block's receipts and ML verdict statistics as well. The same goes for a
+//! pruning pass: the new checkpoint and the deletions below it land
+//! together.
+//!
+//! [`RocksDbBlockStore::stats`] reads RocksDB's own view of the data: key
+//! estimates, SST sizes and pending compaction per column family, and
+//! block cache hits and misses when statistics are enabled with
+//! [`StorageMetricsConfig::enabled`](super::StorageMetricsConfig::enabled).
 
-use std::{path::Path, sync::Arc};
+use std::{ops::Range, path::Path, sync::Arc};
 
-use crate::consensus::store::BlockStore;
-use crate::types::{Block, BlockHash, HASH_LEN, Hash256};
+use crate::consensus::store::{
+    BlockIter, BlockStore, Checkpoint, height_index_updates, indexed_block,
+};
+use crate::consensus::tx_index::TxLocation;
+use crate::execution::{MlVerdictStats, Receipt};
+use crate::types::{Block, BlockHash, HASH_LEN, Hash256, Header, SealedBlock, TxHash};
 
-use rocksdb::{BoundColumnFamily, ColumnFamilyDescriptor, DB, Options};
+use super::snapshot::{self, SnapshotError, SnapshotInfo};
+use super::{StorageConfig, StorageError};
 
-/// Configuration for [`RocksDbBlockStore`].
-#[derive(Clone, Debug)]
-pub struct RocksDbConfig {
-    /// Filesystem path to the RocksDB database directory.
-    pub path: String,
-    /// Whether to create the database and missing column families if they
-    /// do not yet exist.
-    pub create_if_missing: bool,
-}
+use rocksdb::properties::{self, PropName};
+use rocksdb::statistics::Ticker;
+use rocksdb::{
+    BoundColumnFamily, ColumnFamilyDescriptor, DB, Direction, IteratorMode, Options, WriteBatch,
+};
 
-impl Default for RocksDbConfig {
-    fn default() -> Self {
-        Self {
-            path: "data/chain-db".to_string(),
-            create_if_missing: true,
-        }
-    }
+/// Column families of the store, in creation order.
+const COLUMN_FAMILIES: [&str; 7] = [
+    "default", "blocks", "meta", "heights", "txs", "receipts", "ml_stats",
+];
+
+/// RocksDB's figures for one column family; see [`RocksDbBlockStore::stats`].
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ColumnFamilyStats {
+    /// Column family name.
+    pub name: &'static str,
+    /// Estimated number of keys (`rocksdb.estimate-num-keys`).
+    pub estimated_keys: u64,
+    /// Total size of the SST files (`rocksdb.total-sst-files-size`).
+    pub sst_bytes: u64,
+    /// Bytes compaction still has to rewrite
+    /// (`rocksdb.estimate-pending-compaction-bytes`).
+    pub pending_compaction_bytes: u64,
 }
 
-/// Storage-level error type.
-#[derive(Debug)]
-pub enum StorageError {
-    /// Underlying RocksDB error.
-    RocksDb(rocksdb::Error),
-    /// Required column family was not found.
-    MissingColumnFamily(&'static str),
-    /// Corrupted or malformed metadata (e.g. tip hash with wrong length).
-    CorruptedMeta(&'static str),
+/// Snapshot of RocksDB internals, as returned by
+/// [`RocksDbBlockStore::stats`].
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct RocksDbStats {
+    /// One entry per column family.
+    pub column_families: Vec<ColumnFamilyStats>,
+    /// Block cache hits since the database was opened; `0` without
+    /// statistics.
+    pub block_cache_hits: u64,
+    /// Block cache misses since the database was opened; `0` without
+    /// statistics.
+    pub block_cache_misses: u64,
 }
 
-impl From<rocksdb::Error> for StorageError {
-    fn from(e: rocksdb::Error) -> Self {
-        StorageError::RocksDb(e)
+impl RocksDbStats {
+    /// Returns the share of block cache lookups that hit, or `None` before
+    /// the first lookup.
+    pub fn block_cache_hit_ratio(&self) -> Option<f64> {
+        let lookups = self.block_cache_hits + self.block_cache_misses;
+        (lookups > 0).then(|| self.block_cache_hits as f64 / lookups as f64)
     }
 }
 
 /// RocksDB-backed implementation of [`BlockStore`].
 pub struct RocksDbBlockStore {
     db: DB,
+    /// Options the database was opened with, kept while statistics are
+    /// enabled because the tickers are read through them.
+    statistics: Option<Options>,
+    /// Number of batches [`write`](Self::write) still commits before it
+    /// simulates a crash by dropping every later batch.
+    #[cfg(test)]
+    writes_left: std::sync::atomic::AtomicUsize,
 }
 
 impl RocksDbBlockStore {
     /// Opens (or creates) a RocksDB-backed block store at the given path.
     ///
-    /// This sets up the `"blocks"` and `"meta"` column families. The
-    /// `"default"` column family is also created to keep RocksDB happy,
-    /// but it is not currently used.
-    pub fn open(cfg: &RocksDbConfig) -> Result<Self, StorageError> {
+    /// This sets up the `"blocks"`, `"meta"`, `"heights"`, `"txs"`,
+    /// `"receipts"` and `"ml_stats"` column families. The `"default"`
+    /// column family is also created to keep RocksDB happy, but it is not
+    /// currently used.
+    ///
+    /// Databases created before the height or transaction index existed
+    /// are re-indexed on open.
+    pub fn open(cfg: &StorageConfig) -> Result<Self, StorageError> {
         let path = Path::new(&cfg.path);
 
         let mut opts = Options::default();
         opts.create_if_missing(cfg.create_if_missing);
         opts.create_missing_column_families(cfg.create_if_missing);
+        if cfg.metrics.enabled {
+            opts.enable_statistics();
+        }
 
-        let cfs = vec![
-            ColumnFamilyDescriptor::new("default", Options::default()),
-            ColumnFamilyDescriptor::new("blocks", Options::default()),
-            ColumnFamilyDescriptor::new("meta", Options::default()),
-        ];
+        let cfs: Vec<_> = COLUMN_FAMILIES
+            .iter()
+            .map(|name| ColumnFamilyDescriptor::new(*name, Options::default()))
+            .collect();
 
         let db = DB::open_cf_descriptors(&opts, path, cfs)?;
+        let store = Self {
+            db,
+            statistics: cfg.metrics.enabled.then_some(opts),
+            #[cfg(test)]
+            writes_left: std::sync::atomic::AtomicUsize::new(usize::MAX),
+        };
+
+        if let Some(tip) = store.load_tip()? {
+            store.store_tip(&tip)?;
+        }
+        store.index_legacy_txs()?;
 
-        Ok(Self { db })
+        Ok(store)
     }
 
     fn cf_blocks(&self) -> Result<Arc<BoundColumnFamily<'_>>, StorageError> {
@@ -90,9 +156,28 @@ impl RocksDbBlockStore {
             .ok_or(StorageError::MissingColumnFamily("meta"))
     }
 
-    /// Internal helper: encodes a block into canonical bytes (bincode 2).
-    fn encode_block(block: &Block) -> Vec<u8> {
-        block.canonical_bytes()
+    fn cf_heights(&self) -> Result<Arc<BoundColumnFamily<'_>>, StorageError> {
+        self.db
+            .cf_handle("heights")
+            .ok_or(StorageError::MissingColumnFamily("heights"))
+    }
+
+    fn cf_txs(&self) -> Result<Arc<BoundColumnFamily<'_>>, StorageError> {
+        self.db
+            .cf_handle("txs")
+            .ok_or(StorageError::MissingColumnFamily("txs"))
+    }
+
+    fn cf_receipts(&self) -> Result<Arc<BoundColumnFamily<'_>>, StorageError> {
+        self.db
+            .cf_handle("receipts")
+            .ok_or(StorageError::MissingColumnFamily("receipts"))
+    }
+
+    fn cf_ml_stats(&self) -> Result<Arc<BoundColumnFamily<'_>>, StorageError> {
+        self.db
+            .cf_handle("ml_stats")
+            .ok_or(StorageError::MissingColumnFamily("ml_stats"))
     }
 
     /// Internal helper: decodes a block from canonical bytes.
@@ -102,6 +187,103 @@ impl RocksDbBlockStore {
         Some(block)
     }
 
+    /// Internal helper: the `"txs"` key of transaction `tx` in `block`.
+    fn tx_key(tx: &TxHash, block: &BlockHash) -> Vec<u8> {
+        [tx.0.as_bytes().as_slice(), block.0.as_bytes()].concat()
+    }
+
+    /// Commits `batch` atomically.
+    ///
+    /// Every write goes through here, so tests can simulate a crash at any
+    /// point of a write sequence.
+    fn write(&self, batch: WriteBatch) -> Result<(), StorageError> {
+        #[cfg(test)]
+        {
+            use std::sync::atomic::Ordering;
+            let left = self.writes_left.load(Ordering::SeqCst);
+            if left == 0 {
+                return Err(StorageError::Unsupported("writing after a simulated crash"));
+            }
+            self.writes_left.store(left - 1, Ordering::SeqCst);
+        }
+        self.db.write(batch)?;
+        Ok(())
+    }
+
+    /// Stages `block`'s cached canonical bytes and its transaction index
+    /// entries.
+    fn stage_block(&self, batch: &mut WriteBatch, block: &SealedBlock) -> Result<(), StorageError> {
+        let cf = self.cf_blocks()?;
+        let cf_txs = self.cf_txs()?;
+        let hash = &block.hash();
+
+        batch.put_cf(&cf, hash.0.as_bytes(), block.bytes());
+        for (index, tx) in block.txs.iter().enumerate() {
+            let mut location = block.header.height.to_be_bytes().to_vec();
+            location.extend_from_slice(&(index as u32).to_be_bytes());
+            batch.put_cf(&cf_txs, Self::tx_key(&tx.compute_hash(), hash), location);
+        }
+        Ok(())
+    }
+
+    /// Stages the removal of the stored block `hash`, its receipts, ML
+    /// verdict statistics and transaction index entries, and its height
+    /// index entry if it is canonical.
+    fn stage_delete(&self, batch: &mut WriteBatch, hash: &BlockHash) -> Result<(), StorageError> {
+        let Some(block) = self.get_block(hash)? else {
+            return Ok(());
+        };
+        let cf = self.cf_blocks()?;
+        let cf_heights = self.cf_heights()?;
+        let cf_txs = self.cf_txs()?;
+        let cf_receipts = self.cf_receipts()?;
+        let cf_ml_stats = self.cf_ml_stats()?;
+
+        batch.delete_cf(&cf, hash.0.as_bytes());
+        batch.delete_cf(&cf_receipts, hash.0.as_bytes());
+        batch.delete_cf(&cf_ml_stats, hash.0.as_bytes());
+        for tx in &block.txs {
+            batch.delete_cf(&cf_txs, Self::tx_key(&tx.compute_hash(), hash));
+        }
+        if self.get_hash_at_height(block.header.height)? == Some(*hash) {
+            batch.delete_cf(&cf_heights, block.header.height.to_be_bytes());
+        }
+        Ok(())
+    }
+
+    /// Stages `hash` as the new tip together with the matching height
+    /// index.
+    ///
+    /// Index entries above the new tip's height are removed and entries
+    /// from the tip down to the fork point are rewritten. `header` is the
+    /// tip block's header, which need not be stored yet.
+    fn stage_tip(
+        &self,
+        batch: &mut WriteBatch,
+        hash: &BlockHash,
+        header: &Header,
+    ) -> Result<(), StorageError> {
+        let cf_meta = self.cf_meta()?;
+        let cf_heights = self.cf_heights()?;
+
+        batch.put_cf(&cf_meta, b"tip", hash.0.as_bytes());
+
+        let above = header.height.saturating_add(1);
+        batch.delete_range_cf(&cf_heights, above.to_be_bytes(), u64::MAX.to_be_bytes());
+        batch.delete_cf(&cf_heights, u64::MAX.to_be_bytes());
+
+        if self.get_hash_at_height(header.height)? == Some(*hash) {
+            return Ok(());
+        }
+        batch.put_cf(&cf_heights, header.height.to_be_bytes(), hash.0.as_bytes());
+        if header.height > 0 {
+            for (height, h) in height_index_updates(self, &header.parent)? {
+                batch.put_cf(&cf_heights, height.to_be_bytes(), h.0.as_bytes());
+            }
+        }
+        Ok(())
+    }
+
     /// Loads the current tip hash from the meta column family, if present.
     fn load_tip(&self) -> Result<Option<BlockHash>, StorageError> {
         let cf_meta = self.cf_meta()?;
@@ -118,57 +300,341 @@ impl RocksDbBlockStore {
         }
     }
 
-    /// Persists the tip hash into the meta column family.
+    /// Persists the tip hash and the matching height index atomically.
     fn store_tip(&self, hash: &BlockHash) -> Result<(), StorageError> {
+        let mut batch = WriteBatch::default();
+        match self.get_block(hash)? {
+            Some(block) => self.stage_tip(&mut batch, hash, &block.header)?,
+            None => batch.put_cf(&self.cf_meta()?, b"tip", hash.0.as_bytes()),
+        }
+        self.write(batch)
+    }
+
+    /// Indexes the transactions of every stored block if the database
+    /// predates the `"txs"` column family.
+    fn index_legacy_txs(&self) -> Result<(), StorageError> {
         let cf_meta = self.cf_meta()?;
-        let bytes = hash.0.as_bytes();
-        self.db.put_cf(&cf_meta, b"tip", bytes)?;
-        Ok(())
+        if self.db.get_cf(&cf_meta, b"tx_index")?.is_some() {
+            return Ok(());
+        }
+
+        let mut batch = WriteBatch::default();
+        for hash in self.block_hashes()? {
+            if let Some(block) = self.get_block(&hash)? {
+                self.stage_block(&mut batch, &block.seal())?;
+            }
+        }
+        batch.put_cf(&cf_meta, b"tx_index", [1u8]);
+        self.write(batch)
+    }
+
+    /// Loads the canonical hash at `height` from the heights column family.
+    fn load_hash_at_height(&self, height: u64) -> Result<Option<BlockHash>, StorageError> {
+        let cf_heights = self.cf_heights()?;
+        match self.db.get_cf(&cf_heights, height.to_be_bytes())? {
+            None => Ok(None),
+            Some(bytes) => {
+                let arr: [u8; HASH_LEN] = bytes
+                    .as_slice()
+                    .try_into()
+                    .map_err(|_| StorageError::CorruptedMeta("height index hash length"))?;
+                Ok(Some(BlockHash(Hash256(arr))))
+            }
+        }
+    }
+
+    /// Writes a portable snapshot of this store to `path`.
+    ///
+    /// See [`snapshot::export_snapshot`].
+    pub fn export_snapshot(&self, path: impl AsRef<Path>) -> Result<SnapshotInfo, SnapshotError> {
+        snapshot::export_snapshot(self, path.as_ref())
+    }
+
+    /// Bootstraps this (empty) store from the snapshot at `path`.
+    ///
+    /// See [`snapshot::import_snapshot`].
+    pub fn import_snapshot(
+        &mut self,
+        path: impl AsRef<Path>,
+    ) -> Result<SnapshotInfo, SnapshotError> {
+        snapshot::import_snapshot(self, path.as_ref())
+    }
+
+    /// Reads RocksDB's statistics and per-column-family properties.
+    ///
+    /// Properties RocksDB does not report are returned as `0`.
+    pub fn stats(&self) -> Result<RocksDbStats, StorageError> {
+        let column_families = COLUMN_FAMILIES
+            .iter()
+            .map(|&name| -> Result<ColumnFamilyStats, StorageError> {
+                let cf = self
+                    .db
+                    .cf_handle(name)
+                    .ok_or(StorageError::MissingColumnFamily(name))?;
+                let property = |property: &PropName| {
+                    self.db
+                        .property_int_value_cf(&cf, property)
+                        .map(Option::unwrap_or_default)
+                };
+                Ok(ColumnFamilyStats {
+                    name,
+                    estimated_keys: property(properties::ESTIMATE_NUM_KEYS)?,
+                    sst_bytes: property(properties::TOTAL_SST_FILES_SIZE)?,
+                    pending_compaction_bytes: property(
+                        properties::ESTIMATE_PENDING_COMPACTION_BYTES,
+                    )?,
+                })
+            })
+            .collect::<Result<_, _>>()?;
+        let ticker = |ticker: Ticker| {
+            self.statistics
+                .as_ref()
+                .map_or(0, |opts| opts.get_ticker_count(ticker))
+        };
+        Ok(RocksDbStats {
+            column_families,
+            block_cache_hits: ticker(Ticker::BlockCacheHit),
+            block_cache_misses: ticker(Ticker::BlockCacheMiss),
+        })
+    }
+
+    /// Returns every stored block that includes transaction `hash`,
+    /// canonical or not, in no particular order.
+    pub fn tx_locations(&self, hash: &TxHash) -> Result<Vec<TxLocation>, StorageError> {
+        let cf_txs = self.cf_txs()?;
+        let prefix = hash.0.as_bytes();
+
+        let mut locations = Vec::new();
+        for item in self
+            .db
+            .iterator_cf(&cf_txs, IteratorMode::From(prefix, Direction::Forward))
+        {
+            let (key, value) = item?;
+            let Some(block) = key.strip_prefix(prefix.as_slice()) else {
+                break;
+            };
+            let block: [u8; HASH_LEN] = block
+                .try_into()
+                .map_err(|_| StorageError::CorruptedMeta("tx index key length"))?;
+            let (height, index) = value
+                .split_first_chunk::<8>()
+                .and_then(|(height, rest)| Some((height, <[u8; 4]>::try_from(rest).ok()?)))
+                .ok_or(StorageError::CorruptedMeta("tx index location length"))?;
+            locations.push(TxLocation {
+                block_hash: BlockHash(Hash256(block)),
+                height: u64::from_be_bytes(*height),
+                index: u32::from_be_bytes(index) as usize,
+            });
+        }
+        Ok(locations)
     }
 }
 
 impl BlockStore for RocksDbBlockStore {
-    fn get_block(&self, hash: &BlockHash) -> Option<Block> {
-        let cf = self.cf_blocks().ok()?;
-        let key = hash.0.as_bytes();
-        match self.db.get_cf(&cf, key) {
-            Ok(Some(bytes)) => Self::decode_block(&bytes),
-            Ok(None) => None,
-            Err(_) => None,
+    fn get_block(&self, hash: &BlockHash) -> Result<Option<Block>, StorageError> {
+        let cf = self.cf_blocks()?;
+        match self.db.get_cf(&cf, hash.0.as_bytes())? {
+            Some(bytes) => Self::decode_block(&bytes)
+                .map(Some)
+                .ok_or(StorageError::CorruptedBlock(*hash)),
+            None => Ok(None),
         }
     }
 
-    fn put_block(&mut self, block: Block) {
-        // We compute the hash before encoding so the mapping is consistent
-        // with consensus-level hashing.
-        let hash = block.compute_hash();
-        let bytes = Self::encode_block(&block);
+    fn put_block(&mut self, block: Block) -> Result<(), StorageError> {
+        self.put_sealed_block(block.seal())
+    }
 
-        if let Ok(cf) = self.cf_blocks() {
-            // If this write fails, we log to stderr and drop the block.
-            if let Err(e) = self.db.put_cf(&cf, hash.0.as_bytes(), bytes) {
-                eprintln!("RocksDbBlockStore::put_block failed: {e}");
-            }
-        } else {
-            eprintln!("RocksDbBlockStore::put_block: missing 'blocks' CF");
+    /// Stores the sealed bytes under the sealed hash, without encoding the
+    /// block again.
+    fn put_sealed_block(&mut self, block: SealedBlock) -> Result<(), StorageError> {
+        let mut batch = WriteBatch::default();
+        self.stage_block(&mut batch, &block)?;
+        self.write(batch)
+    }
+
+    fn tip(&self) -> Result<Option<BlockHash>, StorageError> {
+        self.load_tip()
+    }
+
+    fn set_tip(&mut self, hash: BlockHash) -> Result<(), StorageError> {
+        self.store_tip(&hash)
+    }
+
+    /// Writes the block, its transaction index entries, the height index
+    /// and the tip in one batch.
+    fn put_block_and_set_tip(&mut self, block: Block) -> Result<(), StorageError> {
+        self.put_sealed_block_and_set_tip(block.seal())
+    }
+
+    fn put_sealed_block_and_set_tip(&mut self, block: SealedBlock) -> Result<(), StorageError> {
+        let mut batch = WriteBatch::default();
+        self.stage_block(&mut batch, &block)?;
+        self.stage_tip(&mut batch, &block.hash(), &block.header)?;
+        self.write(batch)
+    }
+
+    fn get_hash_at_height(&self, height: u64) -> Result<Option<BlockHash>, StorageError> {
+        self.load_hash_at_height(height)
+    }
+
+    /// Scans the `"heights"` column family from `range.start`, whose
+    /// big-endian keys sort by height, reading each indexed block as the
+    /// iterator advances.
+    fn iter_range(&self, range: Range<u64>) -> BlockIter<'_> {
+        let cf_heights = match self.cf_heights() {
+            Ok(cf) => cf,
+            Err(e) => return Box::new(std::iter::once(Err(e))),
+        };
+        let start = range.start.to_be_bytes();
+        let entries = self
+            .db
+            .iterator_cf(&cf_heights, IteratorMode::From(&start, Direction::Forward))
+            .map(|item| -> Result<(u64, BlockHash), StorageError> {
+                let (key, value) = item?;
+                let height: [u8; 8] = key
+                    .as_ref()
+                    .try_into()
+                    .map_err(|_| StorageError::CorruptedMeta("height index key length"))?;
+                let hash: [u8; HASH_LEN] = value
+                    .as_ref()
+                    .try_into()
+                    .map_err(|_| StorageError::CorruptedMeta("height index hash length"))?;
+                Ok((u64::from_be_bytes(height), BlockHash(Hash256(hash))))
+            });
+        Box::new(
+            entries
+                .take_while(move |entry| !matches!(entry, Ok((height, _)) if *height >= range.end))
+                .map(move |entry| entry.and_then(|(_, hash)| indexed_block(self, &hash))),
+        )
+    }
+
+    /// Writes the block, its transaction index entries, its receipts and
+    /// ML verdict statistics, and the height index and tip if `set_tip`,
+    /// in one batch.
+    fn put_executed_block(
+        &mut self,
+        block: SealedBlock,
+        receipts: &[Receipt],
+        ml_stats: &[MlVerdictStats],
+        set_tip: bool,
+    ) -> Result<(), StorageError> {
+        let hash = block.hash();
+        let receipts = bincode::serde::encode_to_vec(receipts, bincode::config::standard())
+            .expect("Receipt should always be serializable with bincode 2 + serde");
+        let ml_stats = bincode::serde::encode_to_vec(ml_stats, bincode::config::standard())
+            .expect("MlVerdictStats should always be serializable with bincode 2 + serde");
+        let mut batch = WriteBatch::default();
+        self.stage_block(&mut batch, &block)?;
+        batch.put_cf(&self.cf_receipts()?, hash.0.as_bytes(), receipts);
+        batch.put_cf(&self.cf_ml_stats()?, hash.0.as_bytes(), ml_stats);
+        if set_tip {
+            self.stage_tip(&mut batch, &hash, &block.header)?;
         }
+        self.write(batch)
+    }
+
+    fn get_receipts(&self, hash: &BlockHash) -> Result<Option<Vec<Receipt>>, StorageError> {
+        let cf_receipts = self.cf_receipts()?;
+        let Some(bytes) = self.db.get_cf(&cf_receipts, hash.0.as_bytes())? else {
+            return Ok(None);
+        };
+        let (receipts, _): (Vec<Receipt>, usize) =
+            bincode::serde::decode_from_slice(&bytes, bincode::config::standard())
+                .map_err(|_| StorageError::CorruptedMeta("receipts"))?;
+        Ok(Some(receipts))
+    }
+
+    fn get_ml_stats(&self, hash: &BlockHash) -> Result<Option<Vec<MlVerdictStats>>, StorageError> {
+        let cf_ml_stats = self.cf_ml_stats()?;
+        let Some(bytes) = self.db.get_cf(&cf_ml_stats, hash.0.as_bytes())? else {
+            return Ok(None);
+        };
+        let (stats, _): (Vec<MlVerdictStats>, usize) =
+            bincode::serde::decode_from_slice(&bytes, bincode::config::standard())
+                .map_err(|_| StorageError::CorruptedMeta("ML verdict statistics"))?;
+        Ok(Some(stats))
+    }
+
+    fn block_hashes(&self) -> Result<Vec<BlockHash>, StorageError> {
+        let cf = self.cf_blocks()?;
+
+        self.db
+            .iterator_cf(&cf, IteratorMode::Start)
+            .map(|item| {
+                let (key, _) = item?;
+                let arr: [u8; HASH_LEN] = key
+                    .as_ref()
+                    .try_into()
+                    .map_err(|_| StorageError::CorruptedMeta("block key length"))?;
+                Ok(BlockHash(Hash256(arr)))
+            })
+            .collect()
+    }
+
+    fn delete_block(&mut self, hash: &BlockHash) -> Result<(), StorageError> {
+        let mut batch = WriteBatch::default();
+        self.stage_delete(&mut batch, hash)?;
+        self.write(batch)
+    }
+
+    fn checkpoint(&self) -> Result<Option<Checkpoint>, StorageError> {
+        let cf_meta = self.cf_meta()?;
+        let Some(bytes) = self.db.get_cf(&cf_meta, b"checkpoint")? else {
+            return Ok(None);
+        };
+        let cfg = bincode::config::standard();
+        let (checkpoint, _): (Checkpoint, usize) =
+            bincode::serde::decode_from_slice(&bytes, cfg)
+                .map_err(|_| StorageError::CorruptedMeta("checkpoint"))?;
+        Ok(Some(checkpoint))
     }
 
-    fn tip(&self) -> Option<BlockHash> {
-        self.load_tip().ok().flatten()
+    /// Writes the checkpoint and deletes the pruned blocks and their
+    /// index entries in one batch, then compacts the blocks column family
+    /// to reclaim the space.
+    fn prune_to(
+        &mut self,
+        checkpoint: Checkpoint,
+        pruned: &[BlockHash],
+    ) -> Result<(), StorageError> {
+        let cf_meta = self.cf_meta()?;
+
+        let encoded = bincode::serde::encode_to_vec(&checkpoint, bincode::config::standard())
+            .expect("Checkpoint should always be serializable with bincode 2 + serde");
+        let mut batch = WriteBatch::default();
+        batch.put_cf(&cf_meta, b"checkpoint", encoded);
+        for hash in pruned {
+            self.stage_delete(&mut batch, hash)?;
+        }
+
+        self.write(batch)?;
+        let cf = self.cf_blocks()?;
+        self.db.compact_range_cf(&cf, None::<&[u8]>, None::<&[u8]>);
+        Ok(())
     }
 
-    fn set_tip(&mut self, hash: BlockHash) {
-        if let Err(e) = self.store_tip(&hash) {
-            eprintln!("RocksDbBlockStore::set_tip failed: {e:?}");
+    /// Flushes every column family's memtable to SST files and syncs the
+    /// write-ahead log, so reopening does not have to replay it.
+    fn flush(&mut self) -> Result<(), StorageError> {
+        for &name in COLUMN_FAMILIES.iter().filter(|&&name| name != "default") {
+            let cf = self
+                .db
+                .cf_handle(name)
+                .ok_or(StorageError::MissingColumnFamily(name))?;
+            self.db.flush_cf(&cf)?;
         }
+        self.db.flush_wal(true)?;
+        Ok(())
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::types::{AccountId, Block, Header};
+    use crate::execution::ReceiptEvent;
+    use crate::types::{AccountId, Aid, Block, Header, Signature, Transaction, TxTransfer};
+    use std::sync::atomic::Ordering;
     use tempfile::TempDir;
 
     fn dummy_hash(byte: u8) -> Hash256 {
@@ -185,6 +651,9 @@ mod tests {
             height,
             timestamp: 1_700_000_000 + height,
             proposer: dummy_account(1),
+            txs_root: Hash256([0u8; HASH_LEN]),
+            state_root: Hash256([0u8; HASH_LEN]),
+            receipts_root: Hash256([0u8; HASH_LEN]),
             pos_proof: None,
         };
 
@@ -197,22 +666,408 @@ mod tests {
     #[test]
     fn rocksdb_store_roundtrip_block_and_tip() {
         let tmp = TempDir::new().expect("create temp dir");
-        let cfg = RocksDbConfig {
+        let cfg = StorageConfig {
             path: tmp.path().to_string_lossy().to_string(),
             create_if_missing: true,
+            ..StorageConfig::default()
         };
 
         let mut store = RocksDbBlockStore::open(&cfg).expect("open RocksDB");
 
         let block = dummy_block(0);
         let hash = block.compute_hash();
-        store.put_block(block);
+        store.put_block(block).unwrap();
 
-        let fetched = store.get_block(&hash).expect("block should exist");
+        let fetched = store.get_block(&hash).unwrap().expect("block should exist");
         assert_eq!(fetched.header.height, 0);
 
-        store.set_tip(hash);
-        let tip = store.tip().expect("tip should be set");
+        store.set_tip(hash).unwrap();
+        let tip = store.tip().unwrap().expect("tip should be set");
         assert_eq!(tip.0.as_bytes(), hash.0.as_bytes());
     }
+
+    #[test]
+    fn rocksdb_store_lists_and_deletes_blocks() {
+        let tmp = TempDir::new().expect("create temp dir");
+        let cfg = StorageConfig {
+            path: tmp.path().to_string_lossy().to_string(),
+            create_if_missing: true,
+            ..StorageConfig::default()
+        };
+
+        let mut store = RocksDbBlockStore::open(&cfg).expect("open RocksDB");
+
+        let b0 = dummy_block(0);
+        let b1 = dummy_block(1);
+        let h0 = b0.compute_hash();
+        let h1 = b1.compute_hash();
+        store.put_block(b0).unwrap();
+        store.put_block(b1).unwrap();
+
+        let mut hashes = store.block_hashes().unwrap();
+        hashes.sort_by_key(|h| h.0.0);
+        let mut expected = vec![h0, h1];
+        expected.sort_by_key(|h| h.0.0);
+        assert_eq!(hashes, expected);
+
+        store.delete_block(&h0).unwrap();
+        assert!(store.get_block(&h0).unwrap().is_none());
+        assert_eq!(store.block_hashes().unwrap(), vec![h1]);
+    }
+
+    #[test]
+    fn rocksdb_store_writes_receipts_with_the_block_and_deletes_them() {
+        let tmp = TempDir::new().expect("create temp dir");
+        let cfg = StorageConfig {
+            path: tmp.path().to_string_lossy().to_string(),
+            create_if_missing: true,
+            ..StorageConfig::default()
+        };
+        let mut store = RocksDbBlockStore::open(&cfg).expect("open RocksDB");
+
+        let block = dummy_block(0);
+        let hash = block.compute_hash();
+        let receipts = vec![Receipt {
+            tx_hash: TxHash(dummy_hash(7)),
+            index: 0,
+            payer: dummy_account(1),
+            fee: 3,
+            events: vec![ReceiptEvent::Transferred {
+                from: dummy_account(1),
+                to: dummy_account(2),
+                amount: 10,
+            }],
+        }];
+        let ml_stats = vec![MlVerdictStats {
+            tx_hash: TxHash(dummy_hash(8)),
+            index: 1,
+            aid: Aid(dummy_hash(9)),
+            scheme_id: "wm-test".to_string(),
+            decision: "accepted".to_string(),
+            score: Some(0.9),
+            trigger_acc: None,
+            feat_dist: None,
+            logit_stat: None,
+            latency_ms: Some(12),
+        }];
+        assert_eq!(store.get_receipts(&hash).unwrap(), None);
+
+        // A crash loses the block, its receipts and the tip together.
+        store.writes_left.store(0, Ordering::SeqCst);
+        assert!(
+            store
+                .put_executed_block(block.clone().seal(), &receipts, &ml_stats, true)
+                .is_err()
+        );
+        assert_eq!(store.get_receipts(&hash).unwrap(), None);
+        assert_eq!(store.tip().unwrap(), None);
+
+        store.writes_left.store(usize::MAX, Ordering::SeqCst);
+        store
+            .put_executed_block(block.seal(), &receipts, &ml_stats, true)
+            .unwrap();
+        assert_eq!(store.tip().unwrap(), Some(hash));
+        assert_eq!(store.get_receipts(&hash).unwrap(), Some(receipts));
+        assert_eq!(store.get_ml_stats(&hash).unwrap(), Some(ml_stats));
+
+        store.delete_block(&hash).unwrap();
+        assert_eq!(store.get_receipts(&hash).unwrap(), None);
+        assert_eq!(store.get_ml_stats(&hash).unwrap(), None);
+    }
+
+    #[test]
+    fn rocksdb_store_height_index_survives_reopen() {
+        let tmp = TempDir::new().expect("create temp dir");
+        let cfg = StorageConfig {
+            path: tmp.path().to_string_lossy().to_string(),
+            create_if_missing: true,
+            ..StorageConfig::default()
+        };
+
+        let b0 = dummy_block(0);
+        let h0 = b0.compute_hash();
+        let mut b1 = dummy_block(1);
+        b1.header.parent = h0;
+        let h1 = b1.compute_hash();
+
+        {
+            let mut store = RocksDbBlockStore::open(&cfg).expect("open RocksDB");
+            store.put_block(b0).unwrap();
+            store.put_block(b1).unwrap();
+            store.set_tip(h1).unwrap();
+        }
+
+        let store = RocksDbBlockStore::open(&cfg).expect("reopen RocksDB");
+        assert_eq!(store.get_hash_at_height(0).unwrap(), Some(h0));
+        assert_eq!(store.get_hash_at_height(1).unwrap(), Some(h1));
+        assert_eq!(
+            store
+                .get_block_by_height(1)
+                .unwrap()
+                .map(|b| b.header.height),
+            Some(1)
+        );
+        assert!(store.get_hash_at_height(2).unwrap().is_none());
+    }
+
+    #[test]
+    fn rocksdb_store_iterates_canonical_blocks_by_height() {
+        let tmp = TempDir::new().expect("create temp dir");
+        let cfg = StorageConfig {
+            path: tmp.path().to_string_lossy().to_string(),
+            create_if_missing: true,
+            ..StorageConfig::default()
+        };
+        let mut store = RocksDbBlockStore::open(&cfg).expect("open RocksDB");
+
+        // Enough blocks that lexicographic and numeric key order would
+        // differ for little-endian keys.
+        let mut parent = BlockHash(dummy_hash(0));
+        let mut hashes = Vec::new();
+        for height in 0..300 {
+            let block = child(parent, height, height);
+            parent = block.compute_hash();
+            store.put_block_and_set_tip(block).unwrap();
+            hashes.push(parent);
+        }
+        store.put_block(child(hashes[9], 10, 1_000)).unwrap();
+
+        let heights = |iter: BlockIter<'_>| -> Vec<u64> {
+            iter.map(|block| block.unwrap().header.height).collect()
+        };
+        assert_eq!(heights(store.iter_blocks()), (0..300).collect::<Vec<_>>());
+        assert_eq!(
+            heights(store.iter_range(254..258)),
+            vec![254, 255, 256, 257]
+        );
+        assert_eq!(heights(store.iter_range(298..1_000)), vec![298, 299]);
+        assert!(heights(store.iter_range(5..5)).is_empty());
+        assert_eq!(
+            store
+                .iter_range(10..11)
+                .map(|block| block.unwrap().compute_hash())
+                .collect::<Vec<_>>(),
+            vec![hashes[10]]
+        );
+
+        // Deleted (e.g. pruned) heights are skipped.
+        store.delete_block(&hashes[1]).unwrap();
+        assert_eq!(heights(store.iter_range(0..3)), vec![0, 2]);
+    }
+
+    #[test]
+    fn rocksdb_store_flushes_before_close() {
+        let tmp = TempDir::new().expect("create temp dir");
+        let cfg = StorageConfig {
+            path: tmp.path().to_string_lossy().to_string(),
+            create_if_missing: true,
+            ..StorageConfig::default()
+        };
+
+        let block = dummy_block(0);
+        let hash = block.compute_hash();
+        {
+            let mut store = RocksDbBlockStore::open(&cfg).expect("open RocksDB");
+            store.put_block_and_set_tip(block).unwrap();
+            store.flush().unwrap();
+        }
+
+        let store = RocksDbBlockStore::open(&cfg).expect("reopen RocksDB");
+        assert_eq!(store.tip().unwrap(), Some(hash));
+        assert!(store.get_block(&hash).unwrap().is_some());
+    }
+
+    #[test]
+    fn rocksdb_store_reports_stats() {
+        let tmp = TempDir::new().expect("create temp dir");
+        let cfg = StorageConfig {
+            path: tmp.path().to_string_lossy().to_string(),
+            create_if_missing: true,
+            ..StorageConfig::default()
+        };
+
+        let mut store = RocksDbBlockStore::open(&cfg).expect("open RocksDB");
+        let block = dummy_block(0);
+        let hash = block.compute_hash();
+        store.put_block_and_set_tip(block).unwrap();
+        store.flush().unwrap();
+        assert!(store.get_block(&hash).unwrap().is_some());
+
+        let stats = store.stats().unwrap();
+        let names: Vec<_> = stats.column_families.iter().map(|cf| cf.name).collect();
+        assert_eq!(names, COLUMN_FAMILIES);
+        let blocks = &stats.column_families[1];
+        assert!(blocks.estimated_keys >= 1);
+        assert!(blocks.sst_bytes > 0);
+        assert!(stats.block_cache_hits + stats.block_cache_misses > 0);
+    }
+
+    fn transfer(nonce: u64) -> Transaction {
+        Transaction::Transfer(TxTransfer {
+            from: dummy_account(1),
+            to: dummy_account(2),
+            amount: 1,
+            fee: 0,
+            nonce,
+            signature: Signature(vec![]),
+        })
+    }
+
+    /// Block at `height` on top of `parent`, holding one transfer.
+    fn child(parent: BlockHash, height: u64, nonce: u64) -> Block {
+        let mut block = dummy_block(height);
+        block.header.parent = parent;
+        block.txs.push(transfer(nonce));
+        block
+    }
+
+    /// Asserts that the tip, the height index, and the transaction index
+    /// all agree with the stored blocks.
+    fn assert_consistent(store: &RocksDbBlockStore) {
+        let Some(tip) = store.tip().unwrap() else {
+            assert!(store.get_hash_at_height(0).unwrap().is_none());
+            return;
+        };
+        let tip_block = store.get_block(&tip).unwrap().expect("tip block stored");
+        assert!(
+            store
+                .get_hash_at_height(tip_block.header.height + 1)
+                .unwrap()
+                .is_none()
+        );
+
+        let mut cursor = tip;
+        loop {
+            let block = store.get_block(&cursor).unwrap().expect("ancestor stored");
+            assert_eq!(
+                store.get_hash_at_height(block.header.height).unwrap(),
+                Some(cursor)
+            );
+            if block.header.height == 0 {
+                break;
+            }
+            cursor = block.header.parent;
+        }
+
+        for hash in store.block_hashes().unwrap() {
+            let block = store.get_block(&hash).unwrap().unwrap();
+            for (index, tx) in block.txs.iter().enumerate() {
+                let locations = store.tx_locations(&tx.compute_hash()).unwrap();
+                assert!(locations.contains(&TxLocation {
+                    block_hash: hash,
+                    height: block.header.height,
+                    index,
+                }));
+            }
+        }
+        let cf_txs = store.cf_txs().unwrap();
+        for item in store.db.iterator_cf(&cf_txs, IteratorMode::Start) {
+            let (key, _) = item.unwrap();
+            let block: [u8; HASH_LEN] = key[HASH_LEN..].try_into().unwrap();
+            assert!(
+                store
+                    .get_block(&BlockHash(Hash256(block)))
+                    .unwrap()
+                    .is_some(),
+                "tx index entry for a missing block"
+            );
+        }
+    }
+
+    #[test]
+    fn rocksdb_store_indexes_transactions_of_every_stored_block() {
+        let tmp = TempDir::new().expect("create temp dir");
+        let cfg = StorageConfig {
+            path: tmp.path().to_string_lossy().to_string(),
+            ..StorageConfig::default()
+        };
+        let mut store = RocksDbBlockStore::open(&cfg).expect("open RocksDB");
+
+        let b0 = dummy_block(0);
+        let h0 = b0.compute_hash();
+        let a1 = child(h0, 1, 0);
+        let mut b1 = child(h0, 1, 0);
+        b1.header.timestamp += 1;
+        let (a1_hash, b1_hash) = (a1.compute_hash(), b1.compute_hash());
+        let tx = transfer(0).compute_hash();
+        store.put_block_and_set_tip(b0).unwrap();
+        store.put_block_and_set_tip(a1).unwrap();
+        store.put_block(b1).unwrap();
+
+        let mut blocks: Vec<_> = store
+            .tx_locations(&tx)
+            .unwrap()
+            .into_iter()
+            .map(|l| l.block_hash)
+            .collect();
+        blocks.sort_by_key(|h| h.0.0);
+        let mut expected = vec![a1_hash, b1_hash];
+        expected.sort_by_key(|h| h.0.0);
+        assert_eq!(blocks, expected);
+
+        store.delete_block(&b1_hash).unwrap();
+        assert_eq!(store.tx_locations(&tx).unwrap().len(), 1);
+        assert!(
+            store
+                .tx_locations(&transfer(9).compute_hash())
+                .unwrap()
+                .is_empty()
+        );
+        assert_consistent(&store);
+    }
+
+    #[test]
+    fn rocksdb_store_stays_consistent_after_a_crash_at_any_write() {
+        // Extends a chain, then reorgs onto a longer side branch, through
+        // both the combined and the separate block/tip writes.
+        fn run(store: &mut RocksDbBlockStore) -> Result<(), StorageError> {
+            let b0 = dummy_block(0);
+            let h0 = b0.compute_hash();
+            store.put_block_and_set_tip(b0)?;
+            let a1 = child(h0, 1, 0);
+            let a1_hash = a1.compute_hash();
+            store.put_block_and_set_tip(a1)?;
+            store.put_block_and_set_tip(child(a1_hash, 2, 1))?;
+
+            let mut side = h0;
+            for height in 1..=3 {
+                let mut block = child(side, height, height + 10);
+                block.header.timestamp += 100;
+                side = block.compute_hash();
+                store.put_block(block)?;
+            }
+            store.set_tip(side)?;
+            store.put_block_and_set_tip(child(side, 4, 20))?;
+            store.delete_block(&a1_hash)
+        }
+
+        let mut crash_at = 0;
+        loop {
+            let tmp = TempDir::new().expect("create temp dir");
+            let cfg = StorageConfig {
+                path: tmp.path().to_string_lossy().to_string(),
+                ..StorageConfig::default()
+            };
+            let finished = {
+                let mut store = RocksDbBlockStore::open(&cfg).expect("open RocksDB");
+                store.writes_left.store(crash_at, Ordering::SeqCst);
+                run(&mut store).is_ok()
+            };
+
+            let store = RocksDbBlockStore::open(&cfg).expect("reopen RocksDB");
+            assert_consistent(&store);
+            if finished {
+                assert_eq!(
+                    store
+                        .get_block(&store.tip().unwrap().unwrap())
+                        .unwrap()
+                        .map(|b| b.header.height),
+                    Some(4)
+                );
+                break;
+            }
+            crash_at += 1;
+        }
+        assert!(crash_at > 5, "every write point was exercised");
+    }
 }
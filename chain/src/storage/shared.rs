@@ -0,0 +1,192 @@
+//! Block store shared between the consensus engine and readers.
+
+use std::collections::VecDeque;
+use std::ops::Range;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use crate::consensus::store::{BlockIter, BlockStore, Checkpoint};
+use crate::execution::{MlVerdictStats, Receipt};
+use crate::types::{Block, BlockHash, SealedBlock};
+
+use super::StorageError;
+
+/// Blocks [`SharedStore::iter_range`] reads from the wrapped store per
+/// lock.
+const ITER_BATCH_BLOCKS: usize = 256;
+
+/// Cloneable handle to a block store behind a lock.
+///
+/// Every [`BlockStore`] call locks the wrapped store for that call only,
+/// so an engine built on a `SharedStore` does not keep it locked while it
+/// validates a block, and clones of the handle (see
+/// [`ChainReader`](crate::consensus::ChainReader)) can read blocks at the
+/// same time. A mutex rather than a read-write lock keeps the handle
+/// shareable across threads for backends whose connection is not `Sync`.
+pub struct SharedStore<S>(Arc<Mutex<S>>);
+
+impl<S> SharedStore<S> {
+    /// Wraps `store`.
+    pub fn new(store: S) -> Self {
+        Self(Arc::new(Mutex::new(store)))
+    }
+
+    /// Locks the store, e.g. for backend-specific calls such as
+    /// snapshots.
+    pub fn lock(&self) -> MutexGuard<'_, S> {
+        self.0.lock().expect("block store lock poisoned")
+    }
+}
+
+impl<S> Clone for SharedStore<S> {
+    fn clone(&self) -> Self {
+        Self(Arc::clone(&self.0))
+    }
+}
+
+impl<S: BlockStore> BlockStore for SharedStore<S> {
+    fn get_block(&self, hash: &BlockHash) -> Result<Option<Block>, StorageError> {
+        self.lock().get_block(hash)
+    }
+
+    fn put_block(&mut self, block: Block) -> Result<(), StorageError> {
+        self.lock().put_block(block)
+    }
+
+    fn tip(&self) -> Result<Option<BlockHash>, StorageError> {
+        self.lock().tip()
+    }
+
+    fn set_tip(&mut self, hash: BlockHash) -> Result<(), StorageError> {
+        self.lock().set_tip(hash)
+    }
+
+    fn put_block_and_set_tip(&mut self, block: Block) -> Result<(), StorageError> {
+        self.lock().put_block_and_set_tip(block)
+    }
+
+    fn put_sealed_block(&mut self, block: SealedBlock) -> Result<(), StorageError> {
+        self.lock().put_sealed_block(block)
+    }
+
+    fn put_sealed_block_and_set_tip(&mut self, block: SealedBlock) -> Result<(), StorageError> {
+        self.lock().put_sealed_block_and_set_tip(block)
+    }
+
+    fn get_hash_at_height(&self, height: u64) -> Result<Option<BlockHash>, StorageError> {
+        self.lock().get_hash_at_height(height)
+    }
+
+    fn get_block_by_height(&self, height: u64) -> Result<Option<Block>, StorageError> {
+        self.lock().get_block_by_height(height)
+    }
+
+    fn put_executed_block(
+        &mut self,
+        block: SealedBlock,
+        receipts: &[Receipt],
+        ml_stats: &[MlVerdictStats],
+        set_tip: bool,
+    ) -> Result<(), StorageError> {
+        self.lock()
+            .put_executed_block(block, receipts, ml_stats, set_tip)
+    }
+
+    fn get_receipts(&self, hash: &BlockHash) -> Result<Option<Vec<Receipt>>, StorageError> {
+        self.lock().get_receipts(hash)
+    }
+
+    /// Reads the range through the wrapped store's own
+    /// [`iter_range`](BlockStore::iter_range), [`ITER_BATCH_BLOCKS`] blocks
+    /// per lock, so long scans neither hold the store nor fall back to one
+    /// lookup per height.
+    fn iter_range(&self, range: Range<u64>) -> BlockIter<'_> {
+        let end = range.end;
+        let mut next = range.start;
+        let mut batch = VecDeque::new();
+        Box::new(std::iter::from_fn(move || {
+            if batch.is_empty() && next < end {
+                batch.extend(self.lock().iter_range(next..end).take(ITER_BATCH_BLOCKS));
+                next = match batch.back() {
+                    Some(Ok(block)) if batch.len() == ITER_BATCH_BLOCKS => {
+                        block.header.height.saturating_add(1)
+                    }
+                    _ => end,
+                };
+            }
+            batch.pop_front()
+        }))
+    }
+
+    fn get_ml_stats(&self, hash: &BlockHash) -> Result<Option<Vec<MlVerdictStats>>, StorageError> {
+        self.lock().get_ml_stats(hash)
+    }
+
+    fn block_hashes(&self) -> Result<Vec<BlockHash>, StorageError> {
+        self.lock().block_hashes()
+    }
+
+    fn delete_block(&mut self, hash: &BlockHash) -> Result<(), StorageError> {
+        self.lock().delete_block(hash)
+    }
+
+    fn checkpoint(&self) -> Result<Option<Checkpoint>, StorageError> {
+        self.lock().checkpoint()
+    }
+
+    fn prune_to(
+        &mut self,
+        checkpoint: Checkpoint,
+        pruned: &[BlockHash],
+    ) -> Result<(), StorageError> {
+        self.lock().prune_to(checkpoint, pruned)
+    }
+
+    fn flush(&mut self) -> Result<(), StorageError> {
+        self.lock().flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::InMemoryBlockStore;
+    use crate::types::{AccountId, HASH_LEN, Hash256, Header};
+
+    #[test]
+    fn iter_range_reads_the_wrapped_store_in_batches() {
+        let mut store = SharedStore::new(InMemoryBlockStore::new());
+        let mut parent = BlockHash(Hash256([0u8; HASH_LEN]));
+        let len = ITER_BATCH_BLOCKS as u64 * 2 + 10;
+        for height in 0..len {
+            let block = Block {
+                header: Header {
+                    parent,
+                    height,
+                    timestamp: 1_700_000_000 + height,
+                    proposer: AccountId(Hash256([1u8; HASH_LEN])),
+                    txs_root: Hash256([0u8; HASH_LEN]),
+                    state_root: Hash256([0u8; HASH_LEN]),
+                    receipts_root: Hash256([0u8; HASH_LEN]),
+                    pos_proof: None,
+                },
+                txs: Vec::new(),
+            };
+            parent = block.compute_hash();
+            store.put_block_and_set_tip(block).unwrap();
+        }
+
+        let heights = |iter: BlockIter<'_>| -> Vec<u64> {
+            iter.map(|block| block.unwrap().header.height).collect()
+        };
+        assert_eq!(heights(store.iter_blocks()), (0..len).collect::<Vec<_>>());
+        assert_eq!(
+            heights(store.iter_range(250..270)),
+            (250..270).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            heights(store.iter_range(len - 3..len + 5)),
+            (len - 3..len).collect::<Vec<_>>()
+        );
+        assert!(store.iter_range(len..len + 5).next().is_none());
+    }
+}
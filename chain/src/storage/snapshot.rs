@@ -0,0 +1,312 @@
+//! Portable block store snapshots.
+//!
+//! A snapshot holds every stored block with its receipts and ML verdict
+//! statistics, the tip, and the pruning checkpoint, if any, so a new node
+//! can be bootstrapped from an existing chain without replaying it over
+//! the network. The format does not depend on the backend: a snapshot
+//! exported from one store can be imported into any other [`BlockStore`].
+//!
+//! On disk a snapshot is the 8-byte magic `MLSNAP` plus a format version,
+//! the BLAKE3 hash of the payload, and the payload itself: a bincode 2
+//! encoding of the tip, the checkpoint, and the blocks in height order,
+//! each with its receipts and statistics.
+
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::consensus::store::{BlockStore, Checkpoint};
+use crate::execution::{MlVerdictStats, Receipt};
+use crate::types::{Block, BlockHash, HASH_LEN};
+
+use super::StorageError;
+
+/// File magic followed by the format version.
+const MAGIC: &[u8; 8] = b"MLSNAP\x00\x02";
+
+/// Errors returned by snapshot export and import.
+#[derive(Debug)]
+pub enum SnapshotError {
+    /// Reading or writing the snapshot file failed.
+    Io(std::io::Error),
+    /// The block store failed.
+    Storage(StorageError),
+    /// The file is not a snapshot, or it is truncated or corrupted.
+    Format(String),
+    /// Import target already has a tip.
+    NotEmpty,
+}
+
+impl From<std::io::Error> for SnapshotError {
+    fn from(e: std::io::Error) -> Self {
+        SnapshotError::Io(e)
+    }
+}
+
+impl From<StorageError> for SnapshotError {
+    fn from(e: StorageError) -> Self {
+        SnapshotError::Storage(e)
+    }
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SnapshotError::Io(e) => write!(f, "snapshot I/O: {e}"),
+            SnapshotError::Storage(e) => write!(f, "storage: {e}"),
+            SnapshotError::Format(msg) => write!(f, "invalid snapshot: {msg}"),
+            SnapshotError::NotEmpty => write!(f, "target store already has a tip"),
+        }
+    }
+}
+
+impl std::error::Error for SnapshotError {}
+
+/// Summary of an exported or imported snapshot.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct SnapshotInfo {
+    /// Number of blocks in the snapshot.
+    pub blocks: u64,
+    /// Tip of the snapshot, if the store had one.
+    pub tip: Option<BlockHash>,
+    /// Height of the tip block.
+    pub tip_height: Option<u64>,
+}
+
+/// Snapshot payload.
+#[derive(Serialize, Deserialize)]
+struct SnapshotBody {
+    tip: Option<BlockHash>,
+    checkpoint: Option<Checkpoint>,
+    blocks: Vec<SnapshotBlock>,
+}
+
+/// A stored block with what executing it produced.
+#[derive(Serialize, Deserialize)]
+struct SnapshotBlock {
+    block: Block,
+    receipts: Vec<Receipt>,
+    ml_stats: Vec<MlVerdictStats>,
+}
+
+impl SnapshotBody {
+    fn info(&self) -> SnapshotInfo {
+        SnapshotInfo {
+            blocks: self.blocks.len() as u64,
+            tip: self.tip,
+            tip_height: self.tip.and_then(|tip| {
+                self.blocks
+                    .iter()
+                    .find(|stored| stored.block.compute_hash() == tip)
+                    .map(|stored| stored.block.header.height)
+            }),
+        }
+    }
+}
+
+/// Writes a snapshot of `store` to `path`, replacing any existing file.
+///
+/// The file is written next to `path` first and renamed into place, so
+/// an interrupted export never leaves a truncated snapshot behind.
+pub fn export_snapshot<S>(store: &S, path: &Path) -> Result<SnapshotInfo, SnapshotError>
+where
+    S: BlockStore + ?Sized,
+{
+    let mut blocks = Vec::new();
+    for hash in store.block_hashes()? {
+        if let Some(block) = store.get_block(&hash)? {
+            blocks.push(SnapshotBlock {
+                block,
+                receipts: store.get_receipts(&hash)?.unwrap_or_default(),
+                ml_stats: store.get_ml_stats(&hash)?.unwrap_or_default(),
+            });
+        }
+    }
+    blocks.sort_by_key(|stored| stored.block.header.height);
+
+    let body = SnapshotBody {
+        tip: store.tip()?,
+        checkpoint: store.checkpoint()?,
+        blocks,
+    };
+    let payload = bincode::serde::encode_to_vec(&body, bincode::config::standard())
+        .expect("Snapshot should always be serializable with bincode 2 + serde");
+
+    let mut bytes = Vec::with_capacity(MAGIC.len() + HASH_LEN + payload.len());
+    bytes.extend_from_slice(MAGIC);
+    bytes.extend_from_slice(blake3::hash(&payload).as_bytes());
+    bytes.extend_from_slice(&payload);
+
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    fs::write(&tmp, bytes)?;
+    fs::rename(&tmp, path)?;
+
+    Ok(body.info())
+}
+
+/// Loads the snapshot at `path` into `store`.
+///
+/// `store` must not have a tip yet. Blocks and the checkpoint are written
+/// first and the tip last, so an interrupted import leaves the store
+/// without a tip and can simply be retried.
+pub fn import_snapshot<S>(store: &mut S, path: &Path) -> Result<SnapshotInfo, SnapshotError>
+where
+    S: BlockStore + ?Sized,
+{
+    if store.tip()?.is_some() {
+        return Err(SnapshotError::NotEmpty);
+    }
+
+    let bytes = fs::read(path)?;
+    let body = decode(&bytes)?;
+    let info = body.info();
+    if body.tip.is_some() && info.tip_height.is_none() {
+        return Err(SnapshotError::Format(
+            "tip block is missing from the snapshot".to_string(),
+        ));
+    }
+
+    for stored in body.blocks {
+        store.put_executed_block(
+            stored.block.seal(),
+            &stored.receipts,
+            &stored.ml_stats,
+            false,
+        )?;
+    }
+    if let Some(checkpoint) = body.checkpoint {
+        store.prune_to(checkpoint, &[])?;
+    }
+    if let Some(tip) = body.tip {
+        store.set_tip(tip)?;
+    }
+
+    Ok(info)
+}
+
+/// Checks the magic and checksum of `bytes` and decodes the payload.
+fn decode(bytes: &[u8]) -> Result<SnapshotBody, SnapshotError> {
+    let rest = bytes
+        .strip_prefix(MAGIC.as_slice())
+        .ok_or_else(|| SnapshotError::Format("unknown magic or version".to_string()))?;
+    let (checksum, payload) = rest
+        .split_first_chunk::<HASH_LEN>()
+        .ok_or_else(|| SnapshotError::Format("truncated header".to_string()))?;
+    if blake3::hash(payload).as_bytes() != checksum {
+        return Err(SnapshotError::Format("checksum mismatch".to_string()));
+    }
+
+    let (body, _): (SnapshotBody, usize) =
+        bincode::serde::decode_from_slice(payload, bincode::config::standard())
+            .map_err(|e| SnapshotError::Format(e.to_string()))?;
+    Ok(body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::{InMemoryBlockStore, RocksDbBlockStore, StorageConfig};
+    use crate::types::{AccountId, Aid, Hash256, Header, TxHash};
+    use tempfile::TempDir;
+
+    fn chain(store: &mut InMemoryBlockStore, len: u64) -> BlockHash {
+        let mut parent = BlockHash(Hash256([0u8; HASH_LEN]));
+        for height in 0..len {
+            let block = Block {
+                header: Header {
+                    parent,
+                    height,
+                    timestamp: 1_700_000_000 + height,
+                    proposer: AccountId(Hash256([1u8; HASH_LEN])),
+                    txs_root: Hash256([0u8; HASH_LEN]),
+                    state_root: Hash256([0u8; HASH_LEN]),
+                    receipts_root: Hash256([0u8; HASH_LEN]),
+                    pos_proof: None,
+                },
+                txs: Vec::new(),
+            };
+            parent = block.compute_hash();
+            store.put_block(block).unwrap();
+        }
+        store.set_tip(parent).unwrap();
+        parent
+    }
+
+    #[test]
+    fn snapshot_round_trips_into_rocksdb() {
+        let tmp = TempDir::new().expect("create temp dir");
+        let path = tmp.path().join("chain.snap");
+        let mut source = InMemoryBlockStore::new();
+        let tip = chain(&mut source, 5);
+        let receipts = vec![Receipt {
+            tx_hash: TxHash(Hash256([7u8; HASH_LEN])),
+            index: 0,
+            payer: AccountId(Hash256([1u8; HASH_LEN])),
+            fee: 3,
+            events: Vec::new(),
+        }];
+        let ml_stats = vec![MlVerdictStats {
+            tx_hash: TxHash(Hash256([7u8; HASH_LEN])),
+            index: 0,
+            aid: Aid(Hash256([8u8; HASH_LEN])),
+            scheme_id: "wm-test".to_string(),
+            decision: "accepted".to_string(),
+            score: Some(0.9),
+            trigger_acc: None,
+            feat_dist: None,
+            logit_stat: None,
+            latency_ms: Some(4),
+        }];
+        let tip_block = source.get_block(&tip).unwrap().unwrap();
+        source
+            .put_executed_block(tip_block.seal(), &receipts, &ml_stats, true)
+            .unwrap();
+
+        let exported = export_snapshot(&source, &path).unwrap();
+        assert_eq!(exported.blocks, 5);
+        assert_eq!(exported.tip_height, Some(4));
+
+        let cfg = StorageConfig {
+            path: tmp.path().join("db").to_string_lossy().to_string(),
+            ..StorageConfig::default()
+        };
+        let mut target = RocksDbBlockStore::open(&cfg).expect("open RocksDB");
+        let imported = target.import_snapshot(&path).unwrap();
+        assert_eq!(imported, exported);
+        assert_eq!(target.tip().unwrap(), Some(tip));
+        assert_eq!(target.get_hash_at_height(4).unwrap(), Some(tip));
+        assert_eq!(target.get_receipts(&tip).unwrap(), Some(receipts));
+        assert_eq!(target.get_ml_stats(&tip).unwrap(), Some(ml_stats));
+        assert_eq!(
+            target.get_hash_at_height(0).unwrap(),
+            source.get_hash_at_height(0).unwrap()
+        );
+
+        assert!(matches!(
+            target.import_snapshot(&path),
+            Err(SnapshotError::NotEmpty)
+        ));
+    }
+
+    #[test]
+    fn corrupted_snapshot_is_rejected() {
+        let tmp = TempDir::new().expect("create temp dir");
+        let path = tmp.path().join("chain.snap");
+        let mut source = InMemoryBlockStore::new();
+        chain(&mut source, 3);
+        export_snapshot(&source, &path).unwrap();
+
+        let mut bytes = fs::read(&path).unwrap();
+        let last = bytes.len() - 1;
+        bytes[last] ^= 0xFF;
+        fs::write(&path, &bytes).unwrap();
+
+        let mut target = InMemoryBlockStore::new();
+        let err = import_snapshot(&mut target, &path).unwrap_err();
+        assert!(matches!(err, SnapshotError::Format(_)), "{err}");
+        assert!(target.is_empty());
+    }
+}
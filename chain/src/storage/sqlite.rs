@@ -0,0 +1,516 @@
+//! SQLite-backed block store.
+//!
+//! A lighter alternative to [`RocksDbBlockStore`](super::RocksDbBlockStore)
+//! for machines where building RocksDB is painful. It keeps the same
+//! layout, with one table per RocksDB column family:
+//!
+//! - `blocks`:  `hash` -> canonical block `bytes`,
+//! - `meta`:    the current tip under `"tip"` and the pruning checkpoint,
+//!   if any, under `"checkpoint"`,
+//! - `heights`: `height` -> `hash` of the canonical block at that height,
+//! - `txs`:     (`tx`, `block`) -> `height` and `idx` of the transaction,
+//!   one row per stored block including it,
+//! - `receipts`: `block` -> the block's receipts, bincode-encoded in
+//!   transaction order,
+//! - `ml_stats`: `block` -> the ML verdict statistics of the block's
+//!   registrations, bincode-encoded in transaction order.
+//!
+//! Every mutation runs in a single SQLite transaction, with the same
+//! atomicity guarantees as the RocksDB write batches: a block lands with
+//! its transaction rows, receipts and ML verdict statistics, and with the
+//! tip and height index when it becomes the tip.
+
+use std::fs;
+use std::path::Path;
+
+use rusqlite::{Connection, OpenFlags, OptionalExtension, params};
+
+use crate::consensus::store::{BlockStore, Checkpoint, height_index_updates};
+use crate::consensus::tx_index::TxLocation;
+use crate::execution::{MlVerdictStats, Receipt};
+use crate::types::{Block, BlockHash, HASH_LEN, Hash256, Header, SealedBlock, TxHash};
+
+use super::snapshot::{self, SnapshotError, SnapshotInfo};
+use super::{StorageConfig, StorageError};
+
+/// Tables created on open.
+const SCHEMA: &str = "
+    PRAGMA journal_mode = WAL;
+    CREATE TABLE IF NOT EXISTS blocks (
+        hash  BLOB PRIMARY KEY,
+        bytes BLOB NOT NULL
+    ) WITHOUT ROWID;
+    CREATE TABLE IF NOT EXISTS meta (
+        key   TEXT PRIMARY KEY,
+        value BLOB NOT NULL
+    ) WITHOUT ROWID;
+    CREATE TABLE IF NOT EXISTS heights (
+        height INTEGER PRIMARY KEY,
+        hash   BLOB NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS txs (
+        tx     BLOB NOT NULL,
+        block  BLOB NOT NULL,
+        height INTEGER NOT NULL,
+        idx    INTEGER NOT NULL,
+        PRIMARY KEY (tx, block)
+    ) WITHOUT ROWID;
+    CREATE TABLE IF NOT EXISTS receipts (
+        block BLOB PRIMARY KEY,
+        bytes BLOB NOT NULL
+    ) WITHOUT ROWID;
+    CREATE TABLE IF NOT EXISTS ml_stats (
+        block BLOB PRIMARY KEY,
+        bytes BLOB NOT NULL
+    ) WITHOUT ROWID;
+";
+
+/// SQLite-backed implementation of [`BlockStore`].
+pub struct SqliteBlockStore {
+    conn: Connection,
+}
+
+impl SqliteBlockStore {
+    /// Opens (or creates) a SQLite-backed block store at `cfg.path`.
+    ///
+    /// With `create_if_missing`, the database file, its parent directory,
+    /// and missing tables are created.
+    pub fn open(cfg: &StorageConfig) -> Result<Self, StorageError> {
+        let path = Path::new(&cfg.path);
+
+        let mut flags = OpenFlags::SQLITE_OPEN_READ_WRITE | OpenFlags::SQLITE_OPEN_NO_MUTEX;
+        if cfg.create_if_missing {
+            flags |= OpenFlags::SQLITE_OPEN_CREATE;
+            if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+                fs::create_dir_all(parent).map_err(|e| {
+                    StorageError::Sqlite(format!("create {}: {e}", parent.display()))
+                })?;
+            }
+        }
+
+        let conn = Connection::open_with_flags(path, flags)?;
+        if cfg.create_if_missing {
+            conn.execute_batch(SCHEMA)?;
+        }
+        Ok(Self { conn })
+    }
+
+    /// Runs `stage` in a transaction and commits it.
+    fn write<F>(&self, stage: F) -> Result<(), StorageError>
+    where
+        F: FnOnce(&Connection) -> Result<(), StorageError>,
+    {
+        let tx = self.conn.unchecked_transaction()?;
+        stage(&tx)?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Decodes a block from canonical bytes.
+    fn decode_block(bytes: &[u8]) -> Option<Block> {
+        let cfg = bincode::config::standard();
+        let (block, _): (Block, usize) = bincode::serde::decode_from_slice(bytes, cfg).ok()?;
+        Some(block)
+    }
+
+    /// Decodes a stored 32-byte hash.
+    fn decode_hash(bytes: &[u8], what: &'static str) -> Result<BlockHash, StorageError> {
+        let arr: [u8; HASH_LEN] = bytes
+            .try_into()
+            .map_err(|_| StorageError::CorruptedMeta(what))?;
+        Ok(BlockHash(Hash256(arr)))
+    }
+
+    /// Inserts `block`'s cached canonical bytes and its transaction rows.
+    fn stage_block(conn: &Connection, block: &SealedBlock) -> Result<(), StorageError> {
+        let hash = &block.hash();
+        conn.execute(
+            "INSERT OR REPLACE INTO blocks (hash, bytes) VALUES (?1, ?2)",
+            params![hash.0.as_bytes(), block.bytes()],
+        )?;
+        for (index, tx) in block.txs.iter().enumerate() {
+            conn.execute(
+                "INSERT OR REPLACE INTO txs (tx, block, height, idx) VALUES (?1, ?2, ?3, ?4)",
+                params![
+                    tx.compute_hash().0.as_bytes(),
+                    hash.0.as_bytes(),
+                    block.header.height as i64,
+                    index as i64
+                ],
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Removes the stored block `hash`, its receipts, ML verdict statistics
+    /// and transaction rows, and its height index entry if it is canonical.
+    fn stage_delete(&self, conn: &Connection, hash: &BlockHash) -> Result<(), StorageError> {
+        let Some(block) = self.get_block(hash)? else {
+            return Ok(());
+        };
+        conn.execute("DELETE FROM blocks WHERE hash = ?1", [hash.0.as_bytes()])?;
+        conn.execute("DELETE FROM txs WHERE block = ?1", [hash.0.as_bytes()])?;
+        conn.execute("DELETE FROM receipts WHERE block = ?1", [hash.0.as_bytes()])?;
+        conn.execute("DELETE FROM ml_stats WHERE block = ?1", [hash.0.as_bytes()])?;
+        conn.execute(
+            "DELETE FROM heights WHERE height = ?1 AND hash = ?2",
+            params![block.header.height as i64, hash.0.as_bytes()],
+        )?;
+        Ok(())
+    }
+
+    /// Makes `hash` the tip and rewrites the height index to match.
+    ///
+    /// `header` is the tip block's header, which need not be stored yet.
+    fn stage_tip(
+        &self,
+        conn: &Connection,
+        hash: &BlockHash,
+        header: &Header,
+    ) -> Result<(), StorageError> {
+        let mut updates = Vec::new();
+        if self.get_hash_at_height(header.height)? != Some(*hash) {
+            updates.push((header.height, *hash));
+            if header.height > 0 {
+                updates.extend(height_index_updates(self, &header.parent)?);
+            }
+        }
+
+        conn.execute(
+            "INSERT OR REPLACE INTO meta (key, value) VALUES ('tip', ?1)",
+            [hash.0.as_bytes()],
+        )?;
+        conn.execute(
+            "DELETE FROM heights WHERE height > ?1",
+            [header.height as i64],
+        )?;
+        for (height, h) in updates {
+            conn.execute(
+                "INSERT OR REPLACE INTO heights (height, hash) VALUES (?1, ?2)",
+                params![height as i64, h.0.as_bytes()],
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Returns every stored block that includes transaction `hash`,
+    /// canonical or not, in no particular order.
+    pub fn tx_locations(&self, hash: &TxHash) -> Result<Vec<TxLocation>, StorageError> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT block, height, idx FROM txs WHERE tx = ?1")?;
+        let rows = stmt.query_map([hash.0.as_bytes()], |row| {
+            Ok((
+                row.get::<_, Vec<u8>>(0)?,
+                row.get::<_, i64>(1)?,
+                row.get::<_, i64>(2)?,
+            ))
+        })?;
+
+        let mut locations = Vec::new();
+        for row in rows {
+            let (block, height, index) = row?;
+            locations.push(TxLocation {
+                block_hash: Self::decode_hash(&block, "tx index block hash length")?,
+                height: height as u64,
+                index: index as usize,
+            });
+        }
+        Ok(locations)
+    }
+
+    /// Writes a portable snapshot of this store to `path`.
+    ///
+    /// See [`snapshot::export_snapshot`].
+    pub fn export_snapshot(&self, path: impl AsRef<Path>) -> Result<SnapshotInfo, SnapshotError> {
+        snapshot::export_snapshot(self, path.as_ref())
+    }
+
+    /// Bootstraps this (empty) store from the snapshot at `path`.
+    ///
+    /// See [`snapshot::import_snapshot`].
+    pub fn import_snapshot(
+        &mut self,
+        path: impl AsRef<Path>,
+    ) -> Result<SnapshotInfo, SnapshotError> {
+        snapshot::import_snapshot(self, path.as_ref())
+    }
+}
+
+impl BlockStore for SqliteBlockStore {
+    fn get_block(&self, hash: &BlockHash) -> Result<Option<Block>, StorageError> {
+        let bytes: Option<Vec<u8>> = self
+            .conn
+            .query_row(
+                "SELECT bytes FROM blocks WHERE hash = ?1",
+                [hash.0.as_bytes()],
+                |row| row.get(0),
+            )
+            .optional()?;
+        match bytes {
+            Some(bytes) => Self::decode_block(&bytes)
+                .map(Some)
+                .ok_or(StorageError::CorruptedBlock(*hash)),
+            None => Ok(None),
+        }
+    }
+
+    fn put_block(&mut self, block: Block) -> Result<(), StorageError> {
+        self.put_sealed_block(block.seal())
+    }
+
+    /// Stores the sealed bytes under the sealed hash, without encoding the
+    /// block again.
+    fn put_sealed_block(&mut self, block: SealedBlock) -> Result<(), StorageError> {
+        self.write(|conn| Self::stage_block(conn, &block))
+    }
+
+    fn tip(&self) -> Result<Option<BlockHash>, StorageError> {
+        let bytes: Option<Vec<u8>> = self
+            .conn
+            .query_row("SELECT value FROM meta WHERE key = 'tip'", [], |row| {
+                row.get(0)
+            })
+            .optional()?;
+        bytes
+            .map(|bytes| Self::decode_hash(&bytes, "tip hash length"))
+            .transpose()
+    }
+
+    fn set_tip(&mut self, hash: BlockHash) -> Result<(), StorageError> {
+        let header = self.get_block(&hash)?.map(|block| block.header);
+        self.write(|conn| match &header {
+            Some(header) => self.stage_tip(conn, &hash, header),
+            None => {
+                conn.execute(
+                    "INSERT OR REPLACE INTO meta (key, value) VALUES ('tip', ?1)",
+                    [hash.0.as_bytes()],
+                )?;
+                Ok(())
+            }
+        })
+    }
+
+    /// Inserts the block, its transaction rows, the height index and the
+    /// tip in one transaction.
+    fn put_block_and_set_tip(&mut self, block: Block) -> Result<(), StorageError> {
+        self.put_sealed_block_and_set_tip(block.seal())
+    }
+
+    fn put_sealed_block_and_set_tip(&mut self, block: SealedBlock) -> Result<(), StorageError> {
+        self.write(|conn| {
+            self.stage_tip(conn, &block.hash(), &block.header)?;
+            Self::stage_block(conn, &block)
+        })
+    }
+
+    fn get_hash_at_height(&self, height: u64) -> Result<Option<BlockHash>, StorageError> {
+        let bytes: Option<Vec<u8>> = self
+            .conn
+            .query_row(
+                "SELECT hash FROM heights WHERE height = ?1",
+                [height as i64],
+                |row| row.get(0),
+            )
+            .optional()?;
+        bytes
+            .map(|bytes| Self::decode_hash(&bytes, "height index hash length"))
+            .transpose()
+    }
+
+    /// Inserts the block, its transaction rows, its receipts and ML verdict
+    /// statistics, and the height index and tip if `set_tip`, in one
+    /// transaction.
+    fn put_executed_block(
+        &mut self,
+        block: SealedBlock,
+        receipts: &[Receipt],
+        ml_stats: &[MlVerdictStats],
+        set_tip: bool,
+    ) -> Result<(), StorageError> {
+        let hash = block.hash();
+        let receipts = bincode::serde::encode_to_vec(receipts, bincode::config::standard())
+            .expect("Receipt should always be serializable with bincode 2 + serde");
+        let ml_stats = bincode::serde::encode_to_vec(ml_stats, bincode::config::standard())
+            .expect("MlVerdictStats should always be serializable with bincode 2 + serde");
+        self.write(|conn| {
+            if set_tip {
+                self.stage_tip(conn, &hash, &block.header)?;
+            }
+            Self::stage_block(conn, &block)?;
+            conn.execute(
+                "INSERT OR REPLACE INTO receipts (block, bytes) VALUES (?1, ?2)",
+                params![hash.0.as_bytes(), receipts],
+            )?;
+            conn.execute(
+                "INSERT OR REPLACE INTO ml_stats (block, bytes) VALUES (?1, ?2)",
+                params![hash.0.as_bytes(), ml_stats],
+            )?;
+            Ok(())
+        })
+    }
+
+    fn get_receipts(&self, hash: &BlockHash) -> Result<Option<Vec<Receipt>>, StorageError> {
+        let bytes: Option<Vec<u8>> = self
+            .conn
+            .query_row(
+                "SELECT bytes FROM receipts WHERE block = ?1",
+                [hash.0.as_bytes()],
+                |row| row.get(0),
+            )
+            .optional()?;
+        let Some(bytes) = bytes else {
+            return Ok(None);
+        };
+        let (receipts, _): (Vec<Receipt>, usize) =
+            bincode::serde::decode_from_slice(&bytes, bincode::config::standard())
+                .map_err(|_| StorageError::CorruptedMeta("receipts"))?;
+        Ok(Some(receipts))
+    }
+
+    fn get_ml_stats(&self, hash: &BlockHash) -> Result<Option<Vec<MlVerdictStats>>, StorageError> {
+        let bytes: Option<Vec<u8>> = self
+            .conn
+            .query_row(
+                "SELECT bytes FROM ml_stats WHERE block = ?1",
+                [hash.0.as_bytes()],
+                |row| row.get(0),
+            )
+            .optional()?;
+        let Some(bytes) = bytes else {
+            return Ok(None);
+        };
+        let (stats, _): (Vec<MlVerdictStats>, usize) =
+            bincode::serde::decode_from_slice(&bytes, bincode::config::standard())
+                .map_err(|_| StorageError::CorruptedMeta("ML verdict statistics"))?;
+        Ok(Some(stats))
+    }
+
+    fn block_hashes(&self) -> Result<Vec<BlockHash>, StorageError> {
+        let mut stmt = self.conn.prepare("SELECT hash FROM blocks")?;
+        let rows = stmt.query_map([], |row| row.get::<_, Vec<u8>>(0))?;
+        rows.map(|bytes| Self::decode_hash(&bytes?, "block key length"))
+            .collect()
+    }
+
+    fn delete_block(&mut self, hash: &BlockHash) -> Result<(), StorageError> {
+        self.write(|conn| self.stage_delete(conn, hash))
+    }
+
+    fn checkpoint(&self) -> Result<Option<Checkpoint>, StorageError> {
+        let bytes: Option<Vec<u8>> = self
+            .conn
+            .query_row(
+                "SELECT value FROM meta WHERE key = 'checkpoint'",
+                [],
+                |row| row.get(0),
+            )
+            .optional()?;
+        let Some(bytes) = bytes else {
+            return Ok(None);
+        };
+        let cfg = bincode::config::standard();
+        let (checkpoint, _): (Checkpoint, usize) =
+            bincode::serde::decode_from_slice(&bytes, cfg)
+                .map_err(|_| StorageError::CorruptedMeta("checkpoint"))?;
+        Ok(Some(checkpoint))
+    }
+
+    /// Writes the checkpoint and deletes the pruned blocks and their rows
+    /// in one transaction.
+    fn prune_to(
+        &mut self,
+        checkpoint: Checkpoint,
+        pruned: &[BlockHash],
+    ) -> Result<(), StorageError> {
+        let encoded = bincode::serde::encode_to_vec(&checkpoint, bincode::config::standard())
+            .expect("Checkpoint should always be serializable with bincode 2 + serde");
+        self.write(|conn| {
+            conn.execute(
+                "INSERT OR REPLACE INTO meta (key, value) VALUES ('checkpoint', ?1)",
+                [encoded],
+            )?;
+            for hash in pruned {
+                self.stage_delete(conn, hash)?;
+            }
+            Ok(())
+        })
+    }
+
+    /// Checkpoints the write-ahead log into the database file and
+    /// truncates it.
+    fn flush(&mut self) -> Result<(), StorageError> {
+        self.conn
+            .query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |_| Ok(()))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::AccountId;
+    use tempfile::TempDir;
+
+    fn child(parent: BlockHash, height: u64, salt: u64) -> Block {
+        Block {
+            header: Header {
+                parent,
+                height,
+                timestamp: 1_700_000_000 + height + salt,
+                proposer: AccountId(Hash256([1u8; HASH_LEN])),
+                txs_root: Hash256([0u8; HASH_LEN]),
+                state_root: Hash256([0u8; HASH_LEN]),
+                receipts_root: Hash256([0u8; HASH_LEN]),
+                pos_proof: None,
+            },
+            txs: Vec::new(),
+        }
+    }
+
+    fn open(tmp: &TempDir) -> SqliteBlockStore {
+        let cfg = StorageConfig {
+            path: tmp
+                .path()
+                .join("db/chain.sqlite3")
+                .to_string_lossy()
+                .to_string(),
+            ..StorageConfig::default()
+        };
+        SqliteBlockStore::open(&cfg).expect("open SQLite")
+    }
+
+    #[test]
+    fn sqlite_store_follows_tip_across_reorg_and_reopen() {
+        let tmp = TempDir::new().expect("create temp dir");
+        let b0 = child(BlockHash(Hash256([0u8; HASH_LEN])), 0, 0);
+        let h0 = b0.compute_hash();
+        let a1 = child(h0, 1, 0);
+        let a2 = child(a1.compute_hash(), 2, 0);
+        let a2_hash = a2.compute_hash();
+        let b1 = child(h0, 1, 100);
+        let b1_hash = b1.compute_hash();
+
+        {
+            let mut store = open(&tmp);
+            store.put_block_and_set_tip(b0).unwrap();
+            store.put_block_and_set_tip(a1).unwrap();
+            store.put_block_and_set_tip(a2).unwrap();
+            assert_eq!(store.get_hash_at_height(2).unwrap(), Some(a2_hash));
+
+            store.put_block(b1).unwrap();
+            store.set_tip(b1_hash).unwrap();
+            assert_eq!(store.block_hashes().unwrap().len(), 4);
+        }
+
+        let mut store = open(&tmp);
+        assert_eq!(store.tip().unwrap(), Some(b1_hash));
+        assert_eq!(store.get_hash_at_height(0).unwrap(), Some(h0));
+        assert_eq!(store.get_hash_at_height(1).unwrap(), Some(b1_hash));
+        assert!(store.get_hash_at_height(2).unwrap().is_none());
+
+        store.delete_block(&a2_hash).unwrap();
+        assert!(store.get_block(&a2_hash).unwrap().is_none());
+        assert_eq!(store.block_hashes().unwrap().len(), 3);
+    }
+}
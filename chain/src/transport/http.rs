@@ -0,0 +1,320 @@
+//! `tower` middleware stack around `reqwest`.
+//!
+//! Requests sent through an [`HttpTransport`] pass through, from the
+//! outside in:
+//!
+//! 1. an optional metrics layer counting requests per client and outcome,
+//! 2. a retry layer that retries transport errors, timeouts, and `5xx`
+//!    responses with exponential, jittered backoff,
+//! 3. a per-attempt timeout, and
+//! 4. an auth layer that adds a bearer token, if configured,
+//!
+//! before reaching the underlying `reqwest::Client`.
+
+use std::collections::hash_map::RandomState;
+use std::fmt;
+use std::hash::BuildHasher;
+use std::time::Duration;
+
+use prometheus::IntCounterVec;
+use reqwest::{Client, Method, Request, RequestBuilder, Response, header};
+use tower::retry::Policy;
+use tower::timeout::error::Elapsed;
+use tower::util::BoxCloneSyncService;
+use tower::{BoxError, ServiceBuilder, ServiceExt};
+
+/// Configuration shared by outbound HTTP clients.
+#[derive(Clone, Debug)]
+pub struct TransportConfig {
+    /// Timeout for a single attempt.
+    pub timeout: Duration,
+    /// Number of retries after the first attempt fails.
+    pub max_retries: u32,
+    /// Delay before the first retry; doubled for every further retry.
+    pub retry_backoff: Duration,
+    /// Upper bound on the delay before a retry.
+    pub max_backoff: Duration,
+    /// Fraction in `[0, 1]` by which each delay is randomly shortened, so
+    /// that clients retrying together spread out.
+    pub jitter: f64,
+    /// Bearer token sent in the `Authorization` header, if any.
+    pub bearer_token: Option<String>,
+}
+
+impl Default for TransportConfig {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(2),
+            max_retries: 2,
+            retry_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(2),
+            jitter: 0.2,
+            bearer_token: None,
+        }
+    }
+}
+
+/// Errors returned by [`HttpTransport`].
+#[derive(Debug)]
+pub enum TransportError {
+    /// The HTTP client or request could not be built.
+    Build(String),
+    /// The last attempt did not complete within the configured timeout.
+    Timeout,
+    /// The request failed at the transport level (connect, I/O, ...).
+    Failed(String),
+}
+
+impl fmt::Display for TransportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TransportError::Build(msg) => write!(f, "failed to build request: {msg}"),
+            TransportError::Timeout => write!(f, "request timed out"),
+            TransportError::Failed(msg) => write!(f, "request failed: {msg}"),
+        }
+    }
+}
+
+impl std::error::Error for TransportError {}
+
+impl From<BoxError> for TransportError {
+    fn from(e: BoxError) -> Self {
+        if e.is::<Elapsed>() {
+            TransportError::Timeout
+        } else {
+            TransportError::Failed(e.to_string())
+        }
+    }
+}
+
+/// Retry policy: retries transport errors and `5xx` responses.
+#[derive(Clone, Debug)]
+struct RetryPolicy {
+    remaining: u32,
+    /// Delay before the next retry, before jitter.
+    backoff: Duration,
+    max_backoff: Duration,
+    jitter: f64,
+}
+
+impl RetryPolicy {
+    fn new(cfg: &TransportConfig) -> Self {
+        Self {
+            remaining: cfg.max_retries,
+            backoff: cfg.retry_backoff.min(cfg.max_backoff),
+            max_backoff: cfg.max_backoff,
+            jitter: cfg.jitter.clamp(0.0, 1.0),
+        }
+    }
+
+    /// Returns the delay before the next retry and doubles the backoff for
+    /// the one after.
+    fn next_delay(&mut self) -> Duration {
+        let base = self.backoff;
+        self.backoff = self.backoff.saturating_mul(2).min(self.max_backoff);
+
+        // A process-random hash is good enough to decorrelate clients.
+        let random = RandomState::new().hash_one(0u8) as f64 / u64::MAX as f64;
+        base.mul_f64(1.0 - self.jitter * random)
+    }
+}
+
+impl Policy<Request, Response, BoxError> for RetryPolicy {
+    type Future = tokio::time::Sleep;
+
+    fn retry(
+        &mut self,
+        _req: &mut Request,
+        result: &mut Result<Response, BoxError>,
+    ) -> Option<Self::Future> {
+        let retryable = match result {
+            Ok(resp) => resp.status().is_server_error(),
+            Err(_) => true,
+        };
+        if !retryable || self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        Some(tokio::time::sleep(self.next_delay()))
+    }
+
+    fn clone_request(&mut self, req: &Request) -> Option<Request> {
+        // Streaming bodies cannot be cloned; such requests are not retried.
+        req.try_clone()
+    }
+}
+
+/// Label value recorded by the metrics layer for a request outcome.
+fn outcome_label(result: &Result<Response, BoxError>) -> &'static str {
+    match result {
+        Ok(resp) if resp.status().is_success() => "ok",
+        Ok(_) => "http_error",
+        Err(e) if e.is::<Elapsed>() => "timeout",
+        Err(_) => "transport_error",
+    }
+}
+
+/// Outbound HTTP client with timeouts, retries, auth, and metrics.
+///
+/// Cheap to share: sending a request clones the boxed service stack, which
+/// only clones `Arc`s internally.
+#[derive(Clone)]
+pub struct HttpTransport {
+    client: Client,
+    service: BoxCloneSyncService<Request, Response, BoxError>,
+}
+
+impl HttpTransport {
+    /// Builds a transport for the client called `name`.
+    ///
+    /// If `requests` is given, every request is counted in it under the
+    /// labels `[name, outcome]`.
+    pub fn new(
+        name: &'static str,
+        cfg: &TransportConfig,
+        requests: Option<IntCounterVec>,
+    ) -> Result<Self, TransportError> {
+        let client = Client::builder()
+            .build()
+            .map_err(|e| TransportError::Build(format!("failed to build HTTP client: {e}")))?;
+
+        let auth = cfg
+            .bearer_token
+            .as_ref()
+            .map(|token| header::HeaderValue::from_str(&format!("Bearer {token}")))
+            .transpose()
+            .map_err(|e| TransportError::Build(format!("invalid bearer token: {e}")))?;
+
+        let service = ServiceBuilder::new()
+            .map_result(move |result: Result<Response, BoxError>| {
+                if let Some(requests) = &requests {
+                    requests
+                        .with_label_values(&[name, outcome_label(&result)])
+                        .inc();
+                }
+                result
+            })
+            .retry(RetryPolicy::new(cfg))
+            .timeout(cfg.timeout)
+            .map_request(move |mut req: Request| {
+                if let Some(value) = &auth {
+                    req.headers_mut()
+                        .insert(header::AUTHORIZATION, value.clone());
+                }
+                req
+            })
+            .service(client.clone());
+
+        Ok(Self {
+            client,
+            service: BoxCloneSyncService::new(service),
+        })
+    }
+
+    /// Starts building a request; send it with [`send`](Self::send).
+    pub fn request(&self, method: Method, url: &str) -> RequestBuilder {
+        self.client.request(method, url)
+    }
+
+    /// Sends a request through the middleware stack.
+    ///
+    /// Non-success HTTP statuses are returned as `Ok`; callers decide how
+    /// to interpret them.
+    pub async fn send(&self, builder: RequestBuilder) -> Result<Response, TransportError> {
+        let req = builder
+            .build()
+            .map_err(|e| TransportError::Build(e.to_string()))?;
+        self.service
+            .clone()
+            .oneshot(req)
+            .await
+            .map_err(TransportError::from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn response(status: u16) -> Response {
+        Response::from(
+            hyper::Response::builder()
+                .status(status)
+                .body("")
+                .expect("valid response"),
+        )
+    }
+
+    fn request() -> Request {
+        Request::new(
+            Method::GET,
+            "http://127.0.0.1/health".parse().expect("valid url"),
+        )
+    }
+
+    #[tokio::test]
+    async fn retry_policy_retries_server_errors_until_budget_is_spent() {
+        let mut policy = RetryPolicy::new(&TransportConfig {
+            max_retries: 1,
+            retry_backoff: Duration::ZERO,
+            ..TransportConfig::default()
+        });
+        let mut req = request();
+
+        assert!(policy.retry(&mut req, &mut Ok(response(503))).is_some());
+        assert!(policy.retry(&mut req, &mut Ok(response(503))).is_none());
+    }
+
+    #[tokio::test]
+    async fn retry_policy_retries_errors_but_not_client_errors() {
+        let mut policy = RetryPolicy::new(&TransportConfig {
+            max_retries: 3,
+            retry_backoff: Duration::ZERO,
+            ..TransportConfig::default()
+        });
+        let mut req = request();
+
+        assert!(policy.retry(&mut req, &mut Ok(response(200))).is_none());
+        assert!(policy.retry(&mut req, &mut Ok(response(404))).is_none());
+        assert!(
+            policy
+                .retry(&mut req, &mut Err(Box::new(Elapsed::new())))
+                .is_some()
+        );
+    }
+
+    #[test]
+    fn retry_backoff_doubles_up_to_the_cap_with_jitter() {
+        let mut policy = RetryPolicy::new(&TransportConfig {
+            retry_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_millis(350),
+            jitter: 0.0,
+            ..TransportConfig::default()
+        });
+        let delays: Vec<_> = (0..4).map(|_| policy.next_delay().as_millis()).collect();
+        assert_eq!(delays, [100, 200, 350, 350]);
+
+        let mut policy = RetryPolicy::new(&TransportConfig {
+            retry_backoff: Duration::from_millis(100),
+            jitter: 0.5,
+            ..TransportConfig::default()
+        });
+        let delay = policy.next_delay();
+        assert!(delay >= Duration::from_millis(50) && delay <= Duration::from_millis(100));
+    }
+
+    #[test]
+    fn boxed_errors_map_to_transport_errors() {
+        let timeout: BoxError = Box::new(Elapsed::new());
+        assert!(matches!(
+            TransportError::from(timeout),
+            TransportError::Timeout
+        ));
+
+        let other: BoxError = "connection refused".into();
+        assert_eq!(
+            TransportError::from(other).to_string(),
+            "request failed: connection refused"
+        );
+    }
+}
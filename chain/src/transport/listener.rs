@@ -0,0 +1,462 @@
+//! Inbound TCP listeners with optional TLS.
+//!
+//! Servers (the metrics exporter, the API gateway) bind one [`Listener`]
+//! per configured [`ListenerConfig`], so a node can listen on several
+//! addresses at once, e.g. IPv4 and IPv6, or localhost and a pod IP. Each
+//! listener serves plain TCP or, with a [`ListenerTlsConfig`], TLS with an
+//! optional client certificate requirement.
+//!
+//! TLS handshakes run on background tasks, so a slow or stalled client
+//! cannot hold up [`Listener::accept`] for everyone else.
+
+use std::fmt;
+use std::io;
+use std::net::{AddrParseError, SocketAddr};
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use serde::Deserialize;
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc;
+use tokio_rustls::TlsAcceptor;
+use tokio_rustls::rustls::pki_types::pem::PemObject;
+use tokio_rustls::rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use tokio_rustls::rustls::server::WebPkiClientVerifier;
+use tokio_rustls::rustls::{RootCertStore, ServerConfig, crypto};
+use tokio_rustls::server::TlsStream;
+
+/// How long a client may take to complete the TLS handshake.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Number of handshaken TLS connections buffered ahead of `accept`.
+const TLS_BACKLOG: usize = 64;
+
+/// Configuration for one listen address.
+///
+/// In config files a listener is either a bare address string
+/// (`"0.0.0.0:9898"`) or a table with `addr` and an optional `tls` table.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+#[serde(from = "RawListenerConfig")]
+pub struct ListenerConfig {
+    /// Address to bind to.
+    pub addr: SocketAddr,
+    /// TLS settings; plain TCP if `None`.
+    pub tls: Option<ListenerTlsConfig>,
+}
+
+impl ListenerConfig {
+    /// Creates a plain (non-TLS) listener on `addr`.
+    pub fn plain(addr: SocketAddr) -> Self {
+        Self { addr, tls: None }
+    }
+}
+
+/// Parses a bare address into a plain listener.
+impl FromStr for ListenerConfig {
+    type Err = AddrParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.trim().parse().map(Self::plain)
+    }
+}
+
+/// Either form a listener can take in a config file.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawListenerConfig {
+    Addr(SocketAddr),
+    Table(ListenerTable),
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ListenerTable {
+    addr: SocketAddr,
+    #[serde(default)]
+    tls: Option<ListenerTlsConfig>,
+}
+
+impl From<RawListenerConfig> for ListenerConfig {
+    fn from(raw: RawListenerConfig) -> Self {
+        match raw {
+            RawListenerConfig::Addr(addr) => Self::plain(addr),
+            RawListenerConfig::Table(table) => Self {
+                addr: table.addr,
+                tls: table.tls,
+            },
+        }
+    }
+}
+
+/// TLS settings for a listener.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct ListenerTlsConfig {
+    /// PEM file with the server certificate chain.
+    pub cert: PathBuf,
+    /// PEM file with the private key for `cert`.
+    pub key: PathBuf,
+    /// PEM file with CA certificate(s) that client certificates must chain
+    /// to. When set, clients without a valid certificate are rejected.
+    #[serde(default)]
+    pub client_ca: Option<PathBuf>,
+}
+
+/// Errors produced while binding a [`Listener`].
+#[derive(Debug)]
+pub enum ListenerError {
+    /// The address could not be bound.
+    Bind { addr: SocketAddr, source: io::Error },
+    /// The TLS certificate, key, or client CA could not be loaded.
+    Tls { addr: SocketAddr, message: String },
+}
+
+impl fmt::Display for ListenerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ListenerError::Bind { addr, source } => write!(f, "failed to bind {addr}: {source}"),
+            ListenerError::Tls { addr, message } => {
+                write!(f, "invalid TLS settings for {addr}: {message}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ListenerError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ListenerError::Bind { source, .. } => Some(source),
+            ListenerError::Tls { .. } => None,
+        }
+    }
+}
+
+/// A bound listen address serving plain TCP or TLS connections.
+pub struct Listener {
+    local_addr: SocketAddr,
+    inner: Inner,
+}
+
+enum Inner {
+    Plain(TcpListener),
+    /// Connections that completed the handshake on the accept task.
+    Tls(mpsc::Receiver<io::Result<(Connection, SocketAddr)>>),
+}
+
+impl Listener {
+    /// Binds `cfg.addr`, loading the TLS settings first if any.
+    ///
+    /// TLS listeners spawn their accept loop onto the current Tokio
+    /// runtime; it stops when the `Listener` is dropped.
+    pub async fn bind(cfg: &ListenerConfig) -> Result<Self, ListenerError> {
+        let acceptor = cfg
+            .tls
+            .as_ref()
+            .map(|tls| {
+                tls_acceptor(tls).map_err(|message| ListenerError::Tls {
+                    addr: cfg.addr,
+                    message,
+                })
+            })
+            .transpose()?;
+
+        let bind_error = |source| ListenerError::Bind {
+            addr: cfg.addr,
+            source,
+        };
+        let listener = TcpListener::bind(cfg.addr).await.map_err(bind_error)?;
+        let local_addr = listener.local_addr().map_err(bind_error)?;
+
+        let inner = match acceptor {
+            None => Inner::Plain(listener),
+            Some(acceptor) => {
+                let (tx, rx) = mpsc::channel(TLS_BACKLOG);
+                tokio::spawn(accept_tls(listener, acceptor, tx));
+                Inner::Tls(rx)
+            }
+        };
+        Ok(Self { local_addr, inner })
+    }
+
+    /// Binds every listener in `cfgs`, failing on the first error.
+    pub async fn bind_all(cfgs: &[ListenerConfig]) -> Result<Vec<Self>, ListenerError> {
+        let mut listeners = Vec::with_capacity(cfgs.len());
+        for cfg in cfgs {
+            listeners.push(Self::bind(cfg).await?);
+        }
+        Ok(listeners)
+    }
+
+    /// Returns the bound address (with the actual port if `addr` used 0).
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// Returns `true` if connections are TLS-wrapped.
+    pub fn is_tls(&self) -> bool {
+        matches!(self.inner, Inner::Tls(_))
+    }
+
+    /// Returns the URL scheme for this listener: `"http"` or `"https"`.
+    pub fn scheme(&self) -> &'static str {
+        if self.is_tls() { "https" } else { "http" }
+    }
+
+    /// Waits for the next connection and returns it with the peer address.
+    ///
+    /// For TLS listeners the handshake has already completed; clients that
+    /// fail or stall the handshake are dropped without surfacing here.
+    pub async fn accept(&mut self) -> io::Result<(Connection, SocketAddr)> {
+        match &mut self.inner {
+            Inner::Plain(listener) => {
+                let (stream, peer) = listener.accept().await?;
+                Ok((Connection::Plain(stream), peer))
+            }
+            Inner::Tls(rx) => rx
+                .recv()
+                .await
+                .unwrap_or_else(|| Err(io::Error::other("TLS accept loop stopped"))),
+        }
+    }
+}
+
+/// Accepts TCP connections and runs each TLS handshake on its own task,
+/// until the receiving [`Listener`] is dropped.
+async fn accept_tls(
+    listener: TcpListener,
+    acceptor: TlsAcceptor,
+    tx: mpsc::Sender<io::Result<(Connection, SocketAddr)>>,
+) {
+    loop {
+        let accepted = tokio::select! {
+            _ = tx.closed() => return,
+            accepted = listener.accept() => accepted,
+        };
+        let (stream, peer) = match accepted {
+            Ok(accepted) => accepted,
+            Err(e) => {
+                if tx.send(Err(e)).await.is_err() {
+                    return;
+                }
+                continue;
+            }
+        };
+
+        let acceptor = acceptor.clone();
+        let tx = tx.clone();
+        tokio::spawn(async move {
+            match tokio::time::timeout(HANDSHAKE_TIMEOUT, acceptor.accept(stream)).await {
+                Ok(Ok(tls)) => {
+                    let _ = tx.send(Ok((Connection::Tls(Box::new(tls)), peer))).await;
+                }
+                Ok(Err(e)) => eprintln!("TLS handshake with {peer} failed: {e}"),
+                Err(_) => eprintln!("TLS handshake with {peer} timed out"),
+            }
+        });
+    }
+}
+
+/// Builds a TLS acceptor from PEM files, using the `ring` provider.
+fn tls_acceptor(tls: &ListenerTlsConfig) -> Result<TlsAcceptor, String> {
+    let provider = Arc::new(crypto::ring::default_provider());
+    let certs = load_certs(&tls.cert)?;
+    let key = PrivateKeyDer::from_pem_file(&tls.key)
+        .map_err(|e| format!("{}: {e}", tls.key.display()))?;
+
+    let builder = ServerConfig::builder_with_provider(provider.clone())
+        .with_safe_default_protocol_versions()
+        .map_err(|e| e.to_string())?;
+    let builder = match &tls.client_ca {
+        Some(path) => {
+            let mut roots = RootCertStore::empty();
+            for cert in load_certs(path)? {
+                roots
+                    .add(cert)
+                    .map_err(|e| format!("{}: {e}", path.display()))?;
+            }
+            let verifier = WebPkiClientVerifier::builder_with_provider(Arc::new(roots), provider)
+                .build()
+                .map_err(|e| format!("{}: {e}", path.display()))?;
+            builder.with_client_cert_verifier(verifier)
+        }
+        None => builder.with_no_client_auth(),
+    };
+
+    let mut config = builder
+        .with_single_cert(certs, key)
+        .map_err(|e| format!("{}: {e}", tls.cert.display()))?;
+    config.alpn_protocols = vec![b"http/1.1".to_vec()];
+    Ok(TlsAcceptor::from(Arc::new(config)))
+}
+
+/// Reads every certificate in a PEM file.
+fn load_certs(path: &Path) -> Result<Vec<CertificateDer<'static>>, String> {
+    let certs = CertificateDer::pem_file_iter(path)
+        .and_then(|certs| certs.collect::<Result<Vec<_>, _>>())
+        .map_err(|e| format!("{}: {e}", path.display()))?;
+    if certs.is_empty() {
+        return Err(format!("{}: no certificates found", path.display()));
+    }
+    Ok(certs)
+}
+
+/// An accepted connection, plain or TLS.
+pub enum Connection {
+    Plain(TcpStream),
+    Tls(Box<TlsStream<TcpStream>>),
+}
+
+impl AsyncRead for Connection {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            Connection::Plain(stream) => Pin::new(stream).poll_read(cx, buf),
+            Connection::Tls(stream) => Pin::new(stream).poll_read(cx, buf),
+        }
+    }
+}
+
+impl AsyncWrite for Connection {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        match self.get_mut() {
+            Connection::Plain(stream) => Pin::new(stream).poll_write(cx, buf),
+            Connection::Tls(stream) => Pin::new(stream).poll_write(cx, buf),
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            Connection::Plain(stream) => Pin::new(stream).poll_flush(cx),
+            Connection::Tls(stream) => Pin::new(stream).poll_flush(cx),
+        }
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            Connection::Plain(stream) => Pin::new(stream).poll_shutdown(cx),
+            Connection::Tls(stream) => Pin::new(stream).poll_shutdown(cx),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio_rustls::TlsConnector;
+    use tokio_rustls::rustls::ClientConfig;
+    use tokio_rustls::rustls::pki_types::ServerName;
+
+    fn localhost() -> SocketAddr {
+        "127.0.0.1:0".parse().unwrap()
+    }
+
+    #[test]
+    fn listeners_parse_from_strings_and_tables() {
+        #[derive(Deserialize)]
+        struct File {
+            listeners: Vec<ListenerConfig>,
+        }
+
+        let file: File = toml::from_str(
+            r#"listeners = ["127.0.0.1:1", { addr = "[::1]:2", tls = { cert = "c.pem", key = "k.pem" } }]"#,
+        )
+        .expect("listeners should parse");
+
+        assert_eq!(
+            file.listeners[0],
+            ListenerConfig::plain("127.0.0.1:1".parse().unwrap())
+        );
+        assert_eq!(file.listeners[1].addr, "[::1]:2".parse().unwrap());
+        let tls = file.listeners[1].tls.as_ref().expect("tls settings");
+        assert_eq!(tls.key, PathBuf::from("k.pem"));
+        assert_eq!(tls.client_ca, None);
+    }
+
+    #[tokio::test]
+    async fn plain_and_tls_listeners_accept_connections() {
+        let dir = tempfile::tempdir().expect("create temp dir");
+        let cert = rcgen::generate_simple_self_signed(vec!["localhost".to_string()])
+            .expect("generate certificate");
+        let cert_path = dir.path().join("cert.pem");
+        let key_path = dir.path().join("key.pem");
+        std::fs::write(&cert_path, cert.cert.pem()).unwrap();
+        std::fs::write(&key_path, cert.signing_key.serialize_pem()).unwrap();
+
+        let mut listeners = Listener::bind_all(&[
+            ListenerConfig::plain(localhost()),
+            ListenerConfig {
+                addr: localhost(),
+                tls: Some(ListenerTlsConfig {
+                    cert: cert_path,
+                    key: key_path,
+                    client_ca: None,
+                }),
+            },
+        ])
+        .await
+        .expect("bind listeners");
+        let mut tls_listener = listeners.pop().unwrap();
+        let mut plain_listener = listeners.pop().unwrap();
+        assert_eq!(plain_listener.scheme(), "http");
+        assert_eq!(tls_listener.scheme(), "https");
+
+        let mut client = TcpStream::connect(plain_listener.local_addr())
+            .await
+            .unwrap();
+        let (mut conn, _) = plain_listener.accept().await.unwrap();
+        client.write_all(b"ping").await.unwrap();
+        let mut buf = [0u8; 4];
+        conn.read_exact(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"ping");
+
+        let mut roots = RootCertStore::empty();
+        roots.add(cert.cert.der().clone()).unwrap();
+        let client_cfg =
+            ClientConfig::builder_with_provider(Arc::new(crypto::ring::default_provider()))
+                .with_safe_default_protocol_versions()
+                .unwrap()
+                .with_root_certificates(roots)
+                .with_no_client_auth();
+        let tcp = TcpStream::connect(tls_listener.local_addr()).await.unwrap();
+        let connect = TlsConnector::from(Arc::new(client_cfg))
+            .connect(ServerName::try_from("localhost").unwrap(), tcp);
+        let (client, accepted) = tokio::join!(connect, tls_listener.accept());
+        let mut client = client.expect("TLS handshake");
+        let (mut conn, _) = accepted.unwrap();
+        client.write_all(b"pong").await.unwrap();
+        client.flush().await.unwrap();
+        conn.read_exact(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"pong");
+    }
+
+    #[tokio::test]
+    async fn missing_tls_files_are_reported_before_binding() {
+        let cfg = ListenerConfig {
+            addr: localhost(),
+            tls: Some(ListenerTlsConfig {
+                cert: PathBuf::from("/nonexistent/cert.pem"),
+                key: PathBuf::from("/nonexistent/key.pem"),
+                client_ca: None,
+            }),
+        };
+
+        match Listener::bind(&cfg).await {
+            Err(ListenerError::Tls { message, .. }) => assert!(message.contains("cert.pem")),
+            Err(other) => panic!("expected a TLS error, got {other}"),
+            Ok(_) => panic!("expected a TLS error"),
+        }
+    }
+}
@@ -0,0 +1,16 @@
+//! Shared transport stack for outbound HTTP clients and inbound listeners.
+//!
+//! Outbound clients (currently the ML verifier) should not each re-implement
+//! timeouts, retries, and authentication on top of `reqwest`. Instead they
+//! send requests through an [`HttpTransport`], which wraps a `reqwest`
+//! client in a `tower` middleware stack configured by a
+//! [`TransportConfig`].
+//!
+//! Inbound servers bind their addresses through [`Listener`], which serves
+//! plain TCP or TLS per [`ListenerConfig`].
+
+pub mod http;
+pub mod listener;
+
+pub use http::{HttpTransport, TransportConfig, TransportError};
+pub use listener::{Connection, Listener, ListenerConfig, ListenerError, ListenerTlsConfig};
@@ -0,0 +1,160 @@
+//! Human-readable addresses for accounts and artefacts.
+//!
+//! [`AccountId`] and [`Aid`] display as Bech32m strings whose prefix names
+//! what they identify: `mls1…` for accounts and `aid1…` for artefacts. The
+//! checksum catches typos, and the prefix stops an artefact id from being
+//! pasted where an account is expected.
+//!
+//! Parsing ([`FromStr`]) also accepts the bare 64-character hex form, so
+//! existing clients keep working.
+
+use std::fmt;
+use std::str::FromStr;
+
+use bech32::primitives::decode::CheckedHrpstring;
+use bech32::{Bech32m, Hrp};
+
+use super::{AccountId, Aid, HASH_LEN, Hash256};
+
+/// Human-readable prefix of account addresses.
+pub const ACCOUNT_HRP: &str = "mls";
+
+/// Human-readable prefix of artefact addresses.
+pub const AID_HRP: &str = "aid";
+
+/// Errors raised when parsing an address.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum AddressError {
+    /// Neither a valid Bech32m string nor 64 hex characters; this includes
+    /// a failed checksum.
+    Invalid(String),
+    /// A valid address for a different kind of identifier.
+    WrongPrefix {
+        expected: &'static str,
+        found: String,
+    },
+    /// The payload is not 32 bytes long.
+    WrongLength(usize),
+}
+
+impl fmt::Display for AddressError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AddressError::Invalid(msg) => write!(f, "invalid address: {msg}"),
+            AddressError::WrongPrefix { expected, found } => {
+                write!(f, "expected a {expected}1... address, got {found}1...")
+            }
+            AddressError::WrongLength(len) => {
+                write!(f, "address holds {len} bytes, expected {HASH_LEN}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for AddressError {}
+
+fn encode(f: &mut fmt::Formatter<'_>, hrp: &'static str, hash: &Hash256) -> fmt::Result {
+    // A 32-byte payload is far below the Bech32m length limit.
+    bech32::encode_lower_to_fmt::<Bech32m, _>(f, Hrp::parse_unchecked(hrp), hash.as_bytes())
+        .map_err(|_| fmt::Error)
+}
+
+/// Parses `s` as a Bech32m address with prefix `hrp`, or as bare hex.
+fn decode(s: &str, hrp: &'static str) -> Result<Hash256, AddressError> {
+    if s.len() == 2 * HASH_LEN && s.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return s
+            .parse()
+            .map_err(|e: hex::FromHexError| AddressError::Invalid(e.to_string()));
+    }
+    let checked =
+        CheckedHrpstring::new::<Bech32m>(s).map_err(|e| AddressError::Invalid(e.to_string()))?;
+    if checked.hrp() != Hrp::parse_unchecked(hrp) {
+        return Err(AddressError::WrongPrefix {
+            expected: hrp,
+            found: checked.hrp().to_lowercase(),
+        });
+    }
+    let bytes: Vec<u8> = checked.byte_iter().collect();
+    let len = bytes.len();
+    bytes
+        .try_into()
+        .map(Hash256)
+        .map_err(|_| AddressError::WrongLength(len))
+}
+
+impl fmt::Display for AccountId {
+    /// Formats the account as a `mls1…` address.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        encode(f, ACCOUNT_HRP, &self.0)
+    }
+}
+
+impl FromStr for AccountId {
+    type Err = AddressError;
+
+    /// Parses a `mls1…` address or 64 hex characters.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        decode(s, ACCOUNT_HRP).map(AccountId)
+    }
+}
+
+impl fmt::Display for Aid {
+    /// Formats the artefact id as an `aid1…` address.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        encode(f, AID_HRP, &self.0)
+    }
+}
+
+impl FromStr for Aid {
+    type Err = AddressError;
+
+    /// Parses an `aid1…` address or 64 hex characters.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        decode(s, AID_HRP).map(Aid)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn addresses_roundtrip_and_reject_typos_and_wrong_prefixes() {
+        let account = AccountId(Hash256::compute(b"alice"));
+        let address = account.to_string();
+        assert!(address.starts_with("mls1"));
+        assert_eq!(address.parse::<AccountId>(), Ok(account));
+        assert_eq!(address.to_uppercase().parse::<AccountId>(), Ok(account));
+        assert_eq!(
+            hex::encode(account.0.as_bytes()).parse::<AccountId>(),
+            Ok(account)
+        );
+
+        let aid = Aid(Hash256::compute(b"model"));
+        let aid_address = aid.to_string();
+        assert!(aid_address.starts_with("aid1"));
+        assert_eq!(aid_address.parse::<Aid>(), Ok(aid));
+        assert_eq!(
+            aid_address.parse::<AccountId>(),
+            Err(AddressError::WrongPrefix {
+                expected: ACCOUNT_HRP,
+                found: AID_HRP.to_string(),
+            })
+        );
+
+        // Flip one data character: the checksum must catch it.
+        let mut typo = address.into_bytes();
+        let i = typo.len() - 10;
+        typo[i] = if typo[i] == b'q' { b'p' } else { b'q' };
+        let typo = String::from_utf8(typo).unwrap();
+        assert!(matches!(
+            typo.parse::<AccountId>(),
+            Err(AddressError::Invalid(_))
+        ));
+
+        assert!(matches!(
+            "abcd".parse::<AccountId>(),
+            Err(AddressError::Invalid(_))
+        ));
+    }
+}
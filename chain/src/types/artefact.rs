@@ -7,11 +7,35 @@
 //! - an owning account (`AccountId`),
 //! - and watermark / authenticity evidence (`EvidenceRef`),
 //!
-//! together with the block height at which the artefact was first accepted.
+//! together with the block height at which the artefact was first accepted
+//! and, if it has been taken off the registry, the height of its revocation.
+//!
+//! It also defines [`ChunkedAid`], an alternative way of deriving an `Aid`
+//! for large models: the bytes are split into fixed-size chunks, each
+//! chunk is hashed on its own, and the chunk hashes are rolled into a
+//! Merkle root. Single chunks can then be checked against the root
+//! without hashing the whole artefact, e.g. to resume an interrupted
+//! download or to spot-check a stored copy.
+
+use std::fmt;
+use std::io::{self, Read};
 
 use serde::{Deserialize, Serialize};
 
-use super::{AccountId, Aid, EvidenceRef};
+use super::merkle::{LEAF_PREFIX, MerkleProof, Side, merkle_root};
+use super::{AccountId, Aid, EvidenceRef, HASH_LEN, Hash256};
+
+/// Default chunk size of a [`ChunkedAid`] (4 MiB).
+pub const DEFAULT_CHUNK_SIZE: u32 = 4 * 1024 * 1024;
+
+/// Smallest accepted chunk size (4 KiB).
+pub const MIN_CHUNK_SIZE: u32 = 4 * 1024;
+
+/// Largest accepted chunk size (64 MiB).
+pub const MAX_CHUNK_SIZE: u32 = 64 * 1024 * 1024;
+
+/// Domain tag of the preimage a [`ChunkedAid`] derives its `Aid` from.
+const CHUNKED_AID_TAG: &[u8] = b"mlsnitch/chunked-aid/v1";
 
 /// Metadata stored in state for a registered ML artefact.
 ///
@@ -30,8 +54,8 @@ pub struct ArtefactMetadata {
     /// Current owner of the artefact.
     ///
     /// Ownership is expressed as an [`AccountId`] derived from the
-    /// owner's Dilithium / ML-DSA public key. Transfer transactions
-    /// update this field while keeping the `aid` stable.
+    /// owner's Dilithium / ML-DSA public key. `TxTransferModel`
+    /// transactions update this field while keeping the `aid` stable.
     pub owner: AccountId,
 
     /// Authenticity evidence reference used when this artefact was accepted.
@@ -48,6 +72,257 @@ pub struct ArtefactMetadata {
     /// or enforcing policies such as “only models registered before
     /// height _H_ are allowed in a given context”.
     pub registered_at: u64,
+
+    /// Height at which the artefact was revoked, if it has been.
+    ///
+    /// Set by a `TxRevokeModel` from the owner or a governance account.
+    /// A revoked artefact stays in the registry, so its `aid` cannot be
+    /// registered again, but `TxUseModel` records for it are invalid.
+    pub revoked_at: Option<u64>,
+
+    /// Chunked form of `aid`, if the artefact was registered with one.
+    #[serde(default)]
+    pub chunked: Option<ChunkedAid>,
+}
+
+impl ArtefactMetadata {
+    /// Returns `true` if the artefact has been revoked.
+    pub fn is_revoked(&self) -> bool {
+        self.revoked_at.is_some()
+    }
+}
+
+/// Merkleized artefact identifier: a Merkle root over the BLAKE3 hashes of
+/// consecutive `chunk_size`-byte chunks of the model bytes (the last chunk
+/// may be shorter).
+///
+/// The registered [`Aid`] is [`ChunkedAid::aid`], which commits to the
+/// chunk size and total length as well as the root, so the same bytes
+/// chunked differently yield different identifiers.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct ChunkedAid {
+    /// Chunk size in bytes, between [`MIN_CHUNK_SIZE`] and
+    /// [`MAX_CHUNK_SIZE`].
+    pub chunk_size: u32,
+    /// Total artefact length in bytes.
+    pub total_len: u64,
+    /// Merkle root over the [`chunk_hash`]es, in order.
+    pub root: Hash256,
+}
+
+/// Reasons a [`ChunkedAid`] or a chunk checked against it is rejected.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ChunkError {
+    /// The chunk size is outside [`MIN_CHUNK_SIZE`]..=[`MAX_CHUNK_SIZE`].
+    BadChunkSize(u32),
+    /// The artefact is empty.
+    Empty,
+    /// The registered `Aid` is not the one derived from the chunked form.
+    AidMismatch,
+    /// The chunk index is past the last chunk.
+    IndexOutOfRange { index: u64, chunks: u64 },
+    /// The chunk does not have the length its index implies.
+    WrongLength { index: u64, expected: u64, got: u64 },
+    /// The chunk is not part of the tree with the artefact's root.
+    HashMismatch { index: u64 },
+}
+
+impl fmt::Display for ChunkError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChunkError::BadChunkSize(size) => write!(
+                f,
+                "chunk size {size} is outside [{MIN_CHUNK_SIZE}, {MAX_CHUNK_SIZE}]"
+            ),
+            ChunkError::Empty => write!(f, "chunked artefact is empty"),
+            ChunkError::AidMismatch => write!(f, "aid does not match chunked aid"),
+            ChunkError::IndexOutOfRange { index, chunks } => {
+                write!(f, "chunk {index} is out of range ({chunks} chunks)")
+            }
+            ChunkError::WrongLength {
+                index,
+                expected,
+                got,
+            } => write!(f, "chunk {index} is {got} bytes, expected {expected}"),
+            ChunkError::HashMismatch { index } => {
+                write!(f, "chunk {index} does not match the chunked aid root")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ChunkError {}
+
+/// Returns the Merkle leaf committing to one chunk of an artefact: the
+/// BLAKE3 hash of the chunk bytes, domain-separated from inner nodes.
+pub fn chunk_hash(chunk: &[u8]) -> Hash256 {
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(&[LEAF_PREFIX]);
+    hasher.update(chunk);
+    Hash256(*hasher.finalize().as_bytes())
+}
+
+impl ChunkedAid {
+    /// Reads `reader` to the end in `chunk_size`-byte chunks and returns the
+    /// chunked identifier together with the chunk hashes, which
+    /// [`ChunkedArtefact::proof`] needs to prove single chunks.
+    ///
+    /// Fails with [`io::ErrorKind::InvalidInput`] if `chunk_size` is out of
+    /// range or the reader yields no bytes.
+    pub fn from_reader<R: Read>(mut reader: R, chunk_size: u32) -> io::Result<ChunkedArtefact> {
+        if !(MIN_CHUNK_SIZE..=MAX_CHUNK_SIZE).contains(&chunk_size) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                ChunkError::BadChunkSize(chunk_size),
+            ));
+        }
+        let mut chunk = vec![0u8; chunk_size as usize];
+        let mut chunk_hashes = Vec::new();
+        let mut total_len = 0u64;
+        loop {
+            // Fill the chunk completely unless the reader runs dry.
+            let mut filled = 0;
+            while filled < chunk.len() {
+                match reader.read(&mut chunk[filled..]) {
+                    Ok(0) => break,
+                    Ok(n) => filled += n,
+                    Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                    Err(e) => return Err(e),
+                }
+            }
+            if filled == 0 {
+                break;
+            }
+            chunk_hashes.push(chunk_hash(&chunk[..filled]));
+            total_len += filled as u64;
+            if filled < chunk.len() {
+                break;
+            }
+        }
+        if total_len == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                ChunkError::Empty,
+            ));
+        }
+        Ok(ChunkedArtefact {
+            aid: ChunkedAid {
+                chunk_size,
+                total_len,
+                root: merkle_root(&chunk_hashes),
+            },
+            chunk_hashes,
+        })
+    }
+
+    /// Derives the registered artefact identifier.
+    pub fn aid(&self) -> Aid {
+        let mut preimage = Vec::with_capacity(CHUNKED_AID_TAG.len() + 4 + 8 + HASH_LEN);
+        preimage.extend_from_slice(CHUNKED_AID_TAG);
+        preimage.extend_from_slice(&self.chunk_size.to_le_bytes());
+        preimage.extend_from_slice(&self.total_len.to_le_bytes());
+        preimage.extend_from_slice(self.root.as_bytes());
+        Aid(Hash256::compute(&preimage))
+    }
+
+    /// Number of chunks the artefact is split into.
+    pub fn chunk_count(&self) -> u64 {
+        self.total_len.div_ceil(u64::from(self.chunk_size.max(1)))
+    }
+
+    /// Checks the chunked identifier is well-formed.
+    pub fn validate(&self) -> Result<(), ChunkError> {
+        if !(MIN_CHUNK_SIZE..=MAX_CHUNK_SIZE).contains(&self.chunk_size) {
+            return Err(ChunkError::BadChunkSize(self.chunk_size));
+        }
+        if self.total_len == 0 {
+            return Err(ChunkError::Empty);
+        }
+        Ok(())
+    }
+
+    /// Checks that a registration of `aid` is consistent with this chunked
+    /// form.
+    pub fn check_registration(&self, aid: &Aid) -> Result<(), ChunkError> {
+        self.validate()?;
+        if self.aid() != *aid {
+            return Err(ChunkError::AidMismatch);
+        }
+        Ok(())
+    }
+
+    /// Checks that `chunk` is chunk number `index` of the artefact, using
+    /// the inclusion `proof` of its hash.
+    pub fn verify_chunk(
+        &self,
+        index: u64,
+        chunk: &[u8],
+        proof: &MerkleProof,
+    ) -> Result<(), ChunkError> {
+        self.validate()?;
+        let chunks = self.chunk_count();
+        if index >= chunks {
+            return Err(ChunkError::IndexOutOfRange { index, chunks });
+        }
+        let chunk_size = u64::from(self.chunk_size);
+        let expected = chunk_size.min(self.total_len - index * chunk_size);
+        let got = chunk.len() as u64;
+        if got != expected {
+            return Err(ChunkError::WrongLength {
+                index,
+                expected,
+                got,
+            });
+        }
+        // The proof's path must lead to `index`, or chunks could be
+        // swapped without changing the root.
+        if !proof_fits_index(proof, index, chunks) || !proof.verify(chunk_hash(chunk), &self.root) {
+            return Err(ChunkError::HashMismatch { index });
+        }
+        Ok(())
+    }
+}
+
+/// Returns `true` if `proof` has the shape of a path from leaf `index` in
+/// a tree of `leaves` leaves: a sibling on the expected side at every level
+/// where the node has one, and none where it is carried up unpaired.
+fn proof_fits_index(proof: &MerkleProof, mut index: u64, leaves: u64) -> bool {
+    let mut siblings = proof.siblings.iter();
+    let mut width = leaves;
+    while width > 1 {
+        if index ^ 1 < width {
+            let expected = if index % 2 == 1 {
+                Side::Left
+            } else {
+                Side::Right
+            };
+            match siblings.next() {
+                Some((side, _)) if *side == expected => {}
+                _ => return false,
+            }
+        }
+        index /= 2;
+        width = width.div_ceil(2);
+    }
+    siblings.next().is_none()
+}
+
+/// A [`ChunkedAid`] together with the hashes of its chunks, as built by
+/// [`ChunkedAid::from_reader`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ChunkedArtefact {
+    /// The chunked identifier.
+    pub aid: ChunkedAid,
+    /// [`chunk_hash`] of each chunk, in order.
+    pub chunk_hashes: Vec<Hash256>,
+}
+
+impl ChunkedArtefact {
+    /// Builds the inclusion proof for chunk `index`, or `None` if out of
+    /// range.
+    pub fn proof(&self, index: u64) -> Option<MerkleProof> {
+        MerkleProof::build(&self.chunk_hashes, usize::try_from(index).ok()?)
+    }
 }
 
 #[cfg(test)]
@@ -75,9 +350,11 @@ mod tests {
 
         let meta = ArtefactMetadata {
             aid,
+            chunked: None,
             owner,
             evidence,
             registered_at: 42,
+            revoked_at: None,
         };
 
         assert_eq!(meta.registered_at, 42);
@@ -105,9 +382,11 @@ mod tests {
 
         let original = ArtefactMetadata {
             aid,
+            chunked: None,
             owner,
             evidence,
             registered_at: 123,
+            revoked_at: None,
         };
 
         let json = serde_json::to_string(&original).expect("serialize metadata to json");
@@ -123,4 +402,72 @@ mod tests {
         );
         assert_eq!(decoded.evidence.scheme_id, original.evidence.scheme_id);
     }
+
+    fn model(len: usize) -> Vec<u8> {
+        (0..len).map(|i| (i % 251) as u8).collect()
+    }
+
+    #[test]
+    fn chunked_aid_proves_each_chunk_and_rejects_tampering() {
+        let bytes = model(5 * MIN_CHUNK_SIZE as usize + 100);
+        let chunked = ChunkedAid::from_reader(bytes.as_slice(), MIN_CHUNK_SIZE).unwrap();
+        let aid = chunked.aid;
+        assert_eq!(aid.chunk_count(), 6);
+        assert_eq!(aid.total_len, bytes.len() as u64);
+        assert!(aid.check_registration(&aid.aid()).is_ok());
+        assert_ne!(aid.aid(), Aid::from_model_bytes(&bytes));
+
+        let chunks: Vec<&[u8]> = bytes.chunks(MIN_CHUNK_SIZE as usize).collect();
+        for (index, chunk) in chunks.iter().enumerate() {
+            let index = index as u64;
+            let proof = chunked.proof(index).unwrap();
+            assert_eq!(aid.verify_chunk(index, chunk, &proof), Ok(()));
+        }
+
+        // A valid chunk presented under another index of the same length.
+        let proof = chunked.proof(1).unwrap();
+        assert_eq!(
+            aid.verify_chunk(2, chunks[1], &proof),
+            Err(ChunkError::HashMismatch { index: 2 })
+        );
+        let mut tampered = chunks[0].to_vec();
+        tampered[0] ^= 1;
+        assert_eq!(
+            aid.verify_chunk(0, &tampered, &chunked.proof(0).unwrap()),
+            Err(ChunkError::HashMismatch { index: 0 })
+        );
+        assert_eq!(
+            aid.verify_chunk(5, chunks[0], &chunked.proof(5).unwrap()),
+            Err(ChunkError::WrongLength {
+                index: 5,
+                expected: 100,
+                got: MIN_CHUNK_SIZE as u64
+            })
+        );
+        assert!(matches!(
+            aid.verify_chunk(6, chunks[5], &chunked.proof(5).unwrap()),
+            Err(ChunkError::IndexOutOfRange {
+                index: 6,
+                chunks: 6
+            })
+        ));
+    }
+
+    #[test]
+    fn chunked_aid_commits_to_chunk_size_and_rejects_bad_forms() {
+        let bytes = model(3 * MIN_CHUNK_SIZE as usize);
+        let small = ChunkedAid::from_reader(bytes.as_slice(), MIN_CHUNK_SIZE).unwrap();
+        let large = ChunkedAid::from_reader(bytes.as_slice(), 2 * MIN_CHUNK_SIZE).unwrap();
+        assert_ne!(small.aid.aid(), large.aid.aid());
+        assert_eq!(
+            small.aid.check_registration(&large.aid.aid()),
+            Err(ChunkError::AidMismatch)
+        );
+
+        assert!(ChunkedAid::from_reader(bytes.as_slice(), 16).is_err());
+        assert!(ChunkedAid::from_reader(&[][..], MIN_CHUNK_SIZE).is_err());
+        let mut empty = small.aid;
+        empty.total_len = 0;
+        assert_eq!(empty.validate(), Err(ChunkError::Empty));
+    }
 }
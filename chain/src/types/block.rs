@@ -9,16 +9,22 @@
 //! Serialization is done with **bincode 2** using the `serde` integration
 //! (`bincode::serde::encode_to_vec`) and an explicit `standard()` config.
 //! The same canonical encoding is used everywhere we need block bytes.
+//! A [`SealedBlock`] computes that encoding and the block hash once, so
+//! validation and storage can share them instead of re-encoding.
+
+use std::ops::Deref;
 
 use serde::{Deserialize, Serialize};
 
-use super::{AccountId, Aid, EvidenceRef, Hash256, Transaction};
+use super::{AccountId, Aid, EvidenceRef, Hash256, MerkleProof, Transaction, merkle_root};
 
 /// Strongly-typed block hash.
 ///
-/// This is the content hash of a [`Block`], computed as a BLAKE3-256
-/// digest over the canonical bincode-2 serialization of the block.
-/// Wrapping the underlying [`Hash256`] avoids passing raw byte arrays
+/// This is the hash of a block's [`Header`], computed as a BLAKE3-256
+/// digest over the header's canonical bincode-2 serialization. The header
+/// commits to the transactions through [`Header::txs_root`], so the hash
+/// still covers the whole block, and a light client can follow a chain of
+/// headers without downloading bodies. Wrapping the underlying [`Hash256`] avoids passing raw byte arrays
 /// around in public APIs.
 #[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
 pub struct BlockHash(pub Hash256);
@@ -48,11 +54,64 @@ pub struct Header {
     /// may be used for rewards, slashing, or accountability.
     pub proposer: AccountId,
 
-    /// Placeholder for PoS proof / VRF output etc.
+    /// Merkle root over the hashes of [`Block::txs`], in block order.
+    ///
+    /// Lets a light client check that a transaction is part of this block
+    /// with a [`MerkleProof`] from [`Block::tx_proof`], without fetching
+    /// the other transactions.
+    pub txs_root: Hash256,
+
+    /// Merkle root of the chain state after executing this block.
+    ///
+    /// See [`crate::execution::merkle`] for the tree layout. The engine
+    /// fills this in when proposing and recomputes it on import, rejecting
+    /// blocks whose root does not match.
+    pub state_root: Hash256,
+
+    /// Merkle root over the receipts of [`Block::txs`], in block order.
     ///
-    /// In a full PoS implementation, this will carry whatever randomness
-    /// or eligibility proof is required by the consensus protocol.
-    pub pos_proof: Option<Vec<u8>>,
+    /// See [`crate::execution::receipt`]. Like the state root, it is
+    /// filled in when proposing and recomputed on import.
+    pub receipts_root: Hash256,
+
+    /// VRF eligibility proof for the block's slot.
+    ///
+    /// Required under VRF leader election (see
+    /// [`crate::consensus::election`]); `None` under round-robin
+    /// scheduling and for the genesis block.
+    pub pos_proof: Option<PosProof>,
+}
+
+impl Header {
+    /// Returns the canonical byte representation of this header, with the
+    /// same encoding as [`Block::canonical_bytes`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if encoding fails, which would be a programming error.
+    pub fn canonical_bytes(&self) -> Vec<u8> {
+        bincode::serde::encode_to_vec(self, bincode::config::standard())
+            .expect("Header should always be serializable with bincode 2 + serde")
+    }
+
+    /// Computes the hash of the block this header belongs to.
+    pub fn compute_hash(&self) -> BlockHash {
+        BlockHash(Hash256::compute(&self.canonical_bytes()))
+    }
+}
+
+/// Proof that a proposer won its slot's VRF lottery.
+///
+/// The proposer evaluates a schnorrkel (sr25519) VRF on the slot with its
+/// VRF secret key. Anyone holding the matching public key can check
+/// `proof` and derive the lottery ticket from `output`.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct PosProof {
+    /// VRF pre-output for the slot input.
+    pub output: [u8; 32],
+    /// 64-byte DLEQ proof that `output` was computed with the proposer's
+    /// VRF key.
+    pub proof: Vec<u8>,
 }
 
 /// Block = header + list of transactions.
@@ -86,15 +145,50 @@ impl Block {
             .expect("Block should always be serializable with bincode 2 + serde")
     }
 
-    /// Computes a canonical BLAKE3-256 hash for this block.
+    /// Decodes a block from its [`canonical_bytes`](Self::canonical_bytes).
     ///
-    /// The block is serialized with [`bincode`] v2 using
-    /// [`Block::canonical_bytes`] and the resulting bytes are hashed
-    /// with [`Hash256::compute`]. This must remain stable across nodes
-    /// for consensus to work correctly.
+    /// Returns `None` unless `bytes` hold exactly one encoded block.
+    pub fn from_canonical_bytes(bytes: &[u8]) -> Option<Self> {
+        let cfg = bincode::config::standard();
+        let (block, read): (Self, usize) = bincode::serde::decode_from_slice(bytes, cfg).ok()?;
+        (read == bytes.len()).then_some(block)
+    }
+
+    /// Computes the canonical BLAKE3-256 hash of this block.
+    ///
+    /// This is [`Header::compute_hash`]: the transactions are covered
+    /// through [`Header::txs_root`], which block validation checks against
+    /// [`Block::txs`]. This must remain stable across nodes for consensus
+    /// to work correctly.
     pub fn compute_hash(&self) -> BlockHash {
-        let bytes = self.canonical_bytes();
-        BlockHash(Hash256::compute(&bytes))
+        self.header.compute_hash()
+    }
+
+    /// Returns `true` if [`Header::txs_root`] matches the transactions.
+    pub fn has_valid_txs_root(&self) -> bool {
+        self.header.txs_root == self.compute_txs_root()
+    }
+
+    /// Returns the leaves of the transactions tree, in block order.
+    fn tx_leaves(&self) -> Vec<Hash256> {
+        self.txs
+            .iter()
+            .map(|tx| tx.compute_hash().merkle_leaf())
+            .collect()
+    }
+
+    /// Computes the transactions root that [`Header::txs_root`] must hold.
+    pub fn compute_txs_root(&self) -> Hash256 {
+        merkle_root(&self.tx_leaves())
+    }
+
+    /// Returns a proof that `txs[index]` is part of this block, or `None`
+    /// if `index` is out of range.
+    ///
+    /// Verify it with `proof.verify(tx.compute_hash().merkle_leaf(),
+    /// &header.txs_root)`.
+    pub fn tx_proof(&self, index: usize) -> Option<MerkleProof> {
+        MerkleProof::build(&self.tx_leaves(), index)
     }
 
     /// Extracts all `(aid, evidence)` pairs from `TxRegisterModel` in this block.
@@ -113,6 +207,67 @@ impl Block {
     }
 }
 
+/// A block together with its canonical encoding and hash.
+///
+/// Importing a block needs its bytes (size check, storage) and its hash
+/// (indexing, tip updates, events) several times. Sealing computes both
+/// once; the fields are private, so they always match the block.
+///
+/// Dereferences to the inner [`Block`].
+#[derive(Clone, Debug)]
+pub struct SealedBlock {
+    block: Block,
+    bytes: Vec<u8>,
+    hash: BlockHash,
+}
+
+impl Block {
+    /// Encodes and hashes this block once, see [`SealedBlock`].
+    pub fn seal(self) -> SealedBlock {
+        SealedBlock {
+            bytes: self.canonical_bytes(),
+            hash: self.compute_hash(),
+            block: self,
+        }
+    }
+}
+
+impl SealedBlock {
+    /// Returns the sealed block.
+    pub fn block(&self) -> &Block {
+        &self.block
+    }
+
+    /// Returns the cached [`Block::canonical_bytes`].
+    pub fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// Returns the cached [`Block::compute_hash`].
+    pub fn hash(&self) -> BlockHash {
+        self.hash
+    }
+
+    /// Unwraps the block, dropping the cached encoding.
+    pub fn into_block(self) -> Block {
+        self.block
+    }
+}
+
+impl Deref for SealedBlock {
+    type Target = Block;
+
+    fn deref(&self) -> &Block {
+        &self.block
+    }
+}
+
+impl From<Block> for SealedBlock {
+    fn from(block: Block) -> Self {
+        block.seal()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -130,6 +285,9 @@ mod tests {
             height: 1,
             timestamp: 1_700_000_000,
             proposer: AccountId(Hash256([2u8; super::super::HASH_LEN])),
+            txs_root: Hash256([0u8; super::super::HASH_LEN]),
+            state_root: Hash256([0u8; super::super::HASH_LEN]),
+            receipts_root: Hash256([0u8; super::super::HASH_LEN]),
             pos_proof: None,
         };
 
@@ -151,6 +309,7 @@ mod tests {
         let tx = Transaction::RegisterModel(crate::types::tx::TxRegisterModel {
             owner: AccountId(Hash256([5u8; super::super::HASH_LEN])),
             aid,
+            chunked: None,
             evidence,
             fee: 0,
             nonce: 0,
@@ -166,5 +325,48 @@ mod tests {
         let h2 = block.compute_hash();
 
         assert_eq!(h1.0.as_bytes(), h2.0.as_bytes());
+
+        let bytes = block.canonical_bytes();
+        let sealed = block.seal();
+        assert_eq!(sealed.hash(), h1);
+        assert_eq!(sealed.bytes(), bytes.as_slice());
+    }
+
+    #[test]
+    fn tx_proofs_verify_against_txs_root() {
+        let txs: Vec<Transaction> = (0..5)
+            .map(|nonce| {
+                Transaction::Transfer(crate::types::TxTransfer {
+                    from: AccountId(Hash256([1u8; super::super::HASH_LEN])),
+                    to: AccountId(Hash256([2u8; super::super::HASH_LEN])),
+                    amount: 10,
+                    fee: 1,
+                    nonce,
+                    signature: crate::types::Signature(vec![]),
+                })
+            })
+            .collect();
+        let block = Block {
+            header: Header {
+                parent: BlockHash(Hash256([0u8; super::super::HASH_LEN])),
+                height: 1,
+                timestamp: 1_700_000_000,
+                proposer: AccountId(Hash256([2u8; super::super::HASH_LEN])),
+                txs_root: Hash256([0u8; super::super::HASH_LEN]),
+                state_root: Hash256([0u8; super::super::HASH_LEN]),
+                receipts_root: Hash256([0u8; super::super::HASH_LEN]),
+                pos_proof: None,
+            },
+            txs,
+        };
+        let root = block.compute_txs_root();
+
+        for (index, tx) in block.txs.iter().enumerate() {
+            let proof = block.tx_proof(index).expect("index in range");
+            assert!(proof.verify(tx.compute_hash().merkle_leaf(), &root));
+        }
+        let foreign = block.txs[0].compute_hash().merkle_leaf();
+        assert!(!block.tx_proof(1).unwrap().verify(foreign, &root));
+        assert!(block.tx_proof(5).is_none());
     }
 }
@@ -0,0 +1,91 @@
+//! Fraud proofs against proposers of blocks with fake ML evidence.
+//!
+//! When a node's ML verifier rejects an artefact in a block, the node can
+//! issue a [`FraudProof`] naming the block, its proposer, the offending
+//! artefact and the verifier's verdict. Wrapped in a
+//! [`TxSlash`](super::TxSlash), the proof burns part of the proposer's
+//! stake.
+//!
+//! An ML verdict cannot be recomputed on-chain, so a proof is only as good
+//! as its reporter: execution accepts proofs from staked validators and
+//! governance accounts only, signed with the reporter's ML-DSA key
+//! ([`FraudProof::verify_signature`]). The block a proof names must be
+//! stored, with the proof's proposer and height, and must register the
+//! proof's artefact; block validation checks this against the store (see
+//! [`StatefulValidity`](crate::validation::StatefulValidity)).
+
+use serde::{Deserialize, Serialize};
+
+use super::keys::SignatureError;
+use super::{AccountId, Aid, BlockHash, PublicKey, Signature};
+
+/// Domain tag prefixed to [`FraudProof::signing_bytes`], so reporter
+/// signatures cannot be replayed as signatures over other messages.
+const SIGNING_DOMAIN: &[u8] = b"mlsnitch/fraud/v1";
+
+/// The ML verifier's judgement behind a fraud proof.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct FraudVerdict {
+    /// Watermark scheme the artefact's evidence claimed.
+    pub scheme_id: String,
+    /// Confidence score reported by the verifier, if any.
+    pub score: Option<f32>,
+}
+
+/// Signed claim that a block carried inauthentic ML evidence.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct FraudProof {
+    /// Rejected block.
+    pub block_hash: BlockHash,
+    /// Height of `block_hash`.
+    pub height: u64,
+    /// Proposer of `block_hash`, whose stake is slashed.
+    pub proposer: AccountId,
+    /// Artefact the verifier judged inauthentic.
+    pub aid: Aid,
+    /// The verifier's verdict on `aid`.
+    pub verdict: FraudVerdict,
+    /// Account that observed the rejection.
+    pub reporter: AccountId,
+    /// Public key of `reporter`, which must hash to it.
+    pub reporter_key: PublicKey,
+    /// Signature by `reporter` over [`FraudProof::signing_bytes`].
+    pub signature: Signature,
+}
+
+impl FraudProof {
+    /// Returns the message the reporter signs: a domain tag followed by
+    /// the canonical encoding of the proof without its key and signature.
+    pub fn signing_bytes(&self) -> Vec<u8> {
+        let encoded = bincode::serde::encode_to_vec(
+            (
+                &self.block_hash,
+                self.height,
+                &self.proposer,
+                &self.aid,
+                &self.verdict,
+                &self.reporter,
+            ),
+            bincode::config::standard(),
+        )
+        .expect("FraudProof should always be serializable with bincode 2 + serde");
+        let mut bytes = SIGNING_DOMAIN.to_vec();
+        bytes.extend_from_slice(&encoded);
+        bytes
+    }
+
+    /// Checks that `reporter_key` belongs to the reporter and that the
+    /// signature over [`signing_bytes`](Self::signing_bytes) verifies
+    /// under it.
+    pub fn verify_signature(&self) -> Result<(), SignatureError> {
+        let key = AccountId::from_public_key(self.reporter_key.as_bytes());
+        if key != self.reporter {
+            return Err(SignatureError::KeyMismatch {
+                sender: self.reporter,
+                key,
+            });
+        }
+        self.reporter_key
+            .verify(&self.signing_bytes(), &self.signature)
+    }
+}
@@ -0,0 +1,220 @@
+//! Account keys and transaction signatures.
+//!
+//! Accounts sign with ML-DSA-65 (Dilithium3), using `pqcrypto-mldsa`. An
+//! [`AccountId`] is the hash of the account's public key
+//! ([`AccountId::from_public_key`]), and a transaction's signature covers
+//! its [`Transaction::signing_bytes`].
+//!
+//! Transactions carry only the signature, not the public key, so checking
+//! one needs the key from elsewhere, e.g. the submitting client
+//! ([`Transaction::verify_signature`]). Fraud proofs carry their
+//! reporter's key ([`FraudProof::verify_signature`]).
+
+use std::fmt;
+
+use pqcrypto_mldsa::mldsa65;
+use pqcrypto_traits::sign::{DetachedSignature as _, PublicKey as _, SecretKey as _};
+
+use super::{AccountId, FraudProof, PublicKey, Signature, Transaction};
+
+/// Errors raised when loading keys or checking a signature.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum SignatureError {
+    /// The public key is not a valid ML-DSA-65 key encoding.
+    MalformedPublicKey,
+    /// The secret key is not a valid ML-DSA-65 key encoding.
+    MalformedSecretKey,
+    /// The signature is not a valid ML-DSA-65 signature encoding.
+    MalformedSignature,
+    /// The public key does not belong to the transaction's sender.
+    KeyMismatch { sender: AccountId, key: AccountId },
+    /// The signature does not verify under the public key.
+    Invalid,
+}
+
+impl fmt::Display for SignatureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SignatureError::MalformedPublicKey => write!(f, "malformed ML-DSA-65 public key"),
+            SignatureError::MalformedSecretKey => write!(f, "malformed ML-DSA-65 secret key"),
+            SignatureError::MalformedSignature => write!(f, "malformed ML-DSA-65 signature"),
+            SignatureError::KeyMismatch { sender, key } => write!(
+                f,
+                "public key belongs to account {}, not sender {}",
+                hex::encode(key.as_hash().as_bytes()),
+                hex::encode(sender.as_hash().as_bytes())
+            ),
+            SignatureError::Invalid => write!(f, "signature does not verify"),
+        }
+    }
+}
+
+impl std::error::Error for SignatureError {}
+
+/// An account's ML-DSA-65 key pair.
+#[derive(Clone)]
+pub struct Keypair {
+    public: mldsa65::PublicKey,
+    secret: mldsa65::SecretKey,
+}
+
+impl Keypair {
+    /// Generates a fresh key pair from the operating system's RNG.
+    pub fn generate() -> Self {
+        let (public, secret) = mldsa65::keypair();
+        Self { public, secret }
+    }
+
+    /// Loads a key pair from the encodings returned by
+    /// [`public_key`](Self::public_key) and
+    /// [`secret_key_bytes`](Self::secret_key_bytes).
+    pub fn from_bytes(public: &[u8], secret: &[u8]) -> Result<Self, SignatureError> {
+        Ok(Self {
+            public: mldsa65::PublicKey::from_bytes(public)
+                .map_err(|_| SignatureError::MalformedPublicKey)?,
+            secret: mldsa65::SecretKey::from_bytes(secret)
+                .map_err(|_| SignatureError::MalformedSecretKey)?,
+        })
+    }
+
+    /// Returns the public key.
+    pub fn public_key(&self) -> PublicKey {
+        PublicKey(self.public.as_bytes().to_vec())
+    }
+
+    /// Returns the secret key encoding. Keep it secret.
+    pub fn secret_key_bytes(&self) -> &[u8] {
+        self.secret.as_bytes()
+    }
+
+    /// Returns the account this key pair controls.
+    pub fn account_id(&self) -> AccountId {
+        AccountId::from_public_key(self.public.as_bytes())
+    }
+
+    /// Signs `msg`, returning a detached signature.
+    pub fn sign(&self, msg: &[u8]) -> Signature {
+        Signature(
+            mldsa65::detached_sign(msg, &self.secret)
+                .as_bytes()
+                .to_vec(),
+        )
+    }
+
+    /// Signs `tx` in place, replacing its signature.
+    ///
+    /// The key pair should belong to [`Transaction::sender`]; otherwise the
+    /// signature will not pass [`Transaction::verify_signature`].
+    pub fn sign_tx(&self, tx: &mut Transaction) {
+        *tx.signature_mut() = self.sign(&tx.signing_bytes());
+    }
+
+    /// Signs `proof` in place as its reporter, replacing its reporter,
+    /// reporter key and signature.
+    pub fn sign_fraud_proof(&self, proof: &mut FraudProof) {
+        proof.reporter = self.account_id();
+        proof.reporter_key = self.public_key();
+        proof.signature = self.sign(&proof.signing_bytes());
+    }
+}
+
+impl fmt::Debug for Keypair {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Keypair")
+            .field(
+                "account",
+                &hex::encode(self.account_id().as_hash().as_bytes()),
+            )
+            .finish_non_exhaustive()
+    }
+}
+
+impl PublicKey {
+    /// Checks `signature` over `msg` under this key.
+    pub fn verify(&self, msg: &[u8], signature: &Signature) -> Result<(), SignatureError> {
+        let public = mldsa65::PublicKey::from_bytes(self.as_bytes())
+            .map_err(|_| SignatureError::MalformedPublicKey)?;
+        let signature = mldsa65::DetachedSignature::from_bytes(signature.as_bytes())
+            .map_err(|_| SignatureError::MalformedSignature)?;
+        mldsa65::verify_detached_signature(&signature, msg, &public)
+            .map_err(|_| SignatureError::Invalid)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Aid, BlockHash, FraudVerdict, HASH_LEN, Hash256, TxTransfer};
+
+    fn transfer(from: AccountId) -> Transaction {
+        Transaction::Transfer(TxTransfer {
+            from,
+            to: AccountId(Hash256([2; HASH_LEN])),
+            amount: 10,
+            fee: 1,
+            nonce: 0,
+            signature: Signature(Vec::new()),
+        })
+    }
+
+    #[test]
+    fn signed_transactions_verify_only_unchanged_and_under_the_senders_key() {
+        let keys = Keypair::generate();
+        let mut tx = transfer(keys.account_id());
+        let unsigned_bytes = tx.signing_bytes();
+        keys.sign_tx(&mut tx);
+        assert_eq!(tx.signing_bytes(), unsigned_bytes);
+        assert_eq!(tx.verify_signature(&keys.public_key()), Ok(()));
+
+        let reloaded =
+            Keypair::from_bytes(keys.public_key().as_bytes(), keys.secret_key_bytes()).unwrap();
+        assert_eq!(reloaded.account_id(), keys.account_id());
+
+        let mut tampered = tx.clone();
+        if let Transaction::Transfer(t) = &mut tampered {
+            t.amount = 11;
+        }
+        assert_eq!(
+            tampered.verify_signature(&keys.public_key()),
+            Err(SignatureError::Invalid)
+        );
+
+        let other = Keypair::generate();
+        assert!(matches!(
+            tx.verify_signature(&other.public_key()),
+            Err(SignatureError::KeyMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn fraud_proofs_verify_only_under_the_reporters_key() {
+        let keys = Keypair::generate();
+        let mut proof = FraudProof {
+            block_hash: BlockHash(Hash256([3; HASH_LEN])),
+            height: 4,
+            proposer: AccountId(Hash256([5; HASH_LEN])),
+            aid: Aid(Hash256([6; HASH_LEN])),
+            verdict: FraudVerdict {
+                scheme_id: "wm-test".to_string(),
+                score: Some(0.1),
+            },
+            reporter: AccountId(Hash256([0; HASH_LEN])),
+            reporter_key: PublicKey(Vec::new()),
+            signature: Signature(Vec::new()),
+        };
+        keys.sign_fraud_proof(&mut proof);
+        assert_eq!(proof.reporter, keys.account_id());
+        assert_eq!(proof.verify_signature(), Ok(()));
+
+        let mut tampered = proof.clone();
+        tampered.height = 5;
+        assert_eq!(tampered.verify_signature(), Err(SignatureError::Invalid));
+
+        let mut foreign = proof.clone();
+        foreign.reporter_key = Keypair::generate().public_key();
+        assert!(matches!(
+            foreign.verify_signature(),
+            Err(SignatureError::KeyMismatch { .. })
+        ));
+    }
+}
@@ -0,0 +1,210 @@
+//! Canonical artefact manifests.
+//!
+//! A model artefact is often more than one file (weights, tokenizer,
+//! config). Rather than letting clients pick "whatever bytes they hashed"
+//! as the [`Aid`], an [`ArtefactManifest`] lists the per-file hashes and
+//! the watermark evidence hash, and the `Aid` is derived from the manifest's
+//! canonical encoding. Two manifests with the same components always map to
+//! the same `Aid`, and the `Aid` commits to the evidence it was registered
+//! with.
+//!
+//! Serialization uses the same bincode-2 `standard()` configuration as
+//! [`Block::canonical_bytes`](super::Block::canonical_bytes).
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use super::{Aid, EvidenceHash, EvidenceRef, HASH_LEN, Hash256};
+
+/// Current manifest format version.
+pub const MANIFEST_VERSION: u8 = 1;
+
+/// Canonical description of a (possibly multi-file) model artefact.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct ArtefactManifest {
+    /// Manifest format version; must equal [`MANIFEST_VERSION`].
+    pub version: u8,
+    /// BLAKE3-256 hash of the model weights file.
+    pub model_hash: Hash256,
+    /// BLAKE3-256 hash of the tokenizer file, if the model has one.
+    pub tokenizer_hash: Option<Hash256>,
+    /// BLAKE3-256 hash of the model config file, if the model has one.
+    pub config_hash: Option<Hash256>,
+    /// Hash of the watermark evidence bound to this artefact.
+    pub evidence_hash: EvidenceHash,
+}
+
+/// Reasons a manifest is rejected.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ManifestError {
+    /// The manifest uses a format version this node does not understand.
+    UnsupportedVersion(u8),
+    /// A component hash is all zeroes, i.e. was never filled in.
+    EmptyHash(&'static str),
+    /// The registered `Aid` is not the one derived from the manifest.
+    AidMismatch,
+    /// The registration's evidence hash differs from the manifest's.
+    EvidenceMismatch,
+}
+
+impl fmt::Display for ManifestError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ManifestError::UnsupportedVersion(v) => {
+                write!(f, "unsupported manifest version {v}")
+            }
+            ManifestError::EmptyHash(field) => write!(f, "manifest {field} is empty"),
+            ManifestError::AidMismatch => write!(f, "aid does not match manifest"),
+            ManifestError::EvidenceMismatch => {
+                write!(f, "evidence hash does not match manifest")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ManifestError {}
+
+impl ArtefactManifest {
+    /// Builds a current-version manifest from its components.
+    pub fn new(
+        model_hash: Hash256,
+        tokenizer_hash: Option<Hash256>,
+        config_hash: Option<Hash256>,
+        evidence_hash: EvidenceHash,
+    ) -> Self {
+        Self {
+            version: MANIFEST_VERSION,
+            model_hash,
+            tokenizer_hash,
+            config_hash,
+            evidence_hash,
+        }
+    }
+
+    /// Returns the canonical byte representation of this manifest.
+    ///
+    /// # Panics
+    ///
+    /// Panics if encoding fails, which would be a programming error.
+    pub fn canonical_bytes(&self) -> Vec<u8> {
+        let cfg = bincode::config::standard();
+        bincode::serde::encode_to_vec(self, cfg)
+            .expect("ArtefactManifest should always be serializable with bincode 2 + serde")
+    }
+
+    /// Derives the artefact identifier from the canonical encoding.
+    pub fn aid(&self) -> Aid {
+        Aid(Hash256::compute(&self.canonical_bytes()))
+    }
+
+    /// Checks the manifest is well-formed.
+    pub fn validate(&self) -> Result<(), ManifestError> {
+        if self.version != MANIFEST_VERSION {
+            return Err(ManifestError::UnsupportedVersion(self.version));
+        }
+
+        let empty = Hash256([0u8; HASH_LEN]);
+        if self.model_hash == empty {
+            return Err(ManifestError::EmptyHash("model_hash"));
+        }
+        if self.tokenizer_hash == Some(empty) {
+            return Err(ManifestError::EmptyHash("tokenizer_hash"));
+        }
+        if self.config_hash == Some(empty) {
+            return Err(ManifestError::EmptyHash("config_hash"));
+        }
+        if *self.evidence_hash.as_hash() == empty {
+            return Err(ManifestError::EmptyHash("evidence_hash"));
+        }
+
+        Ok(())
+    }
+
+    /// Checks that a registration of `aid` with `evidence` is consistent
+    /// with this manifest.
+    pub fn check_registration(
+        &self,
+        aid: &Aid,
+        evidence: &EvidenceRef,
+    ) -> Result<(), ManifestError> {
+        self.validate()?;
+        if self.aid() != *aid {
+            return Err(ManifestError::AidMismatch);
+        }
+        if self.evidence_hash != evidence.evidence_hash {
+            return Err(ManifestError::EvidenceMismatch);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::WmProfile;
+
+    fn manifest() -> ArtefactManifest {
+        ArtefactManifest::new(
+            Hash256::compute(b"weights"),
+            Some(Hash256::compute(b"tokenizer")),
+            None,
+            EvidenceHash::from_bytes(b"wm-key"),
+        )
+    }
+
+    #[test]
+    fn aid_is_deterministic_and_covers_every_component() {
+        let m = manifest();
+        assert_eq!(m.aid(), manifest().aid());
+
+        let mut other = manifest();
+        other.config_hash = Some(Hash256::compute(b"config"));
+        assert_ne!(m.aid(), other.aid());
+
+        let mut other = manifest();
+        other.evidence_hash = EvidenceHash::from_bytes(b"other-wm-key");
+        assert_ne!(m.aid(), other.aid());
+    }
+
+    #[test]
+    fn validate_rejects_bad_version_and_empty_hashes() {
+        assert!(manifest().validate().is_ok());
+
+        let mut m = manifest();
+        m.version = 99;
+        assert_eq!(m.validate(), Err(ManifestError::UnsupportedVersion(99)));
+
+        let mut m = manifest();
+        m.model_hash = Hash256([0u8; HASH_LEN]);
+        assert_eq!(m.validate(), Err(ManifestError::EmptyHash("model_hash")));
+    }
+
+    #[test]
+    fn check_registration_requires_matching_aid_and_evidence() {
+        let m = manifest();
+        let evidence = EvidenceRef {
+            scheme_id: "multi_factor_v1".to_string(),
+            evidence_hash: m.evidence_hash,
+            wm_profile: WmProfile {
+                tau_input: 0.9,
+                tau_feat: 0.1,
+                logit_band_low: 0.02,
+                logit_band_high: 0.05,
+            },
+        };
+
+        assert!(m.check_registration(&m.aid(), &evidence).is_ok());
+        assert_eq!(
+            m.check_registration(&Aid(Hash256::compute(b"weights")), &evidence),
+            Err(ManifestError::AidMismatch)
+        );
+
+        let mut wrong_evidence = evidence.clone();
+        wrong_evidence.evidence_hash = EvidenceHash::from_bytes(b"other");
+        assert_eq!(
+            m.check_registration(&m.aid(), &wrong_evidence),
+            Err(ManifestError::EvidenceMismatch)
+        );
+    }
+}
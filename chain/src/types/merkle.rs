@@ -0,0 +1,133 @@
+//! Binary Merkle trees over 256-bit leaves.
+//!
+//! Leaves and inner nodes are domain-separated (`0x00` / `0x01` prefixes)
+//! so an inner node can never be passed off as a leaf. On a level with an
+//! odd number of nodes the last node is carried up unchanged. The empty
+//! tree has the all-zero root.
+//!
+//! The same tree backs the transactions root and the state root of a
+//! [`Header`](super::Header). A [`MerkleProof`] shows that a single leaf
+//! is part of a given root, which is enough for a light client to check a
+//! transaction or a state entry against a block header.
+
+use serde::{Deserialize, Serialize};
+
+use super::{HASH_LEN, Hash256};
+
+/// Prefix of leaf preimages.
+pub(crate) const LEAF_PREFIX: u8 = 0x00;
+/// Prefix of inner node preimages.
+const NODE_PREFIX: u8 = 0x01;
+
+/// Root of the empty tree.
+pub const EMPTY_ROOT: Hash256 = Hash256([0u8; HASH_LEN]);
+
+fn node_hash(left: &Hash256, right: &Hash256) -> Hash256 {
+    let mut preimage = Vec::with_capacity(1 + 2 * HASH_LEN);
+    preimage.push(NODE_PREFIX);
+    preimage.extend_from_slice(left.as_bytes());
+    preimage.extend_from_slice(right.as_bytes());
+    Hash256::compute(&preimage)
+}
+
+/// Hashes one tree level into the next.
+fn next_level(level: &[Hash256]) -> Vec<Hash256> {
+    level
+        .chunks(2)
+        .map(|pair| match pair {
+            [left, right] => node_hash(left, right),
+            [single] => *single,
+            _ => unreachable!("chunks(2) yields one or two nodes"),
+        })
+        .collect()
+}
+
+/// Returns the Merkle root over `leaves`.
+pub fn merkle_root(leaves: &[Hash256]) -> Hash256 {
+    if leaves.is_empty() {
+        return EMPTY_ROOT;
+    }
+    let mut level = leaves.to_vec();
+    while level.len() > 1 {
+        level = next_level(&level);
+    }
+    level[0]
+}
+
+/// Side of the tree a proof sibling sits on.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum Side {
+    Left,
+    Right,
+}
+
+/// Inclusion proof for one leaf: the siblings on its path to the root,
+/// from the bottom up.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct MerkleProof {
+    pub siblings: Vec<(Side, Hash256)>,
+}
+
+impl MerkleProof {
+    /// Builds the proof for `leaves[index]`, or `None` if out of range.
+    pub fn build(leaves: &[Hash256], mut index: usize) -> Option<Self> {
+        if index >= leaves.len() {
+            return None;
+        }
+        let mut siblings = Vec::new();
+        let mut level = leaves.to_vec();
+        while level.len() > 1 {
+            let sibling = index ^ 1;
+            if sibling < level.len() {
+                let side = if sibling < index {
+                    Side::Left
+                } else {
+                    Side::Right
+                };
+                siblings.push((side, level[sibling]));
+            }
+            level = next_level(&level);
+            index /= 2;
+        }
+        Some(Self { siblings })
+    }
+
+    /// Returns the root this proof yields for `leaf`.
+    pub fn root_for(&self, leaf: Hash256) -> Hash256 {
+        self.siblings
+            .iter()
+            .fold(leaf, |acc, (side, sibling)| match side {
+                Side::Left => node_hash(sibling, &acc),
+                Side::Right => node_hash(&acc, sibling),
+            })
+    }
+
+    /// Returns `true` if `leaf` is part of the tree with root `root`.
+    pub fn verify(&self, leaf: Hash256, root: &Hash256) -> bool {
+        self.root_for(leaf) == *root
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn leaves(n: u8) -> Vec<Hash256> {
+        (0..n).map(|b| Hash256::compute(&[b])).collect()
+    }
+
+    #[test]
+    fn proofs_verify_for_every_leaf_and_tree_size() {
+        for n in 1..=9 {
+            let leaves = leaves(n);
+            let root = merkle_root(&leaves);
+            for (index, leaf) in leaves.iter().enumerate() {
+                let proof = MerkleProof::build(&leaves, index).expect("index in range");
+                assert!(proof.verify(*leaf, &root), "leaf {index} of {n}");
+                assert!(!proof.verify(Hash256::compute(b"other"), &root));
+            }
+            assert!(MerkleProof::build(&leaves, n as usize).is_none());
+        }
+        assert_eq!(merkle_root(&[]), EMPTY_ROOT);
+    }
+}
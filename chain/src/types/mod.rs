@@ -5,18 +5,46 @@
 //! across the chain implementation. The goal is to avoid "naked" byte
 //! buffers in public APIs and instead use domain-specific newtypes.
 
-use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
 
+use hex::FromHex;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// Bech32m addresses for accounts and artefacts.
+pub mod address;
 /// Types related to ML artefacts stored and referenced on-chain.
 pub mod artefact;
 /// Types for blocks, headers, and block hashes.
 pub mod block;
+/// Fraud proofs against proposers of blocks with fake ML evidence.
+pub mod fraud;
+/// ML-DSA account key pairs and transaction signatures.
+pub mod keys;
+/// Canonical multi-file artefact manifests and manifest-derived `Aid`s.
+pub mod manifest;
+/// Binary Merkle trees and inclusion proofs.
+pub mod merkle;
+/// Registry of accepted watermark schemes and their profile rules.
+pub mod scheme;
 /// Types for transactions and transaction payloads.
 pub mod tx;
 
-pub use artefact::ArtefactMetadata;
-pub use block::{Block, BlockHash, Header};
-pub use tx::{ModelUseMetadata, Transaction, TxRegisterModel, TxTransfer, TxUseModel};
+pub use address::{ACCOUNT_HRP, AID_HRP, AddressError};
+pub use artefact::{
+    ArtefactMetadata, ChunkError, ChunkedAid, ChunkedArtefact, DEFAULT_CHUNK_SIZE, MAX_CHUNK_SIZE,
+    MIN_CHUNK_SIZE, chunk_hash,
+};
+pub use block::{Block, BlockHash, Header, PosProof, SealedBlock};
+pub use fraud::{FraudProof, FraudVerdict};
+pub use keys::{Keypair, SignatureError};
+pub use manifest::{ArtefactManifest, MANIFEST_VERSION, ManifestError};
+pub use merkle::{EMPTY_ROOT, MerkleProof, Side, merkle_root};
+pub use scheme::{ProfileRange, SchemeError, SchemeRegistry, SchemeRule};
+pub use tx::{
+    ModelUseMetadata, Transaction, TxHash, TxRegisterModel, TxRevokeModel, TxSlash, TxStake,
+    TxTransfer, TxTransferModel, TxUnstake, TxUseModel,
+};
 
 /// Length in bytes of all 256-bit hash types used in this module.
 pub const HASH_LEN: usize = 32;
@@ -26,7 +54,12 @@ pub const HASH_LEN: usize = 32;
 /// This type is used as the backing representation for all fixed-size hashes
 /// in the chain (account identifiers, artefact identifiers, watermark
 /// evidence hashes, etc.). It is always exactly [`HASH_LEN`] bytes long.
-#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
+///
+/// It displays and parses as 64 lowercase hex characters. Serde uses the
+/// same hex string for human-readable formats (JSON, TOML, YAML) and the
+/// raw bytes for binary ones, so the canonical bincode encoding, and with
+/// it every hash computed over it, is unaffected.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
 pub struct Hash256(pub [u8; HASH_LEN]);
 
 impl Hash256 {
@@ -49,6 +82,43 @@ impl Hash256 {
     }
 }
 
+impl fmt::Display for Hash256 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&hex::encode(self.0))
+    }
+}
+
+impl FromStr for Hash256 {
+    type Err = hex::FromHexError;
+
+    /// Parses exactly 64 hex characters, in either case.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        <[u8; HASH_LEN]>::from_hex(s).map(Hash256)
+    }
+}
+
+impl Serialize for Hash256 {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.collect_str(self)
+        } else {
+            serializer.serialize_newtype_struct("Hash256", &self.0)
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Hash256 {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        if deserializer.is_human_readable() {
+            String::deserialize(deserializer)?
+                .parse()
+                .map_err(serde::de::Error::custom)
+        } else {
+            <[u8; HASH_LEN]>::deserialize(deserializer).map(Hash256)
+        }
+    }
+}
+
 /// Account identifier (hash of the Dilithium public key).
 ///
 /// `AccountId` is derived from a Dilithium / ML-DSA public key using
@@ -126,6 +196,14 @@ impl EvidenceHash {
         EvidenceHash(Hash256::compute(bytes))
     }
 
+    /// Returns `true` if `payload` hashes to this evidence hash.
+    ///
+    /// Use this whenever the raw evidence payload is available, instead of
+    /// trusting a client-supplied hash.
+    pub fn matches_payload(&self, payload: &[u8]) -> bool {
+        *self == Self::from_bytes(payload)
+    }
+
     /// Returns the underlying [`Hash256`] backing this evidence hash.
     pub fn as_hash(&self) -> &Hash256 {
         &self.0
@@ -145,7 +223,8 @@ impl Aid {
     ///
     /// The caller must ensure that `model_bytes` is a canonical encoding
     /// (for example, a normalised archive format), otherwise logically
-    /// equivalent models may receive different identifiers.
+    /// equivalent models may receive different identifiers. Multi-file
+    /// artefacts should use [`ArtefactManifest::aid`] instead.
     pub fn from_model_bytes(model_bytes: &[u8]) -> Self {
         Aid(Hash256::compute(model_bytes))
     }
@@ -236,6 +315,34 @@ mod tests {
         assert_eq!(ev.as_hash(), &expected_ev);
     }
 
+    #[test]
+    fn evidence_hash_matches_only_its_payload() {
+        let ev = EvidenceHash::from_bytes(b"wm-key-and-params");
+
+        assert!(ev.matches_payload(b"wm-key-and-params"));
+        assert!(!ev.matches_payload(b"wm-key-and-params-tampered"));
+    }
+
+    #[test]
+    fn hash256_is_hex_in_json_and_raw_bytes_in_bincode() {
+        let hash = Hash256::compute(b"hello");
+        let hex = hex::encode(hash.as_bytes());
+        assert_eq!(hash.to_string(), hex);
+        assert_eq!(hex.to_uppercase().parse::<Hash256>(), Ok(hash));
+        assert!("abcd".parse::<Hash256>().is_err());
+
+        let json = serde_json::to_string(&AccountId(hash)).unwrap();
+        assert_eq!(json, format!("\"{hex}\""));
+        assert_eq!(serde_json::from_str::<AccountId>(&json).unwrap().0, hash);
+
+        let cfg = bincode::config::standard();
+        let bytes = bincode::serde::encode_to_vec(hash, cfg).unwrap();
+        assert_eq!(bytes, hash.as_bytes());
+        let (decoded, _): (Hash256, usize) =
+            bincode::serde::decode_from_slice(&bytes, cfg).unwrap();
+        assert_eq!(decoded, hash);
+    }
+
     #[test]
     fn evidence_ref_serde_roundtrip() {
         let wm_profile = WmProfile {
@@ -0,0 +1,268 @@
+//! Registry of the watermark schemes a network accepts.
+//!
+//! Registrations name their watermarking scheme in
+//! [`EvidenceRef::scheme_id`]. A [`SchemeRegistry`] lists the schemes the
+//! network knows, each with a [`SchemeRule`] bounding the fields of the
+//! registration's [`WmProfile`] and, optionally, the minimum verdict score
+//! the ML service must report for the scheme.
+//!
+//! The registry is part of the network parameters fixed at genesis
+//! ([`ChainParams::schemes`](crate::execution::ChainParams::schemes)), so
+//! every node applies the same rules. An empty registry accepts any
+//! scheme, which keeps networks that predate it working unchanged.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use super::{EvidenceRef, WmProfile};
+
+/// Inclusive range a watermark profile field must lie in.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProfileRange {
+    /// Smallest allowed value.
+    pub min: f32,
+    /// Largest allowed value.
+    pub max: f32,
+}
+
+impl ProfileRange {
+    /// Returns `true` if `value` lies within the range.
+    pub fn contains(&self, value: f32) -> bool {
+        (self.min..=self.max).contains(&value)
+    }
+}
+
+/// Validation rules for one watermark scheme. Fields left unset are not
+/// constrained.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SchemeRule {
+    /// Allowed range of [`WmProfile::tau_input`].
+    pub tau_input: Option<ProfileRange>,
+    /// Allowed range of [`WmProfile::tau_feat`].
+    pub tau_feat: Option<ProfileRange>,
+    /// Allowed range of [`WmProfile::logit_band_low`].
+    pub logit_band_low: Option<ProfileRange>,
+    /// Allowed range of [`WmProfile::logit_band_high`].
+    pub logit_band_high: Option<ProfileRange>,
+    /// Minimum ML verdict score for artefacts of this scheme; the service's
+    /// boolean verdict is used if unset.
+    pub min_score: Option<f32>,
+}
+
+impl SchemeRule {
+    /// Returns each profile field with its value in `profile` and the
+    /// range it must lie in.
+    fn fields<'a>(
+        &'a self,
+        profile: &WmProfile,
+    ) -> [(&'static str, f32, Option<&'a ProfileRange>); 4] {
+        [
+            ("tau_input", profile.tau_input, self.tau_input.as_ref()),
+            ("tau_feat", profile.tau_feat, self.tau_feat.as_ref()),
+            (
+                "logit_band_low",
+                profile.logit_band_low,
+                self.logit_band_low.as_ref(),
+            ),
+            (
+                "logit_band_high",
+                profile.logit_band_high,
+                self.logit_band_high.as_ref(),
+            ),
+        ]
+    }
+}
+
+/// Reasons a registration is rejected by the [`SchemeRegistry`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum SchemeError {
+    /// The registry does not list the scheme.
+    Unknown(String),
+    /// A profile field lies outside the scheme's range.
+    OutOfRange {
+        scheme: String,
+        field: &'static str,
+        value: f32,
+        range: ProfileRange,
+    },
+}
+
+impl fmt::Display for SchemeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SchemeError::Unknown(scheme) => write!(f, "unknown watermark scheme {scheme:?}"),
+            SchemeError::OutOfRange {
+                scheme,
+                field,
+                value,
+                range,
+            } => write!(
+                f,
+                "{field}={value} is outside [{}, {}] for watermark scheme {scheme:?}",
+                range.min, range.max
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SchemeError {}
+
+/// Watermark schemes accepted by the network, keyed by `scheme_id`.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct SchemeRegistry {
+    schemes: BTreeMap<String, SchemeRule>,
+}
+
+impl SchemeRegistry {
+    /// Creates an empty registry, which accepts any scheme.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds or replaces the rule for `scheme_id`.
+    pub fn with_scheme(mut self, scheme_id: impl Into<String>, rule: SchemeRule) -> Self {
+        self.schemes.insert(scheme_id.into(), rule);
+        self
+    }
+
+    /// Returns `true` if no scheme is listed, i.e. any scheme is accepted.
+    pub fn is_empty(&self) -> bool {
+        self.schemes.is_empty()
+    }
+
+    /// Returns the rule for `scheme_id`, if listed.
+    pub fn get(&self, scheme_id: &str) -> Option<&SchemeRule> {
+        self.schemes.get(scheme_id)
+    }
+
+    /// Iterates over the listed schemes in `scheme_id` order.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &SchemeRule)> {
+        self.schemes.iter().map(|(id, rule)| (id.as_str(), rule))
+    }
+
+    /// Checks `evidence` against the rule for its scheme.
+    pub fn check(&self, evidence: &EvidenceRef) -> Result<(), SchemeError> {
+        if self.is_empty() {
+            return Ok(());
+        }
+        let scheme = &evidence.scheme_id;
+        let rule = self
+            .get(scheme)
+            .ok_or_else(|| SchemeError::Unknown(scheme.clone()))?;
+        for (field, value, range) in rule.fields(&evidence.wm_profile) {
+            if let Some(range) = range
+                && !range.contains(value)
+            {
+                return Err(SchemeError::OutOfRange {
+                    scheme: scheme.clone(),
+                    field,
+                    value,
+                    range: *range,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns a description of every malformed rule: empty or reversed
+    /// ranges, non-finite bounds, and scores outside `[0, 1]`.
+    pub fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        for (scheme, rule) in &self.schemes {
+            let ranges = [
+                ("tau_input", rule.tau_input),
+                ("tau_feat", rule.tau_feat),
+                ("logit_band_low", rule.logit_band_low),
+                ("logit_band_high", rule.logit_band_high),
+            ];
+            for (field, range) in ranges {
+                if let Some(range) = range
+                    && !(range.min.is_finite() && range.max.is_finite() && range.min <= range.max)
+                {
+                    problems.push(format!(
+                        "{scheme}.{field} must have finite bounds with min <= max"
+                    ));
+                }
+            }
+            if let Some(score) = rule.min_score
+                && !(0.0..=1.0).contains(&score)
+            {
+                problems.push(format!("{scheme}.min_score must be between 0 and 1"));
+            }
+        }
+        problems
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{EvidenceHash, HASH_LEN, Hash256};
+
+    fn evidence(scheme_id: &str, tau_input: f32) -> EvidenceRef {
+        EvidenceRef {
+            scheme_id: scheme_id.to_string(),
+            evidence_hash: EvidenceHash(Hash256([7; HASH_LEN])),
+            wm_profile: WmProfile {
+                tau_input,
+                tau_feat: 0.2,
+                logit_band_low: -0.05,
+                logit_band_high: 0.05,
+            },
+        }
+    }
+
+    fn registry() -> SchemeRegistry {
+        SchemeRegistry::new().with_scheme(
+            "wm-laplace-v1",
+            SchemeRule {
+                tau_input: Some(ProfileRange { min: 0.8, max: 1.0 }),
+                min_score: Some(0.7),
+                ..SchemeRule::default()
+            },
+        )
+    }
+
+    #[test]
+    fn empty_registry_accepts_any_scheme() {
+        assert!(
+            SchemeRegistry::new()
+                .check(&evidence("anything", 5.0))
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn registry_rejects_unknown_schemes_and_out_of_range_profiles() {
+        let registry = registry();
+        assert!(registry.check(&evidence("wm-laplace-v1", 0.9)).is_ok());
+        assert_eq!(
+            registry.check(&evidence("wm-other", 0.9)),
+            Err(SchemeError::Unknown("wm-other".to_string()))
+        );
+        match registry.check(&evidence("wm-laplace-v1", 0.5)) {
+            Err(SchemeError::OutOfRange { field, value, .. }) => {
+                assert_eq!((field, value), ("tau_input", 0.5));
+            }
+            other => panic!("unexpected result: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn problems_reports_malformed_rules() {
+        let registry = registry().with_scheme(
+            "wm-bad",
+            SchemeRule {
+                tau_feat: Some(ProfileRange { min: 1.0, max: 0.0 }),
+                min_score: Some(1.5),
+                ..SchemeRule::default()
+            },
+        );
+        assert_eq!(registry.problems().len(), 2);
+    }
+}
@@ -6,12 +6,42 @@
 //! along with a tagged [`Transaction`] enum. Transactions cover:
 //!
 //! - registering new ML model artefacts on-chain,
-//! - recording usage events for existing models, and
-//! - simple value transfers between accounts.
+//! - recording usage events for existing models,
+//! - transferring ownership of registered models,
+//! - revoking registered models,
+//! - simple value transfers between accounts,
+//! - bonding and unbonding proposer stake, and
+//! - slashing proposers with a fraud proof.
 
 use serde::{Deserialize, Serialize};
 
-use super::{AccountId, Aid, EvidenceRef, Signature};
+use super::keys::SignatureError;
+use super::merkle::LEAF_PREFIX;
+use super::{
+    AccountId, Aid, ChunkedAid, EvidenceRef, FraudProof, HASH_LEN, Hash256, PublicKey, Signature,
+};
+
+/// Domain tag prefixed to [`Transaction::signing_bytes`], so transaction
+/// signatures cannot be replayed as signatures over other messages.
+const SIGNING_DOMAIN: &[u8] = b"mlsnitch/tx/v1";
+
+/// Hash of a transaction.
+///
+/// This is the BLAKE3-256 digest of [`Transaction::canonical_bytes`],
+/// including the signature.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub struct TxHash(pub Hash256);
+
+impl TxHash {
+    /// Returns the leaf committing to this transaction in a block's
+    /// transactions tree.
+    pub fn merkle_leaf(&self) -> Hash256 {
+        let mut preimage = Vec::with_capacity(1 + HASH_LEN);
+        preimage.push(LEAF_PREFIX);
+        preimage.extend_from_slice(self.0.as_bytes());
+        Hash256::compute(&preimage)
+    }
+}
 
 /// Transaction that registers a new ML model artefact on-chain.
 ///
@@ -34,6 +64,14 @@ pub struct TxRegisterModel {
     /// same logical artefact always maps to the same `Aid`.
     pub aid: Aid,
 
+    /// Chunked form of the identifier, for large models hashed in chunks.
+    ///
+    /// When present, `aid` must equal [`ChunkedAid::aid`] of it; it is
+    /// recorded in the registry so that single chunks of the model can
+    /// later be verified against the chain.
+    #[serde(default)]
+    pub chunked: Option<ChunkedAid>,
+
     /// Reference to authenticity / watermark evidence.
     ///
     /// This ties the registration to a particular watermarking scheme and
@@ -49,9 +87,9 @@ pub struct TxRegisterModel {
 
     /// Anti-replay nonce relative to the owner account.
     ///
-    /// Typically this is a monotonically increasing counter per account.
-    /// Nodes reject transactions whose nonce is not strictly greater than
-    /// the stored nonce for the signer.
+    /// This is a per-account counter starting at 0. The execution layer
+    /// rejects transactions whose nonce is not exactly the signer's next
+    /// expected nonce (see [`crate::execution::Account::nonce`]).
     pub nonce: u64,
 
     /// Owner's signature over the canonical encoding of this transaction.
@@ -109,6 +147,65 @@ pub struct TxUseModel {
     pub signature: Signature,
 }
 
+/// Transaction that hands a registered model to a new owner.
+///
+/// Only the artefact's current owner may transfer it; the execution layer
+/// rejects the transaction if `current_owner` does not match the owner
+/// recorded in [`ArtefactMetadata`](crate::types::ArtefactMetadata). The
+/// `aid`, evidence and registration height are unchanged.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TxTransferModel {
+    /// Account that currently owns the model.
+    ///
+    /// This is the account that pays the fee and signs the transaction.
+    pub current_owner: AccountId,
+
+    /// Account that becomes the owner of the model.
+    pub new_owner: AccountId,
+
+    /// Identifier of the model being transferred.
+    ///
+    /// Must refer to an existing, previously registered [`Aid`].
+    pub aid: Aid,
+
+    /// Fee paid by `current_owner` to include this transfer.
+    pub fee: u64,
+
+    /// Anti-replay nonce relative to the `current_owner` account.
+    pub nonce: u64,
+
+    /// Signature by `current_owner` over the canonical encoding.
+    pub signature: Signature,
+}
+
+/// Transaction that takes a registered model off the registry.
+///
+/// May be signed by the artefact's current owner or by one of the
+/// governance accounts fixed in the genesis spec. Revocation is final: the
+/// artefact's [`ArtefactMetadata::revoked_at`] is set to the block height
+/// and later `TxUseModel` records for the `aid` are rejected.
+///
+/// [`ArtefactMetadata::revoked_at`]: crate::types::ArtefactMetadata::revoked_at
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TxRevokeModel {
+    /// Owner or governance account revoking the model.
+    ///
+    /// This is the account that pays the fee and signs the transaction.
+    pub signer: AccountId,
+
+    /// Identifier of the model being revoked.
+    pub aid: Aid,
+
+    /// Fee paid by `signer` to include this revocation.
+    pub fee: u64,
+
+    /// Anti-replay nonce relative to the `signer` account.
+    pub nonce: u64,
+
+    /// Signature by `signer` over the canonical encoding.
+    pub signature: Signature,
+}
+
 /// Optional simple value-transfer transaction.
 ///
 /// `TxTransfer` is intentionally minimal: it moves a fungible balance from
@@ -135,6 +232,77 @@ pub struct TxTransfer {
     pub signature: Signature,
 }
 
+/// Transaction that bonds part of an account's balance as proposer stake.
+///
+/// Bonded stake counts towards stake-weighted proposer selection from the
+/// next epoch boundary on (see [`crate::execution::StakingConfig`]).
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TxStake {
+    /// Account bonding the stake.
+    ///
+    /// This is the account that pays `amount` plus the fee and signs the
+    /// transaction.
+    pub staker: AccountId,
+
+    /// Amount moved from the balance into bonded stake.
+    pub amount: u64,
+
+    /// Fee paid by `staker` to include this transaction.
+    pub fee: u64,
+
+    /// Anti-replay nonce relative to the `staker` account.
+    pub nonce: u64,
+
+    /// Signature by `staker` over the canonical encoding.
+    pub signature: Signature,
+}
+
+/// Transaction that starts unbonding previously bonded stake.
+///
+/// The amount stops counting as stake at the next epoch boundary and is
+/// returned to the balance once the unbonding delay has passed.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TxUnstake {
+    /// Account unbonding the stake.
+    ///
+    /// This is the account that pays the fee and signs the transaction.
+    pub staker: AccountId,
+
+    /// Amount of bonded stake to unbond.
+    pub amount: u64,
+
+    /// Fee paid by `staker` to include this transaction.
+    pub fee: u64,
+
+    /// Anti-replay nonce relative to the `staker` account.
+    pub nonce: u64,
+
+    /// Signature by `staker` over the canonical encoding.
+    pub signature: Signature,
+}
+
+/// Transaction that slashes a proposer for a block with fake ML evidence.
+///
+/// Signed and paid for by the proof's reporter. Execution burns
+/// [`StakingConfig::slash_percent`] of the proposer's bonded and unbonding
+/// stake, and slashes each block at most once.
+///
+/// [`StakingConfig::slash_percent`]: crate::execution::StakingConfig::slash_percent
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TxSlash {
+    /// Evidence against the proposer.
+    pub proof: FraudProof,
+
+    /// Fee paid by the reporter to include this transaction.
+    pub fee: u64,
+
+    /// Anti-replay nonce relative to the reporter account.
+    pub nonce: u64,
+
+    /// Signature by the reporter over the canonical encoding.
+    pub signature: Signature,
+}
+
 /// Top-level transaction enum.
 ///
 /// This is the type that appears in blocks and mempool structures. For
@@ -150,6 +318,160 @@ pub enum Transaction {
 
     /// Simple fungible value transfer between accounts.
     Transfer(TxTransfer),
+
+    /// Transfers ownership of a registered model.
+    TransferModel(TxTransferModel),
+
+    /// Revokes a registered model.
+    RevokeModel(TxRevokeModel),
+
+    /// Bonds proposer stake.
+    Stake(TxStake),
+
+    /// Unbonds proposer stake.
+    Unstake(TxUnstake),
+
+    /// Slashes a proposer's stake.
+    Slash(TxSlash),
+}
+
+impl Transaction {
+    /// Returns the account that signs and pays for this transaction.
+    pub fn sender(&self) -> AccountId {
+        match self {
+            Transaction::RegisterModel(tx) => tx.owner,
+            Transaction::UseModel(tx) => tx.caller,
+            Transaction::Transfer(tx) => tx.from,
+            Transaction::TransferModel(tx) => tx.current_owner,
+            Transaction::RevokeModel(tx) => tx.signer,
+            Transaction::Stake(tx) => tx.staker,
+            Transaction::Unstake(tx) => tx.staker,
+            Transaction::Slash(tx) => tx.proof.reporter,
+        }
+    }
+
+    /// Returns every account this transaction involves: the sender, then
+    /// the recipient of a transfer, the new owner of a model transfer, or
+    /// the proposer a slash is reported against.
+    pub fn accounts(&self) -> Vec<AccountId> {
+        let other = match self {
+            Transaction::Transfer(tx) => Some(tx.to),
+            Transaction::TransferModel(tx) => Some(tx.new_owner),
+            Transaction::Slash(tx) => Some(tx.proof.proposer),
+            _ => None,
+        };
+        let sender = self.sender();
+        std::iter::once(sender)
+            .chain(other.filter(|other| *other != sender))
+            .collect()
+    }
+
+    /// Returns the sender's anti-replay nonce.
+    pub fn nonce(&self) -> u64 {
+        match self {
+            Transaction::RegisterModel(tx) => tx.nonce,
+            Transaction::UseModel(tx) => tx.nonce,
+            Transaction::Transfer(tx) => tx.nonce,
+            Transaction::TransferModel(tx) => tx.nonce,
+            Transaction::RevokeModel(tx) => tx.nonce,
+            Transaction::Stake(tx) => tx.nonce,
+            Transaction::Unstake(tx) => tx.nonce,
+            Transaction::Slash(tx) => tx.nonce,
+        }
+    }
+
+    /// Returns the fee offered by the sender.
+    pub fn fee(&self) -> u64 {
+        match self {
+            Transaction::RegisterModel(tx) => tx.fee,
+            Transaction::UseModel(tx) => tx.fee,
+            Transaction::Transfer(tx) => tx.fee,
+            Transaction::TransferModel(tx) => tx.fee,
+            Transaction::RevokeModel(tx) => tx.fee,
+            Transaction::Stake(tx) => tx.fee,
+            Transaction::Unstake(tx) => tx.fee,
+            Transaction::Slash(tx) => tx.fee,
+        }
+    }
+
+    /// Returns the sender's signature.
+    pub fn signature(&self) -> &Signature {
+        match self {
+            Transaction::RegisterModel(tx) => &tx.signature,
+            Transaction::UseModel(tx) => &tx.signature,
+            Transaction::Transfer(tx) => &tx.signature,
+            Transaction::TransferModel(tx) => &tx.signature,
+            Transaction::RevokeModel(tx) => &tx.signature,
+            Transaction::Stake(tx) => &tx.signature,
+            Transaction::Unstake(tx) => &tx.signature,
+            Transaction::Slash(tx) => &tx.signature,
+        }
+    }
+
+    /// Returns a mutable reference to the sender's signature.
+    pub fn signature_mut(&mut self) -> &mut Signature {
+        match self {
+            Transaction::RegisterModel(tx) => &mut tx.signature,
+            Transaction::UseModel(tx) => &mut tx.signature,
+            Transaction::Transfer(tx) => &mut tx.signature,
+            Transaction::TransferModel(tx) => &mut tx.signature,
+            Transaction::RevokeModel(tx) => &mut tx.signature,
+            Transaction::Stake(tx) => &mut tx.signature,
+            Transaction::Unstake(tx) => &mut tx.signature,
+            Transaction::Slash(tx) => &mut tx.signature,
+        }
+    }
+
+    /// Returns the bytes the sender signs: a domain tag followed by the
+    /// [`canonical_bytes`](Self::canonical_bytes) of the transaction with
+    /// an empty signature.
+    ///
+    /// A `TxSlash` keeps the reporter's signature over its fraud proof,
+    /// which is part of the signed payload.
+    pub fn signing_bytes(&self) -> Vec<u8> {
+        let mut unsigned = self.clone();
+        *unsigned.signature_mut() = Signature(Vec::new());
+        let mut bytes = SIGNING_DOMAIN.to_vec();
+        bytes.extend_from_slice(&unsigned.canonical_bytes());
+        bytes
+    }
+
+    /// Checks that `public_key` belongs to the sender and that the
+    /// transaction's signature over [`signing_bytes`](Self::signing_bytes)
+    /// verifies under it.
+    pub fn verify_signature(&self, public_key: &PublicKey) -> Result<(), SignatureError> {
+        let key = AccountId::from_public_key(public_key.as_bytes());
+        let sender = self.sender();
+        if key != sender {
+            return Err(SignatureError::KeyMismatch { sender, key });
+        }
+        public_key.verify(&self.signing_bytes(), self.signature())
+    }
+
+    /// Returns the canonical byte representation of this transaction.
+    ///
+    /// Uses the same bincode-2 `standard()` configuration as
+    /// [`Block::canonical_bytes`](crate::types::Block::canonical_bytes).
+    pub fn canonical_bytes(&self) -> Vec<u8> {
+        let cfg = bincode::config::standard();
+        bincode::serde::encode_to_vec(self, cfg)
+            .expect("Transaction should always be serializable with bincode 2 + serde")
+    }
+
+    /// Decodes a transaction from its [`canonical_bytes`](Self::canonical_bytes).
+    ///
+    /// Returns `None` unless `bytes` hold exactly one encoded transaction,
+    /// so the decoded transaction hashes to the hash of `bytes`.
+    pub fn from_canonical_bytes(bytes: &[u8]) -> Option<Self> {
+        let cfg = bincode::config::standard();
+        let (tx, read): (Self, usize) = bincode::serde::decode_from_slice(bytes, cfg).ok()?;
+        (read == bytes.len()).then_some(tx)
+    }
+
+    /// Computes the transaction hash.
+    pub fn compute_hash(&self) -> TxHash {
+        TxHash(Hash256::compute(&self.canonical_bytes()))
+    }
 }
 
 #[cfg(test)]
@@ -193,6 +515,7 @@ mod tests {
         let tx_reg = TxRegisterModel {
             owner,
             aid,
+            chunked: None,
             evidence: EvidenceRef {
                 scheme_id: evidence.scheme_id.clone(),
                 evidence_hash: evidence.evidence_hash,
@@ -312,4 +635,40 @@ mod tests {
             other => panic!("unexpected transaction variant: {other:?}"),
         }
     }
+
+    #[test]
+    fn from_canonical_bytes_rejects_trailing_bytes() {
+        let tx = Transaction::Transfer(TxTransfer {
+            from: AccountId(dummy_hash(6)),
+            to: AccountId(dummy_hash(7)),
+            amount: 1_000,
+            fee: 3,
+            nonce: 5,
+            signature: dummy_signature(),
+        });
+        let mut bytes = tx.canonical_bytes();
+        let decoded = Transaction::from_canonical_bytes(&bytes).expect("decode");
+        assert_eq!(decoded.compute_hash(), tx.compute_hash());
+
+        bytes.push(0);
+        assert!(Transaction::from_canonical_bytes(&bytes).is_none());
+        assert!(Transaction::from_canonical_bytes(&[0xff]).is_none());
+    }
+
+    #[test]
+    fn tx_hash_is_deterministic_and_covers_nonce() {
+        let tx = |nonce| {
+            Transaction::Transfer(TxTransfer {
+                from: AccountId(dummy_hash(1)),
+                to: AccountId(dummy_hash(2)),
+                amount: 10,
+                fee: 1,
+                nonce,
+                signature: dummy_signature(),
+            })
+        };
+
+        assert_eq!(tx(0).compute_hash(), tx(0).compute_hash());
+        assert_ne!(tx(0).compute_hash(), tx(1).compute_hash());
+    }
 }
@@ -4,14 +4,32 @@
 //! require access to external services, such as:
 //!
 //! - block size and transaction count limits,
-//! - absence of duplicate `Aid` registrations within a single block.
+//! - a header `txs_root` that matches the block's transactions,
+//! - absence of duplicate `Aid` registrations and of byte-identical
+//!   transactions within a single block,
+//! - strictly increasing nonces for each sender within a block,
+//! - signed usage records and transfers, once signatures are required,
+//! - registrations whose watermark scheme and profile satisfy the
+//!   network's [`SchemeRegistry`], if one is set,
+//! - registrations whose chunked `Aid` form, if any, is well-formed and
+//!   derives the registered `Aid`,
+//! - a timestamp no more than `max_future_drift_secs` ahead of the
+//!   validator's clock, and
+//! - given a parent in the [`ValidationContext`], a height one above the
+//!   parent's and, with monotonic timestamps, a timestamp after the
+//!   parent's.
+//!
+//! The consensus engine checks the parent link as well before any
+//! validator runs; checking it here keeps the predicate complete when
+//! blocks are validated outside the engine.
 
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
+use std::time::{SystemTime, UNIX_EPOCH};
 
 use crate::consensus::config::ConsensusConfig;
 use crate::consensus::error::ValidationError;
-use crate::consensus::validator::BlockValidator;
-use crate::types::{Aid, Block, Transaction};
+use crate::consensus::validator::{BlockValidator, ValidationContext};
+use crate::types::{AccountId, Aid, Block, SchemeRegistry, SealedBlock, Transaction, TxHash};
 
 /// Base validity predicate for blocks.
 ///
@@ -21,6 +39,11 @@ use crate::types::{Aid, Block, Transaction};
 pub struct BaseValidity {
     max_block_txs: usize,
     max_block_size_bytes: usize,
+    max_future_drift_secs: u64,
+    monotonic_timestamps: bool,
+    require_signatures: bool,
+    schemes: SchemeRegistry,
+    clock: fn() -> u64,
 }
 
 impl BaseValidity {
@@ -29,29 +52,103 @@ impl BaseValidity {
         Self {
             max_block_txs: cfg.max_block_txs,
             max_block_size_bytes: cfg.max_block_size_bytes,
+            max_future_drift_secs: cfg.max_future_drift_secs,
+            monotonic_timestamps: cfg.monotonic_timestamps,
+            require_signatures: false,
+            schemes: SchemeRegistry::default(),
+            clock: system_clock,
         }
     }
 
+    /// Sets whether usage records and transfers must carry a non-empty
+    /// signature, normally from
+    /// [`TxValidityConfig::require_signatures`](super::TxValidityConfig::require_signatures).
+    pub fn with_signatures_required(mut self, required: bool) -> Self {
+        self.require_signatures = required;
+        self
+    }
+
+    /// Checks registrations against `schemes`, normally the genesis
+    /// [`ChainParams::schemes`](crate::execution::ChainParams::schemes).
+    pub fn with_scheme_registry(mut self, schemes: SchemeRegistry) -> Self {
+        self.schemes = schemes;
+        self
+    }
+
+    /// Replaces the clock (seconds since Unix epoch) that timestamps are
+    /// checked against. Defaults to the system clock.
+    pub fn with_clock(mut self, clock: fn() -> u64) -> Self {
+        self.clock = clock;
+        self
+    }
+
     fn check_tx_count(&self, block: &Block) -> Result<(), ValidationError> {
         let tx_count = block.txs.len();
         if tx_count > self.max_block_txs {
-            return Err(ValidationError::Custom(format!(
-                "block has {} txs, exceeds max_block_txs={}",
-                tx_count, self.max_block_txs
-            )));
+            return Err(ValidationError::TooManyTxs {
+                count: tx_count,
+                max: self.max_block_txs,
+            });
         }
         Ok(())
     }
 
-    fn check_block_size(&self, block: &Block) -> Result<(), ValidationError> {
-        // Use the canonical bincode-2 encoding already defined on Block.
-        let bytes = block.canonical_bytes();
-        let size = bytes.len();
+    /// Checks `size`, the length of the block's canonical bincode-2
+    /// encoding.
+    fn check_block_size(&self, size: usize) -> Result<(), ValidationError> {
         if size > self.max_block_size_bytes {
-            return Err(ValidationError::Custom(format!(
-                "block size {} bytes exceeds max_block_size_bytes={}",
-                size, self.max_block_size_bytes
-            )));
+            return Err(ValidationError::BlockTooLarge {
+                size,
+                max: self.max_block_size_bytes,
+            });
+        }
+        Ok(())
+    }
+
+    fn check_txs_root(&self, block: &Block) -> Result<(), ValidationError> {
+        let computed = block.compute_txs_root();
+        if block.header.txs_root != computed {
+            return Err(ValidationError::TxsRootMismatch {
+                header: block.header.txs_root,
+                computed,
+            });
+        }
+        Ok(())
+    }
+
+    fn check_timestamp(&self, block: &Block) -> Result<(), ValidationError> {
+        if self.max_future_drift_secs == 0 {
+            return Ok(());
+        }
+        let limit = (self.clock)().saturating_add(self.max_future_drift_secs);
+        if block.header.timestamp > limit {
+            return Err(ValidationError::TimestampInFuture {
+                timestamp: block.header.timestamp,
+                max_drift: self.max_future_drift_secs,
+            });
+        }
+        Ok(())
+    }
+
+    fn check_parent(
+        &self,
+        block: &Block,
+        ctx: &ValidationContext<'_>,
+    ) -> Result<(), ValidationError> {
+        let Some(parent) = ctx.parent else {
+            return Ok(());
+        };
+        if block.header.height != ctx.height {
+            return Err(ValidationError::BadHeight {
+                parent_height: parent.header.height,
+                height: block.header.height,
+            });
+        }
+        if self.monotonic_timestamps && block.header.timestamp <= parent.header.timestamp {
+            return Err(ValidationError::TimestampNotAfterParent {
+                timestamp: block.header.timestamp,
+                parent: parent.header.timestamp,
+            });
         }
         Ok(())
     }
@@ -63,31 +160,137 @@ impl BaseValidity {
             if let Transaction::RegisterModel(tx_reg) = tx
                 && !seen.insert(tx_reg.aid)
             {
-                return Err(ValidationError::Custom(
-                    "duplicate Aid in TxRegisterModel within the same block".to_string(),
-                ));
+                return Err(ValidationError::DuplicateAid(tx_reg.aid));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn check_duplicate_txs(&self, block: &Block) -> Result<(), ValidationError> {
+        let mut seen: HashSet<TxHash> = HashSet::new();
+
+        for tx in &block.txs {
+            let hash = tx.compute_hash();
+            if !seen.insert(hash) {
+                return Err(ValidationError::DuplicateTx(hash));
             }
         }
 
         Ok(())
     }
+
+    fn check_schemes(&self, block: &Block) -> Result<(), ValidationError> {
+        for tx in &block.txs {
+            if let Transaction::RegisterModel(tx_reg) = tx {
+                self.schemes.check(&tx_reg.evidence)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn check_chunked_aids(&self, block: &Block) -> Result<(), ValidationError> {
+        for tx in &block.txs {
+            if let Transaction::RegisterModel(tx_reg) = tx
+                && let Some(chunked) = &tx_reg.chunked
+            {
+                chunked.check_registration(&tx_reg.aid)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn check_nonces(&self, block: &Block) -> Result<(), ValidationError> {
+        let mut last: HashMap<AccountId, u64> = HashMap::new();
+
+        for tx in &block.txs {
+            let (sender, nonce) = (tx.sender(), tx.nonce());
+            if let Some(prev) = last.insert(sender, nonce)
+                && nonce <= prev
+            {
+                return Err(ValidationError::NonceNotIncreasing {
+                    account: sender,
+                    previous: prev,
+                    got: nonce,
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    fn check_signatures(&self, block: &Block) -> Result<(), ValidationError> {
+        if !self.require_signatures {
+            return Ok(());
+        }
+        for tx in &block.txs {
+            let signature = match tx {
+                Transaction::UseModel(tx) => &tx.signature,
+                Transaction::Transfer(tx) => &tx.signature,
+                _ => continue,
+            };
+            if signature.as_bytes().is_empty() {
+                return Err(ValidationError::BadSignature(
+                    "usage record or transfer in block is not signed".to_string(),
+                ));
+            }
+        }
+        Ok(())
+    }
 }
 
-impl BlockValidator for BaseValidity {
-    fn validate(&self, block: &Block) -> Result<(), ValidationError> {
+impl BaseValidity {
+    /// Runs every check, given the block's encoded size.
+    fn check(
+        &self,
+        block: &Block,
+        size: usize,
+        ctx: &ValidationContext<'_>,
+    ) -> Result<(), ValidationError> {
         self.check_tx_count(block)?;
-        self.check_block_size(block)?;
+        self.check_block_size(size)?;
+        self.check_txs_root(block)?;
+        self.check_timestamp(block)?;
+        self.check_parent(block, ctx)?;
         self.check_duplicate_aids(block)?;
+        self.check_duplicate_txs(block)?;
+        self.check_schemes(block)?;
+        self.check_chunked_aids(block)?;
+        self.check_nonces(block)?;
+        self.check_signatures(block)?;
         Ok(())
     }
 }
 
+impl BlockValidator for BaseValidity {
+    fn validate(&self, block: &Block, ctx: &ValidationContext<'_>) -> Result<(), ValidationError> {
+        self.check(block, block.canonical_bytes().len(), ctx)
+    }
+
+    /// Like [`validate`](Self::validate), but takes the size from the
+    /// sealed encoding instead of encoding the block again.
+    fn validate_sealed(
+        &self,
+        block: &SealedBlock,
+        ctx: &ValidationContext<'_>,
+    ) -> Result<(), ValidationError> {
+        self.check(block, block.bytes().len(), ctx)
+    }
+}
+
+fn system_clock() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::types::{
         AccountId, Aid, EvidenceHash, EvidenceRef, HASH_LEN, Hash256, Signature, Transaction,
-        TxRegisterModel, WmProfile,
+        TxRegisterModel, TxTransfer, WmProfile,
     };
 
     fn dummy_hash(byte: u8) -> Hash256 {
@@ -119,6 +322,7 @@ mod tests {
         let tx = TxRegisterModel {
             owner,
             aid,
+            chunked: None,
             evidence: dummy_evidence(3),
             fee: 0,
             nonce: 0,
@@ -135,35 +339,50 @@ mod tests {
             height: 0,
             timestamp: 1_700_000_000,
             proposer: dummy_account(1),
+            txs_root: Hash256([0u8; HASH_LEN]),
+            state_root: Hash256([0u8; HASH_LEN]),
+            receipts_root: Hash256([0u8; HASH_LEN]),
             pos_proof: None,
         };
 
-        Block { header, txs }
+        let mut block = Block { header, txs };
+        block.header.txs_root = block.compute_txs_root();
+        block
     }
 
     #[test]
     fn base_validity_accepts_small_block() {
+        let ctx = ValidationContext::detached();
         let cfg = ConsensusConfig {
             block_time_secs: 5,
             max_block_txs: 10,
             max_block_size_bytes: 1_000_000,
             allow_empty_blocks: true,
+            orphan_buffer_size: 0,
+            slot_timing: false,
+            monotonic_timestamps: true,
+            max_future_drift_secs: 15,
         };
         let v = BaseValidity::new(&cfg);
 
         let txs = vec![dummy_reg_tx(dummy_account(1), Aid(dummy_hash(2)))];
         let block = dummy_block_with_txs(txs);
 
-        assert!(v.validate(&block).is_ok());
+        assert!(v.validate(&block, &ctx).is_ok());
     }
 
     #[test]
     fn base_validity_rejects_too_many_txs() {
+        let ctx = ValidationContext::detached();
         let cfg = ConsensusConfig {
             block_time_secs: 5,
             max_block_txs: 1,
             max_block_size_bytes: 1_000_000,
             allow_empty_blocks: true,
+            orphan_buffer_size: 0,
+            slot_timing: false,
+            monotonic_timestamps: true,
+            max_future_drift_secs: 15,
         };
         let v = BaseValidity::new(&cfg);
 
@@ -173,25 +392,25 @@ mod tests {
         ];
         let block = dummy_block_with_txs(txs);
 
-        let err = v.validate(&block).unwrap_err();
-        match err {
-            ValidationError::Custom(msg) => {
-                assert!(
-                    msg.contains("exceeds max_block_txs"),
-                    "unexpected message: {msg}"
-                );
-            }
-            _ => panic!("unexpected error variant: {err:?}"),
-        }
+        let err = v.validate(&block, &ctx).unwrap_err();
+        assert!(
+            matches!(err, ValidationError::TooManyTxs { count: 2, max: 1 }),
+            "unexpected error variant: {err:?}"
+        );
     }
 
     #[test]
     fn base_validity_rejects_duplicate_aids_in_block() {
+        let ctx = ValidationContext::detached();
         let cfg = ConsensusConfig {
             block_time_secs: 5,
             max_block_txs: 10,
             max_block_size_bytes: 1_000_000,
             allow_empty_blocks: true,
+            orphan_buffer_size: 0,
+            slot_timing: false,
+            monotonic_timestamps: true,
+            max_future_drift_secs: 15,
         };
         let v = BaseValidity::new(&cfg);
 
@@ -202,35 +421,285 @@ mod tests {
         ];
         let block = dummy_block_with_txs(txs);
 
-        let err = v.validate(&block).unwrap_err();
-        match err {
-            ValidationError::Custom(msg) => {
-                assert!(msg.contains("duplicate Aid"), "unexpected message: {msg}");
-            }
-            _ => panic!("unexpected error variant: {err:?}"),
+        let err = v.validate(&block, &ctx).unwrap_err();
+        assert!(
+            matches!(err, ValidationError::DuplicateAid(_)),
+            "unexpected error variant: {err:?}"
+        );
+    }
+
+    #[test]
+    fn base_validity_rejects_chunked_aid_not_deriving_aid() {
+        let ctx = ValidationContext::detached();
+        let cfg = ConsensusConfig {
+            block_time_secs: 5,
+            max_block_txs: 10,
+            max_block_size_bytes: 1_000_000,
+            allow_empty_blocks: true,
+            orphan_buffer_size: 0,
+            slot_timing: false,
+            monotonic_timestamps: true,
+            max_future_drift_secs: 15,
+        };
+        let v = BaseValidity::new(&cfg);
+
+        let chunked = crate::types::ChunkedAid {
+            chunk_size: crate::types::MIN_CHUNK_SIZE,
+            total_len: 10_000,
+            root: dummy_hash(9),
+        };
+        let mut tx = dummy_reg_tx(dummy_account(1), chunked.aid());
+        if let Transaction::RegisterModel(reg) = &mut tx {
+            reg.chunked = Some(chunked);
         }
+        assert!(
+            v.validate(&dummy_block_with_txs(vec![tx.clone()]), &ctx)
+                .is_ok()
+        );
+
+        if let Transaction::RegisterModel(reg) = &mut tx {
+            reg.aid = Aid(dummy_hash(2));
+        }
+        let err = v
+            .validate(&dummy_block_with_txs(vec![tx]), &ctx)
+            .unwrap_err();
+        assert_eq!(err.kind(), "invalid_chunked_aid");
     }
 
     #[test]
     fn base_validity_rejects_oversized_block() {
+        let ctx = ValidationContext::detached();
         // Force a tiny max size so even a small block exceeds it.
         let cfg = ConsensusConfig {
             block_time_secs: 5,
             max_block_txs: 10,
             max_block_size_bytes: 1, // absurdly small
             allow_empty_blocks: true,
+            orphan_buffer_size: 0,
+            slot_timing: false,
+            monotonic_timestamps: true,
+            max_future_drift_secs: 15,
         };
         let v = BaseValidity::new(&cfg);
 
         let txs = vec![dummy_reg_tx(dummy_account(1), Aid(dummy_hash(2)))];
         let block = dummy_block_with_txs(txs);
 
-        let err = v.validate(&block).unwrap_err();
-        match err {
-            ValidationError::Custom(msg) => {
-                assert!(msg.contains("block size"), "unexpected message: {msg}");
-            }
-            _ => panic!("unexpected error variant: {err:?}"),
-        }
+        let err = v.validate(&block, &ctx).unwrap_err();
+        assert!(
+            matches!(err, ValidationError::BlockTooLarge { max: 1, .. }),
+            "unexpected error variant: {err:?}"
+        );
+
+        let size = block.canonical_bytes().len();
+        let err = v.validate_sealed(&block.seal(), &ctx).unwrap_err();
+        assert!(
+            matches!(err, ValidationError::BlockTooLarge { size: s, max: 1 } if s == size),
+            "unexpected error variant: {err:?}"
+        );
+    }
+
+    #[test]
+    fn base_validity_rejects_mismatched_txs_root() {
+        let ctx = ValidationContext::detached();
+        let cfg = ConsensusConfig {
+            block_time_secs: 5,
+            max_block_txs: 10,
+            max_block_size_bytes: 1_000_000,
+            allow_empty_blocks: true,
+            orphan_buffer_size: 0,
+            slot_timing: false,
+            monotonic_timestamps: true,
+            max_future_drift_secs: 15,
+        };
+        let v = BaseValidity::new(&cfg);
+
+        let mut block = dummy_block_with_txs(vec![
+            dummy_reg_tx(dummy_account(1), Aid(dummy_hash(2))),
+            dummy_reg_tx(dummy_account(2), Aid(dummy_hash(3))),
+        ]);
+        block.txs.swap(0, 1);
+
+        let err = v.validate(&block, &ctx).unwrap_err();
+        assert!(
+            matches!(err, ValidationError::TxsRootMismatch { .. }),
+            "unexpected error variant: {err:?}"
+        );
+    }
+
+    #[test]
+    fn base_validity_rejects_timestamps_beyond_the_drift_limit() {
+        let ctx = ValidationContext::detached();
+        let cfg = ConsensusConfig {
+            max_future_drift_secs: 15,
+            ..ConsensusConfig::default()
+        };
+        // dummy_block_with_txs stamps blocks at 1_700_000_000.
+        let v = BaseValidity::new(&cfg).with_clock(|| 1_699_999_990);
+        let block = dummy_block_with_txs(Vec::new());
+        assert!(v.validate(&block, &ctx).is_ok());
+
+        let v = v.with_clock(|| 1_699_999_980);
+        let err = v.validate(&block, &ctx).unwrap_err();
+        assert!(
+            matches!(
+                err,
+                ValidationError::TimestampInFuture { max_drift: 15, .. }
+            ),
+            "unexpected error variant: {err:?}"
+        );
+
+        let cfg = ConsensusConfig {
+            max_future_drift_secs: 0,
+            ..ConsensusConfig::default()
+        };
+        let v = BaseValidity::new(&cfg).with_clock(|| 0);
+        assert!(v.validate(&block, &ctx).is_ok());
+    }
+
+    #[test]
+    fn base_validity_checks_the_link_to_the_parent() {
+        let v = BaseValidity::new(&ConsensusConfig::default()).with_clock(|| 1_700_000_000);
+        let store = crate::storage::InMemoryBlockStore::new();
+        // dummy_block_with_txs stamps blocks at 1_700_000_000, height 0.
+        let parent = dummy_block_with_txs(Vec::new());
+        let ctx = ValidationContext::new(Some(&parent), &store);
+        assert_eq!(ctx.height, 1);
+
+        let mut block = dummy_block_with_txs(Vec::new());
+        block.header.height = 1;
+        block.header.timestamp = 1_700_000_005;
+        assert!(v.validate(&block, &ctx).is_ok());
+
+        block.header.height = 2;
+        assert!(matches!(
+            v.validate(&block, &ctx),
+            Err(ValidationError::BadHeight {
+                parent_height: 0,
+                height: 2
+            })
+        ));
+
+        block.header.height = 1;
+        block.header.timestamp = 1_700_000_000;
+        assert!(matches!(
+            v.validate(&block, &ctx),
+            Err(ValidationError::TimestampNotAfterParent { .. })
+        ));
+
+        let cfg = ConsensusConfig {
+            monotonic_timestamps: false,
+            ..ConsensusConfig::default()
+        };
+        let v = BaseValidity::new(&cfg).with_clock(|| 1_700_000_000);
+        assert!(v.validate(&block, &ctx).is_ok());
+    }
+
+    fn dummy_transfer(from: u8, nonce: u64, signature: Vec<u8>) -> Transaction {
+        Transaction::Transfer(TxTransfer {
+            from: dummy_account(from),
+            to: dummy_account(9),
+            amount: 1,
+            fee: 0,
+            nonce,
+            signature: Signature(signature),
+        })
+    }
+
+    #[test]
+    fn base_validity_rejects_duplicate_txs_and_out_of_order_nonces() {
+        let ctx = ValidationContext::detached();
+        let v = BaseValidity::new(&ConsensusConfig::default()).with_clock(|| 1_700_000_000);
+
+        let block = dummy_block_with_txs(vec![
+            dummy_transfer(1, 0, vec![]),
+            dummy_transfer(2, 0, vec![]),
+            dummy_transfer(1, 2, vec![]),
+        ]);
+        assert!(v.validate(&block, &ctx).is_ok());
+
+        let block = dummy_block_with_txs(vec![dummy_transfer(1, 0, vec![]); 2]);
+        let err = v.validate(&block, &ctx).unwrap_err();
+        assert!(
+            matches!(err, ValidationError::DuplicateTx(_)),
+            "unexpected error: {err:?}"
+        );
+
+        // Same nonce, different payload: not byte-identical, still a replay.
+        let block = dummy_block_with_txs(vec![
+            dummy_transfer(1, 1, vec![]),
+            dummy_transfer(1, 1, vec![7]),
+        ]);
+        let err = v.validate(&block, &ctx).unwrap_err();
+        assert!(
+            matches!(
+                err,
+                ValidationError::NonceNotIncreasing {
+                    previous: 1,
+                    got: 1,
+                    ..
+                }
+            ),
+            "unexpected error: {err:?}"
+        );
+
+        let block = dummy_block_with_txs(vec![
+            dummy_transfer(1, 3, vec![]),
+            dummy_transfer(1, 2, vec![]),
+        ]);
+        assert!(v.validate(&block, &ctx).is_err());
+    }
+
+    #[test]
+    fn base_validity_requires_signed_transfers_when_configured() {
+        let ctx = ValidationContext::detached();
+        let block = dummy_block_with_txs(vec![dummy_transfer(1, 0, vec![])]);
+
+        let v = BaseValidity::new(&ConsensusConfig::default()).with_clock(|| 1_700_000_000);
+        assert!(v.validate(&block, &ctx).is_ok());
+
+        let v = v.with_signatures_required(true);
+        assert!(matches!(
+            v.validate(&block, &ctx),
+            Err(ValidationError::BadSignature(_))
+        ));
+        let signed = dummy_block_with_txs(vec![dummy_transfer(1, 0, vec![1; 8])]);
+        assert!(v.validate(&signed, &ctx).is_ok());
+    }
+
+    #[test]
+    fn base_validity_checks_registrations_against_the_scheme_registry() {
+        use crate::types::{ProfileRange, SchemeError, SchemeRegistry, SchemeRule};
+
+        let ctx = ValidationContext::detached();
+        let block = dummy_block_with_txs(vec![dummy_reg_tx(dummy_account(1), Aid(dummy_hash(2)))]);
+        let v = BaseValidity::new(&ConsensusConfig::default()).with_clock(|| 1_700_000_000);
+
+        let known = SchemeRegistry::new().with_scheme("wm-test-3", SchemeRule::default());
+        assert!(
+            v.clone()
+                .with_scheme_registry(known)
+                .validate(&block, &ctx)
+                .is_ok()
+        );
+
+        let other = SchemeRegistry::new().with_scheme("wm-other", SchemeRule::default());
+        assert!(matches!(
+            v.clone().with_scheme_registry(other).validate(&block, &ctx),
+            Err(ValidationError::InvalidScheme(SchemeError::Unknown(_)))
+        ));
+
+        let strict = SchemeRegistry::new().with_scheme(
+            "wm-test-3",
+            SchemeRule {
+                tau_feat: Some(ProfileRange { min: 0.5, max: 1.0 }),
+                ..SchemeRule::default()
+            },
+        );
+        let err = v
+            .with_scheme_registry(strict)
+            .validate(&block, &ctx)
+            .unwrap_err();
+        assert_eq!(err.kind(), "profile_out_of_range");
     }
 }
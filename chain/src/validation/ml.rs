@@ -5,25 +5,123 @@
 //!
 //! - extracts `ML(B)` = all `(Aid, EvidenceRef)` pairs in a block,
 //! - deduplicates them within the block,
-//! - calls the verifier for each pair, and
+//! - verifies all pairs with [`MlVerifier::verify_batch`], split into up to
+//!   [`MlConfig::max_concurrency`] batches that run in parallel, and
 //! - fails the block if any verdict is negative.
+//!
+//! Verdicts are put back in block order before they are judged, so the
+//! reported rejection (the first negative artefact in the block) and the
+//! reported verifier error (from the first failing batch) do not depend on
+//! which batch finishes first.
+//!
+//! A verdict is judged by [`MlConfig::accepts`]: schemes with a configured
+//! score threshold (local, or the `min_score` of the network's
+//! [`SchemeRegistry`]) are decided on the service's continuous `score`,
+//! and schemes with [`VerdictBounds`] (configured, or taken from the
+//! artefact's own `WmProfile` with [`MlConfig::profile_bounds`]) on the
+//! reported `trigger_acc`, `feat_dist` and `logit_stat`. All other schemes
+//! fall back to the service's boolean `ok`.
+//!
+//! [`AsyncMlVerifier`] and [`AsyncMlValidity`] are the non-blocking
+//! counterparts, meant to be awaited from inside a Tokio runtime (e.g.
+//! the api-gateway handlers and block producer).
+//!
+//! Every verdict is also recorded as an `info` event on the
+//! [`DECISION_LOG_TARGET`] tracing target, with the block hash and height,
+//! `Aid`, scheme, decision, score, and verification latency as structured
+//! fields. Node binaries route that target independently of their general
+//! log filter according to [`DecisionLogConfig`], so the accept/reject
+//! history can be kept for review even when other logs are quiet.
+//!
+//! Each validation runs in an `ml_validate` `tracing` span with the block
+//! height and the number of artefacts checked.
+//!
+//! With `with_metrics`, every verdict is also counted in
+//! [`ConsensusMetrics`] by scheme and outcome (`accepted`, `rejected`,
+//! `error` when the verifier failed, or `timeout` when it ran out of
+//! budget), together with the verifier latency.
+//!
+//! With `with_stats_log`, the scores and decision behind every accepted
+//! block's registrations are recorded as [`MlVerdictStats`] in an
+//! [`MlStatsLog`], for the engine to store next to the block's receipts.
+//!
+//! When the verifier is unreachable ([`MlError::Transport`] or
+//! [`MlError::CircuitOpen`]), [`MlConfig::outage_policy`] decides what
+//! happens to the block: it is rejected as
+//! [`ValidationError::MlUnavailable`], accepted unverified, or accepted
+//! and its artefacts handed to a [`RevalidationQueue`] to be re-checked
+//! in the background. Artefacts accepted without a verdict appear in the
+//! decision log as `unverified` or `deferred`. Note that the accepting
+//! policies trade safety for liveness: nodes that saw the outage may keep
+//! blocks that other nodes reject.
+//!
+//! Verification of one block is bounded by
+//! [`MlConfig::verification_budget_ms`]. Every verifier call gets the
+//! block's deadline: [`MlValidity`] passes it to
+//! [`MlVerifier::verify_batch_until`], which gives up cooperatively once it
+//! has passed, and [`AsyncMlValidity`] drops the calls still in flight.
+//! A block whose artefacts were not all judged in time fails with
+//! [`ValidationError::MlBudgetExhausted`] regardless of the outage policy,
+//! and the engine keeps a failed proposal's transactions for a later try.
+
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
+use std::thread;
+use std::time::{Duration, Instant};
 
-use std::collections::HashSet;
+use std::str::FromStr;
+
+use futures_util::future::join_all;
+use serde::Deserialize;
 
 use crate::consensus::error::ValidationError;
-use crate::consensus::validator::BlockValidator;
-use crate::types::{Aid, Block, EvidenceHash, EvidenceRef};
+use crate::consensus::validator::{AsyncBlockValidator, BlockValidator, ValidationContext};
+use crate::execution::{MlStatsLog, MlVerdictStats};
+use crate::metrics::ConsensusMetrics;
+use crate::types::{
+    Aid, Block, EvidenceHash, EvidenceRef, FraudVerdict, ProfileRange, SchemeRegistry, Transaction,
+    WmProfile,
+};
+
+use super::revalidation::{RevalidationConfig, RevalidationQueue};
 
 /// Result of an ML authenticity check for a single artefact.
 #[derive(Clone, Debug)]
 pub struct MlVerdict {
     /// Overall verdict: `true` if the artefact passes V_auth.
     pub ok: bool,
+    /// Continuous confidence score in `[0, 1]` reported by the service.
+    ///
+    /// Higher means more confident that the watermark is present. Used
+    /// instead of `ok` when [`MlConfig::score_thresholds`] has an entry for
+    /// the artefact's scheme.
+    pub score: Option<f32>,
     /// Optional diagnostic statistics (trigger accuracy, etc.).
     pub trigger_acc: Option<f32>,
     pub feat_dist: Option<f32>,
     pub logit_stat: Option<f32>,
     pub latency_ms: Option<u64>,
+    /// How the inner verifiers voted, if this verdict was aggregated by a
+    /// `QuorumMlVerifier`; `None` for a single service.
+    pub quorum: Option<QuorumVotes>,
+}
+
+/// Vote breakdown behind a quorum verdict.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct QuorumVotes {
+    /// Verifiers that accepted the artefact.
+    pub accepted: usize,
+    /// Verifiers that rejected the artefact.
+    pub rejected: usize,
+    /// Verifiers that returned an error instead of a verdict.
+    pub failed: usize,
+}
+
+impl QuorumVotes {
+    /// Returns `true` if the verifiers that answered did not all agree.
+    pub fn is_split(&self) -> bool {
+        self.accepted > 0 && self.rejected > 0
+    }
 }
 
 /// Errors that can occur while contacting the ML verification service.
@@ -35,6 +133,11 @@ pub enum MlError {
     Protocol(String),
     /// The ML service actively refused to verify this artefact.
     Service(String),
+    /// The client's circuit breaker is open after repeated failures, so
+    /// the service is considered down and was not contacted.
+    CircuitOpen(String),
+    /// The call did not finish before the deadline it was given.
+    DeadlineExceeded(String),
 }
 
 /// Abstract ML verifier used by [`MlValidity`].
@@ -44,22 +147,273 @@ pub enum MlError {
 /// checks described in the thesis.
 pub trait MlVerifier: Send + Sync {
     fn verify(&self, aid: &Aid, evidence: &EvidenceRef) -> Result<MlVerdict, MlError>;
+
+    /// Verifies several artefacts at once, returning one verdict per item
+    /// in the same order.
+    ///
+    /// The default calls [`verify`](Self::verify) for each item; clients
+    /// with a batch endpoint override it to save round trips.
+    fn verify_batch(&self, items: &[(Aid, EvidenceRef)]) -> Result<Vec<MlVerdict>, MlError> {
+        items
+            .iter()
+            .map(|(aid, evidence)| self.verify(aid, evidence))
+            .collect()
+    }
+
+    /// Like [`verify_batch`](Self::verify_batch), but gives up with
+    /// [`MlError::DeadlineExceeded`] once `deadline` has passed.
+    ///
+    /// The default only refuses to start a call after the deadline; clients
+    /// that can bound or interrupt their own work override it.
+    fn verify_batch_until(
+        &self,
+        items: &[(Aid, EvidenceRef)],
+        deadline: Instant,
+    ) -> Result<Vec<MlVerdict>, MlError> {
+        if Instant::now() >= deadline {
+            return Err(MlError::DeadlineExceeded(format!(
+                "deadline passed before verifying {} artefacts",
+                items.len()
+            )));
+        }
+        self.verify_batch(items)
+    }
+}
+
+/// Async variant of [`MlVerifier`] used by [`AsyncMlValidity`].
+///
+/// The returned future must be `Send` so verification can be awaited from
+/// tasks spawned on a multi-threaded Tokio runtime.
+pub trait AsyncMlVerifier: Send + Sync {
+    fn verify(
+        &self,
+        aid: &Aid,
+        evidence: &EvidenceRef,
+    ) -> impl Future<Output = Result<MlVerdict, MlError>> + Send;
+
+    /// Async counterpart of [`MlVerifier::verify_batch`]; the default
+    /// awaits [`verify`](Self::verify) for each item in turn.
+    fn verify_batch(
+        &self,
+        items: &[(Aid, EvidenceRef)],
+    ) -> impl Future<Output = Result<Vec<MlVerdict>, MlError>> + Send {
+        async move {
+            let mut verdicts = Vec::with_capacity(items.len());
+            for (aid, evidence) in items {
+                verdicts.push(self.verify(aid, evidence).await?);
+            }
+            Ok(verdicts)
+        }
+    }
+}
+
+/// What to do with a block whose artefacts could not be verified because
+/// the ML service is unreachable.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MlOutagePolicy {
+    /// Reject the block with [`ValidationError::MlUnavailable`]
+    /// (fail closed).
+    #[default]
+    RejectBlock,
+    /// Accept the block and log its artefacts as `unverified` (fail open).
+    AcceptWithFlag,
+    /// Accept the block and re-check its artefacts later through a
+    /// [`RevalidationQueue`].
+    DeferToRevalidationQueue,
+}
+
+impl MlOutagePolicy {
+    /// Returns the policy's configuration name, also used as metric label.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            MlOutagePolicy::RejectBlock => "reject_block",
+            MlOutagePolicy::AcceptWithFlag => "accept_with_flag",
+            MlOutagePolicy::DeferToRevalidationQueue => "defer_to_revalidation_queue",
+        }
+    }
+}
+
+impl FromStr for MlOutagePolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "reject_block" => Ok(MlOutagePolicy::RejectBlock),
+            "accept_with_flag" => Ok(MlOutagePolicy::AcceptWithFlag),
+            "defer_to_revalidation_queue" => Ok(MlOutagePolicy::DeferToRevalidationQueue),
+            _ => Err(
+                "expected \"reject_block\", \"accept_with_flag\" or \"defer_to_revalidation_queue\""
+                    .to_string(),
+            ),
+        }
+    }
+}
+
+/// Bounds on the diagnostic statistics of a verdict. Unset bounds are not
+/// checked; a verdict missing a checked statistic is rejected.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct VerdictBounds {
+    /// Smallest acceptable [`MlVerdict::trigger_acc`].
+    pub min_trigger_acc: Option<f32>,
+    /// Largest acceptable [`MlVerdict::feat_dist`].
+    pub max_feat_dist: Option<f32>,
+    /// Range [`MlVerdict::logit_stat`] must lie in.
+    pub logit_stat: Option<ProfileRange>,
+}
+
+impl VerdictBounds {
+    /// The multi-factor test the ML service applies to `profile`: trigger
+    /// accuracy of at least `tau_input`, feature distance of at most
+    /// `tau_feat`, and a logit statistic within the logit band.
+    pub fn from_profile(profile: &WmProfile) -> Self {
+        Self {
+            min_trigger_acc: Some(profile.tau_input),
+            max_feat_dist: Some(profile.tau_feat),
+            logit_stat: Some(ProfileRange {
+                min: profile.logit_band_low,
+                max: profile.logit_band_high,
+            }),
+        }
+    }
+
+    /// Returns `true` if `verdict` satisfies every bound.
+    pub fn admits(&self, verdict: &MlVerdict) -> bool {
+        let within = |bound: Option<f32>, value: Option<f32>, ok: fn(f32, f32) -> bool| {
+            bound.is_none_or(|bound| value.is_some_and(|value| ok(value, bound)))
+        };
+        within(self.min_trigger_acc, verdict.trigger_acc, |v, b| v >= b)
+            && within(self.max_feat_dist, verdict.feat_dist, |v, b| v <= b)
+            && self
+                .logit_stat
+                .is_none_or(|range| verdict.logit_stat.is_some_and(|v| range.contains(v)))
+    }
 }
 
 /// Configuration options for [`MlValidity`].
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
 pub struct MlConfig {
     /// Maximum number of distinct artefacts per block we are willing to
     /// verify. Blocks exceeding this bound will be rejected to bound
     /// worst-case ML verification cost.
     pub max_artefacts_per_block: usize,
+    /// Minimum acceptable score per watermark scheme (keyed by `scheme_id`).
+    ///
+    /// For schemes listed here the service's `ok` flag is ignored and the
+    /// verdict's `score` must be at least the threshold; a verdict without
+    /// a score is rejected. Network-wide minimums are merged in with
+    /// [`with_scheme_registry`](Self::with_scheme_registry).
+    pub score_thresholds: HashMap<String, f32>,
+    /// Bounds on the verdict statistics per watermark scheme.
+    ///
+    /// For schemes listed here the service's `ok` flag is ignored and the
+    /// verdict must satisfy the bounds (and the score threshold, if any).
+    pub verdict_bounds: HashMap<String, VerdictBounds>,
+    /// Whether schemes without [`verdict_bounds`](Self::verdict_bounds)
+    /// are judged against their artefact's own `WmProfile` (see
+    /// [`VerdictBounds::from_profile`]) instead of the service's `ok`.
+    pub profile_bounds: bool,
+    /// Maximum number of `verify_batch` calls in flight per block.
+    ///
+    /// A block's artefacts are split into this many equally sized batches
+    /// that are verified in parallel. `1` sends the whole block in a single
+    /// batch, which is best for clients with a native batch endpoint.
+    pub max_concurrency: usize,
+    /// Handling of blocks checked while the ML service is unreachable.
+    pub outage_policy: MlOutagePolicy,
+    /// Background re-checks under
+    /// [`MlOutagePolicy::DeferToRevalidationQueue`].
+    pub revalidation: RevalidationConfig,
+    /// Total time in milliseconds the verification of one block may take
+    /// across all its `verify_batch` calls. Blocks not judged in time are
+    /// rejected with [`ValidationError::MlBudgetExhausted`]; `0` disables
+    /// the budget.
+    pub verification_budget_ms: u64,
 }
 
 impl Default for MlConfig {
     fn default() -> Self {
         Self {
             max_artefacts_per_block: 1024,
+            score_thresholds: HashMap::new(),
+            verdict_bounds: HashMap::new(),
+            profile_bounds: false,
+            max_concurrency: 1,
+            outage_policy: MlOutagePolicy::default(),
+            revalidation: RevalidationConfig::default(),
+            verification_budget_ms: 30_000,
+        }
+    }
+}
+
+impl MlConfig {
+    /// Returns the number of artefacts per batch when verifying `len`
+    /// artefacts with [`max_concurrency`](Self::max_concurrency) batches.
+    fn batch_size(&self, len: usize) -> usize {
+        len.div_ceil(self.max_concurrency.max(1)).max(1)
+    }
+
+    /// Returns the verification deadline of a block whose verification
+    /// started at `started`, or `None` without a budget.
+    fn deadline(&self, started: Instant) -> Option<Instant> {
+        (self.verification_budget_ms > 0)
+            .then(|| started + Duration::from_millis(self.verification_budget_ms))
+    }
+
+    /// Applies the `min_score` of every scheme in `schemes` as its score
+    /// threshold, replacing a locally configured one.
+    pub fn with_scheme_registry(mut self, schemes: &SchemeRegistry) -> Self {
+        for (scheme, rule) in schemes.iter() {
+            if let Some(min_score) = rule.min_score {
+                self.score_thresholds.insert(scheme.to_string(), min_score);
+            }
+        }
+        self
+    }
+
+    /// Returns `true` if `verdict` is acceptable for an artefact registered
+    /// with `evidence`.
+    pub fn accepts(&self, evidence: &EvidenceRef, verdict: &MlVerdict) -> bool {
+        let scheme_id = &evidence.scheme_id;
+        let threshold = self.score_thresholds.get(scheme_id);
+        let bounds = self.verdict_bounds.get(scheme_id).copied().or_else(|| {
+            self.profile_bounds
+                .then(|| VerdictBounds::from_profile(&evidence.wm_profile))
+        });
+        if threshold.is_none() && bounds.is_none() {
+            return verdict.ok;
         }
+        threshold.is_none_or(|threshold| verdict.score.is_some_and(|score| score >= *threshold))
+            && bounds.is_none_or(|bounds| bounds.admits(verdict))
+    }
+}
+
+/// Tracing target of the per-artefact decision log.
+pub const DECISION_LOG_TARGET: &str = "mlsnitch::decisions";
+
+/// Configuration for the decision log (see the module docs).
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DecisionLogConfig {
+    /// Whether accepted and rejected artefact decisions are logged.
+    pub enabled: bool,
+}
+
+impl Default for DecisionLogConfig {
+    fn default() -> Self {
+        Self { enabled: true }
+    }
+}
+
+impl DecisionLogConfig {
+    /// Returns the `tracing_subscriber::EnvFilter` directive for
+    /// [`DECISION_LOG_TARGET`]. Appended to a node's general filter, it
+    /// overrides whatever level that filter would give the target.
+    pub fn filter_directive(&self) -> String {
+        let level = if self.enabled { "info" } else { "off" };
+        format!("{DECISION_LOG_TARGET}={level}")
     }
 }
 
@@ -71,58 +425,458 @@ impl Default for MlConfig {
 pub struct MlValidity<V> {
     cfg: MlConfig,
     verifier: V,
+    metrics: Option<ConsensusMetrics>,
+    revalidation: RevalidationQueue,
+    stats: Option<MlStatsLog>,
 }
 
 impl<V> MlValidity<V> {
     /// Constructs a new `MlValidity` from a verifier and configuration.
     pub fn new(verifier: V, cfg: MlConfig) -> Self {
-        Self { cfg, verifier }
+        Self {
+            revalidation: RevalidationQueue::new(&cfg.revalidation),
+            cfg,
+            verifier,
+            metrics: None,
+            stats: None,
+        }
+    }
+
+    /// Records every verdict in `metrics` (see the module docs), and
+    /// marks the configured outage policy in `ml_outage_policy`.
+    pub fn with_metrics(mut self, metrics: ConsensusMetrics) -> Self {
+        metrics.observe_ml_outage_policy(self.cfg.outage_policy.as_str());
+        self.revalidation = self.revalidation.with_metrics(metrics.clone());
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Records the [`MlVerdictStats`] of every accepted block in `log`;
+    /// share it with the engine through
+    /// [`ConsensusEngine::with_ml_stats`](crate::consensus::ConsensusEngine::with_ml_stats).
+    pub fn with_stats_log(mut self, log: MlStatsLog) -> Self {
+        self.stats = Some(log);
+        self
+    }
+
+    /// Returns the queue that blocks accepted under
+    /// [`MlOutagePolicy::DeferToRevalidationQueue`] are pushed to; clone
+    /// it to drive [`RevalidationQueue::revalidate`] from a background
+    /// task.
+    pub fn revalidation_queue(&self) -> &RevalidationQueue {
+        &self.revalidation
+    }
+}
+
+/// Async ML-specific block validity predicate.
+///
+/// Same semantics as [`MlValidity`], but verification goes through an
+/// [`AsyncMlVerifier`] and the predicate is awaited via
+/// [`AsyncBlockValidator`].
+pub struct AsyncMlValidity<V> {
+    cfg: MlConfig,
+    verifier: V,
+    metrics: Option<ConsensusMetrics>,
+    revalidation: RevalidationQueue,
+    stats: Option<MlStatsLog>,
+}
+
+impl<V> AsyncMlValidity<V> {
+    /// Constructs a new `AsyncMlValidity` from a verifier and configuration.
+    pub fn new(verifier: V, cfg: MlConfig) -> Self {
+        Self {
+            revalidation: RevalidationQueue::new(&cfg.revalidation),
+            cfg,
+            verifier,
+            metrics: None,
+            stats: None,
+        }
+    }
+
+    /// Records every verdict in `metrics` (see the module docs), and
+    /// marks the configured outage policy in `ml_outage_policy`.
+    pub fn with_metrics(mut self, metrics: ConsensusMetrics) -> Self {
+        metrics.observe_ml_outage_policy(self.cfg.outage_policy.as_str());
+        self.revalidation = self.revalidation.with_metrics(metrics.clone());
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Records the [`MlVerdictStats`] of every accepted block in `log`;
+    /// share it with the engine through
+    /// [`ConsensusEngine::with_ml_stats`](crate::consensus::ConsensusEngine::with_ml_stats).
+    pub fn with_stats_log(mut self, log: MlStatsLog) -> Self {
+        self.stats = Some(log);
+        self
+    }
+
+    /// Returns the queue that blocks accepted under
+    /// [`MlOutagePolicy::DeferToRevalidationQueue`] are pushed to; clone
+    /// it to drive [`RevalidationQueue::revalidate`] from a background
+    /// task.
+    pub fn revalidation_queue(&self) -> &RevalidationQueue {
+        &self.revalidation
+    }
+}
+
+/// Extracts the deduplicated `ML(B)` set for a block and enforces the
+/// per-block artefact cap.
+fn unique_ml_pairs(
+    block: &Block,
+    cfg: &MlConfig,
+) -> Result<Vec<(Aid, EvidenceRef)>, ValidationError> {
+    // Extract ML(B) = all (Aid, EvidenceRef) pairs from TxRegisterModel.
+    let pairs = block.ml_pairs();
+
+    // Deduplicate by (Aid, EvidenceHash) so we don't re-verify the same
+    // logical artefact multiple times in a single block.
+    let mut seen: HashSet<(Aid, EvidenceHash)> = HashSet::new();
+    let mut unique_pairs = Vec::new();
+
+    for (aid, evidence) in pairs {
+        let key = (aid, evidence.evidence_hash);
+        if seen.insert(key) {
+            unique_pairs.push((aid, evidence));
+        }
+    }
+
+    // Enforce per-block cap on ML artefacts.
+    if unique_pairs.len() > cfg.max_artefacts_per_block {
+        return Err(ValidationError::TooManyArtefacts {
+            count: unique_pairs.len(),
+            max: cfg.max_artefacts_per_block,
+        });
+    }
+
+    Ok(unique_pairs)
+}
+
+/// Concatenates per-batch results in batch order, failing with the error of
+/// the first failed batch.
+fn join_batches(
+    batches: &[&[(Aid, EvidenceRef)]],
+    results: Vec<Result<Vec<MlVerdict>, MlError>>,
+) -> Result<Vec<MlVerdict>, MlError> {
+    let mut verdicts = Vec::new();
+    for (batch, result) in batches.iter().zip(results) {
+        let result = result?;
+        if result.len() != batch.len() {
+            return Err(MlError::Protocol(format!(
+                "expected {} verdicts, got {}",
+                batch.len(),
+                result.len()
+            )));
+        }
+        verdicts.extend(result);
+    }
+    Ok(verdicts)
+}
+
+/// Applies [`MlConfig::outage_policy`] to a block whose verification
+/// failed with `err`, returning the decision it is accepted with.
+///
+/// Only an unreachable service counts as an outage; malformed or refused
+/// answers always reject the block, and so does an exhausted
+/// [`MlConfig::verification_budget_ms`].
+fn on_verifier_error(
+    cfg: &MlConfig,
+    metrics: Option<&ConsensusMetrics>,
+    revalidation: &RevalidationQueue,
+    block: &Block,
+    pairs: &[(Aid, EvidenceRef)],
+    err: &MlError,
+) -> Result<&'static str, ValidationError> {
+    let policy = match err {
+        MlError::Transport(_) | MlError::CircuitOpen(_) => cfg.outage_policy,
+        MlError::Protocol(_) | MlError::Service(_) => {
+            return Err(ValidationError::MlUnavailable(format!("{err:?}")));
+        }
+        MlError::DeadlineExceeded(_) => {
+            tracing::warn!(
+                height = block.header.height,
+                artefacts = pairs.len(),
+                budget_ms = cfg.verification_budget_ms,
+                error = ?err,
+                "ML verification budget exhausted; rejecting block"
+            );
+            return Err(ValidationError::MlBudgetExhausted {
+                budget_ms: cfg.verification_budget_ms,
+                artefacts: pairs.len(),
+            });
+        }
+    };
+    if let Some(metrics) = metrics {
+        metrics
+            .ml_outage_blocks
+            .with_label_values(&[policy.as_str()])
+            .inc();
+    }
+
+    let decision = match policy {
+        MlOutagePolicy::RejectBlock => {
+            return Err(ValidationError::MlUnavailable(format!("{err:?}")));
+        }
+        MlOutagePolicy::AcceptWithFlag => "unverified",
+        MlOutagePolicy::DeferToRevalidationQueue => {
+            revalidation.push(block, pairs.to_vec());
+            "deferred"
+        }
+    };
+    let block_hash = hex::encode(block.compute_hash().0.as_bytes());
+    tracing::warn!(
+        block = %block_hash,
+        height = block.header.height,
+        artefacts = pairs.len(),
+        policy = policy.as_str(),
+        error = ?err,
+        "ML verifier unavailable; accepting block without verdicts"
+    );
+    for (aid, evidence) in pairs {
+        tracing::info!(
+            target: DECISION_LOG_TARGET,
+            block = %block_hash,
+            height = block.header.height,
+            aid = %hex::encode(aid.0.as_bytes()),
+            scheme = %evidence.scheme_id,
+            verdict = decision,
+            "ML artefact decision"
+        );
+    }
+    Ok(decision)
+}
+
+/// Maps a batch verifier outcome onto the block-level validation result:
+/// the decision an accepted block's artefacts are recorded with, or the
+/// rejection.
+///
+/// `latency` is how long the batch call took; every verdict is logged to
+/// the decision log and recorded in `metrics` before the first rejection,
+/// if any, is returned. Verifier errors go through [`on_verifier_error`].
+fn check_verdicts(
+    cfg: &MlConfig,
+    metrics: Option<&ConsensusMetrics>,
+    revalidation: &RevalidationQueue,
+    block: &Block,
+    pairs: &[(Aid, EvidenceRef)],
+    result: &Result<Vec<MlVerdict>, MlError>,
+    latency: Duration,
+) -> Result<&'static str, ValidationError> {
+    let verdicts = match result {
+        Ok(verdicts) => verdicts,
+        Err(err) => {
+            if let Some(metrics) = metrics {
+                let outcome = match err {
+                    MlError::DeadlineExceeded(_) => "timeout",
+                    _ => "error",
+                };
+                for (_, evidence) in pairs {
+                    metrics.observe_ml_verdict(&evidence.scheme_id, outcome, latency);
+                }
+            }
+            return on_verifier_error(cfg, metrics, revalidation, block, pairs, err);
+        }
+    };
+
+    if verdicts.len() != pairs.len() {
+        return Err(ValidationError::MlUnavailable(format!(
+            "expected {} verdicts, got {}",
+            pairs.len(),
+            verdicts.len()
+        )));
+    }
+
+    let log_decisions = tracing::enabled!(target: DECISION_LOG_TARGET, tracing::Level::INFO);
+    let block_hash = log_decisions.then(|| hex::encode(block.compute_hash().0.as_bytes()));
+    let mut rejection = None;
+    for ((aid, evidence), verdict) in pairs.iter().zip(verdicts) {
+        let accepted = cfg.accepts(evidence, verdict);
+        let outcome = if accepted { "accepted" } else { "rejected" };
+        if let Some(metrics) = metrics {
+            metrics.observe_ml_verdict(&evidence.scheme_id, outcome, latency);
+        }
+        if let Some(block_hash) = &block_hash {
+            tracing::info!(
+                target: DECISION_LOG_TARGET,
+                block = %block_hash,
+                height = block.header.height,
+                aid = %hex::encode(aid.0.as_bytes()),
+                scheme = %evidence.scheme_id,
+                verdict = outcome,
+                score = verdict.score,
+                trigger_acc = verdict.trigger_acc,
+                feat_dist = verdict.feat_dist,
+                logit_stat = verdict.logit_stat,
+                latency_ms = latency.as_millis() as u64,
+                "ML artefact decision"
+            );
+        }
+        if !accepted && rejection.is_none() {
+            rejection = Some(ValidationError::MlRejected {
+                aid: *aid,
+                verdict: FraudVerdict {
+                    scheme_id: evidence.scheme_id.clone(),
+                    score: verdict.score,
+                },
+            });
+        }
     }
+
+    rejection.map_or(Ok("accepted"), Err)
+}
+
+/// Records the statistics of every registration in `block` in `log`,
+/// with the verdicts of its unique `pairs` if the verifier answered.
+fn record_stats(
+    log: &MlStatsLog,
+    block: &Block,
+    pairs: &[(Aid, EvidenceRef)],
+    result: &Result<Vec<MlVerdict>, MlError>,
+    decision: &str,
+    latency: Duration,
+) {
+    let verdicts = result.as_ref().ok();
+    let stats = block
+        .txs
+        .iter()
+        .enumerate()
+        .filter_map(|(index, tx)| {
+            let Transaction::RegisterModel(reg) = tx else {
+                return None;
+            };
+            let verdict = verdicts.and_then(|verdicts| {
+                pairs
+                    .iter()
+                    .position(|(aid, evidence)| {
+                        *aid == reg.aid && evidence.evidence_hash == reg.evidence.evidence_hash
+                    })
+                    .map(|i| &verdicts[i])
+            });
+            Some(MlVerdictStats {
+                tx_hash: tx.compute_hash(),
+                index: index as u32,
+                aid: reg.aid,
+                scheme_id: reg.evidence.scheme_id.clone(),
+                decision: decision.to_string(),
+                score: verdict.and_then(|v| v.score),
+                trigger_acc: verdict.and_then(|v| v.trigger_acc),
+                feat_dist: verdict.and_then(|v| v.feat_dist),
+                logit_stat: verdict.and_then(|v| v.logit_stat),
+                latency_ms: verdicts.map(|_| latency.as_millis() as u64),
+            })
+        })
+        .collect();
+    log.record(block.compute_hash(), stats);
 }
 
 impl<V> BlockValidator for MlValidity<V>
 where
     V: MlVerifier,
 {
-    fn validate(&self, block: &Block) -> Result<(), ValidationError> {
-        // Extract ML(B) = all (Aid, EvidenceRef) pairs from TxRegisterModel.
-        let pairs = block.ml_pairs();
-
-        // Deduplicate by (Aid, EvidenceHash) so we don't re-verify the same
-        // logical artefact multiple times in a single block.
-        let mut seen: HashSet<(Aid, EvidenceHash)> = HashSet::new();
-        let mut unique_pairs = Vec::new();
-
-        for (aid, evidence) in pairs {
-            let key = (aid, evidence.evidence_hash);
-            if seen.insert(key) {
-                unique_pairs.push((aid, evidence));
-            }
+    #[tracing::instrument(
+        name = "ml_validate",
+        skip_all,
+        fields(height = block.header.height, artefacts = tracing::field::Empty)
+    )]
+    fn validate(&self, block: &Block, _ctx: &ValidationContext<'_>) -> Result<(), ValidationError> {
+        let pairs = unique_ml_pairs(block, &self.cfg)?;
+        tracing::Span::current().record("artefacts", pairs.len());
+        if pairs.is_empty() {
+            return Ok(());
         }
 
-        // Enforce per-block cap on ML artefacts.
-        if unique_pairs.len() > self.cfg.max_artefacts_per_block {
-            return Err(ValidationError::Custom(format!(
-                "block references {} distinct ML artefacts, exceeds max_artefacts_per_block={}",
-                unique_pairs.len(),
-                self.cfg.max_artefacts_per_block
-            )));
+        let started = Instant::now();
+        let deadline = self.cfg.deadline(started);
+        let verify = |batch: &[(Aid, EvidenceRef)]| match deadline {
+            Some(deadline) => self.verifier.verify_batch_until(batch, deadline),
+            None => self.verifier.verify_batch(batch),
+        };
+        let batches: Vec<_> = pairs.chunks(self.cfg.batch_size(pairs.len())).collect();
+        let result = if let [batch] = batches[..] {
+            verify(batch)
+        } else {
+            let results = thread::scope(|s| {
+                let handles: Vec<_> = batches
+                    .iter()
+                    .map(|batch| s.spawn(|| verify(batch)))
+                    .collect();
+                handles
+                    .into_iter()
+                    .map(|h| h.join().expect("ML verifier thread panicked"))
+                    .collect()
+            });
+            join_batches(&batches, results)
+        };
+        let latency = started.elapsed();
+        let decision = check_verdicts(
+            &self.cfg,
+            self.metrics.as_ref(),
+            &self.revalidation,
+            block,
+            &pairs,
+            &result,
+            latency,
+        )?;
+        if let Some(log) = &self.stats {
+            record_stats(log, block, &pairs, &result, decision, latency);
         }
+        Ok(())
+    }
+}
 
-        // Verify each unique artefact.
-        for (aid, evidence) in unique_pairs {
-            let verdict = self
-                .verifier
-                .verify(&aid, &evidence)
-                .map_err(|e| ValidationError::Custom(format!("ML verifier error: {e:?}")))?;
+impl<V> AsyncBlockValidator for AsyncMlValidity<V>
+where
+    V: AsyncMlVerifier,
+{
+    #[tracing::instrument(
+        name = "ml_validate",
+        skip_all,
+        fields(height = block.header.height, artefacts = tracing::field::Empty)
+    )]
+    async fn validate(
+        &self,
+        block: &Block,
+        _ctx: &ValidationContext<'_>,
+    ) -> Result<(), ValidationError> {
+        let pairs = unique_ml_pairs(block, &self.cfg)?;
+        tracing::Span::current().record("artefacts", pairs.len());
+        if pairs.is_empty() {
+            return Ok(());
+        }
 
-            if !verdict.ok {
-                return Err(ValidationError::Custom(
-                    "ML authenticity check failed for artefact".to_string(),
-                ));
+        let started = Instant::now();
+        let deadline = self.cfg.deadline(started);
+        let batches: Vec<_> = pairs.chunks(self.cfg.batch_size(pairs.len())).collect();
+        // Batches still running at the deadline are dropped, which cancels
+        // their in-flight requests.
+        let results = join_all(batches.iter().map(|batch| async move {
+            let call = self.verifier.verify_batch(batch);
+            match deadline {
+                Some(deadline) => tokio::time::timeout_at(deadline.into(), call)
+                    .await
+                    .unwrap_or_else(|_| {
+                        Err(MlError::DeadlineExceeded(format!(
+                            "no verdicts for {} artefacts within the verification budget",
+                            batch.len()
+                        )))
+                    }),
+                None => call.await,
             }
+        }))
+        .await;
+        let result = join_batches(&batches, results);
+        let latency = started.elapsed();
+        let decision = check_verdicts(
+            &self.cfg,
+            self.metrics.as_ref(),
+            &self.revalidation,
+            block,
+            &pairs,
+            &result,
+            latency,
+        )?;
+        if let Some(log) = &self.stats {
+            record_stats(log, block, &pairs, &result, decision, latency);
         }
-
         Ok(())
     }
 }
@@ -144,10 +898,30 @@ mod tests {
         fn verify(&self, _aid: &Aid, _evidence: &EvidenceRef) -> Result<MlVerdict, MlError> {
             Ok(MlVerdict {
                 ok: self.ok,
+                score: None,
                 trigger_acc: None,
                 feat_dist: None,
                 logit_stat: None,
                 latency_ms: None,
+                quorum: None,
+            })
+        }
+    }
+
+    struct DummyAsyncVerifier {
+        ok: bool,
+    }
+
+    impl AsyncMlVerifier for DummyAsyncVerifier {
+        async fn verify(&self, _aid: &Aid, _evidence: &EvidenceRef) -> Result<MlVerdict, MlError> {
+            Ok(MlVerdict {
+                ok: self.ok,
+                score: None,
+                trigger_acc: None,
+                feat_dist: None,
+                logit_stat: None,
+                latency_ms: None,
+                quorum: None,
             })
         }
     }
@@ -183,6 +957,9 @@ mod tests {
             height: 0,
             timestamp: 1_700_000_000,
             proposer: dummy_account(1),
+            txs_root: Hash256([0u8; HASH_LEN]),
+            state_root: Hash256([0u8; HASH_LEN]),
+            receipts_root: Hash256([0u8; HASH_LEN]),
             pos_proof: None,
         };
 
@@ -192,6 +969,7 @@ mod tests {
                 let tx = TxRegisterModel {
                     owner: dummy_account(*b),
                     aid: Aid(dummy_hash(*b)),
+                    chunked: None,
                     evidence: dummy_evidence(*b),
                     fee: 0,
                     nonce: 0,
@@ -206,59 +984,202 @@ mod tests {
 
     #[test]
     fn ml_validity_accepts_when_verifier_ok() {
+        let ctx = ValidationContext::detached();
         let cfg = MlConfig::default();
         let verifier = DummyVerifier { ok: true };
         let v = MlValidity::new(verifier, cfg);
 
         let block = dummy_block_with_aids(&[1, 2, 3]);
-        assert!(v.validate(&block).is_ok());
+        assert!(v.validate(&block, &ctx).is_ok());
     }
 
     #[test]
     fn ml_validity_rejects_when_verifier_fails() {
+        let ctx = ValidationContext::detached();
         let cfg = MlConfig::default();
         let verifier = DummyVerifier { ok: false };
         let v = MlValidity::new(verifier, cfg);
 
         let block = dummy_block_with_aids(&[1, 2, 3]);
-        let err = v.validate(&block).unwrap_err();
-        match err {
-            ValidationError::Custom(msg) => {
+        let err = v.validate(&block, &ctx).unwrap_err();
+        match &err {
+            ValidationError::MlRejected { .. } => {
                 assert!(
-                    msg.contains("ML authenticity check failed"),
-                    "unexpected message: {msg}"
+                    err.to_string().contains("ML authenticity check failed"),
+                    "unexpected message: {err}"
                 );
             }
             _ => panic!("unexpected error variant: {err:?}"),
         }
     }
 
+    /// Collects formatted log output for [`decisions_are_logged_unless_disabled`].
+    #[derive(Clone, Default)]
+    struct LogBuffer(std::sync::Arc<std::sync::Mutex<Vec<u8>>>);
+
+    impl std::io::Write for LogBuffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn logged_decisions(cfg: &DecisionLogConfig, ok: bool) -> String {
+        let ctx = ValidationContext::detached();
+        let buffer = LogBuffer::default();
+        let writer = buffer.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .with_env_filter(format!("warn,{}", cfg.filter_directive()))
+            .with_writer(move || writer.clone())
+            .with_ansi(false)
+            .finish();
+        let v = MlValidity::new(DummyVerifier { ok }, MlConfig::default());
+        tracing::subscriber::with_default(subscriber, || {
+            let _ = v.validate(&dummy_block_with_aids(&[1, 2]), &ctx);
+        });
+        let bytes = buffer.0.lock().unwrap().clone();
+        String::from_utf8(bytes).unwrap()
+    }
+
+    #[test]
+    fn decisions_are_logged_unless_disabled() {
+        let enabled = DecisionLogConfig::default();
+        let accepted = logged_decisions(&enabled, true);
+        assert_eq!(accepted.lines().count(), 2, "{accepted}");
+        assert!(accepted.contains(DECISION_LOG_TARGET));
+        assert!(accepted.contains(&format!("aid={}", hex::encode([2u8; HASH_LEN]))));
+        assert!(accepted.contains("scheme=wm-test-1"));
+        assert!(accepted.contains("verdict=\"accepted\""));
+
+        // A rejected block still logs the verdicts after the first failure.
+        let rejected = logged_decisions(&enabled, false);
+        assert_eq!(rejected.matches("verdict=\"rejected\"").count(), 2);
+
+        let disabled = DecisionLogConfig { enabled: false };
+        assert!(logged_decisions(&disabled, true).is_empty());
+    }
+
+    struct UnreachableVerifier;
+
+    impl MlVerifier for UnreachableVerifier {
+        fn verify(&self, _aid: &Aid, _evidence: &EvidenceRef) -> Result<MlVerdict, MlError> {
+            Err(MlError::Transport("connection refused".to_string()))
+        }
+    }
+
+    #[test]
+    fn ml_validity_reports_verifier_errors_as_unavailable() {
+        let ctx = ValidationContext::detached();
+        let v = MlValidity::new(UnreachableVerifier, MlConfig::default());
+        let err = v.validate(&dummy_block_with_aids(&[1]), &ctx).unwrap_err();
+        assert!(
+            matches!(&err, ValidationError::MlUnavailable(msg) if msg.contains("connection refused")),
+            "unexpected error: {err:?}"
+        );
+        assert!(crate::consensus::ConsensusError::from(err).is_ml_unavailable());
+    }
+
+    #[test]
+    fn ml_validity_records_verdicts_per_scheme_and_outcome() {
+        use crate::ml_client::{MockMlVerifier, MockOutcome};
+
+        let ctx = ValidationContext::detached();
+        let metrics = ConsensusMetrics::register(
+            &prometheus::Registry::new(),
+            &crate::metrics::HistogramBuckets::default(),
+        )
+        .expect("register metrics");
+        let verdicts = |scheme: &str, outcome: &str| {
+            metrics
+                .ml_verdicts
+                .with_label_values(&[scheme, outcome])
+                .get()
+        };
+
+        let verifier = MockMlVerifier::accepting().with_script(
+            Aid(dummy_hash(2)),
+            EvidenceHash(dummy_hash(2)),
+            [MockOutcome::Reject(0.1)],
+        );
+        let v = MlValidity::new(verifier, MlConfig::default()).with_metrics(metrics.clone());
+        assert!(v.validate(&dummy_block_with_aids(&[1, 2]), &ctx).is_err());
+        assert_eq!(verdicts("wm-test-1", "accepted"), 1);
+        assert_eq!(verdicts("wm-test-2", "rejected"), 1);
+
+        let v =
+            MlValidity::new(UnreachableVerifier, MlConfig::default()).with_metrics(metrics.clone());
+        assert!(v.validate(&dummy_block_with_aids(&[1]), &ctx).is_err());
+        assert_eq!(verdicts("wm-test-1", "error"), 1);
+        assert_eq!(
+            metrics
+                .ml_auth_seconds
+                .with_label_values(&["wm-test-1", "accepted"])
+                .get_sample_count(),
+            1
+        );
+    }
+
+    #[test]
+    fn ml_validity_logs_stats_of_accepted_blocks() {
+        let ctx = ValidationContext::detached();
+        let log = MlStatsLog::new();
+        let block = dummy_block_with_aids(&[1, 2]);
+
+        let v = MlValidity::new(DummyVerifier { ok: true }, MlConfig::default())
+            .with_stats_log(log.clone());
+        v.validate(&block, &ctx).unwrap();
+        let stats = log.take(&block.compute_hash());
+        assert_eq!(stats.len(), 2);
+        assert_eq!(stats[1].index, 1);
+        assert_eq!(stats[1].tx_hash, block.txs[1].compute_hash());
+        assert_eq!(stats[1].scheme_id, "wm-test-2");
+        assert_eq!(stats[1].decision, "accepted");
+
+        let cfg = MlConfig {
+            outage_policy: MlOutagePolicy::AcceptWithFlag,
+            ..MlConfig::default()
+        };
+        let v = MlValidity::new(UnreachableVerifier, cfg).with_stats_log(log.clone());
+        v.validate(&block, &ctx).unwrap();
+        let stats = log.take(&block.compute_hash());
+        assert_eq!(stats[0].decision, "unverified");
+        assert_eq!(stats[0].latency_ms, None);
+
+        let v = MlValidity::new(DummyVerifier { ok: false }, MlConfig::default())
+            .with_stats_log(log.clone());
+        assert!(v.validate(&block, &ctx).is_err());
+        assert!(log.take(&block.compute_hash()).is_empty());
+    }
+
     #[test]
     fn ml_validity_enforces_max_artefacts_per_block() {
+        let ctx = ValidationContext::detached();
         let cfg = MlConfig {
             max_artefacts_per_block: 1,
+            ..MlConfig::default()
         };
         let verifier = DummyVerifier { ok: true };
         let v = MlValidity::new(verifier, cfg);
 
         let block = dummy_block_with_aids(&[1, 2]); // 2 distinct aids
-        let err = v.validate(&block).unwrap_err();
-        match err {
-            ValidationError::Custom(msg) => {
-                assert!(
-                    msg.contains("exceeds max_artefacts_per_block"),
-                    "unexpected message: {msg}"
-                );
-            }
-            _ => panic!("unexpected error variant: {err:?}"),
-        }
+        let err = v.validate(&block, &ctx).unwrap_err();
+        assert!(
+            matches!(err, ValidationError::TooManyArtefacts { count: 2, max: 1 }),
+            "unexpected error variant: {err:?}"
+        );
     }
 
     #[test]
     fn ml_validity_deduplicates_same_aid_and_evidence() {
+        let ctx = ValidationContext::detached();
         // max_artefacts_per_block == 1, but we include the same aid twice.
         let cfg = MlConfig {
             max_artefacts_per_block: 1,
+            ..MlConfig::default()
         };
         let verifier = DummyVerifier { ok: true };
         let v = MlValidity::new(verifier, cfg);
@@ -268,6 +1189,9 @@ mod tests {
             height: 0,
             timestamp: 1_700_000_000,
             proposer: dummy_account(1),
+            txs_root: Hash256([0u8; HASH_LEN]),
+            state_root: Hash256([0u8; HASH_LEN]),
+            receipts_root: Hash256([0u8; HASH_LEN]),
             pos_proof: None,
         };
 
@@ -277,6 +1201,7 @@ mod tests {
         let tx1 = TxRegisterModel {
             owner: dummy_account(1),
             aid,
+            chunked: None,
             evidence: EvidenceRef {
                 scheme_id: evidence.scheme_id.clone(),
                 evidence_hash: evidence.evidence_hash,
@@ -290,6 +1215,7 @@ mod tests {
         let tx2 = TxRegisterModel {
             owner: dummy_account(2),
             aid,
+            chunked: None,
             evidence,
             fee: 0,
             nonce: 1,
@@ -305,6 +1231,277 @@ mod tests {
         };
 
         // Should be accepted because we deduplicate (aid, evidence_hash).
-        assert!(v.validate(&block).is_ok());
+        assert!(v.validate(&block, &ctx).is_ok());
+    }
+
+    #[tokio::test]
+    async fn async_ml_validity_accepts_and_rejects() {
+        let ctx = ValidationContext::detached();
+        let block = dummy_block_with_aids(&[1, 2, 3]);
+
+        let ok = AsyncMlValidity::new(DummyAsyncVerifier { ok: true }, MlConfig::default());
+        assert!(ok.validate(&block, &ctx).await.is_ok());
+
+        let bad = AsyncMlValidity::new(DummyAsyncVerifier { ok: false }, MlConfig::default());
+        let err = bad.validate(&block, &ctx).await.unwrap_err();
+        match &err {
+            ValidationError::MlRejected { .. } => {
+                assert!(
+                    err.to_string().contains("ML authenticity check failed"),
+                    "unexpected message: {err}"
+                );
+            }
+            _ => panic!("unexpected error variant: {err:?}"),
+        }
+    }
+
+    /// Verifier with a native batch call that records the size of every
+    /// batch it receives.
+    #[derive(Default)]
+    struct BatchingVerifier {
+        batches: std::sync::Mutex<Vec<usize>>,
+    }
+
+    impl MlVerifier for BatchingVerifier {
+        fn verify(&self, _aid: &Aid, _evidence: &EvidenceRef) -> Result<MlVerdict, MlError> {
+            panic!("MlValidity should use verify_batch");
+        }
+
+        fn verify_batch(&self, items: &[(Aid, EvidenceRef)]) -> Result<Vec<MlVerdict>, MlError> {
+            self.batches.lock().unwrap().push(items.len());
+            // Reject only the artefact with aid byte 3.
+            Ok(items
+                .iter()
+                .map(|(aid, _)| verdict(aid.as_hash().as_bytes()[0] != 3, None))
+                .collect())
+        }
+    }
+
+    #[test]
+    fn ml_validity_verifies_whole_block_in_one_batch() {
+        let ctx = ValidationContext::detached();
+        let v = MlValidity::new(BatchingVerifier::default(), MlConfig::default());
+
+        assert!(
+            v.validate(&dummy_block_with_aids(&[1, 2, 4, 5]), &ctx)
+                .is_ok()
+        );
+        assert!(v.validate(&dummy_block_with_aids(&[1, 3]), &ctx).is_err());
+        // Blocks without registrations do not reach the verifier.
+        assert!(v.validate(&dummy_block_with_aids(&[]), &ctx).is_ok());
+
+        assert_eq!(*v.verifier.batches.lock().unwrap(), vec![4, 2]);
+    }
+
+    #[test]
+    fn ml_validity_splits_block_into_parallel_batches() {
+        let ctx = ValidationContext::detached();
+        let cfg = MlConfig {
+            max_concurrency: 2,
+            ..MlConfig::default()
+        };
+        let v = MlValidity::new(BatchingVerifier::default(), cfg);
+
+        assert!(
+            v.validate(&dummy_block_with_aids(&[1, 2, 4, 5, 6]), &ctx)
+                .is_ok()
+        );
+        let mut batches = v.verifier.batches.lock().unwrap().clone();
+        batches.sort();
+        assert_eq!(batches, vec![2, 3]);
+
+        // The rejection names the offending artefact whichever batch it is in.
+        let err = v
+            .validate(&dummy_block_with_aids(&[1, 2, 4, 3]), &ctx)
+            .unwrap_err();
+        assert!(err.to_string().contains("wm-test-3"), "{err}");
+    }
+
+    #[tokio::test]
+    async fn async_ml_validity_verifies_batches_concurrently() {
+        let ctx = ValidationContext::detached();
+        struct Slow;
+
+        impl AsyncMlVerifier for Slow {
+            async fn verify(
+                &self,
+                aid: &Aid,
+                _evidence: &EvidenceRef,
+            ) -> Result<MlVerdict, MlError> {
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                Ok(verdict(aid.as_hash().as_bytes()[0] != 3, None))
+            }
+        }
+
+        let cfg = MlConfig {
+            max_concurrency: 4,
+            ..MlConfig::default()
+        };
+        let v = AsyncMlValidity::new(Slow, cfg);
+
+        let started = Instant::now();
+        assert!(
+            v.validate(&dummy_block_with_aids(&[1, 2, 4, 5]), &ctx)
+                .await
+                .is_ok()
+        );
+        assert!(started.elapsed() < Duration::from_millis(150));
+
+        let err = v
+            .validate(&dummy_block_with_aids(&[5, 3, 2]), &ctx)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("wm-test-3"), "{err}");
+    }
+
+    #[test]
+    fn ml_validity_rejects_blocks_exceeding_the_verification_budget() {
+        use crate::ml_client::MockMlVerifier;
+
+        let ctx = ValidationContext::detached();
+        let slow = || MockMlVerifier::accepting().with_latency(Duration::from_secs(5));
+        // An exhausted budget is not an outage: accepting policies do not
+        // let the block through.
+        let cfg = MlConfig {
+            max_concurrency: 2,
+            outage_policy: MlOutagePolicy::AcceptWithFlag,
+            verification_budget_ms: 20,
+            ..MlConfig::default()
+        };
+        let v = MlValidity::new(slow(), cfg);
+
+        let started = Instant::now();
+        let err = v
+            .validate(&dummy_block_with_aids(&[1, 2, 3]), &ctx)
+            .unwrap_err();
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert!(
+            matches!(
+                err,
+                ValidationError::MlBudgetExhausted {
+                    budget_ms: 20,
+                    artefacts: 3
+                }
+            ),
+            "unexpected error variant: {err:?}"
+        );
+        assert_eq!(err.kind(), "ml_budget_exhausted");
+
+        let fast = MlValidity::new(MockMlVerifier::accepting(), MlConfig::default());
+        assert!(
+            fast.validate(&dummy_block_with_aids(&[1, 2, 3]), &ctx)
+                .is_ok()
+        );
+    }
+
+    #[tokio::test]
+    async fn async_ml_validity_cancels_calls_at_the_verification_budget() {
+        use crate::ml_client::MockMlVerifier;
+
+        let ctx = ValidationContext::detached();
+        let cfg = MlConfig {
+            verification_budget_ms: 20,
+            ..MlConfig::default()
+        };
+        let verifier = MockMlVerifier::accepting().with_latency(Duration::from_secs(5));
+        let v = AsyncMlValidity::new(verifier, cfg);
+
+        let started = Instant::now();
+        let err = v
+            .validate(&dummy_block_with_aids(&[1, 2]), &ctx)
+            .await
+            .unwrap_err();
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert!(
+            matches!(err, ValidationError::MlBudgetExhausted { artefacts: 2, .. }),
+            "unexpected error variant: {err:?}"
+        );
+    }
+
+    #[tokio::test]
+    async fn default_verify_batch_falls_back_to_per_item_calls() {
+        let items: Vec<_> = [1, 2]
+            .into_iter()
+            .map(|b| (Aid(dummy_hash(b)), dummy_evidence(b)))
+            .collect();
+
+        let verdicts = DummyVerifier { ok: true }.verify_batch(&items).unwrap();
+        assert_eq!(verdicts.len(), 2);
+
+        let verdicts = DummyAsyncVerifier { ok: false }
+            .verify_batch(&items)
+            .await
+            .unwrap();
+        assert!(verdicts.iter().all(|v| !v.ok));
+    }
+
+    fn verdict(ok: bool, score: Option<f32>) -> MlVerdict {
+        MlVerdict {
+            ok,
+            score,
+            trigger_acc: None,
+            feat_dist: None,
+            logit_stat: None,
+            latency_ms: None,
+            quorum: None,
+        }
+    }
+
+    #[test]
+    fn ml_config_accepts_uses_ok_without_threshold() {
+        let cfg = MlConfig::default();
+
+        assert!(cfg.accepts(&dummy_evidence(1), &verdict(true, None)));
+        assert!(!cfg.accepts(&dummy_evidence(1), &verdict(false, Some(0.99))));
+    }
+
+    #[test]
+    fn ml_config_accepts_uses_score_threshold_per_scheme() {
+        let mut cfg = MlConfig::default();
+        cfg.score_thresholds.insert("wm-test-1".to_string(), 0.8);
+
+        // The service's boolean is ignored for thresholded schemes.
+        assert!(cfg.accepts(&dummy_evidence(1), &verdict(false, Some(0.85))));
+        assert!(!cfg.accepts(&dummy_evidence(1), &verdict(true, Some(0.5))));
+        assert!(!cfg.accepts(&dummy_evidence(1), &verdict(true, None)));
+
+        // Other schemes still fall back to `ok`.
+        assert!(cfg.accepts(&dummy_evidence(2), &verdict(true, Some(0.1))));
+    }
+
+    fn stats_verdict(trigger_acc: f32, feat_dist: f32, logit_stat: f32) -> MlVerdict {
+        MlVerdict {
+            trigger_acc: Some(trigger_acc),
+            feat_dist: Some(feat_dist),
+            logit_stat: Some(logit_stat),
+            ..verdict(true, None)
+        }
+    }
+
+    #[test]
+    fn ml_config_accepts_checks_verdict_statistics() {
+        let mut cfg = MlConfig::default();
+        cfg.verdict_bounds.insert(
+            "wm-test-1".to_string(),
+            VerdictBounds {
+                min_trigger_acc: Some(0.9),
+                ..VerdictBounds::default()
+            },
+        );
+        let evidence = dummy_evidence(1);
+
+        assert!(cfg.accepts(&evidence, &stats_verdict(0.95, 9.0, 9.0)));
+        // `ok` is not trusted when the statistic misses the bound or is absent.
+        assert!(!cfg.accepts(&evidence, &stats_verdict(0.85, 0.0, 0.0)));
+        assert!(!cfg.accepts(&evidence, &verdict(true, None)));
+
+        // With profile bounds, other schemes are judged against their own
+        // WmProfile (tau_input 0.9, tau_feat 0.1, logit band [0.02, 0.05]).
+        let other = dummy_evidence(2);
+        assert!(cfg.accepts(&other, &stats_verdict(0.5, 1.0, 1.0)));
+        cfg.profile_bounds = true;
+        assert!(!cfg.accepts(&other, &stats_verdict(0.5, 1.0, 1.0)));
+        assert!(cfg.accepts(&other, &stats_verdict(0.95, 0.05, 0.03)));
+        assert!(!cfg.accepts(&other, &stats_verdict(0.95, 0.05, 0.2)));
     }
 }
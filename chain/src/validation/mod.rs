@@ -1,16 +1,44 @@
-//! Block validity predicates for the chain.
+//! Block and transaction validity predicates for the chain.
 //!
 //! This module implements concrete block validators that plug into the
-//! consensus layer via [`crate::consensus::validator::BlockValidator`].
+//! consensus layer via [`crate::consensus::validator::BlockValidator`],
+//! and transaction validators that plug in via
+//! [`crate::consensus::validator::TxValidator`].
 //!
 //! It currently provides:
 //!
-//! - [`base::BaseValidity`]: cheap structural and size checks (V_base-ish).
+//! - [`base::BaseValidity`]: cheap structural, size, timestamp and
+//!   intra-block duplicate/nonce checks (V_base-ish).
 //! - [`ml::MlValidity`]: ML-specific authenticity checks via a generic
 //!   [`ml::MlVerifier`] interface.
+//! - [`ml::AsyncMlValidity`]: the same checks, awaited through an
+//!   [`ml::AsyncMlVerifier`].
+//! - [`revalidation::RevalidationQueue`]: background re-checks of blocks
+//!   accepted while the ML service was unreachable.
+//! - [`proposer::ProposerValidity`]: the block's proposer must be the slot
+//!   leader named by a [`crate::consensus::ProposerSelector`].
+//! - [`tx::StatelessTxValidity`]: per-transaction field, signature-shape,
+//!   and fee checks run on submission.
+//! - [`stateful::StatefulTxValidity`]: per-transaction checks against chain
+//!   state (model transfers and revocations need a registered `Aid` and an
+//!   authorised signer; revoked models cannot be used).
+//! - [`stateful::StatefulValidity`]: whole-block balance, nonce and
+//!   registry checks against the tip state published by the engine, and
+//!   fraud proof checks against the store ([`stateful::check_fraud_proof`]).
 
 pub mod base;
 pub mod ml;
+pub mod proposer;
+pub mod revalidation;
+pub mod stateful;
+pub mod tx;
 
 pub use base::BaseValidity;
-pub use ml::{MlConfig, MlError, MlValidity, MlVerdict, MlVerifier};
+pub use ml::{
+    AsyncMlValidity, AsyncMlVerifier, DECISION_LOG_TARGET, DecisionLogConfig, MlConfig, MlError,
+    MlOutagePolicy, MlValidity, MlVerdict, MlVerifier, QuorumVotes, VerdictBounds,
+};
+pub use proposer::ProposerValidity;
+pub use revalidation::{LateRejection, RevalidationConfig, RevalidationQueue, RevalidationSummary};
+pub use stateful::{StatefulTxValidity, StatefulValidity, check_fraud_proof};
+pub use tx::{StatelessTxValidity, TxValidityConfig};
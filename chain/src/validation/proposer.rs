@@ -0,0 +1,84 @@
+//! Proposer validity predicate for blocks.
+//!
+//! Checks that every non-genesis block was proposed by the leader of its
+//! slot, as named by a [`ProposerSelector`], and that it carries whatever
+//! eligibility proof the selector requires (e.g. a VRF proof). The genesis
+//! block is fixed by the genesis spec and exempt.
+
+use crate::consensus::error::ValidationError;
+use crate::consensus::schedule::{ProposerSelector, RoundRobinSelector};
+use crate::consensus::validator::{BlockValidator, ValidationContext};
+use crate::types::Block;
+
+/// Rejects blocks whose proposer is not the slot leader.
+#[derive(Clone, Debug, Default)]
+pub struct ProposerValidity<P = RoundRobinSelector> {
+    selector: P,
+}
+
+impl<P: ProposerSelector> ProposerValidity<P> {
+    /// Constructs the predicate over `selector`.
+    pub fn new(selector: P) -> Self {
+        Self { selector }
+    }
+
+    /// Returns the schedule this predicate enforces.
+    pub fn selector(&self) -> &P {
+        &self.selector
+    }
+}
+
+impl<P: ProposerSelector> BlockValidator for ProposerValidity<P> {
+    fn validate(&self, block: &Block, _ctx: &ValidationContext<'_>) -> Result<(), ValidationError> {
+        if block.header.height == 0 {
+            return Ok(());
+        }
+        match self.selector.expected_proposer(block.header.timestamp) {
+            Some(leader) if leader != block.header.proposer => {
+                Err(ValidationError::WrongProposer {
+                    proposer: block.header.proposer,
+                    leader,
+                })
+            }
+            _ => self
+                .selector
+                .verify_eligibility(&block.header)
+                .map_err(ValidationError::IneligibleProposer),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::consensus::schedule::ValidatorSet;
+    use crate::types::{AccountId, BlockHash, HASH_LEN, Hash256, Header};
+
+    fn block(height: u64, timestamp: u64, proposer: u8) -> Block {
+        Block {
+            header: Header {
+                parent: BlockHash(Hash256([0u8; HASH_LEN])),
+                height,
+                timestamp,
+                proposer: AccountId(Hash256([proposer; HASH_LEN])),
+                txs_root: Hash256([0u8; HASH_LEN]),
+                state_root: Hash256([0u8; HASH_LEN]),
+                receipts_root: Hash256([0u8; HASH_LEN]),
+                pos_proof: None,
+            },
+            txs: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn only_the_slot_leader_may_propose() {
+        let set = ValidatorSet::new([1, 2].map(|b| AccountId(Hash256([b; HASH_LEN]))));
+        let validity = ProposerValidity::new(RoundRobinSelector::new(set, 10));
+
+        let check = |block: Block| validity.validate(&block, &ValidationContext::detached());
+        check(block(1, 10, 2)).expect("slot 1 is leader 2's");
+        assert!(check(block(1, 10, 1)).is_err());
+        assert!(check(block(2, 25, 3)).is_err());
+        check(block(0, 10, 1)).expect("genesis is exempt");
+    }
+}
@@ -0,0 +1,385 @@
+//! Background re-checks of artefacts accepted during an ML outage.
+//!
+//! Under [`MlOutagePolicy::DeferToRevalidationQueue`](super::ml::MlOutagePolicy)
+//! a block whose artefacts could not be verified is accepted and its
+//! `ML(B)` set is pushed to a [`RevalidationQueue`]. A background task
+//! periodically calls [`RevalidationQueue::revalidate`], which verifies
+//! every queued block once more:
+//!
+//! - blocks whose artefacts all pass are dropped from the queue,
+//! - blocks with a negative verdict are returned as [`LateRejection`]s,
+//!   since the block itself is already part of the chain and can only be
+//!   reported, not undone,
+//! - blocks the service still cannot verify are queued again, until they
+//!   have been tried [`RevalidationConfig::max_attempts`] times.
+//!
+//! The queue is bounded by [`RevalidationConfig::queue_size`]; when full,
+//! the oldest entry is dropped. Queue length and outcomes are exported
+//! through [`ConsensusMetrics`] if attached with `with_metrics`.
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+use serde::Deserialize;
+
+use crate::metrics::ConsensusMetrics;
+use crate::types::{AccountId, Aid, Block, BlockHash, EvidenceRef, FraudVerdict};
+
+use super::ml::{AsyncMlVerifier, MlConfig};
+
+/// Configuration for [`RevalidationQueue`].
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RevalidationConfig {
+    /// Maximum number of blocks waiting for a re-check.
+    pub queue_size: usize,
+    /// Seconds between background re-check rounds.
+    pub interval_secs: u64,
+    /// Rounds a block is tried while the service stays unreachable before
+    /// it is given up on; `0` retries forever.
+    pub max_attempts: u32,
+}
+
+impl Default for RevalidationConfig {
+    fn default() -> Self {
+        Self {
+            queue_size: 1024,
+            interval_secs: 30,
+            max_attempts: 10,
+        }
+    }
+}
+
+/// A block whose artefacts still need a verdict.
+#[derive(Clone, Debug)]
+struct DeferredBlock {
+    block_hash: BlockHash,
+    height: u64,
+    proposer: AccountId,
+    pairs: Vec<(Aid, EvidenceRef)>,
+    attempts: u32,
+}
+
+/// A deferred block that failed its re-check.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LateRejection {
+    /// The block carrying the artefact.
+    pub block_hash: BlockHash,
+    /// Height of `block_hash`.
+    pub height: u64,
+    /// Proposer of `block_hash`.
+    pub proposer: AccountId,
+    /// First artefact of the block that was judged inauthentic.
+    pub aid: Aid,
+    /// The verifier's verdict on `aid`.
+    pub verdict: FraudVerdict,
+}
+
+/// Outcome of one [`RevalidationQueue::revalidate`] round.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RevalidationSummary {
+    /// Blocks whose artefacts all passed.
+    pub accepted: usize,
+    /// Blocks with an inauthentic artefact.
+    pub rejections: Vec<LateRejection>,
+    /// Blocks queued again because the service is still unreachable.
+    pub retried: usize,
+    /// Blocks given up on after `max_attempts` rounds.
+    pub dropped: usize,
+}
+
+/// Shared, bounded queue of blocks accepted without ML verdicts.
+///
+/// Clones share the same queue, so the validator that fills it and the
+/// background task that drains it can each hold one.
+#[derive(Clone)]
+pub struct RevalidationQueue {
+    pending: Arc<Mutex<VecDeque<DeferredBlock>>>,
+    queue_size: usize,
+    max_attempts: u32,
+    metrics: Option<ConsensusMetrics>,
+}
+
+impl RevalidationQueue {
+    /// Creates an empty queue.
+    pub fn new(cfg: &RevalidationConfig) -> Self {
+        Self {
+            pending: Arc::new(Mutex::new(VecDeque::new())),
+            queue_size: cfg.queue_size.max(1),
+            max_attempts: cfg.max_attempts,
+            metrics: None,
+        }
+    }
+
+    /// Exports the queue length and re-check outcomes to `metrics`.
+    pub fn with_metrics(mut self, metrics: ConsensusMetrics) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Number of blocks waiting for a re-check.
+    pub fn len(&self) -> usize {
+        self.pending
+            .lock()
+            .expect("revalidation queue poisoned")
+            .len()
+    }
+
+    /// Returns `true` if no block is waiting.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Queues `block` for a re-check of `pairs`, its deduplicated `ML(B)`
+    /// set. Drops the oldest entry if the queue is full.
+    pub fn push(&self, block: &Block, pairs: Vec<(Aid, EvidenceRef)>) {
+        self.requeue(DeferredBlock {
+            block_hash: block.compute_hash(),
+            height: block.header.height,
+            proposer: block.header.proposer,
+            pairs,
+            attempts: 0,
+        });
+    }
+
+    fn requeue(&self, entry: DeferredBlock) {
+        let mut pending = self.pending.lock().expect("revalidation queue poisoned");
+        if pending.len() >= self.queue_size {
+            pending.pop_front();
+            self.count("dropped");
+        }
+        pending.push_back(entry);
+        self.set_len(pending.len());
+    }
+
+    /// Re-checks every block queued when the round starts with `verifier`,
+    /// judging verdicts with [`MlConfig::accepts`].
+    ///
+    /// Blocks pushed while the round runs wait for the next one.
+    pub async fn revalidate<V: AsyncMlVerifier>(
+        &self,
+        verifier: &V,
+        cfg: &MlConfig,
+    ) -> RevalidationSummary {
+        let round: Vec<_> = {
+            let mut pending = self.pending.lock().expect("revalidation queue poisoned");
+            let round = pending.drain(..).collect();
+            self.set_len(0);
+            round
+        };
+
+        let mut summary = RevalidationSummary::default();
+        for mut entry in round {
+            let verdicts = match verifier.verify_batch(&entry.pairs).await {
+                Ok(verdicts) if verdicts.len() == entry.pairs.len() => verdicts,
+                _ => {
+                    entry.attempts += 1;
+                    if self.max_attempts > 0 && entry.attempts >= self.max_attempts {
+                        summary.dropped += 1;
+                        self.count("dropped");
+                    } else {
+                        summary.retried += 1;
+                        self.count("retried");
+                        self.requeue(entry);
+                    }
+                    continue;
+                }
+            };
+
+            let rejected = entry
+                .pairs
+                .iter()
+                .zip(&verdicts)
+                .find(|((_, evidence), verdict)| !cfg.accepts(evidence, verdict));
+            match rejected {
+                Some(((aid, evidence), verdict)) => {
+                    summary.rejections.push(LateRejection {
+                        block_hash: entry.block_hash,
+                        height: entry.height,
+                        proposer: entry.proposer,
+                        aid: *aid,
+                        verdict: FraudVerdict {
+                            scheme_id: evidence.scheme_id.clone(),
+                            score: verdict.score,
+                        },
+                    });
+                    self.count("rejected");
+                }
+                None => {
+                    summary.accepted += 1;
+                    self.count("accepted");
+                }
+            }
+        }
+        summary
+    }
+
+    fn count(&self, outcome: &str) {
+        if let Some(metrics) = &self.metrics {
+            metrics.ml_revalidations.with_label_values(&[outcome]).inc();
+        }
+    }
+
+    fn set_len(&self, len: usize) {
+        if let Some(metrics) = &self.metrics {
+            metrics.ml_revalidation_queue.set(len as i64);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    use crate::consensus::{AsyncBlockValidator, ValidationContext, ValidationError};
+    use crate::types::{
+        EvidenceHash, HASH_LEN, Hash256, Header, Signature, Transaction, TxRegisterModel, WmProfile,
+    };
+    use crate::validation::ml::{AsyncMlValidity, MlError, MlOutagePolicy, MlVerdict};
+
+    /// Verifier that is unreachable until `up` is set, then rejects the
+    /// artefact with aid byte 3.
+    #[derive(Default)]
+    struct FlakyVerifier {
+        up: AtomicBool,
+    }
+
+    impl AsyncMlVerifier for FlakyVerifier {
+        async fn verify(&self, aid: &Aid, _evidence: &EvidenceRef) -> Result<MlVerdict, MlError> {
+            if !self.up.load(Ordering::SeqCst) {
+                return Err(MlError::Transport("connection refused".to_string()));
+            }
+            Ok(MlVerdict {
+                ok: aid.as_hash().as_bytes()[0] != 3,
+                score: None,
+                trigger_acc: None,
+                feat_dist: None,
+                logit_stat: None,
+                latency_ms: None,
+                quorum: None,
+            })
+        }
+    }
+
+    impl AsyncMlVerifier for &FlakyVerifier {
+        async fn verify(&self, aid: &Aid, evidence: &EvidenceRef) -> Result<MlVerdict, MlError> {
+            (*self).verify(aid, evidence).await
+        }
+    }
+
+    fn block_with_aids(height: u64, aids: &[u8]) -> Block {
+        let txs = aids
+            .iter()
+            .map(|&b| {
+                Transaction::RegisterModel(TxRegisterModel {
+                    owner: AccountId(Hash256([9; HASH_LEN])),
+                    aid: Aid(Hash256([b; HASH_LEN])),
+                    chunked: None,
+                    evidence: EvidenceRef {
+                        scheme_id: "wm-test".to_string(),
+                        evidence_hash: EvidenceHash(Hash256([b; HASH_LEN])),
+                        wm_profile: WmProfile {
+                            tau_input: 0.9,
+                            tau_feat: 0.1,
+                            logit_band_low: 0.02,
+                            logit_band_high: 0.05,
+                        },
+                    },
+                    fee: 1,
+                    nonce: 0,
+                    signature: Signature(vec![]),
+                })
+            })
+            .collect();
+        Block {
+            header: Header {
+                parent: BlockHash(Hash256([0; HASH_LEN])),
+                height,
+                timestamp: 1_700_000_000,
+                proposer: AccountId(Hash256([1; HASH_LEN])),
+                txs_root: Hash256([0; HASH_LEN]),
+                state_root: Hash256([0; HASH_LEN]),
+                receipts_root: Hash256([0; HASH_LEN]),
+                pos_proof: None,
+            },
+            txs,
+        }
+    }
+
+    fn config(policy: MlOutagePolicy, max_attempts: u32) -> MlConfig {
+        MlConfig {
+            outage_policy: policy,
+            revalidation: RevalidationConfig {
+                max_attempts,
+                ..RevalidationConfig::default()
+            },
+            ..MlConfig::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn outage_policy_decides_whether_unverified_blocks_pass() {
+        let ctx = ValidationContext::detached();
+        let verifier = FlakyVerifier::default();
+        let block = block_with_aids(1, &[1, 2]);
+
+        let reject = AsyncMlValidity::new(&verifier, config(MlOutagePolicy::RejectBlock, 10));
+        assert!(matches!(
+            reject.validate(&block, &ctx).await,
+            Err(ValidationError::MlUnavailable(_))
+        ));
+
+        let flag = AsyncMlValidity::new(&verifier, config(MlOutagePolicy::AcceptWithFlag, 10));
+        assert!(flag.validate(&block, &ctx).await.is_ok());
+        assert!(flag.revalidation_queue().is_empty());
+
+        let defer = AsyncMlValidity::new(
+            &verifier,
+            config(MlOutagePolicy::DeferToRevalidationQueue, 10),
+        );
+        assert!(defer.validate(&block, &ctx).await.is_ok());
+        assert_eq!(defer.revalidation_queue().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn revalidation_retries_until_the_service_answers() {
+        let ctx = ValidationContext::detached();
+        let verifier = FlakyVerifier::default();
+        let cfg = config(MlOutagePolicy::DeferToRevalidationQueue, 2);
+        let validity = AsyncMlValidity::new(&verifier, cfg.clone());
+        let queue = validity.revalidation_queue().clone();
+        validity
+            .validate(&block_with_aids(1, &[1, 2]), &ctx)
+            .await
+            .unwrap();
+        validity
+            .validate(&block_with_aids(2, &[3]), &ctx)
+            .await
+            .unwrap();
+
+        // Still down: both blocks are retried.
+        let summary = queue.revalidate(&verifier, &cfg).await;
+        assert_eq!(summary.retried, 2);
+        assert_eq!(queue.len(), 2);
+
+        verifier.up.store(true, Ordering::SeqCst);
+        let summary = queue.revalidate(&verifier, &cfg).await;
+        assert_eq!(summary.accepted, 1);
+        assert_eq!(summary.rejections.len(), 1);
+        let late = &summary.rejections[0];
+        assert_eq!(late.height, 2);
+        assert_eq!(late.aid, Aid(Hash256([3; HASH_LEN])));
+        assert!(queue.is_empty());
+
+        // A block that never gets a verdict is dropped after max_attempts.
+        verifier.up.store(false, Ordering::SeqCst);
+        validity
+            .validate(&block_with_aids(3, &[4]), &ctx)
+            .await
+            .unwrap();
+        assert_eq!(queue.revalidate(&verifier, &cfg).await.retried, 1);
+        assert_eq!(queue.revalidate(&verifier, &cfg).await.dropped, 1);
+        assert!(queue.is_empty());
+    }
+}
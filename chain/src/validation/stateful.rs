@@ -0,0 +1,575 @@
+//! Stateful validity checks for individual transactions.
+//!
+//! These complement [`StatelessTxValidity`](super::StatelessTxValidity)
+//! with checks that need the current chain state, so that transactions
+//! bound to fail execution are rejected on submission instead of
+//! occupying the mempool:
+//!
+//! - every transaction must pay at least the network's
+//!   [`ChainParams::min_fee`](crate::execution::ChainParams::min_fee),
+//! - a `TxTransferModel` must name a registered `Aid`, and its
+//!   `current_owner` must be the artefact's recorded owner,
+//! - a `TxUseModel` must not name a revoked `Aid`, and
+//! - a `TxRevokeModel` must name a registered, not yet revoked `Aid`, and
+//!   be signed by its owner or a governance account, and
+//! - a `TxUnstake` must not exceed the signer's bonded stake, and
+//! - a `TxSlash` must come from a staked or governance account, carry a
+//!   fraud proof signed by it, and name a block that has not been slashed
+//!   yet.
+//!
+//! Execution enforces the same rules, except for the fee floor, so a
+//! transaction admitted against a stale state still cannot take effect.
+//!
+//! [`StatefulValidity`] applies the account and registry rules to whole
+//! blocks, reading the tip state through a [`StateHandle`]. It rejects a
+//! block that extends the tip but contains:
+//!
+//! - a transaction whose fee (plus amount, for transfers and stake) exceeds
+//!   the signer's balance at that point of the block,
+//! - a replayed or skipped nonce,
+//! - a `TxRegisterModel` for an `Aid` that is already registered, or
+//! - a `TxUseModel` for an `Aid` that is not registered.
+//!
+//! Blocks on side branches are left to execution, since the handle only
+//! holds the state after the canonical tip. The fraud proofs of every
+//! block, side branches included, are checked against the store
+//! ([`check_fraud_proof`]): the block a proof names must be stored, with
+//! the proof's proposer and height, and must register the proof's
+//! artefact. Without this, a reporter could slash any staker with a
+//! made-up block hash.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::consensus::error::ValidationError;
+use crate::consensus::validator::{BlockValidator, ReadStore, ValidationContext};
+use crate::execution::{Account, ChainState, StateHandle};
+use crate::types::{AccountId, Aid, ArtefactMetadata, Block, FraudProof, Transaction};
+
+/// Transaction validity predicate that consults chain state.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct StatefulTxValidity;
+
+impl StatefulTxValidity {
+    /// Checks `tx` against `state`.
+    pub fn validate_tx(&self, tx: &Transaction, state: &ChainState) -> Result<(), ValidationError> {
+        let min_fee = state.params().min_fee;
+        if tx.fee() < min_fee {
+            return Err(ValidationError::FeeTooLow {
+                fee: tx.fee(),
+                min: min_fee,
+            });
+        }
+        match tx {
+            Transaction::TransferModel(transfer) => {
+                let meta = registered(state, &transfer.aid)?;
+                if meta.owner != transfer.current_owner {
+                    return Err(ValidationError::NotArtefactOwner {
+                        aid: transfer.aid,
+                        signer: transfer.current_owner,
+                    });
+                }
+            }
+            Transaction::UseModel(tx_use) => {
+                if state.artefact(&tx_use.aid).is_some_and(|m| m.is_revoked()) {
+                    return Err(ValidationError::RevokedArtefact(tx_use.aid));
+                }
+            }
+            Transaction::RevokeModel(revoke) => {
+                let meta = registered(state, &revoke.aid)?;
+                if meta.is_revoked() {
+                    return Err(ValidationError::RevokedArtefact(revoke.aid));
+                }
+                if meta.owner != revoke.signer && !state.is_governance(&revoke.signer) {
+                    return Err(ValidationError::RevocationNotAllowed {
+                        aid: revoke.aid,
+                        signer: revoke.signer,
+                    });
+                }
+            }
+            Transaction::Unstake(unstake) => {
+                let bonded = state.stake(&unstake.staker).map_or(0, |s| s.bonded);
+                if unstake.amount > bonded {
+                    return Err(ValidationError::InsufficientStake {
+                        account: unstake.staker,
+                        needed: unstake.amount,
+                        bonded,
+                    });
+                }
+            }
+            Transaction::Slash(slash) => {
+                let proof = &slash.proof;
+                proof
+                    .verify_signature()
+                    .map_err(|e| ValidationError::BadSignature(format!("fraud proof: {e}")))?;
+                let reporter_staked = state
+                    .stake(&proof.reporter)
+                    .is_some_and(|stake| stake.active > 0);
+                if !reporter_staked && !state.is_governance(&proof.reporter) {
+                    return Err(ValidationError::ReportNotAllowed(proof.reporter));
+                }
+                if state.is_slashed(&proof.block_hash) {
+                    return Err(ValidationError::AlreadySlashed(proof.block_hash));
+                }
+            }
+            Transaction::RegisterModel(_) | Transaction::Transfer(_) | Transaction::Stake(_) => {}
+        }
+        Ok(())
+    }
+}
+
+/// Block validity predicate that checks transactions against the tip state.
+#[derive(Clone, Debug, Default)]
+pub struct StatefulValidity {
+    state: StateHandle,
+}
+
+impl StatefulValidity {
+    /// Constructs the predicate over `state`, which the engine keeps
+    /// current (see
+    /// [`ConsensusEngine::with_state_handle`](crate::consensus::ConsensusEngine::with_state_handle)).
+    pub fn new(state: StateHandle) -> Self {
+        Self { state }
+    }
+
+    /// Returns the state handle this predicate reads.
+    pub fn state(&self) -> &StateHandle {
+        &self.state
+    }
+
+    /// Checks the transactions of `block` in order on top of `state`.
+    fn check_block(&self, block: &Block, state: &ChainState) -> Result<(), ValidationError> {
+        let epoch = state.staking().epoch(block.header.height);
+        let mut accounts: HashMap<AccountId, Account> = HashMap::new();
+        let mut registered: HashSet<Aid> = HashSet::new();
+
+        for tx in &block.txs {
+            match tx {
+                Transaction::RegisterModel(reg)
+                    if state.artefact(&reg.aid).is_some() || !registered.insert(reg.aid) =>
+                {
+                    return Err(ValidationError::ArtefactExists(reg.aid));
+                }
+                Transaction::UseModel(tx_use)
+                    if state.artefact(&tx_use.aid).is_none()
+                        && !registered.contains(&tx_use.aid) =>
+                {
+                    return Err(ValidationError::UnknownArtefact(tx_use.aid));
+                }
+                _ => {}
+            }
+
+            let sender = tx.sender();
+            let account = overlay(&mut accounts, state, sender, epoch);
+            if tx.nonce() != account.nonce {
+                return Err(ValidationError::BadNonce {
+                    account: sender,
+                    expected: account.nonce,
+                    got: tx.nonce(),
+                });
+            }
+            let cost = match tx {
+                Transaction::Transfer(transfer) => transfer.amount.saturating_add(transfer.fee),
+                Transaction::Stake(stake) => stake.amount.saturating_add(stake.fee),
+                _ => tx.fee(),
+            };
+            if cost > account.balance {
+                return Err(ValidationError::InsufficientBalance {
+                    account: sender,
+                    needed: cost,
+                    available: account.balance,
+                });
+            }
+            account.balance -= cost;
+            account.nonce += 1;
+
+            if let Transaction::Transfer(transfer) = tx {
+                let to = overlay(&mut accounts, state, transfer.to, epoch);
+                to.balance = to.balance.saturating_add(transfer.amount);
+            }
+            let proposer = overlay(&mut accounts, state, block.header.proposer, epoch);
+            proposer.balance = proposer.balance.saturating_add(tx.fee());
+        }
+
+        Ok(())
+    }
+}
+
+impl BlockValidator for StatefulValidity {
+    fn validate(&self, block: &Block, ctx: &ValidationContext<'_>) -> Result<(), ValidationError> {
+        for tx in &block.txs {
+            if let Transaction::Slash(slash) = tx {
+                check_fraud_proof(&slash.proof, ctx.store)?;
+            }
+        }
+        match self.state.snapshot() {
+            Some((tip, state)) if block.header.height > 0 && block.header.parent == tip => {
+                self.check_block(block, &state)
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Returns the in-block view of account `id`, loading it from `state`
+/// with any stake released at the start of `epoch` on first use.
+fn overlay<'a>(
+    accounts: &'a mut HashMap<AccountId, Account>,
+    state: &ChainState,
+    id: AccountId,
+    epoch: u64,
+) -> &'a mut Account {
+    accounts.entry(id).or_insert_with(|| {
+        let mut account = state.account(&id);
+        account.balance = account
+            .balance
+            .saturating_add(state.unbonded_due(&id, epoch));
+        account
+    })
+}
+
+/// Checks `proof` against the block it names in `store`: the block must
+/// be stored, have the proof's proposer and height, and register the
+/// proof's artefact.
+///
+/// The reporter's signature is checked by [`StatefulTxValidity`] and by
+/// execution, which need no store.
+pub fn check_fraud_proof(proof: &FraudProof, store: &dyn ReadStore) -> Result<(), ValidationError> {
+    let invalid = |reason: &'static str| ValidationError::InvalidFraudProof {
+        block: proof.block_hash,
+        reason,
+    };
+    let block = store
+        .get_block(&proof.block_hash)
+        .map_err(|e| ValidationError::Custom(format!("failed to read block: {e}")))?
+        .ok_or_else(|| invalid("block is not stored"))?;
+    if block.header.proposer != proof.proposer {
+        return Err(invalid("proposer does not match the block"));
+    }
+    if block.header.height != proof.height {
+        return Err(invalid("height does not match the block"));
+    }
+    if !block.ml_pairs().iter().any(|(aid, _)| *aid == proof.aid) {
+        return Err(invalid("artefact is not registered in the block"));
+    }
+    Ok(())
+}
+
+/// Returns the registry entry for `aid`.
+fn registered<'a>(
+    state: &'a ChainState,
+    aid: &Aid,
+) -> Result<&'a ArtefactMetadata, ValidationError> {
+    state
+        .artefact(aid)
+        .ok_or(ValidationError::UnknownArtefact(*aid))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::consensus::BlockStore;
+    use crate::storage::InMemoryBlockStore;
+    use crate::types::{
+        BlockHash, EvidenceHash, EvidenceRef, FraudVerdict, HASH_LEN, Hash256, Header, Keypair,
+        ModelUseMetadata, PublicKey, Signature, TxRegisterModel, TxRevokeModel, TxSlash,
+        TxTransfer, TxTransferModel, TxUseModel, WmProfile,
+    };
+
+    fn account(byte: u8) -> AccountId {
+        AccountId(Hash256([byte; HASH_LEN]))
+    }
+
+    fn transfer(current_owner: AccountId, aid_byte: u8) -> Transaction {
+        Transaction::TransferModel(TxTransferModel {
+            current_owner,
+            new_owner: account(2),
+            aid: Aid(Hash256([aid_byte; HASH_LEN])),
+            fee: 0,
+            nonce: 0,
+            signature: Signature(Vec::new()),
+        })
+    }
+
+    fn revoke(signer: AccountId) -> Transaction {
+        Transaction::RevokeModel(TxRevokeModel {
+            signer,
+            aid: Aid(Hash256([7u8; HASH_LEN])),
+            fee: 0,
+            nonce: 0,
+            signature: Signature(Vec::new()),
+        })
+    }
+
+    fn state_with_artefact() -> ChainState {
+        let mut state = ChainState::new();
+        state.insert_artefact(ArtefactMetadata {
+            aid: Aid(Hash256([7u8; HASH_LEN])),
+            chunked: None,
+            owner: account(1),
+            evidence: EvidenceRef {
+                scheme_id: "wm-test".to_string(),
+                evidence_hash: EvidenceHash(Hash256([3u8; HASH_LEN])),
+                wm_profile: WmProfile {
+                    tau_input: 0.9,
+                    tau_feat: 0.1,
+                    logit_band_low: 0.02,
+                    logit_band_high: 0.05,
+                },
+            },
+            registered_at: 1,
+            revoked_at: None,
+        });
+        state
+    }
+
+    #[test]
+    fn transfers_need_a_registered_artefact_and_its_owner() {
+        let state = state_with_artefact();
+        let validity = StatefulTxValidity;
+
+        validity
+            .validate_tx(&transfer(account(1), 7), &state)
+            .expect("owner transfers a registered artefact");
+        assert!(
+            validity
+                .validate_tx(&transfer(account(1), 8), &state)
+                .is_err()
+        );
+        assert!(
+            validity
+                .validate_tx(&transfer(account(3), 7), &state)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn revocation_needs_owner_or_governance_and_blocks_use() {
+        let mut state = state_with_artefact();
+        state.add_governance(account(5));
+        let validity = StatefulTxValidity;
+
+        validity
+            .validate_tx(&revoke(account(1)), &state)
+            .expect("owner may revoke");
+        validity
+            .validate_tx(&revoke(account(5)), &state)
+            .expect("governance may revoke");
+        assert!(validity.validate_tx(&revoke(account(3)), &state).is_err());
+
+        let use_model = Transaction::UseModel(TxUseModel {
+            caller: account(3),
+            aid: Aid(Hash256([7u8; HASH_LEN])),
+            metadata: ModelUseMetadata {
+                task: "image_classification".to_string(),
+                version: None,
+            },
+            fee: 0,
+            nonce: 0,
+            signature: Signature(Vec::new()),
+        });
+        validity
+            .validate_tx(&use_model, &state)
+            .expect("artefact is live");
+
+        state
+            .artefact_mut(&Aid(Hash256([7u8; HASH_LEN])))
+            .unwrap()
+            .revoked_at = Some(2);
+        assert!(validity.validate_tx(&use_model, &state).is_err());
+        assert!(validity.validate_tx(&revoke(account(1)), &state).is_err());
+    }
+
+    fn pay(from: u8, nonce: u64, amount: u64) -> Transaction {
+        Transaction::Transfer(TxTransfer {
+            from: account(from),
+            to: account(9),
+            amount,
+            fee: 1,
+            nonce,
+            signature: Signature(Vec::new()),
+        })
+    }
+
+    /// Validates `block` on its own; the predicate reads the state handle,
+    /// not the context.
+    fn validate(validity: &StatefulValidity, block: Block) -> Result<(), ValidationError> {
+        validity.validate(&block, &ValidationContext::detached())
+    }
+
+    fn block_on(tip: BlockHash, txs: Vec<Transaction>) -> Block {
+        Block {
+            header: Header {
+                parent: tip,
+                height: 1,
+                timestamp: 1_700_000_001,
+                proposer: account(8),
+                txs_root: Hash256([0u8; HASH_LEN]),
+                state_root: Hash256([0u8; HASH_LEN]),
+                receipts_root: Hash256([0u8; HASH_LEN]),
+                pos_proof: None,
+            },
+            txs,
+        }
+    }
+
+    #[test]
+    fn block_transfers_and_nonces_are_checked_against_the_tip_state() {
+        let tip = BlockHash(Hash256([1u8; HASH_LEN]));
+        let mut state = state_with_artefact();
+        state.account_mut(account(1)).balance = 10;
+        let handle = StateHandle::new();
+        let validity = StatefulValidity::new(handle.clone());
+
+        // Nothing published yet: nothing to check against.
+        validate(&validity, block_on(tip, vec![pay(1, 5, 100)])).expect("no state");
+
+        handle.update(tip, &state);
+        validate(&validity, block_on(tip, vec![pay(1, 0, 4), pay(1, 1, 4)]))
+            .expect("both transfers are covered");
+        assert!(
+            validate(&validity, block_on(tip, vec![pay(1, 0, 4), pay(1, 1, 5)])).is_err(),
+            "second transfer overdraws"
+        );
+        assert!(
+            validate(&validity, block_on(tip, vec![pay(1, 0, 1), pay(1, 0, 1)])).is_err(),
+            "replayed nonce"
+        );
+
+        let elsewhere = BlockHash(Hash256([2u8; HASH_LEN]));
+        validate(&validity, block_on(elsewhere, vec![pay(1, 0, 100)]))
+            .expect("side branches are left to execution");
+    }
+
+    #[test]
+    fn block_registrations_and_usage_are_checked_against_the_registry() {
+        let tip = BlockHash(Hash256([1u8; HASH_LEN]));
+        let state = state_with_artefact();
+        let handle = StateHandle::new();
+        handle.update(tip, &state);
+        let validity = StatefulValidity::new(handle);
+
+        let evidence = state
+            .artefact(&Aid(Hash256([7u8; HASH_LEN])))
+            .unwrap()
+            .evidence
+            .clone();
+        let register = |aid: u8, nonce: u64| {
+            Transaction::RegisterModel(TxRegisterModel {
+                owner: account(1),
+                aid: Aid(Hash256([aid; HASH_LEN])),
+                chunked: None,
+                evidence: evidence.clone(),
+                fee: 0,
+                nonce,
+                signature: Signature(Vec::new()),
+            })
+        };
+        let use_model = |aid: u8, nonce: u64| {
+            Transaction::UseModel(TxUseModel {
+                caller: account(1),
+                aid: Aid(Hash256([aid; HASH_LEN])),
+                metadata: ModelUseMetadata {
+                    task: "image_classification".to_string(),
+                    version: None,
+                },
+                fee: 0,
+                nonce,
+                signature: Signature(Vec::new()),
+            })
+        };
+
+        validate(
+            &validity,
+            block_on(tip, vec![register(8, 0), use_model(8, 1)]),
+        )
+        .expect("use of an artefact registered earlier in the block");
+        assert!(validate(&validity, block_on(tip, vec![register(7, 0)])).is_err());
+        assert!(validate(&validity, block_on(tip, vec![use_model(6, 0)])).is_err());
+    }
+
+    #[test]
+    fn fraud_proofs_must_match_a_stored_block() {
+        let evidence = state_with_artefact()
+            .artefact(&Aid(Hash256([7u8; HASH_LEN])))
+            .unwrap()
+            .evidence
+            .clone();
+        let fake = block_on(
+            BlockHash(Hash256([1u8; HASH_LEN])),
+            vec![Transaction::RegisterModel(TxRegisterModel {
+                owner: account(1),
+                aid: Aid(Hash256([6u8; HASH_LEN])),
+                chunked: None,
+                evidence,
+                fee: 0,
+                nonce: 0,
+                signature: Signature(Vec::new()),
+            })],
+        );
+        let fake_hash = fake.compute_hash();
+        let mut store = InMemoryBlockStore::new();
+        store.put_block(fake).unwrap();
+
+        let reporter = Keypair::generate();
+        let proof = |block_hash: BlockHash, proposer: u8, height: u64, aid: u8| {
+            let mut proof = FraudProof {
+                block_hash,
+                height,
+                proposer: account(proposer),
+                aid: Aid(Hash256([aid; HASH_LEN])),
+                verdict: FraudVerdict {
+                    scheme_id: "wm-test".to_string(),
+                    score: Some(0.1),
+                },
+                reporter: reporter.account_id(),
+                reporter_key: PublicKey(Vec::new()),
+                signature: Signature(Vec::new()),
+            };
+            reporter.sign_fraud_proof(&mut proof);
+            proof
+        };
+        let reason = |proof: FraudProof| match check_fraud_proof(&proof, &store) {
+            Err(ValidationError::InvalidFraudProof { reason, .. }) => reason,
+            other => panic!("expected an invalid fraud proof, got {other:?}"),
+        };
+
+        check_fraud_proof(&proof(fake_hash, 8, 1, 6), &store).expect("proof matches the block");
+        let made_up = BlockHash(Hash256([9u8; HASH_LEN]));
+        assert_eq!(reason(proof(made_up, 8, 1, 6)), "block is not stored");
+        assert_eq!(
+            reason(proof(fake_hash, 3, 1, 6)),
+            "proposer does not match the block"
+        );
+        assert_eq!(
+            reason(proof(fake_hash, 8, 2, 6)),
+            "height does not match the block"
+        );
+        assert_eq!(
+            reason(proof(fake_hash, 8, 1, 5)),
+            "artefact is not registered in the block"
+        );
+
+        // Blocks carrying a made-up proof are rejected, on any branch.
+        let validity = StatefulValidity::new(StateHandle::new());
+        let slash = |proof: FraudProof| {
+            block_on(
+                BlockHash(Hash256([2u8; HASH_LEN])),
+                vec![Transaction::Slash(TxSlash {
+                    proof,
+                    fee: 0,
+                    nonce: 0,
+                    signature: Signature(Vec::new()),
+                })],
+            )
+        };
+        let ctx = ValidationContext::new(None, &store);
+        validity
+            .validate(&slash(proof(fake_hash, 8, 1, 6)), &ctx)
+            .expect("proof names a stored block");
+        assert!(
+            validity
+                .validate(&slash(proof(made_up, 8, 1, 6)), &ctx)
+                .is_err()
+        );
+    }
+}
@@ -0,0 +1,334 @@
+//! Stateless validity checks for individual transactions.
+//!
+//! These checks only look at the transaction itself, never at chain state,
+//! so they can run on submission, before a transaction is pooled:
+//!
+//! - the fee lies within the configured bounds,
+//! - the signature has a plausible shape (present if required, and no
+//!   longer than an ML-DSA-87 signature),
+//! - payload fields are sane: a printable, bounded `scheme_id` and a finite
+//!   watermark profile with an ordered logit band for registrations (and
+//!   a scheme and profile accepted by the [`SchemeRegistry`], if set, and
+//!   a chunked `Aid` form that derives the registered `Aid`, if given), a
+//!   non-empty task for usage records, distinct accounts and a non-zero
+//!   amount for transfers, distinct owners for model transfers, and a
+//!   non-zero amount for stake changes.
+//!
+//! Nonces, balances and artefact ownership depend on chain state and are
+//! checked by the mempool (see [`StatefulTxValidity`](super::StatefulTxValidity))
+//! and the execution layer.
+
+use serde::Deserialize;
+
+use crate::consensus::error::ValidationError;
+use crate::consensus::validator::TxValidator;
+use crate::types::{SchemeRegistry, Signature, Transaction, WmProfile};
+
+/// Size of an ML-DSA-87 signature, the largest scheme the chain expects.
+pub const MAX_SIGNATURE_BYTES: usize = 4627;
+
+/// Maximum length of a watermark `scheme_id`, in bytes.
+pub const MAX_SCHEME_ID_LEN: usize = 64;
+
+/// Maximum length of a usage record's `task`, in bytes.
+pub const MAX_TASK_LEN: usize = 256;
+
+/// Maximum length of a usage record's `version`, in bytes.
+pub const MAX_VERSION_LEN: usize = 64;
+
+/// Configuration for [`StatelessTxValidity`].
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TxValidityConfig {
+    /// Lowest accepted fee.
+    pub min_fee: u64,
+    /// Highest accepted fee.
+    pub max_fee: u64,
+    /// Whether transactions must carry a non-empty signature. Off while the
+    /// API gateway still submits unsigned placeholders.
+    pub require_signatures: bool,
+}
+
+impl Default for TxValidityConfig {
+    fn default() -> Self {
+        Self {
+            min_fee: 0,
+            max_fee: u64::MAX,
+            require_signatures: false,
+        }
+    }
+}
+
+/// Stateless transaction validity predicate.
+#[derive(Clone, Debug, Default)]
+pub struct StatelessTxValidity {
+    cfg: TxValidityConfig,
+    schemes: SchemeRegistry,
+}
+
+impl StatelessTxValidity {
+    /// Constructs the validator from its configuration.
+    pub fn new(cfg: &TxValidityConfig) -> Self {
+        Self {
+            cfg: cfg.clone(),
+            schemes: SchemeRegistry::default(),
+        }
+    }
+
+    /// Rejects registrations that `schemes` does not accept, so they never
+    /// reach a block that [`BaseValidity`](super::BaseValidity) would
+    /// reject.
+    pub fn with_scheme_registry(mut self, schemes: SchemeRegistry) -> Self {
+        self.schemes = schemes;
+        self
+    }
+
+    fn check_fee(&self, fee: u64) -> Result<(), ValidationError> {
+        if fee < self.cfg.min_fee || fee > self.cfg.max_fee {
+            return Err(ValidationError::FeeOutOfRange {
+                fee,
+                min: self.cfg.min_fee,
+                max: self.cfg.max_fee,
+            });
+        }
+        Ok(())
+    }
+
+    fn check_signature(&self, signature: &Signature) -> Result<(), ValidationError> {
+        let len = signature.as_bytes().len();
+        if len == 0 && self.cfg.require_signatures {
+            return Err(ValidationError::BadSignature(
+                "transaction is not signed".to_string(),
+            ));
+        }
+        if len > MAX_SIGNATURE_BYTES {
+            return Err(ValidationError::BadSignature(format!(
+                "signature of {len} bytes exceeds {MAX_SIGNATURE_BYTES}"
+            )));
+        }
+        Ok(())
+    }
+}
+
+fn check_scheme_id(scheme_id: &str) -> Result<(), ValidationError> {
+    if scheme_id.is_empty() || scheme_id.len() > MAX_SCHEME_ID_LEN {
+        return Err(ValidationError::MalformedTx(format!(
+            "scheme_id must be 1 to {MAX_SCHEME_ID_LEN} bytes long"
+        )));
+    }
+    if !scheme_id.bytes().all(|b| b.is_ascii_graphic()) {
+        return Err(ValidationError::MalformedTx(
+            "scheme_id must be printable ASCII without spaces".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+fn check_wm_profile(profile: &WmProfile) -> Result<(), ValidationError> {
+    let values = [
+        profile.tau_input,
+        profile.tau_feat,
+        profile.logit_band_low,
+        profile.logit_band_high,
+    ];
+    if !values.iter().all(|v| v.is_finite()) {
+        return Err(ValidationError::MalformedTx(
+            "watermark profile values must be finite".to_string(),
+        ));
+    }
+    if profile.logit_band_low > profile.logit_band_high {
+        return Err(ValidationError::MalformedTx(
+            "logit_band_low must not exceed logit_band_high".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+impl TxValidator for StatelessTxValidity {
+    fn validate_tx(&self, tx: &Transaction) -> Result<(), ValidationError> {
+        self.check_fee(tx.fee())?;
+        match tx {
+            Transaction::RegisterModel(tx) => {
+                self.check_signature(&tx.signature)?;
+                check_scheme_id(&tx.evidence.scheme_id)?;
+                check_wm_profile(&tx.evidence.wm_profile)?;
+                self.schemes.check(&tx.evidence)?;
+                if let Some(chunked) = &tx.chunked {
+                    chunked.check_registration(&tx.aid)?;
+                }
+            }
+            Transaction::UseModel(tx) => {
+                self.check_signature(&tx.signature)?;
+                let task = &tx.metadata.task;
+                if task.trim().is_empty() || task.len() > MAX_TASK_LEN {
+                    return Err(ValidationError::MalformedTx(format!(
+                        "task must be non-blank and at most {MAX_TASK_LEN} bytes"
+                    )));
+                }
+                if tx
+                    .metadata
+                    .version
+                    .as_ref()
+                    .is_some_and(|v| v.len() > MAX_VERSION_LEN)
+                {
+                    return Err(ValidationError::MalformedTx(format!(
+                        "version must be at most {MAX_VERSION_LEN} bytes"
+                    )));
+                }
+            }
+            Transaction::Transfer(tx) => {
+                self.check_signature(&tx.signature)?;
+                if tx.from == tx.to {
+                    return Err(ValidationError::MalformedTx(
+                        "transfer to the sender itself".to_string(),
+                    ));
+                }
+                if tx.amount == 0 {
+                    return Err(ValidationError::MalformedTx(
+                        "transfer of zero amount".to_string(),
+                    ));
+                }
+            }
+            Transaction::TransferModel(tx) => {
+                self.check_signature(&tx.signature)?;
+                if tx.current_owner == tx.new_owner {
+                    return Err(ValidationError::MalformedTx(
+                        "model transfer to the current owner".to_string(),
+                    ));
+                }
+            }
+            Transaction::RevokeModel(tx) => {
+                self.check_signature(&tx.signature)?;
+            }
+            Transaction::Stake(tx) => {
+                self.check_signature(&tx.signature)?;
+                if tx.amount == 0 {
+                    return Err(ValidationError::MalformedTx(
+                        "stake of zero amount".to_string(),
+                    ));
+                }
+            }
+            Transaction::Unstake(tx) => {
+                self.check_signature(&tx.signature)?;
+                if tx.amount == 0 {
+                    return Err(ValidationError::MalformedTx(
+                        "unstake of zero amount".to_string(),
+                    ));
+                }
+            }
+            Transaction::Slash(tx) => {
+                self.check_signature(&tx.signature)?;
+                self.check_signature(&tx.proof.signature)?;
+                check_scheme_id(&tx.proof.verdict.scheme_id)?;
+                if tx.proof.proposer == tx.proof.reporter {
+                    return Err(ValidationError::MalformedTx(
+                        "fraud proof against the reporter".to_string(),
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{
+        AccountId, Aid, EvidenceHash, EvidenceRef, HASH_LEN, Hash256, TxRegisterModel, TxTransfer,
+    };
+
+    fn account(byte: u8) -> AccountId {
+        AccountId(Hash256([byte; HASH_LEN]))
+    }
+
+    fn register(scheme_id: &str, wm_profile: WmProfile, fee: u64) -> Transaction {
+        Transaction::RegisterModel(TxRegisterModel {
+            owner: account(1),
+            aid: Aid(Hash256([2u8; HASH_LEN])),
+            chunked: None,
+            evidence: EvidenceRef {
+                scheme_id: scheme_id.to_string(),
+                evidence_hash: EvidenceHash(Hash256([3u8; HASH_LEN])),
+                wm_profile,
+            },
+            fee,
+            nonce: 0,
+            signature: Signature(Vec::new()),
+        })
+    }
+
+    fn profile() -> WmProfile {
+        WmProfile {
+            tau_input: 0.9,
+            tau_feat: 0.1,
+            logit_band_low: 0.02,
+            logit_band_high: 0.05,
+        }
+    }
+
+    #[test]
+    fn accepts_well_formed_registration() {
+        let validity = StatelessTxValidity::default();
+        validity
+            .validate_tx(&register("multi_factor_v1", profile(), 0))
+            .expect("well-formed registration");
+    }
+
+    #[test]
+    fn rejects_garbage_fields() {
+        let validity = StatelessTxValidity::default();
+
+        assert!(validity.validate_tx(&register("", profile(), 0)).is_err());
+        assert!(
+            validity
+                .validate_tx(&register("has space", profile(), 0))
+                .is_err()
+        );
+
+        let mut nan = profile();
+        nan.tau_input = f32::NAN;
+        assert!(validity.validate_tx(&register("wm", nan, 0)).is_err());
+
+        let mut inverted = profile();
+        inverted.logit_band_low = 0.5;
+        assert!(validity.validate_tx(&register("wm", inverted, 0)).is_err());
+
+        let self_transfer = Transaction::Transfer(TxTransfer {
+            from: account(1),
+            to: account(1),
+            amount: 5,
+            fee: 0,
+            nonce: 0,
+            signature: Signature(Vec::new()),
+        });
+        assert!(validity.validate_tx(&self_transfer).is_err());
+    }
+
+    #[test]
+    fn enforces_fee_bounds_and_signature_shape() {
+        let validity = StatelessTxValidity::new(&TxValidityConfig {
+            min_fee: 1,
+            max_fee: 100,
+            require_signatures: true,
+        });
+
+        let signed = |fee: u64, sig_len: usize| match register("wm", profile(), fee) {
+            Transaction::RegisterModel(mut tx) => {
+                tx.signature = Signature(vec![7u8; sig_len]);
+                Transaction::RegisterModel(tx)
+            }
+            _ => unreachable!(),
+        };
+
+        validity.validate_tx(&signed(10, 64)).expect("in bounds");
+        assert!(validity.validate_tx(&signed(0, 64)).is_err());
+        assert!(validity.validate_tx(&signed(101, 64)).is_err());
+        assert!(validity.validate_tx(&signed(10, 0)).is_err());
+        assert!(
+            validity
+                .validate_tx(&signed(10, MAX_SIGNATURE_BYTES + 1))
+                .is_err()
+        );
+    }
+}
@@ -4,18 +4,28 @@ FastAPI app for the ML authenticity verification service.
 Endpoints:
 - GET /health
 - POST /verify
+- POST /verify_batch
 """
 
 from __future__ import annotations
 
+import time
 from pathlib import Path
+from typing import Dict, Optional, Tuple, Union
 
 from fastapi import FastAPI
 from fastapi.middleware.cors import CORSMiddleware
 
 from .config import MODEL_ROOT
 from .registry.filesystem_store import FilesystemModelRegistry
-from .schemas import HealthResponse, VerifyRequest, VerifyResponse
+from .schemas import (
+    CachedVerifyResponse,
+    HealthResponse,
+    VerifyBatchRequest,
+    VerifyBatchResponse,
+    VerifyRequest,
+    VerifyResponse,
+)
 from .watermark.verify import verify_model
 
 app = FastAPI(
@@ -35,6 +45,28 @@ app.add_middleware(
 # Attach the model registry to app state for reuse.
 app.state.registry = FilesystemModelRegistry(MODEL_ROOT)
 
+# Verdicts keyed by the full request (minus the cache hint). Each entry also
+# records the model file's mtime so replacing the model invalidates it.
+VerdictKey = Tuple[str, str, str, Tuple[float, float, float, float]]
+app.state.verdict_cache: Dict[VerdictKey, Tuple[Optional[float], VerifyResponse]] = {}
+
+
+def _verdict_key(req: VerifyRequest) -> VerdictKey:
+    p = req.wm_profile
+    return (
+        req.aid,
+        req.scheme_id,
+        req.evidence_hash,
+        (p.tau_input, p.tau_feat, p.logit_band_low, p.logit_band_high),
+    )
+
+
+def _model_mtime(model_path: Path) -> Optional[float]:
+    try:
+        return model_path.stat().st_mtime
+    except OSError:
+        return None
+
 
 @app.get("/health", response_model=HealthResponse)
 async def health() -> HealthResponse:
@@ -42,18 +74,28 @@ async def health() -> HealthResponse:
     return HealthResponse(status="ok")
 
 
-@app.post("/verify", response_model=VerifyResponse)
-async def verify(req: VerifyRequest) -> VerifyResponse:
+def _verify_one(req: VerifyRequest) -> Union[VerifyResponse, CachedVerifyResponse]:
     """
-    Verify authenticity of a model artefact.
+    Verify a single artefact, consulting the verdict cache first.
 
-    This endpoint is called by the Rust `HttpMlVerifier` client in the
-    `chain` crate. It expects the `VerifyRequest` / `VerifyResponse`
-    shapes defined in `schemas.py`.
+    Verdicts are cached per request and model file. If the request carries
+    a `cache_hint` for a verdict that is still cached, the answer is a
+    `CachedVerifyResponse` instead of re-running verification.
     """
+    start = time.perf_counter()
     registry: FilesystemModelRegistry = app.state.registry
     model_path: Path = registry.resolve(req.aid)
 
+    key = _verdict_key(req)
+    mtime = _model_mtime(model_path)
+    cached = app.state.verdict_cache.get(key)
+    if cached is not None and cached[0] == mtime:
+        hint = req.cache_hint
+        if hint is not None and hint.evidence_hash == req.evidence_hash:
+            latency_ms = int((time.perf_counter() - start) * 1000)
+            return CachedVerifyResponse(latency_ms=latency_ms)
+        return cached[1]
+
     # We *do not* return 404 if the model is missing; instead the verifier
     # returns `ok=False` so the consensus layer treats it as an authenticity
     # failure rather than a transport error.
@@ -64,13 +106,39 @@ async def verify(req: VerifyRequest) -> VerifyResponse:
         wm_profile=req.wm_profile,
     )
 
-    return VerifyResponse(
+    response = VerifyResponse(
         ok=stats.ok,
+        score=stats.score,
         trigger_acc=stats.trigger_acc,
         feat_dist=stats.feat_dist,
         logit_stat=stats.logit_stat,
         latency_ms=stats.latency_ms,
     )
+    app.state.verdict_cache[key] = (mtime, response)
+    return response
+
+
+@app.post("/verify", response_model=Union[VerifyResponse, CachedVerifyResponse])
+async def verify(req: VerifyRequest) -> Union[VerifyResponse, CachedVerifyResponse]:
+    """
+    Verify authenticity of a model artefact.
+
+    This endpoint is called by the Rust `HttpMlVerifier` client in the
+    `chain` crate. It expects the `VerifyRequest` / `VerifyResponse`
+    shapes defined in `schemas.py`.
+    """
+    return _verify_one(req)
+
+
+@app.post("/verify_batch", response_model=VerifyBatchResponse)
+async def verify_batch(req: VerifyBatchRequest) -> VerifyBatchResponse:
+    """
+    Verify several artefacts in one call.
+
+    `HttpMlVerifier` uses this to check all artefacts of a block with a
+    single round trip. Verdicts are returned in request order.
+    """
+    return VerifyBatchResponse(verdicts=[_verify_one(item) for item in req.items])
 
 
 def run() -> None:
@@ -7,7 +7,7 @@ These schemas mirror the JSON interface expected by the Rust
 
 from __future__ import annotations
 
-from typing import Optional
+from typing import List, Optional, Union
 
 from pydantic import BaseModel
 
@@ -30,6 +30,17 @@ class WmProfile(BaseModel):
     logit_band_high: float
 
 
+class CacheHint(BaseModel):
+    """
+    Cache-control hint sent by the Rust client when it already holds a
+    verdict for the artefact.
+
+    - evidence_hash: hex-encoded evidence hash of the verdict the client holds
+    """
+
+    evidence_hash: str
+
+
 class VerifyRequest(BaseModel):
     """
     Request payload for POST /verify.
@@ -41,12 +52,14 @@ class VerifyRequest(BaseModel):
     - scheme_id: watermark scheme identifier
     - evidence_hash: hex-encoded hash of the watermark key/params
     - wm_profile: thresholds and bands for verification
+    - cache_hint: optional hint that the client already has a verdict
     """
 
     aid: str
     scheme_id: str
     evidence_hash: str
     wm_profile: WmProfile
+    cache_hint: Optional[CacheHint] = None
 
 
 class VerifyResponse(BaseModel):
@@ -56,6 +69,7 @@ class VerifyResponse(BaseModel):
     This mirrors the Rust `VerifyResponse` struct:
 
     - ok: overall authenticity verdict
+    - score: optional continuous confidence in [0, 1]
     - trigger_acc: optional trigger accuracy statistic
     - feat_dist: optional feature-space distance statistic
     - logit_stat: optional logit-space statistic
@@ -63,12 +77,45 @@ class VerifyResponse(BaseModel):
     """
 
     ok: bool
+    score: Optional[float] = None
     trigger_acc: Optional[float] = None
     feat_dist: Optional[float] = None
     logit_stat: Optional[float] = None
     latency_ms: Optional[int] = None
 
 
+class CachedVerifyResponse(BaseModel):
+    """
+    Response payload for POST /verify when the verdict named in the
+    request's `cache_hint` is still current.
+
+    The Rust client reuses the verdict it already holds.
+
+    - cached: always true
+    - latency_ms: optional time spent answering from the cache
+    """
+
+    cached: bool = True
+    latency_ms: Optional[int] = None
+
+
+class VerifyBatchRequest(BaseModel):
+    """Request payload for POST /verify_batch: one `VerifyRequest` per artefact."""
+
+    items: List[VerifyRequest]
+
+
+class VerifyBatchResponse(BaseModel):
+    """
+    Response payload for POST /verify_batch.
+
+    Holds one entry per request item, in the same order; each is either a
+    full `VerifyResponse` or a `CachedVerifyResponse`.
+    """
+
+    verdicts: List[Union[VerifyResponse, CachedVerifyResponse]]
+
+
 class HealthResponse(BaseModel):
     """Simple health check response."""
 
@@ -31,6 +31,7 @@ from ..schemas import WmProfile
 @dataclass
 class WatermarkStats:
     ok: bool
+    score: float
     trigger_acc: float
     feat_dist: float
     logit_stat: float
@@ -88,6 +89,7 @@ def verify_model(
         latency_ms = int((end - start) * 1000)
         return WatermarkStats(
             ok=False,
+            score=0.0,
             trigger_acc=0.0,
             feat_dist=1.0,
             logit_stat=0.0,
@@ -105,11 +107,16 @@ def verify_model(
         and wm_profile.logit_band_low <= logit_stat <= wm_profile.logit_band_high
     )
 
+    # Continuous confidence: trigger-set accuracy is the primary detection
+    # statistic, so it doubles as the score the chain thresholds on.
+    score = trigger_acc
+
     end = time.perf_counter()
     latency_ms = int((end - start) * 1000)
 
     return WatermarkStats(
         ok=ok,
+        score=score,
         trigger_acc=trigger_acc,
         feat_dist=feat_dist,
         logit_stat=logit_stat,
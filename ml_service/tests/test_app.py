@@ -87,8 +87,81 @@ def test_verify_endpoint_with_valid_model(tmp_path: Path, monkeypatch):
 
     data = resp.json()
     # Check basic keys are present.
-    for key in ("ok", "trigger_acc", "feat_dist", "logit_stat", "latency_ms"):
+    for key in ("ok", "score", "trigger_acc", "feat_dist", "logit_stat", "latency_ms"):
         assert key in data
 
     # Given lax thresholds, ok should be true.
     assert data["ok"] is True
+
+
+def test_verify_endpoint_answers_cache_hint(tmp_path: Path):
+    registry = FilesystemModelRegistry(root=tmp_path)
+    app.state.registry = registry
+    app.state.verdict_cache = {}
+
+    client = TestClient(app)
+
+    aid_hex = "beef" * 16
+    model_path = registry.resolve(aid_hex)
+    os.makedirs(model_path.parent, exist_ok=True)
+    torch.save({"hello": "world"}, model_path)
+
+    payload = {
+        "aid": aid_hex,
+        "scheme_id": "multi_factor_v1",
+        "evidence_hash": "cafe" * 16,
+        "wm_profile": {
+            "tau_input": 0.0,
+            "tau_feat": 1.0,
+            "logit_band_low": -1.0,
+            "logit_band_high": 1.0,
+        },
+    }
+
+    first = client.post("/verify", json=payload).json()
+    assert first["ok"] is True
+
+    # With a hint for the same evidence the service skips verification.
+    hinted = dict(payload, cache_hint={"evidence_hash": payload["evidence_hash"]})
+    data = client.post("/verify", json=hinted).json()
+    assert data["cached"] is True
+    assert "ok" not in data
+
+    # Without a hint the cached verdict is returned in full.
+    data = client.post("/verify", json=payload).json()
+    assert data == first
+
+
+def test_verify_batch_endpoint_preserves_order(tmp_path: Path):
+    registry = FilesystemModelRegistry(root=tmp_path)
+    app.state.registry = registry
+    app.state.verdict_cache = {}
+
+    client = TestClient(app)
+
+    present = "aaaa" * 16
+    model_path = registry.resolve(present)
+    os.makedirs(model_path.parent, exist_ok=True)
+    torch.save({"hello": "world"}, model_path)
+
+    def item(aid_hex: str) -> dict:
+        return {
+            "aid": aid_hex,
+            "scheme_id": "multi_factor_v1",
+            "evidence_hash": "1234" * 16,
+            "wm_profile": {
+                "tau_input": 0.0,
+                "tau_feat": 1.0,
+                "logit_band_low": -1.0,
+                "logit_band_high": 1.0,
+            },
+        }
+
+    resp = client.post(
+        "/verify_batch",
+        json={"items": [item(present), item("bbbb" * 16), item(present)]},
+    )
+    assert resp.status_code == 200
+
+    verdicts = resp.json()["verdicts"]
+    assert [v["ok"] for v in verdicts] == [True, False, True]